@@ -0,0 +1,67 @@
+//! Per-date accumulators of stored predictions
+//!
+//! FarcasterPredictions folds every prediction stored for a date into one
+//! hash, in storage order:
+//!
+//! ```text
+//! accumulator_0 = 0x00..00
+//! accumulator_n = keccak256(accumulator_{n-1} || user || prediction_hash)
+//! ```
+//!
+//! The accumulators are 32 bytes, `user` the 20-byte address and
+//! `prediction_hash` the 32 stored bytes, i.e. Solidity's
+//! `abi.encodePacked(bytes32, address, bytes32)`. Replaying the date's
+//! `PredictionStored` events through [`accumulate`] gives the on-chain
+//! value only if no event was dropped, added or reordered.
+
+use alloy_primitives::Address;
+
+use crate::hash::Keccak256Builder;
+
+/// Accumulator of a date before its first prediction
+pub const EMPTY_ACCUMULATOR: [u8; 32] = [0; 32];
+
+/// Fold `user`'s `prediction_hash` into the date's `previous` accumulator
+pub fn accumulate(previous: &[u8; 32], user: Address, prediction_hash: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(previous).update(user.as_slice()).update(prediction_hash);
+    hasher.finalize32()
+}
+
+/// Accumulator of a date's `(user, prediction_hash)` stores, in order
+pub fn replay<'a>(stores: impl IntoIterator<Item = (Address, &'a [u8; 32])>) -> [u8; 32] {
+    stores
+        .into_iter()
+        .fold(EMPTY_ACCUMULATOR, |accumulator, (user, hash)| accumulate(&accumulator, user, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_packed_encoding() {
+        let user = Address::repeat_byte(0xa1);
+        let mut packed = vec![];
+        packed.extend_from_slice(&[0x11; 32]);
+        packed.extend_from_slice(user.as_slice());
+        packed.extend_from_slice(&[0x22; 32]);
+        assert_eq!(packed.len(), 84);
+
+        let accumulator = accumulate(&[0x11; 32], user, &[0x22; 32]);
+        assert_eq!(accumulator, crate::hash::keccak256(&packed));
+        assert_eq!(hex::encode(accumulator), "eab79afa7c085b6da4adb1df83690d8fd665ae3b3e956e71e927dbad7b736b73");
+    }
+
+    #[test]
+    fn test_replay_depends_on_order() {
+        let (alice, bob) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb2));
+        let (first, second) = ([1; 32], [2; 32]);
+        let accumulator = replay([(alice, &first), (bob, &second)]);
+
+        assert_eq!(replay([]), EMPTY_ACCUMULATOR);
+        assert_eq!(accumulator, accumulate(&accumulate(&EMPTY_ACCUMULATOR, alice, &first), bob, &second));
+        assert_ne!(accumulator, replay([(bob, &second), (alice, &first)]));
+        assert_ne!(accumulator, replay([(alice, &first)]));
+    }
+}
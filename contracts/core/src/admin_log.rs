@@ -0,0 +1,128 @@
+//! Action ids of the FarcasterPredictions admin log
+//!
+//! Every privileged write appends `(action, actor, target, timestamp)` to
+//! the contract's admin log. `target` is a 32-byte word whose meaning
+//! depends on the action:
+//!
+//! - an address, left-padded, for the account or contract the action
+//!   names (owner, relayer, forwarder, astrologer, oracle, registry, hook,
+//!   successor, user, FID attestor, fee recipient)
+//! - the root itself for [`SET_ALLOWLIST_ROOT`]
+//! - a big-endian integer for the value set by [`SET_MIN_PREDICTIONS`],
+//!   [`SET_MIN_RETENTION_DAYS`], [`SET_CHALLENGE_WINDOW`],
+//!   [`SET_RATING_HALF_LIFE`], [`SET_AUTO_PAUSE_THRESHOLD`],
+//!   [`SET_MAX_BACKFILL_DAYS`], [`SET_MAX_BETA_FID`] and
+//!   [`SET_RATING_WINDOW_DAYS`], [`SET_REGISTRATION_FEE`] and
+//!   [`SET_SPONSORSHIP_DAILY_CAP`], the dimension id for
+//!   [`REGISTER_DIMENSION`], and the number of users for [`IMPORT_USERS`]
+//! - `delay << 64 | window` for [`SET_REVEAL_TIMING`]
+//! - `role << 160 | key` for [`ADD_ATTESTOR`] and [`REVOKE_ATTESTOR`]
+//! - `selector << 160 | facet` for [`SET_FACET`], which takes effect two
+//!   days after the record's timestamp
+//! - zero for [`UNPAUSE`]
+//! - `min << 8 | max` for [`SET_ACCEPTED_VERSIONS`]
+//! - `registration << 96 | prediction << 64 | rating << 32 | streak` for
+//!   [`SET_POINT_WEIGHTS`]
+//!
+//! Ids are never reused or renumbered; zero is no action.
+
+pub const INITIALIZE: u8 = 1;
+pub const SUNSET: u8 = 2;
+pub const SET_RELAYER: u8 = 3;
+pub const SET_TRUSTED_FORWARDER: u8 = 4;
+pub const ADD_ASTROLOGER: u8 = 5;
+pub const REMOVE_ASTROLOGER: u8 = 6;
+pub const SET_ACCEPTED_VERSIONS: u8 = 7;
+pub const SET_ALLOWLIST_ROOT: u8 = 8;
+pub const SET_EPHEMERIS_ORACLE: u8 = 9;
+pub const SET_CHART_REGISTRY: u8 = 10;
+pub const SET_PREDICTION_HOOK: u8 = 11;
+pub const SET_MIN_PREDICTIONS: u8 = 12;
+pub const SET_POINT_WEIGHTS: u8 = 13;
+pub const SET_MIN_RETENTION_DAYS: u8 = 14;
+pub const SET_CHALLENGE_WINDOW: u8 = 15;
+pub const DEDUCT_POINTS: u8 = 16;
+pub const REPAIR_USER_COUNTERS: u8 = 17;
+pub const SET_RATING_HALF_LIFE: u8 = 18;
+pub const SET_AUTO_PAUSE_THRESHOLD: u8 = 19;
+pub const UNPAUSE: u8 = 20;
+pub const REGISTER_DIMENSION: u8 = 21;
+pub const SET_REVEAL_TIMING: u8 = 22;
+pub const SET_MAX_BACKFILL_DAYS: u8 = 23;
+pub const SET_FID_ATTESTOR: u8 = 24;
+pub const SET_MAX_BETA_FID: u8 = 25;
+pub const SET_RATING_WINDOW_DAYS: u8 = 26;
+pub const ADD_ATTESTOR: u8 = 27;
+pub const REVOKE_ATTESTOR: u8 = 28;
+pub const IMPORT_USERS: u8 = 29;
+pub const SET_REGISTRATION_FEE: u8 = 30;
+pub const SET_SPONSORSHIP_DAILY_CAP: u8 = 31;
+pub const WITHDRAW_FEES: u8 = 32;
+pub const SET_FACET: u8 = 33;
+
+/// Names indexed by action id
+const ACTION_NAMES: [&str; 34] = [
+    "None",
+    "Initialize",
+    "Sunset",
+    "SetRelayer",
+    "SetTrustedForwarder",
+    "AddAstrologer",
+    "RemoveAstrologer",
+    "SetAcceptedVersions",
+    "SetAllowlistRoot",
+    "SetEphemerisOracle",
+    "SetChartRegistry",
+    "SetPredictionHook",
+    "SetMinPredictions",
+    "SetPointWeights",
+    "SetMinRetentionDays",
+    "SetChallengeWindow",
+    "DeductPoints",
+    "RepairUserCounters",
+    "SetRatingHalfLife",
+    "SetAutoPauseThreshold",
+    "Unpause",
+    "RegisterDimension",
+    "SetRevealTiming",
+    "SetMaxBackfillDays",
+    "SetFidAttestor",
+    "SetMaxBetaFid",
+    "SetRatingWindowDays",
+    "AddAttestor",
+    "RevokeAttestor",
+    "ImportUsers",
+    "SetRegistrationFee",
+    "SetSponsorshipDailyCap",
+    "WithdrawFees",
+    "SetFacet",
+];
+
+/// Name of `action`, or `"Unknown"` for ids not defined here
+pub fn action_name(action: u8) -> &'static str {
+    ACTION_NAMES.get(action as usize).copied().unwrap_or("Unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_names() {
+        assert_eq!(action_name(0), "None");
+        assert_eq!(action_name(INITIALIZE), "Initialize");
+        assert_eq!(action_name(SET_MIN_PREDICTIONS), "SetMinPredictions");
+        assert_eq!(action_name(SET_RATING_HALF_LIFE), "SetRatingHalfLife");
+        assert_eq!(action_name(SET_REVEAL_TIMING), "SetRevealTiming");
+        assert_eq!(action_name(SET_MAX_BACKFILL_DAYS), "SetMaxBackfillDays");
+        assert_eq!(action_name(SET_MAX_BETA_FID), "SetMaxBetaFid");
+        assert_eq!(action_name(SET_RATING_WINDOW_DAYS), "SetRatingWindowDays");
+        assert_eq!(action_name(ADD_ATTESTOR), "AddAttestor");
+        assert_eq!(action_name(REVOKE_ATTESTOR), "RevokeAttestor");
+        assert_eq!(action_name(IMPORT_USERS), "ImportUsers");
+        assert_eq!(action_name(SET_SPONSORSHIP_DAILY_CAP), "SetSponsorshipDailyCap");
+        assert_eq!(action_name(WITHDRAW_FEES), "WithdrawFees");
+        assert_eq!(action_name(SET_FACET), "SetFacet");
+        assert_eq!(action_name(SET_FACET + 1), "Unknown");
+    }
+}
@@ -0,0 +1,727 @@
+//! Zodiac signs of ecliptic longitudes
+//!
+//! Longitudes are in centidegrees, as everywhere else in the contract, and
+//! [`MAX_POSITION`] (360°) is accepted as the same point as 0°. Signs are
+//! numbered from Aries (0) to Pisces (11), each spanning 3000 centidegrees.
+//!
+//! Aspects compare the shortest arc between two longitudes against the
+//! exact angle of each major aspect, within an orb given by the caller.
+//!
+//! Nakshatras divide the sidereal zodiac into 27 mansions of 13°20′, each
+//! split into 4 padas of 3°20′. 13°20′ is 4000/3 centidegrees, so the
+//! mansion and pada come from exact integer math on `longitude × 3`.
+//!
+//! Moon phases use the mean synodic month from a known new moon, which
+//! drifts from the true phase by up to about half a day.
+//!
+//! Compatibility of two signs is looked up in [`COMPATIBILITY`]; that of
+//! two charts also weighs the harmony of the aspects between them.
+//!
+//! Houses are numbered 1 to 12 and start at their cusps, which run
+//! counterclockwise around the zodiac. A house may span 0° Aries, such as a
+//! 1st house from 350° to 20°.
+
+use alloc::vec::Vec;
+
+use alloy_primitives::FixedBytes;
+
+use crate::positions::{unpack_chart, PlanetPosition, PositionError, MAX_POSITION, MAX_POSITIONS, SUN};
+
+/// Number of zodiac signs
+pub const SIGN_COUNT: u8 = 12;
+
+/// Width of one sign in centidegrees (30°)
+pub const SIGN_WIDTH: u16 = 3000;
+
+pub const ARIES: u8 = 0;
+pub const PISCES: u8 = 11;
+
+/// Names indexed by sign number
+const SIGN_NAMES: [&str; SIGN_COUNT as usize] = [
+    "Aries",
+    "Taurus",
+    "Gemini",
+    "Cancer",
+    "Leo",
+    "Virgo",
+    "Libra",
+    "Scorpio",
+    "Sagittarius",
+    "Capricorn",
+    "Aquarius",
+    "Pisces",
+];
+
+/// Major aspects between two bodies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aspect {
+    Conjunction,
+    Sextile,
+    Square,
+    Trine,
+    Opposition,
+}
+
+impl Aspect {
+    /// All aspects in order of increasing angle
+    pub const ALL: [Aspect; 5] =
+        [Aspect::Conjunction, Aspect::Sextile, Aspect::Square, Aspect::Trine, Aspect::Opposition];
+
+    /// Exact angle in centidegrees
+    pub fn angle(self) -> u64 {
+        match self {
+            Aspect::Conjunction => 0,
+            Aspect::Sextile => 6000,
+            Aspect::Square => 9000,
+            Aspect::Trine => 12000,
+            Aspect::Opposition => 18000,
+        }
+    }
+
+    /// Whether the aspect is a flowing one (conjunction, sextile, trine)
+    /// rather than a tense one (square, opposition)
+    pub fn is_harmonious(self) -> bool {
+        matches!(self, Aspect::Conjunction | Aspect::Sextile | Aspect::Trine)
+    }
+
+    /// Weight of the aspect in [`transit_score`]
+    pub fn weight(self) -> u64 {
+        match self {
+            Aspect::Conjunction => 5,
+            Aspect::Opposition => 4,
+            Aspect::Square => 3,
+            Aspect::Trine => 2,
+            Aspect::Sextile => 1,
+        }
+    }
+}
+
+/// Orb of transit aspects in centidegrees (3°)
+pub const TRANSIT_ORB: u64 = 300;
+
+/// Orb of aspects between two natal charts in centidegrees (5°)
+pub const SYNASTRY_ORB: u64 = 500;
+
+/// Compatibility (0-100) of two sun signs, indexed by sign number
+///
+/// Follows from the signs' elements and modalities, i.e. from how many
+/// signs apart they are: same element (trine) 95, same sign 80,
+/// complementary elements (fire-air, earth-water) 80 a sextile apart and
+/// 70 in opposition, same modality in clashing elements (square) 40,
+/// neighbours 50 and a quincunx 35. Symmetric, as a score must not depend
+/// on who asks.
+pub const COMPATIBILITY: [[u8; SIGN_COUNT as usize]; SIGN_COUNT as usize] = [
+    [80, 50, 80, 40, 95, 35, 70, 35, 95, 40, 80, 50], // Aries
+    [50, 80, 50, 80, 40, 95, 35, 70, 35, 95, 40, 80], // Taurus
+    [80, 50, 80, 50, 80, 40, 95, 35, 70, 35, 95, 40], // Gemini
+    [40, 80, 50, 80, 50, 80, 40, 95, 35, 70, 35, 95], // Cancer
+    [95, 40, 80, 50, 80, 50, 80, 40, 95, 35, 70, 35], // Leo
+    [35, 95, 40, 80, 50, 80, 50, 80, 40, 95, 35, 70], // Virgo
+    [70, 35, 95, 40, 80, 50, 80, 50, 80, 40, 95, 35], // Libra
+    [35, 70, 35, 95, 40, 80, 50, 80, 50, 80, 40, 95], // Scorpio
+    [95, 35, 70, 35, 95, 40, 80, 50, 80, 50, 80, 40], // Sagittarius
+    [40, 95, 35, 70, 35, 95, 40, 80, 50, 80, 50, 80], // Capricorn
+    [80, 40, 95, 35, 70, 35, 95, 40, 80, 50, 80, 50], // Aquarius
+    [50, 80, 40, 95, 35, 70, 35, 95, 40, 80, 50, 80], // Pisces
+];
+
+/// Percent of a chart compatibility score from the sun signs; the rest
+/// comes from the aspects between the charts
+const SIGN_SHARE: u16 = 60;
+
+/// Number of nakshatras
+pub const NAKSHATRA_COUNT: u8 = 27;
+
+/// Padas per nakshatra
+pub const PADA_COUNT: u8 = 4;
+
+/// Names indexed by nakshatra number
+const NAKSHATRA_NAMES: [&str; NAKSHATRA_COUNT as usize] = [
+    "Ashwini",
+    "Bharani",
+    "Krittika",
+    "Rohini",
+    "Mrigashira",
+    "Ardra",
+    "Punarvasu",
+    "Pushya",
+    "Ashlesha",
+    "Magha",
+    "Purva Phalguni",
+    "Uttara Phalguni",
+    "Hasta",
+    "Chitra",
+    "Swati",
+    "Vishakha",
+    "Anuradha",
+    "Jyeshtha",
+    "Mula",
+    "Purva Ashadha",
+    "Uttara Ashadha",
+    "Shravana",
+    "Dhanishta",
+    "Shatabhisha",
+    "Purva Bhadrapada",
+    "Uttara Bhadrapada",
+    "Revati",
+];
+
+/// Mean synodic month (29.530588853 days) in milliseconds
+const SYNODIC_MONTH_MS: u64 = 2_551_442_877;
+
+/// New moon of 2000-01-06 18:14 UTC
+const NEW_MOON_EPOCH: u64 = 947_182_440;
+
+/// Number of moon phases (new, waxing crescent, ..., waning crescent)
+pub const PHASE_COUNT: u8 = 8;
+
+pub const NEW_MOON: u8 = 0;
+pub const FIRST_QUARTER: u8 = 2;
+pub const FULL_MOON: u8 = 4;
+pub const LAST_QUARTER: u8 = 6;
+
+/// Resolution of the fixed-point sine in [`moon_phase`]
+const PHASE_SCALE: u128 = 1_000_000;
+
+/// Number of houses, and of cusps bounding them
+pub const HOUSE_COUNT: u8 = 12;
+
+/// Reasons a longitude or sign is rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AstroError {
+    /// Longitude above 36000 centidegrees
+    LongitudeOutOfRange(u64),
+    /// Sign number above Pisces
+    SignOutOfRange(u8),
+}
+
+/// Sign of `longitude_centideg` (0 = Aries ... 11 = Pisces)
+pub fn sign_of(longitude_centideg: u64) -> Result<u8, AstroError> {
+    degree_in_sign(longitude_centideg).map(|(sign, _)| sign)
+}
+
+/// Sign of `longitude_centideg` and the centidegrees into that sign
+/// (0..3000)
+pub fn degree_in_sign(longitude_centideg: u64) -> Result<(u8, u16), AstroError> {
+    if longitude_centideg > MAX_POSITION {
+        return Err(AstroError::LongitudeOutOfRange(longitude_centideg));
+    }
+    // 360° wraps around to the start of Aries
+    let longitude = (longitude_centideg % MAX_POSITION) as u16;
+    Ok(((longitude / SIGN_WIDTH) as u8, longitude % SIGN_WIDTH))
+}
+
+/// English name of `sign`, or `"Unknown"` for numbers above Pisces
+pub fn sign_name(sign: u8) -> &'static str {
+    SIGN_NAMES.get(sign as usize).copied().unwrap_or("Unknown")
+}
+
+/// Sign changes of a body over `(date, longitude_centideg)` days in date
+/// order
+///
+/// Each day is compared with the one before it in `days`, so a gap is
+/// skipped over rather than reported. A body that turns retrograde and
+/// crosses a boundary back and forth is reported at every crossing.
+/// Returns: `(date, from_sign, to_sign)` per change
+pub fn ingresses(days: impl IntoIterator<Item = (u64, u64)>) -> Result<Vec<(u64, u8, u8)>, AstroError> {
+    let mut changes = Vec::new();
+    let mut previous = None;
+    for (date, longitude) in days {
+        let sign = sign_of(longitude)?;
+        if let Some(from) = previous.filter(|from| *from != sign) {
+            changes.push((date, from, sign));
+        }
+        previous = Some(sign);
+    }
+    Ok(changes)
+}
+
+/// Nakshatra (0 = Ashwini ... 26 = Revati) and pada (1-4) of a sidereal
+/// longitude
+pub fn nakshatra_of(sidereal_longitude_centideg: u64) -> Result<(u8, u8), AstroError> {
+    if sidereal_longitude_centideg > MAX_POSITION {
+        return Err(AstroError::LongitudeOutOfRange(sidereal_longitude_centideg));
+    }
+    // A pada is 1/108 of the circle: 36000 / 108 = 1000 / 3 centidegrees
+    let pada_index = (sidereal_longitude_centideg % MAX_POSITION) * 3 / 1000;
+    Ok(((pada_index / PADA_COUNT as u64) as u8, (pada_index % PADA_COUNT as u64) as u8 + 1))
+}
+
+/// English transliteration of nakshatra `index`, or `"Unknown"` past Revati
+pub fn nakshatra_name(index: u8) -> &'static str {
+    NAKSHATRA_NAMES.get(index as usize).copied().unwrap_or("Unknown")
+}
+
+/// Sidereal longitude of a tropical one, subtracting `ayanamsa_centideg`
+/// modulo 360°
+pub fn tropical_to_sidereal(longitude_centideg: u64, ayanamsa_centideg: u64) -> u64 {
+    (longitude_centideg % MAX_POSITION + MAX_POSITION - ayanamsa_centideg % MAX_POSITION) % MAX_POSITION
+}
+
+/// Moon phase at `timestamp`: the phase index (0 = new ... 4 = full ... 7)
+/// and the illuminated fraction × 1000
+///
+/// Each phase spans an eighth of the synodic month centred on its exact
+/// angle, so index 0 covers the new moon ± 1/16 month.
+pub fn moon_phase(timestamp: u64) -> (u8, u16) {
+    let elapsed_ms = (timestamp as i128 - NEW_MOON_EPOCH as i128) * 1000;
+    let age_ms = elapsed_ms.rem_euclid(SYNODIC_MONTH_MS as i128) as u128;
+    let synodic = SYNODIC_MONTH_MS as u128;
+
+    let phase = ((age_ms * PHASE_COUNT as u128 + synodic / 2) / synodic) as u8 % PHASE_COUNT;
+
+    // Illumination is sin²(π·age/month), with Bhaskara I's approximation
+    // sin(πu) ≈ 16u(1-u) / (5 - 4u(1-u)), good to about 0.2%
+    let u = age_ms * PHASE_SCALE / synodic;
+    let arc = u * (PHASE_SCALE - u);
+    let sine = 16 * arc * PHASE_SCALE / (5 * PHASE_SCALE * PHASE_SCALE - 4 * arc);
+    let illumination = (sine * sine * 1000 + PHASE_SCALE * PHASE_SCALE / 2) / (PHASE_SCALE * PHASE_SCALE);
+    (phase, illumination as u16)
+}
+
+/// Shortest arc between two longitudes in centidegrees (0..=18000)
+///
+/// Longitudes at or above 360° are taken modulo 360°.
+pub fn angular_separation(a_centideg: u64, b_centideg: u64) -> u64 {
+    let diff = (a_centideg % MAX_POSITION).abs_diff(b_centideg % MAX_POSITION);
+    diff.min(MAX_POSITION - diff)
+}
+
+/// Aspect formed by `separation`, if it is within `orb_centideg` of an
+/// exact aspect angle (inclusive)
+///
+/// With orbs wide enough for two aspects to match, the closer one wins and
+/// ties go to the smaller angle.
+pub fn classify_aspect(separation: u64, orb_centideg: u64) -> Option<Aspect> {
+    Aspect::ALL
+        .into_iter()
+        .map(|aspect| (aspect.angle().abs_diff(separation), aspect))
+        .filter(|(distance, _)| *distance <= orb_centideg)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, aspect)| aspect)
+}
+
+/// Aspects between every body of `chart_a` and every body of `chart_b`
+///
+/// Returns `(index_a, index_b, aspect)` for each pair in aspect. Only the
+/// first [`MAX_POSITIONS`] bodies of each chart are compared.
+pub fn aspects_between(chart_a: &[u64], chart_b: &[u64], orb_centideg: u64) -> Vec<(u8, u8, Aspect)> {
+    let mut aspects = Vec::new();
+    for (i, a) in chart_a.iter().take(MAX_POSITIONS).enumerate() {
+        for (j, b) in chart_b.iter().take(MAX_POSITIONS).enumerate() {
+            if let Some(aspect) = classify_aspect(angular_separation(*a, *b), orb_centideg) {
+                aspects.push((i as u8, j as u8, aspect));
+            }
+        }
+    }
+    aspects
+}
+
+/// Transit intensity of `transits` over `natal` longitudes
+///
+/// Returns the summed [`Aspect::weight`] of every transit-natal pair in
+/// aspect within [`TRANSIT_ORB`], and the number of such pairs.
+pub fn transit_score(natal: &[u64], transits: &[u64]) -> (u64, u64) {
+    let aspects = aspects_between(transits, natal, TRANSIT_ORB);
+    let score = aspects.iter().map(|(_, _, aspect)| aspect.weight()).sum();
+    (score, aspects.len() as u64)
+}
+
+/// Whether `cusps` bound 12 houses: each at most 360° and each following
+/// the previous one counterclockwise, going around the zodiac exactly once
+pub fn cusps_valid(cusps: &[u64; HOUSE_COUNT as usize]) -> bool {
+    if cusps.iter().any(|cusp| *cusp > MAX_POSITION) {
+        return false;
+    }
+    let mut circuit = 0;
+    for (i, cusp) in cusps.iter().enumerate() {
+        let span = forward_arc(*cusp, cusps[(i + 1) % cusps.len()]);
+        if span == 0 {
+            return false;
+        }
+        circuit += span;
+    }
+    circuit == MAX_POSITION
+}
+
+/// House (1-12) of `longitude_centideg` between [`cusps_valid`] cusps
+///
+/// A longitude on a cusp is in the house that cusp starts. Longitudes at
+/// or above 360° are taken modulo 360°.
+pub fn house_of(longitude_centideg: u64, cusps: &[u64; HOUSE_COUNT as usize]) -> u8 {
+    // The cusp the longitude is the shortest way past
+    let (house, _) = cusps
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, cusp)| forward_arc(**cusp, longitude_centideg))
+        .unwrap_or((0, &0));
+    house as u8 + 1
+}
+
+/// Counterclockwise arc from `from_centideg` to `to_centideg`
+/// (0..36000)
+fn forward_arc(from_centideg: u64, to_centideg: u64) -> u64 {
+    (to_centideg % MAX_POSITION + MAX_POSITION - from_centideg % MAX_POSITION) % MAX_POSITION
+}
+
+/// Sign of the Sun in `chart`
+fn sun_sign(chart: &[PlanetPosition]) -> Result<u8, PositionError> {
+    // Unpacked longitudes are at most 360°
+    Ok(((sun_longitude(chart)? % MAX_POSITION) / SIGN_WIDTH as u64) as u8)
+}
+
+/// Longitude of the Sun in `chart`, in centidegrees
+pub fn sun_longitude(chart: &[PlanetPosition]) -> Result<u64, PositionError> {
+    chart
+        .iter()
+        .find(|position| position.body == SUN)
+        .map(|sun| sun.longitude_centideg as u64)
+        .ok_or(PositionError::MissingBody(SUN))
+}
+
+/// Compatibility (0-100) of two signs, from [`COMPATIBILITY`]
+pub fn compatibility_score(sign_a: u8, sign_b: u8) -> Result<u8, AstroError> {
+    for sign in [sign_a, sign_b] {
+        if sign >= SIGN_COUNT {
+            return Err(AstroError::SignOutOfRange(sign));
+        }
+    }
+    Ok(COMPATIBILITY[sign_a as usize][sign_b as usize])
+}
+
+/// Compatibility (0-100) of two charts packed by
+/// [`crate::positions::pack_chart`], and the number of aspects between them
+///
+/// The score is 60% the sun signs' [`compatibility_score`] and 40% the
+/// share of harmonious aspects among all aspects between the charts
+/// within [`SYNASTRY_ORB`] (half, when there are none).
+pub fn chart_compatibility(
+    packed_a: &[FixedBytes<32>; 2],
+    packed_b: &[FixedBytes<32>; 2],
+) -> Result<(u8, u8), PositionError> {
+    let chart_a = unpack_chart(packed_a)?;
+    let chart_b = unpack_chart(packed_b)?;
+    let sign_score = COMPATIBILITY[sun_sign(&chart_a)? as usize][sun_sign(&chart_b)? as usize] as u16;
+
+    let longitudes = |chart: &[PlanetPosition]| -> Vec<u64> {
+        chart.iter().map(|position| position.longitude_centideg as u64).collect()
+    };
+    let aspects = aspects_between(&longitudes(&chart_a), &longitudes(&chart_b), SYNASTRY_ORB);
+    let harmonious = aspects.iter().filter(|(_, _, aspect)| aspect.is_harmonious()).count() as u16;
+    let aspect_score = match aspects.len() as u16 {
+        0 => 50,
+        count => harmonious * 100 / count,
+    };
+
+    let score = (sign_score * SIGN_SHARE + aspect_score * (100 - SIGN_SHARE)) / 100;
+    Ok((score as u8, aspects.len() as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_boundaries() {
+        for sign in 0..SIGN_COUNT {
+            let cusp = sign as u64 * SIGN_WIDTH as u64;
+            assert_eq!(degree_in_sign(cusp), Ok((sign, 0)));
+            assert_eq!(degree_in_sign(cusp + 2999), Ok((sign, 2999)));
+            if sign > ARIES {
+                assert_eq!(sign_of(cusp - 1), Ok(sign - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_wraparound_and_range() {
+        assert_eq!(sign_of(35999), Ok(PISCES));
+        assert_eq!(degree_in_sign(36000), Ok((ARIES, 0)));
+        assert_eq!(sign_of(36001), Err(AstroError::LongitudeOutOfRange(36001)));
+        assert_eq!(sign_of(u64::MAX), Err(AstroError::LongitudeOutOfRange(u64::MAX)));
+    }
+
+    #[test]
+    fn test_sign_of_known_longitudes() {
+        // 234.56° is 24.56° Scorpio
+        assert_eq!(degree_in_sign(23456), Ok((7, 2456)));
+        assert_eq!(sign_name(sign_of(23456).unwrap()), "Scorpio");
+    }
+
+    #[test]
+    fn test_sign_names() {
+        assert_eq!(sign_name(ARIES), "Aries");
+        assert_eq!(sign_name(PISCES), "Pisces");
+        assert_eq!(sign_name(SIGN_COUNT), "Unknown");
+    }
+
+    #[test]
+    fn test_ingresses() {
+        // Into Pisces, back into Aquarius while retrograde, into Pisces
+        // again, then across 0° Aries
+        let days = [(1, 32_900), (2, 33_050), (3, 32_980), (4, 33_010), (6, 35_990), (7, 10)];
+        assert_eq!(ingresses(days), Ok(vec![(2, 10, 11), (3, 11, 10), (4, 10, 11), (7, 11, 0)]));
+        assert_eq!(ingresses([(1, 100), (2, 200)]), Ok(vec![]));
+        assert_eq!(ingresses([]), Ok(vec![]));
+        assert_eq!(ingresses([(1, 100), (2, 36_001)]), Err(AstroError::LongitudeOutOfRange(36_001)));
+    }
+
+    #[test]
+    fn test_angular_separation() {
+        assert_eq!(angular_separation(1000, 4000), 3000);
+        assert_eq!(angular_separation(4000, 1000), 3000);
+        assert_eq!(angular_separation(0, 18000), 18000);
+        assert_eq!(angular_separation(0, 36000), 0);
+        // 359° vs 1° is 2° across Aries, not 358°
+        assert_eq!(angular_separation(35900, 100), 200);
+        assert_eq!(angular_separation(100, 35900), 200);
+        assert_eq!(angular_separation(35000, 17000), 18000);
+    }
+
+    #[test]
+    fn test_classify_each_aspect() {
+        let orb = 500;
+        for aspect in Aspect::ALL {
+            let angle = aspect.angle();
+            assert_eq!(classify_aspect(angle, orb), Some(aspect));
+            assert_eq!(classify_aspect(angle + orb, orb), Some(aspect));
+            assert_eq!(classify_aspect(angle + orb + 1, orb), None);
+            if angle > 0 {
+                assert_eq!(classify_aspect(angle - orb, orb), Some(aspect));
+                assert_eq!(classify_aspect(angle - orb - 1, orb), None);
+            }
+        }
+        assert_eq!(classify_aspect(4500, 0), None);
+    }
+
+    #[test]
+    fn test_classify_overlapping_orbs() {
+        // Sextile and square are 3000 apart; the nearer angle wins
+        assert_eq!(classify_aspect(7400, 2000), Some(Aspect::Sextile));
+        assert_eq!(classify_aspect(7600, 2000), Some(Aspect::Square));
+        assert_eq!(classify_aspect(7500, 2000), Some(Aspect::Sextile));
+    }
+
+    #[test]
+    fn test_aspects_across_wraparound() {
+        // 359° conjoins 1°, and trines 119° across 0°
+        let chart_a = [35900];
+        let chart_b = [100, 11900, 8500];
+        assert_eq!(
+            aspects_between(&chart_a, &chart_b, 300),
+            vec![(0, 0, Aspect::Conjunction), (0, 1, Aspect::Trine)]
+        );
+    }
+
+    #[test]
+    fn test_aspects_between_caps_bodies() {
+        let chart = [0u64; MAX_POSITIONS + 4];
+        let aspects = aspects_between(&chart, &chart, 0);
+        assert_eq!(aspects.len(), MAX_POSITIONS * MAX_POSITIONS);
+        assert!(aspects.iter().all(|(i, j, aspect)| {
+            (*i as usize) < MAX_POSITIONS && (*j as usize) < MAX_POSITIONS && *aspect == Aspect::Conjunction
+        }));
+    }
+
+    /// First centidegree of nakshatra `index`, ceil(index × 4000 / 3)
+    fn nakshatra_start(index: u64) -> u64 {
+        (index * 4000).div_ceil(3)
+    }
+
+    #[test]
+    fn test_nakshatra_boundaries() {
+        for index in 0..NAKSHATRA_COUNT {
+            let start = nakshatra_start(index as u64);
+            assert_eq!(nakshatra_of(start), Ok((index, 1)));
+            let end = nakshatra_start(index as u64 + 1) - 1;
+            assert_eq!(nakshatra_of(end), Ok((index, 4)));
+            if index > 0 {
+                assert_eq!(nakshatra_of(start - 1), Ok((index - 1, 4)));
+            }
+        }
+        // Exact 13°20′ multiples: 40° starts Rohini, 80° starts Punarvasu
+        assert_eq!(nakshatra_of(4000), Ok((3, 1)));
+        assert_eq!(nakshatra_of(3999), Ok((2, 4)));
+        assert_eq!(nakshatra_of(8000), Ok((6, 1)));
+        assert_eq!(nakshatra_name(3), "Rohini");
+    }
+
+    #[test]
+    fn test_padas() {
+        // Padas of Ashwini start at 0, 3°20′, 6°40′ and 10°
+        assert_eq!(nakshatra_of(333), Ok((0, 1)));
+        assert_eq!(nakshatra_of(334), Ok((0, 2)));
+        assert_eq!(nakshatra_of(667), Ok((0, 3)));
+        assert_eq!(nakshatra_of(1000), Ok((0, 4)));
+        assert_eq!(nakshatra_of(1333), Ok((0, 4)));
+        assert_eq!(nakshatra_of(1334), Ok((1, 1)));
+    }
+
+    #[test]
+    fn test_nakshatra_wraparound() {
+        assert_eq!(nakshatra_of(35999), Ok((26, 4)));
+        assert_eq!(nakshatra_of(36000), Ok((0, 1)));
+        assert_eq!(nakshatra_of(36001), Err(AstroError::LongitudeOutOfRange(36001)));
+        assert_eq!(nakshatra_name(26), "Revati");
+        assert_eq!(nakshatra_name(NAKSHATRA_COUNT), "Unknown");
+    }
+
+    #[test]
+    fn test_tropical_to_sidereal() {
+        // Lahiri ayanamsa is about 24°10′
+        let ayanamsa = 2417;
+        assert_eq!(tropical_to_sidereal(10000, ayanamsa), 7583);
+        assert_eq!(tropical_to_sidereal(2417, ayanamsa), 0);
+        // Wraps below 0° Aries instead of underflowing
+        assert_eq!(tropical_to_sidereal(1000, ayanamsa), 34583);
+        assert_eq!(tropical_to_sidereal(36000, ayanamsa), 33583);
+        assert_eq!(nakshatra_of(tropical_to_sidereal(1000, ayanamsa)), Ok((25, 4)));
+    }
+
+    #[test]
+    fn test_transit_score() {
+        let natal = [0, 9000];
+        // 1° on 0° (conjunction 5) and 89° on 90° (square 3); 180° opposes 0°
+        // (4) and squares 90° (3); 61.5° sextiles 0° (1) but nothing at 90°
+        let transits = [100, 18000, 6150];
+        assert_eq!(transit_score(&natal, &transits), (16, 5));
+        assert_eq!(transit_score(&natal, &[4500]), (0, 0));
+        assert_eq!(transit_score(&[], &transits), (0, 0));
+    }
+
+    #[test]
+    fn test_compatibility_table() {
+        for a in 0..SIGN_COUNT {
+            for b in 0..SIGN_COUNT {
+                let score = compatibility_score(a, b).unwrap();
+                assert!(score <= 100);
+                assert_eq!(score, compatibility_score(b, a).unwrap(), "{} and {}", sign_name(a), sign_name(b));
+                // Same element, a trine apart
+                if a % 4 == b % 4 && a != b {
+                    assert_eq!(score, 95);
+                }
+            }
+            assert_eq!(compatibility_score(a, a), Ok(80));
+        }
+        // Fire and water in square
+        assert_eq!(compatibility_score(ARIES, 3), Ok(40));
+        assert_eq!(compatibility_score(ARIES, 6), Ok(70));
+    }
+
+    #[test]
+    fn test_compatibility_rejects_unknown_signs() {
+        assert_eq!(compatibility_score(SIGN_COUNT, ARIES), Err(AstroError::SignOutOfRange(SIGN_COUNT)));
+        assert_eq!(compatibility_score(PISCES, u8::MAX), Err(AstroError::SignOutOfRange(u8::MAX)));
+    }
+
+    /// Packed chart of bodies `first_body..` all at `longitude`
+    fn uniform_chart(longitude: u16, first_body: u8) -> [FixedBytes<32>; 2] {
+        let positions = core::array::from_fn(|i| PlanetPosition {
+            body: first_body + i as u8,
+            longitude_centideg: longitude,
+            is_retrograde: false,
+        });
+        crate::positions::pack_chart(&positions).unwrap()
+    }
+
+    /// Equal 30° houses from 350°, so the 1st spans 0° Aries
+    const WRAPPING_CUSPS: [u64; 12] =
+        [35000, 2000, 5000, 8000, 11000, 14000, 17000, 20000, 23000, 26000, 29000, 32000];
+
+    #[test]
+    fn test_house_of_across_the_wrap() {
+        assert!(cusps_valid(&WRAPPING_CUSPS));
+        for (longitude, house) in [(35000, 1), (35999, 1), (36000, 1), (0, 1), (1999, 1), (2000, 2), (34999, 12)] {
+            assert_eq!(house_of(longitude, &WRAPPING_CUSPS), house, "{longitude}");
+        }
+        for (i, cusp) in WRAPPING_CUSPS.iter().enumerate() {
+            assert_eq!(house_of(*cusp, &WRAPPING_CUSPS), i as u8 + 1);
+        }
+
+        // Unequal houses, starting at 0°
+        let cusps = [0, 100, 5000, 5001, 18000, 20000, 20500, 25000, 30000, 33000, 35000, 35999];
+        assert!(cusps_valid(&cusps));
+        assert_eq!(house_of(99, &cusps), 1);
+        assert_eq!(house_of(5000, &cusps), 3);
+        assert_eq!(house_of(17999, &cusps), 4);
+        assert_eq!(house_of(35999, &cusps), 12);
+        assert_eq!(house_of(36000, &cusps), 1);
+    }
+
+    #[test]
+    fn test_cusps_valid_rejects_disorder() {
+        let mut swapped = WRAPPING_CUSPS;
+        swapped.swap(4, 5);
+        assert!(!cusps_valid(&swapped));
+
+        let mut repeated = WRAPPING_CUSPS;
+        repeated[3] = repeated[2];
+        assert!(!cusps_valid(&repeated));
+
+        let mut too_far = WRAPPING_CUSPS;
+        too_far[0] = 36001;
+        assert!(!cusps_valid(&too_far));
+
+        // Increasing, but around the zodiac twice
+        let twice: [u64; 12] = core::array::from_fn(|i| i as u64 * 6000 % MAX_POSITION);
+        assert!(!cusps_valid(&twice));
+        assert!(!cusps_valid(&[0; 12]));
+    }
+
+    #[test]
+    fn test_chart_compatibility() {
+        let aries = uniform_chart(0, SUN);
+        // Every pair of bodies is a trine: (95 × 60 + 100 × 40) / 100
+        let leo = uniform_chart(12000, SUN);
+        assert_eq!(chart_compatibility(&aries, &leo), Ok((97, 121)));
+        assert_eq!(chart_compatibility(&leo, &aries), Ok((97, 121)));
+        // Every pair a square: 40 × 60 / 100
+        let cancer = uniform_chart(9000, SUN);
+        assert_eq!(chart_compatibility(&aries, &cancer), Ok((24, 121)));
+        // No aspects within 5° of 45°: the aspects count as neutral
+        let taurus = uniform_chart(4500, SUN);
+        assert_eq!(chart_compatibility(&aries, &taurus), Ok((50, 0)));
+
+        assert_eq!(chart_compatibility(&aries, &uniform_chart(0, 1)), Err(PositionError::MissingBody(SUN)));
+        assert_eq!(chart_compatibility(&aries, &[FixedBytes::ZERO, FixedBytes::repeat_byte(1)]), Err(PositionError::MalformedPacking));
+    }
+
+    /// Times of exact phases from published ephemerides
+    const REFERENCE_PHASES: [(u64, u8); 10] = [
+        (1_704_974_220, NEW_MOON),      // 2024-01-11 11:57 UTC
+        (1_705_549_920, FIRST_QUARTER), // 2024-01-18 03:52
+        (1_706_205_240, FULL_MOON),     // 2024-01-25 17:54
+        (1_706_915_880, LAST_QUARTER),  // 2024-02-02 23:18
+        (1_712_600_460, NEW_MOON),      // 2024-04-08 18:21
+        (1_726_626_840, FULL_MOON),     // 2024-09-18 02:34
+        (1_741_935_300, FULL_MOON),     // 2025-03-14 06:55
+        (1_743_245_880, NEW_MOON),      // 2025-03-29 10:58
+        (1_748_922_060, FIRST_QUARTER), // 2025-06-03 03:41
+        (1_762_348_740, FULL_MOON),     // 2025-11-05 13:19
+    ];
+
+    #[test]
+    fn test_moon_phase_reference_dates() {
+        for (timestamp, expected) in REFERENCE_PHASES {
+            let (phase, illumination) = moon_phase(timestamp);
+            let off_by = (phase + PHASE_COUNT - expected) % PHASE_COUNT;
+            assert!(off_by <= 1 || off_by == PHASE_COUNT - 1, "{timestamp}: phase {phase}, expected {expected}");
+            let expected_illumination = match expected {
+                NEW_MOON => 0,
+                FULL_MOON => 1000,
+                _ => 500,
+            };
+            assert!(illumination.abs_diff(expected_illumination) <= 100, "{timestamp}: {illumination}");
+        }
+    }
+
+    #[test]
+    fn test_moon_phase_epoch_and_cycle() {
+        assert_eq!(moon_phase(NEW_MOON_EPOCH), (NEW_MOON, 0));
+        let half_month = SYNODIC_MONTH_MS / 2000;
+        assert_eq!(moon_phase(NEW_MOON_EPOCH + half_month), (FULL_MOON, 1000));
+        // Before the epoch the cycle runs backwards from the same new moon
+        assert_eq!(moon_phase(NEW_MOON_EPOCH - half_month), (FULL_MOON, 1000));
+        assert_eq!(moon_phase(0).0, moon_phase(SYNODIC_MONTH_MS * 1000).0);
+    }
+}
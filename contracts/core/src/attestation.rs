@@ -0,0 +1,64 @@
+//! Attestations of a rating at a block
+//!
+//! `getRatingAttestation` returns a rating with the block it was read at
+//! and a hash binding both to the chain and contract:
+//!
+//! ```text
+//! attestation = keccak256(chain_id || contract || user || date || rating || block_number)
+//! ```
+//!
+//! `chain_id`, `date` and `block_number` are 32-byte big-endian words,
+//! `contract` and `user` 20-byte addresses and `rating` one byte, i.e.
+//! Solidity's `abi.encodePacked(uint256, address, address, uint256, uint8,
+//! uint256)`. Whoever archives the tuple recomputes the hash with
+//! [`rating_attestation`] to check it wasn't altered.
+
+use alloy_primitives::{Address, U256};
+
+use crate::hash::Keccak256Builder;
+
+/// Hash attesting `rating` of `user`'s prediction for `date`, read at
+/// `block_number` from `contract` on `chain_id`
+pub fn rating_attestation(
+    chain_id: u64,
+    contract: Address,
+    user: Address,
+    date: U256,
+    rating: u8,
+    block_number: u64,
+) -> [u8; 32] {
+    let mut hasher = Keccak256Builder::new();
+    hasher
+        .update(&U256::from(chain_id).to_be_bytes::<32>())
+        .update(contract.as_slice())
+        .update(user.as_slice())
+        .update(&date.to_be_bytes::<32>())
+        .update(&[rating])
+        .update(&U256::from(block_number).to_be_bytes::<32>());
+    hasher.finalize32()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_packed_encoding() {
+        let contract = Address::repeat_byte(0xc0);
+        let user = Address::repeat_byte(0xa1);
+        let date = U256::from(1_728_000_000u64);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&U256::from(42_161).to_be_bytes::<32>());
+        packed.extend_from_slice(contract.as_slice());
+        packed.extend_from_slice(user.as_slice());
+        packed.extend_from_slice(&date.to_be_bytes::<32>());
+        packed.push(4);
+        packed.extend_from_slice(&U256::from(1_234).to_be_bytes::<32>());
+        assert_eq!(packed.len(), 32 * 3 + 20 * 2 + 1);
+
+        let attestation = rating_attestation(42_161, contract, user, date, 4, 1_234);
+        assert_eq!(attestation, crate::hash::keccak256(&packed));
+        assert_eq!(hex::encode(attestation), "3dc357195576a0718fa20da042d9b98ebc99eabe13ff770a43eb3f1de7d9b148");
+    }
+}
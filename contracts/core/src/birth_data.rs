@@ -0,0 +1,205 @@
+//! Birth data in a fixed binary form, and the commitment to it
+//!
+//! The packed form is 16 bytes, big-endian, as `abi.encodePacked` would
+//! lay out the same fields:
+//!
+//! ```text
+//! offset  0  version         u16  (FORMAT_VERSION)
+//!         2  year            u16
+//!         4  month           u8   (1-12)
+//!         5  day             u8   (1 to the month's length)
+//!         6  minutes_of_day  u16  (0-1439, local time)
+//!         8  lat_x10000      i32  (degrees × 10^4, ±90°)
+//!        12  lon_x10000      i32  (degrees × 10^4, ±180°)
+//! ```
+//!
+//! The commitment is `keccak256(packed || salt)` with a 32-byte salt, the
+//! same shape ChartRegistry checks a revealed chart input against. With
+//! the salt kept secret, the commitment reveals nothing of the fields; a
+//! reveal shows all of them, down to 1/10000 of a degree.
+
+use crate::calendar::days_in_month;
+use crate::hash::keccak256;
+
+/// Version in the first two bytes of every packed encoding
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Length of the packed encoding
+pub const PACKED_LEN: usize = 16;
+
+/// Largest latitude, 90° × 10^4
+pub const MAX_LAT_X10000: i32 = 900_000;
+
+/// Largest longitude, 180° × 10^4
+pub const MAX_LON_X10000: i32 = 1_800_000;
+
+/// Minutes per day
+const MINUTES_PER_DAY: u16 = 1440;
+
+/// Reasons birth data is out of range or can't be unpacked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BirthDataError {
+    /// Month outside 1-12
+    InvalidMonth,
+    /// Day outside the month, leap years included
+    InvalidDay,
+    /// `minutes_of_day` past 23:59
+    InvalidTime,
+    /// Latitude beyond ±90°
+    InvalidLatitude,
+    /// Longitude beyond ±180°
+    InvalidLongitude,
+    /// Not [`PACKED_LEN`] bytes, or an unknown format version
+    InvalidEncoding,
+}
+
+/// Date, local time and place of birth
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BirthData {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub minutes_of_day: u16,
+    pub lat_x10000: i32,
+    pub lon_x10000: i32,
+}
+
+impl BirthData {
+    /// Check every field is in range
+    pub fn validate(&self) -> Result<(), BirthDataError> {
+        if !(1..=12).contains(&self.month) {
+            return Err(BirthDataError::InvalidMonth);
+        }
+        if self.day == 0 || self.day as u32 > days_in_month(self.year as i32, self.month as u32) {
+            return Err(BirthDataError::InvalidDay);
+        }
+        if self.minutes_of_day >= MINUTES_PER_DAY {
+            return Err(BirthDataError::InvalidTime);
+        }
+        if self.lat_x10000.unsigned_abs() > MAX_LAT_X10000 as u32 {
+            return Err(BirthDataError::InvalidLatitude);
+        }
+        if self.lon_x10000.unsigned_abs() > MAX_LON_X10000 as u32 {
+            return Err(BirthDataError::InvalidLongitude);
+        }
+        Ok(())
+    }
+
+    /// Packed encoding, see the module docs
+    ///
+    /// Doesn't validate; out-of-range fields pack as they are.
+    pub fn pack(&self) -> [u8; PACKED_LEN] {
+        let mut out = [0u8; PACKED_LEN];
+        out[0..2].copy_from_slice(&FORMAT_VERSION.to_be_bytes());
+        out[2..4].copy_from_slice(&self.year.to_be_bytes());
+        out[4] = self.month;
+        out[5] = self.day;
+        out[6..8].copy_from_slice(&self.minutes_of_day.to_be_bytes());
+        out[8..12].copy_from_slice(&self.lat_x10000.to_be_bytes());
+        out[12..16].copy_from_slice(&self.lon_x10000.to_be_bytes());
+        out
+    }
+
+    /// Parse and validate a packed encoding
+    pub fn unpack(bytes: &[u8]) -> Result<Self, BirthDataError> {
+        let bytes: &[u8; PACKED_LEN] = bytes.try_into().map_err(|_| BirthDataError::InvalidEncoding)?;
+        if u16::from_be_bytes([bytes[0], bytes[1]]) != FORMAT_VERSION {
+            return Err(BirthDataError::InvalidEncoding);
+        }
+        let birth_data = BirthData {
+            year: u16::from_be_bytes([bytes[2], bytes[3]]),
+            month: bytes[4],
+            day: bytes[5],
+            minutes_of_day: u16::from_be_bytes([bytes[6], bytes[7]]),
+            lat_x10000: i32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+            lon_x10000: i32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        };
+        birth_data.validate()?;
+        Ok(birth_data)
+    }
+
+    /// `keccak256(pack() || salt)`
+    pub fn commitment(&self, salt: [u8; 32]) -> [u8; 32] {
+        let mut preimage = [0u8; PACKED_LEN + 32];
+        preimage[..PACKED_LEN].copy_from_slice(&self.pack());
+        preimage[PACKED_LEN..].copy_from_slice(&salt);
+        keccak256(&preimage)
+    }
+}
+
+/// Whether `packed` is valid birth data committed to by `commitment`
+/// under `salt`
+pub fn verify_commitment(commitment: [u8; 32], packed: &[u8], salt: [u8; 32]) -> bool {
+    BirthData::unpack(packed).is_ok_and(|birth_data| birth_data.commitment(salt) == commitment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn london() -> BirthData {
+        BirthData { year: 1990, month: 1, day: 1, minutes_of_day: 720, lat_x10000: 515_074, lon_x10000: -1_278 }
+    }
+
+    #[test]
+    fn test_leap_days() {
+        let feb_29 = |year| BirthData { year, month: 2, day: 29, ..london() };
+        assert_eq!(feb_29(2024).validate(), Ok(()));
+        assert_eq!(feb_29(2023).validate(), Err(BirthDataError::InvalidDay));
+        assert_eq!(feb_29(2000).validate(), Ok(()));
+        assert_eq!(feb_29(1900).validate(), Err(BirthDataError::InvalidDay));
+        assert_eq!(BirthData { month: 4, day: 31, ..london() }.validate(), Err(BirthDataError::InvalidDay));
+        assert_eq!(BirthData { day: 0, ..london() }.validate(), Err(BirthDataError::InvalidDay));
+        assert_eq!(BirthData { month: 13, ..london() }.validate(), Err(BirthDataError::InvalidMonth));
+        assert_eq!(BirthData { month: 0, ..london() }.validate(), Err(BirthDataError::InvalidMonth));
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let at = |minutes_of_day, lat_x10000, lon_x10000| {
+            BirthData { minutes_of_day, lat_x10000, lon_x10000, ..london() }.validate()
+        };
+        assert_eq!(at(1439, MAX_LAT_X10000, MAX_LON_X10000), Ok(()));
+        assert_eq!(at(0, -MAX_LAT_X10000, -MAX_LON_X10000), Ok(()));
+        assert_eq!(at(1440, 0, 0), Err(BirthDataError::InvalidTime));
+        assert_eq!(at(0, MAX_LAT_X10000 + 1, 0), Err(BirthDataError::InvalidLatitude));
+        assert_eq!(at(0, -MAX_LAT_X10000 - 1, 0), Err(BirthDataError::InvalidLatitude));
+        assert_eq!(at(0, 0, MAX_LON_X10000 + 1), Err(BirthDataError::InvalidLongitude));
+        assert_eq!(at(0, 0, i32::MIN), Err(BirthDataError::InvalidLongitude));
+    }
+
+    #[test]
+    fn test_pack_round_trips() {
+        let packed = london().pack();
+        assert_eq!(hex::encode(packed), "000107c6010102d00007dc02fffffb02");
+        assert_eq!(BirthData::unpack(&packed), Ok(london()));
+
+        assert_eq!(BirthData::unpack(&packed[..15]), Err(BirthDataError::InvalidEncoding));
+        let mut unknown_version = packed;
+        unknown_version[1] = 2;
+        assert_eq!(BirthData::unpack(&unknown_version), Err(BirthDataError::InvalidEncoding));
+        let mut leap = BirthData { year: 2023, month: 2, day: 28, ..london() }.pack();
+        leap[5] = 29;
+        assert_eq!(BirthData::unpack(&leap), Err(BirthDataError::InvalidDay));
+    }
+
+    #[test]
+    fn test_commitment_golden_vectors() {
+        assert_eq!(hex::encode(london().commitment([7; 32])), "610e54e56ef8e89c258023022a2105119a93ff47b4f67bac1dee0b404dec3f81");
+        assert_eq!(hex::encode(london().commitment([0; 32])), "336fe6152b56a71a1f05021363f69d3329a8a703cda3caeb46de2907157271f9");
+        let southern = BirthData {
+            year: 2024,
+            month: 2,
+            day: 29,
+            minutes_of_day: 1439,
+            lat_x10000: -338_688,
+            lon_x10000: 1_512_093,
+        };
+        assert_eq!(hex::encode(southern.commitment([7; 32])), "afdea1d925eca4b2c7fdb6294fd9ca2ac5c4f53642276efefc91c1e02fbc61b9");
+
+        assert!(verify_commitment(london().commitment([7; 32]), &london().pack(), [7; 32]));
+        assert!(!verify_commitment(london().commitment([7; 32]), &london().pack(), [8; 32]));
+        let invalid = BirthData { month: 13, ..london() };
+        assert!(!verify_commitment(invalid.commitment([7; 32]), &invalid.pack(), [7; 32]));
+    }
+}
@@ -0,0 +1,277 @@
+//! Integer-only calendar conversions
+//!
+//! Proleptic Gregorian dates to and from days since 1970-01-01, after
+//! Howard Hinnant's `days_from_civil` / `civil_from_days` algorithms. Eras
+//! of 400 years (146097 days) make the leap year rules fall out of plain
+//! integer division, so there are no tables and no floats.
+
+use alloy_primitives::U256;
+
+/// Seconds per UTC day (leap seconds are not counted in Unix time)
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Days per 400-year era
+const DAYS_PER_ERA: i64 = 146_097;
+
+/// Days from 0000-03-01 to 1970-01-01
+const UNIX_EPOCH_DAYS: i64 = 719_468;
+
+/// Julian day of the Unix epoch times 100 (JD 2440587.5)
+const UNIX_EPOCH_JD_X100: u64 = 244_058_750;
+
+/// Reasons a date is rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalendarError {
+    /// Past the last day a day number can hold, day `u32::MAX`
+    InvalidDate,
+}
+
+/// Days since 1970-01-01 of the date `y-m-d`
+///
+/// `m` is 1-12 and `d` 1-31; other values give meaningless results.
+pub fn days_from_civil(y: i32, m: u32, d: u32) -> i64 {
+    // Years start in March so the leap day is the last day of the year
+    let y = y as i64 - (m <= 2) as i64;
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let month_from_march = (m as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + d as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * DAYS_PER_ERA + day_of_era - UNIX_EPOCH_DAYS
+}
+
+/// Date `(year, month, day)` of `days` since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + UNIX_EPOCH_DAYS;
+    let era = z.div_euclid(DAYS_PER_ERA);
+    let day_of_era = z - era * DAYS_PER_ERA;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let d = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let m = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let y = year_of_era + era * 400 + (m <= 2) as i64;
+    (y as i32, m as u32, d as u32)
+}
+
+/// Days in month `m` of year `y`, 0 when `m` isn't 1-12
+pub fn days_in_month(y: i32, m: u32) -> u32 {
+    if !(1..=12).contains(&m) {
+        return 0;
+    }
+    let (next_y, next_m) = if m == 12 { (y + 1, 1) } else { (y, m + 1) };
+    (days_from_civil(next_y, next_m, 1) - days_from_civil(y, m, 1)) as u32
+}
+
+/// Year and day of the year (0 = Jan 1, up to 365 in leap years) of
+/// `days` since 1970-01-01
+pub fn year_and_day(days: i64) -> (i32, u32) {
+    let (y, _, _) = civil_from_days(days);
+    (y, (days - days_from_civil(y, 1, 1)) as u32)
+}
+
+/// Julian day of `timestamp` in fixed point: JD × 100, rounded down
+pub fn unix_to_julian_day(timestamp: u64) -> u64 {
+    UNIX_EPOCH_JD_X100 + timestamp / (SECONDS_PER_DAY / 100)
+}
+
+/// Months since January 1970 of the month containing `timestamp`,
+/// `InvalidDate` past the last day number (see [`to_day_number`])
+pub fn month_index(timestamp: u64) -> Result<u32, CalendarError> {
+    let (y, m, _) = civil_from_days(to_day_number(U256::from(timestamp))? as i64);
+    Ok((y - 1970) as u32 * 12 + (m - 1))
+}
+
+/// Start of the UTC day containing `timestamp`
+pub fn align_to_midnight_utc(timestamp: u64) -> u64 {
+    timestamp - timestamp % SECONDS_PER_DAY
+}
+
+/// Day number (days since 1970-01-01) of the UTC day containing `ts`,
+/// `InvalidDate` for any timestamp past day `u32::MAX`
+///
+/// Per-day storage is keyed by day number while the public interface
+/// takes timestamps; this and [`to_timestamp`] are the only conversions
+/// between the two.
+pub fn to_day_number(ts: U256) -> Result<u32, CalendarError> {
+    // Day u32::MAX ends below 2^64 seconds, so wider timestamps are past
+    // it too, and dividing as u64 keeps U256 division out of contracts
+    let ts = u64::try_from(ts).map_err(|_| CalendarError::InvalidDate)?;
+    u32::try_from(ts / SECONDS_PER_DAY).map_err(|_| CalendarError::InvalidDate)
+}
+
+/// Midnight UTC of day number `day`
+pub fn to_timestamp(day: u32) -> U256 {
+    U256::from(day) * U256::from(SECONDS_PER_DAY)
+}
+
+/// Furthest fixed UTC offset in use, in minutes (UTC+14:00)
+pub const MAX_UTC_OFFSET_MINUTES: i16 = 840;
+
+/// Whether `offset_minutes` is within ±[`MAX_UTC_OFFSET_MINUTES`] and a
+/// whole number of quarter hours
+pub fn is_valid_utc_offset(offset_minutes: i16) -> bool {
+    offset_minutes.unsigned_abs() <= MAX_UTC_OFFSET_MINUTES as u16 && offset_minutes % 15 == 0
+}
+
+/// Date key of the local day containing `timestamp` at a fixed UTC
+/// offset: midnight UTC of the same calendar date
+///
+/// Keys stay UTC-aligned, so a local day maps to the key UTC would use
+/// for that date. `None` for local dates before 1970-01-01 or past
+/// `u64::MAX`.
+pub fn local_date_key(timestamp: u64, offset_minutes: i16) -> Option<u64> {
+    let local = timestamp as i128 + offset_minutes as i128 * 60;
+    u64::try_from(local).ok().map(align_to_midnight_utc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 2, 29), 11_016);
+        assert_eq!(days_from_civil(1600, 3, 1), -135_080);
+
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn test_2100_is_not_leap() {
+        let feb_28 = days_from_civil(2100, 2, 28);
+        assert_eq!(feb_28, 47_540);
+        assert_eq!(civil_from_days(feb_28 + 1), (2100, 3, 1));
+        assert_eq!(days_from_civil(2100, 3, 1), feb_28 + 1);
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(days_in_month(2023, 4), 30);
+        assert_eq!(days_in_month(2023, 12), 31);
+        assert_eq!((days_in_month(2023, 0), days_in_month(2023, 13)), (0, 0));
+    }
+
+    #[test]
+    fn test_year_and_day() {
+        assert_eq!(year_and_day(0), (1970, 0));
+        let dec_31 = days_from_civil(1999, 12, 31);
+        assert_eq!(year_and_day(dec_31), (1999, 364));
+        assert_eq!(year_and_day(dec_31 + 1), (2000, 0));
+        assert_eq!(year_and_day(days_from_civil(2000, 2, 29)), (2000, 59));
+        assert_eq!(year_and_day(days_from_civil(2000, 3, 1)), (2000, 60));
+        assert_eq!(year_and_day(days_from_civil(2000, 12, 31)), (2000, 365));
+    }
+
+    #[test]
+    fn test_julian_day() {
+        assert_eq!(unix_to_julian_day(0), 244_058_750);
+        // J2000.0: 2000-01-01 12:00 UTC is JD 2451545.0
+        assert_eq!(unix_to_julian_day(946_728_000), 245_154_500);
+        assert_eq!(unix_to_julian_day(946_728_000 + 863), 245_154_500);
+        assert_eq!(unix_to_julian_day(946_728_000 + 864), 245_154_501);
+    }
+
+    #[test]
+    fn test_month_index_and_midnight() {
+        assert_eq!(month_index(0), Ok(0));
+        assert_eq!(month_index(SECONDS_PER_DAY * 31 - 1), Ok(0));
+        assert_eq!(month_index(SECONDS_PER_DAY * 31), Ok(1));
+        // 2000-02-29 23:59:59
+        let leap_day = days_from_civil(2000, 2, 29) as u64 * SECONDS_PER_DAY;
+        assert_eq!(month_index(leap_day + SECONDS_PER_DAY - 1), Ok(30 * 12 + 1));
+        assert_eq!(month_index(leap_day + SECONDS_PER_DAY), Ok(30 * 12 + 2));
+        let last = u32::MAX as u64 * SECONDS_PER_DAY;
+        assert!(month_index(last + SECONDS_PER_DAY - 1).is_ok());
+        assert_eq!(month_index(last + SECONDS_PER_DAY), Err(CalendarError::InvalidDate));
+
+        assert_eq!(align_to_midnight_utc(leap_day + 12_345), leap_day);
+        assert_eq!(align_to_midnight_utc(leap_day), leap_day);
+        // Days past the last day number still align to their own midnight
+        assert_eq!(align_to_midnight_utc(last + SECONDS_PER_DAY + 1), last + SECONDS_PER_DAY);
+        assert_eq!(align_to_midnight_utc(u64::MAX), u64::MAX - u64::MAX % SECONDS_PER_DAY);
+    }
+
+    #[test]
+    fn test_local_date_key() {
+        let day = days_from_civil(2024, 3, 10) as u64 * SECONDS_PER_DAY;
+        // 20:00 UTC is 01:30 the next day at +05:30, 12:00 the same day at -08:00
+        assert_eq!(local_date_key(day + 20 * 3600, 330), Some(day + SECONDS_PER_DAY));
+        assert_eq!(local_date_key(day + 20 * 3600, -480), Some(day));
+        // 05:00 UTC is still the day before at -08:00
+        assert_eq!(local_date_key(day + 5 * 3600, -480), Some(day - SECONDS_PER_DAY));
+        assert_eq!(local_date_key(day + 5 * 3600, 0), Some(day));
+
+        assert_eq!(local_date_key(3600, -120), None);
+        assert_eq!(local_date_key(u64::MAX, 60), None);
+
+        assert!(is_valid_utc_offset(330) && is_valid_utc_offset(-480) && is_valid_utc_offset(345));
+        assert!(is_valid_utc_offset(840) && is_valid_utc_offset(-840));
+        assert!(!is_valid_utc_offset(841) && !is_valid_utc_offset(-855) && !is_valid_utc_offset(20));
+        assert!(!is_valid_utc_offset(i16::MIN));
+    }
+
+    #[test]
+    fn test_day_numbers() {
+        // Either side of a leap day and of a year end
+        for (y, m, d) in [(2024, 2, 28), (2024, 2, 29), (2024, 3, 1), (2024, 12, 31), (2025, 1, 1), (2100, 2, 28)] {
+            let day = days_from_civil(y, m, d) as u32;
+            let midnight = to_timestamp(day);
+            assert_eq!(to_day_number(midnight), Ok(day), "{y}-{m}-{d}");
+            assert_eq!(to_day_number(midnight + U256::from(SECONDS_PER_DAY - 1)), Ok(day), "{y}-{m}-{d}");
+            assert_eq!(to_day_number(midnight - U256::from(1)), Ok(day - 1), "{y}-{m}-{d}");
+        }
+        assert_eq!(to_timestamp(days_from_civil(2024, 2, 29) as u32), U256::from(1_709_164_800));
+        assert_eq!(to_day_number(U256::ZERO), Ok(0));
+    }
+
+    #[test]
+    fn test_day_number_bounds() {
+        // The last second of the last day still has a day number, the next
+        // one is rejected rather than sharing it
+        let past_last = to_timestamp(u32::MAX) + U256::from(SECONDS_PER_DAY);
+        assert_eq!(to_day_number(to_timestamp(u32::MAX)), Ok(u32::MAX));
+        assert_eq!(to_day_number(past_last - U256::from(1)), Ok(u32::MAX));
+        assert_eq!(to_day_number(past_last), Err(CalendarError::InvalidDate));
+        assert_eq!(to_day_number(U256::MAX), Err(CalendarError::InvalidDate));
+    }
+
+    mod fuzz {
+        use super::*;
+        use proptest::prelude::*;
+        use proptest::test_runner::RngSeed;
+
+        fn config() -> ProptestConfig {
+            ProptestConfig { cases: 512, rng_seed: RngSeed::Fixed(0x5a4b_4173_7472_6f01), ..ProptestConfig::default() }
+        }
+
+        proptest! {
+            #![proptest_config(config())]
+
+            #[test]
+            fn round_trip_timestamps(timestamp in 0u64..253_402_300_800) {
+                let days = (timestamp / SECONDS_PER_DAY) as i64;
+                let (y, m, d) = civil_from_days(days);
+                prop_assert!((1..=12).contains(&m) && (1..=31).contains(&d));
+                prop_assert_eq!(days_from_civil(y, m, d), days);
+                prop_assert_eq!(align_to_midnight_utc(timestamp), days as u64 * SECONDS_PER_DAY);
+            }
+
+            #[test]
+            fn round_trip_days(days in -1_000_000i64..1_000_000) {
+                let (y, m, d) = civil_from_days(days);
+                prop_assert_eq!(days_from_civil(y, m, d), days);
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Parsed commitment to a user's birth data
+
+use alloc::string::String;
+
+use alloy_primitives::FixedBytes;
+
+use crate::hash::{from_hex32, to_hex};
+
+/// Reasons a commitment string could not be parsed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitmentError {
+    /// Not exactly 64 hex digits (after an optional 0x prefix)
+    InvalidLength(usize),
+    /// A character outside [0-9a-fA-F]
+    InvalidHexDigit,
+}
+
+/// 32-byte commitment to a user's birth data
+///
+/// Clients submit commitments as hex strings; this is the one parsed form
+/// shared by the verifiers and the contract's `bytes32` storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Commitment(pub [u8; 32]);
+
+impl Commitment {
+    /// Parse exactly 64 hex digits, either case, with an optional 0x prefix
+    pub fn from_hex(s: &str) -> Result<Self, CommitmentError> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        if digits.len() != 64 {
+            return Err(CommitmentError::InvalidLength(digits.len()));
+        }
+
+        from_hex32(digits.as_bytes()).map(Commitment).ok_or(CommitmentError::InvalidHexDigit)
+    }
+
+    /// Canonical form: 64 lowercase hex digits, no prefix
+    pub fn to_hex_lower(&self) -> String {
+        to_hex(&self.0).iter().map(|b| char::from(*b)).collect()
+    }
+}
+
+impl From<FixedBytes<32>> for Commitment {
+    fn from(bytes: FixedBytes<32>) -> Self {
+        Commitment(bytes.0)
+    }
+}
+
+impl From<Commitment> for FixedBytes<32> {
+    fn from(commitment: Commitment) -> Self {
+        FixedBytes(commitment.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMITMENT: &str = "1b7f3c9e0d2a4b6c8e0f1a3b5c7d9e1f2a4b6c8d0e1f3a5b7c9d1e3f5a7b9c1d";
+
+    fn commitment() -> Commitment {
+        Commitment::from_hex(COMMITMENT).unwrap()
+    }
+
+    #[test]
+    fn test_commitment_from_hex() {
+        let expected = commitment();
+        assert_eq!(expected.to_hex_lower(), COMMITMENT);
+
+        let prefixed = format!("0x{COMMITMENT}");
+        assert_eq!(Commitment::from_hex(&prefixed), Ok(expected));
+        assert_eq!(Commitment::from_hex(&COMMITMENT.to_ascii_uppercase()), Ok(expected));
+        assert_eq!(Commitment::from_hex(&COMMITMENT[..63]), Err(CommitmentError::InvalidLength(63)));
+        assert_eq!(Commitment::from_hex(&format!("{COMMITMENT}0")), Err(CommitmentError::InvalidLength(65)));
+        assert_eq!(Commitment::from_hex(""), Err(CommitmentError::InvalidLength(0)));
+        assert_eq!(
+            Commitment::from_hex(&COMMITMENT.replace('b', "g")),
+            Err(CommitmentError::InvalidHexDigit)
+        );
+    }
+
+    #[test]
+    fn test_commitment_bytes32_round_trip() {
+        let bytes: FixedBytes<32> = commitment().into();
+        assert_eq!(Commitment::from(bytes), commitment());
+    }
+
+    mod fuzz {
+        use super::*;
+        use proptest::prelude::*;
+        use proptest::test_runner::RngSeed;
+
+        fn config() -> ProptestConfig {
+            ProptestConfig { cases: 512, rng_seed: RngSeed::Fixed(0x5a4b_4173_7472_6f00), ..ProptestConfig::default() }
+        }
+
+        fn text() -> impl Strategy<Value = String> {
+            prop_oneof![any::<String>(), "[0-9a-fA-Fx\\x00]{0,70}"]
+        }
+
+        proptest! {
+            #![proptest_config(config())]
+
+            #[test]
+            fn fuzz_commitment_from_hex(s in text()) {
+                if let Ok(commitment) = Commitment::from_hex(&s) {
+                    prop_assert_eq!(Commitment::from_hex(&commitment.to_hex_lower()), Ok(commitment));
+                }
+            }
+        }
+    }
+}
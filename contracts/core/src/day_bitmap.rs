@@ -0,0 +1,115 @@
+//! Bitmaps of days, one per year
+//!
+//! A year's days fit two 256-bit words: day `d` of the year (0-365, see
+//! [`calendar::year_and_day`]) is bit `d % 256` of word `d / 256`. With
+//! one bitmap per user and year, a contract reads a range of days a word
+//! at a time instead of a slot per day.
+
+use alloc::vec::Vec;
+
+use alloy_primitives::U256;
+
+use crate::calendar;
+
+/// Words per year's bitmap
+pub const WORDS_PER_YEAR: usize = 2;
+
+/// First timestamp past the bitmaps' range (10000-01-01)
+pub const DATE_LIMIT: u64 = 253_402_300_800;
+
+const WORD_BITS: u32 = 256;
+
+/// Where a day's bit lives
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DaySlot {
+    pub year: u32,
+    pub word: usize,
+    pub bit: usize,
+}
+
+/// Consecutive days sharing a bitmap word
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WordSpan {
+    pub year: u32,
+    pub word: usize,
+    /// First and last day (days since 1970-01-01) of the span, inclusive
+    pub first_day: u64,
+    pub last_day: u64,
+}
+
+/// Slot of `day` (days since 1970-01-01)
+pub fn day_slot(day: u64) -> DaySlot {
+    let (year, day_of_year) = calendar::year_and_day(day as i64);
+    DaySlot {
+        year: year as u32,
+        word: (day_of_year / WORD_BITS) as usize,
+        bit: (day_of_year % WORD_BITS) as usize,
+    }
+}
+
+/// Slot of the midnight-aligned timestamp `date`, `None` for any other
+/// date or one past [`DATE_LIMIT`]
+pub fn date_slot(date: U256) -> Option<DaySlot> {
+    let day = calendar::to_day_number(date).ok()?;
+    (date < U256::from(DATE_LIMIT) && calendar::to_timestamp(day) == date).then(|| day_slot(day.into()))
+}
+
+/// Words covering the days `first_day..=last_day`, in order
+///
+/// Reading a range costs one load per span, whatever the number of days.
+pub fn word_spans(first_day: u64, last_day: u64) -> Vec<WordSpan> {
+    let mut spans: Vec<WordSpan> = Vec::new();
+    for day in first_day..=last_day {
+        let slot = day_slot(day);
+        match spans.last_mut() {
+            Some(span) if span.year == slot.year && span.word == slot.word => span.last_day = day,
+            _ => spans.push(WordSpan { year: slot.year, word: slot.word, first_day: day, last_day: day }),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::days_from_civil;
+
+    fn day(y: i32, m: u32, d: u32) -> u64 {
+        days_from_civil(y, m, d) as u64
+    }
+
+    #[test]
+    fn test_slots_across_years() {
+        assert_eq!(day_slot(day(2024, 12, 31)), DaySlot { year: 2024, word: 1, bit: 109 });
+        assert_eq!(day_slot(day(2025, 1, 1)), DaySlot { year: 2025, word: 0, bit: 0 });
+        assert_eq!(day_slot(day(2025, 12, 31)), DaySlot { year: 2025, word: 1, bit: 108 });
+        // Leap day shifts the rest of the year by one
+        assert_eq!(day_slot(day(2024, 2, 29)), DaySlot { year: 2024, word: 0, bit: 59 });
+        assert_eq!(day_slot(day(2024, 3, 1)), DaySlot { year: 2024, word: 0, bit: 60 });
+        assert_eq!(day_slot(day(2025, 3, 1)), DaySlot { year: 2025, word: 0, bit: 59 });
+
+        let midnight = U256::from(day(2024, 2, 29) * calendar::SECONDS_PER_DAY);
+        assert_eq!(date_slot(midnight), Some(day_slot(day(2024, 2, 29))));
+        assert_eq!(date_slot(midnight + U256::from(1)), None);
+        assert_eq!(date_slot(U256::from(DATE_LIMIT)), None);
+        assert_eq!(date_slot(U256::MAX), None);
+    }
+
+    #[test]
+    fn test_word_spans() {
+        let spans = word_spans(day(2024, 12, 20), day(2025, 1, 10));
+        assert_eq!(
+            spans,
+            [
+                WordSpan { year: 2024, word: 1, first_day: day(2024, 12, 20), last_day: day(2024, 12, 31) },
+                WordSpan { year: 2025, word: 0, first_day: day(2025, 1, 1), last_day: day(2025, 1, 10) },
+            ]
+        );
+
+        // Any 30 days touch at most two words
+        for first in day(2023, 1, 1)..day(2025, 1, 1) {
+            assert!(word_spans(first, first + 29).len() <= 2);
+        }
+        assert_eq!(word_spans(day(2024, 1, 1), day(2024, 12, 31)).len(), WORDS_PER_YEAR);
+    }
+}
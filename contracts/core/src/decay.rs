@@ -0,0 +1,105 @@
+//! Time-decayed averages in integer math
+//!
+//! A sample `d` days old weighs `2^(-d / half_life)` of a fresh one. The
+//! average keeps its value and its total weight; a new sample first
+//! decays both by the days since the last one, then adds itself with
+//! weight 1:
+//!
+//! ```text
+//! weight' = weight × decay + 1
+//! value'  = (value × weight × decay + sample) / weight'
+//! ```
+//!
+//! Weights and decay factors are fixed point with [`ONE`] as 1. The whole
+//! halvings of `2^(-d / half_life)` are shifts; the remaining fraction is
+//! rounded to the nearest 1/32 and read from [`MULTIPLIERS`], so a factor
+//! is within 1.1% of the exact one.
+
+/// Fixed-point 1 of weights and decay factors
+pub const ONE: u64 = 1_000_000_000;
+
+/// Steps of the fractional halving in [`MULTIPLIERS`]
+pub const STEPS: u64 = 32;
+
+/// `2^(-i / 32)` × [`ONE`], rounded
+pub const MULTIPLIERS: [u64; STEPS as usize] = [
+    1_000_000_000, 978_572_062, 957_603_281, 937_083_817,
+    917_004_043, 897_354_538, 878_126_080, 859_309_649,
+    840_896_415, 822_877_739, 805_245_166, 787_990_423,
+    771_105_413, 754_582_214, 738_413_073, 722_590_403,
+    707_106_781, 691_954_941, 677_127_773, 662_618_322,
+    648_419_777, 634_525_479, 620_928_906, 607_623_680,
+    594_603_558, 581_862_429, 569_394_317, 557_193_371,
+    545_253_866, 533_570_200, 522_136_891, 510_948_574,
+];
+
+/// `2^(-days / half_life_days)` × [`ONE`]; 1 for a zero half-life
+pub fn decay_factor(days: u64, half_life_days: u64) -> u64 {
+    if half_life_days == 0 {
+        return ONE;
+    }
+    let mut halvings = days / half_life_days;
+    let remainder = (days % half_life_days) as u128;
+    let mut step = ((remainder * STEPS as u128 * 2 + half_life_days as u128) / (half_life_days as u128 * 2)) as u64;
+    if step == STEPS {
+        halvings += 1;
+        step = 0;
+    }
+    if halvings >= u64::BITS as u64 {
+        return 0;
+    }
+    MULTIPLIERS[step as usize] >> halvings
+}
+
+/// Fold `sample` into the average `(value, weight)` after decaying it by
+/// `decay` (see [`decay_factor`])
+///
+/// Returns: the new `(value, weight)`, the value rounded to the nearest
+/// unit of `sample`
+pub fn fold(value: u64, weight: u64, sample: u64, decay: u64) -> (u64, u64) {
+    let decayed = weight as u128 * decay as u128 / ONE as u128;
+    let new_weight = decayed + ONE as u128;
+    let new_value = (value as u128 * decayed + sample as u128 * ONE as u128 + new_weight / 2) / new_weight;
+    (new_value as u64, new_weight.min(u64::MAX as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decay_factor_halves_each_half_life() {
+        assert_eq!(decay_factor(0, 7), ONE);
+        assert_eq!(decay_factor(7, 7), ONE / 2);
+        assert_eq!(decay_factor(21, 7), ONE / 8);
+        assert_eq!(decay_factor(5, 0), ONE);
+        assert_eq!(decay_factor(64 * 7, 7), 0);
+        assert_eq!(decay_factor(u64::MAX, 1), 0);
+
+        // Within 1.1% of the exact factor, and never rising with age
+        let mut previous = ONE;
+        for days in 0..100 {
+            let factor = decay_factor(days, 30);
+            let exact = ONE as f64 * 2f64.powf(-(days as f64) / 30.0);
+            assert!((factor as f64 - exact).abs() <= exact * 0.011, "{days} days: {factor} vs {exact}");
+            assert!(factor <= previous);
+            previous = factor;
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        // The first sample is the average
+        assert_eq!(fold(0, 0, 5000, ONE), (5000, ONE));
+
+        // Undecayed samples average equally
+        let (value, weight) = fold(5000, ONE, 1000, ONE);
+        assert_eq!((value, weight), (3000, 2 * ONE));
+
+        // A half-weight old average counts half as much as the new sample
+        assert_eq!(fold(4000, ONE, 1000, ONE / 2), (2000, ONE + ONE / 2));
+
+        // Fully decayed history is forgotten
+        assert_eq!(fold(5000, 10 * ONE, 1000, 0), (1000, ONE));
+    }
+}
@@ -0,0 +1,41 @@
+//! Entry statuses of `FarcasterPredictions::finalize_batch`
+//!
+//! `finalize_batch` takes (user, date) pairs of proposed predictions and
+//! returns one status per entry in input order. Only entries that get
+//! [`FINALIZED`] are stored; the others are skipped.
+//!
+//! Ids are never reused or renumbered.
+
+/// Most entries one `finalize_batch` call takes
+pub const MAX_FINALIZE_ENTRIES: usize = 100;
+
+/// The proposal was stored
+pub const FINALIZED: u8 = 0;
+/// Nothing is proposed for the date: it was rejected or never proposed
+pub const NOT_PENDING: u8 = 1;
+/// The date is already stored: the proposal was confirmed or finalized,
+/// or the user stored the date directly
+pub const ALREADY_STORED: u8 = 2;
+/// The proposal's challenge window hasn't passed yet
+pub const WINDOW_OPEN: u8 = 3;
+
+/// Names indexed by status
+const STATUS_NAMES: [&str; 4] = ["Finalized", "NotPending", "AlreadyStored", "WindowOpen"];
+
+/// Name of `status`, or `"Unknown"` for statuses not defined here
+pub fn status_name(status: u8) -> &'static str {
+    STATUS_NAMES.get(status as usize).copied().unwrap_or("Unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_names() {
+        assert_eq!(status_name(FINALIZED), "Finalized");
+        assert_eq!(status_name(ALREADY_STORED), "AlreadyStored");
+        assert_eq!(status_name(WINDOW_OPEN), "WindowOpen");
+        assert_eq!(status_name(WINDOW_OPEN + 1), "Unknown");
+    }
+}
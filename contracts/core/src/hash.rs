@@ -0,0 +1,304 @@
+//! Streaming keccak256, hex formatting and the Fiat-Shamir transcript
+//!
+//! Hashes are computed with `tiny-keccak` directly so these helpers behave
+//! the same in contracts, the prover and tests.
+
+use alloy_primitives::Address;
+use tiny_keccak::{Hasher, Keccak};
+
+/// keccak256 of `data`
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(data);
+    hasher.finalize32()
+}
+
+/// Incremental keccak256 over typed inputs
+///
+/// Feeds every input straight into the sponge, so digests of concatenated
+/// data need no intermediate buffer.
+#[derive(Clone)]
+pub struct Keccak256Builder {
+    hasher: Keccak,
+}
+
+impl Default for Keccak256Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keccak256Builder {
+    pub fn new() -> Self {
+        Keccak256Builder { hasher: Keccak::v256() }
+    }
+
+    /// Absorb raw bytes
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        self.hasher.update(data);
+        self
+    }
+
+    /// Absorb a string's UTF-8 bytes
+    pub fn update_str(&mut self, s: &str) -> &mut Self {
+        self.update(s.as_bytes())
+    }
+
+    /// Absorb a u64 as 8 little-endian bytes
+    pub fn update_u64_le(&mut self, value: u64) -> &mut Self {
+        self.update(&value.to_le_bytes())
+    }
+
+    /// Absorb a u64 as a 32-byte big-endian word (Solidity `uint64` in
+    /// `abi.encodePacked` arrays)
+    pub fn update_u64_word(&mut self, value: u64) -> &mut Self {
+        self.update(&[0u8; 24]).update(&value.to_be_bytes())
+    }
+
+    /// Absorb each value with [`Keccak256Builder::update_u64_le`]
+    pub fn update_u64s(&mut self, values: &[u64]) -> &mut Self {
+        for value in values {
+            self.update_u64_le(*value);
+        }
+        self
+    }
+
+    /// Finish and return the digest
+    pub fn finalize32(self) -> [u8; 32] {
+        let mut output = [0u8; 32];
+        self.hasher.finalize(&mut output);
+        output
+    }
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Lowercase hex of a 32-byte digest
+pub fn to_hex(digest: &[u8; 32]) -> [u8; 64] {
+    let mut out = [0u8; 64];
+    for (pair, byte) in out.chunks_exact_mut(2).zip(digest) {
+        pair[0] = HEX_DIGITS[usize::from(byte >> 4)];
+        pair[1] = HEX_DIGITS[usize::from(byte & 0x0f)];
+    }
+    out
+}
+
+/// Parse exactly 64 hex digits (either case, no prefix) into 32 bytes
+pub fn from_hex32(digits: &[u8]) -> Option<[u8; 32]> {
+    if digits.len() != 64 {
+        return None;
+    }
+    let mut out = [0u8; 32];
+    for (byte, pair) in out.iter_mut().zip(digits.chunks_exact(2)) {
+        *byte = hex_value(pair[0])? << 4 | hex_value(pair[1])?;
+    }
+    Some(out)
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Fiat-Shamir transcript over streaming keccak256
+///
+/// Every absorbed item is framed as
+/// `u32_be(len(label)) || label || u64_be(len(data)) || data`, and the
+/// transcript starts with `u32_be(len(domain)) || domain`, so distinct
+/// label/data splits can never collide. Integers are big-endian and
+/// addresses their 20 raw bytes. Nothing is buffered: data is fed straight
+/// into the hasher.
+#[derive(Clone)]
+pub struct Transcript {
+    hasher: Keccak,
+}
+
+impl Transcript {
+    /// Start a transcript bound to `domain`
+    pub fn new(domain: &'static [u8]) -> Self {
+        let mut hasher = Keccak::v256();
+        hasher.update(&(domain.len() as u32).to_be_bytes());
+        hasher.update(domain);
+        Transcript { hasher }
+    }
+
+    fn append_header(&mut self, label: &[u8], data_len: u64) {
+        self.hasher.update(&(label.len() as u32).to_be_bytes());
+        self.hasher.update(label);
+        self.hasher.update(&data_len.to_be_bytes());
+    }
+
+    /// Absorb raw bytes under `label`
+    pub fn append_bytes(&mut self, label: &'static [u8], data: &[u8]) {
+        self.append_header(label, data.len() as u64);
+        self.hasher.update(data);
+    }
+
+    /// Absorb a u64 list under `label`, each value as 8 big-endian bytes
+    pub fn append_u64s(&mut self, label: &'static [u8], values: &[u64]) {
+        self.append_header(label, (values.len() as u64).saturating_mul(8));
+        for value in values {
+            self.hasher.update(&value.to_be_bytes());
+        }
+    }
+
+    /// Absorb an address under `label`
+    pub fn append_address(&mut self, label: &'static [u8], addr: Address) {
+        self.append_bytes(label, addr.as_slice());
+    }
+
+    /// Squeeze a 32-byte challenge under `label`
+    ///
+    /// The challenge is absorbed back into the transcript, so consecutive
+    /// challenges differ even under the same label.
+    pub fn challenge_bytes32(&mut self, label: &'static [u8]) -> [u8; 32] {
+        self.append_header(label, 32);
+        let mut challenge = [0u8; 32];
+        self.hasher.clone().finalize(&mut challenge);
+        self.hasher.update(&challenge);
+        challenge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    // Computed independently over the layout in `test_transcript_hand_computed_digest`
+    const HAND_DIGEST: &str = "51685dcc9445ce866d83add3e2b381692e0b2e71fabedd80e8d9475b58cd7880";
+
+    #[test]
+    fn test_transcript_deterministic() {
+        let run = || {
+            let mut t = Transcript::new(b"test");
+            t.append_bytes(b"a", b"hello");
+            t.append_u64s(b"b", &[1, 2, 3]);
+            t.append_address(b"c", Address::repeat_byte(0x11));
+            t.challenge_bytes32(b"e")
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_transcript_label_separation() {
+        let mut a = Transcript::new(b"test");
+        a.append_bytes(b"commitment", b"data");
+        let mut b = Transcript::new(b"test");
+        b.append_bytes(b"nonce", b"data");
+        assert_ne!(a.challenge_bytes32(b"e"), b.challenge_bytes32(b"e"));
+
+        // Moving bytes between label and data must not collide either
+        let mut c = Transcript::new(b"test");
+        c.append_bytes(b"ab", b"c");
+        let mut d = Transcript::new(b"test");
+        d.append_bytes(b"a", b"bc");
+        assert_ne!(c.challenge_bytes32(b"e"), d.challenge_bytes32(b"e"));
+    }
+
+    #[test]
+    fn test_transcript_domain_separation() {
+        let mut a = Transcript::new(b"domain-a");
+        let mut b = Transcript::new(b"domain-b");
+        assert_ne!(a.challenge_bytes32(b"e"), b.challenge_bytes32(b"e"));
+    }
+
+    #[test]
+    fn test_transcript_consecutive_challenges_differ() {
+        let mut t = Transcript::new(b"test");
+        let first = t.challenge_bytes32(b"e");
+        assert_ne!(first, t.challenge_bytes32(b"e"));
+    }
+
+    #[test]
+    fn test_transcript_hand_computed_digest() {
+        let mut t = Transcript::new(b"ZKAstro.test");
+        t.append_bytes(b"msg", b"abc");
+        t.append_u64s(b"pos", &[1, 0x0102]);
+        t.append_address(b"who", Address::repeat_byte(0xaa));
+        let challenge = t.challenge_bytes32(b"e");
+
+        let mut preimage = Vec::new();
+        preimage.extend_from_slice(&[0, 0, 0, 12]);
+        preimage.extend_from_slice(b"ZKAstro.test");
+        preimage.extend_from_slice(&[0, 0, 0, 3]);
+        preimage.extend_from_slice(b"msg");
+        preimage.extend_from_slice(&3u64.to_be_bytes());
+        preimage.extend_from_slice(b"abc");
+        preimage.extend_from_slice(&[0, 0, 0, 3]);
+        preimage.extend_from_slice(b"pos");
+        preimage.extend_from_slice(&16u64.to_be_bytes());
+        preimage.extend_from_slice(&1u64.to_be_bytes());
+        preimage.extend_from_slice(&0x0102u64.to_be_bytes());
+        preimage.extend_from_slice(&[0, 0, 0, 3]);
+        preimage.extend_from_slice(b"who");
+        preimage.extend_from_slice(&20u64.to_be_bytes());
+        preimage.extend_from_slice(&[0xaa; 20]);
+        preimage.extend_from_slice(&[0, 0, 0, 1]);
+        preimage.extend_from_slice(b"e");
+        preimage.extend_from_slice(&32u64.to_be_bytes());
+        assert_eq!(challenge, keccak256(&preimage));
+
+        // Independently computed over the same layout
+        assert_eq!(&to_hex(&challenge), HAND_DIGEST.as_bytes());
+    }
+
+    #[test]
+    fn test_builder_chunking_irrelevant() {
+        let mut whole = Keccak256Builder::new();
+        whole.update(b"abcdef");
+        let mut split = Keccak256Builder::new();
+        split.update_str("ab").update(b"").update_str("cdef");
+        assert_eq!(whole.finalize32(), split.finalize32());
+
+        let mut ints = Keccak256Builder::new();
+        ints.update_u64s(&[1, 2]);
+        let mut bytes = Keccak256Builder::new();
+        bytes.update(&[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(ints.finalize32(), bytes.finalize32());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let digest = keccak256(b"zkastro");
+        let hex = to_hex(&digest);
+        assert_eq!(from_hex32(&hex), Some(digest));
+        assert_eq!(from_hex32(&hex.to_ascii_uppercase()), Some(digest));
+        assert_eq!(from_hex32(&hex[..63]), None);
+        assert_eq!(from_hex32(b""), None);
+
+        let mut bad = hex;
+        bad[10] = b'g';
+        assert_eq!(from_hex32(&bad), None);
+    }
+
+    mod fuzz {
+        use super::*;
+        use proptest::prelude::*;
+        use proptest::test_runner::RngSeed;
+
+        fn config() -> ProptestConfig {
+            ProptestConfig { cases: 512, rng_seed: RngSeed::Fixed(0x5a4b_4173_7472_6f03), ..ProptestConfig::default() }
+        }
+
+        proptest! {
+            #![proptest_config(config())]
+
+            #[test]
+            fn fuzz_hex_matches_hex_crate(bytes in any::<[u8; 32]>()) {
+                prop_assert_eq!(to_hex(&bytes).to_vec(), hex::encode(bytes).into_bytes());
+            }
+
+            #[test]
+            fn fuzz_from_hex32_matches_hex_crate(s in "[0-9a-fA-Fg ]{62,66}") {
+                let mut expected = [0u8; 32];
+                let decoded = hex::decode_to_slice(&s, &mut expected).ok().map(|_| expected);
+                prop_assert_eq!(from_hex32(s.as_bytes()), decoded);
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Merkle roots of a user's prediction history
+//!
+//! FarcasterPredictions merkleizes a user's stored predictions so a later
+//! contract can accept them day by day against one root, without a
+//! trusted importer. The leaves follow the user's list of dates, as
+//! `exportUser` returns it, skipping pruned dates:
+//!
+//! ```text
+//! leaf = keccak256(date || prediction_hash || rating)
+//! ```
+//!
+//! `date` is a 32-byte big-endian word, `prediction_hash` the 32 stored
+//! bytes and `rating` one byte (0 = unrated), i.e. Solidity's
+//! `abi.encodePacked(uint256, bytes32, uint8)`. Leaves are 65 bytes
+//! hashed and inner nodes 64, so no node passes for a leaf. The tree is
+//! [`merkle`](crate::merkle)'s, so [`merkle::proof`](crate::merkle::proof)
+//! over the same leaves gives the proof of one day.
+
+use alloy_primitives::{FixedBytes, U256};
+
+use crate::hash::Keccak256Builder;
+
+type B32 = FixedBytes<32>;
+
+/// Leaf of one day of a user's history
+pub fn leaf(date: U256, prediction_hash: B32, rating: u8) -> B32 {
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(&date.to_be_bytes::<32>()).update(prediction_hash.as_slice()).update(&[rating]);
+    B32::from(hasher.finalize32())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_packed_encoding() {
+        let date = U256::from(1_728_000_000u64);
+        let hash = B32::repeat_byte(0x42);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&date.to_be_bytes::<32>());
+        packed.extend_from_slice(hash.as_slice());
+        packed.push(3);
+        assert_eq!(packed.len(), 65);
+        assert_eq!(leaf(date, hash, 3).0, crate::hash::keccak256(&packed));
+        assert_ne!(leaf(date, hash, 0), leaf(date, hash, 3));
+    }
+}
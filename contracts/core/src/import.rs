@@ -0,0 +1,45 @@
+//! Entry statuses of the contracts' admin import paths
+//!
+//! `FarcasterPredictions::import_users` and `ChartRegistry::import_charts`
+//! check every entry of a batch before writing anything, and return one
+//! status per entry in input order. A dry run returns the statuses
+//! without writing; a real run writes only when every entry is [`OK`].
+//!
+//! Ids are never reused or renumbered.
+
+/// Most entries one import call takes
+pub const MAX_IMPORT_ENTRIES: usize = 100;
+
+/// The entry can be imported
+pub const OK: u8 = 0;
+/// A zero address, hash or commitment, or an empty chart id
+pub const INVALID: u8 = 1;
+/// A timestamp of zero or in the future
+pub const OUT_OF_RANGE: u8 = 2;
+/// The user or chart is already stored
+pub const ALREADY_EXISTS: u8 = 3;
+/// The same user or chart id appears earlier in the batch
+pub const DUPLICATE: u8 = 4;
+/// Importing the entry would overflow a counter
+pub const COUNTER_OVERFLOW: u8 = 5;
+
+/// Names indexed by status
+const STATUS_NAMES: [&str; 6] = ["Ok", "Invalid", "OutOfRange", "AlreadyExists", "Duplicate", "CounterOverflow"];
+
+/// Name of `status`, or `"Unknown"` for statuses not defined here
+pub fn status_name(status: u8) -> &'static str {
+    STATUS_NAMES.get(status as usize).copied().unwrap_or("Unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_names() {
+        assert_eq!(status_name(OK), "Ok");
+        assert_eq!(status_name(ALREADY_EXISTS), "AlreadyExists");
+        assert_eq!(status_name(COUNTER_OVERFLOW), "CounterOverflow");
+        assert_eq!(status_name(COUNTER_OVERFLOW + 1), "Unknown");
+    }
+}
@@ -0,0 +1,93 @@
+//! Chart hashes of the legacy JavaScript registration
+//!
+//! Charts registered before packed positions were hashed by the backend
+//! (`lib/blockchain/onchain-registry.ts`) as
+//!
+//! ```text
+//! keccak256(utf8(JSON.stringify({ planets, asc, mc, zkProof })))
+//! ```
+//!
+//! [`chart_json`] rebuilds those exact bytes:
+//!
+//! ```text
+//! {"planets":{"sun":S,"moon":M,"mercury":..,"venus":..,"mars":..,"jupiter":..,"saturn":..},"asc":A,"mc":C,"zkProof":"P"}
+//! ```
+//!
+//! Keys come in that fixed order with no whitespace. Longitudes are
+//! integer centidegrees, written as plain decimal integers (negative for
+//! some charts before 2000). The proof string is escaped as
+//! `JSON.stringify` escapes it: `"` and `\` are backslashed,
+//! `\b \f \n \r \t` are used for those control characters, other control
+//! characters become `\u00xx` and everything else is kept as UTF-8.
+//!
+//! `contracts/tests/legacy_chart_hashes.json` holds hashes computed by the
+//! JavaScript itself (`scripts/generate-legacy-chart-fixtures.ts`).
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::hash::keccak256;
+
+/// Keys of the `planets` object, in the order they are serialized
+pub const PLANET_KEYS: [&str; 7] = ["sun", "moon", "mercury", "venus", "mars", "jupiter", "saturn"];
+
+/// JSON the legacy backend hashed for a chart
+///
+/// `planets` are the longitudes of [`PLANET_KEYS`], in that order.
+pub fn chart_json(planets: &[i64; 7], asc: i64, mc: i64, zk_proof: &str) -> String {
+    let mut json = String::from("{\"planets\":{");
+    for (i, (key, longitude)) in PLANET_KEYS.iter().zip(planets).enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let _ = write!(json, "\"{key}\":{longitude}");
+    }
+    let _ = write!(json, "}},\"asc\":{asc},\"mc\":{mc},\"zkProof\":\"");
+    push_escaped(&mut json, zk_proof);
+    json.push_str("\"}");
+    json
+}
+
+/// Chart hash the legacy backend registered
+pub fn legacy_chart_hash(planets: &[i64; 7], asc: i64, mc: i64, zk_proof: &str) -> [u8; 32] {
+    keccak256(chart_json(planets, asc, mc, zk_proof).as_bytes())
+}
+
+/// Append `value` escaped as `JSON.stringify` escapes string contents
+fn push_escaped(json: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\u{8}' => json.push_str("\\b"),
+            '\u{c}' => json.push_str("\\f"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chart_json() {
+        let json = chart_json(&[28045, -1, 0, 3, 4, 5, 35999], -4500, 9, "0xab");
+        assert_eq!(
+            json,
+            r#"{"planets":{"sun":28045,"moon":-1,"mercury":0,"venus":3,"mars":4,"jupiter":5,"saturn":35999},"asc":-4500,"mc":9,"zkProof":"0xab"}"#
+        );
+    }
+
+    #[test]
+    fn test_escapes_like_json_stringify() {
+        let json = chart_json(&[0; 7], 0, 0, "\"\\/\u{8}\u{c}\n\r\t\u{0}\u{1f} é♈");
+        assert!(json.ends_with(r#""zkProof":"\"\\/\b\f\n\r\t\u0000\u001f é♈"}"#), "{json}");
+    }
+}
@@ -0,0 +1,60 @@
+//! Shared building blocks of the ZKAstro contracts and prover
+//!
+//! Everything here is `no_std`, independent of the Stylus SDK and
+//! deterministic, so contracts, the off-chain prover and fixture
+//! generators all hash and encode the same bytes:
+//!
+//! - [`hash`]: streaming keccak256, lowercase hex and the Fiat-Shamir
+//!   [`hash::Transcript`]
+//! - [`commitment`]: the parsed birth data [`commitment::Commitment`]
+//! - [`birth_data`]: packed birth data and the commitment to it
+//! - [`positions`] and [`packing`]: chart encodings and their digests
+//! - [`legacy_hash`]: chart hashes of the legacy JavaScript registration
+//! - [`merkle`]: sorted-pair Merkle trees
+//! - [`history`]: Merkle leaves of a user's prediction history
+//! - [`calendar`] and [`astro`]: integer date and zodiac math
+//! - [`day_bitmap`]: per-year bitmaps of days
+//! - [`lucky`]: the verifiable lucky number and color of a user's day
+//! - [`attestation`]: hashes attesting a rating at a block
+//! - [`accumulator`]: per-date hash chains of stored predictions
+//! - [`seed`]: daily seeds revealed by the ephemeris, and draws from them
+//! - [`admin_log`]: action ids of the contracts' admin log
+//! - [`import`]: entry statuses of the contracts' admin imports
+//! - [`finalize`]: entry statuses of batched proposal finalization
+//! - [`decay`]: time-decayed averages
+//! - [`sealed_rating`]: commitments to ratings revealed later
+//! - [`payload`]: the canonical encoding and hash of a prediction
+//! - [`slots`]: storage slots of mapping entries, for storage proofs
+//!
+//! The `sdk` feature adds [`sdk`], std-only calldata builders and return
+//! decoders for the contracts' ABI.
+
+#![cfg_attr(not(any(test, feature = "sdk")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+pub mod accumulator;
+pub mod admin_log;
+pub mod astro;
+pub mod attestation;
+pub mod birth_data;
+pub mod calendar;
+pub mod commitment;
+pub mod day_bitmap;
+pub mod decay;
+pub mod finalize;
+pub mod hash;
+pub mod history;
+pub mod import;
+pub mod legacy_hash;
+pub mod lucky;
+pub mod merkle;
+pub mod packing;
+pub mod payload;
+pub mod positions;
+pub mod sealed_rating;
+pub mod seed;
+pub mod slots;
+#[cfg(feature = "sdk")]
+pub mod sdk;
@@ -0,0 +1,124 @@
+//! Lucky number and color of a user's day
+//!
+//! Both are derived from the user's birth data commitment and the day, so
+//! the frame, the contract and anyone else get the same values:
+//!
+//! ```text
+//! lucky_number = keccak256(commitment || day || LUCKY_NUMBER_TAG) mod 100 + 1
+//! lucky_color  = keccak256(commitment || day || LUCKY_COLOR_TAG) mod 12
+//! ```
+//!
+//! `commitment` is the 32 stored bytes, `day` the timestamp aligned to
+//! midnight UTC (`calendar::to_timestamp` of the date's day number) as a
+//! 32-byte big-endian word and the tags their ASCII bytes. The keccak digest is read as a big-endian uint256.
+//!
+//! Users check their numbers against these formulas, so the tags, ranges
+//! and palette are fixed for good; a new derivation gets new tags.
+
+use alloy_primitives::U256;
+
+use crate::calendar::{self, CalendarError};
+use crate::hash::Keccak256Builder;
+
+/// Domain tag of the lucky number
+pub const LUCKY_NUMBER_TAG: &[u8] = b"ZKAstro.luckyNumber.v1";
+
+/// Domain tag of the lucky color
+pub const LUCKY_COLOR_TAG: &[u8] = b"ZKAstro.luckyColor.v1";
+
+/// Lucky numbers run from 1 to this
+pub const LUCKY_NUMBER_MAX: u64 = 100;
+
+/// Number of lucky colors
+pub const COLOR_COUNT: u8 = 12;
+
+/// Names indexed by lucky color
+const COLOR_NAMES: [&str; COLOR_COUNT as usize] = [
+    "Red",
+    "Orange",
+    "Yellow",
+    "Lime",
+    "Green",
+    "Teal",
+    "Cyan",
+    "Blue",
+    "Indigo",
+    "Violet",
+    "Magenta",
+    "Pink",
+];
+
+/// keccak256(commitment || day || tag) as a uint256
+fn derive(commitment: &[u8; 32], date: U256, tag: &[u8]) -> Result<U256, CalendarError> {
+    let day = calendar::to_timestamp(calendar::to_day_number(date)?);
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(commitment).update(&day.to_be_bytes::<32>()).update(tag);
+    Ok(U256::from_be_bytes(hasher.finalize32()))
+}
+
+/// Lucky number (1-100) of the day containing `date`, `InvalidDate` past
+/// the last day number
+pub fn lucky_number(commitment: &[u8; 32], date: U256) -> Result<u64, CalendarError> {
+    let number = derive(commitment, date, LUCKY_NUMBER_TAG)? % U256::from(LUCKY_NUMBER_MAX);
+    Ok(number.to::<u64>() + 1)
+}
+
+/// Lucky color (0 = Red ... 11 = Pink) of the day containing `date`,
+/// `InvalidDate` past the last day number
+pub fn lucky_color(commitment: &[u8; 32], date: U256) -> Result<u8, CalendarError> {
+    let color = derive(commitment, date, LUCKY_COLOR_TAG)? % U256::from(COLOR_COUNT);
+    Ok(color.to::<u8>())
+}
+
+/// English name of a lucky color, "Unknown" past the palette
+pub fn color_name(color: u8) -> &'static str {
+    COLOR_NAMES.get(color as usize).copied().unwrap_or("Unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = calendar::SECONDS_PER_DAY;
+
+    #[test]
+    fn test_golden_vectors() {
+        let first = [0xab; 32];
+        let second = hex::decode("1b8f8c1f3a4d2e7c9b0a5f6e3d2c1b0a99887766554433221100ffeeddccbbaa").unwrap();
+        let second: [u8; 32] = second.try_into().unwrap();
+        let date = U256::from(20_000 * DAY);
+        let next = U256::from(20_001 * DAY);
+
+        assert_eq!(
+            [lucky_number(&first, date), lucky_number(&first, next), lucky_number(&second, date), lucky_number(&second, next)],
+            [Ok(59), Ok(21), Ok(83), Ok(32)]
+        );
+        assert_eq!(
+            [lucky_color(&first, date), lucky_color(&first, next), lucky_color(&second, date), lucky_color(&second, next)],
+            [Ok(1), Ok(11), Ok(8), Ok(4)]
+        );
+    }
+
+    #[test]
+    fn test_same_for_the_whole_day() {
+        let commitment = [0x42; 32];
+        let midnight = U256::from(20_000 * DAY);
+        let evening = midnight + U256::from(DAY - 1);
+        assert_eq!(lucky_number(&commitment, midnight), lucky_number(&commitment, evening));
+        assert_eq!(lucky_color(&commitment, midnight), lucky_color(&commitment, evening));
+    }
+
+    #[test]
+    fn test_ranges() {
+        for day in 0..500u64 {
+            let number = lucky_number(&[0x17; 32], U256::from(day * DAY)).unwrap();
+            assert!((1..=LUCKY_NUMBER_MAX).contains(&number));
+            assert!(lucky_color(&[0x17; 32], U256::from(day * DAY)).unwrap() < COLOR_COUNT);
+        }
+        assert_eq!(lucky_number(&[0x17; 32], U256::MAX), Err(CalendarError::InvalidDate));
+        assert_eq!(lucky_color(&[0x17; 32], U256::MAX), Err(CalendarError::InvalidDate));
+        assert_eq!(color_name(0), "Red");
+        assert_eq!(color_name(11), "Pink");
+        assert_eq!(color_name(COLOR_COUNT), "Unknown");
+    }
+}
@@ -0,0 +1,170 @@
+//! Keccak256 Merkle proofs
+//!
+//! Sorted-pair hashing: each node is `keccak256(min(a, b) || max(a, b))`,
+//! so proofs carry no left/right flags. This matches OpenZeppelin's
+//! `MerkleProof.verify`, letting off-chain tooling built for it produce
+//! roots and proofs for this contract.
+//!
+//! [`frontier_push`] and [`frontier_root`] build the same root one leaf at
+//! a time, keeping only one node per level, so a contract can spread a
+//! large tree over several calls.
+
+use alloc::vec::Vec;
+
+use alloy_primitives::{keccak256, FixedBytes};
+
+type B32 = FixedBytes<32>;
+
+/// Hash two nodes in sorted order
+pub fn hash_pair(a: B32, b: B32) -> B32 {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(lo.as_slice());
+    preimage[32..].copy_from_slice(hi.as_slice());
+    keccak256(preimage)
+}
+
+/// Check that `leaf` is in the tree with `root` via `proof` (siblings from
+/// the leaf upwards)
+pub fn verify(proof: &[B32], root: B32, leaf: B32) -> bool {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(node, *sibling)) == root
+}
+
+/// Build every level of the tree over `leaves`, leaves first
+///
+/// A node without a sibling is carried up unchanged.
+fn levels(leaves: &[B32]) -> Vec<Vec<B32>> {
+    let mut levels = vec![leaves.to_vec()];
+    while levels[levels.len() - 1].len() > 1 {
+        let next = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(*a, *b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Root of the tree over `leaves` (zero for no leaves)
+pub fn root(leaves: &[B32]) -> B32 {
+    if leaves.is_empty() {
+        return B32::ZERO;
+    }
+    levels(leaves).last().map(|level| level[0]).unwrap_or_default()
+}
+
+/// Proof for the leaf at `index`, or `None` if out of range
+pub fn proof(leaves: &[B32], index: usize) -> Option<Vec<B32>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut index = index;
+    for level in levels(leaves).iter().filter(|level| level.len() > 1) {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Add the leaf following the first `count` leaves to `frontier`
+///
+/// `frontier[h]` holds, for each set bit `h` of `count`, the root of a
+/// complete subtree of `2^h` leaves not yet paired; other entries are
+/// unused.
+pub fn frontier_push(frontier: &mut Vec<B32>, count: u64, leaf: B32) {
+    let mut node = leaf;
+    let mut height = 0;
+    while count >> height & 1 == 1 {
+        node = hash_pair(frontier[height], node);
+        height += 1;
+    }
+    if frontier.len() <= height {
+        frontier.resize(height + 1, B32::ZERO);
+    }
+    frontier[height] = node;
+}
+
+/// Root of the tree over `count` leaves pushed into `frontier`, equal to
+/// [`root`] of those leaves
+pub fn frontier_root(frontier: &[B32], count: u64) -> B32 {
+    // Pair the pending subtrees from the smallest up, carrying a lone
+    // subtree up unchanged
+    frontier
+        .iter()
+        .enumerate()
+        .filter(|(height, _)| count >> height & 1 == 1)
+        .fold(None, |node, (_, subtree)| Some(node.map_or(*subtree, |node| hash_pair(*subtree, node))))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u8) -> Vec<B32> {
+        (0..count).map(|i| keccak256([i])).collect()
+    }
+
+    #[test]
+    fn test_hash_pair_commutative() {
+        let [a, b] = [keccak256(b"a"), keccak256(b"b")];
+        assert_eq!(hash_pair(a, b), hash_pair(b, a));
+    }
+
+    // Two-leaf root computed independently: keccak256(min || max)
+    #[test]
+    fn test_known_root() {
+        let [a, b] = [B32::repeat_byte(0x11), B32::repeat_byte(0x22)];
+        let mut preimage = [0x11u8; 64];
+        preimage[32..].fill(0x22);
+        assert_eq!(root(&[b, a]), keccak256(preimage));
+    }
+
+    #[test]
+    fn test_every_leaf_proves() {
+        for count in [1, 2, 5, 8] {
+            let leaves = leaves(count);
+            let root = root(&leaves);
+            for (i, leaf) in leaves.iter().enumerate() {
+                assert!(verify(&proof(&leaves, i).unwrap(), root, *leaf), "leaf {i} of {count}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_leaf_and_root() {
+        let leaves = leaves(8);
+        let root = root(&leaves);
+        let proof = proof(&leaves, 3).unwrap();
+        assert_eq!(proof.len(), 3);
+        assert!(!verify(&proof, root, leaves[4]));
+        assert!(!verify(&proof, keccak256(b"other"), leaves[3]));
+        assert!(!verify(&proof[..2], root, leaves[3]));
+    }
+
+    #[test]
+    fn test_frontier_matches_root() {
+        let leaves = leaves(40);
+        let mut frontier = Vec::new();
+        assert_eq!(frontier_root(&frontier, 0), B32::ZERO);
+        for (count, leaf) in leaves.iter().enumerate() {
+            frontier_push(&mut frontier, count as u64, *leaf);
+            assert_eq!(frontier_root(&frontier, count as u64 + 1), root(&leaves[..=count]), "{count} leaves");
+        }
+        assert_eq!(frontier.len(), 6);
+    }
+
+    #[test]
+    fn test_out_of_range_index() {
+        assert_eq!(proof(&leaves(4), 4), None);
+        assert_eq!(root(&[]), B32::ZERO);
+    }
+}
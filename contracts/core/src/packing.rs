@@ -0,0 +1,100 @@
+//! Centidegree positions packed 16 bits each into two words
+
+use alloc::vec::Vec;
+
+use crate::positions::MAX_POSITION;
+
+/// Positions that fit in the two packed words, 16 bits each
+pub const MAX_PACKED_POSITIONS: usize = 32;
+
+/// Pack centidegree positions into two words, 16 bits per position
+///
+/// Position i is stored big-endian at bytes `2 * (i % 16)..` of word
+/// `i / 16`; unused slots are zero. Returns `None` for more than
+/// [`MAX_PACKED_POSITIONS`] values or any value above [`MAX_POSITION`].
+pub fn pack_positions(position_values: &[u64]) -> Option<([u8; 32], [u8; 32])> {
+    if position_values.len() > MAX_PACKED_POSITIONS {
+        return None;
+    }
+
+    let mut words = [[0u8; 32]; 2];
+    for (i, position) in position_values.iter().enumerate() {
+        if *position > MAX_POSITION {
+            return None;
+        }
+        let offset = 2 * (i % 16);
+        words[i / 16][offset..offset + 2].copy_from_slice(&(*position as u16).to_be_bytes());
+    }
+    Some((words[0], words[1]))
+}
+
+/// Unpack the first `count` positions from words built by [`pack_positions`]
+///
+/// Returns `None` if `count` exceeds [`MAX_PACKED_POSITIONS`] or a decoded
+/// value is above [`MAX_POSITION`].
+pub fn unpack_positions(words: &[[u8; 32]; 2], count: u8) -> Option<Vec<u64>> {
+    let count = count as usize;
+    if count > MAX_PACKED_POSITIONS {
+        return None;
+    }
+
+    let mut position_values = Vec::with_capacity(count);
+    for i in 0..count {
+        let offset = 2 * (i % 16);
+        let position = u16::from_be_bytes([words[i / 16][offset], words[i / 16][offset + 1]]) as u64;
+        if position > MAX_POSITION {
+            return None;
+        }
+        position_values.push(position);
+    }
+    Some(position_values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITIONS: [u64; 7] = [12345, 23456, 0, 36000, 9000, 18000, 27000];
+
+    fn chart(len: usize) -> Vec<u64> {
+        (0..len as u64).map(|i| (i * 2311 + 17) % (MAX_POSITION + 1)).collect()
+    }
+
+    #[test]
+    fn test_pack_round_trip() {
+        for len in [7, 11, 16] {
+            let positions = chart(len);
+            let (lo, hi) = pack_positions(&positions).unwrap();
+            assert_eq!(unpack_positions(&[lo, hi], len as u8), Some(positions));
+        }
+
+        let (lo, hi) = pack_positions(&POSITIONS).unwrap();
+        assert_eq!(unpack_positions(&[lo, hi], 7).unwrap(), POSITIONS);
+    }
+
+    #[test]
+    fn test_pack_layout() {
+        let mut positions = chart(17);
+        positions[0] = 0x1234;
+        positions[16] = MAX_POSITION;
+        let (lo, hi) = pack_positions(&positions).unwrap();
+        assert_eq!(&lo[..2], &[0x12, 0x34]);
+        assert_eq!(&hi[..2], &(MAX_POSITION as u16).to_be_bytes());
+        assert_eq!(&hi[2..], &[0u8; 30]);
+    }
+
+    #[test]
+    fn test_pack_rejects_out_of_range() {
+        assert_eq!(pack_positions(&[0, MAX_POSITION + 1]), None);
+        assert_eq!(pack_positions(&[0x1_0000]), None);
+        assert_eq!(pack_positions(&chart(MAX_PACKED_POSITIONS + 1)), None);
+        assert!(pack_positions(&chart(MAX_PACKED_POSITIONS)).is_some());
+    }
+
+    #[test]
+    fn test_unpack_rejects_invalid() {
+        let words = [[0xff; 32], [0; 32]];
+        assert_eq!(unpack_positions(&words, 1), None);
+        assert_eq!(unpack_positions(&[[0; 32]; 2], MAX_PACKED_POSITIONS as u8 + 1), None);
+    }
+}
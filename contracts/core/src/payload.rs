@@ -0,0 +1,216 @@
+//! Canonical binary payload of a prediction
+//!
+//! The `prediction_hash` a user stores is the keccak256 of this encoding,
+//! so anyone holding the prediction can recompute it, and the contract can
+//! check a revealed prediction field by field:
+//!
+//! ```text
+//! field   = length || bytes            (length: u32 little-endian)
+//! payload = field(version) || field(date) || field(category) || field(text)
+//!           || field(lucky_number) || field(lucky_color) || field(model_id)
+//! hash    = keccak256(payload)
+//! ```
+//!
+//! Integers are their fixed-width little-endian bytes (1, 8, 1, 2, 1 and
+//! 4 bytes) and `text` is UTF-8, not normalized. Every field carries its
+//! length, so no two payloads encode to the same bytes. There is no JSON
+//! anywhere, and the golden vectors in the tests pin the bytes for other
+//! implementations.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::hash::keccak256;
+
+/// Reasons bytes don't decode to a payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadError {
+    /// Input ends inside a field
+    Truncated,
+    /// Integer field (by position, 0 = version) of the wrong length
+    FieldLength(u8),
+    /// `text` isn't UTF-8
+    InvalidText,
+    /// Bytes left after the last field
+    TrailingBytes,
+}
+
+/// Everything a prediction hash commits to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PredictionPayload {
+    pub version: u8,
+    /// Date key the prediction is stored under
+    pub date: u64,
+    pub category: u8,
+    pub text: String,
+    pub lucky_number: u16,
+    pub lucky_color: u8,
+    /// Id of the model that wrote `text`
+    pub model_id: u32,
+}
+
+impl PredictionPayload {
+    /// Canonical encoding
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(7 * 4 + 17 + self.text.len());
+        let mut field = |bytes: &[u8]| {
+            out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            out.extend_from_slice(bytes);
+        };
+        field(&[self.version]);
+        field(&self.date.to_le_bytes());
+        field(&[self.category]);
+        field(self.text.as_bytes());
+        field(&self.lucky_number.to_le_bytes());
+        field(&[self.lucky_color]);
+        field(&self.model_id.to_le_bytes());
+        out
+    }
+
+    /// keccak256 of the canonical encoding, the prediction hash
+    pub fn hash(&self) -> [u8; 32] {
+        keccak256(&self.encode())
+    }
+
+    /// Parse bytes produced by [`PredictionPayload::encode`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, PayloadError> {
+        let mut rest = bytes;
+        let mut field = || -> Result<&[u8], PayloadError> {
+            let (length, tail) = rest.split_first_chunk::<4>().ok_or(PayloadError::Truncated)?;
+            let length = u32::from_le_bytes(*length) as usize;
+            if tail.len() < length {
+                return Err(PayloadError::Truncated);
+            }
+            let (value, tail) = tail.split_at(length);
+            rest = tail;
+            Ok(value)
+        };
+        fn int<const N: usize>(value: &[u8], position: u8) -> Result<[u8; N], PayloadError> {
+            value.try_into().map_err(|_| PayloadError::FieldLength(position))
+        }
+
+        let version = int::<1>(field()?, 0)?[0];
+        let date = u64::from_le_bytes(int(field()?, 1)?);
+        let category = int::<1>(field()?, 2)?[0];
+        let text = String::from(core::str::from_utf8(field()?).map_err(|_| PayloadError::InvalidText)?);
+        let lucky_number = u16::from_le_bytes(int(field()?, 4)?);
+        let lucky_color = int::<1>(field()?, 5)?[0];
+        let model_id = u32::from_le_bytes(int(field()?, 6)?);
+        if !rest.is_empty() {
+            return Err(PayloadError::TrailingBytes);
+        }
+        Ok(PredictionPayload { version, date, category, text, lucky_number, lucky_color, model_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> PredictionPayload {
+        PredictionPayload {
+            version: 1,
+            date: 1_735_689_600,
+            category: 2,
+            text: String::from("Mercury favours clear words today."),
+            lucky_number: 42,
+            lucky_color: 7,
+            model_id: 3,
+        }
+    }
+
+    #[test]
+    fn test_golden_vectors() {
+        let payload = sample();
+        assert_eq!(
+            hex::encode(payload.encode()),
+            concat!(
+                "0100000001",
+                "080000008085746700000000",
+                "0100000002",
+                "22000000",
+                "4d657263757279206661766f75727320636c65617220776f72647320746f6461792e",
+                "020000002a00",
+                "0100000007",
+                "0400000003000000",
+            )
+        );
+        assert_eq!(hex::encode(payload.hash()), "7162966306eca3ee74d4d8f979d12af6764bfb25863a5e35dd738efe6095a779");
+
+        let empty = PredictionPayload {
+            version: 0,
+            date: 0,
+            category: 0,
+            text: String::new(),
+            lucky_number: 0,
+            lucky_color: 0,
+            model_id: 0,
+        };
+        assert_eq!(hex::encode(empty.hash()), "c1b1e88f2503767eec94d47488505976d7af2fbf53278ad1a47ace644dad5edd");
+
+        let unicode = PredictionPayload { text: String::from("Vénus ♀ 🌙"), ..sample() };
+        assert_eq!(hex::encode(unicode.hash()), "8fbf016791cc4480ca8e46e9728bd7d554e1477a411aa6a652bbdc1969550f59");
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        let encoded = sample().encode();
+        assert_eq!(PredictionPayload::decode(&encoded), Ok(sample()));
+        assert_eq!(PredictionPayload::decode(&encoded[..encoded.len() - 1]), Err(PayloadError::Truncated));
+        assert_eq!(PredictionPayload::decode(&[]), Err(PayloadError::Truncated));
+        assert_eq!(PredictionPayload::decode(&[encoded.as_slice(), &[0]].concat()), Err(PayloadError::TrailingBytes));
+
+        // A two-byte version field
+        let mut wide = vec![2, 0, 0, 0, 1, 0];
+        wide.extend_from_slice(&encoded[5..]);
+        assert_eq!(PredictionPayload::decode(&wide), Err(PayloadError::FieldLength(0)));
+
+        // Text follows three fields of 4 + 1, 4 + 8 and 4 + 1 bytes and
+        // its own length
+        let mut bad_text = encoded.clone();
+        bad_text[26] = 0xff;
+        assert_eq!(PredictionPayload::decode(&bad_text), Err(PayloadError::InvalidText));
+    }
+
+    mod fuzz {
+        use super::*;
+        use proptest::prelude::*;
+        use proptest::test_runner::RngSeed;
+
+        fn config() -> ProptestConfig {
+            ProptestConfig { cases: 512, rng_seed: RngSeed::Fixed(0x5a4b_4173_7472_6f05), ..ProptestConfig::default() }
+        }
+
+        fn payload() -> impl Strategy<Value = PredictionPayload> {
+            (any::<u8>(), any::<u64>(), any::<u8>(), ".{0,200}", any::<u16>(), any::<u8>(), any::<u32>()).prop_map(
+                |(version, date, category, text, lucky_number, lucky_color, model_id)| PredictionPayload {
+                    version,
+                    date,
+                    category,
+                    text,
+                    lucky_number,
+                    lucky_color,
+                    model_id,
+                },
+            )
+        }
+
+        proptest! {
+            #![proptest_config(config())]
+
+            #[test]
+            fn fuzz_encode_round_trips(payload in payload()) {
+                let encoded = payload.encode();
+                prop_assert_eq!(encoded.len(), 7 * 4 + 17 + payload.text.len());
+                prop_assert_eq!(PredictionPayload::decode(&encoded), Ok(payload));
+            }
+
+            #[test]
+            fn fuzz_decode_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..128)) {
+                if let Ok(payload) = PredictionPayload::decode(&bytes) {
+                    prop_assert_eq!(payload.encode(), bytes);
+                }
+            }
+        }
+    }
+}
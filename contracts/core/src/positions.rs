@@ -0,0 +1,430 @@
+//! Planet positions with explicit body identity
+//!
+//! A bare list of longitudes relies on the clients agreeing on planet order,
+//! which they don't. A [`PlanetPosition`] names its body, and
+//! [`positions_digest`] hashes a chart in canonical body order, so the same
+//! chart always yields the same digest however the client listed it.
+//!
+//! Canonical 4-byte encoding: `body || u16_be(longitude_centideg) || flags`
+//! with flag bit 0 set for retrograde motion. In `u64` calldata each
+//! position travels as that encoding read as a big-endian integer, see
+//! [`PlanetPosition::to_word`].
+//!
+//! A full chart of the 11 planets also packs into two words, see
+//! [`pack_chart`], so contracts can store and read back the positions
+//! themselves rather than only their digest.
+
+use alloc::string::String;
+
+use alloy_primitives::{keccak256, Address, FixedBytes, U256};
+
+use crate::hash::{to_hex, Keccak256Builder};
+
+/// Sun, Moon, Mercury, ..., Pluto (0-10), then the lunar nodes
+pub const SUN: u8 = 0;
+pub const MOON: u8 = 1;
+pub const PLUTO: u8 = 10;
+pub const NORTH_NODE: u8 = 11;
+pub const SOUTH_NODE: u8 = 12;
+
+/// Largest valid position in centidegrees (360.00°)
+pub const MAX_POSITION: u64 = 36000;
+
+/// Most positions a proof may cover
+pub const MAX_POSITIONS: usize = 16;
+
+/// Number of known bodies (valid ids are 0..BODY_COUNT)
+pub const BODY_COUNT: usize = 13;
+
+/// Bodies in a packed chart (Sun to Pluto)
+pub const CHART_BODIES: usize = 11;
+
+/// Bits per packed position: 16-bit longitude, retrograde bit, 5-bit body
+const SLOT_BITS: usize = 22;
+
+/// Positions packed into the first word; the rest go into the second
+const FIRST_WORD_SLOTS: usize = 6;
+
+const RETROGRADE_FLAG: u8 = 0x01;
+
+/// Domain tag of derived chart ids
+pub const CHART_ID_TAG: &[u8] = b"zkastro.chart.v1";
+
+/// Hex digits in a derived chart id
+pub const CHART_ID_LEN: usize = 32;
+
+/// Reasons a set of positions is rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PositionError {
+    /// Body id outside 0..BODY_COUNT
+    UnknownBody(u8),
+    /// Same body listed twice
+    DuplicateBody(u8),
+    /// Body a computation needs missing from the chart
+    MissingBody(u8),
+    /// Longitude above 36000 centidegrees
+    LongitudeOutOfRange(u16),
+    /// Reserved flag bits set or word wider than 4 bytes
+    MalformedWord(u64),
+    /// Packed chart with bits set outside its slots
+    MalformedPacking,
+}
+
+/// Position of one body in a chart
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlanetPosition {
+    pub body: u8,
+    pub longitude_centideg: u16,
+    pub is_retrograde: bool,
+}
+
+impl PlanetPosition {
+    /// Canonical 4-byte encoding
+    pub fn encode(&self) -> [u8; 4] {
+        let longitude = self.longitude_centideg.to_be_bytes();
+        let flags = if self.is_retrograde { RETROGRADE_FLAG } else { 0 };
+        [self.body, longitude[0], longitude[1], flags]
+    }
+
+    /// The canonical encoding as a calldata word
+    pub fn to_word(&self) -> u64 {
+        u32::from_be_bytes(self.encode()) as u64
+    }
+
+    /// Parse a word produced by [`PlanetPosition::to_word`]
+    pub fn from_word(word: u64) -> Result<Self, PositionError> {
+        let word32 = u32::try_from(word).map_err(|_| PositionError::MalformedWord(word))?;
+        let [body, lon_hi, lon_lo, flags] = word32.to_be_bytes();
+        if flags & !RETROGRADE_FLAG != 0 {
+            return Err(PositionError::MalformedWord(word));
+        }
+        let position = PlanetPosition {
+            body,
+            longitude_centideg: u16::from_be_bytes([lon_hi, lon_lo]),
+            is_retrograde: flags & RETROGRADE_FLAG != 0,
+        };
+        position.validate()?;
+        Ok(position)
+    }
+
+    fn validate(&self) -> Result<(), PositionError> {
+        if self.body as usize >= BODY_COUNT {
+            return Err(PositionError::UnknownBody(self.body));
+        }
+        if self.longitude_centideg as u64 > MAX_POSITION {
+            return Err(PositionError::LongitudeOutOfRange(self.longitude_centideg));
+        }
+        Ok(())
+    }
+}
+
+/// Canonical encodings of positions given in any order, sorted by body id
+///
+/// Returns the encodings and how many of them are in use. Sorting is a
+/// bucket per body, so no allocation is needed.
+pub fn sorted_encodings(
+    positions: impl IntoIterator<Item = Result<PlanetPosition, PositionError>>,
+) -> Result<([[u8; 4]; BODY_COUNT], usize), PositionError> {
+    let mut by_body: [Option<[u8; 4]>; BODY_COUNT] = [None; BODY_COUNT];
+    for position in positions {
+        let position = position?;
+        position.validate()?;
+        let slot = &mut by_body[position.body as usize];
+        if slot.is_some() {
+            return Err(PositionError::DuplicateBody(position.body));
+        }
+        *slot = Some(position.encode());
+    }
+
+    let mut sorted = [[0u8; 4]; BODY_COUNT];
+    let mut len = 0;
+    for encoding in by_body.iter().flatten() {
+        sorted[len] = *encoding;
+        len += 1;
+    }
+    Ok((sorted, len))
+}
+
+/// Digest of positions in any order: keccak256 of the encodings sorted by
+/// body id, see [`sorted_encodings`]
+pub fn positions_digest_iter(
+    positions: impl IntoIterator<Item = Result<PlanetPosition, PositionError>>,
+) -> Result<[u8; 32], PositionError> {
+    let (sorted, len) = sorted_encodings(positions)?;
+    Ok(keccak256(sorted[..len].as_flattened()).0)
+}
+
+/// Digest of `positions`, see [`positions_digest_iter`]
+pub fn positions_digest(positions: &[PlanetPosition]) -> Result<[u8; 32], PositionError> {
+    positions_digest_iter(positions.iter().copied().map(Ok))
+}
+
+/// Digest of positions passed as calldata words
+pub fn words_digest(words: &[u64]) -> Result<[u8; 32], PositionError> {
+    positions_digest_iter(words.iter().map(|word| PlanetPosition::from_word(*word)))
+}
+
+/// Pack an 11-body chart into two words
+///
+/// Each position becomes a 22-bit slot
+/// `longitude_centideg (16) || retrograde (1) || body (5)`, and slots are
+/// laid out from the most significant bit down: positions 0-5 in the first
+/// word (bits 255..124), positions 6-10 in the second (bits 255..146). All
+/// lower bits are zero. For example Sun at 280.45° direct is the slot
+/// `0x6d8d << 6 | 0 << 5 | 0`.
+pub fn pack_chart(positions: &[PlanetPosition; CHART_BODIES]) -> Result<[FixedBytes<32>; 2], PositionError> {
+    sorted_encodings(positions.iter().copied().map(Ok))?;
+    let mut words = [U256::ZERO; 2];
+    for (i, position) in positions.iter().enumerate() {
+        let (word, slot) = slot_of(i);
+        let packed = (position.longitude_centideg as u32) << 6 | (position.is_retrograde as u32) << 5 | position.body as u32;
+        words[word] |= U256::from(packed) << slot_shift(slot);
+    }
+    Ok(words.map(|word| FixedBytes(word.to_be_bytes())))
+}
+
+/// Unpack a chart packed by [`pack_chart`]
+///
+/// Rejects unknown or repeated bodies, longitudes above 360° and any bit
+/// set outside the slots.
+pub fn unpack_chart(packed: &[FixedBytes<32>; 2]) -> Result<[PlanetPosition; CHART_BODIES], PositionError> {
+    let words = packed.map(|word| U256::from_be_bytes(word.0));
+    let mut unused = words;
+    let mut positions = [PlanetPosition { body: 0, longitude_centideg: 0, is_retrograde: false }; CHART_BODIES];
+    for (i, position) in positions.iter_mut().enumerate() {
+        let (word, slot) = slot_of(i);
+        let mask = U256::from((1u32 << SLOT_BITS) - 1) << slot_shift(slot);
+        let packed = ((words[word] & mask) >> slot_shift(slot)).to::<u32>();
+        unused[word] &= !mask;
+        *position = PlanetPosition {
+            body: (packed & 0x1f) as u8,
+            longitude_centideg: (packed >> 6) as u16,
+            is_retrograde: packed & 0x20 != 0,
+        };
+    }
+    if unused != [U256::ZERO; 2] {
+        return Err(PositionError::MalformedPacking);
+    }
+    sorted_encodings(positions.iter().copied().map(Ok))?;
+    Ok(positions)
+}
+
+/// Word and slot within it of chart position `index`
+fn slot_of(index: usize) -> (usize, usize) {
+    if index < FIRST_WORD_SLOTS {
+        (0, index)
+    } else {
+        (1, index - FIRST_WORD_SLOTS)
+    }
+}
+
+/// Left shift of slot `slot` within its word
+fn slot_shift(slot: usize) -> usize {
+    256 - SLOT_BITS * (slot + 1)
+}
+
+/// Chart hash binding a chart to a salt: keccak256(positions_digest || salt)
+pub fn chart_hash(positions: &[PlanetPosition], salt: [u8; 32]) -> Result<[u8; 32], PositionError> {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(&positions_digest(positions)?);
+    preimage[32..].copy_from_slice(&salt);
+    Ok(keccak256(preimage).0)
+}
+
+/// Chart id derived from the chart's owner and hash, so a retried
+/// registration reuses the id: the first 32 lowercase hex digits of
+/// keccak256(user || chart_hash || CHART_ID_TAG)
+pub fn derive_chart_id(user: Address, chart_hash: [u8; 32]) -> String {
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(user.as_slice()).update(&chart_hash).update(CHART_ID_TAG);
+    let hex = to_hex(&hasher.finalize32());
+    hex[..CHART_ID_LEN].iter().map(|&digit| char::from(digit)).collect()
+}
+
+/// Calldata words for `positions`
+pub fn to_words(positions: &[PlanetPosition]) -> alloc::vec::Vec<u64> {
+    positions.iter().map(PlanetPosition::to_word).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart() -> [PlanetPosition; 4] {
+        [
+            PlanetPosition { body: SUN, longitude_centideg: 28045, is_retrograde: false },
+            PlanetPosition { body: MOON, longitude_centideg: 12030, is_retrograde: false },
+            PlanetPosition { body: PLUTO, longitude_centideg: 30011, is_retrograde: true },
+            PlanetPosition { body: SOUTH_NODE, longitude_centideg: 0, is_retrograde: true },
+        ]
+    }
+
+    #[test]
+    fn test_chart_id_golden_vectors() {
+        // Clients precompute ids with this derivation; changing it orphans them
+        let user = Address::repeat_byte(0xa1);
+        assert_eq!(derive_chart_id(user, [0x07; 32]), "ab204804fdd3b78cfeeba2e8b109005b");
+        assert_eq!(derive_chart_id(user, [0x08; 32]), "2c5ef5d94230d35f38cce64d00d05605");
+        assert_eq!(derive_chart_id(Address::ZERO, [0x07; 32]), "f9631a506b4a2cbaf2b9ffe5490451af");
+    }
+
+    #[test]
+    fn test_encoding() {
+        let pluto = chart()[2];
+        assert_eq!(pluto.encode(), [10, 0x75, 0x3b, 1]);
+        assert_eq!(pluto.to_word(), 0x0a75_3b01);
+        assert_eq!(PlanetPosition::from_word(pluto.to_word()), Ok(pluto));
+    }
+
+    #[test]
+    fn test_digest_order_independent() {
+        let chart = chart();
+        let mut shuffled = chart;
+        shuffled.reverse();
+        shuffled.swap(0, 2);
+        assert_eq!(positions_digest(&chart), positions_digest(&shuffled));
+
+        let words: [u64; 4] = shuffled.map(|p| p.to_word());
+        assert_eq!(words_digest(&words), positions_digest(&chart));
+    }
+
+    #[test]
+    fn test_digest_known_layout() {
+        let mut packed = [0u8; 16];
+        for (chunk, position) in packed.chunks_exact_mut(4).zip(chart()) {
+            chunk.copy_from_slice(&position.encode());
+        }
+        assert_eq!(positions_digest(&chart()), Ok(keccak256(packed).0));
+    }
+
+    #[test]
+    fn test_digest_distinguishes_retrograde() {
+        let mut flipped = chart();
+        flipped[0].is_retrograde = true;
+        assert_ne!(positions_digest(&chart()), positions_digest(&flipped));
+    }
+
+    #[test]
+    fn test_rejects_duplicate_body() {
+        let mut chart = chart();
+        chart[3].body = MOON;
+        assert_eq!(positions_digest(&chart), Err(PositionError::DuplicateBody(MOON)));
+    }
+
+    #[test]
+    fn test_rejects_unknown_body_and_range() {
+        let mut chart = chart();
+        chart[1].body = NORTH_NODE + 2;
+        assert_eq!(positions_digest(&chart), Err(PositionError::UnknownBody(13)));
+
+        let mut chart = self::chart();
+        chart[0].longitude_centideg = 36001;
+        assert_eq!(positions_digest(&chart), Err(PositionError::LongitudeOutOfRange(36001)));
+    }
+
+    #[test]
+    fn test_rejects_malformed_words() {
+        assert_eq!(PlanetPosition::from_word(1 << 32), Err(PositionError::MalformedWord(1 << 32)));
+        assert_eq!(PlanetPosition::from_word(0x0000_0002), Err(PositionError::MalformedWord(2)));
+        assert_eq!(PlanetPosition::from_word(0x0d00_0000), Err(PositionError::UnknownBody(13)));
+    }
+
+    fn full_chart() -> [PlanetPosition; CHART_BODIES] {
+        let longitudes = [28045, 12030, 27512, 31000, 9000, 17999, 0, 36000, 4417, 22222, 30011];
+        let retrograde = [2, 5, 8, 10];
+        core::array::from_fn(|i| PlanetPosition {
+            body: i as u8,
+            longitude_centideg: longitudes[i],
+            is_retrograde: retrograde.contains(&i),
+        })
+    }
+
+    #[test]
+    fn test_pack_chart_layout() {
+        let packed = pack_chart(&full_chart()).unwrap();
+        assert_eq!(
+            hex::encode(packed[0]),
+            "6d8d00bbf816b7889e4603232811193e50000000000000000000000000000000"
+        );
+        assert_eq!(
+            hex::encode(packed[1]),
+            "00001a328071141a15b389753ba8000000000000000000000000000000000000"
+        );
+        assert_eq!(unpack_chart(&packed), Ok(full_chart()));
+    }
+
+    #[test]
+    fn test_pack_chart_rejects_invalid() {
+        let mut chart = full_chart();
+        chart[4].body = MOON;
+        assert_eq!(pack_chart(&chart), Err(PositionError::DuplicateBody(MOON)));
+
+        let mut packed = pack_chart(&full_chart()).unwrap();
+        packed[1].0[31] = 1;
+        assert_eq!(unpack_chart(&packed), Err(PositionError::MalformedPacking));
+
+        // Longitude 0xffff in the first slot
+        let mut packed = pack_chart(&full_chart()).unwrap();
+        packed[0].0[0] = 0xff;
+        packed[0].0[1] = 0xff;
+        packed[0].0[2] &= 0x3f;
+        assert_eq!(unpack_chart(&packed), Err(PositionError::LongitudeOutOfRange(0xffff)));
+
+        // Body 31 in the last slot
+        let mut packed = pack_chart(&full_chart()).unwrap();
+        packed[1].0[13] |= 0x1f << 2;
+        assert_eq!(unpack_chart(&packed), Err(PositionError::UnknownBody(31)));
+    }
+
+    #[test]
+    fn test_chart_hash() {
+        let salt = [7u8; 32];
+        let mut preimage = [0u8; 64];
+        preimage[..32].copy_from_slice(&positions_digest(&full_chart()).unwrap());
+        preimage[32..].copy_from_slice(&salt);
+        assert_eq!(chart_hash(&full_chart(), salt), Ok(keccak256(preimage).0));
+        assert_ne!(chart_hash(&full_chart(), salt), chart_hash(&full_chart(), [8u8; 32]));
+    }
+
+    mod fuzz {
+        use super::*;
+        use proptest::prelude::*;
+        use proptest::test_runner::RngSeed;
+
+        fn config() -> ProptestConfig {
+            ProptestConfig { cases: 512, rng_seed: RngSeed::Fixed(0x5a4b_4173_7472_6f02), ..ProptestConfig::default() }
+        }
+
+        fn chart() -> impl Strategy<Value = [PlanetPosition; CHART_BODIES]> {
+            (
+                prop::array::uniform11(0u16..=36000),
+                prop::array::uniform11(any::<bool>()),
+                Just((0..CHART_BODIES as u8).collect::<Vec<_>>()).prop_shuffle(),
+            )
+                .prop_map(|(longitudes, retrograde, bodies)| {
+                    core::array::from_fn(|i| PlanetPosition {
+                        body: bodies[i],
+                        longitude_centideg: longitudes[i],
+                        is_retrograde: retrograde[i],
+                    })
+                })
+        }
+
+        proptest! {
+            #![proptest_config(config())]
+
+            #[test]
+            fn pack_round_trip(chart in chart()) {
+                let packed = pack_chart(&chart).unwrap();
+                prop_assert_eq!(unpack_chart(&packed), Ok(chart));
+            }
+
+            #[test]
+            fn unpack_never_panics(a in any::<[u8; 32]>(), b in any::<[u8; 32]>()) {
+                if let Ok(chart) = unpack_chart(&[FixedBytes(a), FixedBytes(b)]) {
+                    prop_assert_eq!(pack_chart(&chart), Ok([FixedBytes(a), FixedBytes(b)]));
+                }
+            }
+        }
+    }
+}
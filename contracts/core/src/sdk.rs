@@ -0,0 +1,732 @@
+//! Calldata builders and return decoders for off-chain callers
+//!
+//! Enabled by the `sdk` feature, which makes the crate depend on std. The
+//! `sol!` interfaces below are the ABI of the deployed contracts, and the
+//! contracts crate routes every one of their selectors through the real
+//! contracts in its tests, so a signature changed on one side only fails
+//! there. Selectors FarcasterPredictions and ChartRegistry delegate to
+//! their facets are only routed as far as the delegation. Functions
+//! without a builder here can be encoded through the interfaces directly,
+//! e.g. `IFarcasterPredictions::getUserStatsCall`.
+
+use alloy_primitives::{Address, FixedBytes, U256};
+use alloy_sol_types::{sol, SolCall, SolValue};
+
+use crate::commitment::Commitment;
+use crate::payload::PredictionPayload;
+
+pub use alloy_sol_types::Error as DecodeError;
+
+sol! {
+    /// FarcasterPredictions (`src/lib.rs` of the contracts crate)
+    interface IFarcasterPredictions {
+        struct UserStats { uint256 totalPredictions; uint256 totalRatings; uint256 averageRatingX10; }
+        struct UserExport { bytes32 commitment; bool registered; uint256 registeredAt; UserStats stats; uint256[] dates; bytes32[] hashes; uint8[] ratings; }
+
+        function initialize(address owner) external;
+        function getImplementationVersion() external view returns (uint256);
+        function sunset(address successor) external;
+        function isSunset() external view returns (bool);
+        function successor() external view returns (address);
+        function setAutoPauseThreshold(uint64 threshold) external;
+        function getAutoPauseThreshold() external view returns (uint64);
+        function getWritesInHour(uint256 hourIndex) external view returns (uint256);
+        function unpause() external;
+        function isPaused() external view returns (bool);
+        function getAdminLogLength() external view returns (uint256);
+        function getAdminLog(uint256 offset, uint256 limit) external view returns (uint8[] actions, address[] actors, bytes32[] targets, uint256[] timestamps);
+        function owner() external view returns (address);
+        function setAcceptedVersions(uint8 minVersion, uint8 maxVersion) external;
+        function getAcceptedVersions() external view returns (uint8 minVersion, uint8 maxVersion);
+        function setAllowlistRoot(bytes32 root) external;
+        function getAllowlistRoot() external view returns (bytes32);
+        function setRelayer(address relayer) external;
+        function getRelayer() external view returns (address);
+        function setTrustedForwarder(address forwarder) external;
+        function getTrustedForwarder() external view returns (address);
+        function isTrustedForwarder(address forwarder) external view returns (bool);
+        function setEphemerisOracle(address oracle) external;
+        function getEphemerisOracle() external view returns (address);
+        function setChartRegistry(address registry) external;
+        function getChartRegistry() external view returns (address);
+        function setPredictionHook(address hook) external;
+        function getPredictionHook() external view returns (address);
+        function setMinPredictionsForCommunity(uint256 minPredictions) external;
+        function getMinPredictionsForCommunity() external view returns (uint256);
+        function communityEligible(address user) external view returns (bool);
+        function setAstrologer(address astrologer, bool approved) external;
+        function isAstrologer(address astrologer) external view returns (bool);
+        function setPointWeights(uint32 registration, uint32 prediction, uint32 rating, uint32 streak) external;
+        function getPointWeights() external view returns (uint32 registration, uint32 prediction, uint32 rating, uint32 streak);
+        function deductPoints(address user, uint256 amount, bytes32 reasonHash) external;
+        function getPoints(address user) external view returns (uint256);
+        function setMinRetentionDays(uint64 days) external;
+        function getMinRetentionDays() external view returns (uint64);
+        function setMaxBackfillDays(uint64 days) external;
+        function getMaxBackfillDays() external view returns (uint64);
+        function setFidAttestor(address attestor) external;
+        function getFidAttestor() external view returns (address);
+        function addAttestor(uint8 role, address key, uint256 expiresAt) external;
+        function revokeAttestor(uint8 role, address key) external;
+        function getAttestorExpiry(uint8 role, address key) external view returns (uint256);
+        function getAttestorCount(uint8 role) external view returns (uint256);
+        function getAttestors(uint8 role, uint256 offset, uint256 limit) external view returns (address[]);
+        function setMaxBetaFid(uint256 maxFid) external;
+        function getMaxBetaFid() external view returns (uint256);
+        function getFid(address user) external view returns (uint256);
+        function getFidOwner(uint256 fid) external view returns (address);
+        function registerUser(bytes32 commitment) external payable;
+        function registerUserAllowlisted(bytes32 commitment, bytes32[] merkleProof) external payable;
+        function registerWithFid(bytes32 commitment, uint256 fid, bytes fidProofSig) external payable;
+        function registerUserWithProof(bytes32 commitment, bytes32 proof, bytes nonce, uint64[] positions, uint8 schemeVersion, uint8 encoding, uint64 epoch) external payable;
+        function registerUserWithAnchoredProof(bytes32 commitment, bytes32 proof, bytes nonce, uint64[] positions, uint64 epoch, uint256 anchorBlock) external payable;
+        function registerUserWithBucket(bytes32 commitment, bytes32 proof, bytes nonce, uint64[] positions, uint64 epoch, uint8 yearBucket, bytes32 openingProof) external payable;
+        function registerUserWithProofPacked(bytes32 commitment, bytes32 proof, bytes nonce, bytes32[2] positionsPacked, uint8 count, uint8 schemeVersion, uint8 encoding, uint64 epoch) external payable;
+        function verifyProof(bytes32 commitment, bytes32 proof, bytes nonce, uint64[] positions, uint8 schemeVersion, uint8 encoding, uint64 epoch) external view returns (bool);
+        function verifyProofHex(string commitment, string proof, string nonce, uint64[] positions, uint8 schemeVersion, uint8 encoding, uint64 epoch) external view returns (bool);
+        function registerUserPedersen(uint256 commitmentX, uint256 commitmentY) external payable;
+        function registerUserSchnorr(uint256 commitmentX, uint256 commitmentY, uint256 rX, uint256 rY, uint256 s, uint64[] positions) external payable;
+        function setRegistrationFee(uint256 fee) external;
+        function getRegistrationFee() external view returns (uint256);
+        function fundSponsorship() external payable;
+        function getSponsorPool() external view returns (uint256);
+        function setSponsorshipDailyCap(uint64 cap) external;
+        function getSponsorshipDailyCap() external view returns (uint64);
+        function getSponsoredSlotsToday() external view returns (uint256);
+        function getCollectedFees() external view returns (uint256);
+        function withdrawFees(address to) external;
+        function getSponsorContribution(address funder) external view returns (uint256);
+        function reclaimSponsorship() external;
+        function storePrediction(uint256 date, bytes32 predictionHash) external;
+        function setChallengeWindow(uint64 seconds) external;
+        function getChallengeWindow() external view returns (uint64);
+        function proposePredictionFor(address user, uint256 date, bytes32 predictionHash, bytes32 idempotencyKey) external;
+        function confirmPrediction(uint256 date) external;
+        function rejectPrediction(uint256 date) external;
+        function finalizePrediction(address user, uint256 date) external;
+        function getPendingPrediction(address user, uint256 date) external view returns (bytes32, uint256);
+        function getFinalizable(address relayer, uint256 limit) external view returns ((address, uint256)[] entries);
+        function finalizeBatch((address, uint256)[] entries) external returns (uint8[] statuses);
+        function anchorDailyRoot(uint256 date, bytes32 root, uint256 count, bytes32 idempotencyKey) external;
+        function isIdempotencyKeyUsed(address relayer, bytes32 key) external view returns (bool);
+        function claimAnchoredPrediction(uint256 date, bytes32 predictionHash, bytes32[] proof) external;
+        function getDailyRoot(uint256 date) external view returns (bytes32 root, uint256 count);
+        function isPredictionAnchored(address user, uint256 date) external view returns (bool);
+        function getDailyAccumulator(uint256 date) external view returns (bytes32 accumulator, uint256 count);
+        function ratePrediction(uint256 date, uint8 rating) external;
+        function registerDimension(uint8 dimensionId, bytes32 nameHash) external;
+        function getDimension(uint8 dimensionId) external view returns (bytes32);
+        function rateDimension(uint256 date, uint8 dimensionId, uint8 value) external;
+        function getDimensionRating(address user, uint256 date, uint8 dimensionId) external view returns (uint8);
+        function getDimensionStats(address user, uint8 dimensionId) external view returns (uint256 totalRatings, uint256 averageRatingX10);
+        function setRevealTiming(uint64 delay, uint64 window) external;
+        function getRevealTiming() external view returns (uint64 delay, uint64 window);
+        function commitRating(address target, uint256 date, bytes32 commitment) external;
+        function revealRating(address target, uint256 date, uint8 rating, bytes32 salt) external;
+        function expireRatingCommitment(address rater, address target, uint256 date) external;
+        function hasRatingCommitment(address rater, address target, uint256 date) external view returns (bool);
+        function getRevealDeadline(address rater, address target, uint256 date) external view returns (uint256 revealAt, uint256 expiresAt);
+        function getCommunityRating(address target, uint256 date, address rater) external view returns (uint8);
+        function getCommunityStats(address target, uint256 date) external view returns (uint256 totalRatings, uint256 averageRatingX10);
+        function prunePredictions(uint256[] dates) external;
+        function getPrunedCount(address user) external view returns (uint256);
+        function isPredictionPruned(address user, uint256 date) external view returns (bool);
+        function getUserCommitment(address user) external view returns (bytes32);
+        function isUserRegistered(address user) external view returns (bool);
+        function getUserSchemeVersion(address user) external view returns (uint8);
+        function getPedersenCommitment(address user) external view returns (uint256 x, uint256 y);
+        function storePredictionForChart(uint256 date, bytes32 predictionHash, bytes32 chartKey) external;
+        function getPredictionMeta(address user, uint256 date) external view returns (bytes32 predictionHash, uint8 rating, bytes32 chartKey);
+        function getPrediction(address user, uint256 date) external view returns (bytes32);
+        function hasPrediction(address user, uint256 date) external view returns (bool);
+        function hasPredictionOnDay(address user, uint32 day) external view returns (bool);
+        function havePredictions(address[] users, uint256 date) external view returns (bool[]);
+        function getPredictionsMulti(address[] users, uint256 date) external view returns (bool[] exists, bytes32[] predictionHashes, uint8[] ratings);
+        function predictionSlot(address user, uint256 date) external pure returns (bytes32);
+        function commitmentSlot(address user) external pure returns (bytes32);
+        function verifyPredictionPayload(address user, uint8 version, uint64 date, uint8 category, string text, uint16 luckyNumber, uint8 luckyColor, uint32 modelId) external view returns (bool);
+        function getRating(address user, uint256 date) external view returns (uint8);
+        function getPredictionsRange(address user, uint256 startDate, uint256 endDate) external view returns (uint256[] dates, bytes32[] hashes);
+        function endorsePrediction(address user, uint256 date, bytes32 noteHash) external;
+        function getEndorsements(address user, uint256 date) external view returns (address[] astrologers, bytes32[] noteHashes, uint256[] timestamps);
+        function getRatingAttestation(address user, uint256 date) external view returns (uint8 rating, bool exists, uint256 blockNumber, bytes32 attestationHash);
+        function getUserStats(address user) external view returns (uint256 totalPredictions, uint256 totalRatings, uint256 averageRatingX10);
+        function getMonthlyRating(address user, uint32 monthIndex) external view returns (uint256 count, uint256 averageX10);
+        function getRatersForDate(uint256 date) external view returns (uint256);
+        function getRatersForWeek(uint256 weekStart) external view returns (uint256[]);
+        function ratePredictionBlinded(uint256 date, uint32 modelId, uint256 blindedRating, bytes rangeProof) external;
+        function revealBlindedAggregate(uint32 modelId, uint256 sum, uint256 blindingSum) external;
+        function getBlindedRating(address user, uint256 date) external view returns (uint256);
+        function getBlindedAggregate(uint32 modelId) external view returns (uint256 count, uint256 commitment);
+        function getRevealedAggregate(uint32 modelId) external view returns (uint256 count, uint256 sum);
+        function getUnratedDates(address user, uint256 beforeDate, uint256 offset, uint256 limit) external view returns (uint256[]);
+        function setRatingHalfLife(uint64 halfLifeDays) external;
+        function getRatingHalfLife() external view returns (uint64);
+        function setRatingWindowDays(uint64 days) external;
+        function getRatingWindowDays() external view returns (uint64);
+        function getEwmaRating(address user) external view returns (uint256 valueX1000, uint256 lastUpdate);
+        function exportUser(address user, uint256 dateOffset, uint256 dateLimit) external view returns (UserExport export);
+        function computeUserHistoryRoot(address user, uint256 dateOffset, uint256 dateLimit) external returns (bytes32 partialRoot, uint256 processed);
+        function finalizeHistoryRoot(address user) external;
+        function getHistoryRoot(address user) external view returns (bytes32 root, uint256 leaves, uint256 finalizedAt);
+        function auditUser(address user, uint256 dateOffset, uint256 dateLimit) external view returns (bool consistent, uint256 predictions, uint256 ratings, uint256 ratingSum);
+        function repairUserCounters(address user) external;
+        function importUsers(address[] users, bytes32[] commitments, uint256[] registeredAt, bool dryRun, bytes32 idempotencyKey) external returns (uint8[] statuses);
+        function getUserBucket(address user) external view returns (bool hasBucket, uint8 bucket);
+        function setAggregateOptOut(bool optedOut) external;
+        function isOptedOut(address user) external view returns (bool);
+        function setTimezone(int16 offsetMinutes) external;
+        function getTimezone(address user) external view returns (int16);
+        function localDateKey(address user, uint256 timestamp) external view returns (uint256);
+        function getBucketStats(uint8 bucket) external view returns (uint256 totalRatings, uint256 ratingSum);
+        function getSignForLongitude(uint256 longitude) external view returns (uint8);
+        function getSignCompatibility(uint8 signA, uint8 signB) external view returns (uint8);
+        function getNakshatra(uint256 longitude, uint256 ayanamsa) external view returns (uint8 nakshatra, uint8 pada);
+        function getMoonPhase(uint256 date) external view returns (uint8 phase, uint256 illumination);
+        function scoreTransits(address user, uint256 date) external view returns (uint256 score, uint256 count);
+        function isSolarReturnWindow(address user, uint256 date, uint64 orbCentideg) external view returns (bool);
+        function getDailySeed(uint256 date) external view returns (bytes32);
+        function pickFromSeed(bytes32 seed, address user, uint256 modulus) external view returns (uint256);
+        function deriveLuckyNumber(address user, uint256 date) external view returns (uint256);
+        function deriveLuckyColor(address user, uint256 date) external view returns (uint8);
+        function getGlobalStats() external view returns (uint256 totalUsers, uint256 totalPredictions);
+        function getGlobalRatingStats() external view returns (uint256 ratingCount, uint256 ratingSum);
+        function checkpoint() external;
+        function getLastCheckpointDay() external view returns (uint64);
+        function joinPair(bytes32 chartKey, bytes32 partnerChartKey) external;
+        function getPair(bytes32 pairKey) external view returns (bytes32 chartA, address ownerA, bytes32 chartB, address ownerB);
+        function storePairPrediction(bytes32 pairKey, uint256 date, bytes32 predictionHash) external;
+        function ratePairPrediction(bytes32 pairKey, uint256 date, uint8 rating) external;
+        function getPairPrediction(bytes32 pairKey, uint256 date) external view returns (bytes32);
+        function hasPairPrediction(bytes32 pairKey, uint256 date) external view returns (bool);
+        function getPairRating(bytes32 pairKey, uint256 date) external view returns (uint8 ratingA, uint8 ratingB, uint256 averageRatingX10);
+        function getPairStats(bytes32 pairKey) external view returns (uint256 totalPredictions, uint256 totalRatings, uint256 averageRatingX10);
+        function multicall(bytes[] calls) external returns (bytes[] results);
+        function estimateBatchCost(address user, uint256[] dates) external view returns (uint256 newSlots, uint256 duplicateCount, uint256 estimatedInk);
+        function setFacet(address facet, bytes4[] selectors) external;
+        function getFacet(bytes4 selector) external view returns (address);
+        function getPendingFacet(bytes4 selector) external view returns (address facet, uint64 effectiveAt);
+    }
+
+    /// ChartRegistry (`src/chart_registry.rs`)
+    interface IChartRegistry {
+        function initialize(address owner) external;
+        function owner() external view returns (address);
+        function setTrustedForwarder(address forwarder) external;
+        function getTrustedForwarder() external view returns (address);
+        function isTrustedForwarder(address forwarder) external view returns (bool);
+        function setRegistrar(address registrar, bool approved) external;
+        function isRegistrar(address registrar) external view returns (bool);
+        function getImplementationVersion() external view returns (uint256);
+        function sunset(address successor) external;
+        function isSunset() external view returns (bool);
+        function successor() external view returns (address);
+        function setVerificationHook(address hook) external;
+        function getVerificationHook() external view returns (address);
+        function setFacet(address facet, bytes4[] selectors) external;
+        function getFacet(bytes4 selector) external view returns (address);
+        function getPendingFacet(bytes4 selector) external view returns (address facet, uint64 effectiveAt);
+        function registerChart(string chartId, bytes32 chartHash, address user, bool zkVerified) external;
+        function registerChartWithPositions(string chartId, bytes32[2] packed, bytes32 salt) external;
+        function getChartPositions(string chartId) external view returns (bool hasPositions, bytes32[2] packed);
+        function registerChartWithHouses(string chartId, bytes32[2] packed, bytes32[2] cusps, bytes32 salt) external;
+        function getHouseCusps(string chartId) external view returns (bool hasCusps, bytes32[2] cusps);
+        function getHousePlacement(string chartId, uint8 body) external view returns (uint8 house);
+        function updateChart(string chartId, bytes32[2] packed, bytes32[2] cusps, bytes32 salt) external;
+        function getChartVersion(string chartId) external view returns (uint256);
+        function diffChartVersions(string chartId, uint256 versionA, uint256 versionB) external view returns ((uint8, uint64, uint64, bool)[] changes);
+        function deriveChartId(address user, bytes32 chartHash) external view returns (string);
+        function registerChartDerived(bytes32 chartHash, address user, bool zkVerified) external returns (string chartId);
+        function registerChartWithInput(string chartId, bytes32 chartHash, address user, bool zkVerified, bytes32 inputCommitment) external;
+        function getInputCommitment(string chartId) external view returns (bytes32);
+        function revealInput(string chartId, bytes birthPayload, bytes32 serverSalt) external;
+        function synastryScore(string chartIdA, string chartIdB) external view returns (uint8 score, uint8 aspectCount);
+        function verifyChart(string chartId, bytes32 chartHash) external view returns (bool);
+        function verifyBirthCommitment(bytes32 commitment, bytes packedBirth, bytes32 salt) external pure returns (bool);
+        function verifyChartLegacy(string chartId, int64[] planets, int64 asc, int64 mc, string zkProof) external view returns (bool);
+        function getChart(string chartId) external view returns (bytes32 chartHash, address user, uint256 timestamp, bool zkVerified, string storedChartId);
+        function chartSlot(string chartId) external pure returns (bytes32);
+        function debugChartKey(string chartId) external pure returns (bytes32);
+        function getUserCharts(address user) external view returns (string[] chartIds);
+        function markAsVerified(string chartId) external;
+        function totalCharts() external view returns (uint256);
+        function isZkVerified(string chartId) external view returns (bool);
+        function auditChart(string chartId) external view returns (bool consistent, uint8 issueFlags);
+        function repairChart(string chartId) external;
+        function importCharts(string[] chartIds, bytes32[] chartHashes, address[] users, uint256[] timestamps, bool dryRun, bytes32 idempotencyKey) external returns (uint8[] statuses);
+        function isIdempotencyKeyUsed(address sender, bytes32 key) external view returns (bool);
+    }
+
+    /// EphemerisOracle (`src/ephemeris.rs`)
+    interface IEphemerisOracle {
+        function initialize(address owner) external;
+        function owner() external view returns (address);
+        function setUpdater(address updater, bool allowed) external;
+        function isUpdater(address account) external view returns (bool);
+        function publishPositions(uint256 date, bytes32[2] packedPositions) external;
+        function getPositions(uint256 date) external view returns (bytes32[2]);
+        function hasPositions(uint256 date) external view returns (bool);
+        function findIngresses(uint256 startDate, uint256 endDate, uint8 body) external view returns (uint256[] dates, uint8[] fromSigns, uint8[] toSigns);
+    }
+
+    /// ZkAstroReader (`src/reader.rs`)
+    interface IZkAstroReader {
+        struct UserStats { uint256 totalPredictions; uint256 totalRatings; uint256 averageRatingX10; }
+        struct ProfileScreen { UserStats stats; uint256 points; uint256 weekStart; bytes32[] hashes; uint8[] ratings; bool[] hasPositions; uint256 chartCount; bool hasVerifiedChart; }
+
+        function initialize(address owner) external;
+        function owner() external view returns (address);
+        function setContracts(address predictions, address chartRegistry, address ephemerisOracle) external;
+        function getContracts() external view returns (address predictions, address chartRegistry, address ephemerisOracle);
+        function getHomeScreen(address user) external view returns (UserStats stats, bytes32 todaysPrediction, uint8 todaysRating, bool hasVerifiedChart, uint8 moonPhase);
+        function getProfileScreen(address user, uint256 weekStart) external view returns (ProfileScreen);
+    }
+}
+
+/// Decoded `getUserStats` result
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserStats {
+    pub total_predictions: U256,
+    pub total_ratings: U256,
+    /// Average rating times ten, zero before the first rating
+    pub average_rating_x10: U256,
+}
+
+/// Decoded `getChart` result; all zero for an unknown chart
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chart {
+    pub chart_hash: [u8; 32],
+    pub user: Address,
+    pub timestamp: U256,
+    pub zk_verified: bool,
+    pub chart_id: String,
+}
+
+/// Calldata of `registerUser(commitment)`
+pub fn encode_register_user(commitment: &Commitment) -> Vec<u8> {
+    IFarcasterPredictions::registerUserCall { commitment: FixedBytes(commitment.0) }.abi_encode()
+}
+
+/// Calldata of `storePrediction(date, predictionHash)`
+///
+/// `date` is the Unix timestamp the prediction is for.
+pub fn encode_store_prediction(date: u64, prediction_hash: [u8; 32]) -> Vec<u8> {
+    IFarcasterPredictions::storePredictionCall { date: U256::from(date), predictionHash: FixedBytes(prediction_hash) }
+        .abi_encode()
+}
+
+/// Calldata of `ratePrediction(date, rating)`, rating 1-5
+pub fn encode_rate_prediction(date: u64, rating: u8) -> Vec<u8> {
+    IFarcasterPredictions::ratePredictionCall { date: U256::from(date), rating }.abi_encode()
+}
+
+/// Calldata of `getUserStats(user)`
+pub fn encode_get_user_stats(user: Address) -> Vec<u8> {
+    IFarcasterPredictions::getUserStatsCall { user }.abi_encode()
+}
+
+/// Calldata of `verifyPredictionPayload(user, ...)` for `payload`
+pub fn encode_verify_prediction_payload(user: Address, payload: &PredictionPayload) -> Vec<u8> {
+    IFarcasterPredictions::verifyPredictionPayloadCall {
+        user,
+        version: payload.version,
+        date: payload.date,
+        category: payload.category,
+        text: payload.text.clone(),
+        luckyNumber: payload.lucky_number,
+        luckyColor: payload.lucky_color,
+        modelId: payload.model_id,
+    }
+    .abi_encode()
+}
+
+/// Calldata of `registerChart(chartId, chartHash, user, zkVerified)`
+pub fn encode_register_chart(chart_id: &str, chart_hash: [u8; 32], user: Address, zk_verified: bool) -> Vec<u8> {
+    IChartRegistry::registerChartCall {
+        chartId: chart_id.into(),
+        chartHash: FixedBytes(chart_hash),
+        user,
+        zkVerified: zk_verified,
+    }
+    .abi_encode()
+}
+
+/// Calldata of `getChart(chartId)`
+pub fn encode_get_chart(chart_id: &str) -> Vec<u8> {
+    IChartRegistry::getChartCall { chartId: chart_id.into() }.abi_encode()
+}
+
+/// Decode the return data of `getUserStats`
+pub fn decode_user_stats(data: &[u8]) -> Result<UserStats, DecodeError> {
+    let stats = IFarcasterPredictions::getUserStatsCall::abi_decode_returns(data, true)?;
+    Ok(UserStats {
+        total_predictions: stats.totalPredictions,
+        total_ratings: stats.totalRatings,
+        average_rating_x10: stats.averageRatingX10,
+    })
+}
+
+/// Decode the return data of `getChart`
+///
+/// Stylus SDK 0.9 returns a tuple with a dynamic member as one encoded
+/// value, behind a leading offset word, not as Solidity-style return
+/// parameters; this decodes what the contract actually returns.
+pub fn decode_chart(data: &[u8]) -> Result<Chart, DecodeError> {
+    let (chart_hash, user, timestamp, zk_verified, chart_id) =
+        <(FixedBytes<32>, Address, U256, bool, String)>::abi_decode(data, true)?;
+    Ok(Chart { chart_hash: chart_hash.0, user, timestamp, zk_verified, chart_id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::keccak256;
+
+    /// Check `selectors` are exactly the pinned ones, and each pin is the
+    /// selector of its signature
+    fn assert_pinned(selectors: &[[u8; 4]], pins: &[(&str, u32)]) {
+        for (signature, selector) in pins {
+            let hash = keccak256(signature.as_bytes());
+            assert_eq!(u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]), *selector, "{signature}");
+        }
+        let mut pinned: Vec<[u8; 4]> = pins.iter().map(|(_, selector)| selector.to_be_bytes()).collect();
+        pinned.sort();
+        let mut actual = selectors.to_vec();
+        actual.sort();
+        assert_eq!(actual, pinned);
+    }
+
+    #[test]
+    fn test_farcaster_predictions_selectors() {
+        assert_pinned(
+            IFarcasterPredictions::IFarcasterPredictionsCalls::SELECTORS,
+            &[
+                ("initialize(address)", 0xc4d66de8),
+                ("getImplementationVersion()", 0xbc327e40),
+                ("sunset(address)", 0x9ec7aaad),
+                ("isSunset()", 0x90b8b0c8),
+                ("successor()", 0x6ff968c3),
+                ("setAutoPauseThreshold(uint64)", 0x38bf1c2d),
+                ("getAutoPauseThreshold()", 0x305c2c36),
+                ("getWritesInHour(uint256)", 0x97943d63),
+                ("unpause()", 0x3f4ba83a),
+                ("isPaused()", 0xb187bd26),
+                ("getAdminLogLength()", 0x332b49c2),
+                ("getAdminLog(uint256,uint256)", 0x42043c06),
+                ("owner()", 0x8da5cb5b),
+                ("setAcceptedVersions(uint8,uint8)", 0xa87720be),
+                ("getAcceptedVersions()", 0x013ef9b0),
+                ("setAllowlistRoot(bytes32)", 0x8e0acd12),
+                ("getAllowlistRoot()", 0x139e5a34),
+                ("setRelayer(address)", 0x6548e9bc),
+                ("getRelayer()", 0xbdc50373),
+                ("setTrustedForwarder(address)", 0xda742228),
+                ("getTrustedForwarder()", 0xce1b815f),
+                ("isTrustedForwarder(address)", 0x572b6c05),
+                ("setEphemerisOracle(address)", 0xb0553245),
+                ("getEphemerisOracle()", 0xcd659fac),
+                ("setChartRegistry(address)", 0x17ad510e),
+                ("getChartRegistry()", 0x1ed3dc22),
+                ("setPredictionHook(address)", 0x6e0f7b6a),
+                ("getPredictionHook()", 0x14e227d4),
+                ("setMinPredictionsForCommunity(uint256)", 0x0cd019df),
+                ("getMinPredictionsForCommunity()", 0xe21f129c),
+                ("communityEligible(address)", 0x7dadbc2b),
+                ("setAstrologer(address,bool)", 0x22303587),
+                ("isAstrologer(address)", 0x6913ff81),
+                ("setPointWeights(uint32,uint32,uint32,uint32)", 0x1eaec0cb),
+                ("getPointWeights()", 0x514ae293),
+                ("deductPoints(address,uint256,bytes32)", 0x20ff7249),
+                ("getPoints(address)", 0xee204abb),
+                ("setMinRetentionDays(uint64)", 0xb4624585),
+                ("getMinRetentionDays()", 0x2d0ffdd4),
+                ("setMaxBackfillDays(uint64)", 0xdda49c84),
+                ("getMaxBackfillDays()", 0xd32964f3),
+                ("setFidAttestor(address)", 0xbf9fc402),
+                ("getFidAttestor()", 0x687a99e8),
+                ("addAttestor(uint8,address,uint256)", 0x25f5a743),
+                ("revokeAttestor(uint8,address)", 0xc1dca134),
+                ("getAttestorExpiry(uint8,address)", 0x14df94ae),
+                ("getAttestorCount(uint8)", 0x7ae970e3),
+                ("getAttestors(uint8,uint256,uint256)", 0xaf16e991),
+                ("setMaxBetaFid(uint256)", 0xf83cc78c),
+                ("getMaxBetaFid()", 0xb36fe3e0),
+                ("getFid(address)", 0x20a84c2e),
+                ("getFidOwner(uint256)", 0x274e77bd),
+                ("registerUser(bytes32)", 0x2dc03259),
+                ("registerUserAllowlisted(bytes32,bytes32[])", 0x04c87f57),
+                ("registerWithFid(bytes32,uint256,bytes)", 0x14841b8f),
+                ("registerUserWithProof(bytes32,bytes32,bytes,uint64[],uint8,uint8,uint64)", 0x30abb6f5),
+                ("registerUserWithAnchoredProof(bytes32,bytes32,bytes,uint64[],uint64,uint256)", 0x9e9e32b0),
+                ("registerUserWithBucket(bytes32,bytes32,bytes,uint64[],uint64,uint8,bytes32)", 0xe93ccadc),
+                ("registerUserWithProofPacked(bytes32,bytes32,bytes,bytes32[2],uint8,uint8,uint8,uint64)", 0xa8b58312),
+                ("verifyProof(bytes32,bytes32,bytes,uint64[],uint8,uint8,uint64)", 0x69628c93),
+                ("verifyProofHex(string,string,string,uint64[],uint8,uint8,uint64)", 0x14811f07),
+                ("registerUserPedersen(uint256,uint256)", 0xc67838bc),
+                ("registerUserSchnorr(uint256,uint256,uint256,uint256,uint256,uint64[])", 0x31b7b45c),
+                ("setRegistrationFee(uint256)", 0xc320c727),
+                ("getRegistrationFee()", 0x0946e807),
+                ("fundSponsorship()", 0xe0dca47b),
+                ("getSponsorPool()", 0x0f75be22),
+                ("setSponsorshipDailyCap(uint64)", 0x7b02844d),
+                ("getSponsorshipDailyCap()", 0x85c039c7),
+                ("getSponsoredSlotsToday()", 0xd7147711),
+                ("getCollectedFees()", 0xd54a8d18),
+                ("withdrawFees(address)", 0x164e68de),
+                ("getSponsorContribution(address)", 0xa47f8795),
+                ("reclaimSponsorship()", 0xd2f7bf97),
+                ("storePrediction(uint256,bytes32)", 0x2e43b9a8),
+                ("setChallengeWindow(uint64)", 0xda75c01e),
+                ("getChallengeWindow()", 0x6a50c71e),
+                ("proposePredictionFor(address,uint256,bytes32,bytes32)", 0xbb2a9d6f),
+                ("confirmPrediction(uint256)", 0x3611da53),
+                ("rejectPrediction(uint256)", 0xfd49577a),
+                ("finalizePrediction(address,uint256)", 0xc6d10597),
+                ("getPendingPrediction(address,uint256)", 0xc895cc64),
+                ("getFinalizable(address,uint256)", 0x59d795a0),
+                ("finalizeBatch((address,uint256)[])", 0x956500b3),
+                ("anchorDailyRoot(uint256,bytes32,uint256,bytes32)", 0xb8c003b8),
+                ("isIdempotencyKeyUsed(address,bytes32)", 0x6e5c2c76),
+                ("claimAnchoredPrediction(uint256,bytes32,bytes32[])", 0xb3dfb026),
+                ("getDailyRoot(uint256)", 0xcc00b2de),
+                ("isPredictionAnchored(address,uint256)", 0xeba09cf4),
+                ("getDailyAccumulator(uint256)", 0xd5c10572),
+                ("ratePrediction(uint256,uint8)", 0xc40324ca),
+                ("registerDimension(uint8,bytes32)", 0x9e5434f8),
+                ("getDimension(uint8)", 0x2eab87a3),
+                ("rateDimension(uint256,uint8,uint8)", 0xe53c6f7c),
+                ("getDimensionRating(address,uint256,uint8)", 0xf87a274f),
+                ("getDimensionStats(address,uint8)", 0xe5a626fe),
+                ("setRevealTiming(uint64,uint64)", 0xb62da8b9),
+                ("getRevealTiming()", 0x85f9049d),
+                ("commitRating(address,uint256,bytes32)", 0x4a38acac),
+                ("revealRating(address,uint256,uint8,bytes32)", 0x691fc8fd),
+                ("expireRatingCommitment(address,address,uint256)", 0xd4644d43),
+                ("hasRatingCommitment(address,address,uint256)", 0x9d042787),
+                ("getRevealDeadline(address,address,uint256)", 0xcaf57ea6),
+                ("getCommunityRating(address,uint256,address)", 0x4b43f45d),
+                ("getCommunityStats(address,uint256)", 0x901e07de),
+                ("prunePredictions(uint256[])", 0xf0f3db12),
+                ("getPrunedCount(address)", 0x123f79ac),
+                ("isPredictionPruned(address,uint256)", 0x8902224a),
+                ("getUserCommitment(address)", 0x415d6a01),
+                ("isUserRegistered(address)", 0x163f7522),
+                ("getUserSchemeVersion(address)", 0x199d72f0),
+                ("getPedersenCommitment(address)", 0xde9e0bbc),
+                ("storePredictionForChart(uint256,bytes32,bytes32)", 0x2d3ae777),
+                ("getPredictionMeta(address,uint256)", 0xe30898ae),
+                ("getPrediction(address,uint256)", 0x3abb69bb),
+                ("hasPrediction(address,uint256)", 0x4bad3ca1),
+                ("hasPredictionOnDay(address,uint32)", 0x253578cb),
+                ("havePredictions(address[],uint256)", 0x6f77d7ed),
+                ("getPredictionsMulti(address[],uint256)", 0x7cffe828),
+                ("predictionSlot(address,uint256)", 0x1b395da7),
+                ("commitmentSlot(address)", 0x151131f5),
+                ("verifyPredictionPayload(address,uint8,uint64,uint8,string,uint16,uint8,uint32)", 0xe4e6260e),
+                ("getRating(address,uint256)", 0x3dbeacbf),
+                ("getPredictionsRange(address,uint256,uint256)", 0xdf9a39c1),
+                ("endorsePrediction(address,uint256,bytes32)", 0xe9135f8a),
+                ("getEndorsements(address,uint256)", 0xd1409eed),
+                ("getRatingAttestation(address,uint256)", 0x2b8e5b8d),
+                ("getUserStats(address)", 0x4e43603a),
+                ("getMonthlyRating(address,uint32)", 0xe7c393ab),
+                ("getRatersForDate(uint256)", 0x64c920da),
+                ("getRatersForWeek(uint256)", 0x457c670a),
+                ("ratePredictionBlinded(uint256,uint32,uint256,bytes)", 0x0f41f29e),
+                ("revealBlindedAggregate(uint32,uint256,uint256)", 0x35518873),
+                ("getBlindedRating(address,uint256)", 0xc21b6a77),
+                ("getBlindedAggregate(uint32)", 0x59c5d1a0),
+                ("getRevealedAggregate(uint32)", 0xa8fe00f0),
+                ("getUnratedDates(address,uint256,uint256,uint256)", 0x038080f2),
+                ("setRatingHalfLife(uint64)", 0x07c03c01),
+                ("getRatingHalfLife()", 0x7fe3a641),
+                ("setRatingWindowDays(uint64)", 0xc69742b8),
+                ("getRatingWindowDays()", 0xc1384b93),
+                ("getEwmaRating(address)", 0x2a83ac93),
+                ("exportUser(address,uint256,uint256)", 0xd6412147),
+                ("computeUserHistoryRoot(address,uint256,uint256)", 0xc9f0d523),
+                ("finalizeHistoryRoot(address)", 0x0296650c),
+                ("getHistoryRoot(address)", 0xf3170f02),
+                ("auditUser(address,uint256,uint256)", 0xedea6b06),
+                ("repairUserCounters(address)", 0x3a25b926),
+                ("importUsers(address[],bytes32[],uint256[],bool,bytes32)", 0x87efb836),
+                ("getUserBucket(address)", 0xc7fe4e55),
+                ("setAggregateOptOut(bool)", 0x6031277e),
+                ("isOptedOut(address)", 0xff1468f2),
+                ("setTimezone(int16)", 0x24257426),
+                ("getTimezone(address)", 0x4cbf37f0),
+                ("localDateKey(address,uint256)", 0x6ed9ed18),
+                ("getBucketStats(uint8)", 0x43309595),
+                ("getSignForLongitude(uint256)", 0x85af1c7d),
+                ("getSignCompatibility(uint8,uint8)", 0x5182534e),
+                ("getNakshatra(uint256,uint256)", 0x5c92d310),
+                ("getMoonPhase(uint256)", 0x70ac810f),
+                ("scoreTransits(address,uint256)", 0x1a291efb),
+                ("isSolarReturnWindow(address,uint256,uint64)", 0xedca6f5b),
+                ("getDailySeed(uint256)", 0xe23ccf20),
+                ("pickFromSeed(bytes32,address,uint256)", 0x7244542a),
+                ("deriveLuckyNumber(address,uint256)", 0x5e8c6684),
+                ("deriveLuckyColor(address,uint256)", 0xf0731103),
+                ("getGlobalStats()", 0x6b4169c3),
+                ("getGlobalRatingStats()", 0x8e210462),
+                ("checkpoint()", 0xc2c4c5c1),
+                ("getLastCheckpointDay()", 0x484ebea9),
+                ("joinPair(bytes32,bytes32)", 0x9ef0d934),
+                ("getPair(bytes32)", 0xb8e5303d),
+                ("storePairPrediction(bytes32,uint256,bytes32)", 0x2834d504),
+                ("ratePairPrediction(bytes32,uint256,uint8)", 0xa28228d5),
+                ("getPairPrediction(bytes32,uint256)", 0xaa75e028),
+                ("hasPairPrediction(bytes32,uint256)", 0xfcc40a46),
+                ("getPairRating(bytes32,uint256)", 0x66b9bb15),
+                ("getPairStats(bytes32)", 0x446e741c),
+                ("multicall(bytes[])", 0xac9650d8),
+                ("estimateBatchCost(address,uint256[])", 0xb74e27d3),
+                ("setFacet(address,bytes4[])", 0x3bf2fe3f),
+                ("getFacet(bytes4)", 0x112b6a67),
+                ("getPendingFacet(bytes4)", 0xce4ca817),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_chart_registry_selectors() {
+        assert_pinned(
+            IChartRegistry::IChartRegistryCalls::SELECTORS,
+            &[
+                ("initialize(address)", 0xc4d66de8),
+                ("owner()", 0x8da5cb5b),
+                ("setTrustedForwarder(address)", 0xda742228),
+                ("getTrustedForwarder()", 0xce1b815f),
+                ("isTrustedForwarder(address)", 0x572b6c05),
+                ("setRegistrar(address,bool)", 0xc1bf1dcf),
+                ("isRegistrar(address)", 0xd5db72eb),
+                ("getImplementationVersion()", 0xbc327e40),
+                ("sunset(address)", 0x9ec7aaad),
+                ("isSunset()", 0x90b8b0c8),
+                ("successor()", 0x6ff968c3),
+                ("setVerificationHook(address)", 0xa9511854),
+                ("getVerificationHook()", 0x9d39c107),
+                ("setFacet(address,bytes4[])", 0x3bf2fe3f),
+                ("getFacet(bytes4)", 0x112b6a67),
+                ("getPendingFacet(bytes4)", 0xce4ca817),
+                ("registerChart(string,bytes32,address,bool)", 0xc12d16fe),
+                ("registerChartWithPositions(string,bytes32[2],bytes32)", 0x5dcac4b7),
+                ("getChartPositions(string)", 0x187e0de3),
+                ("registerChartWithHouses(string,bytes32[2],bytes32[2],bytes32)", 0x64cd212c),
+                ("getHouseCusps(string)", 0x93c23c12),
+                ("getHousePlacement(string,uint8)", 0x37fbc120),
+                ("updateChart(string,bytes32[2],bytes32[2],bytes32)", 0x5ce34b49),
+                ("getChartVersion(string)", 0x90fd7171),
+                ("diffChartVersions(string,uint256,uint256)", 0x76f686e2),
+                ("deriveChartId(address,bytes32)", 0xcf757156),
+                ("registerChartDerived(bytes32,address,bool)", 0xe31de634),
+                ("registerChartWithInput(string,bytes32,address,bool,bytes32)", 0x8890b5f1),
+                ("getInputCommitment(string)", 0x6d9d75dc),
+                ("revealInput(string,bytes,bytes32)", 0x965af908),
+                ("synastryScore(string,string)", 0x926224de),
+                ("verifyChart(string,bytes32)", 0x8c5c7df7),
+                ("verifyBirthCommitment(bytes32,bytes,bytes32)", 0x6f29bc3c),
+                ("verifyChartLegacy(string,int64[],int64,int64,string)", 0x48b4ca03),
+                ("getChart(string)", 0x0fe5b1de),
+                ("chartSlot(string)", 0x3541b86d),
+                ("debugChartKey(string)", 0x7bb78ff3),
+                ("getUserCharts(address)", 0x9cb61acb),
+                ("markAsVerified(string)", 0xed9cc10e),
+                ("totalCharts()", 0xfa9781ea),
+                ("isZkVerified(string)", 0x77100612),
+                ("auditChart(string)", 0x68867ad9),
+                ("repairChart(string)", 0x92e79e98),
+                ("importCharts(string[],bytes32[],address[],uint256[],bool,bytes32)", 0x334882dd),
+                ("isIdempotencyKeyUsed(address,bytes32)", 0x6e5c2c76),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_ephemeris_oracle_selectors() {
+        assert_pinned(
+            IEphemerisOracle::IEphemerisOracleCalls::SELECTORS,
+            &[
+                ("initialize(address)", 0xc4d66de8),
+                ("owner()", 0x8da5cb5b),
+                ("setUpdater(address,bool)", 0x1a153391),
+                ("isUpdater(address)", 0x4fdfb086),
+                ("publishPositions(uint256,bytes32[2])", 0xf2e88c73),
+                ("getPositions(uint256)", 0x30e00596),
+                ("hasPositions(uint256)", 0xb4d40427),
+                ("findIngresses(uint256,uint256,uint8)", 0x80e3b83c),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_zk_astro_reader_selectors() {
+        assert_pinned(
+            IZkAstroReader::IZkAstroReaderCalls::SELECTORS,
+            &[
+                ("initialize(address)", 0xc4d66de8),
+                ("owner()", 0x8da5cb5b),
+                ("setContracts(address,address,address)", 0xb3066d49),
+                ("getContracts()", 0xc3a2a93a),
+                ("getHomeScreen(address)", 0x65468cec),
+                ("getProfileScreen(address,uint256)", 0x4c3861ba),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_encode_calls() {
+        let commitment = Commitment([0xab; 32]);
+        let calldata = encode_register_user(&commitment);
+        assert_eq!(calldata[..4], IFarcasterPredictions::registerUserCall::SELECTOR);
+        assert_eq!(calldata[4..], commitment.0);
+
+        let calldata = encode_store_prediction(1_728_000_000, [1; 32]);
+        assert_eq!(calldata.len(), 4 + 64);
+        assert_eq!(U256::from_be_slice(&calldata[4..36]), U256::from(1_728_000_000u64));
+        assert_eq!(calldata[36..], [1; 32]);
+
+        let user = Address::repeat_byte(0xa1);
+        let calldata = encode_register_chart("chart-1", [7; 32], user, true);
+        let call = IChartRegistry::registerChartCall::abi_decode(&calldata, true).unwrap();
+        assert_eq!((call.chartId.as_str(), call.chartHash.0, call.user, call.zkVerified), ("chart-1", [7; 32], user, true));
+
+        let payload = PredictionPayload {
+            version: 1,
+            date: 1_728_000_000,
+            category: 2,
+            text: String::from("Mercury favours clear words today."),
+            lucky_number: 42,
+            lucky_color: 7,
+            model_id: 3,
+        };
+        let calldata = encode_verify_prediction_payload(user, &payload);
+        let call = IFarcasterPredictions::verifyPredictionPayloadCall::abi_decode(&calldata, true).unwrap();
+        assert_eq!((call.user, call.date, call.text.as_str(), call.modelId), (user, payload.date, payload.text.as_str(), 3));
+    }
+
+    #[test]
+    fn test_decode_returns() {
+        let data = (U256::from(3), U256::from(2), U256::from(45)).abi_encode_params();
+        assert_eq!(
+            decode_user_stats(&data),
+            Ok(UserStats { total_predictions: U256::from(3), total_ratings: U256::from(2), average_rating_x10: U256::from(45) })
+        );
+        assert!(decode_user_stats(&data[..64]).is_err());
+
+        let user = Address::repeat_byte(0xa1);
+        let data = (FixedBytes([7; 32]), user, U256::from(1_700_000_000), true, String::from("chart-1")).abi_encode();
+        let chart = decode_chart(&data).unwrap();
+        assert_eq!(
+            chart,
+            Chart {
+                chart_hash: [7; 32],
+                user,
+                timestamp: U256::from(1_700_000_000),
+                zk_verified: true,
+                chart_id: "chart-1".into(),
+            }
+        );
+        assert!(decode_chart(&data[..data.len() - 32]).is_err());
+    }
+}
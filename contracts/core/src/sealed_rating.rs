@@ -0,0 +1,45 @@
+//! Commitments to ratings revealed later
+//!
+//! A community rater first commits to a rating and reveals it once the
+//! reveal delay passed, so nobody sees earlier scores before rating:
+//!
+//! ```text
+//! commitment = keccak256(rating || salt || rater)
+//! ```
+//!
+//! `rating` is one byte, `salt` 32 bytes and `rater` a 20-byte address,
+//! i.e. Solidity's `abi.encodePacked(uint8, bytes32, address)`. Binding
+//! the rater keeps others from replaying a commitment as their own.
+
+use alloy_primitives::{Address, B256};
+
+use crate::hash::Keccak256Builder;
+
+/// Commitment of `rater` to `rating`, blinded by `salt`
+pub fn commitment(rating: u8, salt: B256, rater: Address) -> [u8; 32] {
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(&[rating]).update(salt.as_slice()).update(rater.as_slice());
+    hasher.finalize32()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_packed_encoding() {
+        let salt = B256::repeat_byte(0x5a);
+        let rater = Address::repeat_byte(0xa1);
+
+        let mut packed = vec![4];
+        packed.extend_from_slice(salt.as_slice());
+        packed.extend_from_slice(rater.as_slice());
+        assert_eq!(packed.len(), 1 + 32 + 20);
+        assert_eq!(commitment(4, salt, rater), crate::hash::keccak256(&packed));
+
+        // Rating, salt and rater all change it
+        assert_ne!(commitment(5, salt, rater), commitment(4, salt, rater));
+        assert_ne!(commitment(4, B256::ZERO, rater), commitment(4, salt, rater));
+        assert_ne!(commitment(4, salt, Address::ZERO), commitment(4, salt, rater));
+    }
+}
@@ -0,0 +1,72 @@
+//! Daily seeds for draws that must not be known before their day
+//!
+//! A day's seed is derived from the positions the ephemeris oracle
+//! publishes for it, so nobody can compute it before publication:
+//!
+//! ```text
+//! seed = keccak256(packed[0] || packed[1] || date || contract)
+//! pick = keccak256(seed || user) mod modulus
+//! ```
+//!
+//! `packed` are the two published words of the day's positions, `date`
+//! the timestamp the oracle keys them by as a 32-byte big-endian word and
+//! `contract` and `user` 20-byte addresses. The digest is read as a
+//! big-endian uint256 for the pick.
+
+use alloy_primitives::{Address, U256};
+
+use crate::hash::Keccak256Builder;
+
+/// Seed of `date`'s draws on `contract`, from the day's packed positions
+pub fn daily_seed(packed: &[[u8; 32]; 2], date: U256, contract: Address) -> [u8; 32] {
+    let mut hasher = Keccak256Builder::new();
+    hasher
+        .update(&packed[0])
+        .update(&packed[1])
+        .update(&date.to_be_bytes::<32>())
+        .update(contract.as_slice());
+    hasher.finalize32()
+}
+
+/// `user`'s draw in `0..modulus` from `seed`, `None` for a zero modulus
+pub fn pick(seed: &[u8; 32], user: Address, modulus: U256) -> Option<U256> {
+    if modulus.is_zero() {
+        return None;
+    }
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(seed).update(user.as_slice());
+    Some(U256::from_be_bytes(hasher.finalize32()) % modulus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_packed_encoding() {
+        let contract = Address::repeat_byte(0xc0);
+        let date = U256::from(1_728_000_000u64);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&[0x11; 32]);
+        packed.extend_from_slice(&[0x22; 32]);
+        packed.extend_from_slice(&date.to_be_bytes::<32>());
+        packed.extend_from_slice(contract.as_slice());
+        assert_eq!(packed.len(), 32 * 3 + 20);
+
+        let seed = daily_seed(&[[0x11; 32], [0x22; 32]], date, contract);
+        assert_eq!(seed, crate::hash::keccak256(&packed));
+        assert_eq!(hex::encode(seed), "f96b5f03776d7fb87f84dd21b2d8376b26c6fa837f8ec559e601e48213de3ba1");
+    }
+
+    #[test]
+    fn test_pick() {
+        let seed = [0x5e; 32];
+        let user = Address::repeat_byte(0xa1);
+        let digest = crate::hash::keccak256(&[&seed[..], user.as_slice()].concat());
+
+        assert_eq!(pick(&seed, user, U256::from(78)), Some(U256::from_be_bytes(digest) % U256::from(78)));
+        assert_eq!(pick(&seed, user, U256::from(1)), Some(U256::ZERO));
+        assert_eq!(pick(&seed, user, U256::ZERO), None);
+    }
+}
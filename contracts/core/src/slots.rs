@@ -0,0 +1,60 @@
+//! Storage slots of Solidity-layout mappings
+//!
+//! The contracts lay out storage the way Solidity does, so a value kept in
+//! a mapping lives at a slot derived from its key and the mapping's own
+//! slot `p`:
+//!
+//! ```text
+//! value slot = keccak256(pad32(key) || p)     (address, uint256, bytes32 keys)
+//! value slot = keccak256(key || p)            (string and bytes keys, unpadded)
+//! ```
+//!
+//! A nested mapping applies the rule once per level, outermost key first,
+//! with the slot found for the outer key as `p` of the inner one. A struct
+//! value starts at the derived slot and its fields follow in order. These
+//! are the slots an L1 storage proof names.
+
+use crate::hash::keccak256;
+
+/// Slot of the value `key` maps to in a mapping at `slot`, for keys
+/// encoded as one 32-byte word
+pub fn mapping_slot(key: [u8; 32], slot: [u8; 32]) -> [u8; 32] {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(&key);
+    preimage[32..].copy_from_slice(&slot);
+    keccak256(&preimage)
+}
+
+/// Slot of the value `key` maps to in a mapping at `slot`, for string and
+/// bytes keys
+pub fn bytes_mapping_slot(key: &[u8], slot: [u8; 32]) -> [u8; 32] {
+    keccak256(&[key, &slot].concat())
+}
+
+/// Big-endian word of a declared base slot
+pub fn base_slot(index: u64) -> [u8; 32] {
+    let mut slot = [0u8; 32];
+    slot[24..].copy_from_slice(&index.to_be_bytes());
+    slot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_slot_vectors() {
+        // keccak256 of 64 zero bytes: key 0 of a mapping at slot 0
+        assert_eq!(
+            hex::encode(mapping_slot([0; 32], base_slot(0))),
+            "ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+        );
+
+        // A string key hashes its bytes, not a padded word
+        let mut padded = [0u8; 32];
+        padded[..5].copy_from_slice(b"chart");
+        assert_ne!(bytes_mapping_slot(b"chart", base_slot(0)), mapping_slot(padded, base_slot(0)));
+        assert_eq!(bytes_mapping_slot(&[0; 32], base_slot(7)), mapping_slot([0; 32], base_slot(7)));
+        assert_eq!(base_slot(0x0102)[30..], [1, 2]);
+    }
+}
@@ -6,6 +6,12 @@
  * - ZK proof integration for birth data privacy
  * - Immutable rating system
  * - User statistics tracking
+ *
+ * The contract is too large for one Stylus program, so the core is deployed
+ * and initialized first, then each `farcaster-*` facet is deployed and
+ * registered with the core's `setFacet` (see contracts/src/facets.rs).
+ * FARCASTER_CONTRACT_ADDRESS is the core, which serves every method once
+ * the facets take over, two days after their registration.
  */
 
 import { execSync } from 'child_process';
@@ -15,6 +21,15 @@ import * as path from 'path';
 import { fileURLToPath } from 'url';
 import { dirname } from 'path';
 import * as dotenv from 'dotenv';
+import {
+  type Artifact,
+  MAX_COMPRESSED_SIZE,
+  MAX_RAW_SIZE,
+  artifactSize,
+  buildArtifact,
+  facetSelectors,
+  listArtifacts,
+} from '../scripts/stylus-artifacts';
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = dirname(__filename);
@@ -29,35 +44,38 @@ if (!PRIVATE_KEY) {
   process.exit(1);
 }
 
-async function buildContract(): Promise<string> {
-  console.log('🔨 Building Farcaster Predictions contract...\n');
-
-  try {
-    // Build the contract
-    execSync('cargo build --release --target wasm32-unknown-unknown --lib', {
-      cwd: __dirname,
-      stdio: 'inherit',
-    });
+interface BuiltArtifact {
+  artifact: Artifact;
+  wasmPath: string;
+}
 
-    const wasmPath = path.join(
-      __dirname,
-      'target/wasm32-unknown-unknown/release/farcaster_predictions_stylus.wasm'
-    );
+async function buildArtifacts(): Promise<BuiltArtifact[]> {
+  console.log('🔨 Building Farcaster Predictions core and facets...\n');
 
-    if (!fs.existsSync(wasmPath)) {
-      throw new Error('WASM file not found after build');
+  try {
+    const built = listArtifacts()
+      .filter((artifact) => artifact.contract === 'farcaster')
+      .map((artifact) => ({ artifact, wasmPath: buildArtifact(artifact) }));
+
+    // An oversized facet would only be rejected after the core is deployed,
+    // so check every artifact first
+    for (const { artifact, wasmPath } of built) {
+      const size = artifactSize(wasmPath);
+      if (size.compressed > MAX_COMPRESSED_SIZE || size.raw > MAX_RAW_SIZE) {
+        throw new Error(`${artifact.name} is ${size.compressed} bytes compressed (${size.raw} raw), over the Stylus limits`);
+      }
     }
 
-    console.log('✅ Contract built successfully\n');
-    return wasmPath;
+    console.log(`✅ Built ${built.length} artifacts\n`);
+    return built;
   } catch (error) {
     console.error('❌ Failed to build contract:', error);
     throw error;
   }
 }
 
-async function deployContract(wasmPath: string): Promise<string> {
-  console.log('🚀 Deploying to Arbitrum Sepolia...\n');
+async function deployContract(name: string, wasmPath: string): Promise<string> {
+  console.log(`🚀 Deploying ${name} to Arbitrum Sepolia...\n`);
 
   try {
     const deployCmd = `cargo stylus deploy \
@@ -90,7 +108,7 @@ async function deployContract(wasmPath: string): Promise<string> {
     }
     const contractAddress = addressMatch[1];
 
-    console.log(`\n🎉 Contract deployed to: ${contractAddress}`);
+    console.log(`\n🎉 ${name} deployed to: ${contractAddress}`);
 
     return contractAddress;
   } catch (error) {
@@ -99,31 +117,86 @@ async function deployContract(wasmPath: string): Promise<string> {
   }
 }
 
-async function testContract(contractAddress: string) {
+async function initializeContract(contractAddress: string) {
+  console.log('🔧 Initializing contract...\n');
+
+  const provider = new ethers.JsonRpcProvider(ARBITRUM_SEPOLIA_RPC);
+  const wallet = new ethers.Wallet(PRIVATE_KEY!, provider);
+  const contract = new ethers.Contract(contractAddress, ['function initialize(address owner) external'], wallet);
+
+  try {
+    const tx = await contract.initialize(wallet.address);
+    const receipt = await tx.wait();
+    console.log(`✅ Contract initialized (block ${receipt.blockNumber})\n`);
+  } catch (error) {
+    console.error('❌ Failed to initialize contract:', error);
+    throw error;
+  }
+}
+
+/**
+ * Deploy every facet and register the selectors it routes with the core
+ *
+ * Returns: each facet's address by name; they serve calls once the core's
+ * facet delay (2 days) is over
+ */
+async function deployFacets(
+  contractAddress: string,
+  facets: BuiltArtifact[],
+  selectors: Map<string, string[]>,
+): Promise<Map<string, string>> {
+  const provider = new ethers.JsonRpcProvider(ARBITRUM_SEPOLIA_RPC);
+  const wallet = new ethers.Wallet(PRIVATE_KEY!, provider);
+  const contract = new ethers.Contract(
+    contractAddress,
+    ['function setFacet(address facet, bytes4[] selectors) external'],
+    wallet,
+  );
+
+  const addresses = new Map<string, string>();
+  for (const { artifact, wasmPath } of facets) {
+    const facetAddress = await deployContract(artifact.name, wasmPath);
+    try {
+      const tx = await contract.setFacet(facetAddress, selectors.get(artifact.name));
+      await tx.wait();
+      console.log(`✅ Registered ${artifact.name} for ${selectors.get(artifact.name)!.length} selectors\n`);
+    } catch (error) {
+      console.error(`❌ Failed to register ${artifact.name}:`, error);
+      throw error;
+    }
+    addresses.set(artifact.name, facetAddress);
+  }
+  return addresses;
+}
+
+async function testContract(contractAddress: string, facets: Map<string, string>, selectors: Map<string, string[]>) {
   console.log(`⚙️ Testing contract at ${contractAddress}...\n`);
 
   const provider = new ethers.JsonRpcProvider(ARBITRUM_SEPOLIA_RPC);
   const wallet = new ethers.Wallet(PRIVATE_KEY!, provider);
 
   const contractAbi = [
-    "function registerUser(bytes32 commitment) external",
-    "function storePrediction(uint256 date, bytes32 predictionHash) external",
-    "function ratePrediction(uint256 date, uint8 rating) external",
-    "function isUserRegistered(address user) external view returns (bool)",
-    "function getUserStats(address user) external view returns (uint256, uint256, uint256)",
-    "function getGlobalStats() external view returns (uint256, uint256)",
+    "function owner() external view returns (address)",
+    "function getPendingFacet(bytes4 selector) external view returns (address, uint64)",
   ];
 
   const contract = new ethers.Contract(contractAddress, contractAbi, wallet);
 
   try {
-    // Test 1: Check global stats (should be 0, 0)
-    const [totalUsers, totalPredictions] = await contract.getGlobalStats();
-    console.log(`✅ Global stats: ${totalUsers.toString()} users, ${totalPredictions.toString()} predictions\n`);
-
-    // Test 2: Check if user is registered (should be false)
-    const isRegistered = await contract.isUserRegistered(wallet.address);
-    console.log(`✅ User registered: ${isRegistered}\n`);
+    // Test 1: The deployer owns the core
+    const owner = await contract.owner();
+    console.log(`✅ Owner: ${owner}\n`);
+
+    // Test 2: Every facet is registered, and takes over after the delay
+    let effectiveAt = 0n;
+    for (const [name, facetAddress] of facets) {
+      const [pending, at] = await contract.getPendingFacet(selectors.get(name)![0]);
+      if (pending.toLowerCase() !== facetAddress.toLowerCase()) {
+        throw new Error(`${name} is not pending for its selectors`);
+      }
+      effectiveAt = at > effectiveAt ? at : effectiveAt;
+    }
+    console.log(`✅ ${facets.size} facets serve calls from ${new Date(Number(effectiveAt) * 1000).toISOString()}\n`);
 
     console.log('✅ Contract initialized and working!\n');
   } catch (error) {
@@ -144,9 +217,12 @@ async function main() {
   console.log('═══════════════════════════════════════════════════════════════════\n');
 
   try {
-    const wasmPath = await buildContract();
-    const contractAddress = await deployContract(wasmPath);
-    await testContract(contractAddress);
+    const [core, ...facets] = await buildArtifacts();
+    const selectors = facetSelectors(core.artifact, facets.map(({ artifact }) => artifact));
+    const contractAddress = await deployContract(core.artifact.name, core.wasmPath);
+    await initializeContract(contractAddress);
+    const facetAddresses = await deployFacets(contractAddress, facets, selectors);
+    await testContract(contractAddress, facetAddresses, selectors);
 
     // Save contract address to .env
     const envPath = path.resolve(__dirname, '../.env');
@@ -169,6 +245,7 @@ async function main() {
 
     console.log('📊 Contract Details:');
     console.log(`   Address: ${contractAddress}`);
+    console.log(`   Facets: ${facets.length}`);
     console.log(`   Network: Arbitrum Sepolia`);
     console.log(`   Explorer: https://sepolia.arbiscan.io/address/${contractAddress}\n`);
 
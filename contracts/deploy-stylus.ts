@@ -5,12 +5,26 @@
  * - 10-100x cheaper gas costs
  * - Better performance for cryptographic operations
  * - Memory-safe Rust implementation
+ *
+ * ChartRegistry is too large for one Stylus program, so its core is deployed
+ * and initialized first, then each `chart-registry-*` facet is deployed and
+ * registered with the core's `setFacet` (see contracts/src/facets.rs).
+ * The facets serve calls two days after their registration.
  */
 
 import { execSync } from 'child_process';
 import { ethers } from 'ethers';
 import * as fs from 'fs';
 import * as path from 'path';
+import {
+  type Artifact,
+  MAX_COMPRESSED_SIZE,
+  MAX_RAW_SIZE,
+  artifactSize,
+  buildArtifact,
+  facetSelectors,
+  listArtifacts,
+} from '../scripts/stylus-artifacts';
 
 // Configuration
 const ARBITRUM_SEPOLIA_RPC = process.env.ARBITRUM_SEPOLIA_RPC || 'https://sepolia-rollup.arbitrum.io/rpc';
@@ -27,28 +41,30 @@ interface DeploymentConfig {
   rpcUrl: string;
 }
 
-async function buildStylusContract(): Promise<string> {
-  console.log('🔨 Building Stylus contract...');
+interface BuiltArtifact {
+  artifact: Artifact;
+  wasmPath: string;
+}
+
+async function buildStylusContracts(): Promise<BuiltArtifact[]> {
+  console.log('🔨 Building Stylus core and facets...');
   
   try {
-    // Build the contract with optimizations
-    execSync('cargo build --release --target wasm32-unknown-unknown', {
-      cwd: path.join(__dirname),
-      stdio: 'inherit',
-    });
-
-    // Get the WASM file path
-    const wasmPath = path.join(
-      __dirname,
-      '../target/wasm32-unknown-unknown/release/chart_registry_stylus.wasm'
-    );
-
-    if (!fs.existsSync(wasmPath)) {
-      throw new Error('WASM file not found after build');
+    const built = listArtifacts()
+      .filter((artifact) => artifact.contract === 'chart-registry')
+      .map((artifact) => ({ artifact, wasmPath: buildArtifact(artifact) }));
+
+    // An oversized facet would only be rejected after the core is deployed,
+    // so check every artifact first
+    for (const { artifact, wasmPath } of built) {
+      const size = artifactSize(wasmPath);
+      if (size.compressed > MAX_COMPRESSED_SIZE || size.raw > MAX_RAW_SIZE) {
+        throw new Error(`${artifact.name} is ${size.compressed} bytes compressed (${size.raw} raw), over the Stylus limits`);
+      }
     }
 
-    console.log('✅ Contract built successfully');
-    return wasmPath;
+    console.log(`✅ Built ${built.length} artifacts`);
+    return built;
   } catch (error) {
     console.error('❌ Failed to build contract:', error);
     throw error;
@@ -98,15 +114,15 @@ async function initializeContract(
   const provider = new ethers.JsonRpcProvider(config.rpcUrl);
   const wallet = new ethers.Wallet(PRIVATE_KEY, provider);
 
-  // ABI for the init function
+  // ABI for the one-shot initializer
   const abi = [
-    'function init() external',
+    'function initialize(address owner) external',
   ];
 
   const contract = new ethers.Contract(contractAddress, abi, wallet);
 
   try {
-    const tx = await contract.init();
+    const tx = await contract.initialize(wallet.address);
     console.log(`  Transaction sent: ${tx.hash}`);
     
     const receipt = await tx.wait();
@@ -117,6 +133,34 @@ async function initializeContract(
   }
 }
 
+async function deployFacets(
+  contractAddress: string,
+  facets: BuiltArtifact[],
+  selectors: Map<string, string[]>,
+  config: DeploymentConfig
+): Promise<void> {
+  const provider = new ethers.JsonRpcProvider(config.rpcUrl);
+  const wallet = new ethers.Wallet(PRIVATE_KEY, provider);
+
+  const abi = [
+    'function setFacet(address facet, bytes4[] selectors) external',
+  ];
+
+  const contract = new ethers.Contract(contractAddress, abi, wallet);
+
+  for (const { artifact, wasmPath } of facets) {
+    const facetAddress = await deployStylusContract(wasmPath, config);
+    try {
+      const tx = await contract.setFacet(facetAddress, selectors.get(artifact.name));
+      await tx.wait();
+      console.log(`✅ Registered ${artifact.name} for ${selectors.get(artifact.name)!.length} selectors`);
+    } catch (error) {
+      console.error(`❌ Failed to register ${artifact.name}:`, error);
+      throw error;
+    }
+  }
+}
+
 async function verifyDeployment(
   contractAddress: string,
   config: DeploymentConfig
@@ -171,19 +215,23 @@ async function main() {
   console.log('═══════════════════════════════════════\n');
 
   try {
-    // Step 1: Build the contract
-    const wasmPath = await buildStylusContract();
+    // Step 1: Build the core and its facets
+    const [core, ...facets] = await buildStylusContracts();
+    const selectors = facetSelectors(core.artifact, facets.map(({ artifact }) => artifact));
 
-    // Step 2: Deploy the contract
-    const contractAddress = await deployStylusContract(wasmPath, config);
+    // Step 2: Deploy the core
+    const contractAddress = await deployStylusContract(core.wasmPath, config);
 
-    // Step 3: Initialize the contract
+    // Step 3: Initialize the core
     await initializeContract(contractAddress, config);
 
-    // Step 4: Verify deployment
+    // Step 4: Deploy and register the facets
+    await deployFacets(contractAddress, facets, selectors, config);
+
+    // Step 5: Verify deployment
     await verifyDeployment(contractAddress, config);
 
-    // Step 5: Save deployment info
+    // Step 6: Save deployment info
     await saveDeploymentInfo(contractAddress, network);
 
     console.log('\n✨ Deployment complete!');
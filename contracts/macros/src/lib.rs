@@ -0,0 +1,389 @@
+//! Attribute macros of the ZKAstro contracts
+//!
+//! [`macro@public_if`] makes an impl's methods entry points only in the
+//! builds selecting it, so one crate builds every facet of a contract.
+
+use convert_case::{Case, Casing};
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, FnArg, ImplItem, ImplItemFn, ItemImpl, LitStr, Meta, Pat, ReturnType, Type};
+
+/// Method attributes that only mean something to `#[public]`
+const ROUTING_ATTRIBUTES: &[&str] = &["payable", "selector", "fallback", "receive", "constructor"];
+
+/// `#[public]` on the impl in builds where the cfg predicate holds
+///
+/// Unlike `#[cfg_attr(<predicate>, public)]`, the methods may keep their
+/// `#[payable]`, `#[selector]` and other routing attributes: builds
+/// without the predicate get a plain impl with those removed, along with
+/// `#[implements(..)]` on the impl itself.
+///
+/// With the `export-abi` feature, every build also gets
+/// `__abi_<predicate>(f, types)` on the type, writing the Solidity
+/// declarations `#[public]` would export for the impl's functions and
+/// adding the structs and errors they return to `types`, so one interface
+/// can list every facet (see the crate's `abi` module). `<predicate>` is its
+/// names and strings in snake case, `feature` left out:
+/// `__abi_not_farcaster_facet` for `not(feature = "farcaster-facet")`.
+///
+/// ```ignore
+/// #[public_if(feature = "farcaster-ratings")]
+/// impl FarcasterPredictions {
+///     #[payable]
+///     pub fn tip(&mut self) { .. }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn public_if(predicate: TokenStream, item: TokenStream) -> TokenStream {
+    let predicate = parse_macro_input!(predicate as Meta);
+    let public = parse_macro_input!(item as ItemImpl);
+    expand(&predicate, public).into()
+}
+
+/// The public impl under `predicate`, and the plain one under its negation
+fn expand(predicate: &Meta, public: ItemImpl) -> proc_macro2::TokenStream {
+    let mut plain = public.clone();
+    plain
+        .attrs
+        .retain(|attr| !attr.path().is_ident("implements"));
+    for item in &mut plain.items {
+        if let ImplItem::Fn(method) = item {
+            method.attrs.retain(|attr| {
+                !ROUTING_ATTRIBUTES
+                    .iter()
+                    .any(|name| attr.path().is_ident(name))
+            });
+        }
+    }
+
+    let writer = abi_writer(predicate, &public);
+    quote! {
+        #[cfg(#predicate)]
+        #[::stylus_sdk::prelude::public]
+        #public
+
+        #[cfg(not(#predicate))]
+        #plain
+
+        #[cfg(feature = "export-abi")]
+        #writer
+    }
+}
+
+/// Name of the ABI writer of the impl under `predicate`
+fn abi_writer_name(predicate: &Meta) -> Ident {
+    fn words(tokens: proc_macro2::TokenStream, out: &mut Vec<String>) {
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => words(group.stream(), out),
+                TokenTree::Ident(ident) if ident != "feature" => out.push(ident.to_string()),
+                TokenTree::Literal(literal) => {
+                    if let Ok(lit) = syn::parse_str::<LitStr>(&literal.to_string()) {
+                        out.push(lit.value());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut out = Vec::new();
+    words(predicate.to_token_stream(), &mut out);
+    Ident::new(&format!("__abi_{}", out.join("_").to_case(Case::Snake)), Span::call_site())
+}
+
+/// Solidity name `#[public]` exports `method` under
+fn sol_name(method: &ImplItemFn) -> String {
+    let mut name = None;
+    for attr in &method.attrs {
+        if attr.path().is_ident("selector") {
+            let _ = attr.parse_nested_meta(|meta| {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            });
+        }
+    }
+    name.unwrap_or_else(|| method.sig.ident.to_string().to_case(Case::Camel))
+}
+
+/// The inherent impl holding the ABI writer of `public`
+///
+/// Mirrors the SDK's `#[public]` export: state mutability from the first
+/// argument (`&self` is view, `&mut self` writes, none is pure) or
+/// `#[payable]`, and no fallback, receive or constructor.
+fn abi_writer(predicate: &Meta, public: &ItemImpl) -> proc_macro2::TokenStream {
+    let name = abi_writer_name(predicate);
+    let functions = public.items.iter().filter_map(|item| match item {
+        ImplItem::Fn(method) => Some(method),
+        _ => None,
+    });
+    let functions = functions.filter(|method| {
+        !method.attrs.iter().any(|attr| {
+            ["fallback", "receive", "constructor"]
+                .iter()
+                .any(|name| attr.path().is_ident(name))
+        })
+    });
+    let functions = functions.map(|method| {
+        let sol_name = sol_name(method);
+        let mut inputs = method.sig.inputs.iter().peekable();
+        let purity = match inputs.peek() {
+            Some(FnArg::Receiver(receiver)) => Some(receiver.mutability.is_some()),
+            Some(FnArg::Typed(arg)) => match &*arg.ty {
+                Type::Reference(reference) => Some(reference.mutability.is_some()),
+                _ => None,
+            },
+            None => None,
+        };
+        if purity.is_some() {
+            inputs.next();
+        }
+        let payable = method.attrs.iter().any(|attr| attr.path().is_ident("payable"));
+        let purity = match (payable, purity) {
+            (true, _) => " payable",
+            (false, Some(true)) => "",
+            (false, Some(false)) => " view",
+            (false, None) => " pure",
+        };
+        let args = inputs.enumerate().map(|(i, input)| {
+            let FnArg::Typed(arg) = input else { unreachable!("receiver after the first argument") };
+            let comma = if i > 0 { ", " } else { "" };
+            let mut pat = &*arg.pat;
+            while let Pat::Reference(reference) = pat {
+                pat = &reference.pat;
+            }
+            let name = match pat {
+                Pat::Ident(ident) => ident.ident.to_string(),
+                _ => String::new(),
+            };
+            let ty = &arg.ty;
+            quote! {
+                write!(
+                    f,
+                    "{}{}{}",
+                    #comma,
+                    <#ty as ::stylus_sdk::abi::AbiType>::EXPORT_ABI_ARG,
+                    ::stylus_sdk::abi::export::underscore_if_sol(#name)
+                )?;
+            }
+        });
+        let returns = match &method.sig.output {
+            ReturnType::Default => None,
+            ReturnType::Type(_, ty) => Some(quote! {
+                ::stylus_sdk::abi::internal::write_solidity_returns::<#ty>(f)?;
+                types.extend(<#ty as ::stylus_sdk::abi::export::internal::InnerTypes>::inner_types());
+            }),
+        };
+        quote! {
+            write!(f, "\n    function {}(", #sol_name)?;
+            #(#args)*
+            write!(f, ") external{}", #purity)?;
+            #returns
+            writeln!(f, ";")?;
+        }
+    });
+
+    let (impl_generics, _, where_clause) = public.generics.split_for_impl();
+    let self_ty = &public.self_ty;
+    quote! {
+        impl #impl_generics #self_ty #where_clause {
+            /// Solidity declarations of the functions `#[public]` exports
+            /// from the impl under this predicate
+            #[doc(hidden)]
+            pub fn #name(
+                f: &mut ::core::fmt::Formatter<'_>,
+                types: &mut ::std::vec::Vec<::stylus_sdk::abi::export::internal::InnerType>,
+            ) -> ::core::fmt::Result {
+                #(#functions)*
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{parse_quote, File, Item};
+
+    /// The public and plain impls `public_if(predicate)` expands `item` to
+    fn expand_impls(predicate: Meta, item: ItemImpl) -> (ItemImpl, ItemImpl) {
+        let (public, plain, _) = expand_all(predicate, item);
+        (public, plain)
+    }
+
+    /// Every impl `public_if(predicate)` expands `item` to, the ABI
+    /// writer's last
+    fn expand_all(predicate: Meta, item: ItemImpl) -> (ItemImpl, ItemImpl, ItemImpl) {
+        let file: File = syn::parse2(expand(&predicate, item)).unwrap();
+        match <[Item; 3]>::try_from(file.items).ok().unwrap() {
+            [Item::Impl(public), Item::Impl(plain), Item::Impl(writer)] => (public, plain, writer),
+            items => panic!("expected three impls, got {}", quote!(#(#items)*)),
+        }
+    }
+
+    /// Each attribute of `attrs` as written
+    fn attributes(attrs: &[syn::Attribute]) -> Vec<String> {
+        attrs.iter().map(|attr| quote!(#attr).to_string()).collect()
+    }
+
+    /// `items` as written
+    fn tokens(items: &[ImplItem]) -> String {
+        quote!(#(#items)*).to_string()
+    }
+
+    #[test]
+    fn test_public_under_predicate() {
+        let item: ItemImpl = parse_quote! {
+            impl Contract {
+                #[payable]
+                pub fn tip(&mut self) {}
+            }
+        };
+        let (public, plain) = expand_impls(parse_quote!(feature = "ratings"), item.clone());
+
+        assert_eq!(
+            attributes(&public.attrs),
+            [
+                quote!(#[cfg(feature = "ratings")]).to_string(),
+                quote!(#[::stylus_sdk::prelude::public]).to_string(),
+            ]
+        );
+        assert_eq!(tokens(&public.items), tokens(&item.items));
+        assert_eq!(attributes(&plain.attrs), [quote!(#[cfg(not(feature = "ratings"))]).to_string()]);
+    }
+
+    #[test]
+    fn test_plain_impl_drops_routing_attributes() {
+        let item: ItemImpl = parse_quote! {
+            #[implements(TestHooks)]
+            #[allow(clippy::too_many_arguments)]
+            impl Contract {
+                /// Docs stay
+                #[payable]
+                #[selector(name = "tipAll")]
+                #[inline]
+                pub fn tip(&mut self) {}
+
+                #[fallback]
+                pub fn other(&mut self, calldata: &[u8]) -> ArbResult { Ok(calldata.to_vec()) }
+
+                #[receive]
+                pub fn receive(&mut self) -> Result<(), Vec<u8>> { Ok(()) }
+
+                #[constructor]
+                pub fn constructor(&mut self) {}
+            }
+        };
+        let (public, plain) = expand_impls(parse_quote!(not(feature = "facet")), item.clone());
+
+        // The public impl keeps everything for `#[public]` to read
+        assert_eq!(attributes(&public.attrs[2..]), attributes(&item.attrs));
+        assert_eq!(tokens(&public.items), tokens(&item.items));
+
+        // The plain one keeps the rest, docs included
+        assert_eq!(
+            attributes(&plain.attrs),
+            [
+                quote!(#[cfg(not(not(feature = "facet")))]).to_string(),
+                quote!(#[allow(clippy::too_many_arguments)]).to_string(),
+            ]
+        );
+        let methods: Vec<Vec<String>> = plain
+            .items
+            .iter()
+            .map(|item| match item {
+                ImplItem::Fn(method) => attributes(&method.attrs),
+                _ => panic!("expected methods only"),
+            })
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                vec![quote!(#[doc = r" Docs stay"]).to_string(), quote!(#[inline]).to_string()],
+                vec![],
+                vec![],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_other_items_untouched() {
+        let item: ItemImpl = parse_quote! {
+            impl Contract {
+                #[payable]
+                const FEE: u64 = 1;
+                type Output = ();
+            }
+        };
+        let (_, plain) = expand_impls(parse_quote!(feature = "fees"), item.clone());
+        assert_eq!(tokens(&plain.items), tokens(&item.items));
+    }
+
+    #[test]
+    fn test_abi_writer_name() {
+        let name = |predicate: Meta| abi_writer_name(&predicate).to_string();
+        assert_eq!(name(parse_quote!(feature = "farcaster-ratings")), "__abi_farcaster_ratings");
+        assert_eq!(name(parse_quote!(not(feature = "farcaster-facet"))), "__abi_not_farcaster_facet");
+        assert_eq!(name(parse_quote!(all(test, feature = "gas-bench"))), "__abi_all_test_gas_bench");
+    }
+
+    #[test]
+    fn test_abi_writer_follows_public() {
+        let item: ItemImpl = parse_quote! {
+            impl Contract {
+                pub fn owner(&self) -> Address { self.owner.get() }
+                pub fn set_owner(&mut self, owner: Address) {}
+                #[payable]
+                pub fn tip(&mut self, &note: &Note, (a, b): (u8, u8)) {}
+                #[selector(name = "hashV2")]
+                pub fn hash_v2(data: Bytes) -> B256 { keccak256(data) }
+                pub fn verify(storage: &impl TopLevelStorage, proof: U256) {}
+                #[fallback]
+                pub fn other(&mut self, calldata: &[u8]) -> ArbResult { Ok(calldata.to_vec()) }
+                #[receive]
+                pub fn receive(&mut self) -> Result<(), Vec<u8>> { Ok(()) }
+            }
+        };
+        let (_, _, writer) = expand_all(parse_quote!(feature = "owners"), item);
+        let writer = quote!(#writer).to_string();
+        let statements = |expected: proc_macro2::TokenStream| {
+            let expected = expected.to_string();
+            assert!(writer.contains(&expected), "{expected} missing from {writer}");
+        };
+
+        assert!(writer.starts_with(&quote!(#[cfg(feature = "export-abi")] impl Contract).to_string()));
+        statements(quote!(pub fn __abi_owners));
+        statements(quote! {
+            write!(f, "\n    function {}(", "owner")?;
+            write!(f, ") external{}", " view")?;
+            ::stylus_sdk::abi::internal::write_solidity_returns::<Address>(f)?;
+        });
+        statements(quote! {
+            write!(f, "\n    function {}(", "setOwner")?;
+            write!(f, "{}{}{}", "", <Address as ::stylus_sdk::abi::AbiType>::EXPORT_ABI_ARG, ::stylus_sdk::abi::export::underscore_if_sol("owner"))?;
+            write!(f, ") external{}", "")?;
+        });
+        // Names are read through references; other patterns stay unnamed
+        statements(quote! {
+            write!(f, "\n    function {}(", "tip")?;
+            write!(f, "{}{}{}", "", <&Note as ::stylus_sdk::abi::AbiType>::EXPORT_ABI_ARG, ::stylus_sdk::abi::export::underscore_if_sol("note"))?;
+            write!(f, "{}{}{}", ", ", <(u8, u8) as ::stylus_sdk::abi::AbiType>::EXPORT_ABI_ARG, ::stylus_sdk::abi::export::underscore_if_sol(""))?;
+            write!(f, ") external{}", " payable")?;
+        });
+        statements(quote! {
+            write!(f, "\n    function {}(", "hashV2")?;
+            write!(f, "{}{}{}", "", <Bytes as ::stylus_sdk::abi::AbiType>::EXPORT_ABI_ARG, ::stylus_sdk::abi::export::underscore_if_sol("data"))?;
+            write!(f, ") external{}", " pure")?;
+        });
+        // A storage argument is no parameter, and sets the mutability
+        statements(quote! {
+            write!(f, "\n    function {}(", "verify")?;
+            write!(f, "{}{}{}", "", <U256 as ::stylus_sdk::abi::AbiType>::EXPORT_ABI_ARG, ::stylus_sdk::abi::export::underscore_if_sol("proof"))?;
+            write!(f, ") external{}", " view")?;
+        });
+        assert!(!writer.contains("\"other\""));
+        assert!(!writer.contains("\"receive\""));
+    }
+}
@@ -0,0 +1,522 @@
+//! Solidity ABI export (`cargo stylus export-abi`)
+//!
+//! `#[public]` exports a contract's functions and the errors of its
+//! `SolidityError` return types, but not its events, the errors it
+//! ABI-encodes into `Vec<u8>` reverts or the structs it returns. Those are
+//! declared through
+//! [`sol_declarations!`], which keeps their Solidity text next to the
+//! `sol!` types, and [`interface`] adds them to the generated interface.
+//!
+//! The exported contract is the entrypoint chosen by the crate features:
+//! `farcaster` (also the default), `chart-registry`, `ephemeris-oracle` or
+//! `reader`. FarcasterPredictions and ChartRegistry are deployed as a core
+//! plus facets (see `facets`), and callers see them as one contract, so
+//! their interface is [`combined`]: the functions of the core and of every
+//! facet, whichever build exports it. `abi --routed` prints only the
+//! functions of the build itself, which the deploy scripts register facets
+//! for.
+
+/// `sol!` events, errors and structs whose Solidity declarations are also
+/// kept in `$decls`, for the exported ABI
+macro_rules! sol_declarations {
+    (@decls [$($decl:expr,)*]) => {
+        [$($decl),*]
+    };
+    (@decls [$($decl:expr,)*] $(#[$attr:meta])* struct $name:ident { $($fields:tt)* } $($rest:tt)*) => {
+        sol_declarations!(@decls [
+            $($decl,)*
+            concat!("struct ", stringify!($name), " { ", stringify!($($fields)*), " }"),
+        ] $($rest)*)
+    };
+    (@decls [$($decl:expr,)*] $(#[$attr:meta])* $kind:ident $name:ident ( $($params:tt)* ) ; $($rest:tt)*) => {
+        sol_declarations!(@decls [
+            $($decl,)*
+            concat!(stringify!($kind), " ", stringify!($name), "(", stringify!($($params)*), ");"),
+        ] $($rest)*)
+    };
+    ($vis:vis const $decls:ident; $($items:tt)*) => {
+        alloy_sol_types::sol! {
+            $($items)*
+        }
+
+        $vis const $decls: &[&str] = &sol_declarations!(@decls [] $($items)*);
+    };
+}
+
+/// Declarations a contract's exported interface needs beyond `#[public]`
+pub trait Declarations {
+    /// Event and error declarations, in Solidity
+    const DECLARATIONS: &'static [&'static str];
+}
+
+impl Declarations for crate::FarcasterPredictions {
+    const DECLARATIONS: &'static [&'static str] = crate::DECLARATIONS;
+}
+
+impl Declarations for crate::chart_registry::ChartRegistry {
+    const DECLARATIONS: &'static [&'static str] = crate::chart_registry::DECLARATIONS;
+}
+
+impl Declarations for crate::ephemeris::EphemerisOracle {
+    const DECLARATIONS: &'static [&'static str] = crate::ephemeris::DECLARATIONS;
+}
+
+impl Declarations for crate::reader::ZkAstroReader {
+    const DECLARATIONS: &'static [&'static str] = crate::reader::DECLARATIONS;
+}
+
+/// `AbiType` of a `sol!` struct returned from `#[public]` functions
+///
+/// `#[derive(AbiType)]` exports a struct return without its `memory`
+/// location, which isn't valid Solidity.
+macro_rules! abi_struct {
+    (in $module:ident: $($ty:ident),*) => {$(
+        abi_struct!(@impl $ty, crate::$module::$ty);
+    )*};
+    ($($ty:ident),*) => {$(
+        abi_struct!(@impl $ty, crate::$ty);
+    )*};
+    (@impl $name:ident, $ty:ty) => {
+        impl stylus_sdk::abi::AbiType for $ty {
+            type SolType = Self;
+
+            const ABI: stylus_sdk::abi::ConstString = stylus_sdk::abi::ConstString::new(stringify!($name));
+            const EXPORT_ABI_ARG: stylus_sdk::abi::ConstString = Self::ABI.concat(stylus_sdk::abi::ConstString::new(" calldata"));
+            const EXPORT_ABI_RET: stylus_sdk::abi::ConstString = Self::ABI.concat(stylus_sdk::abi::ConstString::new(" memory"));
+        }
+
+        // Declared through `DECLARATIONS`, not by the SDK
+        #[cfg(feature = "export-abi")]
+        impl stylus_sdk::abi::export::internal::InnerTypes for $ty {}
+    };
+}
+
+abi_struct!(UserStats, UserExport);
+abi_struct!(in reader: UserStats, ProfileScreen);
+
+#[cfg(feature = "export-abi")]
+pub use export::*;
+
+#[cfg(feature = "export-abi")]
+mod export {
+    use core::fmt;
+    use core::marker::PhantomData;
+
+    use std::collections::HashSet;
+
+    use stylus_sdk::abi::export::internal::InnerType;
+    use stylus_sdk::abi::GenerateAbi;
+
+    use super::Declarations;
+
+    /// Writes the Solidity declarations of some of a contract's functions,
+    /// adding the structs and errors they return to the list
+    pub type FunctionsAbi = fn(&mut fmt::Formatter<'_>, &mut Vec<InnerType>) -> fmt::Result;
+
+    /// Builds making up a contract
+    pub trait Facets {
+        /// Functions of the core and of each facet, from `public_if`;
+        /// empty for a contract deployed whole
+        ///
+        /// A facet missing here fails the check against the SDK's
+        /// interfaces below.
+        const FUNCTIONS: &'static [FunctionsAbi];
+    }
+
+    impl Facets for crate::FarcasterPredictions {
+        const FUNCTIONS: &'static [FunctionsAbi] = &[
+            Self::__abi_not_farcaster_facet,
+            Self::__abi_farcaster_admin,
+            Self::__abi_farcaster_anchored_claims,
+            Self::__abi_farcaster_anchoring,
+            Self::__abi_farcaster_astro,
+            Self::__abi_farcaster_audits,
+            Self::__abi_farcaster_batch_finalization,
+            Self::__abi_farcaster_blinded,
+            Self::__abi_farcaster_blinded_reads,
+            Self::__abi_farcaster_blinded_reveals,
+            Self::__abi_farcaster_chart_predictions,
+            Self::__abi_farcaster_community,
+            Self::__abi_farcaster_config,
+            Self::__abi_farcaster_confirmations,
+            Self::__abi_farcaster_dimension_ratings,
+            Self::__abi_farcaster_dimension_stats,
+            Self::__abi_farcaster_dimensions,
+            Self::__abi_farcaster_endorsements,
+            Self::__abi_farcaster_fees,
+            Self::__abi_farcaster_finalization,
+            Self::__abi_farcaster_history,
+            Self::__abi_farcaster_imports,
+            Self::__abi_farcaster_lucky,
+            Self::__abi_farcaster_pair_ratings,
+            Self::__abi_farcaster_pairs,
+            Self::__abi_farcaster_payloads,
+            Self::__abi_farcaster_pedersen,
+            Self::__abi_farcaster_predictions,
+            Self::__abi_farcaster_proof_checks,
+            Self::__abi_farcaster_proof_variants,
+            Self::__abi_farcaster_proofs,
+            Self::__abi_farcaster_proposals,
+            Self::__abi_farcaster_pruning,
+            Self::__abi_farcaster_queries,
+            Self::__abi_farcaster_ratings,
+            Self::__abi_farcaster_registration,
+            Self::__abi_farcaster_schnorr,
+            Self::__abi_farcaster_sealed_ratings,
+            Self::__abi_farcaster_settings,
+            Self::__abi_farcaster_stats,
+            Self::__abi_farcaster_users,
+        ];
+    }
+
+    impl Facets for crate::chart_registry::ChartRegistry {
+        const FUNCTIONS: &'static [FunctionsAbi] = &[
+            Self::__abi_not_chart_registry_facet,
+            Self::__abi_chart_registry_charts,
+            Self::__abi_chart_registry_inputs,
+            Self::__abi_chart_registry_maintenance,
+            Self::__abi_chart_registry_positions,
+            Self::__abi_chart_registry_registration,
+            Self::__abi_chart_registry_verification,
+            Self::__abi_chart_registry_versions,
+        ];
+    }
+
+    impl Facets for crate::ephemeris::EphemerisOracle {
+        const FUNCTIONS: &'static [FunctionsAbi] = &[];
+    }
+
+    impl Facets for crate::reader::ZkAstroReader {
+        const FUNCTIONS: &'static [FunctionsAbi] = &[];
+    }
+
+    #[cfg(not(any(feature = "chart-registry", feature = "ephemeris-oracle", feature = "reader")))]
+    type Entrypoint = crate::FarcasterPredictions;
+    #[cfg(feature = "chart-registry")]
+    type Entrypoint = crate::chart_registry::ChartRegistry;
+    #[cfg(feature = "ephemeris-oracle")]
+    type Entrypoint = crate::ephemeris::EphemerisOracle;
+    #[cfg(feature = "reader")]
+    type Entrypoint = crate::reader::ZkAstroReader;
+
+    const LICENSE: &str = "MIT-OR-APACHE-2.0";
+    const PRAGMA: &str = "pragma solidity ^0.8.23;";
+
+    pub(crate) struct Abi<T>(pub(crate) PhantomData<T>);
+
+    impl<T: GenerateAbi> fmt::Display for Abi<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            T::fmt_abi(f)
+        }
+    }
+
+    /// Interface of `T` with the functions the writers write, laid out
+    /// like `T::fmt_abi`
+    pub(crate) struct FunctionsInterface<T>(pub(crate) &'static [FunctionsAbi], pub(crate) PhantomData<T>);
+
+    impl<T: GenerateAbi> fmt::Display for FunctionsInterface<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "interface I{}  {{", T::NAME)?;
+            let mut types = Vec::new();
+            for functions in self.0 {
+                functions(f, &mut types)?;
+            }
+            let mut seen = HashSet::new();
+            for item in types {
+                if seen.insert(item.id) {
+                    writeln!(f, "\n    {}", item.name)?;
+                }
+            }
+            writeln!(f, "}}")
+        }
+    }
+
+    /// Solidity interface of `T`, including its events and encoded errors
+    ///
+    /// The functions are the ones this build routes.
+    pub fn interface<T: GenerateAbi + Declarations>() -> String {
+        with_declarations::<T>(Abi::<T>(PhantomData).to_string())
+    }
+
+    /// Solidity interface of `T` as deployed: [`interface`], with the
+    /// functions of its core and all its facets when it has some
+    pub fn combined<T: GenerateAbi + Declarations + Facets>() -> String {
+        if T::FUNCTIONS.is_empty() {
+            return interface::<T>();
+        }
+        with_declarations::<T>(FunctionsInterface::<T>(T::FUNCTIONS, PhantomData).to_string())
+    }
+
+    /// `abi` with `T`'s declarations added at the end of the interface
+    fn with_declarations<T: Declarations>(mut abi: String) -> String {
+        let end = abi.rfind('}').expect("interface is closed");
+        // `stringify!` keeps line breaks of multi-line declarations
+        let declarations: String = T::DECLARATIONS
+            .iter()
+            .map(|decl| format!("\n    {}\n", decl.split_whitespace().collect::<Vec<_>>().join(" ")))
+            .collect();
+        abi.insert_str(end, &declarations);
+        abi
+    }
+
+    /// Print the entrypoint's ABI, as `cargo stylus export-abi` expects
+    ///
+    /// Accepts the SDK's `abi [--license L] [--pragma P]` and
+    /// `constructor` arguments, plus `--routed` for the [`interface`] of
+    /// this build instead of the [`combined`] one.
+    pub fn print_from_args() {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if args.first().map(String::as_str) == Some("constructor") {
+            return stylus_sdk::abi::export::print_from_args::<Entrypoint>();
+        }
+        let option = |name: &str| args.windows(2).find(|pair| pair[0] == name).map(|pair| pair[1].clone());
+        let license = option("--license").unwrap_or_else(|| LICENSE.into());
+        let pragma = option("--pragma").unwrap_or_else(|| PRAGMA.into());
+
+        println!("/**");
+        println!(" * This file was automatically generated by Stylus and represents a Rust program.");
+        println!(" * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).");
+        println!(" */");
+        println!();
+        println!("// SPDX-License-Identifier: {license}");
+        println!("{pragma}");
+        println!();
+        if args.iter().any(|arg| arg == "--routed") {
+            print!("{}", interface::<Entrypoint>());
+        } else {
+            print!("{}", combined::<Entrypoint>());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_sol_types::{SolError, SolEvent};
+
+    /// Canonical signature of a Solidity declaration: `Name(type,...)`
+    fn signature(decl: &str) -> String {
+        let (head, params) = decl.trim_end_matches(");").split_once('(').unwrap();
+        let name = head.split_whitespace().last().unwrap();
+        let types: Vec<&str> = params
+            .split(',')
+            .filter_map(|param| param.split_whitespace().next())
+            .collect();
+        format!("{name}({})", types.join(","))
+    }
+
+    #[test]
+    fn test_declarations_match_sol_types() {
+        let mut signatures: Vec<String> = [
+            crate::DECLARATIONS,
+            crate::chart_registry::DECLARATIONS,
+            crate::ephemeris::DECLARATIONS,
+            crate::reader::DECLARATIONS,
+        ]
+        .concat()
+        .iter()
+        .filter(|decl| !decl.starts_with("struct "))
+        .map(|decl| signature(decl))
+        .collect();
+        signatures.sort();
+
+        let mut expected = vec![
+            crate::UnsupportedProofVersion::SIGNATURE,
+            crate::ProofExpired::SIGNATURE,
+            crate::DateTooOld::SIGNATURE,
+            crate::StaleAnchor::SIGNATURE,
+            crate::NotInBeta::SIGNATURE,
+            crate::InputTooLarge::SIGNATURE,
+            crate::IncorrectFee::SIGNATURE,
+            crate::Overflow::SIGNATURE,
+            crate::UserAdmitted::SIGNATURE,
+            crate::FidLinked::SIGNATURE,
+            crate::DailyRootAnchored::SIGNATURE,
+            crate::AnchoredPredictionClaimed::SIGNATURE,
+            crate::PointsCredited::SIGNATURE,
+            crate::PointsDeducted::SIGNATURE,
+            crate::PointWeightsUpdated::SIGNATURE,
+            crate::PredictionPruned::SIGNATURE,
+            crate::PredictionStored::SIGNATURE,
+            crate::PredictionAttributed::SIGNATURE,
+            crate::PredictionEndorsed::SIGNATURE,
+            crate::PredictionProposed::SIGNATURE,
+            crate::PredictionConfirmed::SIGNATURE,
+            crate::PredictionRejected::SIGNATURE,
+            crate::PredictionFinalized::SIGNATURE,
+            crate::DuplicateSuppressed::SIGNATURE,
+            crate::AutoPaused::SIGNATURE,
+            crate::HookFailed::SIGNATURE,
+            crate::Sunset::SIGNATURE,
+            crate::DimensionRegistered::SIGNATURE,
+            crate::BlindedRatingAdded::SIGNATURE,
+            crate::BlindedAggregateRevealed::SIGNATURE,
+            crate::RatingCommitted::SIGNATURE,
+            crate::RatingRevealed::SIGNATURE,
+            crate::RatingCommitmentExpired::SIGNATURE,
+            crate::HistoryRootFinalized::SIGNATURE,
+            crate::CountersRepaired::SIGNATURE,
+            crate::DailyCheckpoint::SIGNATURE,
+            crate::SponsorshipFunded::SIGNATURE,
+            crate::RegistrationSponsored::SIGNATURE,
+            crate::SponsorshipReclaimed::SIGNATURE,
+            crate::PairJoined::SIGNATURE,
+            crate::PairPredictionStored::SIGNATURE,
+            crate::PairPredictionRated::SIGNATURE,
+            crate::chart_registry::ChartCreated::SIGNATURE,
+            crate::chart_registry::ChartVerified::SIGNATURE,
+            crate::chart_registry::ChartInputRevealed::SIGNATURE,
+            crate::chart_registry::Sunset::SIGNATURE,
+            crate::chart_registry::HookFailed::SIGNATURE,
+            crate::chart_registry::ChartRepaired::SIGNATURE,
+            crate::chart_registry::ChartUpdated::SIGNATURE,
+            crate::chart_registry::DuplicateSuppressed::SIGNATURE,
+            crate::chart_registry::FacetScheduled::SIGNATURE,
+            crate::ephemeris::PositionsPublished::SIGNATURE,
+        ];
+        expected.sort();
+        assert_eq!(signatures, expected);
+    }
+
+    // `cargo test --features export-abi`
+    #[cfg(feature = "export-abi")]
+    #[test]
+    fn test_interfaces_include_functions_events_and_errors() {
+        use super::{combined, interface};
+        use crate::chart_registry::ChartRegistry;
+        use crate::ephemeris::EphemerisOracle;
+        use crate::reader::ZkAstroReader;
+        use crate::FarcasterPredictions;
+
+        // The core and every facet, with their events and errors
+        let abi = combined::<FarcasterPredictions>();
+        assert!(abi.starts_with("interface IFarcasterPredictions"));
+        for item in [
+            "function multicall(bytes[] memory calls) external returns (bytes[] memory);",
+            "function setFacet(address facet, bytes4[] memory selectors) external;",
+            "function getFacet(bytes4 selector) external view returns (address);",
+            "function registerUserWithProof(bytes32 commitment, bytes32 proof, bytes calldata nonce, uint64[] memory positions, uint8 scheme_version, uint8 encoding, uint64 epoch) external payable;",
+            "function exportUser(address user, uint256 date_offset, uint256 date_limit) external view returns (UserExport memory);",
+            "event UserAdmitted(address indexed user, bytes32 indexed root);",
+            "error ProofExpired(uint64 epoch, uint64 currentEpoch);",
+            "struct UserStats { uint256 totalPredictions; uint256 totalRatings; uint256 averageRatingX10; }",
+        ] {
+            assert!(abi.contains(item), "{item} missing from\n{abi}");
+        }
+        // No test hooks: the default build has none, and the SDK doesn't
+        // export `#[implements]` traits (the router tests check selectors)
+        assert!(!abi.contains("function test"));
+        // The build itself routes only the core's
+        let routed = interface::<FarcasterPredictions>();
+        assert!(routed.contains("function setFacet(address facet, bytes4[] memory selectors) external;"));
+        assert!(!routed.contains("function registerUserWithProof"));
+        assert!(routed.contains("event UserAdmitted(address indexed user, bytes32 indexed root);"));
+
+        let abi = combined::<ChartRegistry>();
+        assert!(abi.starts_with("interface IChartRegistry"));
+        for item in [
+            "function initialize(address owner) external;",
+            "function setFacet(address facet, bytes4[] memory selectors) external;",
+            "function registerChart(string calldata chart_id, bytes32 chart_hash, address user, bool zk_verified) external;",
+            "event FacetScheduled(bytes4 indexed selector, address indexed facet, uint64 effective_at);",
+            "event ChartCreated(string indexed chart_id, bytes32 indexed chart_hash, address indexed user, uint256 timestamp, bool zk_verified);",
+            "error ChartAlreadyExists();",
+            "error InvalidChartPositions();",
+        ] {
+            assert!(abi.contains(item), "{item} missing from\n{abi}");
+        }
+        assert!(!abi.contains("function test"));
+        assert!(!interface::<ChartRegistry>().contains("function registerChart("));
+
+        assert_eq!(combined::<EphemerisOracle>(), interface::<EphemerisOracle>());
+        let abi = interface::<EphemerisOracle>();
+        assert!(abi.contains("function publishPositions(uint256 date, bytes32[2] calldata packed_positions) external;"));
+        assert!(abi.contains("event PositionsPublished(uint256 indexed date, address indexed updater, bytes32[2] packedPositions);"));
+        assert!(abi.trim_end().ends_with('}'));
+
+        let abi = interface::<ZkAstroReader>();
+        assert!(abi.contains("function getHomeScreen(address user) external view returns (UserStats memory, bytes32, uint8, bool, uint8);"));
+        assert!(abi.contains("struct ProfileScreen { UserStats stats;"));
+    }
+
+    /// Selectors of the functions in `abi`, structs encoded as tuples
+    #[cfg(feature = "export-abi")]
+    fn function_selectors(abi: &str) -> std::collections::BTreeSet<[u8; 4]> {
+        use std::collections::HashMap;
+        use stylus_sdk::alloy_primitives::keccak256;
+
+        let lines: Vec<&str> = abi.lines().map(str::trim).collect();
+        let structs: HashMap<&str, Vec<&str>> = lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("struct "))
+            .map(|line| {
+                let (name, fields) = line.trim_end_matches('}').split_once('{').unwrap();
+                let types = fields.split(';').filter_map(|field| field.split_whitespace().next()).collect();
+                (name.trim(), types)
+            })
+            .collect();
+        fn canonical(ty: &str, structs: &HashMap<&str, Vec<&str>>) -> String {
+            let base = ty.split('[').next().unwrap();
+            match structs.get(base) {
+                Some(fields) => {
+                    let fields: Vec<String> = fields.iter().map(|field| canonical(field, structs)).collect();
+                    format!("({}){}", fields.join(","), &ty[base.len()..])
+                }
+                None => ty.to_string(),
+            }
+        }
+        lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("function "))
+            .map(|line| {
+                let (name, rest) = line.split_once('(').unwrap();
+                let (params, _) = rest.split_once(") external").unwrap();
+                let types: Vec<String> = params
+                    .split(", ")
+                    .filter_map(|param| param.split_whitespace().next())
+                    .map(|ty| canonical(ty, &structs))
+                    .collect();
+                let signature = format!("{name}({})", types.join(","));
+                keccak256(signature)[..4].try_into().unwrap()
+            })
+            .collect()
+    }
+
+    // The combined interfaces are what the SDK's interfaces, routed through
+    // the real contracts in their tests, declare
+    #[cfg(feature = "export-abi")]
+    #[test]
+    fn test_combined_interfaces_match_sdk() {
+        use super::combined;
+        use zkastro_core::sdk::{IChartRegistry, IFarcasterPredictions};
+
+        let sdk = |selectors: &[[u8; 4]]| selectors.iter().copied().collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(
+            function_selectors(&combined::<crate::FarcasterPredictions>()),
+            sdk(IFarcasterPredictions::IFarcasterPredictionsCalls::SELECTORS)
+        );
+        assert_eq!(
+            function_selectors(&combined::<crate::chart_registry::ChartRegistry>()),
+            sdk(IChartRegistry::IChartRegistryCalls::SELECTORS)
+        );
+    }
+
+    // `public_if` writes the functions of the core as `#[public]` does
+    #[cfg(feature = "export-abi")]
+    #[test]
+    fn test_core_functions_written_as_sdk() {
+        use core::marker::PhantomData;
+
+        use super::export::FunctionsInterface;
+        use crate::chart_registry::ChartRegistry;
+        use crate::FarcasterPredictions;
+
+        assert_eq!(
+            FunctionsInterface::<FarcasterPredictions>(&[FarcasterPredictions::__abi_not_farcaster_facet], PhantomData).to_string(),
+            super::export::Abi::<FarcasterPredictions>(PhantomData).to_string()
+        );
+        assert_eq!(
+            FunctionsInterface::<ChartRegistry>(&[ChartRegistry::__abi_not_chart_registry_facet], PhantomData).to_string(),
+            super::export::Abi::<ChartRegistry>(PhantomData).to_string()
+        );
+    }
+}
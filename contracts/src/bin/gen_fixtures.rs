@@ -0,0 +1,44 @@
+//! Regenerate the cross-language proof fixtures
+//!
+//! ```bash
+//! cargo run --bin gen-fixtures --features prover [-- <output_dir>]
+//! ```
+//!
+//! Writes one JSON file per fixture (default: `tests/fixtures/`) so client
+//! teams can diff their implementations against the Rust verifier.
+
+use std::{env, fs, path::PathBuf};
+
+use farcaster_predictions_stylus::prover;
+use serde_json::json;
+
+fn main() -> std::io::Result<()> {
+    let out_dir = env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"));
+    fs::create_dir_all(&out_dir)?;
+
+    let fixtures = prover::fixtures();
+    for fixture in &fixtures {
+        let value = json!({
+            "name": fixture.name,
+            "scheme_version": fixture.proof.scheme_version,
+            "encoding": fixture.proof.encoding.name(),
+            "commitment": fixture.proof.commitment,
+            "nonce": fixture.proof.nonce,
+            "positions": fixture.positions,
+            "expected_challenge": fixture.challenge,
+            "proof": fixture.proof.proof,
+            "valid": fixture.valid,
+        });
+
+        let path = out_dir.join(format!("{}.json", fixture.name));
+        let mut contents = serde_json::to_string_pretty(&value)?;
+        contents.push('\n');
+        fs::write(&path, contents)?;
+    }
+
+    println!("Wrote {} fixtures to {}", fixtures.len(), out_dir.display());
+    Ok(())
+}
@@ -0,0 +1,2503 @@
+//! ChartRegistry - Arbitrum Stylus Implementation
+//! Stores immutable commitments of natal charts with ZK proofs
+//! 
+//! This Rust implementation provides:
+//! - 10-100x cheaper gas costs compared to Solidity
+//! - Better performance for cryptographic operations
+//! - Memory-safe handling of ZK proofs
+//!
+//! Built as the `chart_registry` module of the contracts crate; enable the
+//! `chart-registry` feature to make it the deployed entrypoint. Deployed as
+//! a core plus facets like FarcasterPredictions (see `facets`): a
+//! `chart-registry-*` feature builds that facet instead of the core.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::abi::Bytes;
+use stylus_sdk::prelude::*;
+use stylus_sdk::stylus_core::calls::{context::Call, errors::Error as CallError};
+use stylus_sdk::alloy_primitives::{keccak256, Address, FixedBytes, U256};
+use stylus_sdk::storage::{
+    StorageAddress, StorageArray, StorageBool, StorageFixedBytes, StorageMap, StorageString, StorageU256,
+    StorageVec,
+};
+
+use zkastro_core::astro::{chart_compatibility, cusps_valid, house_of, HOUSE_COUNT};
+use zkastro_core::birth_data;
+use zkastro_core::import;
+use zkastro_core::legacy_hash::legacy_chart_hash;
+use zkastro_core::packing::{pack_positions, unpack_positions};
+use zkastro_core::positions::{chart_hash, derive_chart_id, unpack_chart, PlanetPosition, CHART_BODIES};
+use zkastro_core::slots;
+use zkastro_macros::public_if;
+
+use crate::facets::{FacetSlot, Faceted};
+use crate::forwarder::{ForwardedSender, Forwarding};
+use crate::math::{self, Overflow};
+
+// Type aliases for clarity
+type B32 = FixedBytes<32>;
+
+/// Chart commitment structure
+#[storage]
+pub struct ChartCommitment {
+    chart_hash: StorageFixedBytes<32>,
+    user: StorageAddress,
+    timestamp: StorageU256,
+    zk_verified: StorageBool,
+    chart_id: StorageString,
+    /// Packed natal positions, see `positions::pack_chart`
+    packed_positions: StorageArray<StorageFixedBytes<32>, 2>,
+    has_positions: StorageBool,
+    /// keccak256(birth_payload || server_salt) of the backend's input,
+    /// revealed only in a dispute (zero = none)
+    input_commitment: StorageFixedBytes<32>,
+    /// House cusps 1-12, see `packing::pack_positions`
+    packed_cusps: StorageArray<StorageFixedBytes<32>, 2>,
+    has_cusps: StorageBool,
+}
+
+/// Positions and house cusps a chart had before an `update_chart`
+#[storage]
+pub struct ChartVersion {
+    packed_positions: StorageArray<StorageFixedBytes<32>, 2>,
+    packed_cusps: StorageArray<StorageFixedBytes<32>, 2>,
+    has_cusps: StorageBool,
+}
+
+/// Main ChartRegistry contract
+#[storage]
+pub struct ChartRegistry {
+    /// Mapping: chartId => ChartCommitment
+    charts: StorageMap<String, ChartCommitment>,
+    
+    /// Mapping: user => chartIds[]
+    user_charts: StorageMap<Address, StorageVec<StorageString>>,
+    
+    /// Total charts created
+    total_charts: StorageU256,
+    
+    /// Contract owner (set once by initialize)
+    owner: StorageAddress,
+    
+    /// Set once by initialize
+    initialized: StorageBool,
+    
+    /// Reserved so an upgraded implementation keeps this layout;
+    /// new fields go after the gap
+    __gap: StorageArray<StorageU256, 50>,
+    
+    /// ERC-2771 forwarder whose calls act for the sender they append
+    /// (zero = none)
+    trusted_forwarder: StorageAddress,
+    
+    /// Accounts the owner approved to reveal chart inputs
+    registrars: StorageMap<Address, StorageBool>,
+    
+    /// Set once by `sunset`, after which nothing is written
+    sunsetted: StorageBool,
+    successor: StorageAddress,
+    
+    /// Contract told of every chart that becomes verified (zero = none)
+    verification_hook: StorageAddress,
+    
+    /// Superseded versions of each updated chart, version 0 (as
+    /// registered) first; the current version is the chart itself
+    chart_versions: StorageMap<String, StorageVec<ChartVersion>>,
+    
+    /// Idempotency keys each sender already used, see `is_idempotency_key_used`
+    used_idempotency_keys: StorageMap<Address, StorageMap<B32, StorageBool>>,
+    
+    /// Facet each selector this build does not route is delegated to, and
+    /// the one taking over after `FACET_DELAY`, see `facets`
+    facets: StorageMap<FixedBytes<4>, FacetSlot>,
+    
+    /// Sender of the current forwarded call (no storage)
+    forwarded_sender: ForwardedSender,
+}
+
+// Deployed through `forwarder::entrypoint` so forwarded calls are resolved
+// before routing; builds with the SDK's `stylus-test` feature (all test
+// builds) have no `user_entrypoint` calling it
+#[cfg(feature = "chart-registry")]
+#[allow(dead_code)]
+#[entrypoint]
+fn chart_registry_entrypoint(input: Vec<u8>, host: stylus_sdk::host::VM) -> stylus_sdk::ArbResult {
+    crate::forwarder::entrypoint::<ChartRegistry>(input, host)
+}
+
+// Routing needs top-level storage, which the entrypoint function does not
+// implement
+unsafe impl TopLevelStorage for ChartRegistry {}
+
+impl Forwarding for ChartRegistry {
+    fn trusted_forwarder(&self) -> Address {
+        self.trusted_forwarder.get()
+    }
+
+    fn forwarded_sender(&self) -> &ForwardedSender {
+        &self.forwarded_sender
+    }
+}
+
+impl Faceted for ChartRegistry {
+    fn facet(&self, selector: FixedBytes<4>) -> Address {
+        self.facets.getter(selector).at(self.vm().block_timestamp())
+    }
+}
+
+/// Raw storage writes for tests that need indexes the entry points keep
+/// consistent, public only with the `test-hooks` feature (see
+/// `crate::TestHooks`)
+#[cfg(any(test, feature = "test-hooks"))]
+pub trait TestHooks {
+    /// Append `chart_id` to `user`'s chart list, whether or not the chart
+    /// exists or is listed already
+    fn test_force_chart_vector_entry(&mut self, user: Address, chart_id: String);
+    /// Overwrite entry `index` of `user`'s chart list (no-op past its end)
+    fn test_set_chart_vector_entry(&mut self, user: Address, index: U256, chart_id: String);
+    /// Overwrite the id stored in `chart_id`'s record
+    fn test_set_stored_chart_id(&mut self, chart_id: String, stored_id: String);
+}
+
+#[cfg(any(test, feature = "test-hooks"))]
+#[cfg_attr(feature = "test-hooks", public)]
+impl TestHooks for ChartRegistry {
+    fn test_force_chart_vector_entry(&mut self, user: Address, chart_id: String) {
+        self.user_charts.setter(user).grow().set_str(chart_id);
+    }
+
+    fn test_set_chart_vector_entry(&mut self, user: Address, index: U256, chart_id: String) {
+        if let Some(mut entry) = self.user_charts.setter(user).setter(index) {
+            entry.set_str(chart_id);
+        }
+    }
+
+    fn test_set_stored_chart_id(&mut self, chart_id: String, stored_id: String) {
+        self.charts.setter(chart_id).chart_id.set_str(stored_id);
+    }
+}
+
+/// Gas given to the verification hook, so a hook can't spend the gas the
+/// verification needs to finish
+const HOOK_GAS: u64 = 200_000;
+
+/// Storage layout version reported by `get_implementation_version`
+pub const IMPLEMENTATION_VERSION: u64 = 10;
+
+/// Declared slot of `charts`, the mapping `chart_slot` derives from
+pub const CHARTS_SLOT: u64 = 0;
+
+/// `audit_chart` issue: the chart is missing from its owner's chart list
+pub const ISSUE_NOT_LISTED: u8 = 1;
+
+/// `audit_chart` issue: the chart is in its owner's chart list more than
+/// once
+pub const ISSUE_LISTED_TWICE: u8 = 2;
+
+/// `audit_chart` issue: the id stored in the chart doesn't derive the key
+/// the chart is stored under
+pub const ISSUE_ID_MISMATCH: u8 = 4;
+
+/// `diff_chart_versions` reports the cusp of house `h` (1-12) under id
+/// `CUSP_ID_OFFSET + h`, clear of the body ids
+pub const CUSP_ID_OFFSET: u8 = 100;
+
+sol! {
+    #[derive(Debug, PartialEq, Eq)]
+    error ChartAlreadyExists();
+    #[derive(Debug, PartialEq, Eq)]
+    error InvalidChartHash();
+    #[derive(Debug, PartialEq, Eq)]
+    error InvalidUserAddress();
+    #[derive(Debug, PartialEq, Eq)]
+    error ChartDoesNotExist();
+    #[derive(Debug, PartialEq, Eq)]
+    error InvalidChartPositions();
+    #[derive(Debug, PartialEq, Eq)]
+    error AlreadyInitialized();
+    #[derive(Debug, PartialEq, Eq)]
+    error InvalidOwner();
+    #[derive(Debug, PartialEq, Eq)]
+    error NotOwner();
+    #[derive(Debug, PartialEq, Eq)]
+    error ChartHasNoPositions();
+    #[derive(Debug, PartialEq, Eq)]
+    error NotRegistrar();
+    #[derive(Debug, PartialEq, Eq)]
+    error NoInputCommitment();
+    #[derive(Debug, PartialEq, Eq)]
+    error InputMismatch();
+    #[derive(Debug, PartialEq, Eq)]
+    error ContractSunset();
+    #[derive(Debug, PartialEq, Eq)]
+    error InvalidHouseCusps();
+    #[derive(Debug, PartialEq, Eq)]
+    error ChartHasNoHouses();
+    #[derive(Debug, PartialEq, Eq)]
+    error BodyNotInChart();
+    #[derive(Debug, PartialEq, Eq)]
+    error LengthMismatch();
+    #[derive(Debug, PartialEq, Eq)]
+    error TooManyEntries();
+    #[derive(Debug, PartialEq, Eq)]
+    error ImportRejected();
+    #[derive(Debug, PartialEq, Eq)]
+    error NotChartOwner();
+    #[derive(Debug, PartialEq, Eq)]
+    error VersionOutOfRange();
+
+    // Declared with `sol!` and called through `vm().call` rather than a
+    // `sol_interface!` stub, for the reasons given at `crate::IPredictionHook`
+    /// Contract notified of every chart that becomes verified, keyed by
+    /// keccak256 of the chart id
+    interface IChartVerificationHook {
+        function onChartVerified(bytes32 chartKey, address owner) external;
+    }
+}
+
+/// Errors
+#[derive(SolidityError, Debug, PartialEq, Eq)]
+pub enum ChartRegistryError {
+    ChartAlreadyExists(ChartAlreadyExists),
+    InvalidChartHash(InvalidChartHash),
+    InvalidUserAddress(InvalidUserAddress),
+    ChartDoesNotExist(ChartDoesNotExist),
+    InvalidChartPositions(InvalidChartPositions),
+    AlreadyInitialized(AlreadyInitialized),
+    InvalidOwner(InvalidOwner),
+    NotOwner(NotOwner),
+    ChartHasNoPositions(ChartHasNoPositions),
+    NotRegistrar(NotRegistrar),
+    NoInputCommitment(NoInputCommitment),
+    InputMismatch(InputMismatch),
+    ContractSunset(ContractSunset),
+    InvalidHouseCusps(InvalidHouseCusps),
+    ChartHasNoHouses(ChartHasNoHouses),
+    BodyNotInChart(BodyNotInChart),
+    LengthMismatch(LengthMismatch),
+    TooManyEntries(TooManyEntries),
+    ImportRejected(ImportRejected),
+    NotChartOwner(NotChartOwner),
+    VersionOutOfRange(VersionOutOfRange),
+    Overflow(Overflow),
+}
+
+// Event definitions, also exported in the ABI
+sol_declarations! {
+    pub(crate) const DECLARATIONS;
+
+    event ChartCreated(
+        string indexed chart_id,
+        bytes32 indexed chart_hash,
+        address indexed user,
+        uint256 timestamp,
+        bool zk_verified
+    );
+
+    event ChartVerified(
+        string indexed chart_id,
+        bytes32 chart_hash
+    );
+
+    event ChartInputRevealed(
+        string indexed chart_id,
+        address indexed registrar,
+        bytes payload,
+        bytes32 salt
+    );
+
+    event Sunset(
+        uint256 timestamp,
+        address indexed successor
+    );
+
+    event HookFailed(
+        address indexed hook,
+        bytes32 indexed chart_key,
+        bytes reason
+    );
+
+    event ChartRepaired(
+        bytes32 indexed chart_key,
+        uint8 issue_flags
+    );
+
+    event ChartUpdated(
+        string indexed chart_id,
+        bytes32 chart_hash,
+        uint256 version
+    );
+
+    event DuplicateSuppressed(
+        address indexed sender,
+        bytes32 indexed key
+    );
+
+    event FacetScheduled(
+        bytes4 indexed selector,
+        address indexed facet,
+        uint64 effective_at
+    );
+}
+
+#[public_if(not(feature = "chart-registry-facet"))]
+#[cfg_attr(feature = "test-hooks", implements(TestHooks))]
+impl ChartRegistry {
+    /// Initialize the contract (once), setting its owner
+    pub fn initialize(&mut self, owner: Address) -> Result<(), ChartRegistryError> {
+        if self.initialized.get() {
+            return Err(ChartRegistryError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if owner == Address::ZERO {
+            return Err(ChartRegistryError::InvalidOwner(InvalidOwner {}));
+        }
+
+        self.initialized.set(true);
+        self.owner.set(owner);
+        Ok(())
+    }
+
+    /// Get the contract owner
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Set the ERC-2771 forwarder trusted to relay calls (zero = none)
+    /// 
+    /// Calls from the forwarder act for the address appended to their
+    /// calldata; see `forwarder`.
+    pub fn set_trusted_forwarder(&mut self, forwarder: Address) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+        if self.msg_sender() != self.owner.get() {
+            return Err(ChartRegistryError::NotOwner(NotOwner {}));
+        }
+        self.trusted_forwarder.set(forwarder);
+        Ok(())
+    }
+
+    /// Get the trusted forwarder (zero when none)
+    pub fn get_trusted_forwarder(&self) -> Address {
+        self.trusted_forwarder.get()
+    }
+
+    /// Whether `forwarder` is the trusted forwarder (ERC-2771)
+    pub fn is_trusted_forwarder(&self, forwarder: Address) -> bool {
+        forwarder != Address::ZERO && forwarder == self.trusted_forwarder.get()
+    }
+
+    /// Approve or revoke an account revealing chart inputs
+    pub fn set_registrar(&mut self, registrar: Address, approved: bool) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+        if self.msg_sender() != self.owner.get() {
+            return Err(ChartRegistryError::NotOwner(NotOwner {}));
+        }
+        self.registrars.setter(registrar).set(approved);
+        Ok(())
+    }
+
+    /// Whether `registrar` may reveal chart inputs
+    pub fn is_registrar(&self, registrar: Address) -> bool {
+        self.registrars.get(registrar)
+    }
+
+    /// Storage layout version of this implementation
+    /// 
+    /// Bumped by every release that changes storage, so a proxy upgrade
+    /// can check it is moving forward.
+    pub fn get_implementation_version(&self) -> U256 {
+        U256::from(IMPLEMENTATION_VERSION)
+    }
+
+    /// Freeze every write for good, pointing to the registry replacing
+    /// this one (zero = none)
+    /// 
+    /// Reads keep working. Irreversible: every write path, the owner's
+    /// included, fails with `ContractSunset` from then on.
+    pub fn sunset(&mut self, successor: Address) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+        if self.msg_sender() != self.owner.get() {
+            return Err(ChartRegistryError::NotOwner(NotOwner {}));
+        }
+        self.sunsetted.set(true);
+        self.successor.set(successor);
+        log(self.vm(), Sunset { timestamp: U256::from(self.vm().block_timestamp()), successor });
+        Ok(())
+    }
+
+    /// Whether the registry was sunset
+    pub fn is_sunset(&self) -> bool {
+        self.sunsetted.get()
+    }
+
+    /// Registry replacing this one (zero when none)
+    pub fn successor(&self) -> Address {
+        self.successor.get()
+    }
+
+    /// Set the contract notified of verified charts (zero = none)
+    /// 
+    /// It gets `IChartVerificationHook.onChartVerified` whenever a chart
+    /// is registered as verified or marked verified, with at most
+    /// `HOOK_GAS`; a hook that reverts is logged in `HookFailed` and
+    /// doesn't stop the verification.
+    pub fn set_verification_hook(&mut self, hook: Address) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+        if self.msg_sender() != self.owner.get() {
+            return Err(ChartRegistryError::NotOwner(NotOwner {}));
+        }
+        self.verification_hook.set(hook);
+        Ok(())
+    }
+
+    /// Get the verification hook (zero when none)
+    pub fn get_verification_hook(&self) -> Address {
+        self.verification_hook.get()
+    }
+
+    /// Delegate calls with each of `selectors` to `facet` (zero = revert
+    /// them instead) from `FACET_DELAY` seconds on, owner only
+    /// 
+    /// The facet runs on this registry's storage, so it must be a build of
+    /// this contract (a `chart-registry-*` feature) of the same
+    /// implementation version; see `facets` for the trust model. Until the
+    /// delay is over the current facet keeps the selector, and a change
+    /// still pending is replaced.
+    pub fn set_facet(&mut self, facet: Address, selectors: Vec<FixedBytes<4>>) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+        if self.msg_sender() != self.owner.get() {
+            return Err(ChartRegistryError::NotOwner(NotOwner {}));
+        }
+        let now = self.vm().block_timestamp();
+        for selector in selectors {
+            let effective_at = self.facets.setter(selector).schedule(facet, now);
+            log(self.vm(), FacetScheduled { selector, facet, effective_at });
+        }
+        Ok(())
+    }
+
+    /// Get the facet calls with `selector` are delegated to (zero = none)
+    pub fn get_facet(&self, selector: FixedBytes<4>) -> Address {
+        self.facet(selector)
+    }
+
+    /// Get the facet registered to take over `selector`, and when
+    /// 
+    /// Returns: (zero, 0) when no change is pending
+    pub fn get_pending_facet(&self, selector: FixedBytes<4>) -> (Address, u64) {
+        self.facets.getter(selector).pending(self.vm().block_timestamp())
+    }
+
+    /// Delegate calls to methods outside the core to their facet, and
+    /// revert with no data when none is set
+    #[fallback]
+    pub fn delegate_to_facet(&mut self, calldata: &[u8]) -> stylus_sdk::ArbResult {
+        self.delegate(calldata).unwrap_or_else(|| Err(Vec::new()))
+    }
+}
+
+#[public_if(feature = "chart-registry-registration")]
+impl ChartRegistry {
+    /// Register a new chart commitment
+    /// 
+    /// A chart registered as verified is reported to the verification
+    /// hook, see `set_verification_hook`.
+    /// 
+    /// # Arguments
+    /// * `chart_id` - Unique chart identifier
+    /// * `chart_hash` - Hash of chart data (including ZK proof)
+    /// * `user` - Chart owner address
+    /// * `zk_verified` - Whether ZK proof was verified
+    pub fn register_chart(
+        &mut self,
+        chart_id: String,
+        chart_hash: B32,
+        user: Address,
+        zk_verified: bool,
+    ) -> Result<(), ChartRegistryError> {
+        let timestamp = U256::from(self.vm().block_timestamp());
+        self.create_chart(chart_id.clone(), chart_hash, user, zk_verified, timestamp)?;
+        if zk_verified {
+            self.notify_verified(&chart_id, user);
+        }
+        Ok(())
+    }
+
+    /// Chart id derived from its owner and hash, see
+    /// `positions::derive_chart_id`
+    pub fn derive_chart_id(&self, user: Address, chart_hash: B32) -> String {
+        derive_chart_id(user, chart_hash.0)
+    }
+
+    /// Register a chart under its derived id
+    /// 
+    /// The same (user, hash) always gets the same id, so a retried
+    /// registration fails with `ChartAlreadyExists` instead of minting a
+    /// second chart.
+    /// 
+    /// # Returns
+    /// * The chart id
+    pub fn register_chart_derived(
+        &mut self,
+        chart_hash: B32,
+        user: Address,
+        zk_verified: bool,
+    ) -> Result<String, ChartRegistryError> {
+        let chart_id = derive_chart_id(user, chart_hash.0);
+        self.register_chart(chart_id.clone(), chart_hash, user, zk_verified)?;
+        Ok(chart_id)
+    }
+}
+
+#[public_if(feature = "chart-registry-positions")]
+impl ChartRegistry {
+    /// Register a chart from its packed positions
+    /// 
+    /// The chart hash is recomputed on-chain as
+    /// `keccak256(positions_digest || salt)`, so the stored hash and
+    /// positions always agree.
+    /// 
+    /// # Arguments
+    /// * `chart_id` - Unique chart identifier
+    /// * `packed` - Positions packed with `positions::pack_chart`
+    /// * `salt` - Salt hiding the positions in the chart hash
+    pub fn register_chart_with_positions(
+        &mut self,
+        chart_id: String,
+        packed: [B32; 2],
+        salt: B32,
+    ) -> Result<(), ChartRegistryError> {
+        let invalid = |_| ChartRegistryError::InvalidChartPositions(InvalidChartPositions {});
+        let positions = unpack_chart(&packed).map_err(invalid)?;
+        let chart_hash = chart_hash(&positions, salt.0).map_err(invalid)?;
+
+        let sender = self.msg_sender();
+        self.register_chart(chart_id.clone(), B32::from(chart_hash), sender, false)?;
+
+        let mut commitment = self.charts.setter(chart_id);
+        for (i, word) in packed.into_iter().enumerate() {
+            if let Some(mut slot) = commitment.packed_positions.setter(i) {
+                slot.set(word);
+            }
+        }
+        commitment.has_positions.set(true);
+
+        Ok(())
+    }
+
+    /// Register a chart from its packed positions and house cusps
+    /// 
+    /// Same as `register_chart_with_positions`, also storing the cusps so
+    /// `get_house_placement` can place the chart's bodies. The chart hash
+    /// covers the positions only.
+    /// 
+    /// # Arguments
+    /// * `cusps` - Centidegree cusps of houses 1-12, packed with
+    ///   `packing::pack_positions`; they must follow each other
+    ///   counterclockwise around the zodiac exactly once
+    pub fn register_chart_with_houses(
+        &mut self,
+        chart_id: String,
+        packed: [B32; 2],
+        cusps: [B32; 2],
+        salt: B32,
+    ) -> Result<(), ChartRegistryError> {
+        if !Self::house_cusps(&cusps).is_some_and(|cusps| cusps_valid(&cusps)) {
+            return Err(ChartRegistryError::InvalidHouseCusps(InvalidHouseCusps {}));
+        }
+        self.register_chart_with_positions(chart_id.clone(), packed, salt)?;
+
+        let mut commitment = self.charts.setter(chart_id);
+        for (i, word) in cusps.into_iter().enumerate() {
+            if let Some(mut slot) = commitment.packed_cusps.setter(i) {
+                slot.set(word);
+            }
+        }
+        commitment.has_cusps.set(true);
+
+        Ok(())
+    }
+
+    /// Register a chart with a commitment to the input it was computed
+    /// from
+    /// 
+    /// The input stays off-chain; in a dispute a registrar reveals it with
+    /// `reveal_input`. The commitment is set here only, so it can't be
+    /// swapped after the fact.
+    /// 
+    /// # Arguments
+    /// * `input_commitment` - keccak256(birth_payload || server_salt), or
+    ///   zero for none
+    pub fn register_chart_with_input(
+        &mut self,
+        chart_id: String,
+        chart_hash: B32,
+        user: Address,
+        zk_verified: bool,
+        input_commitment: B32,
+    ) -> Result<(), ChartRegistryError> {
+        let timestamp = U256::from(self.vm().block_timestamp());
+        self.create_chart(chart_id.clone(), chart_hash, user, zk_verified, timestamp)?;
+        self.charts.setter(chart_id.clone()).input_commitment.set(input_commitment);
+        if zk_verified {
+            self.notify_verified(&chart_id, user);
+        }
+        Ok(())
+    }
+}
+
+#[public_if(feature = "chart-registry-inputs")]
+impl ChartRegistry {
+    /// Get a chart's input commitment (zero when none)
+    pub fn get_input_commitment(&self, chart_id: String) -> B32 {
+        self.charts.getter(chart_id).input_commitment.get()
+    }
+
+    /// Reveal the input a chart was computed from, settling a dispute
+    /// 
+    /// Only approved registrars may reveal. The payload must match the
+    /// chart's input commitment; it is logged in `ChartInputRevealed`,
+    /// not stored.
+    pub fn reveal_input(
+        &mut self,
+        chart_id: String,
+        birth_payload: Bytes,
+        server_salt: B32,
+    ) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+        let registrar = self.msg_sender();
+        if !self.registrars.get(registrar) {
+            return Err(ChartRegistryError::NotRegistrar(NotRegistrar {}));
+        }
+
+        let commitment = self.charts.getter(chart_id.clone());
+        if commitment.timestamp.get().is_zero() {
+            return Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}));
+        }
+        let input_commitment = commitment.input_commitment.get();
+        if input_commitment == B32::ZERO {
+            return Err(ChartRegistryError::NoInputCommitment(NoInputCommitment {}));
+        }
+        if keccak256([birth_payload.as_slice(), server_salt.as_slice()].concat()) != input_commitment {
+            return Err(ChartRegistryError::InputMismatch(InputMismatch {}));
+        }
+
+        log(
+            self.vm(),
+            ChartInputRevealed {
+                chart_id: chart_key_from_string(&chart_id),
+                registrar,
+                payload: birth_payload.0.into(),
+                salt: server_salt,
+            },
+        );
+        Ok(())
+    }
+
+    /// Get the packed positions of a chart
+    /// 
+    /// # Returns
+    /// * Tuple of (has_positions, packed positions)
+    pub fn get_chart_positions(&self, chart_id: String) -> (bool, [B32; 2]) {
+        let commitment = self.charts.getter(chart_id);
+        let word = |i: usize| commitment.packed_positions.get(i).unwrap_or_default();
+        (commitment.has_positions.get(), [word(0), word(1)])
+    }
+
+    /// Get the packed house cusps of a chart
+    /// 
+    /// # Returns
+    /// * Tuple of (has_cusps, packed cusps)
+    pub fn get_house_cusps(&self, chart_id: String) -> (bool, [B32; 2]) {
+        let commitment = self.charts.getter(chart_id);
+        let word = |i: usize| commitment.packed_cusps.get(i).unwrap_or_default();
+        (commitment.has_cusps.get(), [word(0), word(1)])
+    }
+
+    /// House (1-12) a body of a chart registered with houses is in
+    /// 
+    /// # Arguments
+    /// * `body` - Body id, see `positions` (0 = Sun ... 10 = Pluto)
+    pub fn get_house_placement(&self, chart_id: String, body: u8) -> Result<u8, ChartRegistryError> {
+        let positions = unpack_chart(&self.stored_positions(chart_id.clone())?)
+            .map_err(|_| ChartRegistryError::InvalidChartPositions(InvalidChartPositions {}))?;
+        let (has_cusps, packed_cusps) = self.get_house_cusps(chart_id);
+        if !has_cusps {
+            return Err(ChartRegistryError::ChartHasNoHouses(ChartHasNoHouses {}));
+        }
+        // Validated at registration
+        let cusps = Self::house_cusps(&packed_cusps)
+            .ok_or(ChartRegistryError::InvalidHouseCusps(InvalidHouseCusps {}))?;
+        let position = positions
+            .iter()
+            .find(|position| position.body == body)
+            .ok_or(ChartRegistryError::BodyNotInChart(BodyNotInChart {}))?;
+        Ok(house_of(position.longitude_centideg as u64, &cusps))
+    }
+}
+
+#[public_if(feature = "chart-registry-versions")]
+impl ChartRegistry {
+    /// Replace the positions, and house cusps if it has any, of one of
+    /// the sender's charts registered with positions
+    /// 
+    /// The chart keeps its id. Its hash is recomputed as at registration,
+    /// and it stays unverified until marked again. What it had before is
+    /// kept as the previous version, see `diff_chart_versions`.
+    /// 
+    /// # Arguments
+    /// * `packed` - New positions, of the same bodies as the chart's
+    /// * `cusps` - New cusps, zero exactly when the chart has no houses
+    pub fn update_chart(
+        &mut self,
+        chart_id: String,
+        packed: [B32; 2],
+        cusps: [B32; 2],
+        salt: B32,
+    ) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+        let invalid = |_| ChartRegistryError::InvalidChartPositions(InvalidChartPositions {});
+        let current = unpack_chart(&self.stored_positions(chart_id.clone())?).map_err(invalid)?;
+        let commitment = self.charts.getter(chart_id.clone());
+        if commitment.user.get() != self.msg_sender() {
+            return Err(ChartRegistryError::NotChartOwner(NotChartOwner {}));
+        }
+        let positions = unpack_chart(&packed).map_err(invalid)?;
+        // Bodies are distinct on both sides, so equal counts and every old
+        // body kept mean the same set
+        let same_bodies = positions.len() == current.len()
+            && current.iter().all(|old| positions.iter().any(|position| position.body == old.body));
+        if !same_bodies {
+            return Err(ChartRegistryError::InvalidChartPositions(InvalidChartPositions {}));
+        }
+        let has_cusps = commitment.has_cusps.get();
+        let cusps_valid = if has_cusps {
+            Self::house_cusps(&cusps).is_some_and(|cusps| cusps_valid(&cusps))
+        } else {
+            cusps == [B32::ZERO; 2]
+        };
+        if !cusps_valid {
+            return Err(ChartRegistryError::InvalidHouseCusps(InvalidHouseCusps {}));
+        }
+        let chart_hash = B32::from(chart_hash(&positions, salt.0).map_err(invalid)?);
+
+        let (_, old_packed) = self.get_chart_positions(chart_id.clone());
+        let (_, old_cusps) = self.get_house_cusps(chart_id.clone());
+        let mut versions = self.chart_versions.setter(chart_id.clone());
+        let mut previous = versions.grow();
+        set_words(&mut previous.packed_positions, old_packed);
+        set_words(&mut previous.packed_cusps, old_cusps);
+        previous.has_cusps.set(has_cusps);
+        let version = U256::from(versions.len());
+
+        let mut commitment = self.charts.setter(chart_id.clone());
+        set_words(&mut commitment.packed_positions, packed);
+        set_words(&mut commitment.packed_cusps, cusps);
+        commitment.chart_hash.set(chart_hash);
+        commitment.zk_verified.set(false);
+
+        log(self.vm(), ChartUpdated { chart_id: chart_key_from_string(&chart_id), chart_hash, version });
+        Ok(())
+    }
+
+    /// Current version of a chart: how many times it was updated
+    pub fn get_chart_version(&self, chart_id: String) -> U256 {
+        U256::from(self.chart_versions.getter(chart_id).len())
+    }
+
+    /// What changed between two versions of a chart registered with
+    /// positions
+    /// 
+    /// Version 0 is the chart as registered, `get_chart_version` the
+    /// current one. Only bodies and cusps whose values differ are
+    /// reported, bodies in `version_a`'s order, then cusps by house.
+    /// 
+    /// # Returns
+    /// * (id, longitude in `version_a`, longitude in `version_b`, whether
+    ///   the retrograde flag changed) per change; a cusp's id is
+    ///   `CUSP_ID_OFFSET` plus its house, and it is never retrograde
+    pub fn diff_chart_versions(
+        &self,
+        chart_id: String,
+        version_a: U256,
+        version_b: U256,
+    ) -> Result<Vec<(u8, u64, u64, bool)>, ChartRegistryError> {
+        self.stored_positions(chart_id.clone())?;
+        let (positions_a, cusps_a) = self.chart_version(&chart_id, version_a)?;
+        let (positions_b, cusps_b) = self.chart_version(&chart_id, version_b)?;
+
+        // `update_chart` keeps a chart's bodies, so every version lists
+        // the same ones
+        let mut diff = Vec::new();
+        for a in positions_a {
+            let Some(b) = positions_b.iter().find(|b| b.body == a.body) else {
+                continue;
+            };
+            if a != *b {
+                let (old, new) = (a.longitude_centideg as u64, b.longitude_centideg as u64);
+                diff.push((a.body, old, new, a.is_retrograde != b.is_retrograde));
+            }
+        }
+        if let (Some(cusps_a), Some(cusps_b)) = (cusps_a, cusps_b) {
+            for (house, (a, b)) in (1..=HOUSE_COUNT).zip(cusps_a.into_iter().zip(cusps_b)) {
+                if a != b {
+                    diff.push((CUSP_ID_OFFSET + house, a, b, false));
+                }
+            }
+        }
+        Ok(diff)
+    }
+
+    /// Compatibility of two charts that registered their positions
+    /// 
+    /// Combines the sun signs' compatibility with the aspects between the
+    /// charts; see `astro::chart_compatibility`.
+    /// 
+    /// # Returns
+    /// * Tuple of (score 0-100, aspect_count)
+    pub fn synastry_score(&self, chart_id_a: String, chart_id_b: String) -> Result<(u8, u8), ChartRegistryError> {
+        let packed_a = self.stored_positions(chart_id_a)?;
+        let packed_b = self.stored_positions(chart_id_b)?;
+        chart_compatibility(&packed_a, &packed_b)
+            .map_err(|_| ChartRegistryError::InvalidChartPositions(InvalidChartPositions {}))
+    }
+}
+
+#[public_if(feature = "chart-registry-verification")]
+impl ChartRegistry {
+    /// Verify a chart commitment matches provided data
+    /// 
+    /// # Arguments
+    /// * `chart_id` - Chart identifier
+    /// * `chart_hash` - Hash to verify
+    /// 
+    /// # Returns
+    /// * `bool` - Whether the hash matches
+    pub fn verify_chart(
+        &self,
+        chart_id: String,
+        chart_hash: B32,
+    ) -> bool {
+        let commitment = self.charts.getter(chart_id);
+        commitment.chart_hash.get() == chart_hash
+    }
+
+    /// Check packed birth data against a commitment to it
+    /// 
+    /// For dispute and reveal flows: `packed_birth` must be the 16-byte
+    /// encoding of `birth_data::BirthData`, in range, and
+    /// `keccak256(packed_birth || salt)` must equal `commitment`. A chart's
+    /// input commitment made this way is revealed with the same bytes.
+    pub fn verify_birth_commitment(commitment: B32, packed_birth: Bytes, salt: B32) -> bool {
+        birth_data::verify_commitment(commitment.0, &packed_birth, salt.0)
+    }
+
+    /// Verify a chart registered by the legacy JavaScript backend
+    /// 
+    /// Rebuilds the `JSON.stringify({ planets, asc, mc, zkProof })` bytes
+    /// it hashed, see `legacy_hash`, and compares their keccak256 with the
+    /// stored hash.
+    /// 
+    /// # Arguments
+    /// * `chart_id` - Chart identifier
+    /// * `planets` - Centidegree longitudes of sun, moon, mercury, venus,
+    ///   mars, jupiter and saturn, in that order
+    /// * `asc`, `mc` - Centidegree angles
+    /// * `zk_proof` - Proof string the chart was registered with
+    /// 
+    /// # Returns
+    /// * `bool` - Whether the chart exists and its hash matches
+    pub fn verify_chart_legacy(
+        &self,
+        chart_id: String,
+        planets: Vec<i64>,
+        asc: i64,
+        mc: i64,
+        zk_proof: String,
+    ) -> bool {
+        let Ok(planets) = <[i64; 7]>::try_from(planets) else {
+            return false;
+        };
+        let commitment = self.charts.getter(chart_id);
+        !commitment.timestamp.get().is_zero()
+            && commitment.chart_hash.get() == B32::from(legacy_chart_hash(&planets, asc, mc, &zk_proof))
+    }
+}
+
+#[public_if(feature = "chart-registry-charts")]
+impl ChartRegistry {
+    /// Get chart commitment details
+    /// 
+    /// # Arguments
+    /// * `chart_id` - Chart identifier
+    /// 
+    /// # Returns
+    /// * Tuple of (chart_hash, user, timestamp, zk_verified, chart_id)
+    pub fn get_chart(
+        &self,
+        chart_id: String,
+    ) -> (B32, Address, U256, bool, String) {
+        let commitment = self.charts.getter(chart_id.clone());
+        
+        (
+            commitment.chart_hash.get(),
+            commitment.user.get(),
+            commitment.timestamp.get(),
+            commitment.zk_verified.get(),
+            chart_id,
+        )
+    }
+
+    /// Storage slot where `chart_id`'s commitment starts, for verifying
+    /// it with a storage proof (see `slots`)
+    /// 
+    /// The chart hash is that slot, the owner the next one and the
+    /// registration time the one after.
+    pub fn chart_slot(chart_id: String) -> B32 {
+        B32::from(slots::bytes_mapping_slot(chart_id.as_bytes(), slots::base_slot(CHARTS_SLOT)))
+    }
+
+    /// Key of `chart_id` in events, the verification hook and
+    /// FarcasterPredictions' chart attribution, see `chart_key_from_string`
+    /// 
+    /// Lets off-chain tools check their own derivation.
+    pub fn debug_chart_key(chart_id: String) -> B32 {
+        chart_key_from_string(&chart_id)
+    }
+
+    /// Get all chart IDs for a user
+    /// 
+    /// # Arguments
+    /// * `user` - User address
+    /// 
+    /// # Returns
+    /// * Array of chart IDs
+    pub fn get_user_charts(&self, user: Address) -> Vec<String> {
+        let user_chart_list = self.user_charts.getter(user);
+        let len = user_chart_list.len();
+        
+        let mut charts = Vec::new();
+        for i in 0..len {
+            if let Some(chart_id) = user_chart_list.getter(i) {
+                charts.push(chart_id.get_string());
+            }
+        }
+        
+        charts
+    }
+
+    /// Mark a chart as ZK verified
+    /// 
+    /// A chart already verified is left alone: no `ChartVerified` event
+    /// and no hook call.
+    /// 
+    /// # Arguments
+    /// * `chart_id` - Chart identifier
+    pub fn mark_as_verified(
+        &mut self,
+        chart_id: String,
+    ) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+
+        // Check if chart exists
+        let chart = self.charts.getter(chart_id.clone());
+        if chart.timestamp.get().is_zero() {
+            return Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}));
+        }
+        if chart.zk_verified.get() {
+            return Ok(());
+        }
+
+        // Update verification status
+        let mut commitment = self.charts.setter(chart_id.clone());
+        commitment.zk_verified.set(true);
+
+        // Emit event
+        let chart_hash = commitment.chart_hash.get();
+        let user = commitment.user.get();
+        log(
+            self.vm(),
+            ChartVerified {
+                chart_id: chart_key_from_string(&chart_id),
+                chart_hash,
+            },
+        );
+
+        self.notify_verified(&chart_id, user);
+        Ok(())
+    }
+
+    /// Get total number of charts
+    pub fn total_charts(&self) -> U256 {
+        self.total_charts.get()
+    }
+
+    /// Check if a chart is ZK verified
+    pub fn is_zk_verified(&self, chart_id: String) -> bool {
+        self.charts.getter(chart_id).zk_verified.get()
+    }
+}
+
+#[public_if(feature = "chart-registry-maintenance")]
+impl ChartRegistry {
+    /// Check a chart's secondary indexes against its record
+    /// 
+    /// # Returns
+    /// * Tuple of (consistent, issue_flags), the flags an OR of the
+    ///   `ISSUE_*` constants
+    pub fn audit_chart(&self, chart_id: String) -> Result<(bool, u8), ChartRegistryError> {
+        let issues = self.chart_issues(&chart_id)?;
+        Ok((issues == 0, issues))
+    }
+
+    /// Rebuild a chart's secondary indexes from its record (owner only)
+    /// 
+    /// Lists the chart exactly once in its owner's chart list, keeping
+    /// the list's order, and rewrites the id stored in the chart. Emits
+    /// `ChartRepaired` when anything was fixed.
+    pub fn repair_chart(&mut self, chart_id: String) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+        if self.msg_sender() != self.owner.get() {
+            return Err(ChartRegistryError::NotOwner(NotOwner {}));
+        }
+        let issues = self.chart_issues(&chart_id)?;
+        if issues == 0 {
+            return Ok(());
+        }
+
+        if issues & (ISSUE_NOT_LISTED | ISSUE_LISTED_TWICE) != 0 {
+            let user = self.charts.getter(chart_id.clone()).user.get();
+            let mut listed = false;
+            let kept: Vec<String> = self
+                .get_user_charts(user)
+                .into_iter()
+                .filter(|id| *id != chart_id || !core::mem::replace(&mut listed, true))
+                .collect();
+            let mut user_chart_list = self.user_charts.setter(user);
+            for (i, id) in kept.iter().enumerate() {
+                if let Some(mut slot) = user_chart_list.setter(i) {
+                    slot.set_str(id);
+                }
+            }
+            while user_chart_list.len() > kept.len() {
+                user_chart_list.erase_last();
+            }
+            if !listed {
+                user_chart_list.grow().set_str(&chart_id);
+            }
+        }
+        if issues & ISSUE_ID_MISMATCH != 0 {
+            self.charts.setter(chart_id.clone()).chart_id.set_str(&chart_id);
+        }
+
+        log(self.vm(), ChartRepaired { chart_key: chart_key_from_string(&chart_id), issue_flags: issues });
+        Ok(())
+    }
+
+    /// Import charts registered elsewhere, e.g. in the registry this one
+    /// replaces (owner only)
+    /// 
+    /// Each chart is stored as `register_chart` stores an unverified
+    /// chart, registered at its given timestamp, and emits `ChartCreated`
+    /// with it. Every entry is checked first, see `import`. With `dry_run`
+    /// the statuses are returned and nothing is written. Otherwise the
+    /// batch is written only when every entry is `import::OK`, and fails
+    /// with `ImportRejected` when one isn't.
+    /// 
+    /// A nonzero `idempotency_key` the sender already used makes a write
+    /// a no-op returning no statuses, see `is_idempotency_key_used`. Dry
+    /// runs ignore the key.
+    /// 
+    /// # Arguments
+    /// * `chart_ids`, `chart_hashes`, `users`, `timestamps` - One chart per
+    ///   index, at most `import::MAX_IMPORT_ENTRIES`
+    /// 
+    /// # Returns
+    /// * The status of each entry, in input order
+    pub fn import_charts(
+        &mut self,
+        chart_ids: Vec<String>,
+        chart_hashes: Vec<B32>,
+        users: Vec<Address>,
+        timestamps: Vec<U256>,
+        dry_run: bool,
+        idempotency_key: B32,
+    ) -> Result<Vec<u8>, ChartRegistryError> {
+        self.only_live()?;
+        if self.msg_sender() != self.owner.get() {
+            return Err(ChartRegistryError::NotOwner(NotOwner {}));
+        }
+        if dry_run {
+            return self.check_chart_import(&chart_ids, &chart_hashes, &users, &timestamps);
+        }
+        let mut statuses = Vec::new();
+        self.idempotent(idempotency_key, |registry| {
+            statuses = registry.check_chart_import(&chart_ids, &chart_hashes, &users, &timestamps)?;
+            if statuses.iter().any(|status| *status != import::OK) {
+                return Err(ChartRegistryError::ImportRejected(ImportRejected {}));
+            }
+            for (i, chart_id) in chart_ids.into_iter().enumerate() {
+                registry.create_chart(chart_id, chart_hashes[i], users[i], false, timestamps[i])?;
+            }
+            Ok(())
+        })?;
+        Ok(statuses)
+    }
+
+    /// Whether `sender` already used `key` for a write
+    /// 
+    /// A write whose key its sender already used succeeds without doing
+    /// anything and logs `DuplicateSuppressed`. A zero key is never
+    /// recorded.
+    pub fn is_idempotency_key_used(&self, sender: Address, key: B32) -> bool {
+        self.used_idempotency_keys.getter(sender).get(key)
+    }
+}
+
+impl ChartRegistry {
+    /// Store a new chart commitment registered at `timestamp`, see
+    /// `register_chart`
+    fn create_chart(
+        &mut self,
+        chart_id: String,
+        chart_hash: B32,
+        user: Address,
+        zk_verified: bool,
+        timestamp: U256,
+    ) -> Result<(), ChartRegistryError> {
+        self.only_live()?;
+
+        // Check if chart already exists (timestamp will be 0 if not)
+        if !self.charts.getter(chart_id.clone()).timestamp.get().is_zero() {
+            return Err(ChartRegistryError::ChartAlreadyExists(ChartAlreadyExists {}));
+        }
+        
+        if chart_hash == B32::ZERO {
+            return Err(ChartRegistryError::InvalidChartHash(InvalidChartHash {}));
+        }
+        
+        if user == Address::ZERO {
+            return Err(ChartRegistryError::InvalidUserAddress(InvalidUserAddress {}));
+        }
+
+        // Create commitment
+        let mut commitment = self.charts.setter(chart_id.clone());
+        commitment.chart_hash.set(chart_hash);
+        commitment.user.set(user);
+        commitment.timestamp.set(timestamp);
+        commitment.zk_verified.set(zk_verified);
+        commitment.chart_id.set_str(&chart_id);
+
+        // Add to user's charts
+        let mut user_chart_list = self.user_charts.setter(user);
+        let mut new_chart = user_chart_list.grow();
+        new_chart.set_str(&chart_id);
+
+        // Increment total
+        let current_total = math::checked_inc(self.total_charts.get(), math::FIELD_TOTAL_CHARTS)?;
+        self.total_charts.set(current_total);
+
+        // Emit event
+        log(
+            self.vm(),
+            ChartCreated {
+                chart_id: chart_key_from_string(&chart_id),
+                chart_hash,
+                user,
+                timestamp,
+                zk_verified,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Tell the verification hook, if any, that a chart became verified
+    /// 
+    /// Best effort: a failing hook is logged and otherwise ignored. Called
+    /// last, once the chart is stored.
+    fn notify_verified(&mut self, chart_id: &str, user: Address) {
+        let hook = self.verification_hook.get();
+        if hook == Address::ZERO {
+            return;
+        }
+
+        let chart_key = chart_key_from_string(chart_id);
+        let call = IChartVerificationHook::onChartVerifiedCall { chartKey: chart_key, owner: user };
+        if let Err(error) = self.vm().call(&Call::new().gas(HOOK_GAS), hook, &call.abi_encode()) {
+            let reason = match error {
+                CallError::Revert(reason) => reason,
+                CallError::AbiDecodingFailed(_) => Vec::new(),
+            };
+            log(self.vm(), HookFailed { hook, chart_key, reason: reason.into() });
+        }
+    }
+
+    /// Statuses `import_charts` gives its entries, without writing
+    fn check_chart_import(
+        &self,
+        chart_ids: &[String],
+        chart_hashes: &[B32],
+        users: &[Address],
+        timestamps: &[U256],
+    ) -> Result<Vec<u8>, ChartRegistryError> {
+        let len = chart_ids.len();
+        if chart_hashes.len() != len || users.len() != len || timestamps.len() != len {
+            return Err(ChartRegistryError::LengthMismatch(LengthMismatch {}));
+        }
+        if len > import::MAX_IMPORT_ENTRIES {
+            return Err(ChartRegistryError::TooManyEntries(TooManyEntries {}));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let mut total = self.total_charts.get();
+        let statuses = chart_ids
+            .iter()
+            .enumerate()
+            .map(|(i, chart_id)| {
+                if chart_id.is_empty() || chart_hashes[i] == B32::ZERO || users[i] == Address::ZERO {
+                    import::INVALID
+                } else if timestamps[i].is_zero() || timestamps[i] > now {
+                    import::OUT_OF_RANGE
+                } else if !self.charts.getter(chart_id.clone()).timestamp.get().is_zero() {
+                    import::ALREADY_EXISTS
+                } else if chart_ids[..i].contains(chart_id) {
+                    import::DUPLICATE
+                } else if let Some(next) = total.checked_add(U256::from(1)) {
+                    total = next;
+                    import::OK
+                } else {
+                    import::COUNTER_OVERFLOW
+                }
+            })
+            .collect();
+        Ok(statuses)
+    }
+
+    /// Issues `audit_chart` reports for a chart
+    fn chart_issues(&self, chart_id: &str) -> Result<u8, ChartRegistryError> {
+        let commitment = self.charts.getter(String::from(chart_id));
+        if commitment.timestamp.get().is_zero() {
+            return Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}));
+        }
+        let chart_key = chart_key_from_string(chart_id);
+        let mut issues = 0;
+        if chart_key_from_string(&commitment.chart_id.get_string()) != chart_key {
+            issues |= ISSUE_ID_MISMATCH;
+        }
+        let listings = self
+            .get_user_charts(commitment.user.get())
+            .iter()
+            .filter(|id| chart_key_from_string(id) == chart_key)
+            .count();
+        match listings {
+            0 => issues |= ISSUE_NOT_LISTED,
+            1 => {}
+            _ => issues |= ISSUE_LISTED_TWICE,
+        }
+        Ok(issues)
+    }
+
+    /// Run the write `f` once per nonzero `key` of the sender
+    /// 
+    /// The key is recorded only when `f` succeeds, so a failed write can
+    /// be retried with the same key.
+    fn idempotent(
+        &mut self,
+        key: B32,
+        f: impl FnOnce(&mut Self) -> Result<(), ChartRegistryError>,
+    ) -> Result<(), ChartRegistryError> {
+        if key == B32::ZERO {
+            return f(self);
+        }
+        let sender = self.msg_sender();
+        if self.used_idempotency_keys.getter(sender).get(key) {
+            log(self.vm(), DuplicateSuppressed { sender, key });
+            return Ok(());
+        }
+        f(self)?;
+        self.used_idempotency_keys.setter(sender).insert(key, true);
+        Ok(())
+    }
+
+    /// Reject writes once the registry was sunset
+    fn only_live(&self) -> Result<(), ChartRegistryError> {
+        if self.sunsetted.get() {
+            return Err(ChartRegistryError::ContractSunset(ContractSunset {}));
+        }
+        Ok(())
+    }
+
+    /// Cusps packed by `pack_positions`, `None` with any other bit set
+    fn house_cusps(packed: &[B32; 2]) -> Option<[u64; HOUSE_COUNT as usize]> {
+        let words = packed.map(|word| word.0);
+        let cusps = unpack_positions(&words, HOUSE_COUNT)?;
+        if pack_positions(&cusps)? != (words[0], words[1]) {
+            return None;
+        }
+        cusps.try_into().ok()
+    }
+
+    /// Positions and cusps (`None` without houses) of `version` of a
+    /// chart with positions
+    fn chart_version(
+        &self,
+        chart_id: &str,
+        version: U256,
+    ) -> Result<([PlanetPosition; CHART_BODIES], Option<[u64; HOUSE_COUNT as usize]>), ChartRegistryError> {
+        let versions = self.chart_versions.getter(String::from(chart_id));
+        let current = U256::from(versions.len());
+        if version > current {
+            return Err(ChartRegistryError::VersionOutOfRange(VersionOutOfRange {}));
+        }
+        let (packed, (has_cusps, cusps)) = if version == current {
+            let (_, packed) = self.get_chart_positions(String::from(chart_id));
+            (packed, self.get_house_cusps(String::from(chart_id)))
+        } else {
+            // In range, so present
+            let previous = versions.getter(version.to::<usize>()).unwrap();
+            let word = |words: &StorageArray<StorageFixedBytes<32>, 2>, i: usize| words.get(i).unwrap_or_default();
+            let packed = [word(&previous.packed_positions, 0), word(&previous.packed_positions, 1)];
+            let cusps = [word(&previous.packed_cusps, 0), word(&previous.packed_cusps, 1)];
+            (packed, (previous.has_cusps.get(), cusps))
+        };
+
+        // Both validated when stored
+        let positions = unpack_chart(&packed)
+            .map_err(|_| ChartRegistryError::InvalidChartPositions(InvalidChartPositions {}))?;
+        let cusps = if has_cusps {
+            Some(Self::house_cusps(&cusps).ok_or(ChartRegistryError::InvalidHouseCusps(InvalidHouseCusps {}))?)
+        } else {
+            None
+        };
+        Ok((positions, cusps))
+    }
+
+    /// Packed positions of a chart that registered them
+    fn stored_positions(&self, chart_id: String) -> Result<[B32; 2], ChartRegistryError> {
+        let commitment = self.charts.getter(chart_id);
+        if commitment.timestamp.get().is_zero() {
+            return Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}));
+        }
+        if !commitment.has_positions.get() {
+            return Err(ChartRegistryError::ChartHasNoPositions(ChartHasNoPositions {}));
+        }
+        let word = |i: usize| commitment.packed_positions.get(i).unwrap_or_default();
+        Ok([word(0), word(1)])
+    }
+}
+
+/// Store two packed words in a chart's array of them
+fn set_words(array: &mut StorageArray<StorageFixedBytes<32>, 2>, words: [B32; 2]) {
+    for (i, word) in words.into_iter().enumerate() {
+        if let Some(mut slot) = array.setter(i) {
+            slot.set(word);
+        }
+    }
+}
+
+/// Key of a chart outside the registry's own storage: keccak256 of the id's
+/// UTF-8 bytes, as Solidity hashes an indexed `string`
+/// 
+/// Ids aren't normalized, so "Chart-1", "chart-1" and "chart_1" are three
+/// charts. Every event, the verification hook and FarcasterPredictions
+/// derive the key here; storage itself is keyed by the same bytes.
+pub(crate) fn chart_key_from_string(chart_id: &str) -> B32 {
+    keccak256(chart_id.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeris::tests::packed_day;
+    use crate::facets::FACET_DELAY;
+    use alloy_sol_types::{SolCall, SolEvent};
+    use stylus_sdk::abi::Router;
+    use stylus_sdk::testing::*;
+    use zkastro_core::positions::{pack_chart, unpack_chart, MOON, PLUTO, SOUTH_NODE};
+    use zkastro_core::sdk::{self, IChartRegistry};
+
+    const NOW: u64 = 1_754_006_400;
+
+    fn alice() -> Address {
+        Address::repeat_byte(0xa1)
+    }
+
+    fn bob() -> Address {
+        Address::repeat_byte(0xb0)
+    }
+
+    fn registry(vm: &TestVM) -> ChartRegistry {
+        vm.set_block_timestamp(NOW);
+        let mut registry = ChartRegistry::from(vm);
+        registry.initialize(Address::repeat_byte(0x01)).unwrap();
+        registry
+    }
+
+    /// ABI-encoded `(uint256, bool)` event data
+    fn event_data(timestamp: u64, flag: bool) -> Vec<u8> {
+        let mut data = B32::from(U256::from(timestamp)).to_vec();
+        data.extend_from_slice(B32::from(U256::from(flag as u8)).as_slice());
+        data
+    }
+
+    #[test]
+    fn test_register_charts_from_several_senders() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+
+        vm.set_sender(alice());
+        registry.register_chart("alice-1".into(), B32::repeat_byte(1), alice(), false).unwrap();
+        vm.set_sender(bob());
+        registry.register_chart("bob-1".into(), B32::repeat_byte(2), bob(), true).unwrap();
+        // Anyone may register on behalf of a user
+        registry.register_chart("alice-2".into(), B32::repeat_byte(3), alice(), false).unwrap();
+
+        assert_eq!(registry.total_charts(), U256::from(3));
+        assert_eq!(registry.get_user_charts(alice()), vec!["alice-1".to_string(), "alice-2".to_string()]);
+        assert_eq!(registry.get_user_charts(bob()), vec!["bob-1".to_string()]);
+        assert!(registry.get_user_charts(Address::repeat_byte(0x66)).is_empty());
+
+        let (hash, user, timestamp, zk_verified, chart_id) = registry.get_chart("bob-1".into());
+        assert_eq!(hash, B32::repeat_byte(2));
+        assert_eq!(user, bob());
+        assert_eq!(timestamp, U256::from(NOW));
+        assert!(zk_verified);
+        assert_eq!(chart_id, "bob-1");
+    }
+
+    #[test]
+    fn test_register_chart_rejects_bad_input() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+
+        registry.register_chart("c".into(), B32::repeat_byte(1), alice(), false).unwrap();
+        assert_eq!(
+            registry.register_chart("c".into(), B32::repeat_byte(9), bob(), false),
+            Err(ChartRegistryError::ChartAlreadyExists(ChartAlreadyExists {}))
+        );
+        assert_eq!(
+            registry.register_chart("d".into(), B32::ZERO, alice(), false),
+            Err(ChartRegistryError::InvalidChartHash(InvalidChartHash {}))
+        );
+        assert_eq!(
+            registry.register_chart("d".into(), B32::repeat_byte(1), Address::ZERO, false),
+            Err(ChartRegistryError::InvalidUserAddress(InvalidUserAddress {}))
+        );
+
+        assert_eq!(registry.total_charts(), U256::from(1));
+        assert_eq!(registry.get_chart("c".into()).1, alice());
+        assert_eq!(registry.get_user_charts(bob()), Vec::<String>::new());
+
+        // A full chart counter fails instead of wrapping to zero
+        registry.total_charts.set(U256::MAX);
+        assert_eq!(
+            registry.register_chart("e".into(), B32::repeat_byte(1), bob(), false),
+            Err(ChartRegistryError::Overflow(Overflow { field: math::FIELD_TOTAL_CHARTS }))
+        );
+    }
+
+    #[test]
+    fn test_register_chart_derived_is_idempotent() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+
+        let chart_id = registry.register_chart_derived(B32::repeat_byte(7), alice(), false).unwrap();
+        assert_eq!(chart_id, "ab204804fdd3b78cfeeba2e8b109005b");
+        assert_eq!(registry.derive_chart_id(alice(), B32::repeat_byte(7)), chart_id);
+        assert_eq!(registry.get_chart(chart_id.clone()).1, alice());
+
+        // A retry maps to the same chart
+        assert_eq!(
+            registry.register_chart_derived(B32::repeat_byte(7), alice(), true),
+            Err(ChartRegistryError::ChartAlreadyExists(ChartAlreadyExists {}))
+        );
+        assert_eq!(registry.total_charts(), U256::from(1));
+
+        let other = registry.register_chart_derived(B32::repeat_byte(8), alice(), false).unwrap();
+        assert_ne!(other, chart_id);
+        assert_eq!(registry.get_user_charts(alice()), vec![chart_id, other]);
+    }
+
+    #[test]
+    fn test_input_escrow_and_reveal() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        let registrar = Address::repeat_byte(0x5e);
+        let payload = br#"{"dob":"1990-01-01","tob":"12:00","lat":"51.5074","lon":"-0.1278"}"#.to_vec();
+        let salt = B32::repeat_byte(0x2a);
+        let input_commitment = keccak256([payload.as_slice(), salt.as_slice()].concat());
+
+        registry
+            .register_chart_with_input("escrowed".into(), B32::repeat_byte(1), alice(), true, input_commitment)
+            .unwrap();
+        registry.register_chart("plain".into(), B32::repeat_byte(2), alice(), true).unwrap();
+        assert_eq!(registry.get_input_commitment("escrowed".into()), input_commitment);
+        assert_eq!(registry.get_input_commitment("plain".into()), B32::ZERO);
+
+        let reveal = |registry: &mut ChartRegistry, chart_id: &str, payload: &[u8]| {
+            registry.reveal_input(chart_id.into(), Bytes::from(payload.to_vec()), salt)
+        };
+        vm.set_sender(registrar);
+        assert_eq!(reveal(&mut registry, "escrowed", &payload), Err(ChartRegistryError::NotRegistrar(NotRegistrar {})));
+        assert_eq!(registry.set_registrar(registrar, true), Err(ChartRegistryError::NotOwner(NotOwner {})));
+        vm.set_sender(Address::repeat_byte(0x01));
+        registry.set_registrar(registrar, true).unwrap();
+        assert!(registry.is_registrar(registrar));
+        vm.set_sender(registrar);
+
+        // A re-registration can't replace the commitment
+        assert_eq!(
+            registry.register_chart_with_input("escrowed".into(), B32::repeat_byte(1), alice(), true, B32::repeat_byte(9)),
+            Err(ChartRegistryError::ChartAlreadyExists(ChartAlreadyExists {}))
+        );
+
+        let mut tampered = payload.clone();
+        tampered[8] = b'2';
+        assert_eq!(reveal(&mut registry, "escrowed", &tampered), Err(ChartRegistryError::InputMismatch(InputMismatch {})));
+        assert_eq!(reveal(&mut registry, "plain", &payload), Err(ChartRegistryError::NoInputCommitment(NoInputCommitment {})));
+        assert_eq!(reveal(&mut registry, "missing", &payload), Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {})));
+        assert!(vm.get_emitted_logs().iter().all(|(topics, _)| topics[0] != ChartInputRevealed::SIGNATURE_HASH));
+
+        reveal(&mut registry, "escrowed", &payload).unwrap();
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(
+            topics,
+            vec![
+                ChartInputRevealed::SIGNATURE_HASH,
+                keccak256(b"escrowed"),
+                B32::left_padding_from(registrar.as_slice()),
+            ]
+        );
+        let event = ChartInputRevealed { chart_id: keccak256(b"escrowed"), registrar, payload: payload.into(), salt };
+        assert_eq!(data, event.encode_data());
+    }
+
+    #[test]
+    fn test_packed_birth_commitment() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        let birth = birth_data::BirthData {
+            year: 2024,
+            month: 2,
+            day: 29,
+            minutes_of_day: 615,
+            lat_x10000: -338_688,
+            lon_x10000: 1_512_093,
+        };
+        let salt = [0x2a; 32];
+        let commitment = B32::from(crate::prover::generate_packed_commitment(&birth, salt).unwrap().0);
+        let packed = Bytes::from(birth.pack().to_vec());
+
+        assert!(ChartRegistry::verify_birth_commitment(commitment, packed.clone(), B32::from(salt)));
+        assert!(!ChartRegistry::verify_birth_commitment(commitment, packed.clone(), B32::repeat_byte(0x2b)));
+        let mut moved = birth.pack();
+        moved[15] ^= 1;
+        assert!(!ChartRegistry::verify_birth_commitment(commitment, Bytes::from(moved.to_vec()), B32::from(salt)));
+
+        // Out-of-range data is refused even when the hash matches
+        let leap = birth_data::BirthData { year: 2023, ..birth };
+        assert_eq!(
+            crate::prover::generate_packed_commitment(&leap, salt),
+            Err(birth_data::BirthDataError::InvalidDay)
+        );
+        let unchecked = B32::from(leap.commitment(salt));
+        assert!(!ChartRegistry::verify_birth_commitment(unchecked, Bytes::from(leap.pack().to_vec()), B32::from(salt)));
+
+        // A chart's input commitment made this way reveals with the packed bytes
+        registry.register_chart_with_input("packed".into(), B32::repeat_byte(1), alice(), true, commitment).unwrap();
+        let registrar = Address::repeat_byte(0x5e);
+        vm.set_sender(Address::repeat_byte(0x01));
+        registry.set_registrar(registrar, true).unwrap();
+        vm.set_sender(registrar);
+        registry.reveal_input("packed".into(), packed, B32::from(salt)).unwrap();
+    }
+
+    #[test]
+    fn test_sunset_freezes_writes() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        let owner = Address::repeat_byte(0x01);
+        let successor = Address::repeat_byte(0x5c);
+        registry.register_chart("before".into(), B32::repeat_byte(1), alice(), false).unwrap();
+
+        assert_eq!(registry.sunset(successor), Err(ChartRegistryError::NotOwner(NotOwner {})));
+        vm.set_sender(owner);
+        registry.sunset(successor).unwrap();
+        assert!(registry.is_sunset());
+        assert_eq!(registry.successor(), successor);
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics, vec![Sunset::SIGNATURE_HASH, B32::left_padding_from(successor.as_slice())]);
+        assert_eq!(data, B32::from(U256::from(NOW)).to_vec());
+
+        // Every write fails, the owner's included, and sunset can't be redone
+        let sunset = Err(ChartRegistryError::ContractSunset(ContractSunset {}));
+        assert_eq!(registry.register_chart("after".into(), B32::repeat_byte(2), alice(), false), sunset);
+        assert_eq!(registry.register_chart_derived(B32::repeat_byte(2), alice(), false).map(|_| ()), sunset);
+        assert_eq!(registry.register_chart_with_positions("after".into(), packed_day(0), B32::repeat_byte(3)), sunset);
+        assert_eq!(registry.mark_as_verified("before".into()), sunset);
+        assert_eq!(registry.set_trusted_forwarder(bob()), sunset);
+        assert_eq!(registry.set_registrar(bob(), true), sunset);
+        assert_eq!(registry.reveal_input("before".into(), Bytes::from(vec![1]), B32::ZERO), sunset);
+        assert_eq!(registry.sunset(Address::ZERO), sunset);
+        assert_eq!(registry.successor(), successor);
+
+        // Reads keep working
+        assert_eq!(registry.total_charts(), U256::from(1));
+        assert!(registry.verify_chart("before".into(), B32::repeat_byte(1)));
+        assert_eq!(registry.get_user_charts(alice()), vec!["before".to_string()]);
+    }
+
+    #[test]
+    fn test_chart_created_event() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+
+        registry.register_chart("chart".into(), B32::repeat_byte(7), alice(), true).unwrap();
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 1);
+        let (topics, data) = &logs[0];
+        assert_eq!(topics.len(), 4);
+        assert_eq!(topics[0], ChartCreated::SIGNATURE_HASH);
+        assert_eq!(topics[1], keccak256(b"chart"));
+        assert_eq!(topics[2], B32::repeat_byte(7));
+        assert_eq!(topics[3], B32::left_padding_from(alice().as_slice()));
+        assert_eq!(data, &event_data(NOW, true));
+    }
+
+    #[test]
+    fn test_mark_as_verified() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+
+        assert_eq!(
+            registry.mark_as_verified("missing".into()),
+            Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}))
+        );
+        assert!(vm.get_emitted_logs().is_empty());
+
+        registry.register_chart("chart".into(), B32::repeat_byte(7), alice(), false).unwrap();
+        assert!(!registry.is_zk_verified("chart".into()));
+        registry.mark_as_verified("chart".into()).unwrap();
+        assert!(registry.is_zk_verified("chart".into()));
+        assert!(!registry.is_zk_verified("missing".into()));
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 2);
+        let (topics, data) = &logs[1];
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0], ChartVerified::SIGNATURE_HASH);
+        assert_eq!(topics[1], keccak256(b"chart"));
+        assert_eq!(data.as_slice(), B32::repeat_byte(7).as_slice());
+    }
+
+    #[test]
+    fn test_import_charts() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        let owner = Address::repeat_byte(0x01);
+        registry.register_chart("taken".into(), B32::repeat_byte(9), bob(), false).unwrap();
+        let before = vm.get_emitted_logs().len();
+        let at = U256::from(NOW - 1000);
+        let run_keyed = |registry: &mut ChartRegistry, ids: &[&str], dry_run: bool, key: B32| {
+            let len = ids.len();
+            registry.import_charts(
+                ids.iter().map(|id| String::from(*id)).collect(),
+                (1..=len as u8).map(B32::repeat_byte).collect(),
+                vec![alice(); len],
+                vec![at; len],
+                dry_run,
+                key,
+            )
+        };
+        let run = |registry: &mut ChartRegistry, ids: &[&str], dry_run: bool| run_keyed(registry, ids, dry_run, B32::ZERO);
+
+        // Two bad entries: a chart already stored and a repeated id
+        vm.set_sender(owner);
+        let dataset = ["natal", "taken", "solar", "natal", "lunar"];
+        let expected = vec![import::OK, import::ALREADY_EXISTS, import::OK, import::DUPLICATE, import::OK];
+        assert_eq!(run(&mut registry, &dataset, true), Ok(expected));
+        assert_eq!(
+            run(&mut registry, &dataset, false),
+            Err(ChartRegistryError::ImportRejected(ImportRejected {}))
+        );
+        assert_eq!(registry.total_charts(), U256::from(1));
+        assert!(registry.get_user_charts(alice()).is_empty());
+        assert_eq!(vm.get_emitted_logs().len(), before);
+
+        // The other checks
+        let statuses = registry.import_charts(
+            vec!["".into(), "a".into(), "b".into(), "c".into(), "d".into()],
+            vec![B32::repeat_byte(1), B32::ZERO, B32::repeat_byte(1), B32::repeat_byte(1), B32::repeat_byte(1)],
+            vec![alice(), alice(), Address::ZERO, alice(), alice()],
+            vec![at, at, at, U256::ZERO, U256::from(NOW + 1)],
+            true,
+            B32::ZERO,
+        );
+        let invalid = import::INVALID;
+        assert_eq!(statuses, Ok(vec![invalid, invalid, invalid, import::OUT_OF_RANGE, import::OUT_OF_RANGE]));
+        assert_eq!(
+            registry.import_charts(vec!["a".into()], vec![], vec![alice()], vec![at], true, B32::ZERO),
+            Err(ChartRegistryError::LengthMismatch(LengthMismatch {}))
+        );
+        let many: Vec<String> = (0..=import::MAX_IMPORT_ENTRIES).map(|i| i.to_string()).collect();
+        let ids: Vec<&str> = many.iter().map(String::as_str).collect();
+        assert_eq!(run(&mut registry, &ids, true), Err(ChartRegistryError::TooManyEntries(TooManyEntries {})));
+        registry.total_charts.set(U256::MAX - U256::from(1));
+        assert_eq!(run(&mut registry, &["a", "b"], true), Ok(vec![import::OK, import::COUNTER_OVERFLOW]));
+        registry.total_charts.set(U256::from(1));
+        vm.set_sender(alice());
+        assert_eq!(run(&mut registry, &["a"], true), Err(ChartRegistryError::NotOwner(NotOwner {})));
+
+        // The cleaned dataset goes through, keeping the original times
+        vm.set_sender(owner);
+        let cleaned = ["natal", "solar", "lunar"];
+        let key = B32::repeat_byte(0x1d);
+        assert_eq!(run_keyed(&mut registry, &cleaned, false, key), Ok(vec![import::OK; 3]));
+        assert_eq!(registry.get_user_charts(alice()), vec!["natal", "solar", "lunar"]);
+        assert_eq!(registry.total_charts(), U256::from(4));
+        assert_eq!(registry.get_chart("solar".into()), (B32::repeat_byte(2), alice(), at, false, "solar".into()));
+        let (_, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(data, event_data(NOW - 1000, false));
+        assert_eq!(registry.audit_chart("lunar".into()), Ok((true, 0)));
+
+        // A retry with the same key is suppressed instead of rejected as
+        // already stored
+        let logged = vm.get_emitted_logs().len();
+        assert_eq!(run_keyed(&mut registry, &cleaned, false, key), Ok(vec![]));
+        assert!(registry.is_idempotency_key_used(owner, key));
+        assert_eq!(registry.total_charts(), U256::from(4));
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), logged + 1);
+        assert_eq!(logs[logged].0, vec![
+            DuplicateSuppressed::SIGNATURE_HASH,
+            B32::left_padding_from(owner.as_slice()),
+            key,
+        ]);
+    }
+
+    #[test]
+    fn test_audit_and_repair_chart() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        for id in ["a", "b", "c"] {
+            registry.register_chart(id.into(), B32::repeat_byte(7), alice(), false).unwrap();
+        }
+        assert_eq!(registry.audit_chart("b".into()), Ok((true, 0)));
+        assert_eq!(
+            registry.audit_chart("missing".into()),
+            Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}))
+        );
+
+        // Corrupt the indexes the way a buggy import could: list "b" twice
+        // and drop "c", and store the wrong id in "a"
+        registry.test_set_chart_vector_entry(alice(), U256::from(2), "b".into());
+        registry.test_force_chart_vector_entry(alice(), "b".into());
+        registry.test_set_stored_chart_id("a".into(), "z".into());
+        assert_eq!(registry.audit_chart("a".into()), Ok((false, ISSUE_ID_MISMATCH)));
+        assert_eq!(registry.audit_chart("b".into()), Ok((false, ISSUE_LISTED_TWICE)));
+        assert_eq!(registry.audit_chart("c".into()), Ok((false, ISSUE_NOT_LISTED)));
+
+        vm.set_sender(alice());
+        assert_eq!(registry.repair_chart("b".into()), Err(ChartRegistryError::NotOwner(NotOwner {})));
+        vm.set_sender(Address::repeat_byte(0x01));
+        for id in ["a", "b", "c"] {
+            registry.repair_chart(id.into()).unwrap();
+            assert_eq!(registry.audit_chart(id.into()), Ok((true, 0)));
+        }
+        assert_eq!(registry.get_user_charts(alice()), vec!["a", "b", "c"]);
+        assert_eq!(registry.charts.getter("a".into()).chart_id.get_string(), "a");
+
+        let repaired: Vec<_> = vm
+            .get_emitted_logs()
+            .into_iter()
+            .filter(|(topics, _)| topics[0] == ChartRepaired::SIGNATURE_HASH)
+            .map(|(topics, data)| (topics[1], data[31]))
+            .collect();
+        assert_eq!(
+            repaired,
+            vec![
+                (keccak256(b"a"), ISSUE_ID_MISMATCH),
+                (keccak256(b"b"), ISSUE_LISTED_TWICE),
+                (keccak256(b"c"), ISSUE_NOT_LISTED),
+            ]
+        );
+
+        // Nothing to fix: no event
+        registry.repair_chart("a".into()).unwrap();
+        assert_eq!(vm.get_emitted_logs().len(), 6);
+    }
+
+    #[test]
+    fn test_chart_keys_match_across_apis() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        vm.set_sender(alice());
+        let derived = derive_chart_id(alice(), [7; 32]);
+        let long = "x".repeat(200);
+        let ids = [
+            "Chart-A",
+            "chart-a",
+            "chart_a",
+            "chart-a ",
+            "thirty-one-bytes-fits-one-slot!",
+            derived.as_str(),
+            long.as_str(),
+            "Ästro-チャート",
+        ];
+        assert_eq!((ids[4].len(), ids[5].len()), (31, 32));
+
+        // Register through each string API in turn, then read back by key
+        for (i, id) in ids.into_iter().enumerate() {
+            let hash = B32::repeat_byte(i as u8 + 1);
+            match i % 3 {
+                0 => registry.register_chart(id.into(), hash, alice(), false).unwrap(),
+                1 => registry.register_chart_with_input(id.into(), hash, alice(), false, hash).unwrap(),
+                _ => registry.register_chart_with_positions(id.into(), packed_day(i as u16), hash).unwrap(),
+            }
+            let key = ChartRegistry::debug_chart_key(id.into());
+            assert_eq!(key, keccak256(id.as_bytes()), "{id:?}");
+            let (topics, _) = vm.get_emitted_logs().pop().unwrap();
+            assert_eq!((topics[0], topics[1]), (ChartCreated::SIGNATURE_HASH, key), "{id:?}");
+
+            registry.mark_as_verified(id.into()).unwrap();
+            let (topics, _) = vm.get_emitted_logs().pop().unwrap();
+            assert_eq!((topics[0], topics[1]), (ChartVerified::SIGNATURE_HASH, key), "{id:?}");
+        }
+
+        // Near-identical ids stay separate charts with separate keys
+        let keys: Vec<B32> = ids.iter().map(|id| ChartRegistry::debug_chart_key((*id).into())).collect();
+        let listed: Vec<B32> = registry.get_user_charts(alice()).iter().map(|id| chart_key_from_string(id)).collect();
+        assert_eq!(listed, keys);
+        assert!(keys.iter().enumerate().all(|(i, key)| !keys[..i].contains(key)));
+        for id in ids {
+            let (_, user, _, verified, stored_id) = registry.get_chart(id.into());
+            assert_eq!((user, verified, stored_id.as_str()), (alice(), true, id));
+            assert_eq!(registry.audit_chart(id.into()), Ok((true, 0)), "{id:?}");
+        }
+        assert_eq!(registry.total_charts(), U256::from(ids.len()));
+
+        // A stored id that differs only in case doesn't re-derive the key
+        registry.test_set_stored_chart_id("Chart-A".into(), "chart-a".into());
+        assert_eq!(registry.audit_chart("Chart-A".into()), Ok((false, ISSUE_ID_MISMATCH)));
+        assert_eq!(registry.audit_chart("chart-a".into()), Ok((true, 0)));
+    }
+
+    #[test]
+    fn test_verify_chart() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+
+        registry.register_chart("chart".into(), B32::repeat_byte(7), alice(), false).unwrap();
+        assert!(registry.verify_chart("chart".into(), B32::repeat_byte(7)));
+        assert!(!registry.verify_chart("chart".into(), B32::repeat_byte(8)));
+        // Unknown charts only match the zero hash, which can't be registered
+        assert!(!registry.verify_chart("missing".into(), B32::repeat_byte(7)));
+    }
+
+    #[test]
+    fn test_verify_chart_legacy() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+
+        // Hashes computed by the legacy backend's own JavaScript
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/legacy_chart_hashes.json");
+        let fixtures: Vec<serde_json::Value> = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(fixtures.len(), 4);
+        for fixture in &fixtures {
+            let name = fixture["name"].as_str().unwrap();
+            let planets: Vec<i64> = fixture["planets"].as_array().unwrap().iter().map(|v| v.as_i64().unwrap()).collect();
+            let (asc, mc) = (fixture["asc"].as_i64().unwrap(), fixture["mc"].as_i64().unwrap());
+            let zk_proof = fixture["zkProof"].as_str().unwrap();
+            let hash: B32 = fixture["hash"].as_str().unwrap().parse().unwrap();
+
+            let json = zkastro_core::legacy_hash::chart_json(&planets.clone().try_into().unwrap(), asc, mc, zk_proof);
+            assert_eq!(json, fixture["json"].as_str().unwrap(), "{name}");
+            registry.register_chart(name.into(), hash, alice(), true).unwrap();
+            assert!(registry.verify_chart_legacy(name.into(), planets.clone(), asc, mc, zk_proof.into()), "{name}");
+
+            // Any other field, a missing planet or another chart fails
+            assert!(!registry.verify_chart_legacy(name.into(), planets.clone(), asc + 1, mc, zk_proof.into()));
+            assert!(!registry.verify_chart_legacy(name.into(), planets.clone(), asc, mc, format!("{zk_proof} ")));
+            assert!(!registry.verify_chart_legacy(name.into(), planets[..6].to_vec(), asc, mc, zk_proof.into()));
+            assert!(!registry.verify_chart_legacy("missing".into(), planets, asc, mc, zk_proof.into()));
+        }
+    }
+
+    #[test]
+    fn test_timestamps_follow_block_clock() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+
+        registry.register_chart("first".into(), B32::repeat_byte(1), alice(), false).unwrap();
+        vm.set_block_timestamp(NOW + 3_600);
+        registry.register_chart("second".into(), B32::repeat_byte(2), alice(), false).unwrap();
+
+        assert_eq!(registry.get_chart("first".into()).2, U256::from(NOW));
+        assert_eq!(registry.get_chart("second".into()).2, U256::from(NOW + 3_600));
+        assert_eq!(registry.get_chart("missing".into()).2, U256::ZERO);
+        assert_eq!(vm.get_emitted_logs()[1].1, event_data(NOW + 3_600, false));
+    }
+
+    #[test]
+    fn test_register_chart_with_positions() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        vm.set_sender(alice());
+
+        let packed = packed_day(0);
+        let salt = B32::repeat_byte(0x5a);
+        registry.register_chart_with_positions("natal".into(), packed, salt).unwrap();
+
+        let expected = chart_hash(&unpack_chart(&packed).unwrap(), salt.0).unwrap();
+        assert!(registry.verify_chart("natal".into(), B32::from(expected)));
+        assert_eq!(registry.get_chart("natal".into()).1, alice());
+        assert_eq!(registry.get_chart_positions("natal".into()), (true, packed));
+        assert_eq!(registry.get_chart_positions("missing".into()), (false, [B32::ZERO; 2]));
+
+        let mut bad = packed;
+        bad[1].0[31] = 1;
+        assert_eq!(
+            registry.register_chart_with_positions("bad".into(), bad, salt),
+            Err(ChartRegistryError::InvalidChartPositions(InvalidChartPositions {}))
+        );
+        assert_eq!(registry.total_charts(), U256::from(1));
+    }
+
+    #[test]
+    fn test_house_placement() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        vm.set_sender(alice());
+
+        // Equal houses from 350°, so the 1st spans 0° Aries
+        let cusp_values = [35000, 2000, 5000, 8000, 11000, 14000, 17000, 20000, 23000, 26000, 29000, 32000];
+        let (lo, hi) = pack_positions(&cusp_values).unwrap();
+        let cusps = [B32::from(lo), B32::from(hi)];
+        let salt = B32::repeat_byte(0x5a);
+
+        // packed_day puts Venus (body 3) at 93° + shift
+        const VENUS: u8 = 3;
+        let placements = [("before-0", 35500, 1), ("after-0", 500, 1), ("cusp-2", 2000, 2), ("late-12", 34999, 12)];
+        for (chart_id, venus, house) in placements {
+            let packed = packed_day(((venus + 36000 - 9300) % 36000) as u16);
+            registry.register_chart_with_houses(chart_id.into(), packed, cusps, salt).unwrap();
+            assert_eq!(registry.get_house_placement(chart_id.into(), VENUS), Ok(house), "{chart_id}");
+            assert_eq!(registry.get_chart_positions(chart_id.into()), (true, packed));
+        }
+        assert_eq!(registry.get_house_cusps("after-0".into()), (true, cusps));
+        assert_eq!(
+            registry.get_house_placement("after-0".into(), 11),
+            Err(ChartRegistryError::BodyNotInChart(BodyNotInChart {}))
+        );
+
+        // Positions without houses, and no chart at all
+        registry.register_chart_with_positions("no-houses".into(), packed_day(0), salt).unwrap();
+        assert_eq!(registry.get_house_cusps("no-houses".into()), (false, [B32::ZERO; 2]));
+        assert_eq!(
+            registry.get_house_placement("no-houses".into(), VENUS),
+            Err(ChartRegistryError::ChartHasNoHouses(ChartHasNoHouses {}))
+        );
+        assert_eq!(
+            registry.get_house_placement("missing".into(), VENUS),
+            Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}))
+        );
+
+        // Unordered cusps, or bits past the 12th, are rejected
+        let invalid = Err(ChartRegistryError::InvalidHouseCusps(InvalidHouseCusps {}));
+        let mut swapped = cusp_values;
+        swapped.swap(4, 5);
+        let (lo, hi) = pack_positions(&swapped).unwrap();
+        let swapped = [B32::from(lo), B32::from(hi)];
+        assert_eq!(registry.register_chart_with_houses("swapped".into(), packed_day(0), swapped, salt), invalid);
+        let mut trailing = cusps;
+        trailing[1].0[0] = 1;
+        assert_eq!(registry.register_chart_with_houses("trailing".into(), packed_day(0), trailing, salt), invalid);
+        assert_eq!(registry.total_charts(), U256::from(5));
+    }
+
+    #[test]
+    fn test_diff_chart_versions() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        vm.set_sender(alice());
+
+        let cusp_values = [35000, 2000, 5000, 8000, 11000, 14000, 17000, 20000, 23000, 26000, 29000, 32000];
+        let pack_cusps = |values: &[u64]| {
+            let (lo, hi) = pack_positions(values).unwrap();
+            [B32::from(lo), B32::from(hi)]
+        };
+        let salt = B32::repeat_byte(0x5a);
+        let original = packed_day(0);
+        registry.register_chart_with_houses("chart".into(), original, pack_cusps(&cusp_values), salt).unwrap();
+        registry.mark_as_verified("chart".into()).unwrap();
+        assert_eq!(registry.get_chart_version("chart".into()), U256::ZERO);
+
+        // Shift only the Moon and the ascendant (1st house cusp)
+        let mut positions = unpack_chart(&original).unwrap();
+        let moon = positions.iter_mut().find(|position| position.body == MOON).unwrap();
+        let old_moon = moon.longitude_centideg as u64;
+        moon.longitude_centideg += 150;
+        let updated = pack_chart(&positions).unwrap();
+        let mut new_cusps = cusp_values;
+        new_cusps[0] = 35200;
+        registry.update_chart("chart".into(), updated, pack_cusps(&new_cusps), salt).unwrap();
+
+        let expected_hash = B32::from(chart_hash(&positions, salt.0).unwrap());
+        let (stored_hash, _, _, zk_verified, _) = registry.get_chart("chart".into());
+        assert_eq!((stored_hash, zk_verified), (expected_hash, false));
+        assert_eq!(registry.get_chart_positions("chart".into()), (true, updated));
+        assert_eq!(registry.get_chart_version("chart".into()), U256::from(1));
+        let event = ChartUpdated { chart_id: chart_key_from_string("chart"), chart_hash: expected_hash, version: U256::from(1) };
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!((topics[0], data), (ChartUpdated::SIGNATURE_HASH, event.encode_data()));
+
+        let diff = |a: u64, b: u64| registry.diff_chart_versions("chart".into(), U256::from(a), U256::from(b));
+        let ascendant = CUSP_ID_OFFSET + 1;
+        assert_eq!(diff(0, 1), Ok(vec![(MOON, old_moon, old_moon + 150, false), (ascendant, 35000, 35200, false)]));
+        assert_eq!(diff(1, 0), Ok(vec![(MOON, old_moon + 150, old_moon, false), (ascendant, 35200, 35000, false)]));
+        assert_eq!(diff(1, 1), Ok(vec![]));
+        assert_eq!(diff(0, 2), Err(ChartRegistryError::VersionOutOfRange(VersionOutOfRange {})));
+
+        // Charts without positions, or no chart at all, fail distinctly
+        registry.register_chart("hash-only".into(), B32::repeat_byte(0x11), alice(), false).unwrap();
+        assert_eq!(
+            registry.diff_chart_versions("hash-only".into(), U256::ZERO, U256::ZERO),
+            Err(ChartRegistryError::ChartHasNoPositions(ChartHasNoPositions {}))
+        );
+        assert_eq!(
+            registry.diff_chart_versions("missing".into(), U256::ZERO, U256::ZERO),
+            Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}))
+        );
+
+        // Only the owner updates, keeping the chart's bodies and houses
+        assert_eq!(
+            registry.update_chart("chart".into(), original, [B32::ZERO; 2], salt),
+            Err(ChartRegistryError::InvalidHouseCusps(InvalidHouseCusps {}))
+        );
+        // Dropping the Moon, for a body the chart didn't have, fails too
+        let mut dropped_moon = positions;
+        dropped_moon.iter_mut().find(|position| position.body == MOON).unwrap().body = SOUTH_NODE;
+        dropped_moon.sort_by_key(|position| position.body);
+        assert_eq!(
+            registry.update_chart("chart".into(), pack_chart(&dropped_moon).unwrap(), pack_cusps(&new_cusps), salt),
+            Err(ChartRegistryError::InvalidChartPositions(InvalidChartPositions {}))
+        );
+        positions[0].body = PLUTO + 1;
+        positions.sort_by_key(|position| position.body);
+        let other_bodies = pack_chart(&positions).unwrap();
+        assert_eq!(
+            registry.update_chart("chart".into(), other_bodies, pack_cusps(&cusp_values), salt),
+            Err(ChartRegistryError::InvalidChartPositions(InvalidChartPositions {}))
+        );
+        vm.set_sender(bob());
+        assert_eq!(
+            registry.update_chart("chart".into(), original, pack_cusps(&cusp_values), salt),
+            Err(ChartRegistryError::NotChartOwner(NotChartOwner {}))
+        );
+        assert_eq!(registry.get_chart_version("chart".into()), U256::from(1));
+    }
+
+    #[test]
+    fn test_synastry_score() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        vm.set_sender(alice());
+
+        let salt = B32::repeat_byte(0x5a);
+        registry.register_chart_with_positions("a".into(), packed_day(0), salt).unwrap();
+        registry.register_chart_with_positions("b".into(), packed_day(40), salt).unwrap();
+        registry.register_chart("hash-only".into(), B32::repeat_byte(0x11), alice(), false).unwrap();
+
+        let expected = chart_compatibility(&packed_day(0), &packed_day(40)).unwrap();
+        assert_eq!(registry.synastry_score("a".into(), "b".into()), Ok(expected));
+        assert_eq!(registry.synastry_score("b".into(), "a".into()), Ok(expected));
+
+        assert_eq!(
+            registry.synastry_score("a".into(), "hash-only".into()),
+            Err(ChartRegistryError::ChartHasNoPositions(ChartHasNoPositions {}))
+        );
+        assert_eq!(
+            registry.synastry_score("missing".into(), "a".into()),
+            Err(ChartRegistryError::ChartDoesNotExist(ChartDoesNotExist {}))
+        );
+    }
+
+    #[test]
+    fn test_initialize_once() {
+        let vm = TestVM::default();
+        let mut registry = ChartRegistry::from(&vm);
+
+        assert_eq!(
+            registry.initialize(Address::ZERO),
+            Err(ChartRegistryError::InvalidOwner(InvalidOwner {}))
+        );
+        registry.initialize(alice()).unwrap();
+        assert_eq!(registry.owner(), alice());
+        assert_eq!(registry.get_implementation_version(), U256::from(IMPLEMENTATION_VERSION));
+
+        // Re-initializing can no longer reset state or take ownership
+        registry.register_chart("chart".into(), B32::repeat_byte(1), alice(), false).unwrap();
+        vm.set_sender(bob());
+        assert_eq!(
+            registry.initialize(bob()),
+            Err(ChartRegistryError::AlreadyInitialized(AlreadyInitialized {}))
+        );
+        assert_eq!(registry.owner(), alice());
+        assert_eq!(registry.total_charts(), U256::from(1));
+    }
+
+    #[test]
+    fn test_chart_slot_matches_storage() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        vm.set_sender(alice());
+        registry.register_chart("alice-1".into(), B32::repeat_byte(0xc1), alice(), true).unwrap();
+
+        let slot = U256::from_be_bytes(ChartRegistry::chart_slot("alice-1".into()).0);
+        assert_eq!(vm.get_storage(slot), B32::repeat_byte(0xc1));
+        assert_eq!(vm.get_storage(slot + U256::from(1)), B32::left_padding_from(alice().as_slice()));
+        assert_eq!(vm.get_storage(slot + U256::from(2)), B32::from(U256::from(NOW)));
+        assert_ne!(ChartRegistry::chart_slot("alice-2".into()), ChartRegistry::chart_slot("alice-1".into()));
+    }
+
+    #[test]
+    fn test_storage_layout_survives_upgrade() {
+        let vm = TestVM::default();
+        let mut registry = ChartRegistry::from(&vm);
+
+        // Sentinel in the total_charts slot reads back through the contract
+        vm.set_storage(U256::from(2), B32::from(U256::from(41)));
+        assert_eq!(registry.total_charts(), U256::from(41));
+
+        // So does one at the entry of the charts map (slot 0) that
+        // `chart_slot` names
+        let slot = ChartRegistry::chart_slot("sentinel".into());
+        vm.set_storage(U256::from_be_bytes(slot.0), B32::repeat_byte(0x5c));
+        assert_eq!(registry.get_chart("sentinel".into()).0, B32::repeat_byte(0x5c));
+
+        // Owner and flag share slot 3; the gap spans slots 4..=53
+        registry.initialize(alice()).unwrap();
+        let mut expected = B32::left_padding_from(alice().as_slice());
+        expected.0[11] = 1;
+        assert_eq!(vm.get_storage(U256::from(3)), expected);
+
+        // The forwarder, the first field after the gap, is slot 54
+        vm.set_sender(alice());
+        registry.set_trusted_forwarder(bob()).unwrap();
+        assert_eq!(vm.get_storage(U256::from(54)), B32::left_padding_from(bob().as_slice()));
+
+        // chart_versions is rooted at slot 58
+        registry.chart_versions.setter("sentinel".into()).grow();
+        let versions = slots::bytes_mapping_slot(b"sentinel", slots::base_slot(58));
+        assert_eq!(vm.get_storage(U256::from_be_bytes(versions)), B32::from(U256::from(1)));
+
+        // used_idempotency_keys is rooted at slot 59
+        let key = B32::repeat_byte(0x4b);
+        registry.used_idempotency_keys.setter(alice()).insert(key, true);
+        let keys = slots::mapping_slot(B32::left_padding_from(alice().as_slice()).0, slots::base_slot(59));
+        let used = slots::mapping_slot(key.0, keys);
+        assert_eq!(vm.get_storage(U256::from_be_bytes(used)), B32::from(U256::from(1)));
+
+        // The last declared field, facets, is rooted at slot 60: the pending
+        // facet and its time share the second word of an entry
+        let selector = FixedBytes([0xab; 4]);
+        registry.set_facet(Address::repeat_byte(0xfa), vec![selector]).unwrap();
+        let mut selector_key = [0; 32];
+        selector_key[..4].copy_from_slice(&selector.0);
+        let entry = U256::from_be_bytes(slots::mapping_slot(selector_key, slots::base_slot(60)));
+        let effective_at = U256::from(vm.block_timestamp() + FACET_DELAY);
+        let pending = effective_at << 160 | U256::from_be_slice(&[0xfa; 20]);
+        assert_eq!(vm.get_storage(entry + U256::from(1)), B32::from(pending));
+
+        let before = vm.snapshot().storage;
+        registry.__gap.setter(0).unwrap().set(U256::from(1));
+        registry.__gap.setter(49).unwrap().set(U256::from(2));
+        assert_eq!(vm.get_storage(U256::from(4)), B32::from(U256::from(1)));
+        assert_eq!(vm.get_storage(U256::from(53)), B32::from(U256::from(2)));
+
+        // A field added by the next release (slot 61) leaves every existing
+        // slot untouched
+        vm.set_storage(U256::from(61), B32::repeat_byte(0xfe));
+        let after = vm.snapshot().storage;
+        assert_eq!(after.len(), before.len() + 3);
+        assert!(before.iter().all(|(slot, value)| after.get(slot) == Some(value)));
+        assert_eq!(registry.owner(), alice());
+        assert_eq!(registry.total_charts(), U256::from(41));
+        assert_eq!(registry.get_trusted_forwarder(), bob());
+        assert_eq!(registry.get_chart_version("sentinel".into()), U256::from(1));
+        assert!(registry.is_idempotency_key_used(alice(), key));
+        assert_eq!(registry.get_pending_facet(selector), (Address::repeat_byte(0xfa), effective_at.to::<u64>()));
+
+        // The sunset flag and successor share slot 56
+        registry.sunset(bob()).unwrap();
+        let packed = U256::from_be_slice(bob().as_slice()) << 8 | U256::from(1);
+        assert_eq!(vm.get_storage(U256::from(56)), B32::from(packed));
+    }
+
+    #[test]
+    fn test_verification_hook_best_effort() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        let hook = Address::repeat_byte(0x40);
+        let owner = Address::repeat_byte(0x01);
+
+        vm.set_sender(alice());
+        assert_eq!(registry.set_verification_hook(hook), Err(ChartRegistryError::NotOwner(NotOwner {})));
+        vm.set_sender(owner);
+        registry.set_verification_hook(hook).unwrap();
+        assert_eq!(registry.get_verification_hook(), hook);
+
+        let notified = |chart_id: &str, owner: Address| {
+            IChartVerificationHook::onChartVerifiedCall { chartKey: keccak256(chart_id.as_bytes()), owner }.abi_encode()
+        };
+        let failures = |vm: &TestVM| {
+            vm.get_emitted_logs()
+                .into_iter()
+                .filter(|(topics, _)| topics[0] == HookFailed::SIGNATURE_HASH)
+                .collect::<Vec<_>>()
+        };
+
+        // Registered verified: the hook accepts
+        vm.mock_call(hook, notified("verified", bob()), Ok(vec![]));
+        registry.register_chart("verified".into(), B32::repeat_byte(1), bob(), true).unwrap();
+        assert!(registry.is_zk_verified("verified".into()));
+        assert!(failures(&vm).is_empty());
+
+        // Marked verified later: TestVM answers unmocked calls with
+        // success, so this revert also shows the hook got the key and owner
+        registry.register_chart("later".into(), B32::repeat_byte(2), bob(), false).unwrap();
+        vm.mock_call(hook, notified("later", bob()), Err(b"RewardsPaused".to_vec()));
+        registry.mark_as_verified("later".into()).unwrap();
+        assert!(registry.is_zk_verified("later".into()));
+
+        let failed = failures(&vm);
+        assert_eq!(failed.len(), 1);
+        let (topics, data) = &failed[0];
+        assert_eq!(topics[1..], [B32::left_padding_from(hook.as_slice()), keccak256("later".as_bytes())]);
+        let event = HookFailed { hook, chart_key: keccak256("later".as_bytes()), reason: b"RewardsPaused".to_vec().into() };
+        assert_eq!(*data, event.encode_data());
+
+        // Marking a verified chart again reaches neither the log nor the hook
+        let verified = |vm: &TestVM| {
+            vm.get_emitted_logs().into_iter().filter(|(topics, _)| topics[0] == ChartVerified::SIGNATURE_HASH).count()
+        };
+        let before = verified(&vm);
+        registry.mark_as_verified("later".into()).unwrap();
+        registry.mark_as_verified("verified".into()).unwrap();
+        assert_eq!(verified(&vm), before);
+        assert_eq!(failures(&vm).len(), 1);
+
+        // Unverified registrations aren't reported
+        vm.mock_call(hook, notified("unverified", bob()), Err(b"Unexpected".to_vec()));
+        registry.register_chart("unverified".into(), B32::repeat_byte(3), bob(), false).unwrap();
+        assert_eq!(failures(&vm).len(), 1);
+
+        // Registered verified with an input: also reported
+        vm.mock_call(hook, notified("with-input", bob()), Err(b"RewardsPaused".to_vec()));
+        let input = B32::repeat_byte(9);
+        registry.register_chart_with_input("with-input".into(), B32::repeat_byte(4), bob(), true, input).unwrap();
+        assert!(registry.is_zk_verified("with-input".into()));
+        assert_eq!(failures(&vm).len(), 2);
+
+        // A zero hook isn't called
+        registry.set_verification_hook(Address::ZERO).unwrap();
+        vm.mock_call(Address::ZERO, notified("unhooked", bob()), Err(b"RewardsPaused".to_vec()));
+        registry.register_chart("unhooked".into(), B32::repeat_byte(5), bob(), true).unwrap();
+        assert_eq!(failures(&vm).len(), 2);
+    }
+
+    #[test]
+    fn test_forwarded_chart_belongs_to_appended_sender() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        let forwarder = Address::repeat_byte(0xf0);
+
+        vm.set_sender(bob());
+        assert_eq!(
+            registry.set_trusted_forwarder(forwarder),
+            Err(ChartRegistryError::NotOwner(NotOwner {}))
+        );
+        vm.set_sender(Address::repeat_byte(0x01));
+        registry.set_trusted_forwarder(forwarder).unwrap();
+        assert!(registry.is_trusted_forwarder(forwarder));
+        assert!(!registry.is_trusted_forwarder(bob()));
+
+        // The core's methods act for the appended sender
+        let facet = Address::repeat_byte(0xfa);
+        let register_selector = FixedBytes(IChartRegistry::registerChartWithPositionsCall::SELECTOR);
+        let set_facet = IChartRegistry::setFacetCall { facet, selectors: vec![register_selector] };
+        vm.set_sender(forwarder);
+        let calldata = [set_facet.abi_encode(), Address::repeat_byte(0x01).to_vec()].concat();
+        assert_eq!(crate::forwarder::route(&mut registry, &calldata), Ok(vec![]));
+        vm.set_block_timestamp(vm.block_timestamp() + FACET_DELAY);
+        assert_eq!(registry.get_facet(register_selector), facet);
+        let calldata = [set_facet.abi_encode(), alice().to_vec()].concat();
+        assert_eq!(crate::forwarder::route(&mut registry, &calldata), Err(ChartRegistryError::NotOwner(NotOwner {}).into()));
+
+        // Facets get the call with its suffix, so they resolve the same
+        // sender
+        let register = IChartRegistry::registerChartWithPositionsCall {
+            chartId: "relayed".into(),
+            packed: packed_day(0),
+            salt: B32::repeat_byte(0x5a),
+        };
+        let calldata = [register.abi_encode(), alice().to_vec()].concat();
+        vm.mock_delegate_call(facet, calldata.clone(), Ok(b"for alice".to_vec()));
+        assert_eq!(crate::forwarder::route(&mut registry, &calldata), Ok(b"for alice".to_vec()));
+
+        // Charts registered while a call is forwarded belong to the
+        // appended sender
+        registry.register_chart_with_positions("relayed".into(), packed_day(0), B32::repeat_byte(0x5a)).unwrap();
+        assert_eq!(registry.get_chart("relayed".into()).1, alice());
+        assert!(registry.get_user_charts(forwarder).is_empty());
+
+        // Direct calls are attributed to their sender as before
+        vm.set_sender(bob());
+        vm.mock_delegate_call(facet, register.abi_encode(), Ok(b"for bob".to_vec()));
+        assert_eq!(crate::forwarder::route(&mut registry, &register.abi_encode()), Ok(b"for bob".to_vec()));
+        registry.register_chart_with_positions("direct".into(), packed_day(0), B32::repeat_byte(0x5a)).unwrap();
+        assert_eq!(registry.get_chart("direct".into()).1, bob());
+    }
+
+    /// Run `calldata` through the registry's router
+    fn call(registry: &mut ChartRegistry, calldata: &[u8]) -> Option<Result<Vec<u8>, Vec<u8>>> {
+        let (selector, input) = calldata.split_first_chunk::<4>().unwrap();
+        <ChartRegistry as Router<ChartRegistry>>::route(registry, u32::from_be_bytes(*selector), input)
+    }
+
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_sdk_selectors_route() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        let facet = Address::repeat_byte(0xfa);
+        vm.set_sender(Address::repeat_byte(0x01));
+
+        // Empty input reaches the decoder or the function, never "no route",
+        // for every function of the SDK's interface the core implements;
+        // the others reach the facet set for them
+        let selectors = IChartRegistry::IChartRegistryCalls::SELECTORS;
+        registry.set_facet(facet, selectors.iter().map(|selector| FixedBytes(*selector)).collect()).unwrap();
+        vm.set_block_timestamp(vm.block_timestamp() + FACET_DELAY);
+        let core = [
+            IChartRegistry::initializeCall::SELECTOR,
+            IChartRegistry::ownerCall::SELECTOR,
+            IChartRegistry::setTrustedForwarderCall::SELECTOR,
+            IChartRegistry::getTrustedForwarderCall::SELECTOR,
+            IChartRegistry::isTrustedForwarderCall::SELECTOR,
+            IChartRegistry::setRegistrarCall::SELECTOR,
+            IChartRegistry::isRegistrarCall::SELECTOR,
+            IChartRegistry::getImplementationVersionCall::SELECTOR,
+            IChartRegistry::sunsetCall::SELECTOR,
+            IChartRegistry::isSunsetCall::SELECTOR,
+            IChartRegistry::successorCall::SELECTOR,
+            IChartRegistry::setVerificationHookCall::SELECTOR,
+            IChartRegistry::getVerificationHookCall::SELECTOR,
+            IChartRegistry::setFacetCall::SELECTOR,
+            IChartRegistry::getFacetCall::SELECTOR,
+            IChartRegistry::getPendingFacetCall::SELECTOR,
+        ];
+        for selector in selectors {
+            assert_eq!(call(&mut registry, selector).is_some(), core.contains(selector), "{selector:02x?}");
+            if !core.contains(selector) {
+                vm.mock_delegate_call(facet, selector.to_vec(), Ok(b"facet".to_vec()));
+                assert_eq!(crate::forwarder::route(&mut registry, selector), Ok(b"facet".to_vec()), "{selector:02x?}");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_hooks_route_only_with_feature() {
+        let vm = TestVM::default();
+        let mut registry = ChartRegistry::from(&vm);
+
+        for signature in [
+            "testForceChartVectorEntry(address,string)",
+            "testSetChartVectorEntry(address,uint256,string)",
+            "testSetStoredChartId(string,string)",
+        ] {
+            let selector = keccak256(signature);
+            assert_eq!(call(&mut registry, &selector[..4]).is_some(), cfg!(feature = "test-hooks"), "{signature}");
+        }
+    }
+
+    #[test]
+    fn test_sdk_calldata_round_trip() {
+        let vm = TestVM::default();
+        let mut registry = ChartRegistry::from(&vm);
+        vm.set_block_timestamp(1_700_000_000);
+
+        // The builders' calldata decodes to the arguments the methods take
+        let calldata = sdk::encode_register_chart("chart-1", [7; 32], alice(), true);
+        let register = IChartRegistry::registerChartCall::abi_decode(&calldata, true).unwrap();
+        registry.register_chart(register.chartId, register.chartHash, register.user, register.zkVerified).unwrap();
+
+        let get = IChartRegistry::getChartCall::abi_decode(&sdk::encode_get_chart("chart-1"), true).unwrap();
+        // Encoded the way the router returns it, see `sdk::decode_chart`
+        let chart = alloy_sol_types::SolValue::abi_encode(&registry.get_chart(get.chartId));
+        assert_eq!(
+            sdk::decode_chart(&chart).unwrap(),
+            sdk::Chart {
+                chart_hash: [7; 32],
+                user: alice(),
+                timestamp: U256::from(1_700_000_000),
+                zk_verified: true,
+                chart_id: "chart-1".into(),
+            }
+        );
+    }
+
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_facets() {
+        let vm = TestVM::default();
+        let mut registry = registry(&vm);
+        let owner = Address::repeat_byte(0x01);
+        let facet = Address::repeat_byte(0xfa);
+        let register_selector = FixedBytes(IChartRegistry::registerChartCall::SELECTOR);
+        let get_selector = FixedBytes(IChartRegistry::getChartCall::SELECTOR);
+
+        vm.set_sender(bob());
+        assert_eq!(registry.set_facet(facet, vec![register_selector]), Err(ChartRegistryError::NotOwner(NotOwner {})));
+        vm.set_sender(owner);
+        registry.set_facet(facet, vec![register_selector, get_selector]).unwrap();
+        // Logged once per selector
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 2);
+        let scheduled = FacetScheduled { selector: register_selector, facet, effective_at: NOW + FACET_DELAY };
+        assert_eq!(logs[0], (scheduled.encode_topics().into_iter().map(|topic| topic.0).collect(), scheduled.encode_data()));
+
+        // Facets take over only after the delay
+        let register = sdk::encode_register_chart("chart-1", [7; 32], alice(), true);
+        assert_eq!(registry.get_facet(register_selector), Address::ZERO);
+        assert_eq!(registry.get_pending_facet(register_selector), (facet, NOW + FACET_DELAY));
+        vm.set_block_timestamp(NOW + FACET_DELAY - 1);
+        assert_eq!(crate::forwarder::route(&mut registry, &register), Err(vec![]));
+        vm.set_block_timestamp(NOW + FACET_DELAY);
+        assert_eq!(registry.get_facet(register_selector), facet);
+        assert_eq!(registry.get_facet(get_selector), facet);
+        assert_eq!(registry.get_pending_facet(register_selector), (Address::ZERO, 0));
+
+        // Calls outside the core are delegated through the fallback, and
+        // the facet's revert data passed on
+        vm.mock_delegate_call(facet, register.clone(), Err(ChartRegistryError::ChartAlreadyExists(ChartAlreadyExists {}).into()));
+        let reverted: Vec<u8> = ChartRegistryError::ChartAlreadyExists(ChartAlreadyExists {}).into();
+        assert_eq!(crate::forwarder::route(&mut registry, &register), Err(reverted.clone()));
+        assert_eq!(registry.delegate_to_facet(&register), Err(reverted));
+
+        // Unset selectors revert with no data, as unknown ones do
+        registry.set_facet(Address::ZERO, vec![get_selector]).unwrap();
+        assert_eq!(registry.get_facet(get_selector), facet);
+        vm.set_block_timestamp(NOW + 2 * FACET_DELAY);
+        assert_eq!(registry.get_facet(get_selector), Address::ZERO);
+        assert_eq!(crate::forwarder::route(&mut registry, &sdk::encode_get_chart("chart-1")), Err(vec![]));
+        assert_eq!(crate::forwarder::route(&mut registry, &[0xde, 0xad, 0xbe, 0xef]), Err(vec![]));
+
+        registry.sunset(Address::ZERO).unwrap();
+        assert_eq!(registry.set_facet(facet, vec![get_selector]), Err(ChartRegistryError::ContractSunset(ContractSunset {})));
+    }
+}
@@ -0,0 +1,560 @@
+//! Pedersen commitments over the BN254 (alt_bn128) G1 group
+//!
+//! Birth data commitments of the form `C = v·G + r·H`:
+//!
+//! - `v` is the committed value and `r` the blinding factor (both mod n)
+//! - `G` is the standard generator (1, 2) used by the ecAdd/ecMul precompiles
+//! - `H` is derived by hash-to-curve from `H_DOMAIN`, so nobody knows log_G(H)
+//!
+//! Commitments are additively homomorphic: commit(a, r) + commit(b, s) ==
+//! commit(a + b, r + s), which lets future circuits prove relations over the
+//! committed values without a new commitment format.
+//!
+//! Points use the EIP-196 encoding (affine x, y with the point at infinity
+//! as (0, 0)), so they can be handed to the precompiles unchanged. The
+//! contract computes commitments on the ecAdd/ecMul precompiles through
+//! [`Precompiles`] and [`commit_with`]. The pure Rust group arithmetic
+//! ([`Native`], [`commit`], [`verify_opening`]) is a fallback for clients
+//! and tests, compiled only with the `prover` feature or in tests.
+
+use alloc::vec::Vec;
+
+use stylus_sdk::alloy_primitives::{address, keccak256, uint, Address, U256};
+use stylus_sdk::stylus_core::{calls::context::Call, Host};
+
+use crate::field;
+
+/// Base field modulus p of BN254
+pub const FIELD_MODULUS: U256 = uint!(
+    21888242871839275222246405745257275088696311157297823662689037894645226208583_U256
+);
+
+/// Order n of the BN254 G1 group (scalars are reduced mod n)
+///
+/// Equal to the scalar field modulus r, see `field`.
+pub const GROUP_ORDER: U256 = field::MODULUS;
+
+/// Curve constant b in y² = x³ + b
+const CURVE_B: U256 = uint!(3_U256);
+
+/// 2^512 mod p, taking Montgomery products back to plain ones
+const MONTGOMERY_R2: U256 = uint!(
+    3096616502983703923843567936837374451735540968419076528771170197431451843209_U256
+);
+
+/// -p⁻¹ mod 2^64, the Montgomery reduction constant
+const MONTGOMERY_INV: u64 = 9786893198990664585;
+
+/// Domain tag for deriving the blinding generator H
+pub const H_DOMAIN: &[u8] = b"ZKAstro.Pedersen.H.v1";
+
+/// Value generator G = (1, 2)
+pub const G: G1Point = G1Point {
+    x: uint!(1_U256),
+    y: uint!(2_U256),
+};
+
+/// Blinding generator H = derive_generator(H_DOMAIN)
+///
+/// First counter (0) whose x = keccak256(H_DOMAIN || counter_be32) mod p
+/// lands on the curve, with the even square root chosen for y.
+pub const H: G1Point = G1Point {
+    x: uint!(
+        0x2192cb742c4ebc2d1fcc15cb214cc8f0888e5d810657321bacb5e5f844b37aa2_U256
+    ),
+    y: uint!(
+        0x2521e8930286d020f7508854bb15334bde1aba5e1bc6afe809db701e35979754_U256
+    ),
+};
+
+/// Bit 255 of a compressed point marks an odd y coordinate
+const COMPRESSED_Y_ODD: usize = 255;
+
+/// Affine point on BN254 G1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct G1Point {
+    pub x: U256,
+    pub y: U256,
+}
+
+/// Point in Jacobian coordinates (X/Z², Y/Z³), used internally to avoid
+/// an inversion per group operation
+#[cfg(any(test, feature = "prover"))]
+#[derive(Clone, Copy)]
+struct Jacobian {
+    x: U256,
+    y: U256,
+    z: U256,
+}
+
+/// a + b mod p, for a and b below p
+fn fadd(a: U256, b: U256) -> U256 {
+    // p < 2^254, so the sum can't overflow; `add_mod` would link U256
+    // division for the case where it does
+    let sum = a + b;
+    if sum >= FIELD_MODULUS {
+        sum - FIELD_MODULUS
+    } else {
+        sum
+    }
+}
+
+#[cfg(any(test, feature = "prover"))]
+fn fsub(a: U256, b: U256) -> U256 {
+    fadd(a, FIELD_MODULUS - b)
+}
+
+/// a · b mod p, for a and b below p
+fn fmul(a: U256, b: U256) -> U256 {
+    // Two Montgomery products (a·b·2^-256, then times 2^512·2^-256) are
+    // far less code than `mul_mod`'s 512-bit division
+    let product = a.mul_redc(b, FIELD_MODULUS, MONTGOMERY_INV);
+    product.mul_redc(MONTGOMERY_R2, FIELD_MODULUS, MONTGOMERY_INV)
+}
+
+/// a^exp mod p, for a below p
+fn fpow(mut a: U256, exp: U256) -> U256 {
+    // Square and multiply over `fmul`, as `pow_mod` goes through `mul_mod`
+    let mut result = U256::from(1);
+    for i in 0..exp.bit_len() {
+        if exp.bit(i) {
+            result = fmul(result, a);
+        }
+        a = fmul(a, a);
+    }
+    result
+}
+
+/// Square root mod p (p ≡ 3 mod 4), if one exists
+fn fsqrt(a: U256) -> Option<U256> {
+    let exp = (FIELD_MODULUS + U256::from(1)) >> 2;
+    let root = fpow(a, exp);
+    if fmul(root, root) == a {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+/// Right-hand side of the curve equation: x³ + 3
+fn curve_rhs(x: U256) -> U256 {
+    fadd(fmul(fmul(x, x), x), CURVE_B)
+}
+
+#[cfg(any(test, feature = "prover"))]
+impl Jacobian {
+    const INFINITY: Jacobian = Jacobian {
+        x: U256::ZERO,
+        y: U256::from_limbs([1, 0, 0, 0]),
+        z: U256::ZERO,
+    };
+
+    fn is_infinity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    fn double(&self) -> Jacobian {
+        if self.is_infinity() || self.y.is_zero() {
+            return Jacobian::INFINITY;
+        }
+        // dbl-2009-l (a = 0)
+        let a = fmul(self.x, self.x);
+        let b = fmul(self.y, self.y);
+        let c = fmul(b, b);
+        let xb = fadd(self.x, b);
+        let d = fsub(fsub(fmul(xb, xb), a), c);
+        let d = fadd(d, d);
+        let e = fadd(fadd(a, a), a);
+        let f = fmul(e, e);
+        let x3 = fsub(f, fadd(d, d));
+        let c8 = fadd(c, c);
+        let c8 = fadd(c8, c8);
+        let c8 = fadd(c8, c8);
+        let y3 = fsub(fmul(e, fsub(d, x3)), c8);
+        let yz = fmul(self.y, self.z);
+        Jacobian {
+            x: x3,
+            y: y3,
+            z: fadd(yz, yz),
+        }
+    }
+
+    fn add(&self, other: &Jacobian) -> Jacobian {
+        if self.is_infinity() {
+            return *other;
+        }
+        if other.is_infinity() {
+            return *self;
+        }
+        // add-2007-bl
+        let z1z1 = fmul(self.z, self.z);
+        let z2z2 = fmul(other.z, other.z);
+        let u1 = fmul(self.x, z2z2);
+        let u2 = fmul(other.x, z1z1);
+        let s1 = fmul(fmul(self.y, other.z), z2z2);
+        let s2 = fmul(fmul(other.y, self.z), z1z1);
+        let h = fsub(u2, u1);
+        let r = fsub(s2, s1);
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Jacobian::INFINITY
+            };
+        }
+        let h2 = fadd(h, h);
+        let i = fmul(h2, h2);
+        let j = fmul(h, i);
+        let r = fadd(r, r);
+        let v = fmul(u1, i);
+        let x3 = fsub(fsub(fmul(r, r), j), fadd(v, v));
+        let s1j = fmul(s1, j);
+        let y3 = fsub(fmul(r, fsub(v, x3)), fadd(s1j, s1j));
+        let z12 = fadd(self.z, other.z);
+        let z3 = fmul(fsub(fsub(fmul(z12, z12), z1z1), z2z2), h);
+        Jacobian {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    fn to_affine(self) -> G1Point {
+        if self.is_infinity() {
+            return G1Point::INFINITY;
+        }
+        // z is non-zero and p is prime, so the inverse always exists
+        let z_inv = self.z.inv_mod(FIELD_MODULUS).unwrap_or_default();
+        let z_inv2 = fmul(z_inv, z_inv);
+        G1Point {
+            x: fmul(self.x, z_inv2),
+            y: fmul(self.y, fmul(z_inv2, z_inv)),
+        }
+    }
+}
+
+impl G1Point {
+    /// Point at infinity in the precompile encoding
+    pub const INFINITY: G1Point = G1Point {
+        x: U256::ZERO,
+        y: U256::ZERO,
+    };
+
+    pub fn new(x: U256, y: U256) -> Self {
+        G1Point { x, y }
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self.x.is_zero() && self.y.is_zero()
+    }
+
+    /// Check coordinates are canonical and satisfy y² = x³ + 3
+    ///
+    /// The point at infinity is considered valid.
+    pub fn is_on_curve(&self) -> bool {
+        if self.is_infinity() {
+            return true;
+        }
+        if self.x >= FIELD_MODULUS || self.y >= FIELD_MODULUS {
+            return false;
+        }
+        fmul(self.y, self.y) == curve_rhs(self.x)
+    }
+
+    #[cfg(any(test, feature = "prover"))]
+    fn to_jacobian(self) -> Jacobian {
+        if self.is_infinity() {
+            return Jacobian::INFINITY;
+        }
+        Jacobian {
+            x: self.x,
+            y: self.y,
+            z: U256::from(1),
+        }
+    }
+
+    /// Additive inverse, (x, p - y)
+    pub fn neg(&self) -> G1Point {
+        if self.is_infinity() {
+            return *self;
+        }
+        G1Point { x: self.x, y: FIELD_MODULUS - self.y }
+    }
+
+    /// Group addition (ecAdd)
+    #[cfg(any(test, feature = "prover"))]
+    pub fn add(&self, other: &G1Point) -> G1Point {
+        self.to_jacobian().add(&other.to_jacobian()).to_affine()
+    }
+
+    /// Scalar multiplication (ecMul), scalar taken mod n
+    #[cfg(any(test, feature = "prover"))]
+    pub fn mul(&self, scalar: U256) -> G1Point {
+        let scalar = scalar.reduce_mod(GROUP_ORDER);
+        let base = self.to_jacobian();
+        let mut acc = Jacobian::INFINITY;
+        for i in (0..scalar.bit_len()).rev() {
+            acc = acc.double();
+            if scalar.bit(i) {
+                acc = acc.add(&base);
+            }
+        }
+        acc.to_affine()
+    }
+
+    /// Compress to 32 bytes: x with bit 255 set when y is odd
+    ///
+    /// x < p < 2^254, so the top bits are always free for the flag.
+    pub fn compress(&self) -> U256 {
+        let mut compressed = self.x;
+        if self.y.bit(0) {
+            compressed.set_bit(COMPRESSED_Y_ODD, true);
+        }
+        compressed
+    }
+
+    /// Recover a point from `compress`, rejecting x values off the curve
+    pub fn decompress(compressed: U256) -> Option<G1Point> {
+        let y_odd = compressed.bit(COMPRESSED_Y_ODD);
+        let mut x = compressed;
+        x.set_bit(COMPRESSED_Y_ODD, false);
+        if x.is_zero() && !y_odd {
+            return Some(G1Point::INFINITY);
+        }
+        if x >= FIELD_MODULUS {
+            return None;
+        }
+        let mut y = fsqrt(curve_rhs(x))?;
+        if y.bit(0) != y_odd {
+            y = FIELD_MODULUS - y;
+        }
+        Some(G1Point { x, y })
+    }
+
+    /// 64-byte precompile encoding: x || y, each 32 bytes big-endian
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.x.to_be_bytes::<32>());
+        bytes[32..].copy_from_slice(&self.y.to_be_bytes::<32>());
+        bytes
+    }
+
+    /// Parse the 64-byte precompile encoding, rejecting points off the curve
+    pub fn from_bytes(bytes: &[u8]) -> Option<G1Point> {
+        if bytes.len() != 64 {
+            return None;
+        }
+        let point = G1Point {
+            x: U256::from_be_slice(&bytes[..32]),
+            y: U256::from_be_slice(&bytes[32..]),
+        };
+        point.is_on_curve().then_some(point)
+    }
+}
+
+/// Source of G1 group operations
+///
+/// Lets verifiers run on the pure Rust arithmetic off-chain and on the
+/// EIP-196 precompiles inside the contract.
+pub trait G1Ops {
+    /// a + b, `None` if the backend rejects the inputs
+    fn ec_add(&self, a: &G1Point, b: &G1Point) -> Option<G1Point>;
+    /// scalar · p, `None` if the backend rejects the inputs
+    fn ec_mul(&self, p: &G1Point, scalar: U256) -> Option<G1Point>;
+}
+
+/// Pure Rust group operations
+#[cfg(any(test, feature = "prover"))]
+pub struct Native;
+
+#[cfg(any(test, feature = "prover"))]
+impl G1Ops for Native {
+    fn ec_add(&self, a: &G1Point, b: &G1Point) -> Option<G1Point> {
+        (a.is_on_curve() && b.is_on_curve()).then(|| a.add(b))
+    }
+
+    fn ec_mul(&self, p: &G1Point, scalar: U256) -> Option<G1Point> {
+        p.is_on_curve().then(|| p.mul(scalar))
+    }
+}
+
+/// ecAdd precompile (EIP-196)
+pub const EC_ADD: Address = address!("0000000000000000000000000000000000000006");
+
+/// ecMul precompile (EIP-196)
+pub const EC_MUL: Address = address!("0000000000000000000000000000000000000007");
+
+/// Group operations via the ecAdd/ecMul precompiles
+///
+/// Much cheaper on-chain than the pure Rust arithmetic.
+pub struct Precompiles<'a>(pub &'a dyn Host);
+
+impl Precompiles<'_> {
+    fn call(&self, to: Address, input: &[u8]) -> Option<G1Point> {
+        let output = self.0.static_call(&Call::new(), to, input).ok()?;
+        G1Point::from_bytes(&output)
+    }
+}
+
+impl G1Ops for Precompiles<'_> {
+    fn ec_add(&self, a: &G1Point, b: &G1Point) -> Option<G1Point> {
+        let mut input = [0u8; 128];
+        input[..64].copy_from_slice(&a.to_bytes());
+        input[64..].copy_from_slice(&b.to_bytes());
+        self.call(EC_ADD, &input)
+    }
+
+    fn ec_mul(&self, p: &G1Point, scalar: U256) -> Option<G1Point> {
+        let mut input = [0u8; 96];
+        input[..64].copy_from_slice(&p.to_bytes());
+        input[64..].copy_from_slice(&scalar.to_be_bytes::<32>());
+        self.call(EC_MUL, &input)
+    }
+}
+
+/// Derive a generator with unknown discrete log via try-and-increment
+///
+/// For counter = 0, 1, 2, ...: x = keccak256(domain || counter as u32 BE) mod p.
+/// The first x with x³ + 3 a quadratic residue wins, taking the even root as y.
+pub fn derive_generator(domain: &[u8]) -> G1Point {
+    let mut preimage = Vec::with_capacity(domain.len() + 4);
+    preimage.extend_from_slice(domain);
+    preimage.extend_from_slice(&[0u8; 4]);
+    let counter_at = domain.len();
+
+    let mut counter: u32 = 0;
+    loop {
+        preimage[counter_at..].copy_from_slice(&counter.to_be_bytes());
+        let digest = keccak256(&preimage);
+        let x = U256::from_be_bytes(digest.0).reduce_mod(FIELD_MODULUS);
+        if let Some(mut y) = fsqrt(curve_rhs(x)) {
+            if y.bit(0) {
+                y = FIELD_MODULUS - y;
+            }
+            return G1Point { x, y };
+        }
+        counter += 1;
+    }
+}
+
+/// v·G + r·H for `value` v and blinding factor r over `ops`, `None` if
+/// the backend rejects the inputs
+pub fn commit_with(ops: &impl G1Ops, value: U256, blinding: U256) -> Option<G1Point> {
+    let value = ops.ec_mul(&G, value)?;
+    let blinding = ops.ec_mul(&H, blinding)?;
+    ops.ec_add(&value, &blinding)
+}
+
+/// Commit to `value` with blinding factor `blinding` in pure Rust
+///
+/// Returns the affine point (x, y) of v·G + r·H.
+#[cfg(any(test, feature = "prover"))]
+pub fn commit(value: U256, blinding: U256) -> (U256, U256) {
+    let point = G.mul(value).add(&H.mul(blinding));
+    (point.x, point.y)
+}
+
+/// Check that `point` opens to (`value`, `blinding`) in pure Rust
+#[cfg(any(test, feature = "prover"))]
+pub fn verify_opening(point: (U256, U256), value: U256, blinding: U256) -> bool {
+    let point = G1Point::new(point.0, point.1);
+    if !point.is_on_curve() {
+        return false;
+    }
+    commit(value, blinding) == (point.x, point.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generators_on_curve() {
+        assert!(G.is_on_curve());
+        assert!(H.is_on_curve());
+        assert_ne!(G, H);
+    }
+
+    #[test]
+    fn test_h_matches_derivation() {
+        assert_eq!(derive_generator(H_DOMAIN), H);
+    }
+
+    #[test]
+    fn test_group_order() {
+        assert!(G.mul(GROUP_ORDER).is_infinity());
+        assert_eq!(G.mul(GROUP_ORDER + U256::from(1)), G);
+    }
+
+    #[test]
+    fn test_add_matches_double() {
+        assert_eq!(G.add(&G), G.mul(U256::from(2)));
+        assert!(G.add(&G.neg()).is_infinity());
+        assert_eq!(G.neg().neg(), G);
+        assert_eq!(G.add(&G.mul(U256::from(2))), G.mul(U256::from(3)));
+    }
+
+    #[test]
+    fn test_commit_open_round_trip() {
+        let value = U256::from(19_900_101u64);
+        let blinding = U256::from_be_bytes(keccak256(b"salt").0);
+        let point = commit(value, blinding);
+
+        assert!(G1Point::new(point.0, point.1).is_on_curve());
+        assert!(verify_opening(point, value, blinding));
+    }
+
+    #[test]
+    fn test_commit_with_matches_commit() {
+        let (value, blinding) = (U256::from(1990u64), U256::from(42u64));
+        let point = commit_with(&Native, value, blinding).unwrap();
+        assert_eq!((point.x, point.y), commit(value, blinding));
+    }
+
+    #[test]
+    fn test_wrong_blinding_rejected() {
+        let value = U256::from(1990u64);
+        let point = commit(value, U256::from(42u64));
+
+        assert!(!verify_opening(point, value, U256::from(43u64)));
+        assert!(!verify_opening(point, U256::from(1991u64), U256::from(42u64)));
+    }
+
+    #[test]
+    fn test_homomorphic_addition() {
+        let (a, r) = (U256::from(1900u64), U256::from(7u64));
+        let (b, s) = (U256::from(110u64), U256::from(11u64));
+        let ca = commit(a, r);
+        let cb = commit(b, s);
+
+        let sum = G1Point::new(ca.0, ca.1).add(&G1Point::new(cb.0, cb.1));
+        assert_eq!((sum.x, sum.y), commit(a + b, r + s));
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        for k in 1u64..6 {
+            let point = H.mul(U256::from(k));
+            assert_eq!(G1Point::decompress(point.compress()), Some(point));
+        }
+        assert_eq!(
+            G1Point::decompress(G1Point::INFINITY.compress()),
+            Some(G1Point::INFINITY)
+        );
+    }
+
+    #[test]
+    fn test_fmul_matches_mul_mod() {
+        let minus_one = FIELD_MODULUS - U256::from(1);
+        for (a, b) in [(U256::ZERO, minus_one), (U256::from(3), U256::from(5)), (minus_one, minus_one), (H.x, H.y)] {
+            assert_eq!(fmul(a, b), a.mul_mod(b, FIELD_MODULUS));
+            assert_eq!(fadd(a, b), a.add_mod(b, FIELD_MODULUS));
+        }
+    }
+
+    #[test]
+    fn test_off_curve_rejected() {
+        assert!(!G1Point::new(U256::from(1), U256::from(3)).is_on_curve());
+        assert!(!verify_opening((U256::from(1), U256::from(3)), U256::ZERO, U256::ZERO));
+    }
+}
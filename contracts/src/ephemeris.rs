@@ -0,0 +1,358 @@
+//! EphemerisOracle - daily planetary positions attested on-chain
+//!
+//! Updaters chosen by the owner publish one packed chart per day (see
+//! [`positions::pack_chart`]), keyed by the day's midnight UTC timestamp.
+//! A published day never changes, so predictions can refer to "today's
+//! transits" and anyone can check what they were.
+//!
+//! The crate has a single deployable entrypoint; build with the
+//! `ephemeris-oracle` feature to make this contract the entrypoint instead
+//! of `FarcasterPredictions`.
+
+use alloc::vec::Vec;
+
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256},
+    prelude::*,
+};
+
+use crate::astro;
+use crate::calendar;
+use crate::positions;
+
+type B32 = FixedBytes<32>;
+
+/// `find_ingresses` result: (dates, from_signs, to_signs)
+type Ingresses = (Vec<U256>, Vec<u8>, Vec<u8>);
+
+/// Most days `find_ingresses` walks per call
+pub const MAX_INGRESS_DAYS: u64 = 60;
+
+sol_declarations! {
+    pub(crate) const DECLARATIONS;
+
+    /// Positions of a day published by `updater`
+    event PositionsPublished(uint256 indexed date, address indexed updater, bytes32[2] packedPositions);
+}
+
+sol! {
+    /// External view of the oracle, as called by other contracts
+    interface IEphemerisOracle {
+        function hasPositions(uint256 date) external view returns (bool);
+        function getPositions(uint256 date) external view returns (bytes32[2]);
+    }
+}
+
+sol_storage! {
+    #[cfg_attr(feature = "ephemeris-oracle", entrypoint)]
+    pub struct EphemerisOracle {
+        // Contract owner (set once by initialize), and the flag it sets
+        address owner;
+        bool initialized;
+
+        // Accounts allowed to publish positions
+        mapping(address => bool) updaters;
+
+        // Packed positions per day: date => word
+        mapping(uint256 => bytes32) positions_first;
+        mapping(uint256 => bytes32) positions_second;
+        mapping(uint256 => bool) published;
+    }
+}
+
+#[public]
+impl EphemerisOracle {
+    /// Initialize the contract (once), setting its owner
+    pub fn initialize(&mut self, owner: Address) -> Result<(), Vec<u8>> {
+        if self.initialized.get() {
+            return Err(b"AlreadyInitialized".to_vec());
+        }
+        if owner == Address::ZERO {
+            return Err(b"InvalidOwner".to_vec());
+        }
+
+        self.initialized.set(true);
+        self.owner.set(owner);
+        Ok(())
+    }
+
+    /// Get the contract owner
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Allow or disallow `updater` to publish positions (owner only)
+    pub fn set_updater(&mut self, updater: Address, allowed: bool) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.owner.get() {
+            return Err(b"NotOwner".to_vec());
+        }
+        self.updaters.setter(updater).set(allowed);
+        Ok(())
+    }
+
+    /// Whether `account` may publish positions
+    pub fn is_updater(&self, account: Address) -> bool {
+        self.updaters.get(account)
+    }
+
+    /// Publish the positions of a day (updaters only, once per day)
+    ///
+    /// Parameters:
+    /// - date: Unix timestamp of the day at midnight UTC
+    /// - packed_positions: Chart packed with `positions::pack_chart`
+    pub fn publish_positions(&mut self, date: U256, packed_positions: [B32; 2]) -> Result<(), Vec<u8>> {
+        let updater = self.vm().msg_sender();
+        if !self.updaters.get(updater) {
+            return Err(b"NotUpdater".to_vec());
+        }
+
+        let day = u64::try_from(date).map_err(|_| b"InvalidDate".to_vec())?;
+        if calendar::align_to_midnight_utc(day) != day {
+            return Err(b"InvalidDate".to_vec());
+        }
+        if self.published.get(date) {
+            return Err(b"PositionsAlreadyPublished".to_vec());
+        }
+        positions::unpack_chart(&packed_positions).map_err(|_| b"InvalidPositions".to_vec())?;
+
+        self.positions_first.setter(date).set(packed_positions[0]);
+        self.positions_second.setter(date).set(packed_positions[1]);
+        self.published.setter(date).set(true);
+
+        log(self.vm(), PositionsPublished { date, updater, packedPositions: packed_positions });
+
+        Ok(())
+    }
+
+    /// Get the packed positions of a day (zero when unpublished)
+    pub fn get_positions(&self, date: U256) -> [B32; 2] {
+        [self.positions_first.get(date), self.positions_second.get(date)]
+    }
+
+    /// Whether positions were published for a day
+    pub fn has_positions(&self, date: U256) -> bool {
+        self.published.get(date)
+    }
+
+    /// Days from `start_date` to `end_date` (inclusive, at most
+    /// `MAX_INGRESS_DAYS`) on which `body` (0 = Sun ... 10 = Pluto)
+    /// changed sign
+    ///
+    /// Each published day is compared with the published day before it,
+    /// the day before `start_date` included, so unpublished days are
+    /// skipped. A retrograde body crossing a boundary back and forth is
+    /// reported at each crossing.
+    ///
+    /// Returns: (dates, from_signs, to_signs)
+    pub fn find_ingresses(
+        &self,
+        start_date: U256,
+        end_date: U256,
+        body: u8,
+    ) -> Result<Ingresses, Vec<u8>> {
+        let start = u64::try_from(start_date).map_err(|_| b"InvalidDate".to_vec())?;
+        let end = u64::try_from(end_date).map_err(|_| b"InvalidDate".to_vec())?;
+        if calendar::align_to_midnight_utc(start) != start || end < start {
+            return Err(b"InvalidDate".to_vec());
+        }
+        if (end - start) / calendar::SECONDS_PER_DAY >= MAX_INGRESS_DAYS {
+            return Err(b"RangeTooLong".to_vec());
+        }
+        if body as usize >= positions::CHART_BODIES {
+            return Err(b"InvalidBody".to_vec());
+        }
+
+        let mut days = Vec::new();
+        let first = start.saturating_sub(calendar::SECONDS_PER_DAY);
+        for day in (first..=end).step_by(calendar::SECONDS_PER_DAY as usize) {
+            let date = U256::from(day);
+            if !self.published.get(date) {
+                continue;
+            }
+            let chart = positions::unpack_chart(&self.get_positions(date)).map_err(|_| b"InvalidPositions".to_vec())?;
+            if let Some(position) = chart.iter().find(|position| position.body == body) {
+                days.push((day, position.longitude_centideg as u64));
+            }
+        }
+
+        let changes = astro::ingresses(days).map_err(|_| b"InvalidPositions".to_vec())?;
+        Ok((
+            changes.iter().map(|(date, _, _)| U256::from(*date)).collect(),
+            changes.iter().map(|(_, from, _)| *from).collect(),
+            changes.iter().map(|(_, _, to)| *to).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use positions::{PlanetPosition, CHART_BODIES};
+    use stylus_sdk::abi::Router;
+    use stylus_sdk::testing::*;
+    use zkastro_core::sdk::IEphemerisOracle;
+
+    // Routing needs top-level storage, which only the deployed entrypoint gets
+    #[cfg(not(feature = "ephemeris-oracle"))]
+    unsafe impl TopLevelStorage for EphemerisOracle {}
+
+    const DAY: u64 = calendar::SECONDS_PER_DAY;
+
+    /// Packed positions of a valid chart, with longitudes offset by `shift`
+    pub(crate) fn packed_day(shift: u16) -> [B32; 2] {
+        let chart: [PlanetPosition; CHART_BODIES] = core::array::from_fn(|i| PlanetPosition {
+            body: i as u8,
+            longitude_centideg: ((i as u32 * 3100 + shift as u32) % 36000) as u16,
+            is_retrograde: i >= 7,
+        });
+        positions::pack_chart(&chart).unwrap()
+    }
+
+    fn oracle_with_updater(vm: &TestVM, updater: Address) -> EphemerisOracle {
+        let mut oracle = EphemerisOracle::from(vm);
+        oracle.initialize(vm.msg_sender()).unwrap();
+        oracle.set_updater(updater, true).unwrap();
+        vm.set_sender(updater);
+        oracle
+    }
+
+    #[test]
+    fn test_initialize_once() {
+        let vm = TestVM::default();
+        let mut oracle = EphemerisOracle::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+
+        assert_eq!(oracle.initialize(Address::ZERO), Err(b"InvalidOwner".to_vec()));
+        oracle.initialize(owner).unwrap();
+        assert_eq!(oracle.owner(), owner);
+
+        // A later caller can't take over and choose the updaters
+        vm.set_sender(Address::repeat_byte(0x66));
+        assert_eq!(oracle.initialize(Address::repeat_byte(0x66)), Err(b"AlreadyInitialized".to_vec()));
+        assert_eq!(oracle.set_updater(Address::repeat_byte(0x66), true), Err(b"NotOwner".to_vec()));
+        assert_eq!(oracle.owner(), owner);
+    }
+
+    #[test]
+    fn test_publish_positions() {
+        let vm = TestVM::default();
+        let updater = Address::repeat_byte(0x0e);
+        let mut oracle = oracle_with_updater(&vm, updater);
+
+        let date = U256::from(20_000 * DAY);
+        assert!(!oracle.has_positions(date));
+        assert_eq!(oracle.publish_positions(date, packed_day(0)), Ok(()));
+        assert!(oracle.has_positions(date));
+        assert_eq!(oracle.get_positions(date), packed_day(0));
+        assert_eq!(oracle.get_positions(date + U256::from(DAY)), [B32::ZERO; 2]);
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].0[0], PositionsPublished::SIGNATURE_HASH);
+        assert_eq!(logs[0].0[1], B32::from(date));
+        assert_eq!(logs[0].0[2], B32::left_padding_from(updater.as_slice()));
+    }
+
+    #[test]
+    fn test_publish_rejects_duplicates_and_bad_input() {
+        let vm = TestVM::default();
+        let mut oracle = oracle_with_updater(&vm, Address::repeat_byte(0x0e));
+
+        let date = U256::from(20_000 * DAY);
+        oracle.publish_positions(date, packed_day(0)).unwrap();
+        assert_eq!(
+            oracle.publish_positions(date, packed_day(50)),
+            Err(b"PositionsAlreadyPublished".to_vec())
+        );
+        assert_eq!(oracle.get_positions(date), packed_day(0));
+
+        let next = date + U256::from(DAY);
+        assert_eq!(
+            oracle.publish_positions(next + U256::from(1), packed_day(0)),
+            Err(b"InvalidDate".to_vec())
+        );
+        assert_eq!(oracle.publish_positions(U256::MAX, packed_day(0)), Err(b"InvalidDate".to_vec()));
+
+        let mut bad = packed_day(0);
+        bad[1].0[31] = 1;
+        assert_eq!(oracle.publish_positions(next, bad), Err(b"InvalidPositions".to_vec()));
+        assert!(!oracle.has_positions(next));
+    }
+
+    #[test]
+    fn test_only_updaters_publish() {
+        let vm = TestVM::default();
+        let updater = Address::repeat_byte(0x0e);
+        let mut oracle = oracle_with_updater(&vm, updater);
+
+        let date = U256::from(20_000 * DAY);
+        vm.set_sender(Address::repeat_byte(0x66));
+        assert_eq!(oracle.publish_positions(date, packed_day(0)), Err(b"NotUpdater".to_vec()));
+        assert_eq!(oracle.set_updater(Address::repeat_byte(0x66), true), Err(b"NotOwner".to_vec()));
+
+        // Revoked updaters lose access
+        vm.set_sender(oracle.owner());
+        oracle.set_updater(updater, false).unwrap();
+        vm.set_sender(updater);
+        assert!(!oracle.is_updater(updater));
+        assert_eq!(oracle.publish_positions(date, packed_day(0)), Err(b"NotUpdater".to_vec()));
+    }
+
+    #[test]
+    fn test_find_ingresses() {
+        let vm = TestVM::default();
+        let mut oracle = oracle_with_updater(&vm, Address::repeat_byte(0x0e));
+        let start = 20_000 * DAY;
+        let day = |n: u64| U256::from(start + n * DAY);
+
+        // A month of Mercury: into Pisces on day 5, back into Aquarius
+        // while retrograde on day 12, into Pisces again on day 20. Day 15
+        // and days 25-29 weren't published.
+        let mercury = |n: u64| -> u16 {
+            match n {
+                0..=9 => 32_550 + n as u16 * 100,
+                10..=14 => 33_450 - (n as u16 - 9) * 180,
+                _ => 32_550 + (n as u16 - 14) * 80,
+            }
+        };
+        for n in (0..25).filter(|n| *n != 15) {
+            let chart: [PlanetPosition; CHART_BODIES] = core::array::from_fn(|i| PlanetPosition {
+                body: i as u8,
+                longitude_centideg: if i == 2 { mercury(n) } else { (i as u16 * 3100) % 36000 },
+                is_retrograde: i == 2 && (10..=14).contains(&n),
+            });
+            oracle.publish_positions(day(n), positions::pack_chart(&chart).unwrap()).unwrap();
+        }
+
+        let (aquarius, pisces) = (astro::PISCES - 1, astro::PISCES);
+        let (dates, from, to) = oracle.find_ingresses(day(0), day(29), 2).unwrap();
+        assert_eq!(dates, vec![day(5), day(12), day(20)]);
+        assert_eq!(from, vec![aquarius, pisces, aquarius]);
+        assert_eq!(to, vec![pisces, aquarius, pisces]);
+
+        // The day before the range counts; other bodies stay put
+        assert_eq!(oracle.find_ingresses(day(12), day(12), 2).unwrap().0, vec![day(12)]);
+        assert!(oracle.find_ingresses(day(13), day(19), 2).unwrap().0.is_empty());
+        assert!(oracle.find_ingresses(day(0), day(29), 3).unwrap().0.is_empty());
+
+        assert_eq!(oracle.find_ingresses(day(0), day(60), 2), Err(b"RangeTooLong".to_vec()));
+        assert_eq!(oracle.find_ingresses(day(0), day(59), 2).unwrap().0.len(), 3);
+        assert_eq!(oracle.find_ingresses(day(1), day(0), 2), Err(b"InvalidDate".to_vec()));
+        assert_eq!(oracle.find_ingresses(day(0) + U256::from(1), day(1), 2), Err(b"InvalidDate".to_vec()));
+        assert_eq!(oracle.find_ingresses(day(0), day(1), 11), Err(b"InvalidBody".to_vec()));
+    }
+
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_sdk_selectors_route() {
+        let vm = TestVM::default();
+        let mut oracle = EphemerisOracle::from(&vm);
+        
+        for selector in IEphemerisOracle::IEphemerisOracleCalls::SELECTORS {
+            let routed = <EphemerisOracle as Router<EphemerisOracle>>::route(&mut oracle, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "no route for {selector:02x?}");
+        }
+    }
+}
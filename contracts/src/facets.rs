@@ -0,0 +1,111 @@
+//! Contracts split across several deployed programs
+//!
+//! A Stylus program must fit in 24 KiB compressed, which FarcasterPredictions
+//! and ChartRegistry do not. Each is deployed as a core holding the storage,
+//! plus facets, each a build of the same contract with only some of its
+//! methods public (see the `farcaster-*` and `chart-registry-*` features).
+//! The core routes its own methods; any other selector is delegated to the
+//! facet the owner registered for it, which runs against the core's storage
+//! with the caller and value untouched.
+//!
+//! Every build has the same storage layout, so facets must be rebuilt and
+//! registered again whenever it changes.
+//!
+//! # Trust model
+//!
+//! A facet runs with the core's storage and balance, so registering one is
+//! an upgrade: a facet that isn't a build of this contract can rewrite any
+//! state or move any funds. Only the owner registers facets, and every
+//! registration, the first included, takes effect [`FACET_DELAY`] after it
+//! is made. Until then the current facet keeps serving the selector, and
+//! the pending one is public (`getPendingFacet`, plus the registration's
+//! event or admin log entry), so users, funders and monitors have the delay
+//! to check the new code against a build of the published source and stop
+//! using the contract if it doesn't match. Registering the current facet
+//! again cancels a pending change. The core's own methods, `setFacet`
+//! included, never reach a facet.
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U64},
+    prelude::*,
+    storage::{StorageAddress, StorageU64},
+    stylus_core::calls::{context::Call, errors::Error as CallError},
+    ArbResult,
+};
+
+/// Seconds between registering a facet and calls reaching it (2 days)
+pub const FACET_DELAY: u64 = 2 * 86_400;
+
+/// Facet of one selector, and the one replacing it after the delay
+#[storage]
+pub struct FacetSlot {
+    current: StorageAddress,
+    next: StorageAddress,
+    next_at: StorageU64,
+}
+
+impl FacetSlot {
+    /// Facet serving the selector at `now` (zero = none)
+    pub fn at(&self, now: u64) -> Address {
+        let next_at = self.next_at.get().to::<u64>();
+        if next_at != 0 && now >= next_at {
+            self.next.get()
+        } else {
+            self.current.get()
+        }
+    }
+
+    /// Facet registered to take over and when, if it hasn't by `now`
+    ///
+    /// Returns: (zero, 0) when no change is pending
+    pub fn pending(&self, now: u64) -> (Address, u64) {
+        let next_at = self.next_at.get().to::<u64>();
+        if next_at > now {
+            (self.next.get(), next_at)
+        } else {
+            (Address::ZERO, 0)
+        }
+    }
+
+    /// Register `facet` (zero = none) to take over [`FACET_DELAY`] after
+    /// `now`, replacing any change still pending
+    ///
+    /// Returns: when it takes over
+    pub fn schedule(&mut self, facet: Address, now: u64) -> u64 {
+        let current = self.at(now);
+        self.current.set(current);
+        let next_at = now.saturating_add(FACET_DELAY);
+        self.next.set(facet);
+        self.next_at.set(U64::from(next_at));
+        next_at
+    }
+}
+
+/// A contract whose methods are partly implemented by facets
+pub trait Faceted: HostAccess {
+    /// Facet implementing `selector` now (zero = none)
+    fn facet(&self, selector: FixedBytes<4>) -> Address;
+
+    /// Delegate `calldata` to the facet implementing its selector
+    ///
+    /// The facet's revert data is passed on unchanged.
+    /// Returns: None when the calldata has no selector or no facet
+    /// implements it
+    fn delegate(&mut self, calldata: &[u8]) -> Option<ArbResult> {
+        let (selector, _) = calldata.split_first_chunk::<4>()?;
+        let facet = self.facet(FixedBytes(*selector));
+        if facet == Address::ZERO {
+            return None;
+        }
+        // SAFETY: only the owner registers facets, behind FACET_DELAY (see
+        // the trust model above), and they are builds of this contract
+        // sharing its storage layout
+        let result = unsafe { self.vm().delegate_call(&Call::new(), facet, calldata) };
+        Some(result.map_err(|error| match error {
+            CallError::Revert(reason) => reason,
+            CallError::AbiDecodingFailed(_) => Vec::new(),
+        }))
+    }
+}
@@ -0,0 +1,95 @@
+//! Cross-language fixture tests
+//!
+//! Replays every JSON vector in `tests/fixtures/` (regenerate with
+//! `cargo run --bin gen-fixtures --features prover`) through the verifier,
+//! asserting the intermediate challenge as well as the final verdict.
+
+use std::{fs, path::PathBuf};
+
+use serde_json::Value;
+
+use crate::poseidon::{compute_challenge, to_hex, verify_zk_proof, Commitment, Encoding};
+use crate::prover;
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn load_fixtures() -> Vec<Value> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(fixtures_dir())
+        .expect("fixtures directory")
+        .map(|entry| entry.expect("fixture entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let contents = fs::read_to_string(path).expect("readable fixture");
+            serde_json::from_str(&contents).expect("valid fixture JSON")
+        })
+        .collect()
+}
+
+fn field<'a>(fixture: &'a Value, key: &str) -> &'a str {
+    fixture[key].as_str().unwrap_or_else(|| panic!("fixture field {key}"))
+}
+
+#[test]
+fn test_fixture_count() {
+    assert!(load_fixtures().len() >= 10);
+}
+
+#[test]
+fn test_fixtures_verify() {
+    for fixture in load_fixtures() {
+        let name = field(&fixture, "name");
+        let encoding = Encoding::from_name(field(&fixture, "encoding")).expect("known encoding");
+        let commitment = field(&fixture, "commitment");
+        let nonce = field(&fixture, "nonce");
+        let proof = field(&fixture, "proof");
+        let positions: Vec<u64> = fixture["positions"]
+            .as_array()
+            .expect("positions array")
+            .iter()
+            .map(|p| p.as_u64().expect("u64 position"))
+            .collect();
+
+        let valid = fixture["valid"].as_bool().expect("valid flag");
+        let Ok(commitment) = Commitment::from_hex(commitment) else {
+            assert!(!valid, "malformed commitment accepted in {name}");
+            continue;
+        };
+
+        // The challenge is always over the canonical lowercase commitment
+        let challenge = to_hex(&compute_challenge(&commitment.to_hex_lower(), &positions, encoding));
+        assert_eq!(
+            core::str::from_utf8(&challenge).unwrap(),
+            field(&fixture, "expected_challenge"),
+            "challenge mismatch in {name}"
+        );
+
+        assert_eq!(
+            verify_zk_proof(&commitment, proof, nonce, &positions, encoding),
+            valid,
+            "verdict mismatch in {name}"
+        );
+    }
+}
+
+#[test]
+fn test_fixtures_up_to_date() {
+    let on_disk = load_fixtures();
+    let generated = prover::fixtures();
+    assert_eq!(on_disk.len(), generated.len(), "run gen-fixtures to refresh");
+
+    for fixture in generated {
+        let file = on_disk
+            .iter()
+            .find(|f| f["name"] == fixture.name.as_str())
+            .unwrap_or_else(|| panic!("missing fixture {}", fixture.name));
+        assert_eq!(file["proof"], fixture.proof.proof.as_str(), "stale fixture {}", fixture.name);
+        assert_eq!(file["valid"], fixture.valid, "stale fixture {}", fixture.name);
+    }
+}
@@ -0,0 +1,152 @@
+//! ERC-2771 meta-transactions
+//!
+//! A trusted forwarder relays calls users signed off-chain, paying their
+//! gas, and appends the user's address to the calldata. Contracts take
+//! their caller from [`Forwarding::msg_sender`]: the appended address when
+//! the call came from their trusted forwarder, `msg.sender` otherwise.
+//!
+//! The suffix is split off in [`route`], before the arguments are decoded,
+//! so a forwarded call decodes exactly like a direct one. Calls from any
+//! other account are routed untouched, suffix or not. Calls a contract
+//! delegates to one of its facets (see `facets`) keep the suffix, which
+//! the facet splits off the same way.
+
+use core::cell::Cell;
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    abi::Router,
+    alloy_primitives::{Address, U256},
+    host::VM,
+    prelude::*,
+    ArbResult,
+};
+
+/// Length of the sender suffix a forwarder appends
+const SUFFIX_LEN: usize = 20;
+
+/// Sender a trusted forwarder appended to the current call
+///
+/// Takes no storage: it only lives for the call and is set by [`route`].
+pub struct ForwardedSender(Cell<Option<Address>>);
+
+impl StorageType for ForwardedSender {
+    type Wraps<'a>
+        = Option<Address>
+    where
+        Self: 'a;
+    type WrapsMut<'a>
+        = Option<Address>
+    where
+        Self: 'a;
+
+    const SLOT_BYTES: usize = 0;
+    const REQUIRED_SLOTS: usize = 0;
+
+    unsafe fn new(_slot: U256, _offset: u8, _host: VM) -> Self {
+        Self(Cell::new(None))
+    }
+
+    fn load<'s>(self) -> Self::Wraps<'s>
+    where
+        Self: 's,
+    {
+        self.0.get()
+    }
+
+    fn load_mut<'s>(self) -> Self::WrapsMut<'s>
+    where
+        Self: 's,
+    {
+        self.0.get()
+    }
+}
+
+/// A contract that accepts calls relayed by a trusted forwarder
+pub trait Forwarding: HostAccess {
+    /// The trusted forwarder (zero = none)
+    fn trusted_forwarder(&self) -> Address;
+
+    /// Sender appended to the current call, if it was forwarded
+    fn forwarded_sender(&self) -> &ForwardedSender;
+
+    /// Caller the current call acts for
+    fn msg_sender(&self) -> Address {
+        self.forwarded_sender().0.get().unwrap_or_else(|| self.vm().msg_sender())
+    }
+
+    /// `calldata` for a call on behalf of the current caller, with the
+    /// sender suffix appended when the current call was forwarded
+    fn forwardable(&self, calldata: &[u8]) -> Vec<u8> {
+        let mut forwardable = calldata.to_vec();
+        if let Some(sender) = self.forwarded_sender().0.get() {
+            forwardable.extend_from_slice(sender.as_slice());
+        }
+        forwardable
+    }
+}
+
+/// Split the sender suffix off `calldata` sent by `sender`
+///
+/// Only calls from a nonzero `forwarder` that are long enough to hold a
+/// selector and the suffix are forwarded.
+/// Returns: (forwarded sender, calldata without the suffix)
+pub fn split_sender(sender: Address, forwarder: Address, calldata: &[u8]) -> (Option<Address>, &[u8]) {
+    if forwarder == Address::ZERO || sender != forwarder || calldata.len() < 4 + SUFFIX_LEN {
+        return (None, calldata);
+    }
+    let (calldata, suffix) = calldata.split_at(calldata.len() - SUFFIX_LEN);
+    (Some(Address::from_slice(suffix)), calldata)
+}
+
+/// Route `calldata` to the contract, resolving a forwarded sender first
+///
+/// Same dispatch as the SDK's entrypoint for contracts without a receive
+/// function: calldata without a known selector goes to the fallback
+/// function, suffix included, and reverts with no data when there is none.
+pub fn route<S>(storage: &mut S, calldata: &[u8]) -> ArbResult
+where
+    S: Router<S, Storage = S> + TopLevelStorage + ValueDenier + Forwarding,
+{
+    let (sender, input) = split_sender(storage.vm().msg_sender(), storage.trusted_forwarder(), calldata);
+    storage.forwarded_sender().0.set(sender);
+
+    let routed = match input.split_first_chunk::<4>() {
+        Some((selector, input)) => S::route(storage, u32::from_be_bytes(*selector), input),
+        None => None,
+    };
+    routed.or_else(|| S::fallback(storage, calldata)).unwrap_or_else(|| Err(Vec::new()))
+}
+
+/// Entrypoint of a deployed contract that accepts forwarded calls
+pub fn entrypoint<S>(input: Vec<u8>, host: VM) -> ArbResult
+where
+    S: StorageType + Router<S, Storage = S> + TopLevelStorage + ValueDenier + Forwarding,
+{
+    // SAFETY: the contract is the only storage of the program, rooted at
+    // slot zero as in the SDK's entrypoint
+    let mut storage = unsafe { S::new(U256::ZERO, 0, host) };
+    route(&mut storage, &input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_sender() {
+        let forwarder = Address::repeat_byte(0xf0);
+        let user = Address::repeat_byte(0xa1);
+        let call = [0x12, 0x34, 0x56, 0x78, 0x9a];
+        let forwarded = [&call[..], user.as_slice()].concat();
+
+        assert_eq!(split_sender(forwarder, forwarder, &forwarded), (Some(user), &call[..]));
+        // Other callers' calldata is left alone, suffix or not
+        assert_eq!(split_sender(user, forwarder, &forwarded), (None, &forwarded[..]));
+        // No forwarder set: the zero address forwards nothing
+        assert_eq!(split_sender(Address::ZERO, Address::ZERO, &forwarded), (None, &forwarded[..]));
+        // Too short for a selector and a sender
+        assert_eq!(split_sender(forwarder, forwarder, &forwarded[2..]), (None, &forwarded[2..]));
+    }
+}
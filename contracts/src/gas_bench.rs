@@ -0,0 +1,248 @@
+//! Gas bounds for the hot paths (`cargo test --features gas-bench`)
+//!
+//! TestVM doesn't meter execution, so each call is priced by what it
+//! leaves behind: every slot it changed as a cold SSTORE and every log at
+//! LOG rates. Storage and logs are the bulk of these methods' cost, and a
+//! new write shows up as at least 5,000 gas. Views can only be checked to
+//! stay read-only.
+//!
+//! The table goes to stderr directly so it shows without `--nocapture`.
+//! Ratchet `BOUNDS` down when a path gets cheaper.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256, U8},
+    testing::*,
+};
+
+use crate::chart_registry::ChartRegistry;
+use crate::poseidon::Encoding;
+use crate::{prover, FarcasterPredictions};
+
+type B32 = FixedBytes<32>;
+
+/// Upper bound (gas) per benchmarked path, kept within 5,000 of the
+/// measurement so one extra SSTORE fails
+const BOUNDS: &[(&str, u64)] = &[
+    ("register_user", 99_000),
+    ("register_user_with_proof", 99_000),
+    ("store_prediction", 183_000),
+    ("rate_prediction", 176_000),
+    ("register_chart", 142_000),
+    ("verify_chart", 0),
+    ("oversize_nonce", 0),
+    ("oversize_positions", 0),
+];
+
+/// Largest gap, in percent of the measured ink, allowed between
+/// `estimate_batch_cost` and a measured batch
+const ESTIMATE_TOLERANCE_PERCENT: u64 = 5;
+
+/// Stylus ink per unit of gas
+const INK_PER_GAS: u64 = 10_000;
+
+/// SSTORE of a zero slot, cold (EIP-2929)
+const SSTORE_SET: u64 = 22_100;
+/// SSTORE of a non-zero slot, cold
+const SSTORE_RESET: u64 = 5_000;
+const LOG_BASE: u64 = 375;
+const LOG_TOPIC: u64 = 375;
+const LOG_DATA_BYTE: u64 = 8;
+
+const DATE: u64 = 20_000 * 86_400;
+
+struct Measurement {
+    name: &'static str,
+    slots_set: u64,
+    slots_reset: u64,
+    logs: u64,
+    gas: u64,
+}
+
+/// Price the storage and logs `f` produced
+fn measure(vm: &TestVM, name: &'static str, f: impl FnOnce()) -> Measurement {
+    let before: HashMap<U256, B32> = vm.snapshot().storage;
+    let logs_before = vm.get_emitted_logs().len();
+    f();
+
+    let mut measurement = Measurement { name, slots_set: 0, slots_reset: 0, logs: 0, gas: 0 };
+    for (slot, value) in vm.snapshot().storage {
+        match before.get(&slot).copied().unwrap_or_default() {
+            old if old == value => {}
+            old if old == B32::ZERO => measurement.slots_set += 1,
+            _ => measurement.slots_reset += 1,
+        }
+    }
+    measurement.gas = measurement.slots_set * SSTORE_SET + measurement.slots_reset * SSTORE_RESET;
+    for (topics, data) in &vm.get_emitted_logs()[logs_before..] {
+        measurement.logs += 1;
+        measurement.gas += LOG_BASE + LOG_TOPIC * topics.len() as u64 + LOG_DATA_BYTE * data.len() as u64;
+    }
+    measurement
+}
+
+/// Initialized contract where another user already went through every
+/// path, so global counters are warm as on a live deployment
+fn predictions(vm: &TestVM) -> FarcasterPredictions {
+    let mut contract = FarcasterPredictions::from(vm);
+    contract.initialize(vm.msg_sender()).unwrap();
+
+    let user = vm.msg_sender();
+    vm.set_sender(Address::repeat_byte(0x0b));
+    contract.register_user(B32::repeat_byte(0x0b)).unwrap();
+    contract.store_prediction(U256::from(DATE), B32::repeat_byte(0x0b)).unwrap();
+    contract.rate_prediction(U256::from(DATE), U8::from(5)).unwrap();
+    vm.set_sender(user);
+    contract
+}
+
+fn bench_predictions() -> Vec<Measurement> {
+    let vm = TestVM::default();
+    let mut contract = predictions(&vm);
+    let date = U256::from(DATE);
+    // Registration records a nonzero time
+    vm.set_block_timestamp(DATE);
+
+    vec![
+        measure(&vm, "register_user", || contract.register_user(B32::repeat_byte(0xab)).unwrap()),
+        measure(&vm, "store_prediction", || contract.store_prediction(date, B32::repeat_byte(0x01)).unwrap()),
+        measure(&vm, "rate_prediction", || contract.rate_prediction(date, U8::from(4)).unwrap()),
+    ]
+}
+
+fn bench_proof_registration() -> Vec<Measurement> {
+    let vm = TestVM::default();
+    let mut contract = predictions(&vm);
+
+    let positions = [28045, 12030, 30011, 33520, 21077, 4590, 17333];
+    let birth_data = prover::BirthData {
+        dob: "1990-01-01".into(),
+        tob: "12:00".into(),
+        tz: "UTC".into(),
+        lat: "51.5074".into(),
+        lon: "-0.1278".into(),
+    };
+    let commitment = prover::generate_commitment(&birth_data, [3u8; 32]);
+    let proof = prover::generate_proof(&commitment, "nonce", &positions, Encoding::Legacy);
+    let bytes = proof.to_bytes().unwrap();
+
+    let mut register = |nonce: Vec<u8>, positions: Vec<u64>| {
+        contract.register_user_with_proof(
+            bytes.commitment.into(),
+            B32::from(bytes.proof),
+            Bytes::from(nonce),
+            positions,
+            proof.scheme_version,
+            proof.encoding.id(),
+            proof.epoch,
+        )
+    };
+
+    // Oversized input is turned away before anything is hashed or stored
+    vec![
+        measure(&vm, "oversize_nonce", || assert!(register(vec![b'a'; 5_000], positions.to_vec()).is_err())),
+        measure(&vm, "oversize_positions", || assert!(register(bytes.nonce.clone(), vec![0; 1_000]).is_err())),
+        measure(&vm, "register_user_with_proof", || register(bytes.nonce.clone(), positions.to_vec()).unwrap()),
+    ]
+}
+
+fn bench_chart_registry() -> Vec<Measurement> {
+    let vm = TestVM::default();
+    let mut registry = ChartRegistry::from(&vm);
+    registry.initialize(vm.msg_sender()).unwrap();
+    let user = Address::repeat_byte(0xa1);
+    registry.register_chart("chart-0".into(), B32::repeat_byte(6), Address::repeat_byte(0x0b), true).unwrap();
+
+    vec![
+        measure(&vm, "register_chart", || {
+            registry.register_chart("chart-1".into(), B32::repeat_byte(7), user, true).unwrap()
+        }),
+        measure(&vm, "verify_chart", || {
+            assert!(registry.verify_chart("chart-1".into(), B32::repeat_byte(7)));
+        }),
+    ]
+}
+
+/// Measure storing `size` consecutive days for a fresh user, the writes of
+/// one `multicall` batch, next to its estimate
+fn bench_batch(size: u64) -> (Measurement, (U256, U256, U256)) {
+    let vm = TestVM::default();
+    let mut contract = predictions(&vm);
+    vm.set_block_timestamp(DATE);
+    let user = vm.msg_sender();
+    contract.register_user(B32::repeat_byte(0xab)).unwrap();
+
+    let dates: Vec<u64> = (1..=size).map(|i| DATE + i * 86_400).collect();
+    // The repeated first date is reported, not priced
+    let mut estimated = dates.iter().map(|date| U256::from(*date)).collect::<Vec<_>>();
+    estimated.push(U256::from(dates[0]));
+    let estimate = contract.estimate_batch_cost(user, estimated);
+
+    // The batch delegates each store to the predictions facet, which
+    // writes the same slots as a direct call
+    let measurement = measure(&vm, "store_prediction batch", || {
+        for date in &dates {
+            contract.store_prediction(U256::from(*date), B32::repeat_byte(0x01)).unwrap();
+        }
+    });
+    (measurement, estimate)
+}
+
+fn bound(name: &str) -> u64 {
+    BOUNDS
+        .iter()
+        .find(|(path, _)| *path == name)
+        .map(|(_, bound)| *bound)
+        .unwrap_or_else(|| panic!("no gas bound for {name}"))
+}
+
+#[test]
+fn gas_within_bounds() {
+    let mut measurements = bench_predictions();
+    measurements.extend(bench_proof_registration());
+    measurements.extend(bench_chart_registry());
+
+    let mut table = String::from(
+        "\npath                      new slots  updated  logs        gas            ink      bound\n",
+    );
+    for m in &measurements {
+        table += &format!(
+            "{:<25} {:>9} {:>8} {:>5} {:>10} {:>14} {:>10}\n",
+            m.name,
+            m.slots_set,
+            m.slots_reset,
+            m.logs,
+            m.gas,
+            m.gas * INK_PER_GAS,
+            bound(m.name),
+        );
+    }
+    std::io::stderr().write_all(table.as_bytes()).unwrap();
+
+    for m in &measurements {
+        assert!(m.gas <= bound(m.name), "{} costs {} gas, bound {}", m.name, m.gas, bound(m.name));
+    }
+}
+
+#[test]
+fn batch_estimate_within_tolerance() {
+    for size in [1, 8, 32] {
+        let (measured, (new_slots, duplicates, estimated_ink)) = bench_batch(size);
+        let measured_ink = measured.gas * INK_PER_GAS;
+        let estimated_ink = estimated_ink.to::<u64>();
+        std::io::stderr()
+            .write_all(format!("batch of {size:>2}: measured {measured_ink} ink, estimated {estimated_ink}\n").as_bytes())
+            .unwrap();
+
+        assert_eq!(new_slots, U256::from(measured.slots_set), "batch of {size}");
+        assert_eq!(duplicates, U256::from(1));
+        let gap = measured_ink.abs_diff(estimated_ink);
+        assert!(
+            gap * 100 <= measured_ink * ESTIMATE_TOLERANCE_PERCENT,
+            "batch of {size}: estimated {estimated_ink} ink, measured {measured_ink}"
+        );
+    }
+}
@@ -8,23 +8,409 @@
 
 #![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
 #![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+// The ABI export chains one iterator per public function
+#![cfg_attr(feature = "export-abi", recursion_limit = "512")]
 
 #[macro_use]
 extern crate alloc;
 
+// Native builds of the library (the bins link it) still need std's panic
+// handler and allocator; the WASM build leaves std out entirely
+#[cfg(all(not(target_arch = "wasm32"), not(any(test, feature = "export-abi"))))]
+extern crate std;
+
+/// Panics abort without formatting their message, which would link
+/// `core::fmt` into every contract
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
 use alloc::string::String;
 use alloc::vec::Vec;
 
+use alloy_sol_types::{sol, SolCall, SolError};
 use stylus_sdk::{
-    alloy_primitives::{Address, FixedBytes, U256, U8},
+    abi::{Bytes, Router},
+    alloy_primitives::{address, Address, FixedBytes, I16, U128, U256, U32, U64, U8},
+    crypto::keccak,
     prelude::*,
+    stylus_core::calls::{context::Call, errors::Error as CallError},
+};
+use zkastro_macros::public_if;
+
+#[macro_use]
+pub mod abi;
+pub mod chart_registry;
+pub mod commitment;
+pub mod ephemeris;
+pub mod facets;
+pub mod field;
+#[cfg(test)]
+mod fixtures;
+pub mod forwarder;
+pub mod math;
+#[cfg(all(test, feature = "gas-bench"))]
+mod gas_bench;
+#[cfg(any(test, feature = "mimc"))]
+pub mod mimc;
+pub mod poseidon;
+#[cfg(any(test, feature = "poseidon2"))]
+pub mod poseidon2;
+#[cfg(any(test, feature = "prover"))]
+pub mod prover;
+pub mod range_proof;
+pub mod reader;
+pub mod reentrancy;
+pub mod schnorr;
+#[cfg(test)]
+mod stats_props;
+
+// Each contract feature makes that contract the entrypoint; only one can be
+#[cfg(any(
+    all(feature = "farcaster", any(feature = "chart-registry", feature = "ephemeris-oracle", feature = "reader")),
+    all(feature = "chart-registry", any(feature = "ephemeris-oracle", feature = "reader")),
+    all(feature = "ephemeris-oracle", feature = "reader"),
+))]
+compile_error!("enable at most one of the farcaster, chart-registry, ephemeris-oracle and reader features");
+
+pub use zkastro_core::{
+    accumulator, admin_log, astro, attestation, birth_data, calendar, day_bitmap, decay, finalize, history, import,
+    legacy_hash, lucky, merkle, payload, positions, sealed_rating, seed, slots,
+};
+
+use commitment::{G1Ops, G1Point, Precompiles};
+use ephemeris::IEphemerisOracle;
+use facets::{FacetSlot, Faceted};
+use forwarder::{ForwardedSender, Forwarding};
+use reentrancy::{LockHeld, NonReentrant};
+use poseidon::{
+    BlockAnchor, Commitment, Encoding, InputError, ProofBytes, ProofError, ZkProof, LATEST_SCHEME_VERSION, SCHEME_V1,
 };
 
 type B32 = FixedBytes<32>;
 
+/// Parallel (exists, prediction_hash, rating) arrays of `get_predictions_multi`
+type PredictionColumns = (Vec<bool>, Vec<B32>, Vec<u8>);
+
+/// Storage layout version reported by `get_implementation_version`
+pub const IMPLEMENTATION_VERSION: u64 = 39;
+
+/// Declared slot of `user_commitments`, the mapping `commitment_slot`
+/// derives from
+pub const USER_COMMITMENTS_SLOT: u64 = 0;
+
+/// Declared slot of `predictions`, the nested mapping `prediction_slot`
+/// derives from
+pub const PREDICTIONS_SLOT: u64 = 2;
+
+// Errors encoded into revert data, events and returned structs, also
+// exported in the ABI
+sol_declarations! {
+    pub(crate) const DECLARATIONS;
+    
+    /// Proof scheme version outside the accepted range
+    error UnsupportedProofVersion(uint8 version);
+    
+    /// Proof bound to an epoch other than the current or previous one
+    error ProofExpired(uint64 epoch, uint64 currentEpoch);
+    
+    /// Prediction date `daysLate` days before the oldest one still open
+    error DateTooOld(uint256 daysLate);
+    
+    /// Anchor block not among the 256 blocks before the current one
+    error StaleAnchor(uint256 anchorBlock, uint256 currentBlock);
+    
+    /// Farcaster ID above `maxBetaFid` while the beta gate is on
+    error NotInBeta(uint256 fid, uint256 maxBetaFid);
+    
+    /// Proof input `field` (a `poseidon::INPUT_*` id) is too long, or for
+    /// positions, has a count outside `MIN_POSITIONS..=MAX_POSITIONS`
+    error InputTooLarge(uint8 field);
+    
+    /// Registration sent `sent` wei where the fee is `expected`
+    error IncorrectFee(uint256 expected, uint256 sent);
+    
+    /// Counter `field` (a `math::FIELD_*` id) can't grow any further
+    #[derive(Debug, PartialEq, Eq)]
+    error Overflow(uint8 field);
+    
+    /// User registered through the allowlist gate under `root`
+    event UserAdmitted(address indexed user, bytes32 indexed root);
+    
+    /// User's address linked to their Farcaster ID on an attestor's word
+    event FidLinked(address indexed user, uint256 indexed fid);
+    
+    /// Merkle root of a day's predictions posted by the backend
+    event DailyRootAnchored(uint256 indexed date, bytes32 root, uint256 count);
+    
+    /// User stored a prediction from an anchored daily root
+    event AnchoredPredictionClaimed(address indexed user, uint256 indexed date, uint32 day, bytes32 predictionHash);
+    
+    /// Reward points earned; activity 0 = registration, 1 = prediction,
+    /// 2 = rating, 3 = streak milestone
+    event PointsCredited(address indexed user, uint8 indexed activity, uint256 amount);
+    
+    /// Reward points taken back by the owner
+    event PointsDeducted(address indexed user, uint256 amount, bytes32 reasonHash);
+    
+    /// Owner changed the points per activity
+    event PointWeightsUpdated(uint32 registration, uint32 prediction, uint32 rating, uint32 streak);
+    
+    /// User deleted a prediction past the retention horizon; its stats
+    /// stay counted
+    event PredictionPruned(address indexed user, uint256 indexed date, uint32 day, bytes32 predictionHash);
+    
+    /// The prediction hook reverted; the prediction was stored anyway
+    event HookFailed(address indexed hook, address indexed user, uint256 date, bytes reason);
+    
+    /// User stored a prediction; replayed per date in log order, these
+    /// rebuild `getDailyAccumulator` (see `accumulator`)
+    event PredictionStored(address indexed user, uint256 indexed date, uint32 day, bytes32 predictionHash);
+    
+    /// User stored a prediction generated from one of their charts,
+    /// `chartKey` being keccak256 of the chart id
+    event PredictionAttributed(address indexed user, uint256 indexed date, uint32 day, bytes32 indexed chartKey);
+    
+    /// An astrologer co-signed a user's prediction
+    event PredictionEndorsed(address indexed user, uint256 indexed date, uint32 day, address indexed astrologer, bytes32 noteHash);
+    
+    /// The relayer proposed a prediction for a user, stored at
+    /// `finalizableAt` unless the user rejects it first
+    event PredictionProposed(address indexed user, uint256 indexed date, uint32 day, address indexed relayer, bytes32 predictionHash, uint256 finalizableAt);
+    
+    /// The user accepted a proposed prediction, which is now stored
+    event PredictionConfirmed(address indexed user, uint256 indexed date, uint32 day);
+    
+    /// The user turned down a proposed prediction
+    event PredictionRejected(address indexed user, uint256 indexed date, uint32 day);
+    
+    /// An unchallenged proposed prediction was stored
+    event PredictionFinalized(address indexed user, uint256 indexed date, uint32 day);
+    
+    /// A relayer write was skipped because its idempotency key was used
+    event DuplicateSuppressed(address indexed relayer, bytes32 indexed key);
+    
+    /// Prediction writes in the hour passed the owner's threshold, so
+    /// they were paused until the owner unpauses
+    event AutoPaused(uint256 indexed hourIndex, uint256 count);
+    
+    /// Owner froze all writes for good, pointing to the contract replacing
+    /// this one (zero = none)
+    event Sunset(uint256 timestamp, address indexed successor);
+    
+    /// Owner added a rating dimension
+    event DimensionRegistered(uint8 indexed dimensionId, bytes32 nameHash);
+    
+    /// User added a blinded rating to `modelId`'s aggregate; `commitment`
+    /// is the compressed Pedersen commitment
+    event BlindedRatingAdded(address indexed user, uint256 indexed date, uint32 day, uint32 indexed modelId, uint256 commitment);
+    
+    /// Owner opened `modelId`'s aggregate of `count` blinded ratings
+    event BlindedAggregateRevealed(uint32 indexed modelId, uint256 count, uint256 sum);
+    
+    /// A rater committed to a hidden rating of `target`'s prediction,
+    /// revealable from `revealAt` until `expiresAt`
+    event RatingCommitted(
+        address indexed rater,
+        address indexed target,
+        uint256 indexed date,
+        uint32 day,
+        uint64 revealAt,
+        uint64 expiresAt
+    );
+    
+    /// A rater revealed their committed rating, now in the community totals
+    event RatingRevealed(address indexed rater, address indexed target, uint256 indexed date, uint32 day, uint8 rating);
+    
+    /// A commitment went unrevealed past its window and was dropped
+    event RatingCommitmentExpired(address indexed rater, address indexed target, uint256 indexed date, uint32 day);
+    
+    /// A user's history root is final, for a later contract to import
+    /// against; `leaves` days went into it
+    event HistoryRootFinalized(address indexed user, bytes32 root, uint256 leaves);
+    
+    /// Owner rewrote a user's cached counters from their stored predictions
+    event CountersRepaired(address indexed user, uint256 totalPredictions, uint256 totalRatings, uint256 ratingSum);
+    
+    /// `partner` joined synastry pair `pairKey` with their chart `chartKey`
+    event PairJoined(bytes32 indexed pairKey, address indexed partner, bytes32 chartKey);
+    
+    /// A partner of pair `pairKey` stored its joint prediction for `date`
+    event PairPredictionStored(bytes32 indexed pairKey, uint256 indexed date, uint32 day, address indexed partner, bytes32 predictionHash);
+    
+    /// A partner of pair `pairKey` rated its joint prediction for `date`
+    /// (0 = cleared)
+    event PairPredictionRated(bytes32 indexed pairKey, uint256 indexed date, uint32 day, address indexed partner, uint8 rating);
+    
+    /// The global counters at the first `checkpoint` of day index `day`
+    event DailyCheckpoint(uint64 indexed day, uint256 totalUsers, uint256 globalPredictions, uint256 globalRatingCount, uint256 globalRatingSum);
+    
+    /// `funder` added `amount` wei to the sponsor pool, leaving it at `pool`
+    event SponsorshipFunded(address indexed funder, uint256 amount, uint256 pool);
+    
+    /// The sponsor pool paid `user`'s registration fee of `amount` wei
+    event RegistrationSponsored(address indexed user, uint256 amount, uint256 remainingPool);
+    
+    /// `funder` took back `amount` wei, their share of the sponsor pool
+    /// after sunset
+    event SponsorshipReclaimed(address indexed funder, uint256 amount);
+    
+    /// A user's prediction and rating totals, as `getUserStats` returns them
+    #[derive(Debug, PartialEq, Eq)]
+    struct UserStats {
+        uint256 totalPredictions;
+        uint256 totalRatings;
+        uint256 averageRatingX10;
+    }
+    
+    /// Everything stored for a user, with one page of their predictions;
+    /// `dates`, `hashes` and `ratings` are parallel
+    #[derive(Debug, PartialEq, Eq)]
+    struct UserExport {
+        bytes32 commitment;
+        bool registered;
+        uint256 registeredAt;
+        UserStats stats;
+        uint256[] dates;
+        bytes32[] hashes;
+        uint8[] ratings;
+    }
+}
+
+/// Activities that earn reward points, as logged by `PointsCredited`
+#[derive(Clone, Copy)]
+enum Activity {
+    Registration,
+    Prediction,
+    Rating,
+    Streak,
+}
+
+sol! {
+    /// Arbitrum system precompile, the source of L2 block numbers and hashes
+    interface IArbSys {
+        function arbBlockNumber() external view returns (uint256 number);
+        function arbBlockHash(uint256 arbBlockNum) external view returns (bytes32 hash);
+    }
+    
+    /// Chart registry holding users' packed natal positions
+    interface IChartRegistry {
+        function getUserCharts(address user) external view returns (string[] chartIds);
+        function getChartPositions(string chartId) external view returns (bool hasPositions, bytes32[2] packed);
+        function isZkVerified(string chartId) external view returns (bool);
+    }
+    
+    // Not `sol_interface!`: in SDK 0.9 its stubs call through the
+    // deprecated `stylus_sdk::call` functions, which go to the host
+    // directly rather than through `self.vm()`, so TestVM can't mock them
+    // and the hook tests would run different code than the chain does.
+    // `notify_hook` sends the `sol!` call type with `vm().call` instead,
+    // which also lets it cap the hook's gas and keep its revert data.
+    /// Contract notified of every stored prediction, e.g. to pay out
+    /// streak rewards
+    interface IPredictionHook {
+        function onPredictionStored(address user, uint256 date, uint256 streak) external;
+    }
+}
+
+/// Points for registering, storing a prediction, a first rating and each
+/// streak milestone, until the owner sets other weights
+pub const DEFAULT_POINT_WEIGHTS: (u32, u32, u32, u32) = (10, 1, 2, 5);
+
+/// Consecutive days of predictions per streak milestone
+pub const STREAK_MILESTONE: u64 = 7;
+
+/// Days before today a prediction can still be stored for, until the
+/// owner sets another limit
+pub const DEFAULT_MAX_BACKFILL_DAYS: u64 = 3;
+
+/// Most entries a paginated view returns per call
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Most users `have_predictions` and `get_predictions_multi` take
+pub const MAX_BATCH_USERS: usize = 100;
+
+/// Most settled entries one proposal drops from the front of its
+/// relayer's pending queue
+pub const QUEUE_CLEANUP_STEPS: usize = 8;
+
+/// Attestor role of the keys that sign FID links for `register_with_fid`
+pub const ATTESTOR_ROLE_FID: u8 = 0;
+
+/// Days until a rating weighs half as much in `get_ewma_rating`, until
+/// the owner sets another half-life
+pub const DEFAULT_RATING_HALF_LIFE_DAYS: u64 = 30;
+
+/// Most astrologers that can endorse one prediction
+pub const MAX_ENDORSEMENTS: usize = 5;
+
+/// Most days `get_predictions_range` covers per call
+pub const MAX_RANGE_DAYS: u64 = 366;
+
+/// Number of rating dimension ids (0 to 15)
+pub const MAX_DIMENSIONS: u8 = 16;
+
+/// Dimension of the single rating `rate_prediction` sets
+pub const LEGACY_DIMENSION: u8 = 0;
+
+/// Seconds after a rating commitment before it can be revealed, until
+/// the owner sets the reveal timing
+pub const DEFAULT_REVEAL_DELAY: u64 = 86_400;
+
+/// Seconds a rating commitment stays revealable, until the owner sets
+/// the reveal timing
+pub const DEFAULT_REVEAL_WINDOW: u64 = 7 * 86_400;
+
+/// Ink prices `estimate_batch_cost` charges, from EVM gas at 10,000 ink
+/// per gas
+pub struct InkCosts {
+    /// SSTORE of a zero slot, cold (22,100 gas)
+    pub slot_set: u64,
+    /// SSTORE of a non-zero slot, cold (5,000 gas)
+    pub slot_reset: u64,
+    /// LOG base price (375 gas)
+    pub log: u64,
+    /// Per log topic (375 gas)
+    pub log_topic: u64,
+    /// Per byte of log data (8 gas)
+    pub log_byte: u64,
+}
+
+pub const INK_COSTS: InkCosts = InkCosts {
+    slot_set: 221_000_000,
+    slot_reset: 50_000_000,
+    log: 3_750_000,
+    log_topic: 3_750_000,
+    log_byte: 80_000,
+};
+
+/// ArbSys precompile address
+const ARB_SYS: Address = address!("0000000000000000000000000000000000000064");
+
+/// ecrecover precompile address
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+
+/// How many blocks back an anchor may be (the blockhash window)
+const ANCHOR_WINDOW: u64 = 256;
+
+/// Gas given to the prediction hook, so a hook can't spend the gas the
+/// store needs to finish
+const HOOK_GAS: u64 = 200_000;
+
+/// Length of a circuit breaker bucket, in seconds
+const SECONDS_PER_HOUR: u64 = 3600;
+
+/// `rating_flags` bit set while the monthly totals hold the date's rating
+const RATED_MONTHLY: u8 = 1;
+
+/// Shift of the rating the global totals hold of a date (zero = none) in
+/// `rating_flags`
+const GLOBAL_RATING_SHIFT: u8 = 1;
+
 // Storage structure
 sol_storage! {
-    #[entrypoint]
     pub struct FarcasterPredictions {
         // User birth data commitments (ZK proof)
         mapping(address => bytes32) user_commitments;
@@ -45,203 +431,8997 @@ sol_storage! {
         // Global stats
         uint256 total_users;
         uint256 global_predictions;
-    }
-}
-
-#[public]
-impl FarcasterPredictions {
-    /// Register user with ZK proof of birth data
-    /// 
-    /// This stores a commitment to the user's birth data without revealing it.
-    /// The commitment can be used to verify predictions were generated from
-    /// the same birth data.
-    pub fn register_user(
-        &mut self,
-        commitment: B32,
-    ) -> Result<(), Vec<u8>> {
-        let user = self.vm().msg_sender();
         
-        if commitment == B32::ZERO {
-            return Err(b"InvalidCommitment".to_vec());
-        }
+        // Users whose commitment is a compressed Pedersen point
+        mapping(address => bool) pedersen_commitments;
         
-        // Check if user already registered
-        if self.user_has_data.get(user) {
-            return Err(b"UserAlreadyRegistered".to_vec());
-        }
+        // Contract owner (set once by initialize)
+        address owner;
         
-        // Store commitment
-        self.user_commitments.setter(user).set(commitment);
-        self.user_has_data.setter(user).set(true);
+        // Accepted proof scheme versions (inclusive range)
+        uint8 min_accepted_version;
+        uint8 max_accepted_version;
         
-        // Increment total users
-        let current_total = self.total_users.get();
-        self.total_users.set(current_total + U256::from(1));
+        // Proof scheme version each user registered with (0 = no proof)
+        mapping(address => uint8) user_scheme_versions;
         
-        Ok(())
-    }
-    
-    /// Store daily prediction on-chain
-    /// 
-    /// Parameters:
-    /// - date: Unix timestamp (midnight UTC) for the prediction date
-    /// - prediction_hash: Hash of the prediction text + lucky elements
-    pub fn store_prediction(
-        &mut self,
-        date: U256,
-        prediction_hash: B32,
-    ) -> Result<(), Vec<u8>> {
-        let user = self.vm().msg_sender();
+        // Merkle root of allowlisted commitment hashes (zero = open)
+        bytes32 allowlist_root;
         
-        // Check if user is registered
-        if !self.user_has_data.get(user) {
-            return Err(b"UserNotRegistered".to_vec());
-        }
+        // Account allowed to anchor daily roots besides the owner
+        address relayer;
         
-        if prediction_hash == B32::ZERO {
-            return Err(b"InvalidPredictionHash".to_vec());
-        }
+        // Daily prediction roots: date => root / number of leaves
+        mapping(uint256 => bytes32) daily_roots;
+        mapping(uint256 => uint256) daily_counts;
         
-        // Check if prediction already exists for this date
-        if self.prediction_exists.getter(user).get(date) {
-            return Err(b"PredictionAlreadyExists".to_vec());
-        }
+        // Predictions stored by claiming from a daily root
+        mapping(address => mapping(uint256 => bool)) anchored_predictions;
         
-        // Store prediction
-        let mut user_predictions = self.predictions.setter(user);
-        user_predictions.setter(date).set(prediction_hash);
+        // Birth-year bucket (decade index) of users who disclosed one
+        mapping(address => uint8) user_buckets;
+        mapping(address => bool) user_has_bucket;
         
-        let mut user_exists = self.prediction_exists.setter(user);
-        user_exists.setter(date).set(true);
+        // Rating aggregates per birth-year bucket
+        mapping(uint8 => uint256) bucket_total_ratings;
+        mapping(uint8 => uint256) bucket_rating_sum;
         
-        // Update user stats
-        let user_total = self.total_predictions.get(user);
-        self.total_predictions.setter(user).set(user_total + U256::from(1));
+        // Ephemeris oracle that must have positions for prediction dates
+        // (zero = no check)
+        address ephemeris_oracle;
         
-        // Update global stats
-        let global_total = self.global_predictions.get();
-        self.global_predictions.set(global_total + U256::from(1));
+        // Chart registry read for natal positions by score_transits
+        address chart_registry;
         
-        Ok(())
-    }
-    
-    /// Rate a prediction (0-5 stars)
-    /// 
-    /// Parameters:
-    /// - date: Unix timestamp for the prediction date
-    /// - rating: Rating value (0-5)
-    pub fn rate_prediction(
-        &mut self,
-        date: U256,
-        rating: U8,
-    ) -> Result<(), Vec<u8>> {
-        let user = self.vm().msg_sender();
+        // Set once by initialize
+        bool initialized;
         
-        // Validate rating
-        if rating > U8::from(5) {
-            return Err(b"InvalidRating".to_vec());
-        }
+        // Reserved so an upgraded implementation keeps this layout;
+        // new fields go after the gap
+        uint256[50] __gap;
         
-        // Check if prediction exists
-        if !self.prediction_exists.getter(user).get(date) {
-            return Err(b"PredictionNotFound".to_vec());
-        }
+        // ERC-2771 forwarder whose calls act for the sender they append
+        // (zero = none)
+        address trusted_forwarder;
         
-        // Check if already rated
-        let existing_rating = self.ratings.getter(user).get(date);
-        let is_new_rating = existing_rating == U8::ZERO;
+        // Reward points per user
+        mapping(address => uint256) points;
         
-        // Store rating
-        let mut user_ratings = self.ratings.setter(user);
-        user_ratings.setter(date).set(rating);
+        // Points per activity, `DEFAULT_POINT_WEIGHTS` until the owner
+        // sets them
+        uint32 registration_points;
+        uint32 prediction_points;
+        uint32 rating_points;
+        uint32 streak_points;
+        bool point_weights_set;
         
-        if is_new_rating {
-            // New rating
-            let user_total_ratings = self.total_ratings.get(user);
-            self.total_ratings.setter(user).set(user_total_ratings + U256::from(1));
-            
-            let user_rating_sum = self.rating_sum.get(user);
-            self.rating_sum.setter(user).set(user_rating_sum + U256::from(rating));
-        } else {
-            // Update existing rating
-            let user_rating_sum = self.rating_sum.get(user);
-            let new_sum = user_rating_sum - U256::from(existing_rating) + U256::from(rating);
-            self.rating_sum.setter(user).set(new_sum);
-        }
+        // Consecutive days of predictions per user
+        mapping(address => PredictionStreak) streaks;
         
-        Ok(())
+        // Ratings that earned points, so clearing and re-rating earns none
+        mapping(address => mapping(uint256 => bool)) rated_for_points;
+        
+        // Registration time per user (zero if registered before it was kept)
+        mapping(address => uint256) registered_at;
+        
+        // Dates of each user's predictions, in the order they were stored
+        // (from this version on)
+        mapping(address => uint256[]) prediction_dates;
+        
+        // Days a prediction is kept before its user may prune it
+        // (zero = pruning disabled)
+        uint64 min_retention_days;
+        
+        // Pruned predictions per user, still counted in their stats, and
+        // the pruned dates, which can't be stored again
+        mapping(address => uint256) pruned_count;
+        mapping(address => mapping(uint256 => bool)) pruned_predictions;
+        
+        // Ratings (count and sum) of each user's pruned predictions, still
+        // counted in their stats
+        mapping(address => uint256) pruned_ratings;
+        mapping(address => uint256) pruned_rating_sum;
+        
+        // Contract called after each stored prediction (zero = none)
+        address prediction_hook;
+        
+        // Predictions a user must have stored before taking part in
+        // community features
+        uint256 min_predictions_for_community;
+        
+        // Midnight-aligned prediction dates per user and year, see
+        // `day_bitmap` (from this version on)
+        mapping(address => mapping(uint32 => uint256[2])) prediction_days;
+        
+        // Set once by `sunset`, after which nothing is written
+        bool sunsetted;
+        address successor_contract;
+        
+        // Hash chain and count of the predictions stored per date, see
+        // `accumulator`; pruning leaves both as they were
+        mapping(uint256 => bytes32) daily_accumulators;
+        mapping(uint256 => uint256) daily_prediction_counts;
+        
+        // Astrologers the owner allows to endorse predictions
+        mapping(address => bool) astrologers;
+        
+        // Endorsements of each prediction, in the order they were made
+        // (at most `MAX_ENDORSEMENTS`)
+        mapping(address => mapping(uint256 => Endorsement[])) endorsements;
+        
+        // Set while a call that makes external calls runs, see
+        // `reentrancy`
+        bool reentrancy_locked;
+        
+        // Users whose actions stay out of global and bucket aggregates
+        mapping(address => bool) aggregate_opt_outs;
+        
+        // Rating still counted in the bucket aggregates for a prediction
+        // whose user rated it while opted out
+        mapping(address => mapping(uint256 => AggregatedRating)) aggregated_ratings;
+        
+        // Seconds a user has to reject a prediction the relayer proposed
+        // for them (zero = proposals disabled)
+        uint64 challenge_window;
+        
+        // Predictions proposed by the relayer, not yet stored
+        mapping(address => mapping(uint256 => PendingPrediction)) pending_predictions;
+        
+        // Idempotency keys each relayer (or the owner) has used
+        mapping(address => mapping(bytes32 => bool)) used_idempotency_keys;
+        
+        // Append-only trail of privileged writes
+        AdminLogEntry[] admin_log;
+        
+        // Time-decayed rating average per user, and its half-life in days
+        // (zero = DEFAULT_RATING_HALF_LIFE_DAYS)
+        mapping(address => RatingAverage) rating_averages;
+        uint64 rating_half_life_days;
+        
+        // Circuit breaker: prediction writes per hour since the epoch, the
+        // most allowed in one hour (zero = off) and whether it tripped
+        mapping(uint256 => uint256) writes_in_hour;
+        uint64 auto_pause_threshold;
+        bool paused;
+        
+        // Rating dimensions: name hash per id (zero = unregistered), and
+        // each user's ratings and totals; dimension 0 lives in `ratings`,
+        // `total_ratings` and `rating_sum` instead
+        mapping(uint8 => bytes32) dimension_names;
+        mapping(address => mapping(uint256 => mapping(uint8 => uint8))) dimension_ratings;
+        mapping(address => mapping(uint8 => RatingTotals)) dimension_stats;
+        
+        // Commit-reveal community ratings: rater => target => date =>
+        // commitment, the revealed ratings (target => date => rater) and
+        // their totals, and the reveal timing (zero window = defaults)
+        mapping(address => mapping(address => mapping(uint256 => RatingCommitment))) rating_commitments;
+        mapping(address => mapping(uint256 => mapping(address => uint8))) community_ratings;
+        mapping(address => mapping(uint256 => RatingTotals)) community_totals;
+        uint64 reveal_delay;
+        uint64 reveal_window;
+        
+        // History roots for migration: the build in progress and the last
+        // finalized root per user
+        mapping(address => HistoryBuild) history_builds;
+        mapping(address => HistoryRoot) history_roots;
+        
+        // Chart each prediction was generated from, when attributed:
+        // user => date => chart key
+        mapping(address => mapping(uint256 => bytes32)) prediction_charts;
+        
+        // Fixed UTC offset per user in minutes, for local date keys
+        // (zero = UTC)
+        mapping(address => int16) utc_offsets;
+        
+        // Days before today a prediction can be stored for,
+        // `DEFAULT_MAX_BACKFILL_DAYS` until the owner sets it
+        uint64 max_backfill_days;
+        bool max_backfill_days_set;
+        
+        // Ratings per user and month of the rated prediction's date
+        // (`calendar::month_index`), and per rated date what the monthly
+        // and global totals hold of it (`RATED_MONTHLY`,
+        // `GLOBAL_RATING_SHIFT`): ratings set before this version are only
+        // counted once edited
+        mapping(address => mapping(uint32 => RatingTotals)) monthly_ratings;
+        mapping(address => mapping(uint256 => uint8)) rating_flags;
+        
+        // FID attestor key of `set_fid_attestor` (one of the role's keys in
+        // `attestor_keys`), and the highest FID let in through
+        // `register_with_fid` (zero = any)
+        address fid_attestor;
+        uint256 max_beta_fid;
+        
+        // Farcaster IDs linked to addresses, one address per FID
+        mapping(address => uint256) user_fids;
+        mapping(uint256 => address) fid_owners;
+        
+        // Days after its date a prediction can be rated (zero = no limit)
+        uint64 rating_window_days;
+        
+        // Blinded ratings (see `range_proof`): each one's commitment,
+        // compressed, and per model their sum and what was last revealed
+        mapping(address => mapping(uint256 => uint256)) blinded_ratings;
+        mapping(uint32 => BlindedAggregate) blinded_aggregates;
+        
+        // Users who rated each day's predictions, by midnight UTC
+        mapping(uint256 => uint256) raters_per_date;
+        
+        // Attestor keys by role (see `ATTESTOR_ROLE_FID`): each role's
+        // registered keys, and each key's expiry and place in that list
+        mapping(uint8 => address[]) attestor_keys;
+        mapping(uint8 => mapping(address => AttestorEntry)) attestor_entries;
+        
+        // Ratings of all users who aren't opted out, and the day index
+        // (days since the epoch) of the last `checkpoint`
+        RatingTotals global_ratings;
+        uint64 last_checkpoint_day;
+        
+        // Synastry pairs by `pair_key`, and their joint predictions: each
+        // date's hash, each partner's rating of it, and per pair the count
+        // of predictions and the totals of both partners' ratings
+        mapping(bytes32 => SynastryPair) synastry_pairs;
+        mapping(bytes32 => mapping(uint256 => bytes32)) pair_predictions;
+        mapping(bytes32 => mapping(uint256 => mapping(address => uint8))) pair_ratings;
+        mapping(bytes32 => uint256) pair_prediction_counts;
+        mapping(bytes32 => RatingTotals) pair_rating_totals;
+        
+        // Registration fee in wei, the fees paid and not yet withdrawn,
+        // and the sponsor pool paying the fee for new users, at most
+        // `sponsorship_daily_cap` (zero = no cap) per day number
+        uint256 registration_fee;
+        uint256 collected_fees;
+        uint256 sponsor_pool;
+        uint64 sponsorship_daily_cap;
+        mapping(uint32 => uint64) sponsored_per_day;
+        
+        // Each relayer's proposals in the order made, see `get_finalizable`
+        mapping(address => PendingQueue) pending_queues;
+        
+        // Wei each funder put in the sponsor pool, and their sum, both less
+        // what `reclaim_sponsorship` already settled
+        mapping(address => uint256) sponsor_contributions;
+        uint256 sponsor_contributions_total;
+        
+        // Facet each selector this build does not route is delegated to,
+        // and the one taking over after `FACET_DELAY`, see `facets`
+        mapping(bytes4 => FacetSlot) facets;
+        
+        // Whether the current call holds that lock (no storage)
+        LockHeld reentrancy_held;
+        
+        // Sender of the current forwarded call (no storage)
+        ForwardedSender forwarded_sender;
     }
     
-    /// Get user's birth data commitment
-    pub fn get_user_commitment(&self, user: Address) -> B32 {
-        self.user_commitments.get(user)
+    // One slot per user
+    pub struct PredictionStreak {
+        // Last day counted (date / 1 day) and the streak's length up to it
+        uint64 last_day;
+        uint64 length;
     }
     
-    /// Check if user is registered
-    pub fn is_user_registered(&self, user: Address) -> bool {
-        self.user_has_data.get(user)
+    // Zero commitment = none; the times are fixed at commit
+    pub struct RatingCommitment {
+        bytes32 commitment;
+        uint64 reveal_at;
+        uint64 expires_at;
     }
     
-    /// Get prediction hash for a specific date
-    pub fn get_prediction(
-        &self,
-        user: Address,
-        date: U256,
-    ) -> B32 {
-        self.predictions.getter(user).get(date)
+    // Progress through the user's date list, leaves so far and the
+    // merkle frontier over them (see `merkle::frontier_push`)
+    pub struct HistoryBuild {
+        uint64 next_index;
+        uint64 leaf_count;
+        bytes32[] frontier;
     }
     
-    /// Check if prediction exists for a date
-    pub fn has_prediction(
-        &self,
-        user: Address,
-        date: U256,
-    ) -> bool {
-        self.prediction_exists.getter(user).get(date)
+    // Zero root = never finalized
+    pub struct HistoryRoot {
+        bytes32 root;
+        uint64 leaf_count;
+        uint64 finalized_at;
     }
     
-    /// Get rating for a specific date
-    pub fn get_rating(
-        &self,
-        user: Address,
-        date: U256,
-    ) -> U8 {
-        self.ratings.getter(user).get(date)
+    // Count and sum of ratings, in one slot
+    pub struct RatingTotals {
+        uint128 total_ratings;
+        uint128 rating_sum;
     }
     
-    /// Get user statistics
-    /// Returns: (total_predictions, total_ratings, average_rating_x10)
-    /// Note: average_rating is multiplied by 10 to avoid decimals
-    pub fn get_user_stats(&self, user: Address) -> (U256, U256, U256) {
-        let total_predictions = self.total_predictions.get(user);
-        let total_ratings = self.total_ratings.get(user);
-        let rating_sum = self.rating_sum.get(user);
+    // When an attestor key stops signing, its 1-based place in its
+    // role's key list (zero = not registered), and whether
+    // `set_fid_attestor` added it rather than `add_attestor`
+    pub struct AttestorEntry {
+        uint256 expires_at;
+        uint64 position;
+        bool fid_setter_added;
+    }
+    
+    // A synastry pair's chart keys, the lower first, and the owner that
+    // joined with each (zero until they do)
+    pub struct SynastryPair {
+        bytes32 chart_a;
+        bytes32 chart_b;
+        address owner_a;
+        address owner_b;
+    }
+    
+    // Sum of a model's blinded rating commitments as an affine point, how
+    // many there are, and the count and sum the owner last revealed
+    pub struct BlindedAggregate {
+        uint256 sum_x;
+        uint256 sum_y;
+        uint64 count;
+        uint64 revealed_count;
+        uint128 revealed_sum;
+    }
+    
+    // One slot: `rating` replaces the stored rating in the aggregates
+    // while `set`
+    pub struct AggregatedRating {
+        bool set;
+        uint8 rating;
+    }
+    
+    // Average × 1000, total weight (`decay::ONE` = 1) and time of the last
+    // rating, in one slot
+    pub struct RatingAverage {
+        uint64 value_x1000;
+        uint64 weight;
+        uint64 last_update;
+    }
+    
+    // Action, actor and time share a slot; see `admin_log` for targets
+    pub struct AdminLogEntry {
+        uint8 action;
+        address actor;
+        uint64 timestamp;
+        bytes32 target;
+    }
+    
+    // Zero hash = nothing pending
+    pub struct PendingPrediction {
+        bytes32 prediction_hash;
+        uint64 finalizable_at;
+    }
+    
+    // Entries before `head` are settled and cleared; the list is emptied
+    // once every entry is
+    pub struct PendingQueue {
+        uint64 head;
+        QueuedProposal[] entries;
+    }
+    
+    // A proposal as made; settled once the date's pending prediction is
+    // gone or has another deadline
+    pub struct QueuedProposal {
+        address user;
+        uint64 deadline;
+        uint256 date;
+    }
+    
+    // One slot for the astrologer and time, one for the note
+    pub struct Endorsement {
+        address astrologer;
+        uint64 timestamp;
+        bytes32 note_hash;
+    }
+}
+
+// Deployed through `forwarder::entrypoint` so forwarded calls are resolved
+// before routing; builds with the SDK's `stylus-test` feature (all test
+// builds) have no `user_entrypoint` calling it
+#[cfg(not(any(feature = "ephemeris-oracle", feature = "chart-registry", feature = "reader")))]
+#[allow(dead_code)]
+#[entrypoint]
+fn farcaster_entrypoint(input: Vec<u8>, host: stylus_sdk::host::VM) -> stylus_sdk::ArbResult {
+    forwarder::entrypoint::<FarcasterPredictions>(input, host)
+}
+
+#[public_if(feature = "farcaster-admin")]
+impl FarcasterPredictions {
+    /// Storage layout version of this implementation
+    /// 
+    /// Bumped by every release that changes storage, so a proxy upgrade
+    /// can check it is moving forward.
+    pub fn get_implementation_version(&self) -> U256 {
+        U256::from(IMPLEMENTATION_VERSION)
+    }
+    
+    /// Freeze every write for good, e.g. once users moved to `successor`
+    /// 
+    /// Reads keep working. Irreversible: every write path, the owner's
+    /// included, fails with ContractSunset from then on.
+    pub fn sunset(&mut self, successor: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.sunsetted.set(true);
+        self.successor_contract.set(successor);
+        log(self.vm(), Sunset { timestamp: U256::from(self.vm().block_timestamp()), successor });
+        self.log_admin(admin_log::SUNSET, successor.into_word());
+        Ok(())
+    }
+    
+    /// Check if the contract was sunset
+    pub fn is_sunset(&self) -> bool {
+        self.sunsetted.get()
+    }
+    
+    /// Get the contract replacing this one (zero when none), for frontends
+    /// to redirect to
+    pub fn successor(&self) -> Address {
+        self.successor_contract.get()
+    }
+    
+    /// Pause prediction writes once more than `threshold` land in one
+    /// hour, zero to turn the breaker off
+    /// 
+    /// Hours are block timestamps divided by 3600, so each count starts
+    /// over on the hour. The write that passes the threshold still lands;
+    /// later ones fail with Paused until the owner calls `unpause`.
+    pub fn set_auto_pause_threshold(&mut self, threshold: u64) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.auto_pause_threshold.set(U64::from(threshold));
+        self.log_admin(admin_log::SET_AUTO_PAUSE_THRESHOLD, B32::from(U256::from(threshold)));
+        Ok(())
+    }
+    
+    /// Get the most prediction writes allowed in one hour (zero = no limit)
+    pub fn get_auto_pause_threshold(&self) -> u64 {
+        self.auto_pause_threshold.get().to::<u64>()
+    }
+    
+    /// Get the prediction writes counted in `hour_index` (timestamp / 3600)
+    /// 
+    /// Writes are only counted while a threshold is set.
+    pub fn get_writes_in_hour(&self, hour_index: U256) -> U256 {
+        self.writes_in_hour.get(hour_index)
+    }
+    
+    /// Resume prediction writes after the breaker tripped
+    /// 
+    /// The current hour's count is kept, so raise the threshold first if
+    /// the volume was legitimate.
+    pub fn unpause(&mut self) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.paused.set(false);
+        self.log_admin(admin_log::UNPAUSE, B32::ZERO);
+        Ok(())
+    }
+    
+    /// Check if prediction writes are paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.get()
+    }
+    
+    /// Get the number of entries in the admin log
+    pub fn get_admin_log_length(&self) -> U256 {
+        U256::from(self.admin_log.len())
+    }
+    
+    /// Get entries of the admin log, oldest first
+    /// 
+    /// Every owner write appends one entry; `admin_log` defines the action
+    /// ids and what each target holds.
+    /// 
+    /// Parameters:
+    /// - offset: Index of the first entry
+    /// - limit: Most entries to return, capped at `MAX_PAGE_SIZE`
+    /// 
+    /// Returns: (actions, actors, targets, timestamps)
+    pub fn get_admin_log(&self, offset: U256, limit: U256) -> (Vec<u8>, Vec<Address>, Vec<B32>, Vec<U256>) {
+        let len = U256::from(self.admin_log.len());
+        let start = offset.min(len);
+        let end = start.saturating_add(limit.min(U256::from(MAX_PAGE_SIZE))).min(len);
+        let entries = (start.to::<usize>()..end.to::<usize>()).filter_map(|i| self.admin_log.getter(i));
+        let mut log = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for entry in entries {
+            log.0.push(entry.action.get().to::<u8>());
+            log.1.push(entry.actor.get());
+            log.2.push(entry.target.get());
+            log.3.push(U256::from(entry.timestamp.get()));
+        }
+        log
+    }
+    
+    /// Get the contract owner
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+    
+    /// Set the range of proof scheme versions accepted at registration
+    /// 
+    /// Parameters:
+    /// - min_version: Oldest accepted version (>= 1)
+    /// - max_version: Newest accepted version (>= min_version)
+    pub fn set_accepted_versions(
+        &mut self,
+        min_version: u8,
+        max_version: u8,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
         
-        let average_x10 = if total_ratings > U256::ZERO {
-            (rating_sum * U256::from(10)) / total_ratings
-        } else {
-            U256::ZERO
-        };
+        if min_version < SCHEME_V1 || min_version > max_version {
+            return Err(b"InvalidVersionRange".to_vec());
+        }
         
-        (total_predictions, total_ratings, average_x10)
+        self.min_accepted_version.set(U8::from(min_version));
+        self.max_accepted_version.set(U8::from(max_version));
+        
+        let versions = U256::from(min_version) << 8 | U256::from(max_version);
+        self.log_admin(admin_log::SET_ACCEPTED_VERSIONS, B32::from(versions));
+        Ok(())
     }
     
-    /// Get global statistics
-    /// Returns: (total_users, total_predictions)
-    pub fn get_global_stats(&self) -> (U256, U256) {
-        (self.total_users.get(), self.global_predictions.get())
+    /// Get the accepted proof scheme versions
+    /// Returns: (min_version, max_version)
+    pub fn get_accepted_versions(&self) -> (u8, u8) {
+        (
+            self.min_accepted_version.get().to::<u8>(),
+            self.max_accepted_version.get().to::<u8>(),
+        )
+    }
+    
+    /// Restrict registration to an allowlisted cohort
+    /// 
+    /// Parameters:
+    /// - root: Merkle root over keccak256(commitment) leaves (see `merkle`);
+    ///   zero reopens registration to everyone
+    pub fn set_allowlist_root(&mut self, root: B32) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.allowlist_root.set(root);
+        self.log_admin(admin_log::SET_ALLOWLIST_ROOT, root);
+        Ok(())
+    }
+    
+    /// Get the allowlist root (zero when registration is open)
+    pub fn get_allowlist_root(&self) -> B32 {
+        self.allowlist_root.get()
+    }
+    
+    /// Set the relayer allowed to anchor daily roots (zero = owner only)
+    pub fn set_relayer(&mut self, relayer: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.relayer.set(relayer);
+        self.log_admin(admin_log::SET_RELAYER, relayer.into_word());
+        Ok(())
+    }
+    
+    /// Get the relayer (zero when only the owner anchors)
+    pub fn get_relayer(&self) -> Address {
+        self.relayer.get()
+    }
+    
+    /// Set the ERC-2771 forwarder trusted to relay calls (zero = none)
+    /// 
+    /// Calls from the forwarder act for the address appended to their
+    /// calldata; see `forwarder`.
+    pub fn set_trusted_forwarder(&mut self, forwarder: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.trusted_forwarder.set(forwarder);
+        self.log_admin(admin_log::SET_TRUSTED_FORWARDER, forwarder.into_word());
+        Ok(())
+    }
+    
+    /// Get the trusted forwarder (zero when none)
+    pub fn get_trusted_forwarder(&self) -> Address {
+        self.trusted_forwarder.get()
+    }
+    
+    /// Whether `forwarder` is the trusted forwarder (ERC-2771)
+    pub fn is_trusted_forwarder(&self, forwarder: Address) -> bool {
+        forwarder != Address::ZERO && forwarder == self.trusted_forwarder.get()
+    }
+    
+    /// Set the ephemeris oracle checked when storing predictions
+    /// (zero = no check)
+    pub fn set_ephemeris_oracle(&mut self, oracle: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.ephemeris_oracle.set(oracle);
+        self.log_admin(admin_log::SET_EPHEMERIS_ORACLE, oracle.into_word());
+        Ok(())
+    }
+    
+    /// Get the ephemeris oracle (zero when predictions are not checked)
+    pub fn get_ephemeris_oracle(&self) -> Address {
+        self.ephemeris_oracle.get()
+    }
+    
+    /// Set the chart registry read for natal positions
+    pub fn set_chart_registry(&mut self, registry: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.chart_registry.set(registry);
+        self.log_admin(admin_log::SET_CHART_REGISTRY, registry.into_word());
+        Ok(())
+    }
+    
+    /// Get the chart registry (zero when unset)
+    pub fn get_chart_registry(&self) -> Address {
+        self.chart_registry.get()
+    }
+    
+    /// Set the contract notified of stored predictions (zero = none)
+    /// 
+    /// It gets `IPredictionHook.onPredictionStored` after every stored
+    /// prediction; a hook that reverts doesn't stop the prediction.
+    pub fn set_prediction_hook(&mut self, hook: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.prediction_hook.set(hook);
+        self.log_admin(admin_log::SET_PREDICTION_HOOK, hook.into_word());
+        Ok(())
+    }
+    
+    /// Get the prediction hook (zero when none)
+    pub fn get_prediction_hook(&self) -> Address {
+        self.prediction_hook.get()
+    }
+    
+    /// Set how many predictions a user must have stored before taking part
+    /// in community features (zero = everyone registered)
+    pub fn set_min_predictions_for_community(&mut self, min_predictions: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.min_predictions_for_community.set(min_predictions);
+        self.log_admin(admin_log::SET_MIN_PREDICTIONS, B32::from(min_predictions));
+        Ok(())
+    }
+    
+    /// Get the predictions required for community features
+    pub fn get_min_predictions_for_community(&self) -> U256 {
+        self.min_predictions_for_community.get()
+    }
+    
+    /// Check if a user may take part in community features
+    /// 
+    /// Counts `total_predictions`, which never decreases, so pruning old
+    /// predictions doesn't take eligibility away. Community entry points
+    /// (`commit_rating`) fail with `InsufficientHistory` for a user who
+    /// isn't eligible.
+    pub fn community_eligible(&self, user: Address) -> bool {
+        self.user_has_data.get(user) && self.total_predictions.get(user) >= self.min_predictions_for_community.get()
+    }
+    
+    /// Allow or revoke an astrologer's endorsements (owner only)
+    /// 
+    /// Revoking keeps the astrologer's past endorsements.
+    pub fn set_astrologer(&mut self, astrologer: Address, approved: bool) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.astrologers.setter(astrologer).set(approved);
+        let action = if approved { admin_log::ADD_ASTROLOGER } else { admin_log::REMOVE_ASTROLOGER };
+        self.log_admin(action, astrologer.into_word());
+        Ok(())
+    }
+    
+    /// Check if an address may endorse predictions
+    pub fn is_astrologer(&self, astrologer: Address) -> bool {
+        self.astrologers.get(astrologer)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[public_if(feature = "farcaster-config")]
+impl FarcasterPredictions {
+    /// Set the reward points per activity
+    /// 
+    /// Only later activity earns the new amounts; balances are unchanged.
+    /// Zero turns an activity's points off.
+    pub fn set_point_weights(
+        &mut self,
+        registration: u32,
+        prediction: u32,
+        rating: u32,
+        streak: u32,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        
+        self.registration_points.set(U32::from(registration));
+        self.prediction_points.set(U32::from(prediction));
+        self.rating_points.set(U32::from(rating));
+        self.streak_points.set(U32::from(streak));
+        self.point_weights_set.set(true);
+        log(self.vm(), PointWeightsUpdated { registration, prediction, rating, streak });
+        let weights = [registration, prediction, rating, streak]
+            .into_iter()
+            .fold(U256::ZERO, |packed, weight| packed << 32 | U256::from(weight));
+        self.log_admin(admin_log::SET_POINT_WEIGHTS, B32::from(weights));
+        
+        Ok(())
+    }
     
-    #[test]
-    fn test_register_user() {
-        // This would require a test harness
-        // For now, compilation is the test
-        assert!(true);
+    /// Get the reward points per activity
+    /// Returns: (registration, prediction, rating, streak milestone)
+    pub fn get_point_weights(&self) -> (u32, u32, u32, u32) {
+        if !self.point_weights_set.get() {
+            return DEFAULT_POINT_WEIGHTS;
+        }
+        (
+            self.registration_points.get().to::<u32>(),
+            self.prediction_points.get().to::<u32>(),
+            self.rating_points.get().to::<u32>(),
+            self.streak_points.get().to::<u32>(),
+        )
+    }
+    
+    /// Take back reward points, e.g. from an abusive account
+    /// 
+    /// Deducts at most the user's balance.
+    /// 
+    /// Parameters:
+    /// - user: Account to deduct from
+    /// - amount: Points to deduct
+    /// - reason_hash: Hash of the off-chain reason, logged
+    pub fn deduct_points(&mut self, user: Address, amount: U256, reason_hash: B32) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        
+        let balance = self.points.get(user);
+        let amount = amount.min(balance);
+        self.points.setter(user).set(balance - amount);
+        log(self.vm(), PointsDeducted { user, amount, reasonHash: reason_hash });
+        self.log_admin(admin_log::DEDUCT_POINTS, user.into_word());
+        
+        Ok(())
+    }
+    
+    /// Get a user's reward points
+    pub fn get_points(&self, user: Address) -> U256 {
+        self.points.get(user)
+    }
+    
+    /// Set how many days predictions are kept before users may prune them
+    /// (zero disables pruning)
+    pub fn set_min_retention_days(&mut self, days: u64) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.min_retention_days.set(U64::from(days));
+        self.log_admin(admin_log::SET_MIN_RETENTION_DAYS, B32::from(U256::from(days)));
+        Ok(())
+    }
+    
+    /// Get the retention horizon in days (zero when pruning is disabled)
+    pub fn get_min_retention_days(&self) -> u64 {
+        self.min_retention_days.get().to::<u64>()
+    }
+    
+    /// Set how many days back predictions can be stored: dates before
+    /// `block.timestamp - days * 86400` are rejected with `DateTooOld`
+    /// 
+    /// Applies to `store_prediction`, `store_prediction_for_chart` and
+    /// relayer proposals; claims from an anchored root are exempt.
+    pub fn set_max_backfill_days(&mut self, days: u64) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.max_backfill_days.set(U64::from(days));
+        self.max_backfill_days_set.set(true);
+        self.log_admin(admin_log::SET_MAX_BACKFILL_DAYS, B32::from(U256::from(days)));
+        Ok(())
+    }
+    
+    /// Get how many days back predictions can be stored
+    pub fn get_max_backfill_days(&self) -> u64 {
+        if !self.max_backfill_days_set.get() {
+            return DEFAULT_MAX_BACKFILL_DAYS;
+        }
+        self.max_backfill_days.get().to::<u64>()
+    }
+    
+    /// Replace the FID attestor key this setter last set with `attestor`
+    /// (zero = none), as a key of `ATTESTOR_ROLE_FID` that never expires
+    /// 
+    /// Keys added with `add_attestor` are left alone: one that becomes the
+    /// FID attestor keeps its expiry, and isn't removed when replaced.
+    pub fn set_fid_attestor(&mut self, attestor: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let role = U8::from(ATTESTOR_ROLE_FID);
+        let previous = self.fid_attestor.get();
+        if self.attestor_entries.getter(role).getter(previous).fid_setter_added.get() {
+            self.remove_attestor(ATTESTOR_ROLE_FID, previous);
+        }
+        let registered = !self.attestor_entries.getter(role).getter(attestor).position.get().is_zero();
+        if attestor != Address::ZERO && !registered {
+            self.insert_attestor(ATTESTOR_ROLE_FID, attestor, U256::MAX);
+            self.attestor_entries.setter(role).setter(attestor).fid_setter_added.set(true);
+        }
+        self.fid_attestor.set(attestor);
+        self.log_admin(admin_log::SET_FID_ATTESTOR, attestor.into_word());
+        Ok(())
+    }
+    
+    /// Get the FID attestor set with `set_fid_attestor` (zero when none);
+    /// `get_attestors` lists every key of the role
+    pub fn get_fid_attestor(&self) -> Address {
+        self.fid_attestor.get()
+    }
+    
+    /// Make `key` an attestor of `role` until `expires_at`, or move the
+    /// expiry of a key that already is one (owner only)
+    /// 
+    /// A key signs for its role while `block.timestamp < expires_at`, so
+    /// `U256::MAX` never expires. To rotate, add the new key, then revoke
+    /// the old one; signatures from either are accepted in between. A key
+    /// `set_fid_attestor` added is managed here from then on.
+    pub fn add_attestor(&mut self, role: u8, key: Address, expires_at: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if key == Address::ZERO {
+            return Err(b"InvalidAttestor".to_vec());
+        }
+        if expires_at <= U256::from(self.vm().block_timestamp()) {
+            return Err(b"InvalidExpiry".to_vec());
+        }
+        self.insert_attestor(role, key, expires_at);
+        self.attestor_entries.setter(U8::from(role)).setter(key).fid_setter_added.set(false);
+        self.log_admin(admin_log::ADD_ATTESTOR, attestor_target(role, key));
+        Ok(())
+    }
+    
+    /// Remove `key` from `role`'s attestors (owner only)
+    /// 
+    /// Its signatures are rejected from then on, including ones made
+    /// before. Revoking the key set with `set_fid_attestor` clears it.
+    pub fn revoke_attestor(&mut self, role: u8, key: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if !self.remove_attestor(role, key) {
+            return Err(b"UnknownAttestor".to_vec());
+        }
+        if role == ATTESTOR_ROLE_FID && key == self.fid_attestor.get() {
+            self.fid_attestor.set(Address::ZERO);
+        }
+        self.log_admin(admin_log::REVOKE_ATTESTOR, attestor_target(role, key));
+        Ok(())
+    }
+    
+    /// Get when `key` stops signing for `role` (zero = not an attestor)
+    pub fn get_attestor_expiry(&self, role: u8, key: Address) -> U256 {
+        self.attestor_entries.getter(U8::from(role)).getter(key).expires_at.get()
+    }
+    
+    /// Get how many keys `role` has, expired ones included
+    pub fn get_attestor_count(&self, role: u8) -> U256 {
+        U256::from(self.attestor_keys.getter(U8::from(role)).len())
+    }
+    
+    /// List `role`'s attestors that haven't expired
+    /// 
+    /// Pages over the role's keys in storage order, leaving out expired
+    /// ones, so a page can hold fewer than `limit` keys with more to come;
+    /// the next page starts at `offset + limit`. Revoking a key moves the
+    /// role's last key into its place.
+    /// 
+    /// Parameters:
+    /// - offset: Index of the first key to look at
+    /// - limit: Most keys to look at, capped at `MAX_PAGE_SIZE`
+    pub fn get_attestors(&self, role: u8, offset: U256, limit: U256) -> Vec<Address> {
+        let role = U8::from(role);
+        let keys = self.attestor_keys.getter(role);
+        let len = U256::from(keys.len());
+        let start = offset.min(len);
+        let end = start.saturating_add(limit.min(U256::from(MAX_PAGE_SIZE))).min(len);
+        let now = U256::from(self.vm().block_timestamp());
+        (start.to::<usize>()..end.to::<usize>())
+            .filter_map(|i| keys.get(i))
+            .filter(|key| self.attestor_entries.getter(role).getter(*key).expires_at.get() > now)
+            .collect()
+    }
+    
+    /// Set the highest FID `register_with_fid` admits (zero = any)
+    pub fn set_max_beta_fid(&mut self, max_fid: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.max_beta_fid.set(max_fid);
+        self.log_admin(admin_log::SET_MAX_BETA_FID, B32::from(max_fid));
+        Ok(())
+    }
+    
+    /// Get the highest FID admitted (zero when the gate is off)
+    pub fn get_max_beta_fid(&self) -> U256 {
+        self.max_beta_fid.get()
+    }
+    
+    /// Get the Farcaster ID linked to `user` (zero when none)
+    pub fn get_fid(&self, user: Address) -> U256 {
+        self.user_fids.get(user)
+    }
+    
+    /// Get the address linked to `fid` (zero when none)
+    pub fn get_fid_owner(&self, fid: U256) -> Address {
+        self.fid_owners.get(fid)
     }
 }
 
+#[public_if(feature = "farcaster-registration")]
+impl FarcasterPredictions {
+    /// Register user with ZK proof of birth data
+    /// 
+    /// This stores a commitment to the user's birth data without revealing it.
+    /// The commitment can be used to verify predictions were generated from
+    /// the same birth data.
+    #[payable]
+    pub fn register_user(
+        &mut self,
+        commitment: B32,
+    ) -> Result<(), Vec<u8>> {
+        let user = self.msg_sender();
+        
+        if commitment == B32::ZERO {
+            return Err(b"InvalidCommitment".to_vec());
+        }
+        self.only_open_registration()?;
+        
+        self.register_commitment(user, commitment)
+    }
+    
+    /// Register user with a commitment from the allowlisted cohort
+    /// 
+    /// Parameters:
+    /// - commitment: Commitment to the birth data
+    /// - merkle_proof: Siblings proving keccak256(commitment) is under the
+    ///   current allowlist root (ignored while the root is zero)
+    #[payable]
+    pub fn register_user_allowlisted(
+        &mut self,
+        commitment: B32,
+        merkle_proof: Vec<B32>,
+    ) -> Result<(), Vec<u8>> {
+        let user = self.msg_sender();
+        
+        if commitment == B32::ZERO {
+            return Err(b"InvalidCommitment".to_vec());
+        }
+        
+        let root = self.allowlist_root.get();
+        if root != B32::ZERO && !merkle::verify(&merkle_proof, root, keccak(commitment)) {
+            return Err(b"NotAllowlisted".to_vec());
+        }
+        
+        self.register_commitment(user, commitment)?;
+        log(self.vm(), UserAdmitted { user, root });
+        
+        Ok(())
+    }
+    
+    /// Register user and link their Farcaster ID in one call
+    /// 
+    /// Parameters:
+    /// - commitment: Commitment to the birth data
+    /// - fid: The caller's Farcaster ID, at most `get_max_beta_fid` while
+    ///   the beta gate is on
+    /// - fid_proof_sig: A 65-byte `r || s || v` signature of
+    ///   `fid_attestation_digest(chain_id, this, caller, fid)` by an
+    ///   unexpired attestor of `ATTESTOR_ROLE_FID`
+    #[payable]
+    pub fn register_with_fid(
+        &mut self,
+        commitment: B32,
+        fid: U256,
+        fid_proof_sig: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        let user = self.msg_sender();
+        
+        if commitment == B32::ZERO {
+            return Err(b"InvalidCommitment".to_vec());
+        }
+        self.only_open_registration()?;
+        
+        let max_beta_fid = self.max_beta_fid.get();
+        if fid == U256::ZERO {
+            return Err(b"InvalidFid".to_vec());
+        }
+        if max_beta_fid != U256::ZERO && fid > max_beta_fid {
+            return Err(NotInBeta { fid, maxBetaFid: max_beta_fid }.abi_encode());
+        }
+        
+        let digest = fid_attestation_digest(self.vm().chain_id(), self.vm().contract_address(), user, fid);
+        self.check_attestation(ATTESTOR_ROLE_FID, digest, &fid_proof_sig).map_err(|error| {
+            if error == b"AttestorExpired" {
+                error
+            } else {
+                b"InvalidFidAttestation".to_vec()
+            }
+        })?;
+        if self.fid_owners.get(fid) != Address::ZERO {
+            return Err(b"FidAlreadyLinked".to_vec());
+        }
+        
+        self.register_commitment(user, commitment)?;
+        self.user_fids.setter(user).set(fid);
+        self.fid_owners.setter(fid).set(user);
+        log(self.vm(), FidLinked { user, fid });
+        
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-proofs")]
+impl FarcasterPredictions {
+    /// Register user with a verified proof of knowledge of the birth data
+    /// 
+    /// Parameters:
+    /// - commitment: Commitment to the birth data (stored as is)
+    /// - proof: Proof digest, the bytes of the prover's hex proof
+    /// - nonce: Nonce as produced by the prover, at most
+    ///   `poseidon::MAX_FIELD_BYTES`; a longer one fails with
+    ///   `InputTooLarge(poseidon::INPUT_NONCE)`
+    /// - positions: Planetary positions; centidegrees in client order for v1,
+    ///   `positions::PlanetPosition` words in any order from v2 on
+    /// - scheme_version: Proof scheme version, must be in the accepted range
+    /// - encoding: Challenge encoding id (0 = legacy, 1 = solidity packed)
+    /// - epoch: Epoch the proof is bound to (v2 on), must be the current or
+    ///   previous one (`block.timestamp / 604800`)
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn register_user_with_proof(
+        &mut self,
+        commitment: B32,
+        proof: B32,
+        nonce: Bytes,
+        positions: Vec<u64>,
+        scheme_version: u8,
+        encoding: u8,
+        epoch: u64,
+    ) -> Result<(), Vec<u8>> {
+        let proof = zk_proof(commitment, proof, nonce, scheme_version, encoding, epoch)?;
+        self.register_verified(&proof, &positions)
+    }
+    
+    /// Register user with a v2 proof salted with a recent block
+    /// 
+    /// The proof's transcript includes the number and hash of
+    /// `anchor_block` (see `poseidon::compute_proof_v2_anchored`), so it
+    /// can't have been generated before that block.
+    /// 
+    /// Parameters:
+    /// - commitment, proof, nonce, positions, epoch: As for
+    ///   `register_user_with_proof` under scheme v2
+    /// - anchor_block: Arbitrum block number the proof is salted with, one
+    ///   of the 256 blocks before the current one
+    #[payable]
+    pub fn register_user_with_anchored_proof(
+        &mut self,
+        commitment: B32,
+        proof: B32,
+        nonce: Bytes,
+        positions: Vec<u64>,
+        epoch: u64,
+        anchor_block: U256,
+    ) -> Result<(), Vec<u8>> {
+        let mut proof = zk_proof(commitment, proof, nonce, poseidon::SCHEME_V2, Encoding::Legacy.id(), epoch)?;
+        proof.anchor = Some(self.resolve_anchor(anchor_block)?);
+        self.register_verified(&proof, &positions)
+    }
+}
+
+#[public_if(feature = "farcaster-proof-variants")]
+impl FarcasterPredictions {
+    /// Register user with a v2 proof, disclosing a birth-year bucket
+    /// 
+    /// The commitment must be a bucketed commitment (see
+    /// `poseidon::bucket_commitment`) and the proof's transcript includes
+    /// the claimed bucket. Only the decade is revealed.
+    /// 
+    /// Parameters:
+    /// - commitment, proof, nonce, positions, epoch: As for
+    ///   `register_user_with_proof` under scheme v2
+    /// - year_bucket: Decade index of the birth year, 0 (1900s) to 12 (2020s)
+    /// - opening_proof: Opening of the bucketed commitment
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn register_user_with_bucket(
+        &mut self,
+        commitment: B32,
+        proof: B32,
+        nonce: Bytes,
+        positions: Vec<u64>,
+        epoch: u64,
+        year_bucket: u8,
+        opening_proof: B32,
+    ) -> Result<(), Vec<u8>> {
+        let user = self.msg_sender();
+        
+        // Input sizes are checked before the bucket claim is hashed
+        let mut proof = zk_proof(commitment, proof, nonce, poseidon::SCHEME_V2, Encoding::Legacy.id(), epoch)?;
+        poseidon::check_positions(&positions).map_err(input_error)?;
+        if year_bucket > poseidon::MAX_YEAR_BUCKET {
+            return Err(b"InvalidBucket".to_vec());
+        }
+        if !poseidon::verify_bucket_claim(&commitment.into(), year_bucket, &opening_proof.0) {
+            return Err(b"InvalidBucketClaim".to_vec());
+        }
+        
+        proof.claimed_bucket = Some(year_bucket);
+        self.register_verified(&proof, &positions)?;
+        
+        self.user_buckets.setter(user).set(U8::from(year_bucket));
+        self.user_has_bucket.setter(user).set(true);
+        
+        Ok(())
+    }
+    
+    /// Register user with a verified proof, positions packed 16 bits each
+    /// 
+    /// Same as `register_user_with_proof` with the positions passed as two
+    /// words built by `poseidon::pack_positions` (saves ~30 bytes of
+    /// calldata per position).
+    /// 
+    /// Parameters:
+    /// - positions_packed: Packed positions, up to 16 per word
+    /// - count: Number of packed positions
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn register_user_with_proof_packed(
+        &mut self,
+        commitment: B32,
+        proof: B32,
+        nonce: Bytes,
+        positions_packed: [B32; 2],
+        count: u8,
+        scheme_version: u8,
+        encoding: u8,
+        epoch: u64,
+    ) -> Result<(), Vec<u8>> {
+        let words = [positions_packed[0].0, positions_packed[1].0];
+        let positions = poseidon::unpack_positions(&words, count).ok_or(b"InvalidPositions".to_vec())?;
+        
+        let proof = zk_proof(commitment, proof, nonce, scheme_version, encoding, epoch)?;
+        self.register_verified(&proof, &positions)
+    }
+}
+
+#[public_if(feature = "farcaster-proof-checks")]
+impl FarcasterPredictions {
+    /// Check a proof without registering
+    /// 
+    /// Same parameters and checks as `register_user_with_proof`; a proof
+    /// that doesn't verify returns false.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_proof(
+        &self,
+        commitment: B32,
+        proof: B32,
+        nonce: Bytes,
+        positions: Vec<u64>,
+        scheme_version: u8,
+        encoding: u8,
+        epoch: u64,
+    ) -> Result<bool, Vec<u8>> {
+        let proof = zk_proof(commitment, proof, nonce, scheme_version, encoding, epoch)?;
+        self.check_proof_inputs(scheme_version, &positions)?;
+        self.verify_proof_now(&proof, &positions)
+    }
+    
+    /// `verify_proof` for the prover's hex output, for clients still
+    /// holding proofs as strings
+    /// 
+    /// Parameters:
+    /// - commitment: 64 hex digits, either case, optional 0x prefix
+    /// - proof: 64 hex digits, either case, no prefix
+    /// - nonce: UTF-8 nonce
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_proof_hex(
+        &self,
+        commitment: String,
+        proof: String,
+        nonce: String,
+        positions: Vec<u64>,
+        scheme_version: u8,
+        encoding: u8,
+        epoch: u64,
+    ) -> Result<bool, Vec<u8>> {
+        let proof = ZkProof::from_bytes(commitment.as_bytes(), proof.as_bytes(), nonce.as_bytes(), encoding, scheme_version, epoch)
+            .map_err(input_error)?;
+        self.check_proof_inputs(scheme_version, &positions)?;
+        Commitment::from_hex(&proof.commitment).map_err(|_| b"InvalidCommitment".to_vec())?;
+        match proof.to_bytes() {
+            Some(proof) => self.verify_proof_now(&proof, &positions),
+            None => Ok(false),
+        }
+    }
+}
+
+#[public_if(feature = "farcaster-pedersen")]
+impl FarcasterPredictions {
+    /// Register user with a Pedersen commitment to birth data
+    /// 
+    /// The commitment is a BN254 G1 point C = v·G + r·H (see `commitment`).
+    /// It is stored compressed in the same slot as hash commitments, so
+    /// `get_user_commitment` returns the compressed form.
+    #[payable]
+    pub fn register_user_pedersen(
+        &mut self,
+        commitment_x: U256,
+        commitment_y: U256,
+    ) -> Result<(), Vec<u8>> {
+        let user = self.msg_sender();
+        
+        let point = G1Point::new(commitment_x, commitment_y);
+        if point.is_infinity() || !point.is_on_curve() {
+            return Err(b"InvalidCommitment".to_vec());
+        }
+        self.only_open_registration()?;
+        
+        let compressed = B32::from(point.compress().to_be_bytes::<32>());
+        self.register_commitment(user, compressed)?;
+        self.pedersen_commitments.setter(user).set(true);
+        
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-schnorr")]
+impl FarcasterPredictions {
+    /// Register user with a Schnorr proof of knowledge of the commitment's
+    /// discrete log (see `schnorr`)
+    /// 
+    /// Verified on the ecAdd/ecMul precompiles. The commitment C = x·G is
+    /// stored compressed like Pedersen commitments.
+    /// 
+    /// Parameters:
+    /// - commitment_x, commitment_y: Commitment point C
+    /// - r_x, r_y: Nonce commitment R
+    /// - s: Response, must be < n
+    /// - positions: Planetary positions in centidegrees
+    #[payable]
+    pub fn register_user_schnorr(
+        &mut self,
+        commitment_x: U256,
+        commitment_y: U256,
+        r_x: U256,
+        r_y: U256,
+        s: U256,
+        positions: Vec<u64>,
+    ) -> Result<(), Vec<u8>> {
+        let user = self.msg_sender();
+        
+        poseidon::check_positions(&positions).map_err(input_error)?;
+        let point = G1Point::new(commitment_x, commitment_y);
+        if point.is_infinity() || !point.is_on_curve() {
+            return Err(b"InvalidCommitment".to_vec());
+        }
+        self.only_open_registration()?;
+        
+        let proof = schnorr::SchnorrProof { r: G1Point::new(r_x, r_y), s };
+        if !schnorr::verify_schnorr_with(&Precompiles(self.vm()), &point, &proof, &positions, user) {
+            return Err(b"InvalidProof".to_vec());
+        }
+        
+        let compressed = B32::from(point.compress().to_be_bytes::<32>());
+        self.register_commitment(user, compressed)
+    }
+}
+
+#[public_if(feature = "farcaster-fees")]
+impl FarcasterPredictions {
+    /// Set the fee, in wei, every registration path but `import_users`
+    /// takes (zero = free)
+    pub fn set_registration_fee(&mut self, fee: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.registration_fee.set(fee);
+        self.log_admin(admin_log::SET_REGISTRATION_FEE, B32::from(fee));
+        Ok(())
+    }
+    
+    /// Get the registration fee in wei
+    pub fn get_registration_fee(&self) -> U256 {
+        self.registration_fee.get()
+    }
+    
+    /// Add the sent value to the sponsor pool
+    /// 
+    /// Anyone can fund it. While it covers the fee, a registration sent
+    /// without value is paid from the pool, and `RegistrationSponsored`
+    /// is emitted. Funds in the pool can't be taken back before `sunset`;
+    /// after it, funders reclaim what is left with `reclaim_sponsorship`.
+    #[payable]
+    pub fn fund_sponsorship(&mut self) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let funder = self.msg_sender();
+        let amount = self.vm().msg_value();
+        if amount.is_zero() {
+            return Err(b"InvalidAmount".to_vec());
+        }
+        let pool = math::checked_add(self.sponsor_pool.get(), amount, math::FIELD_SPONSOR_POOL)?;
+        let field = math::FIELD_SPONSOR_CONTRIBUTIONS;
+        let contribution = math::checked_add(self.sponsor_contributions.get(funder), amount, field)?;
+        let total = math::checked_add(self.sponsor_contributions_total.get(), amount, field)?;
+        self.sponsor_pool.set(pool);
+        self.sponsor_contributions.setter(funder).set(contribution);
+        self.sponsor_contributions_total.set(total);
+        log(self.vm(), SponsorshipFunded { funder, amount, pool });
+        Ok(())
+    }
+    
+    /// Get the wei `funder` put in the sponsor pool and hasn't reclaimed
+    pub fn get_sponsor_contribution(&self, funder: Address) -> U256 {
+        self.sponsor_contributions.get(funder)
+    }
+    
+    /// Take back the caller's share of the sponsor pool after `sunset`
+    /// 
+    /// The share is the pool's balance times the caller's part of the
+    /// unreclaimed contributions, so the fees the pool paid come out of
+    /// every funder in proportion, and the last funder takes the rest.
+    pub fn reclaim_sponsorship(&mut self) -> Result<(), Vec<u8>> {
+        if !self.is_sunset() {
+            return Err(b"NotSunset".to_vec());
+        }
+        let funder = self.msg_sender();
+        let contribution = self.sponsor_contributions.get(funder);
+        if contribution.is_zero() {
+            return Err(b"NothingToReclaim".to_vec());
+        }
+        self.non_reentrant(|contract| {
+            let pool = contract.sponsor_pool.get();
+            let total = contract.sponsor_contributions_total.get();
+            // The pool never exceeds the contributions, and both are
+            // bounded by the ether supply, so the product fits
+            let amount = pool * contribution / total;
+            contract.sponsor_pool.set(pool - amount);
+            contract.sponsor_contributions.setter(funder).set(U256::ZERO);
+            contract.sponsor_contributions_total.set(total - contribution);
+            log(contract.vm(), SponsorshipReclaimed { funder, amount });
+            contract.vm().transfer_eth(funder, amount)
+        })
+    }
+    
+    /// Get the sponsor pool's balance in wei
+    pub fn get_sponsor_pool(&self) -> U256 {
+        self.sponsor_pool.get()
+    }
+    
+    /// Limit sponsored registrations to `cap` per UTC day (zero = no cap)
+    pub fn set_sponsorship_daily_cap(&mut self, cap: u64) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.sponsorship_daily_cap.set(U64::from(cap));
+        self.log_admin(admin_log::SET_SPONSORSHIP_DAILY_CAP, B32::from(U256::from(cap)));
+        Ok(())
+    }
+    
+    /// Get the daily cap on sponsored registrations (zero = no cap)
+    pub fn get_sponsorship_daily_cap(&self) -> u64 {
+        self.sponsorship_daily_cap.get().to::<u64>()
+    }
+    
+    /// Get how many more registrations the pool sponsors today: the fees
+    /// it covers, at most what is left of the daily cap (zero while the
+    /// fee is zero)
+    pub fn get_sponsored_slots_today(&self) -> U256 {
+        let fee = self.registration_fee.get();
+        if fee.is_zero() {
+            return U256::ZERO;
+        }
+        let covered = self.sponsor_pool.get() / fee;
+        let cap = self.sponsorship_daily_cap.get();
+        if cap.is_zero() {
+            return covered;
+        }
+        let Ok(day) = calendar::to_day_number(U256::from(self.vm().block_timestamp())) else {
+            return U256::ZERO;
+        };
+        let left = cap.saturating_sub(self.sponsored_per_day.get(U32::from(day)));
+        covered.min(U256::from(left))
+    }
+    
+    /// Get the registration fees paid and not yet withdrawn, in wei
+    pub fn get_collected_fees(&self) -> U256 {
+        self.collected_fees.get()
+    }
+    
+    /// Send the collected registration fees to `to`
+    /// 
+    /// Also works after `sunset`. The sponsor pool belongs to its funders
+    /// and stays out of it, see `reclaim_sponsorship`.
+    pub fn withdraw_fees(&mut self, to: Address) -> Result<(), Vec<u8>> {
+        if self.msg_sender() != self.owner.get() {
+            return Err(b"NotOwner".to_vec());
+        }
+        if to == Address::ZERO {
+            return Err(b"InvalidRecipient".to_vec());
+        }
+        self.non_reentrant(|contract| {
+            let amount = contract.collected_fees.get();
+            contract.collected_fees.set(U256::ZERO);
+            contract.log_admin(admin_log::WITHDRAW_FEES, to.into_word());
+            contract.vm().transfer_eth(to, amount)
+        })
+    }
+}
+
+#[public_if(feature = "farcaster-predictions")]
+impl FarcasterPredictions {
+    /// Store daily prediction on-chain
+    /// 
+    /// Parameters:
+    /// - date: Unix timestamp (midnight UTC) for the prediction date
+    /// - prediction_hash: Hash of the prediction text + lucky elements
+    pub fn store_prediction(
+        &mut self,
+        date: U256,
+        prediction_hash: B32,
+    ) -> Result<(), Vec<u8>> {
+        self.non_reentrant(|contract| {
+            let user = contract.msg_sender();
+            contract.check_backfill(date)?;
+            contract.store_prediction_for(user, date, prediction_hash, B32::ZERO)
+        })
+    }
+    
+    /// Get what is stored about a user's prediction for a date
+    /// Returns: (prediction_hash, rating, chart_key), zero when missing
+    /// or unattributed
+    pub fn get_prediction_meta(&self, user: Address, date: U256) -> (B32, u8, B32) {
+        (
+            self.predictions.getter(user).get(date),
+            self.ratings.getter(user).get(date).to::<u8>(),
+            self.prediction_charts.getter(user).get(date),
+        )
+    }
+    
+    /// Set how long users have to reject predictions the relayer proposes
+    /// (zero disables proposals)
+    /// 
+    /// Applies to later proposals; pending ones keep their deadline.
+    pub fn set_challenge_window(&mut self, seconds: u64) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.challenge_window.set(U64::from(seconds));
+        self.log_admin(admin_log::SET_CHALLENGE_WINDOW, B32::from(U256::from(seconds)));
+        Ok(())
+    }
+    
+    /// Get the challenge window in seconds (zero when proposals are disabled)
+    pub fn get_challenge_window(&self) -> u64 {
+        self.challenge_window.get().to::<u64>()
+    }
+}
+
+#[public_if(feature = "farcaster-chart-predictions")]
+impl FarcasterPredictions {
+    /// Store a prediction for a date, attributed to the sender's chart
+    /// it was generated from
+    /// 
+    /// When a chart registry is configured, the chart must be one of the
+    /// sender's there. The attribution can't change afterwards: a date
+    /// takes one prediction, and pruning it erases the attribution too.
+    /// 
+    /// Parameters:
+    /// - chart_key: keccak256 of the chart id, as the registry's events
+    ///   index it; zero stores without attribution, like `store_prediction`
+    pub fn store_prediction_for_chart(
+        &mut self,
+        date: U256,
+        prediction_hash: B32,
+        chart_key: B32,
+    ) -> Result<(), Vec<u8>> {
+        self.non_reentrant(|contract| {
+            let user = contract.msg_sender();
+            contract.check_backfill(date)?;
+            if chart_key != B32::ZERO {
+                contract.check_chart_owner(user, chart_key)?;
+            }
+            contract.store_prediction_for(user, date, prediction_hash, chart_key)
+        })
+    }
+}
+
+#[public_if(feature = "farcaster-proposals")]
+impl FarcasterPredictions {
+    /// Propose a prediction on a user's behalf (owner or relayer)
+    /// 
+    /// The prediction stays pending, outside every stat, until the user
+    /// confirms it or the challenge window passes without a rejection and
+    /// anyone finalizes it. Storing the date directly cancels it.
+    /// 
+    /// A nonzero `idempotency_key` the sender already used makes this a
+    /// no-op, see `is_idempotency_key_used`.
+    pub fn propose_prediction_for(
+        &mut self,
+        user: Address,
+        date: U256,
+        prediction_hash: B32,
+        idempotency_key: B32,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner_or_relayer()?;
+        self.idempotent(idempotency_key, |contract| {
+            let window = contract.challenge_window.get().to::<u64>();
+            if window == 0 {
+                return Err(b"ProposalsDisabled".to_vec());
+            }
+            contract.check_backfill(date)?;
+            contract.check_storable(user, date, prediction_hash)?;
+            if contract.pending_predictions.getter(user).getter(date).prediction_hash.get() != B32::ZERO {
+                return Err(b"PredictionPending".to_vec());
+            }
+            
+            let finalizable_at = contract.vm().block_timestamp().saturating_add(window);
+            let mut pending = contract.pending_predictions.setter(user);
+            let mut pending = pending.setter(date);
+            pending.prediction_hash.set(prediction_hash);
+            pending.finalizable_at.set(U64::from(finalizable_at));
+            contract.enqueue_proposal(contract.msg_sender(), user, date, finalizable_at);
+            log(
+                contract.vm(),
+                PredictionProposed {
+                    user,
+                    date,
+                    day: day_number(date)?,
+                    relayer: contract.msg_sender(),
+                    predictionHash: prediction_hash,
+                    finalizableAt: U256::from(finalizable_at),
+                },
+            );
+            Ok(())
+        })
+    }
+}
+
+#[public_if(feature = "farcaster-confirmations")]
+impl FarcasterPredictions {
+    /// Accept the prediction proposed for the sender's `date`, storing it
+    /// right away
+    pub fn confirm_prediction(&mut self, date: U256) -> Result<(), Vec<u8>> {
+        self.non_reentrant(|contract| {
+            let user = contract.msg_sender();
+            contract.promote_pending(user, date)?;
+            log(contract.vm(), PredictionConfirmed { user, date, day: day_number(date)? });
+            Ok(())
+        })
+    }
+    
+    /// Turn down the prediction proposed for the sender's `date`, within
+    /// the challenge window
+    pub fn reject_prediction(&mut self, date: U256) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let user = self.msg_sender();
+        let (_, finalizable_at) = self.pending_prediction(user, date)?;
+        if self.vm().block_timestamp() >= finalizable_at {
+            return Err(b"ChallengeWindowClosed".to_vec());
+        }
+        self.clear_pending(user, date);
+        log(self.vm(), PredictionRejected { user, date, day: day_number(date)? });
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-finalization")]
+impl FarcasterPredictions {
+    /// Store a proposed prediction whose challenge window passed (anyone)
+    pub fn finalize_prediction(&mut self, user: Address, date: U256) -> Result<(), Vec<u8>> {
+        self.non_reentrant(|contract| {
+            let (_, finalizable_at) = contract.pending_prediction(user, date)?;
+            if contract.vm().block_timestamp() < finalizable_at {
+                return Err(b"ChallengeWindowOpen".to_vec());
+            }
+            contract.promote_pending(user, date)?;
+            log(contract.vm(), PredictionFinalized { user, date, day: day_number(date)? });
+            Ok(())
+        })
+    }
+    
+    /// Get a prediction proposed for a user, not yet stored
+    /// Returns: (prediction_hash, finalizable_at), zero hash when none
+    pub fn get_pending_prediction(&self, user: Address, date: U256) -> (B32, U256) {
+        let pending = self.pending_predictions.getter(user);
+        let pending = pending.getter(date);
+        (pending.prediction_hash.get(), U256::from(pending.finalizable_at.get()))
+    }
+    
+    /// Get up to `limit` proposals `relayer` made that are still pending
+    /// and past their challenge window, as (user, date), oldest first
+    /// 
+    /// Reads the relayer's queue in the order the proposals were made and
+    /// stops at the first pending one still in its window. Deadlines keep
+    /// that order unless the challenge window was shortened in between;
+    /// a proposal queued behind a later deadline shows once that one
+    /// passes, and `finalize_prediction` takes it any time.
+    pub fn get_finalizable(&self, relayer: Address, limit: U256) -> Vec<(Address, U256)> {
+        let now = self.vm().block_timestamp();
+        let limit = limit.saturating_to::<usize>();
+        let queue = self.pending_queues.getter(relayer);
+        let mut finalizable = Vec::new();
+        for i in queue.head.get().to::<usize>()..queue.entries.len() {
+            if finalizable.len() >= limit {
+                break;
+            }
+            let Some(entry) = queue.entries.getter(i) else {
+                break;
+            };
+            let (user, date, deadline) = (entry.user.get(), entry.date.get(), entry.deadline.get().to::<u64>());
+            if !self.proposal_pending(user, date, deadline) {
+                continue;
+            }
+            if deadline > now {
+                break;
+            }
+            finalizable.push((user, date));
+        }
+        finalizable
+    }
+}
+
+#[public_if(feature = "farcaster-batch-finalization")]
+impl FarcasterPredictions {
+    /// Finalize several proposals whose challenge window passed (anyone)
+    /// 
+    /// Takes up to `finalize::MAX_FINALIZE_ENTRIES` (user, date) entries,
+    /// such as those `get_finalizable` returns, and returns a `finalize`
+    /// status per entry in input order. Entries that can't be finalized
+    /// are skipped; any other failure, such as `Paused`, reverts the
+    /// batch.
+    pub fn finalize_batch(&mut self, entries: Vec<(Address, U256)>) -> Result<Vec<u8>, Vec<u8>> {
+        if entries.len() > finalize::MAX_FINALIZE_ENTRIES {
+            return Err(b"TooManyEntries".to_vec());
+        }
+        self.non_reentrant(|contract| {
+            let now = contract.vm().block_timestamp();
+            let mut statuses = Vec::with_capacity(entries.len());
+            for (user, date) in entries {
+                let (prediction_hash, finalizable_at) = contract.get_pending_prediction(user, date);
+                let status = if prediction_hash != B32::ZERO {
+                    if now < finalizable_at.to::<u64>() {
+                        finalize::WINDOW_OPEN
+                    } else {
+                        contract.promote_pending(user, date)?;
+                        log(contract.vm(), PredictionFinalized { user, date, day: day_number(date)? });
+                        finalize::FINALIZED
+                    }
+                } else if contract.prediction_exists.getter(user).get(date) || contract.pruned_predictions.getter(user).get(date) {
+                    finalize::ALREADY_STORED
+                } else {
+                    finalize::NOT_PENDING
+                };
+                statuses.push(status);
+            }
+            Ok(statuses)
+        })
+    }
+}
+
+#[public_if(feature = "farcaster-anchoring")]
+impl FarcasterPredictions {
+    /// Anchor the Merkle root of a day's predictions (owner or relayer)
+    /// 
+    /// Each root can be set once per date and never changes. Users then
+    /// claim their own leaf with `claim_anchored_prediction`.
+    /// 
+    /// Parameters:
+    /// - date: Unix timestamp (midnight UTC) of the day
+    /// - root: Merkle root over `prediction_leaf` leaves (see `merkle`)
+    /// - count: Number of leaves, informational
+    /// - idempotency_key: Retry key, zero for none (see
+    ///   `is_idempotency_key_used`)
+    pub fn anchor_daily_root(
+        &mut self,
+        date: U256,
+        root: B32,
+        count: U256,
+        idempotency_key: B32,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner_or_relayer()?;
+        self.idempotent(idempotency_key, |contract| {
+            if root == B32::ZERO {
+                return Err(b"InvalidRoot".to_vec());
+            }
+            if contract.daily_roots.get(date) != B32::ZERO {
+                return Err(b"RootAlreadyAnchored".to_vec());
+            }
+            
+            contract.daily_roots.setter(date).set(root);
+            contract.daily_counts.setter(date).set(count);
+            log(contract.vm(), DailyRootAnchored { date, root, count });
+            
+            Ok(())
+        })
+    }
+    
+    /// Whether `relayer` already used `key` for a write
+    /// 
+    /// Relayer writes take an idempotency key so retries are safe: a write
+    /// whose key its sender already used succeeds without doing anything
+    /// and logs `DuplicateSuppressed`. Keys are scoped per sender, and a
+    /// zero key is never recorded.
+    pub fn is_idempotency_key_used(&self, relayer: Address, key: B32) -> bool {
+        self.used_idempotency_keys.getter(relayer).get(key)
+    }
+}
+
+#[public_if(feature = "farcaster-anchored-claims")]
+impl FarcasterPredictions {
+    /// Store a prediction included in the anchored root for `date`
+    /// 
+    /// Parameters:
+    /// - date: Day the root was anchored for
+    /// - prediction_hash: The sender's prediction hash for that day
+    /// - proof: Siblings proving `prediction_leaf(sender, date, prediction_hash)`
+    pub fn claim_anchored_prediction(
+        &mut self,
+        date: U256,
+        prediction_hash: B32,
+        proof: Vec<B32>,
+    ) -> Result<(), Vec<u8>> {
+        self.non_reentrant(|contract| {
+            let user = contract.msg_sender();
+            
+            let root = contract.daily_roots.get(date);
+            if root == B32::ZERO {
+                return Err(b"RootNotAnchored".to_vec());
+            }
+            if !merkle::verify(&proof, root, prediction_leaf(user, date, prediction_hash)) {
+                return Err(b"InvalidMerkleProof".to_vec());
+            }
+            
+            contract.store_prediction_for(user, date, prediction_hash, B32::ZERO)?;
+            contract.anchored_predictions.setter(user).setter(date).set(true);
+            log(contract.vm(), AnchoredPredictionClaimed { user, date, day: day_number(date)?, predictionHash: prediction_hash });
+            
+            Ok(())
+        })
+    }
+    
+    /// Get the anchored root for a date
+    /// Returns: (root, count), zero root if none
+    pub fn get_daily_root(&self, date: U256) -> (B32, U256) {
+        (self.daily_roots.get(date), self.daily_counts.get(date))
+    }
+    
+    /// Check if a prediction was stored from an anchored root
+    pub fn is_prediction_anchored(&self, user: Address, date: U256) -> bool {
+        self.anchored_predictions.getter(user).get(date)
+    }
+    
+    /// Get the hash chain of the predictions stored for a date
+    /// 
+    /// Replaying the date's `PredictionStored` events in order through
+    /// `accumulator::accumulate` gives the same hash, so an indexer can't
+    /// drop or reorder them unnoticed. Pruned predictions stay counted.
+    /// 
+    /// Returns: (accumulator, count), zero for a date without predictions
+    pub fn get_daily_accumulator(&self, date: U256) -> (B32, U256) {
+        (self.daily_accumulators.get(date), self.daily_prediction_counts.get(date))
+    }
+}
+
+#[public_if(feature = "farcaster-ratings")]
+impl FarcasterPredictions {
+    /// Rate a prediction (1-5 stars), or clear its rating with 0
+    /// 
+    /// Every rating, an edit included, is a fresh sample of the user's
+    /// time-decayed average (see `get_ewma_rating`); clearing one isn't.
+    /// 
+    /// Parameters:
+    /// - date: Unix timestamp for the prediction date
+    /// - rating: Rating value (1-5), 0 to clear
+    pub fn rate_prediction(
+        &mut self,
+        date: U256,
+        rating: U8,
+    ) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let user = self.msg_sender();
+        
+        // Validate rating
+        if rating > U8::from(5) {
+            return Err(b"InvalidRating".to_vec());
+        }
+        
+        // Check if prediction exists
+        if !self.prediction_exists.getter(user).get(date) {
+            return Err(b"PredictionNotFound".to_vec());
+        }
+        if !self.rating_window_open(date) {
+            return Err(b"RatingWindowClosed".to_vec());
+        }
+        
+        // Check if already rated
+        let existing_rating = self.ratings.getter(user).get(date);
+        let is_new_rating = existing_rating == U8::ZERO;
+        let is_cleared = rating == U8::ZERO;
+        
+        // Clearing a prediction that was never rated changes nothing
+        if is_new_rating && is_cleared {
+            return Ok(());
+        }
+        
+        // Store rating
+        let mut user_ratings = self.ratings.setter(user);
+        user_ratings.setter(date).set(rating);
+        
+        if is_new_rating {
+            // New rating
+            let user_total_ratings = self.total_ratings.get(user);
+            let user_total_ratings = math::checked_inc(user_total_ratings, math::FIELD_TOTAL_RATINGS)?;
+            self.total_ratings.setter(user).set(user_total_ratings);
+            
+            let user_rating_sum = self.rating_sum.get(user);
+            let user_rating_sum = math::checked_add(user_rating_sum, U256::from(rating), math::FIELD_RATING_SUM)?;
+            self.rating_sum.setter(user).set(user_rating_sum);
+        } else {
+            // Update or clear existing rating
+            if is_cleared {
+                let user_total_ratings = self.total_ratings.get(user);
+                self.total_ratings.setter(user).set(user_total_ratings - U256::from(1));
+            }
+            
+            let user_rating_sum = self.rating_sum.get(user);
+            let new_sum = user_rating_sum - U256::from(existing_rating);
+            let new_sum = math::checked_add(new_sum, U256::from(rating), math::FIELD_RATING_SUM)?;
+            self.rating_sum.setter(user).set(new_sum);
+        }
+        self.update_rating_totals(user, date, existing_rating, rating)?;
+        
+        // Per-bucket aggregates, for users who disclosed a birth-year bucket
+        // and didn't opt out
+        if self.user_has_bucket.get(user) && self.aggregate_opt_outs.get(user) {
+            // Keep counting the rating the aggregates hold
+            let mut aggregated = self.aggregated_ratings.setter(user);
+            let mut aggregated = aggregated.setter(date);
+            if !aggregated.set.get() {
+                aggregated.set.set(true);
+                aggregated.rating.set(existing_rating);
+            }
+        } else if self.user_has_bucket.get(user) {
+            let aggregated = self.aggregated_rating(user, date);
+            let counted = aggregated.unwrap_or(existing_rating);
+            let bucket = self.user_buckets.get(user);
+            let bucket_total = self.bucket_total_ratings.get(bucket);
+            if counted == U8::ZERO && !is_cleared {
+                let bucket_total = math::checked_inc(bucket_total, math::FIELD_BUCKET_RATINGS)?;
+                self.bucket_total_ratings.setter(bucket).set(bucket_total);
+            } else if counted != U8::ZERO && is_cleared {
+                self.bucket_total_ratings.setter(bucket).set(bucket_total - U256::from(1));
+            }
+            let bucket_sum = self.bucket_rating_sum.get(bucket);
+            let new_sum = bucket_sum - U256::from(counted);
+            let new_sum = math::checked_add(new_sum, U256::from(rating), math::FIELD_BUCKET_RATINGS)?;
+            self.bucket_rating_sum.setter(bucket).set(new_sum);
+            if aggregated.is_some() {
+                self.aggregated_ratings.setter(user).setter(date).set.set(false);
+            }
+        }
+        
+        if !is_cleared {
+            self.fold_rating(user, rating);
+        }
+        
+        // Points and the day's rater count for the first rating of each
+        // prediction only, the count leaving out users who opted out
+        if is_new_rating && !self.rated_for_points.getter(user).get(date) {
+            self.rated_for_points.setter(user).setter(date).set(true);
+            self.credit_points(user, Activity::Rating);
+            let day = day_key(date)?;
+            if !self.aggregate_opt_outs.get(user) {
+                let raters = math::checked_inc(self.raters_per_date.get(day), math::FIELD_RATERS_PER_DATE)?;
+                self.raters_per_date.setter(day).set(raters);
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-dimensions")]
+impl FarcasterPredictions {
+    /// Add rating dimension `dimension_id` (below `MAX_DIMENSIONS`), e.g.
+    /// keccak256("humor")
+    /// 
+    /// Registration is permanent, so a dimension's ratings keep their
+    /// meaning. Dimension 0 is the rating `rate_prediction` sets, named
+    /// keccak256("rating") by `initialize`.
+    pub fn register_dimension(&mut self, dimension_id: u8, name_hash: B32) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if dimension_id >= MAX_DIMENSIONS {
+            return Err(b"InvalidDimension".to_vec());
+        }
+        if name_hash == B32::ZERO {
+            return Err(b"InvalidDimensionName".to_vec());
+        }
+        let id = U8::from(dimension_id);
+        if self.dimension_names.get(id) != B32::ZERO {
+            return Err(b"DimensionExists".to_vec());
+        }
+        self.dimension_names.setter(id).set(name_hash);
+        log(self.vm(), DimensionRegistered { dimensionId: dimension_id, nameHash: name_hash });
+        self.log_admin(admin_log::REGISTER_DIMENSION, B32::from(U256::from(dimension_id)));
+        Ok(())
+    }
+    
+    /// Get the name hash of a rating dimension (zero = unregistered)
+    pub fn get_dimension(&self, dimension_id: u8) -> B32 {
+        self.dimension_names.get(U8::from(dimension_id))
+    }
+}
+
+#[public_if(feature = "farcaster-dimension-ratings")]
+impl FarcasterPredictions {
+    /// Rate a prediction (1-5) in one dimension, or clear that rating
+    /// with 0
+    /// 
+    /// Dimension 0 is `rate_prediction`. Other dimensions keep their own
+    /// totals, and earn no points.
+    pub fn rate_dimension(&mut self, date: U256, dimension_id: u8, value: U8) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        if dimension_id == LEGACY_DIMENSION {
+            return self.rate_prediction(date, value);
+        }
+        let id = U8::from(dimension_id);
+        if dimension_id >= MAX_DIMENSIONS || self.dimension_names.get(id) == B32::ZERO {
+            return Err(b"UnknownDimension".to_vec());
+        }
+        if value > U8::from(5) {
+            return Err(b"InvalidRating".to_vec());
+        }
+        let user = self.msg_sender();
+        if !self.prediction_exists.getter(user).get(date) {
+            return Err(b"PredictionNotFound".to_vec());
+        }
+        if !self.rating_window_open(date) {
+            return Err(b"RatingWindowClosed".to_vec());
+        }
+        
+        let existing = self.dimension_ratings.getter(user).getter(date).get(id);
+        if existing == value {
+            return Ok(());
+        }
+        self.dimension_ratings.setter(user).setter(date).setter(id).set(value);
+        
+        let mut stats = self.dimension_stats.setter(user);
+        stats.setter(id).replace(existing, value, math::FIELD_DIMENSION_RATINGS)?;
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-dimension-stats")]
+impl FarcasterPredictions {
+    /// Get a user's rating of a prediction in one dimension (0 = unrated)
+    pub fn get_dimension_rating(&self, user: Address, date: U256, dimension_id: u8) -> U8 {
+        if dimension_id == LEGACY_DIMENSION {
+            return self.ratings.getter(user).get(date);
+        }
+        self.dimension_ratings.getter(user).getter(date).get(U8::from(dimension_id))
+    }
+    
+    /// Get a user's rating totals in one dimension, pruned predictions'
+    /// ratings included
+    /// Returns: (total_ratings, average_rating_x10)
+    pub fn get_dimension_stats(&self, user: Address, dimension_id: u8) -> (U256, U256) {
+        let (total_ratings, rating_sum) = if dimension_id == LEGACY_DIMENSION {
+            (self.total_ratings.get(user), self.rating_sum.get(user))
+        } else {
+            let stats = self.dimension_stats.getter(user);
+            let stats = stats.getter(U8::from(dimension_id));
+            (U256::from(stats.total_ratings.get()), U256::from(stats.rating_sum.get()))
+        };
+        let average_x10 = if total_ratings.is_zero() {
+            U256::ZERO
+        } else {
+            rating_sum * U256::from(10) / total_ratings
+        };
+        (total_ratings, average_x10)
+    }
+}
+
+#[public_if(feature = "farcaster-sealed-ratings")]
+impl FarcasterPredictions {
+    /// Set how long after a rating commitment it can be revealed, and
+    /// for how long
+    /// 
+    /// Applies to later commitments only.
+    pub fn set_reveal_timing(&mut self, delay: u64, window: u64) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if window == 0 {
+            return Err(b"InvalidRevealWindow".to_vec());
+        }
+        self.reveal_delay.set(U64::from(delay));
+        self.reveal_window.set(U64::from(window));
+        let target = (U256::from(delay) << 64) | U256::from(window);
+        self.log_admin(admin_log::SET_REVEAL_TIMING, B32::from(target));
+        Ok(())
+    }
+    
+    /// Get the reveal timing of rating commitments
+    /// Returns: (delay, window) in seconds
+    pub fn get_reveal_timing(&self) -> (u64, u64) {
+        match self.reveal_window.get().to::<u64>() {
+            0 => (DEFAULT_REVEAL_DELAY, DEFAULT_REVEAL_WINDOW),
+            window => (self.reveal_delay.get().to::<u64>(), window),
+        }
+    }
+    
+    /// Commit to a hidden rating of `target`'s prediction for `date`
+    /// 
+    /// `commitment` is `keccak256(rating || salt || rater)`, see
+    /// `sealed_rating`. Reveal it with `reveal_rating` once the reveal
+    /// delay passed and before the window closes; unrevealed, it expires
+    /// without effect. Each rater rates a prediction once.
+    pub fn commit_rating(&mut self, target: Address, date: U256, commitment: B32) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let rater = self.msg_sender();
+        if !self.community_eligible(rater) {
+            return Err(b"InsufficientHistory".to_vec());
+        }
+        if rater == target {
+            return Err(b"CannotRateOwnPrediction".to_vec());
+        }
+        if commitment == B32::ZERO {
+            return Err(b"InvalidCommitment".to_vec());
+        }
+        if !self.prediction_exists.getter(target).get(date) {
+            return Err(b"PredictionNotFound".to_vec());
+        }
+        if self.community_ratings.getter(target).getter(date).get(rater) != U8::ZERO {
+            return Err(b"AlreadyRated".to_vec());
+        }
+        
+        let now = self.vm().block_timestamp();
+        let (_, _, expires_at) = self.rating_commitment(rater, target, date);
+        if expires_at != 0 {
+            if now < expires_at {
+                return Err(b"CommitmentPending".to_vec());
+            }
+            log(self.vm(), RatingCommitmentExpired { rater, target, date, day: day_number(date)? });
+        }
+        
+        let (delay, window) = self.get_reveal_timing();
+        let reveal_at = now.saturating_add(delay);
+        let expires_at = reveal_at.saturating_add(window);
+        let mut commitments = self.rating_commitments.setter(rater);
+        let mut commitments = commitments.setter(target);
+        let mut entry = commitments.setter(date);
+        entry.commitment.set(commitment);
+        entry.reveal_at.set(U64::from(reveal_at));
+        entry.expires_at.set(U64::from(expires_at));
+        log(self.vm(), RatingCommitted { rater, target, date, day: day_number(date)?, revealAt: reveal_at, expiresAt: expires_at });
+        Ok(())
+    }
+    
+    /// Reveal a committed rating (1-5) of `target`'s prediction for
+    /// `date`, adding it to the community totals
+    pub fn reveal_rating(&mut self, target: Address, date: U256, rating: U8, salt: B32) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let rater = self.msg_sender();
+        let (commitment, reveal_at, expires_at) = self.rating_commitment(rater, target, date);
+        if commitment == B32::ZERO {
+            return Err(b"NoCommitment".to_vec());
+        }
+        let now = self.vm().block_timestamp();
+        if now < reveal_at {
+            return Err(b"RevealTooEarly".to_vec());
+        }
+        if now >= expires_at {
+            return Err(b"CommitmentExpired".to_vec());
+        }
+        if rating == U8::ZERO || rating > U8::from(5) {
+            return Err(b"InvalidRating".to_vec());
+        }
+        if B32::from(sealed_rating::commitment(rating.to::<u8>(), salt, rater)) != commitment {
+            return Err(b"InvalidReveal".to_vec());
+        }
+        if !self.prediction_exists.getter(target).get(date) {
+            return Err(b"PredictionNotFound".to_vec());
+        }
+        
+        self.clear_rating_commitment(rater, target, date);
+        self.community_ratings.setter(target).setter(date).setter(rater).set(rating);
+        let mut totals = self.community_totals.setter(target);
+        totals.setter(date).replace(U8::ZERO, rating, math::FIELD_COMMUNITY_RATINGS)?;
+        log(self.vm(), RatingRevealed { rater, target, date, day: day_number(date)?, rating: rating.to::<u8>() });
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-community")]
+impl FarcasterPredictions {
+    /// Drop a commitment whose reveal window closed; anyone may call
+    pub fn expire_rating_commitment(&mut self, rater: Address, target: Address, date: U256) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let (commitment, _, expires_at) = self.rating_commitment(rater, target, date);
+        if commitment == B32::ZERO {
+            return Err(b"NoCommitment".to_vec());
+        }
+        if self.vm().block_timestamp() < expires_at {
+            return Err(b"CommitmentNotExpired".to_vec());
+        }
+        self.clear_rating_commitment(rater, target, date);
+        log(self.vm(), RatingCommitmentExpired { rater, target, date, day: day_number(date)? });
+        Ok(())
+    }
+    
+    /// Check if `rater` has an unexpired, unrevealed rating commitment
+    /// for `target`'s `date`
+    pub fn has_rating_commitment(&self, rater: Address, target: Address, date: U256) -> bool {
+        let (commitment, _, expires_at) = self.rating_commitment(rater, target, date);
+        commitment != B32::ZERO && self.vm().block_timestamp() < expires_at
+    }
+    
+    /// Get when a rating commitment can be revealed and when it expires
+    /// Returns: (reveal_at, expires_at), zero when there's no commitment
+    pub fn get_reveal_deadline(&self, rater: Address, target: Address, date: U256) -> (U256, U256) {
+        let (_, reveal_at, expires_at) = self.rating_commitment(rater, target, date);
+        (U256::from(reveal_at), U256::from(expires_at))
+    }
+    
+    /// Get the rating `rater` revealed for `target`'s `date` (0 = none)
+    pub fn get_community_rating(&self, target: Address, date: U256, rater: Address) -> U8 {
+        self.community_ratings.getter(target).getter(date).get(rater)
+    }
+    
+    /// Get the revealed community ratings of `target`'s `date`
+    /// Returns: (total_ratings, average_rating_x10)
+    pub fn get_community_stats(&self, target: Address, date: U256) -> (U256, U256) {
+        let totals = self.community_totals.getter(target);
+        let totals = totals.getter(date);
+        let total_ratings = U256::from(totals.total_ratings.get());
+        let average_x10 = if total_ratings.is_zero() {
+            U256::ZERO
+        } else {
+            U256::from(totals.rating_sum.get()) * U256::from(10) / total_ratings
+        };
+        (total_ratings, average_x10)
+    }
+}
+
+#[public_if(feature = "farcaster-pruning")]
+impl FarcasterPredictions {
+    /// Delete the sender's predictions for `dates`, all past the retention
+    /// horizon, freeing their storage
+    /// 
+    /// Stats and rating aggregates keep counting pruned predictions, and a
+    /// pruned date can't be stored again. Each date is logged with its hash
+    /// so indexers keep the history.
+    pub fn prune_predictions(&mut self, dates: Vec<U256>) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let user = self.msg_sender();
+        
+        let retention_days = self.min_retention_days.get();
+        if retention_days.is_zero() {
+            return Err(b"PruningDisabled".to_vec());
+        }
+        let retention = U256::from(retention_days) * U256::from(calendar::SECONDS_PER_DAY);
+        let now = U256::from(self.vm().block_timestamp());
+        
+        let dimensions: Vec<U8> = (1..MAX_DIMENSIONS)
+            .map(U8::from)
+            .filter(|id| self.dimension_names.get(*id) != B32::ZERO)
+            .collect();
+        let mut pruned_ratings = U256::ZERO;
+        let mut pruned_rating_sum = U256::ZERO;
+        for date in &dates {
+            let date = *date;
+            if !self.prediction_exists.getter(user).get(date) {
+                return Err(b"PredictionNotFound".to_vec());
+            }
+            if date.saturating_add(retention) > now {
+                return Err(b"PredictionTooRecent".to_vec());
+            }
+            
+            let rating = self.ratings.getter(user).get(date);
+            if rating != U8::ZERO {
+                pruned_ratings = math::checked_inc(pruned_ratings, math::FIELD_PRUNED_RATINGS)?;
+                pruned_rating_sum = math::checked_add(pruned_rating_sum, U256::from(rating), math::FIELD_PRUNED_RATING_SUM)?;
+            }
+            
+            let prediction_hash = self.predictions.getter(user).get(date);
+            self.predictions.setter(user).setter(date).erase();
+            self.prediction_exists.setter(user).setter(date).erase();
+            self.ratings.setter(user).setter(date).erase();
+            for id in &dimensions {
+                self.dimension_ratings.setter(user).setter(date).setter(*id).erase();
+            }
+            self.prediction_charts.setter(user).setter(date).erase();
+            self.pruned_predictions.setter(user).setter(date).set(true);
+            self.set_prediction_day(user, date, false);
+            log(self.vm(), PredictionPruned { user, date, day: day_number(date)?, predictionHash: prediction_hash });
+        }
+        
+        let pruned = math::checked_add(self.pruned_count.get(user), U256::from(dates.len()), math::FIELD_PRUNED_COUNT)?;
+        self.pruned_count.setter(user).set(pruned);
+        if !pruned_ratings.is_zero() {
+            let ratings = self.pruned_ratings.get(user);
+            let ratings = math::checked_add(ratings, pruned_ratings, math::FIELD_PRUNED_RATINGS)?;
+            self.pruned_ratings.setter(user).set(ratings);
+            let sum = self.pruned_rating_sum.get(user);
+            let sum = math::checked_add(sum, pruned_rating_sum, math::FIELD_PRUNED_RATING_SUM)?;
+            self.pruned_rating_sum.setter(user).set(sum);
+        }
+        
+        Ok(())
+    }
+    
+    /// Get how many of a user's predictions were pruned
+    /// 
+    /// Their stats still count them: `total_predictions` minus this is the
+    /// number of predictions still stored.
+    pub fn get_pruned_count(&self, user: Address) -> U256 {
+        self.pruned_count.get(user)
+    }
+    
+    /// Check if a user pruned their prediction for a date
+    pub fn is_prediction_pruned(&self, user: Address, date: U256) -> bool {
+        self.pruned_predictions.getter(user).get(date)
+    }
+}
+
+#[public_if(feature = "farcaster-audits")]
+impl FarcasterPredictions {
+    /// Recount a user's predictions, ratings and rating sum from storage,
+    /// one page of their dates at a time
+    /// 
+    /// The first page (offset 0) also counts what the user pruned, so the
+    /// pages add up to what `total_predictions`, `total_ratings` and
+    /// `rating_sum` should hold. When one page covers every date,
+    /// `consistent` compares them; otherwise it only checks the page
+    /// doesn't exceed them. It also checks the page's dates against the
+    /// day bitmap. Users registered before dates were kept (`registered_at`
+    /// zero) may have predictions no page lists.
+    /// 
+    /// Parameters:
+    /// - user: Account to audit
+    /// - date_offset: Index of the first date to recount
+    /// - date_limit: Most dates to recount, capped at `MAX_PAGE_SIZE`
+    /// 
+    /// Returns: (consistent, predictions, ratings, rating_sum)
+    pub fn audit_user(&self, user: Address, date_offset: U256, date_limit: U256) -> (bool, U256, U256, U256) {
+        let len = U256::from(self.prediction_dates.getter(user).len());
+        let start = date_offset.min(len);
+        let end = start.saturating_add(date_limit.min(U256::from(MAX_PAGE_SIZE))).min(len);
+        let (predictions, ratings, rating_sum, days_match) = self.recount(user, start.to::<usize>(), end.to::<usize>());
+        
+        let cached = (self.total_predictions.get(user), self.total_ratings.get(user), self.rating_sum.get(user));
+        let counters_match = if start.is_zero() && end == len {
+            (predictions, ratings, rating_sum) == cached
+        } else {
+            predictions <= cached.0 && ratings <= cached.1 && rating_sum <= cached.2
+        };
+        (counters_match && days_match, predictions, ratings, rating_sum)
+    }
+}
+
+#[public_if(feature = "farcaster-imports")]
+impl FarcasterPredictions {
+    /// Import users registered elsewhere, e.g. on the contract this one
+    /// replaces (owner only)
+    /// 
+    /// Each user is registered as `register_user` would, registration
+    /// points included, with the given registration time. Every entry is
+    /// checked first, see `import`. With `dry_run` the statuses are
+    /// returned and nothing is written. Otherwise the batch is written
+    /// only when every entry is `import::OK`, and fails with
+    /// `ImportRejected` when one isn't.
+    /// 
+    /// A nonzero `idempotency_key` the sender already used makes a write
+    /// a no-op returning no statuses, see `is_idempotency_key_used`. Dry
+    /// runs ignore the key.
+    /// 
+    /// Parameters:
+    /// - users, commitments, registered_at: One user per index, at most
+    ///   `import::MAX_IMPORT_ENTRIES`
+    /// 
+    /// Returns: the status of each entry, in input order
+    pub fn import_users(
+        &mut self,
+        users: Vec<Address>,
+        commitments: Vec<B32>,
+        registered_at: Vec<U256>,
+        dry_run: bool,
+        idempotency_key: B32,
+    ) -> Result<Vec<u8>, Vec<u8>> {
+        self.only_owner()?;
+        if dry_run {
+            return self.check_user_import(&users, &commitments, &registered_at);
+        }
+        let mut statuses = Vec::new();
+        self.idempotent(idempotency_key, |contract| {
+            statuses = contract.check_user_import(&users, &commitments, &registered_at)?;
+            if statuses.iter().any(|status| *status != import::OK) {
+                return Err(b"ImportRejected".to_vec());
+            }
+            for (i, user) in users.iter().enumerate() {
+                contract.store_commitment(*user, commitments[i])?;
+                contract.registered_at.setter(*user).set(registered_at[i]);
+            }
+            contract.log_admin(admin_log::IMPORT_USERS, B32::from(U256::from(users.len())));
+            Ok(())
+        })?;
+        Ok(statuses)
+    }
+    
+    /// Overwrite a user's cached counters with a recount of all their dates
+    /// 
+    /// Their day bitmap is rewritten from the same dates, which also fills
+    /// it in for predictions stored before it existed.
+    /// Global and birth-year bucket aggregates are left as they are: which
+    /// of them a bad counter also skewed can't be told from storage. The
+    /// recount walks every date in one call, so its gas grows with the
+    /// user's predictions. Users registered before dates were kept can't
+    /// be repaired, as their recount would miss predictions.
+    pub fn repair_user_counters(&mut self, user: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if !self.user_has_data.get(user) {
+            return Err(b"UserNotRegistered".to_vec());
+        }
+        if self.registered_at.get(user).is_zero() {
+            return Err(b"IncompleteDateHistory".to_vec());
+        }
+        
+        let len = self.prediction_dates.getter(user).len();
+        let (predictions, ratings, rating_sum, _) = self.recount(user, 0, len);
+        for i in 0..len {
+            let date = self.prediction_dates.getter(user).get(i).unwrap_or_default();
+            let exists = self.prediction_exists.getter(user).get(date);
+            self.set_prediction_day(user, date, exists);
+        }
+        self.total_predictions.setter(user).set(predictions);
+        self.total_ratings.setter(user).set(ratings);
+        self.rating_sum.setter(user).set(rating_sum);
+        
+        log(self.vm(), CountersRepaired {
+            user,
+            totalPredictions: predictions,
+            totalRatings: ratings,
+            ratingSum: rating_sum,
+        });
+        self.log_admin(admin_log::REPAIR_USER_COUNTERS, user.into_word());
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-users")]
+impl FarcasterPredictions {
+    /// Get user's birth data commitment
+    pub fn get_user_commitment(&self, user: Address) -> B32 {
+        self.user_commitments.get(user)
+    }
+    
+    /// Check if user is registered
+    pub fn is_user_registered(&self, user: Address) -> bool {
+        self.user_has_data.get(user)
+    }
+    
+    /// Get the proof scheme version a user registered with
+    /// Returns 0 for users registered without a proof
+    pub fn get_user_scheme_version(&self, user: Address) -> u8 {
+        self.user_scheme_versions.get(user).to::<u8>()
+    }
+    
+    /// Export everything stored for a user, e.g. for account portability
+    /// 
+    /// Predictions are paged over the user's dates in storage order;
+    /// pruned ones are listed with a zero hash and rating.
+    /// 
+    /// Parameters:
+    /// - user: Account to export
+    /// - date_offset: Index of the first prediction to include
+    /// - date_limit: Most predictions to include, capped at `MAX_PAGE_SIZE`
+    pub fn export_user(&self, user: Address, date_offset: U256, date_limit: U256) -> UserExport {
+        let (total_predictions, total_ratings, average_rating_x10) = self.get_user_stats(user);
+        
+        let all_dates = self.prediction_dates.getter(user);
+        let len = U256::from(all_dates.len());
+        let start = date_offset.min(len);
+        let end = start.saturating_add(date_limit.min(U256::from(MAX_PAGE_SIZE))).min(len);
+        let dates: Vec<U256> = (start.to::<usize>()..end.to::<usize>()).filter_map(|i| all_dates.get(i)).collect();
+        let hashes = dates.iter().map(|date| self.predictions.getter(user).get(*date)).collect();
+        let ratings = dates.iter().map(|date| self.ratings.getter(user).get(*date).to::<u8>()).collect();
+        
+        UserExport {
+            commitment: self.user_commitments.get(user),
+            registered: self.user_has_data.get(user),
+            registeredAt: self.registered_at.get(user),
+            stats: UserStats {
+                totalPredictions: total_predictions,
+                totalRatings: total_ratings,
+                averageRatingX10: average_rating_x10,
+            },
+            dates,
+            hashes,
+            ratings,
+        }
+    }
+}
+
+#[public_if(feature = "farcaster-history")]
+impl FarcasterPredictions {
+    /// Merkleize the next page of a user's history, see `history`
+    /// 
+    /// Pages go through the user's date list in order: `date_offset` 0
+    /// starts over, any other offset must be where the last page ended.
+    /// Pages hold up to `MAX_PAGE_SIZE` dates; pruned dates add no leaf.
+    /// Only the user and the owner may build a user's root.
+    /// Returns: (root of the leaves so far, dates processed so far)
+    pub fn compute_user_history_root(
+        &mut self,
+        user: Address,
+        date_offset: U256,
+        date_limit: U256,
+    ) -> Result<(B32, U256), Vec<u8>> {
+        self.only_live()?;
+        self.only_user_or_owner(user)?;
+        let mut build = self.history_builds.setter(user);
+        if date_offset.is_zero() {
+            build.next_index.set(U64::ZERO);
+            build.leaf_count.set(U64::ZERO);
+        } else if date_offset != U256::from(build.next_index.get()) {
+            return Err(b"HistoryOutOfOrder".to_vec());
+        }
+        
+        let mut leaf_count = build.leaf_count.get().to::<u64>();
+        let mut frontier: Vec<B32> = (0..build.frontier.len()).filter_map(|i| build.frontier.get(i)).collect();
+        let dates = self.prediction_dates.getter(user);
+        let start = date_offset.saturating_to::<usize>();
+        let end = start.saturating_add(date_limit.min(U256::from(MAX_PAGE_SIZE)).to::<usize>()).min(dates.len());
+        for date in (start..end).filter_map(|i| dates.get(i)) {
+            if !self.prediction_exists.getter(user).get(date) {
+                continue;
+            }
+            let prediction_hash = self.predictions.getter(user).get(date);
+            let rating = self.ratings.getter(user).get(date).to::<u8>();
+            merkle::frontier_push(&mut frontier, leaf_count, history::leaf(date, prediction_hash, rating));
+            leaf_count += 1;
+        }
+        
+        let mut build = self.history_builds.setter(user);
+        while build.frontier.len() < frontier.len() {
+            build.frontier.push(B32::ZERO);
+        }
+        for (i, node) in frontier.iter().enumerate() {
+            if let Some(mut slot) = build.frontier.setter(i) {
+                slot.set(*node);
+            }
+        }
+        let next_index = end.max(start) as u64;
+        build.next_index.set(U64::from(next_index));
+        build.leaf_count.set(U64::from(leaf_count));
+        Ok((merkle::frontier_root(&frontier, leaf_count), U256::from(next_index)))
+    }
+    
+    /// Store the root `compute_user_history_root` built over the user's
+    /// whole date list, and emit it
+    /// 
+    /// A rating changed after its page was processed isn't in the root;
+    /// build again from offset 0 to include it.
+    pub fn finalize_history_root(&mut self, user: Address) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        self.only_user_or_owner(user)?;
+        let build = self.history_builds.getter(user);
+        let next_index = build.next_index.get().to::<u64>();
+        if next_index == 0 || next_index < self.prediction_dates.getter(user).len() as u64 {
+            return Err(b"HistoryIncomplete".to_vec());
+        }
+        let leaf_count = build.leaf_count.get().to::<u64>();
+        let frontier: Vec<B32> = (0..build.frontier.len()).filter_map(|i| build.frontier.get(i)).collect();
+        let root = merkle::frontier_root(&frontier, leaf_count);
+        
+        let now = self.vm().block_timestamp();
+        let mut stored = self.history_roots.setter(user);
+        stored.root.set(root);
+        stored.leaf_count.set(U64::from(leaf_count));
+        stored.finalized_at.set(U64::from(now));
+        log(self.vm(), HistoryRootFinalized { user, root, leaves: U256::from(leaf_count) });
+        Ok(())
+    }
+    
+    /// Get a user's finalized history root
+    /// Returns: (root, leaves, finalized_at), zero before any
+    pub fn get_history_root(&self, user: Address) -> (B32, U256, U256) {
+        let stored = self.history_roots.getter(user);
+        (stored.root.get(), U256::from(stored.leaf_count.get()), U256::from(stored.finalized_at.get()))
+    }
+    
+    /// Get user's Pedersen commitment point
+    /// Returns: (x, y), or (0, 0) if the user has no Pedersen commitment
+    pub fn get_pedersen_commitment(&self, user: Address) -> (U256, U256) {
+        if !self.pedersen_commitments.get(user) {
+            return (U256::ZERO, U256::ZERO);
+        }
+        
+        let compressed = U256::from_be_bytes(self.user_commitments.get(user).0);
+        match G1Point::decompress(compressed) {
+            Some(point) => (point.x, point.y),
+            None => (U256::ZERO, U256::ZERO),
+        }
+    }
+}
+
+#[public_if(feature = "farcaster-queries")]
+impl FarcasterPredictions {
+    /// Get prediction hash for a specific date
+    pub fn get_prediction(
+        &self,
+        user: Address,
+        date: U256,
+    ) -> B32 {
+        self.predictions.getter(user).get(date)
+    }
+    
+    /// Check if prediction exists for a date
+    pub fn has_prediction(
+        &self,
+        user: Address,
+        date: U256,
+    ) -> bool {
+        self.prediction_exists.getter(user).get(date)
+    }
+    
+    /// Check if a prediction exists for day number `day` (days since
+    /// 1970-01-01, see `calendar::to_day_number`)
+    /// 
+    /// Reads the day bitmap, which holds predictions stored under a
+    /// midnight timestamp before `day_bitmap::DATE_LIMIT`; those stored
+    /// under any other timestamp are only found by `has_prediction`.
+    pub fn has_prediction_on_day(&self, user: Address, day: u32) -> bool {
+        let date = calendar::to_timestamp(day);
+        day_bitmap::date_slot(date).is_some_and(|slot| self.prediction_day(user, slot))
+    }
+    
+    /// `has_prediction` for each of `users` on one date, in input order
+    /// 
+    /// Takes up to `MAX_BATCH_USERS` users; a repeated user gets a repeated
+    /// answer. The date is looked up as given, like `has_prediction`.
+    pub fn have_predictions(&self, users: Vec<Address>, date: U256) -> Result<Vec<bool>, Vec<u8>> {
+        if users.len() > MAX_BATCH_USERS {
+            return Err(b"TooManyUsers".to_vec());
+        }
+        Ok(users.into_iter().map(|user| self.prediction_exists.getter(user).get(date)).collect())
+    }
+    
+    /// Get each of `users`' prediction for one date, in input order
+    /// 
+    /// Same limits as `have_predictions`.
+    /// 
+    /// Returns: (exists, prediction_hash, rating), parallel arrays with an
+    /// entry per user; zero hash and rating when missing or unrated
+    pub fn get_predictions_multi(&self, users: Vec<Address>, date: U256) -> Result<PredictionColumns, Vec<u8>> {
+        let exists = self.have_predictions(users.clone(), date)?;
+        let hashes = users.iter().map(|user| self.predictions.getter(*user).get(date)).collect();
+        let ratings = users.iter().map(|user| self.ratings.getter(*user).get(date).to::<u8>()).collect();
+        Ok((exists, hashes, ratings))
+    }
+    
+    /// Storage slot holding `user`'s prediction hash for `date`, for
+    /// verifying it with a storage proof (see `slots`)
+    pub fn prediction_slot(user: Address, date: U256) -> B32 {
+        let user_slot = slots::mapping_slot(user.into_word().0, slots::base_slot(PREDICTIONS_SLOT));
+        B32::from(slots::mapping_slot(date.to_be_bytes(), user_slot))
+    }
+    
+    /// Storage slot holding `user`'s birth data commitment, for verifying
+    /// it with a storage proof (see `slots`)
+    pub fn commitment_slot(user: Address) -> B32 {
+        B32::from(slots::mapping_slot(user.into_word().0, slots::base_slot(USER_COMMITMENTS_SLOT)))
+    }
+}
+
+#[public_if(feature = "farcaster-payloads")]
+impl FarcasterPredictions {
+    /// Check a revealed prediction against the hash stored for its date
+    /// 
+    /// The fields are re-encoded as a `payload::PredictionPayload` and
+    /// hashed here, so an audit checks the prediction itself rather than
+    /// trusting a hash or a pre-encoded blob from the backend.
+    /// 
+    /// Returns: whether `user` stored a prediction for `date` whose hash
+    /// is the payload's
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_prediction_payload(
+        &self,
+        user: Address,
+        version: u8,
+        date: u64,
+        category: u8,
+        text: String,
+        lucky_number: u16,
+        lucky_color: u8,
+        model_id: u32,
+    ) -> bool {
+        let payload = payload::PredictionPayload { version, date, category, text, lucky_number, lucky_color, model_id };
+        let date = U256::from(date);
+        self.prediction_exists.getter(user).get(date)
+            && self.predictions.getter(user).get(date) == B32::from(payload.hash())
+    }
+    
+    /// Get rating for a specific date
+    pub fn get_rating(
+        &self,
+        user: Address,
+        date: U256,
+    ) -> U8 {
+        self.ratings.getter(user).get(date)
+    }
+    
+    /// Get a user's predictions for the days from `start_date` to
+    /// `end_date`, inclusive
+    /// 
+    /// Reads the user's day bitmap a word (up to 256 days) at a time and
+    /// loads hashes only for days with a prediction, so empty days cost
+    /// nothing. Only predictions stored at midnight UTC are in the bitmap,
+    /// and those stored before it existed only once
+    /// `repair_user_counters` fills it in.
+    /// 
+    /// Parameters:
+    /// - start_date, end_date: Timestamps in the first and last day, at
+    ///   most `MAX_RANGE_DAYS` days apart
+    /// 
+    /// Returns: (dates, hashes) of the days with a prediction, in order
+    pub fn get_predictions_range(
+        &self,
+        user: Address,
+        start_date: U256,
+        end_date: U256,
+    ) -> Result<(Vec<U256>, Vec<B32>), Vec<u8>> {
+        let day = |date: U256| {
+            calendar::to_day_number(date)
+                .ok()
+                .filter(|_| date < U256::from(day_bitmap::DATE_LIMIT))
+                .map(u64::from)
+                .ok_or(b"InvalidDate".to_vec())
+        };
+        let (first_day, last_day) = (day(start_date)?, day(end_date)?);
+        if last_day < first_day {
+            return Err(b"InvalidRange".to_vec());
+        }
+        if last_day - first_day >= MAX_RANGE_DAYS {
+            return Err(b"RangeTooLong".to_vec());
+        }
+        
+        let mut dates = Vec::new();
+        let mut hashes = Vec::new();
+        for span in day_bitmap::word_spans(first_day, last_day) {
+            let bits = self.prediction_days.getter(user).getter(U32::from(span.year)).get(span.word).unwrap_or_default();
+            if bits.is_zero() {
+                continue;
+            }
+            for day in span.first_day..=span.last_day {
+                if bits.bit(day_bitmap::day_slot(day).bit) {
+                    let date = calendar::to_timestamp(day as u32);
+                    dates.push(date);
+                    hashes.push(self.predictions.getter(user).get(date));
+                }
+            }
+        }
+        Ok((dates, hashes))
+    }
+}
+
+#[public_if(feature = "farcaster-endorsements")]
+impl FarcasterPredictions {
+    /// Get a rating with a hash attesting it at the current block
+    /// 
+    /// The hash binds the rating to this chain, contract, user, date and
+    /// block, see `attestation::rating_attestation`, so whoever archives
+    /// the tuple can check it stayed consistent. Unrated dates are
+    /// attested too, with rating 0.
+    /// Returns: (rating, exists, block_number, attestation_hash)
+    pub fn get_rating_attestation(&self, user: Address, date: U256) -> (u8, bool, U256, B32) {
+        let rating = self.ratings.getter(user).get(date).to::<u8>();
+        let block_number = self.vm().block_number();
+        let hash = attestation::rating_attestation(
+            self.vm().chain_id(),
+            self.vm().contract_address(),
+            user,
+            date,
+            rating,
+            block_number,
+        );
+        (rating, rating != 0, U256::from(block_number), B32::from(hash))
+    }
+    
+    /// Endorse (co-sign) a user's prediction as an astrologer
+    /// 
+    /// Each astrologer endorses a prediction once, and a prediction takes
+    /// at most `MAX_ENDORSEMENTS`.
+    /// 
+    /// Parameters:
+    /// - user, date: The prediction, which must exist
+    /// - note_hash: Hash of the astrologer's note, kept off-chain
+    pub fn endorse_prediction(&mut self, user: Address, date: U256, note_hash: B32) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let astrologer = self.msg_sender();
+        if !self.astrologers.get(astrologer) {
+            return Err(b"NotAstrologer".to_vec());
+        }
+        if !self.prediction_exists.getter(user).get(date) {
+            return Err(b"PredictionNotFound".to_vec());
+        }
+        
+        let endorsements = self.endorsements.getter(user);
+        let endorsements = endorsements.getter(date);
+        if (0..endorsements.len()).any(|i| endorsements.getter(i).is_some_and(|e| e.astrologer.get() == astrologer)) {
+            return Err(b"AlreadyEndorsed".to_vec());
+        }
+        if endorsements.len() >= MAX_ENDORSEMENTS {
+            return Err(b"TooManyEndorsements".to_vec());
+        }
+        
+        let timestamp = self.vm().block_timestamp();
+        let mut endorsements = self.endorsements.setter(user);
+        let mut endorsements = endorsements.setter(date);
+        let mut endorsement = endorsements.grow();
+        endorsement.astrologer.set(astrologer);
+        endorsement.timestamp.set(U64::from(timestamp));
+        endorsement.note_hash.set(note_hash);
+        log(self.vm(), PredictionEndorsed { user, date, day: day_number(date)?, astrologer, noteHash: note_hash });
+        Ok(())
+    }
+    
+    /// Get a prediction's endorsements, in the order they were made
+    /// Returns: (astrologers, note_hashes, timestamps), parallel
+    pub fn get_endorsements(&self, user: Address, date: U256) -> (Vec<Address>, Vec<B32>, Vec<U256>) {
+        let endorsements = self.endorsements.getter(user);
+        let endorsements = endorsements.getter(date);
+        let mut astrologers = Vec::with_capacity(endorsements.len());
+        let mut note_hashes = Vec::with_capacity(endorsements.len());
+        let mut timestamps = Vec::with_capacity(endorsements.len());
+        for endorsement in (0..endorsements.len()).filter_map(|i| endorsements.getter(i)) {
+            astrologers.push(endorsement.astrologer.get());
+            note_hashes.push(endorsement.note_hash.get());
+            timestamps.push(U256::from(endorsement.timestamp.get()));
+        }
+        (astrologers, note_hashes, timestamps)
+    }
+    
+    /// Get user statistics
+    /// Returns: (total_predictions, total_ratings, average_rating_x10)
+    /// Note: average_rating is multiplied by 10 to avoid decimals
+    pub fn get_user_stats(&self, user: Address) -> (U256, U256, U256) {
+        let total_predictions = self.total_predictions.get(user);
+        let total_ratings = self.total_ratings.get(user);
+        let rating_sum = self.rating_sum.get(user);
+        
+        let average_x10 = if total_ratings > U256::ZERO {
+            (rating_sum * U256::from(10)) / total_ratings
+        } else {
+            U256::ZERO
+        };
+        
+        (total_predictions, total_ratings, average_x10)
+    }
+    
+    /// Get a user's ratings of predictions dated in one month, whenever
+    /// they were rated
+    /// 
+    /// Parameters:
+    /// - month_index: Months since January 1970 (see `calendar::month_index`)
+    /// 
+    /// Returns: (count, average_x10), zero for a month without ratings.
+    /// Ratings set before this version count once they are edited.
+    pub fn get_monthly_rating(&self, user: Address, month_index: u32) -> (U256, U256) {
+        let totals = self.monthly_ratings.getter(user);
+        let totals = totals.getter(U32::from(month_index));
+        let count = U256::from(totals.total_ratings.get());
+        if count.is_zero() {
+            return (U256::ZERO, U256::ZERO);
+        }
+        (count, U256::from(totals.rating_sum.get()) * U256::from(10) / count)
+    }
+    
+    /// Get how many users rated their prediction for a date
+    /// 
+    /// `date` is taken down to midnight UTC, the day predictions are keyed
+    /// by. A user counts from their first rating of the date on: edits
+    /// don't add to the count, and clearing a rating doesn't take them off
+    /// it. Users who were opted out of aggregates at their first rating
+    /// aren't counted, nor are ratings given before this version, and
+    /// dates past the last day number have none.
+    pub fn get_raters_for_date(&self, date: U256) -> U256 {
+        day_key(date).map_or(U256::ZERO, |day| self.raters_per_date.get(day))
+    }
+    
+    /// Get `get_raters_for_date` for the 7 days starting at `week_start`
+    pub fn get_raters_for_week(&self, week_start: U256) -> Vec<U256> {
+        let day = U256::from(calendar::SECONDS_PER_DAY);
+        (0..7u64)
+            .map(|i| self.get_raters_for_date(week_start.saturating_add(day * U256::from(i))))
+            .collect()
+    }
+}
+
+#[public_if(feature = "farcaster-pairs")]
+impl FarcasterPredictions {
+    /// Join the synastry pair of the sender's chart `chart_key` and
+    /// `partner_chart_key`, whose key is `pair_key` of the two
+    /// 
+    /// The pair is formed once the owners of both charts have joined, two
+    /// different accounts. From then on either partner can store the
+    /// pair's joint predictions and rate them. When a chart registry is
+    /// configured, each chart must be the joining partner's there; without
+    /// one, each chart's place goes to whoever joins with it first.
+    pub fn join_pair(&mut self, chart_key: B32, partner_chart_key: B32) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let partner = self.msg_sender();
+        if chart_key == B32::ZERO || partner_chart_key == B32::ZERO || chart_key == partner_chart_key {
+            return Err(b"InvalidPair".to_vec());
+        }
+        self.check_chart_owner(partner, chart_key)?;
+        
+        let key = pair_key(chart_key, partner_chart_key);
+        let mut pair = self.synastry_pairs.setter(key);
+        let (joined, other) = if chart_key < partner_chart_key {
+            (pair.owner_a.get(), pair.owner_b.get())
+        } else {
+            (pair.owner_b.get(), pair.owner_a.get())
+        };
+        if joined != Address::ZERO {
+            return Err(b"AlreadyJoined".to_vec());
+        }
+        if other == partner {
+            return Err(b"InvalidPair".to_vec());
+        }
+        if chart_key < partner_chart_key {
+            pair.chart_a.set(chart_key);
+            pair.chart_b.set(partner_chart_key);
+            pair.owner_a.set(partner);
+        } else {
+            pair.chart_a.set(partner_chart_key);
+            pair.chart_b.set(chart_key);
+            pair.owner_b.set(partner);
+        }
+        log(self.vm(), PairJoined { pairKey: key, partner, chartKey: chart_key });
+        Ok(())
+    }
+    
+    /// Get a synastry pair's charts and the partners that joined with them
+    /// Returns: (chart_a, owner_a, chart_b, owner_b), the lower chart key
+    /// first and zero owners until they join
+    pub fn get_pair(&self, pair_key: B32) -> (B32, Address, B32, Address) {
+        let pair = self.synastry_pairs.getter(pair_key);
+        (pair.chart_a.get(), pair.owner_a.get(), pair.chart_b.get(), pair.owner_b.get())
+    }
+    
+    /// Store the joint prediction of a formed synastry pair for a date
+    /// 
+    /// Either partner can store it, once per date, under the same date
+    /// rules as `store_prediction`: no backfilling, the ephemeris published
+    /// when an oracle is set, and no dates past the last day number. Pair
+    /// predictions are kept apart from each partner's own: they don't
+    /// count in user or global stats, and earn no points.
+    pub fn store_pair_prediction(&mut self, pair_key: B32, date: U256, prediction_hash: B32) -> Result<(), Vec<u8>> {
+        self.only_storing()?;
+        let partner = self.msg_sender();
+        self.check_pair_member(pair_key, partner)?;
+        self.check_backfill(date)?;
+        if prediction_hash == B32::ZERO {
+            return Err(b"InvalidPredictionHash".to_vec());
+        }
+        if self.pair_predictions.getter(pair_key).get(date) != B32::ZERO {
+            return Err(b"PredictionAlreadyExists".to_vec());
+        }
+        let day = self.check_prediction_date(date)?;
+        self.count_write();
+        
+        self.pair_predictions.setter(pair_key).setter(date).set(prediction_hash);
+        let count = math::checked_inc(self.pair_prediction_counts.get(pair_key), math::FIELD_PAIR_PREDICTIONS)?;
+        self.pair_prediction_counts.setter(pair_key).set(count);
+        log(self.vm(), PairPredictionStored { pairKey: pair_key, date, day, partner, predictionHash: prediction_hash });
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-pair-ratings")]
+impl FarcasterPredictions {
+    /// Rate a synastry pair's joint prediction (1-5 stars) as one of its
+    /// partners, or clear the sender's rating with 0
+    /// 
+    /// Each partner's rating is kept apart, and the pair's stats count
+    /// both. The rating window applies as for `rate_prediction`.
+    pub fn rate_pair_prediction(&mut self, pair_key: B32, date: U256, rating: U8) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        if rating > U8::from(5) {
+            return Err(b"InvalidRating".to_vec());
+        }
+        let partner = self.msg_sender();
+        self.check_pair_member(pair_key, partner)?;
+        if !self.has_pair_prediction(pair_key, date) {
+            return Err(b"PredictionNotFound".to_vec());
+        }
+        if !self.rating_window_open(date) {
+            return Err(b"RatingWindowClosed".to_vec());
+        }
+        
+        let existing = self.pair_ratings.getter(pair_key).getter(date).get(partner);
+        if existing == rating {
+            return Ok(());
+        }
+        self.pair_ratings.setter(pair_key).setter(date).setter(partner).set(rating);
+        self.pair_rating_totals.setter(pair_key).replace(existing, rating, math::FIELD_PAIR_RATINGS)?;
+        log(self.vm(), PairPredictionRated { pairKey: pair_key, date, day: day_number(date)?, partner, rating: rating.to::<u8>() });
+        Ok(())
+    }
+    
+    /// Get a synastry pair's joint prediction hash for a date (zero = none)
+    pub fn get_pair_prediction(&self, pair_key: B32, date: U256) -> B32 {
+        self.pair_predictions.getter(pair_key).get(date)
+    }
+    
+    /// Check if a synastry pair has a joint prediction for a date
+    pub fn has_pair_prediction(&self, pair_key: B32, date: U256) -> bool {
+        self.get_pair_prediction(pair_key, date) != B32::ZERO
+    }
+    
+    /// Get both partners' ratings of a pair's joint prediction, in the
+    /// order of `get_pair`, and their average
+    /// Returns: (rating_a, rating_b, average_rating_x10), zero for
+    /// unrated; the average is over the partners who rated
+    pub fn get_pair_rating(&self, pair_key: B32, date: U256) -> (U8, U8, U256) {
+        let pair = self.synastry_pairs.getter(pair_key);
+        let ratings = self.pair_ratings.getter(pair_key);
+        let ratings = ratings.getter(date);
+        let rating_a = ratings.get(pair.owner_a.get());
+        let rating_b = ratings.get(pair.owner_b.get());
+        
+        let count = [rating_a, rating_b].iter().filter(|rating| !rating.is_zero()).count();
+        let average_x10 = if count == 0 {
+            U256::ZERO
+        } else {
+            U256::from(rating_a.to::<u64>() + rating_b.to::<u64>()) * U256::from(10) / U256::from(count)
+        };
+        (rating_a, rating_b, average_x10)
+    }
+    
+    /// Get a synastry pair's statistics, counting both partners' ratings
+    /// Returns: (total_predictions, total_ratings, average_rating_x10)
+    pub fn get_pair_stats(&self, pair_key: B32) -> (U256, U256, U256) {
+        let totals = self.pair_rating_totals.getter(pair_key);
+        let total_ratings = U256::from(totals.total_ratings.get());
+        let average_x10 = if total_ratings.is_zero() {
+            U256::ZERO
+        } else {
+            U256::from(totals.rating_sum.get()) * U256::from(10) / total_ratings
+        };
+        (self.pair_prediction_counts.get(pair_key), total_ratings, average_x10)
+    }
+}
+
+#[public_if(feature = "farcaster-blinded")]
+impl FarcasterPredictions {
+    /// Rate a prediction without revealing the rating
+    /// 
+    /// Stores only a Pedersen commitment `C = rating·G + blinding·H` (see
+    /// `commitment`) and adds it to `model_id`'s aggregate, once a range
+    /// proof shows C holds a rating from 0 to 5. Blinded ratings don't
+    /// count in the plain stats. A prediction takes one blinded rating,
+    /// which can't be changed; `reveal_blinded_aggregate` opens a model's
+    /// sum.
+    /// 
+    /// Parameters:
+    /// - date: Date of the caller's prediction
+    /// - model_id: Model that wrote the prediction (the payload's `model_id`)
+    /// - blinded_rating: C, compressed (`G1Point::compress`)
+    /// - range_proof: `range_proof::RangeProof` bytes, bound to the caller,
+    ///   `date` and `model_id`
+    pub fn rate_prediction_blinded(
+        &mut self,
+        date: U256,
+        model_id: u32,
+        blinded_rating: U256,
+        range_proof: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let user = self.msg_sender();
+        
+        if !self.prediction_exists.getter(user).get(date) {
+            return Err(b"PredictionNotFound".to_vec());
+        }
+        if !self.rating_window_open(date) {
+            return Err(b"RatingWindowClosed".to_vec());
+        }
+        if !self.blinded_ratings.getter(user).get(date).is_zero() {
+            return Err(b"AlreadyRated".to_vec());
+        }
+        
+        let commitment = G1Point::decompress(blinded_rating)
+            .filter(|point| !point.is_infinity())
+            .ok_or_else(|| b"InvalidCommitment".to_vec())?;
+        let proof = range_proof::RangeProof::from_bytes(&range_proof).ok_or_else(|| b"InvalidRangeProof".to_vec())?;
+        let context = range_proof::RatingContext { rater: user, date, model_id };
+        let ops = Precompiles(self.vm());
+        if !range_proof::verify_with(&ops, &commitment, &proof, &context) {
+            return Err(b"InvalidRangeProof".to_vec());
+        }
+        
+        let aggregate = self.blinded_aggregates.getter(U32::from(model_id));
+        let sum = G1Point::new(aggregate.sum_x.get(), aggregate.sum_y.get());
+        let count = aggregate.count.get();
+        let sum = ops.ec_add(&sum, &commitment).ok_or_else(|| b"InvalidCommitment".to_vec())?;
+        let mut aggregate = self.blinded_aggregates.setter(U32::from(model_id));
+        aggregate.sum_x.set(sum.x);
+        aggregate.sum_y.set(sum.y);
+        aggregate.count.set(math::checked_inc(count, math::FIELD_BLINDED_RATINGS)?);
+        self.blinded_ratings.setter(user).setter(date).set(blinded_rating);
+        
+        log(self.vm(), BlindedRatingAdded { user, date, day: day_number(date)?, modelId: model_id, commitment: blinded_rating });
+        Ok(())
+    }
+}
+
+#[public_if(feature = "farcaster-blinded-reveals")]
+impl FarcasterPredictions {
+    /// Open `model_id`'s aggregate, publishing the sum of its blinded
+    /// ratings
+    /// 
+    /// `blinding_sum` is the sum of the ratings' blinding factors mod the
+    /// group order, collected from the raters off-chain. The aggregate
+    /// must equal `sum·G + blinding_sum·H`, so only the true sum opens it.
+    /// Ratings added afterwards count from the next reveal on.
+    pub fn reveal_blinded_aggregate(&mut self, model_id: u32, sum: U256, blinding_sum: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        
+        let aggregate = self.blinded_aggregates.getter(U32::from(model_id));
+        let point = G1Point::new(aggregate.sum_x.get(), aggregate.sum_y.get());
+        let count = aggregate.count.get();
+        let ops = Precompiles(self.vm());
+        let opened = commitment::commit_with(&ops, sum, blinding_sum);
+        let max_sum = U256::from(count) * U256::from(range_proof::MAX_RATING);
+        if count.is_zero() || sum > max_sum || opened != Some(point) {
+            return Err(b"InvalidOpening".to_vec());
+        }
+        
+        let mut aggregate = self.blinded_aggregates.setter(U32::from(model_id));
+        aggregate.revealed_count.set(count);
+        aggregate.revealed_sum.set(U128::from(sum));
+        
+        log(self.vm(), BlindedAggregateRevealed { modelId: model_id, count: U256::from(count), sum });
+        Ok(())
+    }
+    
+    /// Get the compressed commitment of a user's blinded rating (zero when
+    /// none)
+    pub fn get_blinded_rating(&self, user: Address, date: U256) -> U256 {
+        self.blinded_ratings.getter(user).get(date)
+    }
+}
+
+#[public_if(feature = "farcaster-blinded-reads")]
+impl FarcasterPredictions {
+    /// Get a model's aggregate of blinded ratings
+    /// 
+    /// Returns: (count, sum of their commitments, compressed)
+    pub fn get_blinded_aggregate(&self, model_id: u32) -> (U256, U256) {
+        let aggregate = self.blinded_aggregates.getter(U32::from(model_id));
+        let sum = G1Point::new(aggregate.sum_x.get(), aggregate.sum_y.get());
+        (U256::from(aggregate.count.get()), sum.compress())
+    }
+    
+    /// Get what the owner last revealed of a model's blinded ratings
+    /// 
+    /// Returns: (count, sum), zero before the first reveal
+    pub fn get_revealed_aggregate(&self, model_id: u32) -> (U256, U256) {
+        let aggregate = self.blinded_aggregates.getter(U32::from(model_id));
+        (U256::from(aggregate.revealed_count.get()), U256::from(aggregate.revealed_sum.get()))
+    }
+    
+    /// List a user's stored predictions that are still unrated and still
+    /// open for rating, e.g. to remind them
+    /// 
+    /// Pages over the user's dates in storage order like `export_user`, so
+    /// a page can hold fewer than `limit` dates, or none, with more to
+    /// come; the next page starts at `offset + limit`. Pruned predictions
+    /// and dates whose rating window has closed are skipped.
+    /// 
+    /// Parameters:
+    /// - user: Account to list
+    /// - before_date: Only dates before this one
+    /// - offset: Index of the first date to look at
+    /// - limit: Most dates to look at, capped at `MAX_PAGE_SIZE`
+    pub fn get_unrated_dates(&self, user: Address, before_date: U256, offset: U256, limit: U256) -> Vec<U256> {
+        let all_dates = self.prediction_dates.getter(user);
+        let len = U256::from(all_dates.len());
+        let start = offset.min(len);
+        let end = start.saturating_add(limit.min(U256::from(MAX_PAGE_SIZE))).min(len);
+        (start.to::<usize>()..end.to::<usize>())
+            .filter_map(|i| all_dates.get(i))
+            .filter(|date| {
+                *date < before_date
+                    && self.prediction_exists.getter(user).get(*date)
+                    && self.ratings.getter(user).get(*date) == U8::ZERO
+                    && self.rating_window_open(*date)
+            })
+            .collect()
+    }
+}
+
+#[public_if(feature = "farcaster-settings")]
+impl FarcasterPredictions {
+    /// Set the half-life, in days, of the time-decayed rating average
+    /// 
+    /// Applies from each user's next rating on; averages aren't
+    /// recomputed.
+    pub fn set_rating_half_life(&mut self, half_life_days: u64) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if half_life_days == 0 {
+            return Err(b"InvalidHalfLife".to_vec());
+        }
+        self.rating_half_life_days.set(U64::from(half_life_days));
+        self.log_admin(admin_log::SET_RATING_HALF_LIFE, B32::from(U256::from(half_life_days)));
+        Ok(())
+    }
+    
+    /// Get the half-life, in days, of the time-decayed rating average
+    pub fn get_rating_half_life(&self) -> u64 {
+        match self.rating_half_life_days.get().to::<u64>() {
+            0 => DEFAULT_RATING_HALF_LIFE_DAYS,
+            days => days,
+        }
+    }
+    
+    /// Set how many days after its date a prediction can be rated (zero =
+    /// no limit)
+    /// 
+    /// Once `block.timestamp` passes `date + days * 86400`, rating, editing
+    /// or clearing that prediction's rating fails with
+    /// `RatingWindowClosed`.
+    pub fn set_rating_window_days(&mut self, days: u64) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.rating_window_days.set(U64::from(days));
+        self.log_admin(admin_log::SET_RATING_WINDOW_DAYS, B32::from(U256::from(days)));
+        Ok(())
+    }
+    
+    /// Get how many days after its date a prediction can be rated (zero
+    /// when there's no limit)
+    pub fn get_rating_window_days(&self) -> u64 {
+        self.rating_window_days.get().to::<u64>()
+    }
+    
+    /// Get a user's time-decayed average rating
+    /// 
+    /// A rating given `d` days before the latest weighs `2^(-d / half_life)`
+    /// of it, see `decay`. Edits count as fresh ratings. Unlike
+    /// `get_user_stats`, the average reflects recent ratings quickly.
+    /// Returns: (average × 1000, time of the last rating), zero before any
+    pub fn get_ewma_rating(&self, user: Address) -> (U256, U256) {
+        let average = self.rating_averages.getter(user);
+        (U256::from(average.value_x1000.get()), U256::from(average.last_update.get()))
+    }
+    
+    /// Get a user's birth-year bucket
+    /// Returns: (has_bucket, bucket)
+    pub fn get_user_bucket(&self, user: Address) -> (bool, u8) {
+        (self.user_has_bucket.get(user), self.user_buckets.get(user).to::<u8>())
+    }
+    
+    /// Keep the sender's future actions out of anonymous aggregates, or
+    /// let them back in
+    /// 
+    /// While opted out, registering, storing and rating leave the global
+    /// and bucket stats and the raters per date as they are; personal
+    /// stats, points and the daily accumulators still count everything. Toggling never rewrites what
+    /// the aggregates already hold.
+    pub fn set_aggregate_opt_out(&mut self, opted_out: bool) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let user = self.msg_sender();
+        self.aggregate_opt_outs.setter(user).set(opted_out);
+        Ok(())
+    }
+    
+    /// Check if a user's actions are kept out of aggregates
+    pub fn is_opted_out(&self, user: Address) -> bool {
+        self.aggregate_opt_outs.get(user)
+    }
+    
+    /// Set the sender's UTC offset, in minutes, for `local_date_key`
+    /// 
+    /// The offset is fixed: there is no daylight saving time, so users
+    /// who observe it set the offset again when their clocks change.
+    /// Stored predictions keep their UTC date keys whatever the offset.
+    /// 
+    /// Parameters:
+    /// - offset_minutes: -840 to 840 (UTC-14:00 to UTC+14:00), in whole
+    ///   quarter hours
+    pub fn set_timezone(&mut self, offset_minutes: i16) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        if !calendar::is_valid_utc_offset(offset_minutes) {
+            return Err(b"InvalidUtcOffset".to_vec());
+        }
+        let user = self.msg_sender();
+        self.utc_offsets.setter(user).set(I16::unchecked_from(offset_minutes));
+        Ok(())
+    }
+    
+    /// Get a user's UTC offset in minutes (zero unless they set one)
+    pub fn get_timezone(&self, user: Address) -> i16 {
+        self.utc_offsets.get(user).as_i16()
+    }
+    
+    /// Date key of the user's local day containing `timestamp`
+    /// 
+    /// The key is midnight UTC of the user's local calendar date, so it
+    /// is the `date` to store the user's prediction for that day under:
+    /// with offset +05:30, 20:00 UTC on the 10th gives the key of the
+    /// 11th. See `calendar::local_date_key`.
+    pub fn local_date_key(&self, user: Address, timestamp: U256) -> Result<U256, Vec<u8>> {
+        let timestamp = u64::try_from(timestamp).map_err(|_| b"InvalidDate".to_vec())?;
+        calendar::local_date_key(timestamp, self.get_timezone(user))
+            .map(U256::from)
+            .ok_or(b"InvalidDate".to_vec())
+    }
+    
+    /// Get rating aggregates of a birth-year bucket
+    /// Returns: (total_ratings, rating_sum)
+    pub fn get_bucket_stats(&self, bucket: u8) -> (U256, U256) {
+        let bucket = U8::from(bucket);
+        (self.bucket_total_ratings.get(bucket), self.bucket_rating_sum.get(bucket))
+    }
+}
+
+#[public_if(feature = "farcaster-astro")]
+impl FarcasterPredictions {
+    /// Get the zodiac sign (0 = Aries ... 11 = Pisces) of a longitude in
+    /// centidegrees
+    pub fn get_sign_for_longitude(&self, longitude: U256) -> Result<u8, Vec<u8>> {
+        let longitude = u64::try_from(longitude).map_err(|_| b"InvalidLongitude".to_vec())?;
+        astro::sign_of(longitude).map_err(|_| b"InvalidLongitude".to_vec())
+    }
+    
+    /// Get the compatibility (0-100) of two zodiac signs (0 = Aries ...
+    /// 11 = Pisces), from the fixed table `astro::COMPATIBILITY`
+    pub fn get_sign_compatibility(&self, sign_a: u8, sign_b: u8) -> Result<u8, Vec<u8>> {
+        astro::compatibility_score(sign_a, sign_b).map_err(|_| b"InvalidSign".to_vec())
+    }
+    
+    /// Get the nakshatra and pada of a tropical longitude, both in
+    /// centidegrees
+    /// Returns: (nakshatra 0-26, pada 1-4)
+    pub fn get_nakshatra(&self, longitude: U256, ayanamsa: U256) -> Result<(u8, u8), Vec<u8>> {
+        let longitude = u64::try_from(longitude)
+            .ok()
+            .filter(|longitude| *longitude <= poseidon::MAX_POSITION)
+            .ok_or(b"InvalidLongitude".to_vec())?;
+        let ayanamsa = u64::try_from(ayanamsa)
+            .ok()
+            .filter(|ayanamsa| *ayanamsa <= poseidon::MAX_POSITION)
+            .ok_or(b"InvalidAyanamsa".to_vec())?;
+        astro::nakshatra_of(astro::tropical_to_sidereal(longitude, ayanamsa)).map_err(|_| b"InvalidLongitude".to_vec())
+    }
+    
+    /// Get the moon phase at a timestamp
+    /// Returns: (phase_index 0-7 from new moon, illumination x1000)
+    pub fn get_moon_phase(&self, date: U256) -> Result<(u8, U256), Vec<u8>> {
+        let date = u64::try_from(date).map_err(|_| b"InvalidDate".to_vec())?;
+        let (phase, illumination) = astro::moon_phase(date);
+        Ok((phase, U256::from(illumination)))
+    }
+    
+    /// Score the transits of a day against a user's natal chart
+    /// 
+    /// Natal positions come from the user's latest chart with positions in
+    /// the chart registry, of their latest `MAX_PAGE_SIZE`, transits from
+    /// the ephemeris oracle. Aspects
+    /// within 3° are weighted conjunction 5, opposition 4, square 3,
+    /// trine 2, sextile 1.
+    /// Returns: (score, aspect_count)
+    pub fn score_transits(&self, user: Address, date: U256) -> Result<(U256, U256), Vec<u8>> {
+        let natal = self.natal_positions(user)?;
+        let transits = self.ephemeris_positions(date)?;
+        
+        let longitudes = |chart: [positions::PlanetPosition; positions::CHART_BODIES]| {
+            chart.map(|position| position.longitude_centideg as u64)
+        };
+        let (score, count) = astro::transit_score(&longitudes(natal), &longitudes(transits));
+        Ok((U256::from(score), U256::from(count)))
+    }
+}
+
+#[public_if(feature = "farcaster-lucky")]
+impl FarcasterPredictions {
+    /// Check if the Sun on `date` is within `orb_centideg` of the user's
+    /// natal Sun, i.e. around their birthday
+    /// 
+    /// Positions are read as for `score_transits`. Missing positions are
+    /// errors, not `false`: NoNatalPositions, NoEphemerisForDate or
+    /// MissingSun when a chart has no Sun.
+    pub fn is_solar_return_window(&self, user: Address, date: U256, orb_centideg: u64) -> Result<bool, Vec<u8>> {
+        let natal = self.natal_positions(user)?;
+        let transits = self.ephemeris_positions(date)?;
+        
+        let natal_sun = astro::sun_longitude(&natal).map_err(|_| b"MissingSun".to_vec())?;
+        let transit_sun = astro::sun_longitude(&transits).map_err(|_| b"MissingSun".to_vec())?;
+        Ok(astro::angular_separation(natal_sun, transit_sun) <= orb_centideg)
+    }
+    
+    /// Get the seed of a day's draws, such as the frame's tarot card
+    /// 
+    /// Derived from the positions the ephemeris oracle published for
+    /// `date`, see `seed`, so it can't be known before they are: until
+    /// then it fails with SeedNotAvailable.
+    pub fn get_daily_seed(&self, date: U256) -> Result<B32, Vec<u8>> {
+        let packed = self.ephemeris_packed(date).map_err(|error| match error.as_slice() {
+            b"NoEphemerisForDate" => b"SeedNotAvailable".to_vec(),
+            _ => error,
+        })?;
+        let seed = seed::daily_seed(&packed.map(|word| word.0), date, self.vm().contract_address());
+        Ok(B32::from(seed))
+    }
+    
+    /// Draw a user's pick in `0..modulus` from a daily seed
+    /// 
+    /// Pure: `keccak256(seed || user) mod modulus`, see `seed::pick`.
+    pub fn pick_from_seed(&self, seed: B32, user: Address, modulus: U256) -> Result<U256, Vec<u8>> {
+        seed::pick(&seed.0, user, modulus).ok_or_else(|| b"InvalidModulus".to_vec())
+    }
+    
+    /// Get a user's lucky number (1-100) for the day containing `date`
+    /// 
+    /// Derived from the user's commitment and the day only, so anyone can
+    /// recompute it; see `lucky`. Dates past the last day number fail
+    /// with InvalidDate.
+    pub fn derive_lucky_number(&self, user: Address, date: U256) -> Result<U256, Vec<u8>> {
+        let commitment = self.registered_commitment(user)?;
+        let number = lucky::lucky_number(&commitment.0, date).map_err(|_| b"InvalidDate".to_vec())?;
+        Ok(U256::from(number))
+    }
+    
+    /// Get a user's lucky color (0 = Red ... 11 = Pink, see
+    /// `lucky::color_name`) for the day containing `date`
+    pub fn derive_lucky_color(&self, user: Address, date: U256) -> Result<u8, Vec<u8>> {
+        let commitment = self.registered_commitment(user)?;
+        lucky::lucky_color(&commitment.0, date).map_err(|_| b"InvalidDate".to_vec())
+    }
+}
+
+#[public_if(feature = "farcaster-stats")]
+impl FarcasterPredictions {
+    /// Get global statistics, without actions of opted-out users
+    /// Returns: (total_users, total_predictions)
+    pub fn get_global_stats(&self) -> (U256, U256) {
+        (self.total_users.get(), self.global_predictions.get())
+    }
+    
+    /// Get the count and sum of all ratings, without those of opted-out
+    /// users
+    /// 
+    /// Ratings given before this version are only counted once edited.
+    /// Returns: (rating_count, rating_sum)
+    pub fn get_global_rating_stats(&self) -> (U256, U256) {
+        let totals = &self.global_ratings;
+        (U256::from(totals.total_ratings.get()), U256::from(totals.rating_sum.get()))
+    }
+    
+    /// Emit the global counters in a `DailyCheckpoint` event
+    /// 
+    /// Anyone can call this, once per UTC day, so the counters can be
+    /// rebuilt from logs alone. Reverts with `AlreadyCheckpointed` if
+    /// today's checkpoint is already emitted.
+    pub fn checkpoint(&mut self) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let day = day_number(U256::from(self.vm().block_timestamp()))?;
+        if self.last_checkpoint_day.get() == U64::from(day) {
+            return Err(b"AlreadyCheckpointed".to_vec());
+        }
+        self.last_checkpoint_day.set(U64::from(day));
+        
+        let (rating_count, rating_sum) = self.get_global_rating_stats();
+        log(self.vm(), DailyCheckpoint {
+            day: day.into(),
+            totalUsers: self.total_users.get(),
+            globalPredictions: self.global_predictions.get(),
+            globalRatingCount: rating_count,
+            globalRatingSum: rating_sum,
+        });
+        Ok(())
+    }
+    
+    /// Get the day index (days since the epoch) of the last checkpoint,
+    /// zero before the first
+    pub fn get_last_checkpoint_day(&self) -> u64 {
+        self.last_checkpoint_day.get().to::<u64>()
+    }
+    
+    /// Estimate what storing predictions for `dates` costs `user`, as a
+    /// `multicall` of `store_prediction` calls
+    /// 
+    /// Counts the slots the batch would write (hash, exists flag, date
+    /// list, day bitmap words, daily accumulator and count, user and
+    /// global counters, points, streak, cleared proposals, hourly write
+    /// count) and the logs it
+    /// would emit, priced by `INK_COSTS`. Duplicates are dates already
+    /// stored or pruned, or listed twice; they would revert the batch and
+    /// aren't counted. Execution, reads and the prediction hook aren't
+    /// priced, so the estimate runs below the final cost.
+    /// 
+    /// Returns: (new_slots, duplicate_count, estimated_ink)
+    pub fn estimate_batch_cost(&self, user: Address, dates: Vec<U256>) -> (U256, U256, U256) {
+        let mut fresh: Vec<U256> = Vec::with_capacity(dates.len());
+        let mut duplicates = 0u64;
+        for date in dates {
+            if fresh.contains(&date)
+                || self.prediction_exists.getter(user).get(date)
+                || self.pruned_predictions.getter(user).get(date)
+            {
+                duplicates += 1;
+            } else {
+                fresh.push(date);
+            }
+        }
+        if fresh.is_empty() {
+            return (U256::ZERO, U256::from(duplicates), U256::ZERO);
+        }
+        
+        // Whether each slot written after the per-date entries was zero
+        let mut writes = Vec::new();
+        let mut write = |was_zero: bool| writes.push(was_zero);
+        let mut cleared = 0u64;
+        
+        let count = fresh.len() as u64;
+        write(self.prediction_dates.getter(user).is_empty());
+        write(self.total_predictions.get(user).is_zero());
+        if !self.aggregate_opt_outs.get(user) {
+            write(self.global_predictions.get().is_zero());
+        }
+        if self.auto_pause_threshold.get() != U64::ZERO {
+            let hour_index = U256::from(self.vm().block_timestamp() / SECONDS_PER_HOUR);
+            write(self.writes_in_hour.get(hour_index).is_zero());
+        }
+        
+        let mut words = Vec::new();
+        for date in &fresh {
+            write(self.daily_accumulators.get(*date) == B32::ZERO);
+            write(self.daily_prediction_counts.get(*date).is_zero());
+            // Clearing a proposal resets both of its slots
+            if self.pending_predictions.getter(user).getter(*date).prediction_hash.get() != B32::ZERO {
+                cleared += 2;
+            }
+            if let Some(slot) = day_bitmap::date_slot(*date) {
+                if !words.contains(&(slot.year, slot.word)) {
+                    words.push((slot.year, slot.word));
+                }
+            }
+        }
+        for (year, word) in words {
+            let days = self.prediction_days.getter(user);
+            write(days.getter(U32::from(year)).get(word).unwrap_or_default().is_zero());
+        }
+        
+        // Replay the streak to count milestones
+        let streak = self.streaks.getter(user);
+        let (mut last_day, mut length) = (streak.last_day.get().to::<u64>(), streak.length.get().to::<u64>());
+        let streak_was_zero = last_day == 0 && length == 0;
+        let mut milestones = 0u64;
+        for date in &fresh {
+            // A date without a day number can't be stored
+            let Ok(day) = calendar::to_day_number(*date).map(u64::from) else {
+                continue;
+            };
+            if day <= last_day {
+                continue;
+            }
+            length = if day == last_day + 1 { length + 1 } else { 1 };
+            last_day = day;
+            if length % STREAK_MILESTONE == 0 {
+                milestones += 1;
+            }
+        }
+        if (last_day, length) != (streak.last_day.get().to::<u64>(), streak.length.get().to::<u64>()) {
+            write(streak_was_zero);
+        }
+        
+        let (_, prediction_points, _, streak_points) = self.get_point_weights();
+        let credits = if prediction_points > 0 { count } else { 0 } + if streak_points > 0 { milestones } else { 0 };
+        if credits > 0 {
+            write(self.points.get(user).is_zero());
+        }
+        
+        // Hash, exists flag and date list entry of each date are new
+        let set = 3 * count + writes.iter().filter(|was_zero| **was_zero).count() as u64;
+        let reset = cleared + writes.iter().filter(|was_zero| !**was_zero).count() as u64;
+        
+        // Three topics each; PredictionStored has two data words (day
+        // number and hash), PointsCredited one
+        let log_ink = |words: u64| INK_COSTS.log + 3 * INK_COSTS.log_topic + 32 * words * INK_COSTS.log_byte;
+        let logs = count * log_ink(2) + credits * log_ink(1);
+        let ink = set * INK_COSTS.slot_set + reset * INK_COSTS.slot_reset + logs;
+        (U256::from(set), U256::from(duplicates), U256::from(ink))
+    }
+}
+
+#[public_if(not(feature = "farcaster-facet"))]
+#[cfg_attr(feature = "test-hooks", implements(TestHooks))]
+impl FarcasterPredictions {
+    /// Initialize the contract (once), setting its owner
+    /// 
+    /// Accepts proofs from scheme v1 up to the latest supported version.
+    /// Deployments initialized before the flag existed already have an
+    /// owner and are rejected too.
+    pub fn initialize(&mut self, owner: Address) -> Result<(), Vec<u8>> {
+        if self.initialized.get() || self.owner.get() != Address::ZERO {
+            return Err(b"AlreadyInitialized".to_vec());
+        }
+        if owner == Address::ZERO {
+            return Err(b"InvalidOwner".to_vec());
+        }
+        
+        self.initialized.set(true);
+        self.owner.set(owner);
+        self.min_accepted_version.set(U8::from(SCHEME_V1));
+        self.max_accepted_version.set(U8::from(LATEST_SCHEME_VERSION));
+        self.dimension_names.setter(U8::from(LEGACY_DIMENSION)).set(keccak(b"rating"));
+        
+        self.log_admin(admin_log::INITIALIZE, owner.into_word());
+        Ok(())
+    }
+    
+    /// Execute several calls to this contract in one transaction
+    /// 
+    /// Each entry is ABI-encoded calldata (selector and arguments) for a
+    /// method of this contract, run in order. Methods of the core are
+    /// routed internally and the others delegated to their facet, so every
+    /// one sees the original msg_sender, forwarded or not. Not payable: a
+    /// batch sent with value is rejected. The batch reverts with the revert
+    /// data of the first failing call. The batch holds the reentrancy lock,
+    /// and the guarded methods it routes run under it; delegated methods
+    /// take it themselves.
+    /// Returns: return data of each call
+    pub fn multicall(&mut self, calls: Vec<Bytes>) -> Result<Vec<Bytes>, Vec<u8>> {
+        // Guarded methods it routes run under its lock
+        self.non_reentrant(|contract| {
+            let mut results = Vec::with_capacity(calls.len());
+            for call in calls {
+                let Some((selector, input)) = call.split_first_chunk::<4>() else {
+                    return Err(b"UnknownSelector".to_vec());
+                };
+                let selector = u32::from_be_bytes(*selector);
+                let routed = <Self as Router<Self>>::route(contract, selector, input).or_else(|| {
+                    let call = contract.forwardable(&call);
+                    contract.released(|contract| contract.delegate(&call))
+                });
+                match routed {
+                    Some(Ok(output)) => results.push(Bytes::from(output)),
+                    Some(Err(revert)) => return Err(revert),
+                    None => return Err(b"UnknownSelector".to_vec()),
+                }
+            }
+            Ok(results)
+        })
+    }
+    
+    /// Delegate calls with each of `selectors` to `facet` (zero = revert
+    /// them instead) from `FACET_DELAY` seconds on, owner only
+    /// 
+    /// The facet runs on this contract's storage, so it must be a build
+    /// of this contract (a `farcaster-*` feature) of the same
+    /// implementation version; see `facets` for the trust model. Until
+    /// the delay is over the current facet keeps the selector, and a
+    /// change still pending is replaced. Selectors of the core are always
+    /// routed by the core.
+    pub fn set_facet(&mut self, facet: Address, selectors: Vec<FixedBytes<4>>) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let now = self.vm().block_timestamp();
+        for selector in selectors {
+            self.facets.setter(selector).schedule(facet, now);
+            self.log_admin(admin_log::SET_FACET, facet_target(selector, facet));
+        }
+        Ok(())
+    }
+    
+    /// Get the facet calls with `selector` are delegated to (zero = none)
+    pub fn get_facet(&self, selector: FixedBytes<4>) -> Address {
+        self.facet(selector)
+    }
+    
+    /// Get the facet registered to take over `selector`, and when
+    /// 
+    /// Returns: (zero, 0) when no change is pending
+    pub fn get_pending_facet(&self, selector: FixedBytes<4>) -> (Address, u64) {
+        self.facets.getter(selector).pending(self.vm().block_timestamp())
+    }
+    
+    /// Delegate calls to methods outside the core to their facet, and
+    /// revert with no data when none is set
+    /// 
+    /// Payable so payable facet methods can be reached; the value stays
+    /// with the call and non-payable ones reject it themselves.
+    #[fallback]
+    #[payable]
+    pub fn delegate_to_facet(&mut self, calldata: &[u8]) -> stylus_sdk::ArbResult {
+        self.delegate(calldata).unwrap_or_else(|| Err(Vec::new()))
+    }
+}
+
+// Routing needs top-level storage; the entrypoint is a function, so the
+// macro does not implement it, and `multicall` routes through this
+// contract's own router even when another contract of the crate is deployed.
+unsafe impl stylus_sdk::stylus_core::storage::TopLevelStorage for FarcasterPredictions {}
+
+impl Forwarding for FarcasterPredictions {
+    fn trusted_forwarder(&self) -> Address {
+        self.trusted_forwarder.get()
+    }
+    
+    fn forwarded_sender(&self) -> &ForwardedSender {
+        &self.forwarded_sender
+    }
+}
+
+impl Faceted for FarcasterPredictions {
+    fn facet(&self, selector: FixedBytes<4>) -> Address {
+        self.facets.getter(selector).at(self.vm().block_timestamp())
+    }
+}
+
+impl NonReentrant for FarcasterPredictions {
+    fn locked(&self) -> bool {
+        self.reentrancy_locked.get()
+    }
+    
+    fn set_locked(&mut self, locked: bool) {
+        self.reentrancy_locked.set(locked);
+    }
+    
+    fn lock_held(&self) -> &LockHeld {
+        &self.reentrancy_held
+    }
+}
+
+/// Raw storage writes for tests that need states the entry points rule
+/// out, such as counters out of step with the predictions they count
+/// 
+/// Unit tests call them directly. With the `test-hooks` feature they are
+/// also public, for tests driving a deployed build; without it (every
+/// release build) they don't exist and no selector routes to them.
+#[cfg(any(test, feature = "test-hooks"))]
+pub trait TestHooks {
+    /// Overwrite `user`'s cached prediction count
+    fn test_set_total_predictions(&mut self, user: Address, value: U256);
+    /// Overwrite `user`'s cached rating count
+    fn test_set_total_ratings(&mut self, user: Address, value: U256);
+    /// Overwrite `user`'s cached rating sum
+    fn test_set_rating_sum(&mut self, user: Address, value: U256);
+    /// Write a prediction hash and its exists flag, and nothing else: no
+    /// date list entry, day bit, counter or aggregate
+    fn test_force_prediction(&mut self, user: Address, date: U256, prediction_hash: B32);
+    /// Write a rating, and nothing else
+    fn test_force_rating(&mut self, user: Address, date: U256, rating: u8);
+}
+
+#[cfg(any(test, feature = "test-hooks"))]
+#[cfg_attr(feature = "test-hooks", public)]
+impl TestHooks for FarcasterPredictions {
+    fn test_set_total_predictions(&mut self, user: Address, value: U256) {
+        self.total_predictions.setter(user).set(value);
+    }
+    
+    fn test_set_total_ratings(&mut self, user: Address, value: U256) {
+        self.total_ratings.setter(user).set(value);
+    }
+    
+    fn test_set_rating_sum(&mut self, user: Address, value: U256) {
+        self.rating_sum.setter(user).set(value);
+    }
+    
+    fn test_force_prediction(&mut self, user: Address, date: U256, prediction_hash: B32) {
+        self.predictions.setter(user).setter(date).set(prediction_hash);
+        self.prediction_exists.setter(user).setter(date).set(true);
+    }
+    
+    fn test_force_rating(&mut self, user: Address, date: U256, rating: u8) {
+        self.ratings.setter(user).setter(date).set(U8::from(rating));
+    }
+}
+
+impl FarcasterPredictions {
+    /// Reject writes once the contract was sunset
+    fn only_live(&self) -> Result<(), Vec<u8>> {
+        if self.sunsetted.get() {
+            return Err(b"ContractSunset".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Reject storing predictions once sunset or while paused
+    fn only_storing(&self) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        if self.paused.get() {
+            return Err(b"Paused".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Check the ephemeris and day number of a prediction's `date`, the
+    /// last checks before it is written
+    /// Returns: the day number
+    fn check_prediction_date(&self, date: U256) -> Result<u32, Vec<u8>> {
+        self.check_ephemeris(date)?;
+        day_number(date)
+    }
+    
+    /// Reject callers other than the owner
+    /// 
+    /// Owner-only methods all write, so they stop at sunset too.
+    fn only_owner(&self) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        if self.msg_sender() != self.owner.get() {
+            return Err(b"NotOwner".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Reject callers other than `user` and the owner
+    fn only_user_or_owner(&self, user: Address) -> Result<(), Vec<u8>> {
+        let sender = self.msg_sender();
+        if sender != user && sender != self.owner.get() {
+            return Err(b"NotUserOrOwner".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Reject callers other than the owner and the relayer
+    fn only_owner_or_relayer(&self) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        let sender = self.msg_sender();
+        let relayer = self.relayer.get();
+        if sender != self.owner.get() && (relayer == Address::ZERO || sender != relayer) {
+            return Err(b"NotRelayer".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Append a privileged write by the sender to the admin log
+    fn log_admin(&mut self, action: u8, target: B32) {
+        let actor = self.msg_sender();
+        let timestamp = self.vm().block_timestamp();
+        let mut entry = self.admin_log.grow();
+        entry.action.set(U8::from(action));
+        entry.actor.set(actor);
+        entry.timestamp.set(U64::from(timestamp));
+        entry.target.set(target);
+    }
+    
+    /// Run the relayer write `f` once per nonzero `key` of the sender
+    /// 
+    /// The key is recorded only when `f` succeeds, so a failed write can
+    /// be retried with the same key.
+    fn idempotent(&mut self, key: B32, f: impl FnOnce(&mut Self) -> Result<(), Vec<u8>>) -> Result<(), Vec<u8>> {
+        if key == B32::ZERO {
+            return f(self);
+        }
+        let relayer = self.msg_sender();
+        if self.used_idempotency_keys.getter(relayer).get(key) {
+            log(self.vm(), DuplicateSuppressed { relayer, key });
+            return Ok(());
+        }
+        f(self)?;
+        self.used_idempotency_keys.setter(relayer).insert(key, true);
+        Ok(())
+    }
+    
+    /// Signer of `digest` by the ecrecover precompile, `None` for a
+    /// malformed or unrecoverable 65-byte `r || s || v` signature
+    fn recover_signer(&self, digest: B32, signature: &[u8]) -> Option<Address> {
+        let signature: &[u8; 65] = signature.try_into().ok()?;
+        let v = match signature[64] {
+            v @ (27 | 28) => v,
+            v @ (0 | 1) => v + 27,
+            _ => return None,
+        };
+        let mut input = [0u8; 128];
+        input[..32].copy_from_slice(digest.as_slice());
+        input[63] = v;
+        input[64..].copy_from_slice(&signature[..64]);
+        let output = self.vm().static_call(&Call::new(), ECRECOVER, &input).ok()?;
+        let word: [u8; 32] = output.as_slice().try_into().ok()?;
+        let signer = Address::from_word(B32::from(word));
+        (signer != Address::ZERO).then_some(signer)
+    }
+    
+    /// Statuses `import_users` gives its entries, without writing
+    fn check_user_import(&self, users: &[Address], commitments: &[B32], registered_at: &[U256]) -> Result<Vec<u8>, Vec<u8>> {
+        if commitments.len() != users.len() || registered_at.len() != users.len() {
+            return Err(b"LengthMismatch".to_vec());
+        }
+        if users.len() > import::MAX_IMPORT_ENTRIES {
+            return Err(b"TooManyEntries".to_vec());
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let mut total_users = self.total_users.get();
+        let statuses = users
+            .iter()
+            .enumerate()
+            .map(|(i, user)| {
+                if *user == Address::ZERO || commitments[i] == B32::ZERO {
+                    import::INVALID
+                } else if registered_at[i].is_zero() || registered_at[i] > now {
+                    import::OUT_OF_RANGE
+                } else if self.user_has_data.get(*user) {
+                    import::ALREADY_EXISTS
+                } else if users[..i].contains(user) {
+                    import::DUPLICATE
+                } else if let Some(next) = total_users.checked_add(U256::from(1)) {
+                    total_users = next;
+                    import::OK
+                } else {
+                    import::COUNTER_OVERFLOW
+                }
+            })
+            .collect();
+        Ok(statuses)
+    }
+    
+    /// Signer of `signature` over `digest`, when it is an attestor of
+    /// `role` that hasn't expired
+    /// 
+    /// Fails with `AttestorExpired` for an attestor past its expiry, and
+    /// `InvalidAttestation` for any other signer or a malformed signature.
+    fn check_attestation(&self, role: u8, digest: B32, signature: &[u8]) -> Result<Address, Vec<u8>> {
+        let signer = self.recover_signer(digest, signature).ok_or_else(|| b"InvalidAttestation".to_vec())?;
+        let entries = self.attestor_entries.getter(U8::from(role));
+        let entry = entries.getter(signer);
+        if entry.position.get().is_zero() {
+            return Err(b"InvalidAttestation".to_vec());
+        }
+        if entry.expires_at.get() <= U256::from(self.vm().block_timestamp()) {
+            return Err(b"AttestorExpired".to_vec());
+        }
+        Ok(signer)
+    }
+    
+    /// Add `key` to `role`'s attestors, or update its expiry
+    fn insert_attestor(&mut self, role: u8, key: Address, expires_at: U256) {
+        let role = U8::from(role);
+        if self.attestor_entries.getter(role).getter(key).position.get().is_zero() {
+            let mut keys = self.attestor_keys.setter(role);
+            keys.push(key);
+            let position = U64::from(keys.len());
+            self.attestor_entries.setter(role).setter(key).position.set(position);
+        }
+        self.attestor_entries.setter(role).setter(key).expires_at.set(expires_at);
+    }
+    
+    /// Take `key` out of `role`'s attestors, moving the last key into its
+    /// place; false when it isn't one
+    fn remove_attestor(&mut self, role: u8, key: Address) -> bool {
+        let role = U8::from(role);
+        let position = self.attestor_entries.getter(role).getter(key).position.get();
+        if position.is_zero() {
+            return false;
+        }
+        let mut keys = self.attestor_keys.setter(role);
+        let last = keys.pop().unwrap_or_default();
+        if last != key {
+            if let Some(mut slot) = keys.setter(position.to::<usize>() - 1) {
+                slot.set(last);
+            }
+            self.attestor_entries.setter(role).setter(last).position.set(position);
+        }
+        let mut entries = self.attestor_entries.setter(role);
+        let mut entry = entries.setter(key);
+        entry.position.set(U64::ZERO);
+        entry.expires_at.set(U256::ZERO);
+        entry.fid_setter_added.set(false);
+        true
+    }
+    
+    /// Look up a recent block's hash through ArbSys
+    /// 
+    /// Blocks older than `ANCHOR_WINDOW`, the current block and future
+    /// blocks are `StaleAnchor`.
+    fn resolve_anchor(&self, anchor_block: U256) -> Result<BlockAnchor, Vec<u8>> {
+        let output = self
+            .vm()
+            .static_call(&Call::new(), ARB_SYS, &IArbSys::arbBlockNumberCall {}.abi_encode())
+            .map_err(|_| b"ArbSysUnavailable".to_vec())?;
+        let current_block = IArbSys::arbBlockNumberCall::abi_decode_returns(&output, true)
+            .map_err(|_| b"ArbSysUnavailable".to_vec())?
+            .number;
+        
+        let stale = || StaleAnchor { anchorBlock: anchor_block, currentBlock: current_block }.abi_encode();
+        if anchor_block >= current_block || current_block - anchor_block > U256::from(ANCHOR_WINDOW) {
+            return Err(stale());
+        }
+        let number = u64::try_from(anchor_block).map_err(|_| stale())?;
+        
+        let call = IArbSys::arbBlockHashCall { arbBlockNum: anchor_block };
+        let hash = self
+            .vm()
+            .static_call(&Call::new(), ARB_SYS, &call.abi_encode())
+            .ok()
+            .and_then(|output| IArbSys::arbBlockHashCall::abi_decode_returns(&output, true).ok())
+            .map(|ret| ret.hash)
+            .filter(|hash| *hash != B32::ZERO)
+            .ok_or_else(stale)?;
+        
+        Ok(BlockAnchor { number, hash: hash.0 })
+    }
+    
+    /// Reject dates without published positions when an ephemeris oracle
+    /// is configured
+    fn check_ephemeris(&self, date: U256) -> Result<(), Vec<u8>> {
+        let oracle = self.ephemeris_oracle.get();
+        if oracle == Address::ZERO {
+            return Ok(());
+        }
+        
+        let call = IEphemerisOracle::hasPositionsCall { date };
+        let output = self
+            .vm()
+            .static_call(&Call::new(), oracle, &call.abi_encode())
+            .map_err(|_| b"EphemerisUnavailable".to_vec())?;
+        let published = IEphemerisOracle::hasPositionsCall::abi_decode_returns(&output, true)
+            .map_err(|_| b"EphemerisUnavailable".to_vec())?
+            ._0;
+        if !published {
+            return Err(b"NoEphemerisForDate".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Reject `chart_key` unless it is one of `user`'s charts, when a
+    /// chart registry is configured
+    fn check_chart_owner(&self, user: Address, chart_key: B32) -> Result<(), Vec<u8>> {
+        let registry = self.chart_registry.get();
+        if registry == Address::ZERO {
+            return Ok(());
+        }
+        
+        let call = IChartRegistry::getUserChartsCall { user };
+        let output = self
+            .vm()
+            .static_call(&Call::new(), registry, &call.abi_encode())
+            .map_err(|_| b"ChartRegistryUnavailable".to_vec())?;
+        let chart_ids = IChartRegistry::getUserChartsCall::abi_decode_returns(&output, true)
+            .map_err(|_| b"ChartRegistryUnavailable".to_vec())?
+            .chartIds;
+        if !chart_ids.iter().any(|chart_id| chart_registry::chart_key_from_string(chart_id) == chart_key) {
+            return Err(b"NotChartOwner".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Reject `user` unless they are a partner of the formed synastry pair
+    /// `pair_key`
+    fn check_pair_member(&self, pair_key: B32, user: Address) -> Result<(), Vec<u8>> {
+        let pair = self.synastry_pairs.getter(pair_key);
+        let (owner_a, owner_b) = (pair.owner_a.get(), pair.owner_b.get());
+        if owner_a == Address::ZERO || owner_b == Address::ZERO {
+            return Err(b"PairNotFormed".to_vec());
+        }
+        if user != owner_a && user != owner_b {
+            return Err(b"NotPairMember".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Natal positions of `user`'s latest chart that has them
+    /// 
+    /// Only the latest `MAX_PAGE_SIZE` charts are looked at, one registry
+    /// read each, so a user with many charts can't run reads out of gas.
+    fn natal_positions(&self, user: Address) -> Result<[positions::PlanetPosition; positions::CHART_BODIES], Vec<u8>> {
+        let registry = self.chart_registry.get();
+        if registry == Address::ZERO {
+            return Err(b"NoNatalPositions".to_vec());
+        }
+        
+        let call = IChartRegistry::getUserChartsCall { user };
+        let output = self
+            .vm()
+            .static_call(&Call::new(), registry, &call.abi_encode())
+            .map_err(|_| b"ChartRegistryUnavailable".to_vec())?;
+        let chart_ids = IChartRegistry::getUserChartsCall::abi_decode_returns(&output, true)
+            .map_err(|_| b"ChartRegistryUnavailable".to_vec())?
+            .chartIds;
+        
+        for chart_id in chart_ids.into_iter().rev().take(MAX_PAGE_SIZE as usize) {
+            let call = IChartRegistry::getChartPositionsCall { chartId: chart_id };
+            let output = self
+                .vm()
+                .static_call(&Call::new(), registry, &call.abi_encode())
+                .map_err(|_| b"ChartRegistryUnavailable".to_vec())?;
+            let chart = IChartRegistry::getChartPositionsCall::abi_decode_returns(&output, true)
+                .map_err(|_| b"ChartRegistryUnavailable".to_vec())?;
+            if chart.hasPositions {
+                return positions::unpack_chart(&chart.packed).map_err(|_| b"InvalidPositions".to_vec());
+            }
+        }
+        Err(b"NoNatalPositions".to_vec())
+    }
+    
+    /// Packed positions published by the ephemeris oracle for `date`
+    fn ephemeris_packed(&self, date: U256) -> Result<[B32; 2], Vec<u8>> {
+        let oracle = self.ephemeris_oracle.get();
+        if oracle == Address::ZERO {
+            return Err(b"NoEphemerisForDate".to_vec());
+        }
+        self.check_ephemeris(date)?;
+        
+        let call = IEphemerisOracle::getPositionsCall { date };
+        let output = self
+            .vm()
+            .static_call(&Call::new(), oracle, &call.abi_encode())
+            .map_err(|_| b"EphemerisUnavailable".to_vec())?;
+        Ok(IEphemerisOracle::getPositionsCall::abi_decode_returns(&output, true)
+            .map_err(|_| b"EphemerisUnavailable".to_vec())?
+            ._0)
+    }
+    
+    /// Positions published by the ephemeris oracle for `date`
+    fn ephemeris_positions(&self, date: U256) -> Result<[positions::PlanetPosition; positions::CHART_BODIES], Vec<u8>> {
+        let packed = self.ephemeris_packed(date)?;
+        positions::unpack_chart(&packed).map_err(|_| b"InvalidPositions".to_vec())
+    }
+    
+    /// Rating of `user`'s prediction for `date` the bucket aggregates
+    /// count, if it was changed while opted out (else the stored one)
+    fn aggregated_rating(&self, user: Address, date: U256) -> Option<U8> {
+        let aggregated = self.aggregated_ratings.getter(user);
+        let aggregated = aggregated.getter(date);
+        aggregated.set.get().then(|| aggregated.rating.get())
+    }
+    
+    /// Commitment of a registered user
+    fn registered_commitment(&self, user: Address) -> Result<B32, Vec<u8>> {
+        if !self.user_has_data.get(user) {
+            return Err(b"UserNotRegistered".to_vec());
+        }
+        Ok(self.user_commitments.get(user))
+    }
+    
+    /// Reject registration paths that bypass an active allowlist
+    fn only_open_registration(&self) -> Result<(), Vec<u8>> {
+        if self.allowlist_root.get() != B32::ZERO {
+            return Err(b"AllowlistActive".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Store a prediction for `user`, shared by direct and anchored storage,
+    /// attributed to `chart_key` unless it is zero
+    fn store_prediction_for(
+        &mut self,
+        user: Address,
+        date: U256,
+        prediction_hash: B32,
+        chart_key: B32,
+    ) -> Result<(), Vec<u8>> {
+        self.only_storing()?;
+        self.check_storable(user, date, prediction_hash)?;
+        let day = self.check_prediction_date(date)?;
+        self.count_write();
+        
+        // A stored prediction replaces one proposed for the date
+        if self.pending_predictions.getter(user).getter(date).prediction_hash.get() != B32::ZERO {
+            self.clear_pending(user, date);
+        }
+        
+        // Store prediction
+        let mut user_predictions = self.predictions.setter(user);
+        user_predictions.setter(date).set(prediction_hash);
+        
+        let mut user_exists = self.prediction_exists.setter(user);
+        user_exists.setter(date).set(true);
+        
+        self.prediction_dates.setter(user).push(date);
+        self.set_prediction_day(user, date, true);
+        
+        // Update user stats
+        let user_total = math::checked_inc(self.total_predictions.get(user), math::FIELD_TOTAL_PREDICTIONS)?;
+        self.total_predictions.setter(user).set(user_total);
+        
+        // Update global stats
+        if !self.aggregate_opt_outs.get(user) {
+            let global_total = math::checked_inc(self.global_predictions.get(), math::FIELD_GLOBAL_PREDICTIONS)?;
+            self.global_predictions.set(global_total);
+        }
+        
+        let previous = self.daily_accumulators.get(date);
+        let accumulator = accumulator::accumulate(&previous.0, user, &prediction_hash.0);
+        self.daily_accumulators.setter(date).set(accumulator.into());
+        let count = math::checked_inc(self.daily_prediction_counts.get(date), math::FIELD_DAILY_PREDICTIONS)?;
+        self.daily_prediction_counts.setter(date).set(count);
+        log(self.vm(), PredictionStored { user, date, day, predictionHash: prediction_hash });
+        if chart_key != B32::ZERO {
+            self.prediction_charts.setter(user).setter(date).set(chart_key);
+            log(self.vm(), PredictionAttributed { user, date, day, chartKey: chart_key });
+        }
+        
+        self.credit_points(user, Activity::Prediction);
+        self.extend_streak(user, date, day);
+        self.notify_hook(user, date);
+        
+        Ok(())
+    }
+    
+    /// Rating commitment of `rater` for `target`'s `date`
+    /// Returns: (commitment, reveal_at, expires_at)
+    fn rating_commitment(&self, rater: Address, target: Address, date: U256) -> (B32, u64, u64) {
+        let commitments = self.rating_commitments.getter(rater);
+        let commitments = commitments.getter(target);
+        let entry = commitments.getter(date);
+        (entry.commitment.get(), entry.reveal_at.get().to::<u64>(), entry.expires_at.get().to::<u64>())
+    }
+    
+    /// Drop the rating commitment of `rater` for `target`'s `date`
+    fn clear_rating_commitment(&mut self, rater: Address, target: Address, date: U256) {
+        let mut commitments = self.rating_commitments.setter(rater);
+        let mut commitments = commitments.setter(target);
+        let mut entry = commitments.setter(date);
+        entry.commitment.set(B32::ZERO);
+        entry.reveal_at.set(U64::ZERO);
+        entry.expires_at.set(U64::ZERO);
+    }
+    
+    /// Count a prediction write in the current hour, pausing writes when
+    /// the count passes the threshold
+    fn count_write(&mut self) {
+        let threshold = self.auto_pause_threshold.get();
+        if threshold.is_zero() {
+            return;
+        }
+        let hour_index = U256::from(self.vm().block_timestamp() / SECONDS_PER_HOUR);
+        // Saturates: a count at the maximum is still past the threshold
+        let count = self.writes_in_hour.get(hour_index).saturating_add(U256::from(1));
+        self.writes_in_hour.setter(hour_index).set(count);
+        if count > U256::from(threshold) && !self.paused.get() {
+            self.paused.set(true);
+            log(self.vm(), AutoPaused { hourIndex: hour_index, count });
+        }
+    }
+    
+    /// Days, rounded up, `date` is before `block.timestamp -
+    /// max_backfill_days` (zero when it isn't)
+    fn days_late(&self, date: U256) -> U256 {
+        let window = self.get_max_backfill_days().saturating_mul(calendar::SECONDS_PER_DAY);
+        let oldest = self.vm().block_timestamp().saturating_sub(window);
+        // No later than `oldest`, so u64 math does; U256 division would
+        // link ruint's into every build storing predictions
+        let late = U256::from(oldest).saturating_sub(date).to::<u64>();
+        U256::from(late.div_ceil(calendar::SECONDS_PER_DAY))
+    }
+    
+    /// Whether a prediction for `date` can still be rated
+    fn rating_window_open(&self, date: U256) -> bool {
+        let days = self.rating_window_days.get();
+        if days.is_zero() {
+            return true;
+        }
+        let window = U256::from(days).saturating_mul(U256::from(calendar::SECONDS_PER_DAY));
+        U256::from(self.vm().block_timestamp()) <= date.saturating_add(window)
+    }
+    
+    /// Reject `date` when it's older than `max_backfill_days` allows
+    fn check_backfill(&self, date: U256) -> Result<(), Vec<u8>> {
+        let days_late = self.days_late(date);
+        if days_late != 0 {
+            return Err(DateTooOld { daysLate: days_late }.abi_encode());
+        }
+        Ok(())
+    }
+    
+    /// Check `user` can store `prediction_hash` for `date`
+    fn check_storable(&self, user: Address, date: U256, prediction_hash: B32) -> Result<(), Vec<u8>> {
+        // Check if user is registered
+        if !self.user_has_data.get(user) {
+            return Err(b"UserNotRegistered".to_vec());
+        }
+        
+        if prediction_hash == B32::ZERO {
+            return Err(b"InvalidPredictionHash".to_vec());
+        }
+        
+        // Check if prediction already exists for this date (or did)
+        if self.prediction_exists.getter(user).get(date) || self.pruned_predictions.getter(user).get(date) {
+            return Err(b"PredictionAlreadyExists".to_vec());
+        }
+        Ok(())
+    }
+    
+    /// Prediction proposed for `user`'s `date`
+    /// Returns: (prediction_hash, finalizable_at)
+    fn pending_prediction(&self, user: Address, date: U256) -> Result<(B32, u64), Vec<u8>> {
+        let (prediction_hash, finalizable_at) = self.get_pending_prediction(user, date);
+        if prediction_hash == B32::ZERO {
+            return Err(b"NoPendingPrediction".to_vec());
+        }
+        Ok((prediction_hash, finalizable_at.to::<u64>()))
+    }
+    
+    /// Store the prediction proposed for `user`'s `date`
+    fn promote_pending(&mut self, user: Address, date: U256) -> Result<(), Vec<u8>> {
+        let (prediction_hash, _) = self.pending_prediction(user, date)?;
+        self.store_prediction_for(user, date, prediction_hash, B32::ZERO)
+    }
+    
+    /// Whether the proposal for `user`'s `date` with `deadline` is still
+    /// pending
+    fn proposal_pending(&self, user: Address, date: U256, deadline: u64) -> bool {
+        let (prediction_hash, finalizable_at) = self.get_pending_prediction(user, date);
+        prediction_hash != B32::ZERO && finalizable_at == U256::from(deadline)
+    }
+    
+    /// Append a proposal to `relayer`'s queue, first dropping up to
+    /// `QUEUE_CLEANUP_STEPS` settled entries from its front
+    fn enqueue_proposal(&mut self, relayer: Address, user: Address, date: U256, deadline: u64) {
+        let queue = self.pending_queues.getter(relayer);
+        let (mut head, len) = (queue.head.get().to::<usize>(), queue.entries.len());
+        let end = len.min(head + QUEUE_CLEANUP_STEPS);
+        while head < end {
+            let queue = self.pending_queues.getter(relayer);
+            let Some(entry) = queue.entries.getter(head) else {
+                break;
+            };
+            if self.proposal_pending(entry.user.get(), entry.date.get(), entry.deadline.get().to::<u64>()) {
+                break;
+            }
+            let mut queue = self.pending_queues.setter(relayer);
+            if let Some(mut entry) = queue.entries.setter(head) {
+                entry.user.set(Address::ZERO);
+                entry.deadline.set(U64::ZERO);
+                entry.date.set(U256::ZERO);
+            }
+            head += 1;
+        }
+        
+        let mut queue = self.pending_queues.setter(relayer);
+        if head == len {
+            queue.entries.truncate(0);
+            head = 0;
+        }
+        queue.head.set(U64::from(head));
+        let mut entry = queue.entries.grow();
+        entry.user.set(user);
+        entry.deadline.set(U64::from(deadline));
+        entry.date.set(date);
+    }
+    
+    /// Drop the prediction proposed for `user`'s `date`
+    fn clear_pending(&mut self, user: Address, date: U256) {
+        let mut pending = self.pending_predictions.setter(user);
+        let mut pending = pending.setter(date);
+        pending.prediction_hash.set(B32::ZERO);
+        pending.finalizable_at.set(U64::ZERO);
+    }
+    
+    /// Count stored predictions, ratings and rating sum over the user's
+    /// dates `[start, end)`, plus the pruned ones when `start` is 0, and
+    /// check the dates against the day bitmap
+    /// Returns: (predictions, ratings, rating_sum, bitmap_matches)
+    fn recount(&self, user: Address, start: usize, end: usize) -> (U256, U256, U256, bool) {
+        let (mut predictions, mut ratings, mut rating_sum) = if start == 0 {
+            (self.pruned_count.get(user), self.pruned_ratings.get(user), self.pruned_rating_sum.get(user))
+        } else {
+            (U256::ZERO, U256::ZERO, U256::ZERO)
+        };
+        
+        let mut bitmap_matches = true;
+        let dates = self.prediction_dates.getter(user);
+        for date in (start..end).filter_map(|i| dates.get(i)) {
+            let exists = self.prediction_exists.getter(user).get(date);
+            if let Some(slot) = day_bitmap::date_slot(date) {
+                bitmap_matches &= self.prediction_day(user, slot) == exists;
+            }
+            if !exists {
+                continue;
+            }
+            predictions += U256::from(1);
+            let rating = self.ratings.getter(user).get(date);
+            if rating != U8::ZERO {
+                ratings += U256::from(1);
+                rating_sum += U256::from(rating);
+            }
+        }
+        (predictions, ratings, rating_sum, bitmap_matches)
+    }
+    
+    /// Bit of a day in the user's day bitmap
+    fn prediction_day(&self, user: Address, slot: day_bitmap::DaySlot) -> bool {
+        let word = self.prediction_days.getter(user).getter(U32::from(slot.year)).get(slot.word);
+        word.unwrap_or_default().bit(slot.bit)
+    }
+    
+    /// Set or clear `date` in the user's day bitmap; dates other than
+    /// midnight aren't kept there
+    fn set_prediction_day(&mut self, user: Address, date: U256, exists: bool) {
+        let Some(slot) = day_bitmap::date_slot(date) else {
+            return;
+        };
+        let mut days = self.prediction_days.setter(user);
+        let mut year = days.setter(U32::from(slot.year));
+        if let Some(mut word) = year.setter(slot.word) {
+            let mut bits = word.get();
+            bits.set_bit(slot.bit, exists);
+            word.set(bits);
+        }
+    }
+    
+    /// Fold `rating` into the user's time-decayed average
+    fn fold_rating(&mut self, user: Address, rating: U8) {
+        let now = self.vm().block_timestamp();
+        let half_life = self.get_rating_half_life();
+        let mut average = self.rating_averages.setter(user);
+        let days = now.saturating_sub(average.last_update.get().to::<u64>()) / calendar::SECONDS_PER_DAY;
+        let (value, weight) = decay::fold(
+            average.value_x1000.get().to::<u64>(),
+            average.weight.get().to::<u64>(),
+            rating.to::<u64>() * 1000,
+            decay::decay_factor(days, half_life),
+        );
+        average.value_x1000.set(U64::from(value));
+        average.weight.set(U64::from(weight));
+        average.last_update.set(U64::from(now));
+    }
+    
+    /// Tell the prediction hook, if any, about a stored prediction
+    /// 
+    /// Best effort: a failing hook is logged and otherwise ignored. Called
+    /// last, once the prediction is stored.
+    fn notify_hook(&mut self, user: Address, date: U256) {
+        let hook = self.prediction_hook.get();
+        if hook == Address::ZERO {
+            return;
+        }
+        
+        let streak = U256::from(self.streaks.getter(user).length.get());
+        let call = IPredictionHook::onPredictionStoredCall { user, date, streak };
+        if let Err(error) = self.vm().call(&Call::new().gas(HOOK_GAS), hook, &call.abi_encode()) {
+            let reason = match error {
+                CallError::Revert(reason) => reason,
+                CallError::AbiDecodingFailed(_) => Vec::new(),
+            };
+            log(self.vm(), HookFailed { hook, user, date, reason: reason.into() });
+        }
+    }
+    
+    /// Count `date`, of day number `day`, towards the user's streak of
+    /// consecutive prediction days, crediting every `STREAK_MILESTONE`th day
+    /// 
+    /// Backfilled dates before the last day leave the streak as it is.
+    fn extend_streak(&mut self, user: Address, date: U256, day: u32) {
+        // Late claims and finalized proposals are stored, but only dates
+        // still open can extend a streak
+        if self.days_late(date) != 0 {
+            return;
+        }
+        let day = U256::from(day);
+        let mut streak = self.streaks.setter(user);
+        let last_day = U256::from(streak.last_day.get());
+        if day <= last_day {
+            return;
+        }
+        
+        // Saturates: a streak at the maximum length stays there
+        let length = if day == last_day + U256::from(1) {
+            streak.length.get().saturating_add(U64::from(1))
+        } else {
+            U64::from(1)
+        };
+        streak.last_day.set(U64::saturating_from(day));
+        streak.length.set(length);
+        
+        if (length % U64::from(STREAK_MILESTONE)).is_zero() {
+            self.credit_points(user, Activity::Streak);
+        }
+    }
+    
+    /// Move `user`'s rating of `date` from `existing` to `rating` (zero
+    /// for none) in the global totals and those of the date's month
+    /// 
+    /// While the user is opted out, the global totals keep the rating
+    /// they hold, as the other global stats do.
+    fn update_rating_totals(&mut self, user: Address, date: U256, existing: U8, rating: U8) -> Result<(), Overflow> {
+        let flags = self.rating_flags.getter(user).get(date).to::<u8>();
+        let mut global = flags >> GLOBAL_RATING_SHIFT;
+        if !self.aggregate_opt_outs.get(user) {
+            self.global_ratings.replace(U8::from(global), rating, math::FIELD_GLOBAL_RATINGS)?;
+            global = rating.to::<u8>();
+        }
+        
+        let mut monthly = 0;
+        if let Some(month) = rating_month(date) {
+            let counted = if flags & RATED_MONTHLY != 0 { existing } else { U8::ZERO };
+            let mut totals = self.monthly_ratings.setter(user);
+            totals.setter(U32::from(month)).replace(counted, rating, math::FIELD_MONTHLY_RATINGS)?;
+            if rating != U8::ZERO {
+                monthly = RATED_MONTHLY;
+            }
+        }
+        let flags = global << GLOBAL_RATING_SHIFT | monthly;
+        self.rating_flags.setter(user).setter(date).set(U8::from(flags));
+        Ok(())
+    }
+    
+    /// Credit the points of `activity` to `user`
+    fn credit_points(&mut self, user: Address, activity: Activity) {
+        let (registration, prediction, rating, streak) = self.get_point_weights();
+        let amount = match activity {
+            Activity::Registration => registration,
+            Activity::Prediction => prediction,
+            Activity::Rating => rating,
+            Activity::Streak => streak,
+        };
+        if amount == 0 {
+            return;
+        }
+        
+        let amount = U256::from(amount);
+        // Saturates: a balance at the maximum stays there
+        let balance = self.points.get(user);
+        self.points.setter(user).set(balance.saturating_add(amount));
+        log(self.vm(), PointsCredited { user, activity: activity as u8, amount });
+    }
+    
+    /// Verify a proof for the sender and store its commitment
+    fn register_verified(&mut self, proof: &ProofBytes, positions: &[u64]) -> Result<(), Vec<u8>> {
+        let user = self.msg_sender();
+        
+        self.check_proof_inputs(proof.scheme_version, positions)?;
+        self.only_open_registration()?;
+        if !self.verify_proof_now(proof, positions)? {
+            return Err(b"InvalidProof".to_vec());
+        }
+        
+        self.register_commitment(user, proof.commitment.into())?;
+        self.user_scheme_versions.setter(user).set(U8::from(proof.scheme_version));
+        
+        Ok(())
+    }
+    
+    /// Reject position counts out of bounds and versions outside the
+    /// accepted range
+    fn check_proof_inputs(&self, scheme_version: u8, positions: &[u64]) -> Result<(), Vec<u8>> {
+        poseidon::check_positions(positions).map_err(input_error)?;
+        
+        let min_version = self.min_accepted_version.get().to::<u8>();
+        let max_version = self.max_accepted_version.get().to::<u8>();
+        if scheme_version < min_version || scheme_version > max_version {
+            return Err(UnsupportedProofVersion { version: scheme_version }.abi_encode());
+        }
+        Ok(())
+    }
+    
+    /// Verify a proof against the current epoch
+    fn verify_proof_now(&self, proof: &ProofBytes, positions: &[u64]) -> Result<bool, Vec<u8>> {
+        let current_epoch = poseidon::compute_epoch(self.vm().block_timestamp());
+        proof.verify_at(positions, current_epoch).map_err(|err| match err {
+            ProofError::UnsupportedProofVersion(version) => UnsupportedProofVersion { version }.abi_encode(),
+            ProofError::ProofExpired(epoch) => ProofExpired { epoch, currentEpoch: current_epoch }.abi_encode(),
+        })
+    }
+    
+    /// Take the registration fee from a user registering themselves, and
+    /// store their validated commitment
+    fn register_commitment(&mut self, user: Address, commitment: B32) -> Result<(), Vec<u8>> {
+        self.pay_registration_fee(user)?;
+        self.store_commitment(user, commitment)
+    }
+    
+    /// Take the registration fee for `user`: from the sponsor pool when
+    /// no value is sent and `get_sponsored_slots_today` is nonzero, else
+    /// exactly the fee from `msg.value`
+    fn pay_registration_fee(&mut self, user: Address) -> Result<(), Vec<u8>> {
+        let fee = self.registration_fee.get();
+        let sent = self.vm().msg_value();
+        if sent.is_zero() && !fee.is_zero() && !self.get_sponsored_slots_today().is_zero() {
+            let day = U32::from(day_number(U256::from(self.vm().block_timestamp()))?);
+            let sponsored = math::checked_inc(self.sponsored_per_day.get(day), math::FIELD_SPONSORED_REGISTRATIONS)?;
+            self.sponsored_per_day.setter(day).set(sponsored);
+            let pool = self.sponsor_pool.get() - fee;
+            self.sponsor_pool.set(pool);
+            // The fee moves from the pool to the owner's withdrawable fees
+            let collected = math::checked_add(self.collected_fees.get(), fee, math::FIELD_COLLECTED_FEES)?;
+            self.collected_fees.set(collected);
+            log(self.vm(), RegistrationSponsored { user, amount: fee, remainingPool: pool });
+            return Ok(());
+        }
+        
+        if sent != fee {
+            return Err(IncorrectFee { expected: fee, sent }.abi_encode());
+        }
+        let collected = math::checked_add(self.collected_fees.get(), sent, math::FIELD_COLLECTED_FEES)?;
+        self.collected_fees.set(collected);
+        Ok(())
+    }
+    
+    /// Store a validated commitment for a new user
+    fn store_commitment(&mut self, user: Address, commitment: B32) -> Result<(), Vec<u8>> {
+        self.only_live()?;
+        
+        // Check if user already registered
+        if self.user_has_data.get(user) {
+            return Err(b"UserAlreadyRegistered".to_vec());
+        }
+        
+        // Store commitment
+        let now = U256::from(self.vm().block_timestamp());
+        self.user_commitments.setter(user).set(commitment);
+        self.user_has_data.setter(user).set(true);
+        self.registered_at.setter(user).set(now);
+        
+        // Increment total users
+        if !self.aggregate_opt_outs.get(user) {
+            let current_total = math::checked_inc(self.total_users.get(), math::FIELD_TOTAL_USERS)?;
+            self.total_users.set(current_total);
+        }
+        
+        self.credit_points(user, Activity::Registration);
+        
+        Ok(())
+    }
+}
+
+/// Month index (see `calendar::month_index`) of a prediction date, `None`
+/// past `day_bitmap::DATE_LIMIT`
+fn rating_month(date: U256) -> Option<u32> {
+    let date = u64::try_from(date).ok().filter(|date| *date < day_bitmap::DATE_LIMIT)?;
+    calendar::month_index(date).ok()
+}
+
+impl RatingTotals {
+    /// Replace a rating these totals hold, `counted`, with `rating`
+    /// (either zero for none), failing with `Overflow(field)` past their
+    /// 128 bits
+    fn replace(&mut self, counted: U8, rating: U8, field: u8) -> Result<(), Overflow> {
+        let count = self.total_ratings.get();
+        match (counted == U8::ZERO, rating == U8::ZERO) {
+            (true, false) => self.total_ratings.set(math::checked_inc(count, field)?),
+            (false, true) => self.total_ratings.set(count - U128::from(1)),
+            _ => {}
+        }
+        let sum = self.rating_sum.get() - U128::from(counted);
+        self.rating_sum.set(math::checked_add(sum, U128::from(rating), field)?);
+        Ok(())
+    }
+}
+
+/// Day number of `date` (see `calendar::to_day_number`), InvalidDate
+/// past the last one
+fn day_number(date: U256) -> Result<u32, Vec<u8>> {
+    calendar::to_day_number(date).map_err(|_| b"InvalidDate".to_vec())
+}
+
+/// Midnight UTC of the day containing `date`, InvalidDate past the last
+/// day number
+fn day_key(date: U256) -> Result<U256, Vec<u8>> {
+    day_number(date).map(calendar::to_timestamp)
+}
+
+/// Admin log target of an attestor change: `role << 160 | key`
+fn attestor_target(role: u8, key: Address) -> B32 {
+    B32::from(U256::from(role) << 160 | U256::from_be_slice(key.as_slice()))
+}
+
+/// Admin log target of a facet change: `selector << 160 | facet`
+fn facet_target(selector: FixedBytes<4>, facet: Address) -> B32 {
+    B32::from(U256::from(u32::from_be_bytes(selector.0)) << 160 | U256::from_be_slice(facet.as_slice()))
+}
+
+/// Domain tag of FID attestations, see `fid_attestation_digest`
+pub const FID_ATTESTATION_TAG: &[u8] = b"ZKAstro.fid.v1";
+
+/// Digest the FID attestor signs to link `user` to `fid` on `contract`
+/// of `chain_id`: the EIP-191 personal-sign digest of
+/// `keccak256(abi.encodePacked("ZKAstro.fid.v1", uint256 chain_id, address contract, address user, uint256 fid))`,
+/// so a signature holds for one deployment only
+pub fn fid_attestation_digest(chain_id: u64, contract: Address, user: Address, fid: U256) -> B32 {
+    let mut preimage = FID_ATTESTATION_TAG.to_vec();
+    preimage.extend_from_slice(&U256::from(chain_id).to_be_bytes::<32>());
+    preimage.extend_from_slice(contract.as_slice());
+    preimage.extend_from_slice(user.as_slice());
+    preimage.extend_from_slice(&fid.to_be_bytes::<32>());
+    let mut message = b"\x19Ethereum Signed Message:\n32".to_vec();
+    message.extend_from_slice(keccak(preimage).as_slice());
+    keccak(message)
+}
+
+/// Leaf of a daily anchoring tree:
+/// `keccak256(abi.encodePacked(address user, uint256 date, bytes32 prediction_hash))`
+pub fn prediction_leaf(user: Address, date: U256, prediction_hash: B32) -> B32 {
+    let mut preimage = [0u8; 84];
+    preimage[..20].copy_from_slice(user.as_slice());
+    preimage[20..52].copy_from_slice(&date.to_be_bytes::<32>());
+    preimage[52..].copy_from_slice(prediction_hash.as_slice());
+    keccak(preimage)
+}
+
+/// Key of the synastry pair of two charts, in either order:
+/// `keccak256(abi.encodePacked(bytes32 lower, bytes32 higher))` of their
+/// chart keys
+pub fn pair_key(chart_key_a: B32, chart_key_b: B32) -> B32 {
+    let (lower, higher) = if chart_key_a < chart_key_b { (chart_key_a, chart_key_b) } else { (chart_key_b, chart_key_a) };
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(lower.as_slice());
+    preimage[32..].copy_from_slice(higher.as_slice());
+    keccak(preimage)
+}
+
+/// Assemble a proof from calldata fields
+fn zk_proof(
+    commitment: B32,
+    proof: B32,
+    nonce: Bytes,
+    scheme_version: u8,
+    encoding: u8,
+    epoch: u64,
+) -> Result<ProofBytes, Vec<u8>> {
+    ProofBytes::new(commitment.into(), proof.0, nonce.0, encoding, scheme_version, epoch).map_err(input_error)
+}
+
+fn input_error(err: InputError) -> Vec<u8> {
+    match err {
+        InputError::InvalidEncoding(_) => b"InvalidEncoding".to_vec(),
+        InputError::InvalidUtf8 => b"InvalidUtf8".to_vec(),
+        InputError::FieldTooLong(field, _) => InputTooLarge { field }.abi_encode(),
+        InputError::PositionCount(_) => InputTooLarge { field: poseidon::INPUT_POSITIONS }.abi_encode(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+    use alloy_sol_types::SolEvent;
+    use facets::FACET_DELAY;
+    use poseidon::{SCHEME_V2, SCHEME_V3};
+    use zkastro_core::sdk::{self, IFarcasterPredictions};
+    
+    #[test]
+    fn test_register_user() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        
+        let commitment = B32::repeat_byte(0xab);
+        assert!(contract.register_user(commitment).is_ok());
+        assert!(contract.is_user_registered(user));
+        assert_eq!(contract.get_user_commitment(user), commitment);
+        assert_eq!(contract.get_global_stats().0, U256::from(1));
+    }
+    
+    #[test]
+    fn test_register_user_twice_rejected() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        
+        assert_eq!(contract.register_user(B32::ZERO), Err(b"InvalidCommitment".to_vec()));
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        assert_eq!(
+            contract.register_user(B32::repeat_byte(0xcd)),
+            Err(b"UserAlreadyRegistered".to_vec())
+        );
+        
+        // The first commitment stands and the user is counted once
+        assert_eq!(contract.get_user_commitment(user), B32::repeat_byte(0xab));
+        assert_eq!(contract.get_global_stats().0, U256::from(1));
+    }
+    
+    #[test]
+    fn test_store_prediction_once_per_date() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        let date = U256::from(20_000 * DAY);
+        assert_eq!(contract.store_prediction(date, B32::ZERO), Err(b"InvalidPredictionHash".to_vec()));
+        assert_eq!(contract.store_prediction(date, B32::repeat_byte(0x01)), Ok(()));
+        assert_eq!(
+            contract.store_prediction(date, B32::repeat_byte(0x02)),
+            Err(b"PredictionAlreadyExists".to_vec())
+        );
+        
+        assert!(contract.has_prediction(user, date));
+        assert_eq!(contract.get_prediction(user, date), B32::repeat_byte(0x01));
+        assert!(!contract.has_prediction(user, date + U256::from(DAY)));
+        assert_eq!(contract.get_user_stats(user).0, U256::from(1));
+    }
+    
+    #[test]
+    fn test_verify_prediction_payload() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        let date = 20_000 * DAY;
+        let payload = payload::PredictionPayload {
+            version: 1,
+            date,
+            category: 2,
+            text: String::from("Mercury favours clear words today."),
+            lucky_number: 42,
+            lucky_color: 7,
+            model_id: 3,
+        };
+        let verify = |contract: &FarcasterPredictions, payload: &payload::PredictionPayload| {
+            let payload = payload.clone();
+            contract.verify_prediction_payload(
+                user,
+                payload.version,
+                payload.date,
+                payload.category,
+                payload.text,
+                payload.lucky_number,
+                payload.lucky_color,
+                payload.model_id,
+            )
+        };
+        assert!(!verify(&contract, &payload));
+        
+        contract.store_prediction(U256::from(date), B32::from(payload.hash())).unwrap();
+        assert!(verify(&contract, &payload));
+        assert!(!verify(&contract, &payload::PredictionPayload { text: String::from("Mercury favours clear words today"), ..payload.clone() }));
+        assert!(!verify(&contract, &payload::PredictionPayload { model_id: 4, ..payload.clone() }));
+        // The date is both hashed and the key looked up
+        assert!(!verify(&contract, &payload::PredictionPayload { date: date + DAY, ..payload }));
+    }
+    
+    #[test]
+    fn test_store_prediction_for_chart() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.initialize(user).unwrap();
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        let date = |n: u64| U256::from((20_000 + n) * DAY);
+        let natal = keccak(b"natal");
+        let relocated = keccak(b"relocated");
+        
+        // Without a registry the attribution is taken as given
+        contract.store_prediction_for_chart(date(0), B32::repeat_byte(1), natal).unwrap();
+        assert_eq!(contract.get_prediction_meta(user, date(0)), (B32::repeat_byte(1), 0, natal));
+        
+        let registry = Address::repeat_byte(0xc0);
+        contract.set_chart_registry(registry).unwrap();
+        vm.mock_static_call(
+            registry,
+            IChartRegistry::getUserChartsCall { user }.abi_encode(),
+            Ok(IChartRegistry::getUserChartsCall::abi_encode_returns(&(vec![String::from("natal"), String::from("relocated")],))),
+        );
+        contract.store_prediction_for_chart(date(1), B32::repeat_byte(2), relocated).unwrap();
+        assert_eq!(contract.get_prediction_meta(user, date(1)), (B32::repeat_byte(2), 0, relocated));
+        let logs = vm.get_emitted_logs();
+        let (topics, _) = logs.iter().rfind(|(topics, _)| topics[0] == PredictionAttributed::SIGNATURE_HASH).unwrap();
+        assert_eq!(topics[..], [PredictionAttributed::SIGNATURE_HASH, B32::left_padding_from(user.as_slice()), B32::from(date(1)), relocated]);
+        
+        // Someone else's chart, or none: nothing is stored
+        assert_eq!(
+            contract.store_prediction_for_chart(date(2), B32::repeat_byte(3), keccak(b"other")),
+            Err(b"NotChartOwner".to_vec())
+        );
+        assert!(!contract.has_prediction(user, date(2)));
+        contract.store_prediction_for_chart(date(2), B32::repeat_byte(3), B32::ZERO).unwrap();
+        assert_eq!(contract.get_prediction_meta(user, date(2)), (B32::repeat_byte(3), 0, B32::ZERO));
+        
+        // The attribution is fixed with the prediction
+        assert_eq!(
+            contract.store_prediction_for_chart(date(1), B32::repeat_byte(4), natal),
+            Err(b"PredictionAlreadyExists".to_vec())
+        );
+        contract.rate_prediction(date(1), U8::from(4)).unwrap();
+        assert_eq!(contract.get_prediction_meta(user, date(1)), (B32::repeat_byte(2), 4, relocated));
+        
+        // A registry that doesn't answer can't vouch for the chart
+        vm.mock_static_call(registry, IChartRegistry::getUserChartsCall { user }.abi_encode(), Err(b"Sunset".to_vec()));
+        assert_eq!(
+            contract.store_prediction_for_chart(date(3), B32::repeat_byte(5), natal),
+            Err(b"ChartRegistryUnavailable".to_vec())
+        );
+        assert_eq!(contract.get_user_stats(user).0, U256::from(3));
+    }
+    
+    #[test]
+    fn test_chart_attribution_uses_registry_keys() {
+        use chart_registry::ChartRegistry;
+        
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.initialize(user).unwrap();
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        let registry = Address::repeat_byte(0xc0);
+        contract.set_chart_registry(registry).unwrap();
+        let owned = [String::from("Chart-A"), String::from("chart_a"), "x".repeat(200), String::from("Ästro-チャート")];
+        vm.mock_static_call(
+            registry,
+            IChartRegistry::getUserChartsCall { user }.abi_encode(),
+            Ok(IChartRegistry::getUserChartsCall::abi_encode_returns(&(owned.to_vec(),))),
+        );
+        
+        // The key the registry reports for each id attributes to it
+        for (n, chart_id) in owned.into_iter().enumerate() {
+            let date = U256::from((20_000 + n as u64) * DAY);
+            let key = ChartRegistry::debug_chart_key(chart_id);
+            contract.store_prediction_for_chart(date, B32::repeat_byte(1), key).unwrap();
+            assert_eq!(contract.get_prediction_meta(user, date).2, key);
+        }
+        // Ids one character off are someone else's charts
+        let date = U256::from(20_010 * DAY);
+        for chart_id in ["chart-a", "Chart_A", "chart_a "] {
+            let key = ChartRegistry::debug_chart_key(chart_id.into());
+            assert_eq!(
+                contract.store_prediction_for_chart(date, B32::repeat_byte(1), key),
+                Err(b"NotChartOwner".to_vec())
+            );
+        }
+    }
+    
+    #[test]
+    fn test_rating_stats_and_update() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        let day = U256::from(20_000 * DAY);
+        let next = day + U256::from(DAY);
+        contract.store_prediction(day, B32::repeat_byte(0x01)).unwrap();
+        contract.store_prediction(next, B32::repeat_byte(0x02)).unwrap();
+        
+        assert_eq!(contract.rate_prediction(day, U8::from(6)), Err(b"InvalidRating".to_vec()));
+        contract.rate_prediction(day, U8::from(5)).unwrap();
+        contract.rate_prediction(next, U8::from(4)).unwrap();
+        // (5 + 4) / 2 = 4.5
+        assert_eq!(contract.get_user_stats(user), (U256::from(2), U256::from(2), U256::from(45)));
+        
+        // Re-rating replaces the old rating rather than adding one
+        contract.rate_prediction(day, U8::from(2)).unwrap();
+        assert_eq!(contract.get_rating(user, day), U8::from(2));
+        assert_eq!(contract.get_user_stats(user), (U256::from(2), U256::from(2), U256::from(30)));
+        
+        // 0 clears a rating; rating again counts it once
+        contract.rate_prediction(day, U8::ZERO).unwrap();
+        assert_eq!(contract.get_user_stats(user), (U256::from(2), U256::from(1), U256::from(40)));
+        contract.rate_prediction(day, U8::ZERO).unwrap();
+        contract.rate_prediction(day, U8::from(3)).unwrap();
+        assert_eq!(contract.get_user_stats(user), (U256::from(2), U256::from(2), U256::from(35)));
+    }
+    
+    #[test]
+    fn test_unregistered_user_paths() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let stranger = Address::repeat_byte(0x5e);
+        vm.set_sender(stranger);
+        
+        let date = U256::from(20_000 * DAY);
+        assert_eq!(
+            contract.store_prediction(date, B32::repeat_byte(0x01)),
+            Err(b"UserNotRegistered".to_vec())
+        );
+        assert_eq!(contract.rate_prediction(date, U8::from(3)), Err(b"PredictionNotFound".to_vec()));
+        
+        assert!(!contract.is_user_registered(stranger));
+        assert_eq!(contract.get_user_commitment(stranger), B32::ZERO);
+        assert_eq!(contract.get_user_stats(stranger), (U256::ZERO, U256::ZERO, U256::ZERO));
+        assert_eq!(contract.get_global_stats(), (U256::ZERO, U256::ZERO));
+    }
+    
+    #[test]
+    fn test_multi_user_global_stats() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        
+        let day = U256::from(NOW + 2 * DAY);
+        for (i, predictions) in [3u64, 1, 2].into_iter().enumerate() {
+            let user = Address::repeat_byte(0x40 + i as u8);
+            vm.set_sender(user);
+            vm.set_block_timestamp(NOW + i as u64 * DAY);
+            contract.register_user(B32::repeat_byte(0xa0 + i as u8)).unwrap();
+            for d in 0..predictions {
+                contract.store_prediction(day + U256::from(d * DAY), B32::repeat_byte(0x01)).unwrap();
+            }
+            assert_eq!(contract.get_user_stats(user).0, U256::from(predictions));
+        }
+        
+        // Same date and hash for different users don't collide
+        assert_eq!(contract.get_prediction(Address::repeat_byte(0x41), day), B32::repeat_byte(0x01));
+        assert_eq!(contract.get_global_stats(), (U256::from(3), U256::from(6)));
+    }
+    
+    #[test]
+    fn test_import_users() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        vm.set_block_timestamp(NOW);
+        contract.initialize(owner).unwrap();
+        let taken = Address::repeat_byte(0xee);
+        vm.set_sender(taken);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        vm.set_sender(owner);
+        let user = |n: u8| Address::repeat_byte(n);
+        let commitment = |n: u8| B32::repeat_byte(n);
+        let since = U256::from(NOW - 1000);
+        
+        // A dataset with two bad entries: a registered user and a repeat
+        let users = vec![user(1), taken, user(2), user(1), user(3)];
+        let commitments: Vec<B32> = [1, 9, 2, 1, 3].into_iter().map(commitment).collect();
+        let registered_at = vec![since; 5];
+        let log_length = contract.get_admin_log_length();
+        let simulated = contract.import_users(users.clone(), commitments.clone(), registered_at.clone(), true, B32::ZERO);
+        assert_eq!(simulated, Ok(vec![import::OK, import::ALREADY_EXISTS, import::OK, import::DUPLICATE, import::OK]));
+        assert_eq!(
+            contract.import_users(users, commitments, registered_at, false, B32::ZERO),
+            Err(b"ImportRejected".to_vec())
+        );
+        // Neither run wrote anything
+        assert!(!contract.is_user_registered(user(1)));
+        assert_eq!(contract.get_global_stats().0, U256::from(1));
+        assert_eq!(contract.get_admin_log_length(), log_length);
+        
+        // The other checks
+        let simulated = contract.import_users(
+            vec![Address::ZERO, user(4), user(5), user(6)],
+            vec![commitment(4), B32::ZERO, commitment(5), commitment(6)],
+            vec![since, since, U256::ZERO, U256::from(NOW + 1)],
+            true,
+            B32::ZERO,
+        );
+        assert_eq!(simulated, Ok(vec![import::INVALID, import::INVALID, import::OUT_OF_RANGE, import::OUT_OF_RANGE]));
+        assert_eq!(contract.import_users(vec![user(1)], vec![], vec![since], true, B32::ZERO), Err(b"LengthMismatch".to_vec()));
+        let many = import::MAX_IMPORT_ENTRIES + 1;
+        assert_eq!(
+            contract.import_users(vec![user(1); many], vec![commitment(1); many], vec![since; many], true, B32::ZERO),
+            Err(b"TooManyEntries".to_vec())
+        );
+        contract.total_users.set(U256::MAX - U256::from(1));
+        let simulated = contract.import_users(vec![user(1), user(2)], vec![commitment(1); 2], vec![since; 2], true, B32::ZERO);
+        assert_eq!(simulated, Ok(vec![import::OK, import::COUNTER_OVERFLOW]));
+        contract.total_users.set(U256::from(1));
+        vm.set_sender(user(1));
+        assert_eq!(contract.import_users(vec![], vec![], vec![], true, B32::ZERO), Err(b"NotOwner".to_vec()));
+        
+        // The cleaned dataset goes through
+        vm.set_sender(owner);
+        let users = vec![user(1), user(2), user(3)];
+        let commitments: Vec<B32> = [1, 2, 3].into_iter().map(commitment).collect();
+        let key = B32::repeat_byte(0x1d);
+        assert_eq!(
+            contract.import_users(users.clone(), commitments.clone(), vec![since; 3], false, key),
+            Ok(vec![import::OK; 3])
+        );
+        for (n, user) in (1..).zip(users.clone()) {
+            assert_eq!(contract.get_user_commitment(user), commitment(n));
+            assert_eq!(contract.registered_at.get(user), since);
+        }
+        assert_eq!(contract.get_global_stats().0, U256::from(4));
+        let (actions, _, targets, _) = contract.get_admin_log(log_length, U256::from(1));
+        assert_eq!((actions, targets), (vec![admin_log::IMPORT_USERS], vec![B32::from(U256::from(3))]));
+        
+        // A retry with the same key is suppressed instead of rejected as
+        // already registered
+        assert_eq!(contract.import_users(users, commitments, vec![since; 3], false, key), Ok(vec![]));
+        assert!(contract.is_idempotency_key_used(owner, key));
+        assert_eq!(contract.get_global_stats().0, U256::from(4));
+        assert_eq!(contract.get_admin_log_length(), log_length + U256::from(1));
+        let suppressed = vm.get_emitted_logs().into_iter().filter(|(topics, _)| topics[0] == DuplicateSuppressed::SIGNATURE_HASH);
+        assert_eq!(suppressed.count(), 1);
+    }
+    
+    #[test]
+    fn test_batched_prediction_queries() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(Address::repeat_byte(0x0a)).unwrap();
+        let (with, without, stranger) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb0), Address::repeat_byte(0xc2));
+        let date = U256::from(20_000 * DAY);
+        vm.set_block_timestamp(20_000 * DAY);
+        for user in [with, without] {
+            vm.set_sender(user);
+            contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        }
+        vm.set_sender(with);
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        contract.rate_prediction(date, U8::from(4)).unwrap();
+        
+        // Answers follow the input order, repeats included
+        let users = vec![stranger, with, without, with];
+        assert_eq!(contract.have_predictions(users.clone(), date), Ok(vec![false, true, false, true]));
+        let (exists, hashes, ratings) = contract.get_predictions_multi(users.clone(), date).unwrap();
+        assert_eq!(exists, [false, true, false, true]);
+        assert_eq!(hashes, [B32::ZERO, B32::repeat_byte(1), B32::ZERO, B32::repeat_byte(1)]);
+        assert_eq!(ratings, [0, 4, 0, 4]);
+        for (i, user) in users.iter().enumerate() {
+            assert_eq!(exists[i], contract.has_prediction(*user, date));
+        }
+        let next = date + U256::from(DAY);
+        assert_eq!(contract.have_predictions(users, next), Ok(vec![false; 4]));
+        assert_eq!(contract.have_predictions(vec![], date), Ok(vec![]));
+        
+        let full = vec![with; MAX_BATCH_USERS];
+        assert_eq!(contract.have_predictions(full.clone(), date), Ok(vec![true; MAX_BATCH_USERS]));
+        let over = vec![with; MAX_BATCH_USERS + 1];
+        assert_eq!(contract.have_predictions(over.clone(), date), Err(b"TooManyUsers".to_vec()));
+        assert_eq!(contract.get_predictions_multi(over, date), Err(b"TooManyUsers".to_vec()));
+    }
+    
+    #[test]
+    fn test_register_user_pedersen() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        
+        let (x, y) = commitment::commit(U256::from(19_900_101u64), U256::from(7u64));
+        assert!(contract.register_user_pedersen(x, y).is_ok());
+        assert!(contract.is_user_registered(user));
+        assert_eq!(contract.get_pedersen_commitment(user), (x, y));
+        
+        let duplicate = contract.register_user_pedersen(x, y);
+        assert_eq!(duplicate, Err(b"UserAlreadyRegistered".to_vec()));
+    }
+    
+    #[test]
+    fn test_register_user_pedersen_rejects_off_curve() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        
+        let result = contract.register_user_pedersen(U256::from(1), U256::from(3));
+        assert_eq!(result, Err(b"InvalidCommitment".to_vec()));
+        
+        let result = contract.register_user_pedersen(U256::ZERO, U256::ZERO);
+        assert_eq!(result, Err(b"InvalidCommitment".to_vec()));
+    }
+    
+    const POSITIONS: [u64; 7] = [28045, 12030, 30011, 33520, 21077, 4590, 17333];
+    
+    // 2025-08-01, inside epoch 2900
+    const NOW: u64 = 1_754_006_400;
+    
+    fn sample_commitment() -> Commitment {
+        let birth_data = prover::BirthData {
+            dob: String::from("1990-01-01"),
+            tob: String::from("12:00"),
+            tz: String::from("UTC"),
+            lat: String::from("51.5074"),
+            lon: String::from("-0.1278"),
+        };
+        prover::generate_commitment(&birth_data, [3u8; 32])
+    }
+    
+    /// POSITIONS with explicit bodies, for v2 proofs
+    fn sample_chart() -> Vec<positions::PlanetPosition> {
+        POSITIONS
+            .iter()
+            .zip(0u8..)
+            .map(|(longitude, body)| positions::PlanetPosition {
+                body,
+                longitude_centideg: *longitude as u16,
+                is_retrograde: false,
+            })
+            .collect()
+    }
+    
+    fn sample_proof() -> poseidon::ZkProof {
+        prover::generate_proof(&sample_commitment(), "nonce", &POSITIONS, Encoding::Legacy)
+    }
+    
+    /// Calldata form of a prover proof: (commitment, proof, nonce)
+    fn calldata(proof: &poseidon::ZkProof) -> (B32, B32, Bytes) {
+        let bytes = proof.to_bytes().expect("prover output is valid hex");
+        (bytes.commitment.into(), B32::from(bytes.proof), Bytes::from(bytes.nonce))
+    }
+    
+    /// Positions matching the sample proofs of `version`
+    fn sample_positions(version: u8) -> Vec<u64> {
+        if version >= SCHEME_V2 {
+            positions::to_words(&sample_chart())
+        } else {
+            POSITIONS.to_vec()
+        }
+    }
+    
+    fn register_with_proof(
+        contract: &mut FarcasterPredictions,
+        proof: &poseidon::ZkProof,
+        version: u8,
+    ) -> Result<(), Vec<u8>> {
+        let (commitment, proof_bytes, nonce) = calldata(proof);
+        contract.register_user_with_proof(
+            commitment,
+            proof_bytes,
+            nonce,
+            sample_positions(version),
+            version,
+            proof.encoding.id(),
+            proof.epoch,
+        )
+    }
+    
+    #[test]
+    fn test_register_with_proof_in_range() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        assert!(register_with_proof(&mut contract, &sample_proof(), SCHEME_V1).is_ok());
+        assert_eq!(contract.get_user_scheme_version(vm.msg_sender()), SCHEME_V1);
+    }
+    
+    #[test]
+    fn test_register_with_proof_above_max() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let future = LATEST_SCHEME_VERSION + 1;
+        let result = register_with_proof(&mut contract, &sample_proof(), future);
+        assert_eq!(result, Err(UnsupportedProofVersion { version: future }.abi_encode()));
+        assert!(!contract.is_user_registered(vm.msg_sender()));
+    }
+    
+    #[test]
+    fn test_owner_narrows_version_window() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        // Window dropping v1 and accepting a future version the verifier
+        // doesn't know yet
+        let future = LATEST_SCHEME_VERSION + 1;
+        contract.set_accepted_versions(SCHEME_V2, future).unwrap();
+        assert_eq!(contract.get_accepted_versions(), (SCHEME_V2, future));
+        
+        let result = register_with_proof(&mut contract, &sample_proof(), SCHEME_V1);
+        assert_eq!(result, Err(UnsupportedProofVersion { version: SCHEME_V1 }.abi_encode()));
+        
+        let result = register_with_proof(&mut contract, &sample_proof(), future);
+        assert_eq!(result, Err(UnsupportedProofVersion { version: future }.abi_encode()));
+        
+        vm.set_block_timestamp(NOW);
+        let epoch = poseidon::compute_epoch(NOW);
+        let proof = prover::generate_proof_v2(&sample_commitment(), "nonce", &sample_chart(), epoch).unwrap();
+        assert_eq!(register_with_proof(&mut contract, &proof, SCHEME_V2), Ok(()));
+        assert_eq!(contract.get_user_scheme_version(vm.msg_sender()), SCHEME_V2);
+    }
+    
+    #[test]
+    fn test_register_with_poseidon2_proof() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        vm.set_block_timestamp(NOW);
+        
+        let epoch = poseidon::compute_epoch(NOW);
+        let proof = prover::generate_proof_v3(&sample_commitment(), "nonce", &sample_chart(), epoch).unwrap();
+        assert_eq!(register_with_proof(&mut contract, &proof, SCHEME_V2), Err(b"InvalidProof".to_vec()));
+        assert_eq!(register_with_proof(&mut contract, &proof, SCHEME_V3), Ok(()));
+        assert_eq!(contract.get_user_scheme_version(vm.msg_sender()), SCHEME_V3);
+    }
+    
+    #[test]
+    fn test_set_accepted_versions_owner_only() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        assert_eq!(contract.set_accepted_versions(2, 1), Err(b"InvalidVersionRange".to_vec()));
+        assert_eq!(contract.set_accepted_versions(0, 1), Err(b"InvalidVersionRange".to_vec()));
+        
+        vm.set_sender(Address::repeat_byte(0x22));
+        assert_eq!(contract.set_accepted_versions(1, 1), Err(b"NotOwner".to_vec()));
+        assert_eq!(contract.initialize(vm.msg_sender()), Err(b"AlreadyInitialized".to_vec()));
+    }
+    
+    #[test]
+    fn test_register_with_invalid_proof() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let mut proof = sample_proof();
+        proof.nonce.push('x');
+        let result = register_with_proof(&mut contract, &proof, SCHEME_V1);
+        assert_eq!(result, Err(b"InvalidProof".to_vec()));
+    }
+    
+    #[test]
+    fn test_register_rejects_oversized_input() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let too_large = |field| InputTooLarge { field }.abi_encode();
+        let mut proof = sample_proof();
+        proof.nonce = "n".repeat(poseidon::MAX_FIELD_BYTES + 1);
+        let result = register_with_proof(&mut contract, &proof, SCHEME_V1);
+        assert_eq!(result, Err(too_large(poseidon::INPUT_NONCE)));
+        
+        let (commitment, proof, nonce) = calldata(&sample_proof());
+        for count in [poseidon::MIN_POSITIONS - 1, poseidon::MAX_POSITIONS + 1] {
+            let result = contract.register_user_with_proof(
+                commitment,
+                proof,
+                nonce.clone(),
+                vec![0; count],
+                SCHEME_V1,
+                Encoding::Legacy.id(),
+                0,
+            );
+            assert_eq!(result, Err(too_large(poseidon::INPUT_POSITIONS)));
+        }
+        
+        // A 5 KB nonce or 1,000 positions fail on their size before the
+        // bucket claim, Schnorr proof or hex commitment is looked at
+        let huge_nonce = Bytes::from(vec![b'n'; 5 * 1024]);
+        let many = vec![0u64; 1000];
+        let mut bucket = |nonce: &Bytes, positions: &[u64]| {
+            contract.register_user_with_bucket(commitment, proof, nonce.clone(), positions.to_vec(), 0, 3, B32::ZERO)
+        };
+        assert_eq!(bucket(&huge_nonce, &POSITIONS), Err(too_large(poseidon::INPUT_NONCE)));
+        assert_eq!(bucket(&nonce, &many), Err(too_large(poseidon::INPUT_POSITIONS)));
+        assert_eq!(bucket(&nonce, &POSITIONS), Err(b"InvalidBucketClaim".to_vec()));
+        let schnorr = contract.register_user_schnorr(U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO, many.clone());
+        assert_eq!(schnorr, Err(too_large(poseidon::INPUT_POSITIONS)));
+        let hex = contract.verify_proof_hex(
+            "zz".into(),
+            "zz".into(),
+            "n".repeat(5 * 1024),
+            POSITIONS.to_vec(),
+            SCHEME_V1,
+            Encoding::Legacy.id(),
+            0,
+        );
+        assert_eq!(hex, Err(too_large(poseidon::INPUT_NONCE)));
+        let hex = contract.verify_proof_hex("zz".into(), "zz".into(), "n".into(), many, SCHEME_V1, Encoding::Legacy.id(), 0);
+        assert_eq!(hex, Err(too_large(poseidon::INPUT_POSITIONS)));
+        assert!(!contract.is_user_registered(vm.msg_sender()));
+    }
+    
+    #[test]
+    fn test_verify_proof_hex_normalizes_commitment() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let mut proof = sample_proof();
+        proof.commitment.truncate(63);
+        assert_eq!(verify_hex(&contract, &proof, SCHEME_V1), Err(b"InvalidCommitment".to_vec()));
+        
+        let mut proof = sample_proof();
+        proof.commitment = format!("0x{}", proof.commitment.to_ascii_uppercase());
+        assert_eq!(verify_hex(&contract, &proof, SCHEME_V1), Ok(true));
+        proof.proof = proof.proof.to_ascii_uppercase();
+        assert_eq!(verify_hex(&contract, &proof, SCHEME_V1), Ok(true));
+        assert_eq!(calldata(&proof).0, B32::from(sample_commitment()));
+    }
+    
+    #[test]
+    fn test_hex_and_byte_proofs_verify_identically() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        vm.set_block_timestamp(NOW);
+        let epoch = poseidon::compute_epoch(NOW);
+        
+        let v1 = sample_proof();
+        let packed = prover::generate_proof(&sample_commitment(), "nonce", &POSITIONS, Encoding::SolidityPacked);
+        let v2 = prover::generate_proof_v2(&sample_commitment(), "nonce", &sample_chart(), epoch).unwrap();
+        let v3 = prover::generate_proof_v3(&sample_commitment(), "nonce", &sample_chart(), epoch).unwrap();
+        let mut wrong_nonce = v2.clone();
+        wrong_nonce.nonce = String::from("other");
+        let mut tampered = v1.clone();
+        tampered.proof.replace_range(..1, if tampered.proof.starts_with('0') { "1" } else { "0" });
+        let expired = prover::generate_proof_v2(&sample_commitment(), "nonce", &sample_chart(), epoch - 2).unwrap();
+        
+        let cases = [
+            (&v1, SCHEME_V1, Ok(true)),
+            (&packed, SCHEME_V1, Ok(true)),
+            (&v2, SCHEME_V2, Ok(true)),
+            (&v3, SCHEME_V3, Ok(true)),
+            (&v3, SCHEME_V2, Ok(false)),
+            (&wrong_nonce, SCHEME_V2, Ok(false)),
+            (&tampered, SCHEME_V1, Ok(false)),
+            (&expired, SCHEME_V2, Err(ProofExpired { epoch: epoch - 2, currentEpoch: epoch }.abi_encode())),
+            (&v1, 9, Err(UnsupportedProofVersion { version: 9 }.abi_encode())),
+        ];
+        for (proof, version, expected) in cases {
+            assert_eq!(verify_hex(&contract, proof, version), expected, "hex, version {version}");
+            assert_eq!(verify_bytes(&contract, proof, version), expected, "bytes, version {version}");
+        }
+        
+        // Views never register
+        assert!(!contract.is_user_registered(vm.msg_sender()));
+        assert_eq!(register_with_proof(&mut contract, &v2, SCHEME_V2), Ok(()));
+    }
+    
+    fn verify_hex(contract: &FarcasterPredictions, proof: &poseidon::ZkProof, version: u8) -> Result<bool, Vec<u8>> {
+        contract.verify_proof_hex(
+            proof.commitment.clone(),
+            proof.proof.clone(),
+            proof.nonce.clone(),
+            sample_positions(version),
+            version,
+            proof.encoding.id(),
+            proof.epoch,
+        )
+    }
+    
+    fn verify_bytes(contract: &FarcasterPredictions, proof: &poseidon::ZkProof, version: u8) -> Result<bool, Vec<u8>> {
+        let (commitment, proof_bytes, nonce) = calldata(proof);
+        contract.verify_proof(
+            commitment,
+            proof_bytes,
+            nonce,
+            sample_positions(version),
+            version,
+            proof.encoding.id(),
+            proof.epoch,
+        )
+    }
+    
+    #[test]
+    fn test_register_with_packed_positions() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let proof = sample_proof();
+        let (commitment, proof_bytes, nonce) = calldata(&proof);
+        let (lo, hi) = poseidon::pack_positions(&POSITIONS).unwrap();
+        let packed = [B32::from(lo), B32::from(hi)];
+        
+        // Dropping a position leaves fewer than a chart's seven bodies
+        let result = contract.register_user_with_proof_packed(
+            commitment,
+            proof_bytes,
+            nonce.clone(),
+            packed,
+            POSITIONS.len() as u8 - 1,
+            SCHEME_V1,
+            proof.encoding.id(),
+            proof.epoch,
+        );
+        assert_eq!(result, Err(InputTooLarge { field: poseidon::INPUT_POSITIONS }.abi_encode()));
+        
+        let result = contract.register_user_with_proof_packed(
+            commitment,
+            proof_bytes,
+            nonce.clone(),
+            [B32::repeat_byte(0xff), B32::ZERO],
+            1,
+            SCHEME_V1,
+            proof.encoding.id(),
+            proof.epoch,
+        );
+        assert_eq!(result, Err(b"InvalidPositions".to_vec()));
+        
+        let result = contract.register_user_with_proof_packed(
+            commitment,
+            proof_bytes,
+            nonce.clone(),
+            packed,
+            POSITIONS.len() as u8,
+            SCHEME_V1,
+            proof.encoding.id(),
+            proof.epoch,
+        );
+        assert_eq!(result, Ok(()));
+        assert!(contract.is_user_registered(vm.msg_sender()));
+    }
+    
+    #[test]
+    fn test_proof_epoch_grace_period() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        vm.set_block_timestamp(NOW);
+        let epoch = poseidon::compute_epoch(NOW);
+        let proof = prover::generate_proof_v2(&sample_commitment(), "nonce", &sample_chart(), epoch).unwrap();
+        let next_epoch_start = (epoch + 1) * poseidon::EPOCH_SECONDS;
+        
+        // Last second of the proof's own epoch
+        vm.set_block_timestamp(next_epoch_start - 1);
+        vm.set_sender(Address::repeat_byte(0x01));
+        assert_eq!(register_with_proof(&mut contract, &proof, SCHEME_V2), Ok(()));
+        
+        // Previous epoch is still within the grace period
+        vm.set_block_timestamp(next_epoch_start);
+        vm.set_sender(Address::repeat_byte(0x02));
+        assert_eq!(register_with_proof(&mut contract, &proof, SCHEME_V2), Ok(()));
+        
+        // Two epochs on, the proof has expired
+        vm.set_block_timestamp(next_epoch_start + poseidon::EPOCH_SECONDS);
+        vm.set_sender(Address::repeat_byte(0x03));
+        assert_eq!(
+            register_with_proof(&mut contract, &proof, SCHEME_V2),
+            Err(ProofExpired { epoch, currentEpoch: epoch + 2 }.abi_encode())
+        );
+        assert!(!contract.is_user_registered(Address::repeat_byte(0x03)));
+        
+        // v1 proofs are not epoch-bound
+        assert_eq!(register_with_proof(&mut contract, &sample_proof(), SCHEME_V1), Ok(()));
+    }
+    
+    fn allowlist(seed: u8) -> Vec<B32> {
+        (0..8).map(|i| B32::repeat_byte(seed + i)).collect()
+    }
+    
+    fn allowlist_leaves(commitments: &[B32]) -> Vec<B32> {
+        commitments.iter().map(keccak).collect()
+    }
+    
+    #[test]
+    fn test_register_allowlisted() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let cohort = allowlist(0x10);
+        let leaves = allowlist_leaves(&cohort);
+        let root = merkle::root(&leaves);
+        contract.set_allowlist_root(root).unwrap();
+        assert_eq!(contract.get_allowlist_root(), root);
+        
+        // Excluded commitment, even with a valid-looking proof
+        let outsider = B32::repeat_byte(0xee);
+        let proof = merkle::proof(&leaves, 0).unwrap();
+        assert_eq!(
+            contract.register_user_allowlisted(outsider, proof),
+            Err(b"NotAllowlisted".to_vec())
+        );
+        
+        // Other registration paths are closed while the gate is active
+        assert_eq!(contract.register_user(cohort[5]), Err(b"AllowlistActive".to_vec()));
+        
+        let user = vm.msg_sender();
+        let proof = merkle::proof(&leaves, 5).unwrap();
+        assert_eq!(contract.register_user_allowlisted(cohort[5], proof), Ok(()));
+        assert_eq!(contract.get_user_commitment(user), cohort[5]);
+        
+        let logs = vm.get_emitted_logs();
+        let admitted: Vec<_> = logs.iter().filter(|(topics, _)| topics[0] == UserAdmitted::SIGNATURE_HASH).collect();
+        assert_eq!(admitted.len(), 1);
+        assert_eq!(admitted[0].0[1], B32::left_padding_from(user.as_slice()));
+        assert_eq!(admitted[0].0[2], root);
+    }
+    
+    #[test]
+    fn test_allowlist_root_rotation() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let cohort = allowlist(0x10);
+        let old_leaves = allowlist_leaves(&cohort);
+        contract.set_allowlist_root(merkle::root(&old_leaves)).unwrap();
+        let stale_proof = merkle::proof(&old_leaves, 2).unwrap();
+        
+        // Rotate to a cohort that still contains the commitment
+        let mut rotated = allowlist(0x40);
+        rotated[7] = cohort[2];
+        let new_leaves = allowlist_leaves(&rotated);
+        contract.set_allowlist_root(merkle::root(&new_leaves)).unwrap();
+        
+        assert_eq!(
+            contract.register_user_allowlisted(cohort[2], stale_proof),
+            Err(b"NotAllowlisted".to_vec())
+        );
+        let fresh_proof = merkle::proof(&new_leaves, 7).unwrap();
+        assert_eq!(contract.register_user_allowlisted(cohort[2], fresh_proof), Ok(()));
+    }
+    
+    #[test]
+    fn test_allowlist_disabled_by_zero_root() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        vm.set_sender(Address::repeat_byte(0x22));
+        assert_eq!(contract.set_allowlist_root(B32::repeat_byte(1)), Err(b"NotOwner".to_vec()));
+        
+        assert_eq!(contract.register_user_allowlisted(B32::repeat_byte(0xee), Vec::new()), Ok(()));
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs[0].0[2], B32::ZERO);
+    }
+    
+    /// Answer the contract's ecrecover call for `signature` of the FID
+    /// attestation of `(user, fid)` with `signer`, standing in for the
+    /// precompile
+    fn mock_ecrecover(vm: &TestVM, user: Address, fid: U256, signature: &[u8; 65], signer: Address) {
+        let mut input = fid_attestation_digest(vm.chain_id(), vm.contract_address(), user, fid).to_vec();
+        input.extend_from_slice(&U256::from(signature[64]).to_be_bytes::<32>());
+        input.extend_from_slice(&signature[..64]);
+        vm.mock_static_call(ECRECOVER, input, Ok(signer.into_word().to_vec()));
+    }
+    
+    #[test]
+    fn test_register_with_fid() {
+        use alloy_primitives::{address, hex};
+        
+        // Signed with secp256k1 keys [0x11; 32] (the attestor) and [0x22; 32]
+        // for the contract at 0xc0..c0 on chain 42161
+        let attestor = address!("19e7e376e7c213b7e7e7e46cc70a5dd086daff2a");
+        let impostor = address!("1563915e194d8cfba1943570603f7606a3115508");
+        let (alice, bob) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xbb));
+        let (alice_fid, bob_fid) = (U256::from(100), U256::from(5000));
+        let alice_sig = hex!("a32f5c63aec46b583627a73562125750b7231e95a9e611f675b2987a2c09450660b545a7740947500c4ce5be8ed111abec370673b876ab67f30c5aae87719dc21b");
+        let bob_sig = hex!("b800f1a2a52ce4498bd9559871ad3a25bb6a46e0618d7df7ffeac8892b5fa4d4086d99aac7f24ac6339029a83b65284fdd32c0c0ef549319d19863fc26243ad61b");
+        let forged_sig = hex!("a64d24b5a783292196504df32cdb3755ce232d7cd676c496a7b06b3615f873ae2532e8215f050169ecd7a1652a2e2ca720729cf952dbf88aacba57a453a434251b");
+        let deployment = Address::repeat_byte(0xc0);
+        assert_eq!(
+            fid_attestation_digest(42_161, deployment, alice, alice_fid),
+            B32::from(hex!("092a16d4ad3e43ab1ee439ca9b37848299add9aa90c1eeb38760b1a9e0a08e74"))
+        );
+        // Another chain or deployment signs another digest
+        assert_ne!(
+            fid_attestation_digest(1, deployment, alice, alice_fid),
+            fid_attestation_digest(42_161, deployment, alice, alice_fid)
+        );
+        assert_ne!(
+            fid_attestation_digest(42_161, Address::repeat_byte(0xc1), alice, alice_fid),
+            fid_attestation_digest(42_161, deployment, alice, alice_fid)
+        );
+        
+        let vm = TestVM::default();
+        vm.set_chain_id(42_161);
+        vm.set_contract_address(deployment);
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        mock_ecrecover(&vm, alice, alice_fid, &alice_sig, attestor);
+        mock_ecrecover(&vm, bob, bob_fid, &bob_sig, attestor);
+        mock_ecrecover(&vm, alice, alice_fid, &forged_sig, impostor);
+        
+        // No attestor yet: nothing verifies
+        vm.set_sender(alice);
+        let register = |contract: &mut FarcasterPredictions, fid, sig: &[u8]| {
+            contract.register_with_fid(B32::repeat_byte(0xc0), fid, Bytes::from(sig.to_vec()))
+        };
+        assert_eq!(register(&mut contract, alice_fid, &alice_sig), Err(b"InvalidFidAttestation".to_vec()));
+        assert_eq!(contract.set_fid_attestor(attestor), Err(b"NotOwner".to_vec()));
+        
+        vm.set_sender(owner);
+        contract.set_fid_attestor(attestor).unwrap();
+        contract.set_max_beta_fid(U256::from(1000)).unwrap();
+        assert_eq!(contract.get_fid_attestor(), attestor);
+        assert_eq!(contract.get_max_beta_fid(), U256::from(1000));
+        
+        // A key other than the attestor's, a truncated signature, and
+        // Alice's signature replayed by Bob
+        vm.set_sender(alice);
+        assert_eq!(register(&mut contract, alice_fid, &forged_sig), Err(b"InvalidFidAttestation".to_vec()));
+        assert_eq!(register(&mut contract, alice_fid, &alice_sig[..64]), Err(b"InvalidFidAttestation".to_vec()));
+        vm.set_sender(bob);
+        assert_eq!(register(&mut contract, alice_fid, &alice_sig), Err(b"InvalidFidAttestation".to_vec()));
+        
+        // Bob's FID is past the beta range
+        assert_eq!(
+            register(&mut contract, bob_fid, &bob_sig),
+            Err(NotInBeta { fid: bob_fid, maxBetaFid: U256::from(1000) }.abi_encode())
+        );
+        assert!(!contract.is_user_registered(bob));
+        
+        vm.set_sender(alice);
+        assert_eq!(register(&mut contract, alice_fid, &alice_sig), Ok(()));
+        assert!(contract.is_user_registered(alice));
+        assert_eq!(contract.get_fid(alice), alice_fid);
+        assert_eq!(contract.get_fid_owner(alice_fid), alice);
+        let logs = vm.get_emitted_logs();
+        let linked = logs.last().unwrap();
+        assert_eq!(linked.0, [FidLinked::SIGNATURE_HASH, alice.into_word(), B32::from(alice_fid)]);
+        assert_eq!(register(&mut contract, alice_fid, &alice_sig), Err(b"FidAlreadyLinked".to_vec()));
+        
+        // Lifting the gate lets Bob in
+        vm.set_sender(owner);
+        contract.set_max_beta_fid(U256::ZERO).unwrap();
+        vm.set_sender(bob);
+        assert_eq!(register(&mut contract, bob_fid, &bob_sig), Ok(()));
+        assert_eq!(contract.get_fid(bob), bob_fid);
+        assert_eq!(contract.get_fid_owner(bob_fid), bob);
+        assert_eq!(contract.get_fid(owner), U256::ZERO);
+    }
+    
+    /// Answer the contract's ecAdd/ecMul calls for `proof` with the pure
+    /// Rust results, standing in for the precompiles
+    fn mock_schnorr_precompiles(
+        vm: &TestVM,
+        commitment: &G1Point,
+        proof: &schnorr::SchnorrProof,
+        positions: &[u64],
+        sender: Address,
+    ) {
+        use commitment::{EC_ADD, EC_MUL, G};
+        
+        let mock_mul = |point: &G1Point, scalar: U256| {
+            let mut input = point.to_bytes().to_vec();
+            input.extend_from_slice(&scalar.to_be_bytes::<32>());
+            vm.mock_static_call(EC_MUL, input, Ok(point.mul(scalar).to_bytes().to_vec()));
+        };
+        let e = schnorr::challenge(commitment, &proof.r, positions, sender);
+        mock_mul(&G, proof.s);
+        mock_mul(commitment, e);
+        
+        let e_c = commitment.mul(e);
+        let mut input = proof.r.to_bytes().to_vec();
+        input.extend_from_slice(&e_c.to_bytes());
+        vm.mock_static_call(EC_ADD, input, Ok(proof.r.add(&e_c).to_bytes().to_vec()));
+    }
+    
+    #[test]
+    fn test_attestor_rotation() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        vm.set_block_timestamp(NOW);
+        contract.initialize(owner).unwrap();
+        let (old_key, new_key) = (Address::repeat_byte(0x51), Address::repeat_byte(0x52));
+        let role = ATTESTOR_ROLE_FID;
+        
+        // The precompile is mocked, so any 65 bytes stand in for a
+        // signature by `key`; user n asks for FID n
+        let register = |contract: &mut FarcasterPredictions, n: u8, key: Address| {
+            let (user, fid) = (Address::repeat_byte(n), U256::from(n));
+            let mut signature = [key.0[0]; 65];
+            signature[64] = 27;
+            mock_ecrecover(&vm, user, fid, &signature, key);
+            vm.set_sender(user);
+            let result = contract.register_with_fid(B32::repeat_byte(0xc0), fid, Bytes::from(signature.to_vec()));
+            vm.set_sender(owner);
+            result
+        };
+        let attestors = |contract: &FarcasterPredictions| contract.get_attestors(role, U256::ZERO, U256::from(MAX_PAGE_SIZE));
+        
+        vm.set_sender(Address::repeat_byte(0x01));
+        assert_eq!(contract.add_attestor(role, old_key, U256::MAX), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        assert_eq!(contract.add_attestor(role, Address::ZERO, U256::MAX), Err(b"InvalidAttestor".to_vec()));
+        assert_eq!(contract.add_attestor(role, old_key, U256::from(NOW)), Err(b"InvalidExpiry".to_vec()));
+        contract.add_attestor(role, old_key, U256::MAX).unwrap();
+        assert_eq!(register(&mut contract, 1, old_key), Ok(()));
+        
+        // Mid-rotation both keys sign; once revoked, the old key's
+        // signatures fail, even ones made before
+        contract.add_attestor(role, new_key, U256::MAX).unwrap();
+        assert_eq!(attestors(&contract), [old_key, new_key]);
+        assert_eq!(register(&mut contract, 2, old_key), Ok(()));
+        assert_eq!(register(&mut contract, 3, new_key), Ok(()));
+        contract.revoke_attestor(role, old_key).unwrap();
+        assert_eq!(attestors(&contract), [new_key]);
+        assert_eq!(contract.get_attestor_expiry(role, old_key), U256::ZERO);
+        assert_eq!(register(&mut contract, 4, old_key), Err(b"InvalidFidAttestation".to_vec()));
+        assert_eq!(register(&mut contract, 4, new_key), Ok(()));
+        assert_eq!(contract.revoke_attestor(role, old_key), Err(b"UnknownAttestor".to_vec()));
+        assert!(contract.get_attestors(role + 1, U256::ZERO, U256::from(MAX_PAGE_SIZE)).is_empty());
+        
+        // A key signs until the second before its expiry
+        let expires_at = NOW + 100;
+        contract.add_attestor(role, old_key, U256::from(expires_at)).unwrap();
+        vm.set_block_timestamp(expires_at - 1);
+        assert_eq!(register(&mut contract, 5, old_key), Ok(()));
+        vm.set_block_timestamp(expires_at);
+        assert_eq!(register(&mut contract, 6, old_key), Err(b"AttestorExpired".to_vec()));
+        assert_eq!(attestors(&contract), [new_key]);
+        assert_eq!(contract.get_attestor_count(role), U256::from(2));
+        assert!(contract.get_attestors(role, U256::from(1), U256::from(1)).is_empty());
+        // Moving the expiry brings it back
+        contract.add_attestor(role, old_key, U256::from(expires_at + 1)).unwrap();
+        assert_eq!(register(&mut contract, 6, old_key), Ok(()));
+        
+        // `set_fid_attestor` swaps its own key only
+        let (first, second) = (Address::repeat_byte(0x53), Address::repeat_byte(0x54));
+        contract.set_fid_attestor(first).unwrap();
+        assert_eq!(contract.get_attestor_expiry(role, first), U256::MAX);
+        contract.set_fid_attestor(second).unwrap();
+        assert_eq!(attestors(&contract), [new_key, old_key, second]);
+        contract.revoke_attestor(role, new_key).unwrap();
+        assert_eq!(attestors(&contract), [second, old_key]);
+        contract.revoke_attestor(role, second).unwrap();
+        assert_eq!(contract.get_fid_attestor(), Address::ZERO);
+        assert_eq!(attestors(&contract), [old_key]);
+        
+        let last = contract.get_admin_log_length() - U256::from(1);
+        let (actions, _, targets, _) = contract.get_admin_log(last, U256::from(1));
+        assert_eq!((actions[0], targets[0]), (admin_log::REVOKE_ATTESTOR, attestor_target(role, second)));
+        
+        // A key added with `add_attestor` keeps its expiry as the FID
+        // attestor, and stays when replaced
+        contract.set_fid_attestor(old_key).unwrap();
+        assert_eq!(contract.get_attestor_expiry(role, old_key), U256::from(expires_at + 1));
+        contract.set_fid_attestor(first).unwrap();
+        assert_eq!(contract.get_attestor_expiry(role, old_key), U256::from(expires_at + 1));
+        assert_eq!(attestors(&contract), [old_key, first]);
+        // So does a setter key the owner then adds with `add_attestor`
+        contract.add_attestor(role, first, U256::from(NOW + 1000)).unwrap();
+        contract.set_fid_attestor(Address::ZERO).unwrap();
+        assert_eq!(contract.get_attestor_expiry(role, first), U256::from(NOW + 1000));
+        assert_eq!(attestors(&contract), [old_key, first]);
+    }
+    
+    #[test]
+    fn test_register_user_schnorr() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        
+        let secret = U256::from(0xa57e0u64);
+        let commitment = prover::generate_schnorr_commitment(secret);
+        let proof = prover::generate_schnorr_proof(secret, &POSITIONS, user);
+        mock_schnorr_precompiles(&vm, &commitment, &proof, &POSITIONS, user);
+        
+        // Forged response: the precompiles answer, but the equation fails
+        let forged = schnorr::SchnorrProof { s: proof.s ^ U256::from(1), ..proof };
+        mock_schnorr_precompiles(&vm, &commitment, &forged, &POSITIONS, user);
+        let result = contract.register_user_schnorr(
+            commitment.x,
+            commitment.y,
+            forged.r.x,
+            forged.r.y,
+            forged.s,
+            POSITIONS.to_vec(),
+        );
+        assert_eq!(result, Err(b"InvalidProof".to_vec()));
+        
+        let result = contract.register_user_schnorr(
+            commitment.x,
+            commitment.y,
+            proof.r.x,
+            proof.r.y,
+            proof.s,
+            POSITIONS.to_vec(),
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            contract.get_user_commitment(user),
+            B32::from(commitment.compress().to_be_bytes::<32>())
+        );
+    }
+    
+    #[test]
+    fn test_register_user_schnorr_precompile_failure() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        
+        // No mocks: the precompile calls return empty data
+        let secret = U256::from(0xa57e0u64);
+        let commitment = prover::generate_schnorr_commitment(secret);
+        let proof = prover::generate_schnorr_proof(secret, &POSITIONS, user);
+        let result = contract.register_user_schnorr(
+            commitment.x,
+            commitment.y,
+            proof.r.x,
+            proof.r.y,
+            proof.s,
+            POSITIONS.to_vec(),
+        );
+        assert_eq!(result, Err(b"InvalidProof".to_vec()));
+    }
+    
+    const DAY: u64 = 86_400;
+    
+    /// Eight users with one prediction hash each
+    fn daily_batch() -> Vec<(Address, B32)> {
+        (1..=8u8).map(|i| (Address::repeat_byte(i), B32::repeat_byte(0x80 + i))).collect()
+    }
+    
+    fn daily_leaves(date: U256, batch: &[(Address, B32)]) -> Vec<B32> {
+        batch.iter().map(|(user, hash)| prediction_leaf(*user, date, *hash)).collect()
+    }
+    
+    #[test]
+    fn test_anchor_and_claim_daily_root() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        let owner = vm.msg_sender();
+        
+        let day1 = U256::from(20_000 * DAY);
+        let day2 = day1 + U256::from(DAY);
+        let batch1 = daily_batch();
+        let leaves1 = daily_leaves(day1, &batch1);
+        let batch2 = daily_batch();
+        let leaves2 = daily_leaves(day2, &batch2);
+        
+        let relayer = Address::repeat_byte(0x77);
+        contract.set_relayer(relayer).unwrap();
+        contract.anchor_daily_root(day1, merkle::root(&leaves1), U256::from(8), B32::ZERO).unwrap();
+        vm.set_sender(relayer);
+        contract.anchor_daily_root(day2, merkle::root(&leaves2), U256::from(8), B32::ZERO).unwrap();
+        assert_eq!(contract.get_daily_root(day1), (merkle::root(&leaves1), U256::from(8)));
+        
+        for index in [2, 6] {
+            let (user, hash) = batch1[index];
+            vm.set_sender(user);
+            contract.register_user(B32::repeat_byte(0xc0 + index as u8)).unwrap();
+            
+            // A day-1 leaf doesn't verify against the day-2 root
+            let proof = merkle::proof(&leaves1, index).unwrap();
+            assert_eq!(
+                contract.claim_anchored_prediction(day2, hash, proof.clone()),
+                Err(b"InvalidMerkleProof".to_vec())
+            );
+            
+            assert_eq!(contract.claim_anchored_prediction(day1, hash, proof.clone()), Ok(()));
+            assert_eq!(contract.get_prediction(user, day1), hash);
+            assert!(contract.is_prediction_anchored(user, day1));
+            assert_eq!(
+                contract.claim_anchored_prediction(day1, hash, proof),
+                Err(b"PredictionAlreadyExists".to_vec())
+            );
+        }
+        assert_eq!(contract.get_global_stats().1, U256::from(2));
+        
+        // Another user can't claim someone else's leaf
+        vm.set_sender(owner);
+        contract.register_user(B32::repeat_byte(0xdd)).unwrap();
+        let proof = merkle::proof(&leaves1, 3).unwrap();
+        assert_eq!(
+            contract.claim_anchored_prediction(day1, batch1[3].1, proof),
+            Err(b"InvalidMerkleProof".to_vec())
+        );
+        
+        let logs = vm.get_emitted_logs();
+        let anchored: Vec<_> = logs.iter().filter(|log| log.0[0] == DailyRootAnchored::SIGNATURE_HASH).collect();
+        let claimed: Vec<_> = logs.iter().filter(|log| log.0[0] == AnchoredPredictionClaimed::SIGNATURE_HASH).collect();
+        assert_eq!((anchored.len(), claimed.len()), (2, 2));
+        assert_eq!(claimed[1].0[1], B32::left_padding_from(batch1[6].0.as_slice()));
+        assert_eq!(claimed[1].0[2], B32::from(day1.to_be_bytes::<32>()));
+    }
+    
+    #[test]
+    fn test_anchor_daily_root_access_and_immutability() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let date = U256::from(20_000 * DAY);
+        let root = B32::repeat_byte(0x01);
+        assert_eq!(contract.anchor_daily_root(date, B32::ZERO, U256::ZERO, B32::ZERO), Err(b"InvalidRoot".to_vec()));
+        contract.anchor_daily_root(date, root, U256::from(8), B32::ZERO).unwrap();
+        assert_eq!(
+            contract.anchor_daily_root(date, B32::repeat_byte(0x02), U256::from(8), B32::ZERO),
+            Err(b"RootAlreadyAnchored".to_vec())
+        );
+        assert_eq!(contract.get_daily_root(date).0, root);
+        
+        vm.set_sender(Address::repeat_byte(0x77));
+        let next = date + U256::from(DAY);
+        assert_eq!(contract.anchor_daily_root(next, root, U256::from(1), B32::ZERO), Err(b"NotRelayer".to_vec()));
+        assert_eq!(contract.set_relayer(Address::repeat_byte(0x77)), Err(b"NotOwner".to_vec()));
+        
+        contract.register_user(B32::repeat_byte(0xaa)).unwrap();
+        assert_eq!(
+            contract.claim_anchored_prediction(next, B32::repeat_byte(0x03), Vec::new()),
+            Err(b"RootNotAnchored".to_vec())
+        );
+    }
+    
+    #[test]
+    fn test_idempotency_keys() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        let relayer = Address::repeat_byte(0x32);
+        contract.set_relayer(relayer).unwrap();
+        
+        let date = U256::from(20_000 * DAY);
+        let key = B32::repeat_byte(0x4b);
+        let suppressed = |vm: &TestVM| -> Vec<Vec<B32>> {
+            vm.get_emitted_logs()
+                .into_iter()
+                .filter(|(topics, _)| topics[0] == DuplicateSuppressed::SIGNATURE_HASH)
+                .map(|(topics, _)| topics)
+                .collect()
+        };
+        
+        // A retry with the same key succeeds without writing again
+        vm.set_sender(relayer);
+        contract.anchor_daily_root(date, B32::repeat_byte(1), U256::from(8), key).unwrap();
+        assert!(contract.is_idempotency_key_used(relayer, key));
+        contract.anchor_daily_root(date, B32::repeat_byte(2), U256::from(9), key).unwrap();
+        assert_eq!(contract.get_daily_root(date), (B32::repeat_byte(1), U256::from(8)));
+        let anchored = vm.get_emitted_logs().into_iter().filter(|(topics, _)| topics[0] == DailyRootAnchored::SIGNATURE_HASH);
+        assert_eq!(anchored.count(), 1);
+        assert_eq!(suppressed(&vm), vec![vec![
+            DuplicateSuppressed::SIGNATURE_HASH,
+            B32::left_padding_from(relayer.as_slice()),
+            key,
+        ]]);
+        
+        // Keys are per sender: the owner's first use of it runs
+        vm.set_sender(owner);
+        assert!(!contract.is_idempotency_key_used(owner, key));
+        assert_eq!(
+            contract.anchor_daily_root(date, B32::repeat_byte(2), U256::from(9), key),
+            Err(b"RootAlreadyAnchored".to_vec())
+        );
+        
+        // A failed write leaves its key free for the retry
+        assert!(!contract.is_idempotency_key_used(owner, key));
+        let next = date + U256::from(DAY);
+        contract.anchor_daily_root(next, B32::repeat_byte(3), U256::from(1), key).unwrap();
+        assert_eq!(contract.get_daily_root(next).0, B32::repeat_byte(3));
+        
+        // Without a key every call runs
+        vm.set_sender(relayer);
+        assert_eq!(
+            contract.anchor_daily_root(next, B32::repeat_byte(4), U256::from(1), B32::ZERO),
+            Err(b"RootAlreadyAnchored".to_vec())
+        );
+        assert!(!contract.is_idempotency_key_used(relayer, B32::ZERO));
+        
+        // Proposals take keys too
+        vm.set_sender(owner);
+        contract.set_challenge_window(3600).unwrap();
+        let user = Address::repeat_byte(0x31);
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xaa)).unwrap();
+        vm.set_sender(relayer);
+        let proposal_key = B32::repeat_byte(0x50);
+        contract.propose_prediction_for(user, next, B32::repeat_byte(5), proposal_key).unwrap();
+        contract.propose_prediction_for(user, next, B32::repeat_byte(6), proposal_key).unwrap();
+        assert_eq!(contract.get_pending_prediction(user, next).0, B32::repeat_byte(5));
+        assert_eq!(suppressed(&vm).len(), 2);
+        
+        // Writes are still gated before keys are looked at
+        vm.set_sender(user);
+        assert_eq!(
+            contract.anchor_daily_root(date, B32::repeat_byte(1), U256::from(8), key),
+            Err(b"NotRelayer".to_vec())
+        );
+    }
+    
+    #[test]
+    fn test_admin_log() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        vm.set_block_timestamp(NOW);
+        contract.initialize(owner).unwrap();
+        
+        let relayer = Address::repeat_byte(0x32);
+        let astrologer = Address::repeat_byte(0x41);
+        vm.set_block_timestamp(NOW + 60);
+        contract.set_relayer(relayer).unwrap();
+        contract.set_min_retention_days(30).unwrap();
+        contract.set_astrologer(astrologer, false).unwrap();
+        
+        // Failed and unprivileged calls leave no entry
+        vm.set_sender(relayer);
+        assert_eq!(contract.set_relayer(relayer), Err(b"NotOwner".to_vec()));
+        assert_eq!(contract.get_admin_log_length(), U256::from(4));
+        
+        let (actions, actors, targets, timestamps) = contract.get_admin_log(U256::ZERO, U256::from(10));
+        assert_eq!(
+            actions,
+            [admin_log::INITIALIZE, admin_log::SET_RELAYER, admin_log::SET_MIN_RETENTION_DAYS, admin_log::REMOVE_ASTROLOGER]
+        );
+        assert_eq!(actors, [owner; 4]);
+        assert_eq!(targets, [owner.into_word(), relayer.into_word(), B32::from(U256::from(30)), astrologer.into_word()]);
+        assert_eq!(timestamps, [NOW, NOW + 60, NOW + 60, NOW + 60].map(U256::from));
+        
+        // Pages
+        let page = contract.get_admin_log(U256::from(1), U256::from(2));
+        assert_eq!(page.0, [admin_log::SET_RELAYER, admin_log::SET_MIN_RETENTION_DAYS]);
+        assert_eq!(contract.get_admin_log(U256::from(4), U256::from(2)).0, Vec::<u8>::new());
+        assert_eq!(contract.get_admin_log(U256::MAX, U256::MAX).0, Vec::<u8>::new());
+        
+        // Weights pack into the target's low 16 bytes
+        vm.set_sender(owner);
+        contract.set_point_weights(1, 2, 3, 4).unwrap();
+        let (actions, _, targets, _) = contract.get_admin_log(U256::from(4), U256::from(1));
+        assert_eq!(actions, [admin_log::SET_POINT_WEIGHTS]);
+        assert_eq!(&targets[0][16..], &[0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4]);
+    }
+    
+    #[test]
+    fn test_estimate_batch_cost() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        let user = vm.msg_sender();
+        contract.register_user(B32::repeat_byte(0xaa)).unwrap();
+        
+        assert_eq!(contract.estimate_batch_cost(user, Vec::new()), (U256::ZERO, U256::ZERO, U256::ZERO));
+        
+        // First store: every counter is new, as are the bitmap word, the
+        // day's accumulator and count and the streak (registration already
+        // credited points)
+        let (first, duplicates, first_ink) = contract.estimate_batch_cost(user, vec![day(0)]);
+        assert_eq!((first, duplicates), (U256::from(10), U256::ZERO));
+        contract.store_prediction(day(0), B32::repeat_byte(1)).unwrap();
+        
+        // Stored and repeated dates are duplicates; the next day only adds
+        // its own entries and the day's accumulator and count
+        let (next, duplicates, next_ink) = contract.estimate_batch_cost(user, vec![day(0), day(1), day(1)]);
+        assert_eq!((next, duplicates), (U256::from(5), U256::from(2)));
+        assert!(next_ink < first_ink);
+        
+        // Opted out: the global counter is left alone
+        contract.set_aggregate_opt_out(true).unwrap();
+        let (_, _, opted_out_ink) = contract.estimate_batch_cost(user, vec![day(1)]);
+        assert_eq!(next_ink - opted_out_ink, U256::from(INK_COSTS.slot_reset));
+    }
+    
+    #[test]
+    fn test_ewma_rating_decays() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        // Dates here trail the clock by months
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        assert_eq!(contract.get_rating_half_life(), DEFAULT_RATING_HALF_LIFE_DAYS);
+        assert_eq!(contract.set_rating_half_life(0), Err(b"InvalidHalfLife".to_vec()));
+        contract.set_rating_half_life(7).unwrap();
+        assert_eq!(contract.get_rating_half_life(), 7);
+        
+        let user = Address::repeat_byte(0x3e);
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        assert_eq!(contract.get_ewma_rating(user), (U256::ZERO, U256::ZERO));
+        
+        // Four weeks of 5s, one a day
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        let rate = |contract: &mut FarcasterPredictions, n: u64, rating: u8| {
+            vm.set_block_timestamp(NOW + n * DAY);
+            contract.store_prediction(day(n), B32::repeat_byte(0x01)).unwrap();
+            contract.rate_prediction(day(n), U8::from(rating)).unwrap();
+        };
+        for n in 0..28 {
+            rate(&mut contract, n, 5);
+        }
+        
+        // Then daily 1s: the average falls below 3.0 on the seventh, one
+        // half-life after the switch
+        let mut values = vec![];
+        for n in 28..40 {
+            rate(&mut contract, n, 1);
+            values.push(contract.get_ewma_rating(user).0.to::<u64>());
+        }
+        assert!(values[5] > 3000, "{values:?}");
+        assert!(values[6] < 3000, "{values:?}");
+        assert!(values.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(contract.get_ewma_rating(user).1, U256::from(NOW + 39 * DAY));
+        
+        // An edit is a fresh sample; clearing isn't one
+        vm.set_block_timestamp(NOW + 40 * DAY);
+        let before = contract.get_ewma_rating(user).0;
+        contract.rate_prediction(day(39), U8::from(5)).unwrap();
+        let (edited, last_update) = contract.get_ewma_rating(user);
+        assert!(edited > before);
+        assert_eq!(last_update, U256::from(NOW + 40 * DAY));
+        vm.set_block_timestamp(NOW + 41 * DAY);
+        contract.rate_prediction(day(39), U8::ZERO).unwrap();
+        assert_eq!(contract.get_ewma_rating(user), (edited, last_update));
+    }
+    
+    #[test]
+    fn test_history_root_in_pages() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        let user = Address::repeat_byte(0x3c);
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        // 20 days, every third one rated
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        for n in 0..20 {
+            contract.store_prediction(day(n), B32::repeat_byte(n as u8 + 1)).unwrap();
+            if n % 3 == 0 {
+                contract.rate_prediction(day(n), U8::from(n % 5 + 1)).unwrap();
+            }
+        }
+        
+        assert_eq!(contract.finalize_history_root(user), Err(b"HistoryIncomplete".to_vec()));
+        let (_, processed) = contract.compute_user_history_root(user, U256::ZERO, U256::from(12)).unwrap();
+        assert_eq!(processed, U256::from(12));
+        assert_eq!(contract.finalize_history_root(user), Err(b"HistoryIncomplete".to_vec()));
+        assert_eq!(
+            contract.compute_user_history_root(user, U256::from(11), U256::from(12)),
+            Err(b"HistoryOutOfOrder".to_vec())
+        );
+        vm.set_sender(Address::repeat_byte(0x66));
+        assert_eq!(
+            contract.compute_user_history_root(user, U256::from(12), U256::from(12)),
+            Err(b"NotUserOrOwner".to_vec())
+        );
+        vm.set_sender(owner);
+        let (root, processed) = contract.compute_user_history_root(user, U256::from(12), U256::from(12)).unwrap();
+        assert_eq!(processed, U256::from(20));
+        contract.finalize_history_root(user).unwrap();
+        assert_eq!(contract.get_history_root(user), (root, U256::from(20), U256::ZERO));
+        
+        // Off-chain: the same leaves from the export give the root, and
+        // one day's proof verifies against it
+        let export = contract.export_user(user, U256::ZERO, U256::from(20));
+        let leaves: Vec<B32> = (0..20).map(|i| history::leaf(export.dates[i], export.hashes[i], export.ratings[i])).collect();
+        assert_eq!(merkle::root(&leaves), root);
+        let proof = merkle::proof(&leaves, 9).unwrap();
+        assert!(merkle::verify(&proof, root, history::leaf(day(9), B32::repeat_byte(10), 5)));
+        assert!(!merkle::verify(&proof, root, history::leaf(day(9), B32::repeat_byte(10), 4)));
+        
+        let finalized: Vec<_> = vm
+            .get_emitted_logs()
+            .into_iter()
+            .filter(|(topics, _)| topics[0] == HistoryRootFinalized::SIGNATURE_HASH)
+            .collect();
+        assert_eq!(finalized.len(), 1);
+        assert_eq!(finalized[0].1, HistoryRootFinalized { user, root, leaves: U256::from(20) }.encode_data());
+        
+        // Offset 0 starts over, e.g. after a new rating
+        vm.set_sender(user);
+        contract.rate_prediction(day(1), U8::from(2)).unwrap();
+        let (fresh_root, _) = contract.compute_user_history_root(user, U256::ZERO, U256::from(100)).unwrap();
+        assert_ne!(fresh_root, root);
+        assert_eq!(contract.get_history_root(user).0, root);
+    }
+    
+    #[test]
+    fn test_circuit_breaker() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        // Dates here trail the clock by months
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        let hour = NOW / 3600;
+        vm.set_block_timestamp(hour * 3600);
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        
+        let user = Address::repeat_byte(0x3f);
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        // Off by default: writes aren't counted
+        contract.store_prediction(day(0), B32::repeat_byte(0x01)).unwrap();
+        assert_eq!(contract.get_writes_in_hour(U256::from(hour)), U256::ZERO);
+        assert_eq!(contract.set_auto_pause_threshold(3), Err(b"NotOwner".to_vec()));
+        
+        vm.set_sender(owner);
+        contract.set_auto_pause_threshold(3).unwrap();
+        assert_eq!(contract.get_auto_pause_threshold(), 3);
+        
+        // Three writes fit; the fourth lands and trips the breaker
+        vm.set_sender(user);
+        for n in 1..=4 {
+            contract.store_prediction(day(n), B32::repeat_byte(0x01)).unwrap();
+            assert_eq!(contract.is_paused(), n == 4);
+        }
+        assert!(contract.has_prediction(user, day(4)));
+        assert_eq!(contract.get_writes_in_hour(U256::from(hour)), U256::from(4));
+        let tripped: Vec<_> = vm
+            .get_emitted_logs()
+            .into_iter()
+            .filter(|(topics, _)| topics[0] == AutoPaused::SIGNATURE_HASH)
+            .collect();
+        assert_eq!(tripped.len(), 1);
+        assert_eq!(tripped[0].0[1], B32::from(U256::from(hour)));
+        assert_eq!(U256::from_be_slice(&tripped[0].1), U256::from(4));
+        
+        // Every prediction path stops, even in a later hour, until unpaused
+        vm.set_block_timestamp((hour + 1) * 3600);
+        for n in 5..8 {
+            assert_eq!(contract.store_prediction(day(n), B32::repeat_byte(0x01)), Err(b"Paused".to_vec()));
+        }
+        assert_eq!(contract.unpause(), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.unpause().unwrap();
+        assert!(!contract.is_paused());
+        
+        // The new hour counts from zero
+        vm.set_sender(user);
+        for n in 5..8 {
+            contract.store_prediction(day(n), B32::repeat_byte(0x01)).unwrap();
+        }
+        assert!(!contract.is_paused());
+        assert_eq!(contract.get_writes_in_hour(U256::from(hour + 1)), U256::from(3));
+        
+        // Zero turns the breaker off
+        vm.set_sender(owner);
+        contract.set_auto_pause_threshold(0).unwrap();
+        vm.set_sender(user);
+        contract.store_prediction(day(8), B32::repeat_byte(0x01)).unwrap();
+        assert!(!contract.is_paused());
+    }
+    
+    #[test]
+    fn test_rating_dimensions() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        let humor = keccak(b"humor");
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        
+        // Dimension 0 is pre-registered; others are added at runtime
+        assert_eq!(contract.get_dimension(LEGACY_DIMENSION), keccak(b"rating"));
+        assert_eq!(contract.get_dimension(3), B32::ZERO);
+        assert_eq!(contract.register_dimension(MAX_DIMENSIONS, humor), Err(b"InvalidDimension".to_vec()));
+        assert_eq!(contract.register_dimension(3, B32::ZERO), Err(b"InvalidDimensionName".to_vec()));
+        assert_eq!(contract.register_dimension(LEGACY_DIMENSION, humor), Err(b"DimensionExists".to_vec()));
+        
+        let user = Address::repeat_byte(0x3d);
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        contract.store_prediction(day(0), B32::repeat_byte(0x01)).unwrap();
+        contract.store_prediction(day(1), B32::repeat_byte(0x02)).unwrap();
+        assert_eq!(contract.rate_dimension(day(0), 3, U8::from(4)), Err(b"UnknownDimension".to_vec()));
+        assert_eq!(contract.register_dimension(3, humor), Err(b"NotOwner".to_vec()));
+        
+        vm.set_sender(owner);
+        contract.register_dimension(3, humor).unwrap();
+        assert_eq!(contract.get_dimension(3), humor);
+        assert_eq!(contract.register_dimension(3, keccak(b"wit")), Err(b"DimensionExists".to_vec()));
+        
+        // Humor ratings don't touch the legacy rating or its aggregates
+        vm.set_sender(user);
+        assert_eq!(contract.rate_dimension(day(0), 3, U8::from(6)), Err(b"InvalidRating".to_vec()));
+        assert_eq!(contract.rate_dimension(day(2), 3, U8::from(4)), Err(b"PredictionNotFound".to_vec()));
+        contract.rate_dimension(day(0), 3, U8::from(4)).unwrap();
+        contract.rate_dimension(day(1), 3, U8::from(1)).unwrap();
+        contract.rate_dimension(day(1), 3, U8::from(2)).unwrap();
+        assert_eq!(contract.get_dimension_rating(user, day(0), 3), U8::from(4));
+        assert_eq!(contract.get_dimension_stats(user, 3), (U256::from(2), U256::from(30)));
+        assert_eq!(contract.get_rating(user, day(0)), U8::ZERO);
+        assert_eq!(contract.get_dimension_stats(user, LEGACY_DIMENSION), (U256::ZERO, U256::ZERO));
+        let points = contract.get_points(user);
+        
+        // Dimension 0 is the legacy rating, which leaves humor alone
+        contract.rate_dimension(day(0), LEGACY_DIMENSION, U8::from(5)).unwrap();
+        assert_eq!(contract.get_rating(user, day(0)), U8::from(5));
+        assert_eq!(contract.get_dimension_rating(user, day(0), LEGACY_DIMENSION), U8::from(5));
+        assert_eq!(contract.get_dimension_stats(user, LEGACY_DIMENSION), (U256::from(1), U256::from(50)));
+        assert_eq!(contract.get_user_stats(user).1, U256::from(1));
+        assert!(contract.get_points(user) > points);
+        assert_eq!(contract.get_dimension_stats(user, 3), (U256::from(2), U256::from(30)));
+        
+        // Clearing drops the rating from its own dimension only
+        contract.rate_dimension(day(1), 3, U8::ZERO).unwrap();
+        assert_eq!(contract.get_dimension_stats(user, 3), (U256::from(1), U256::from(40)));
+        assert_eq!(contract.get_dimension_stats(user, LEGACY_DIMENSION), (U256::from(1), U256::from(50)));
+        
+        // Every dimension closes with the rating window: day 0's closed,
+        // day 1's closes at the end of this second
+        vm.set_sender(owner);
+        contract.set_rating_window_days(5).unwrap();
+        vm.set_sender(user);
+        vm.set_block_timestamp(20_006 * DAY);
+        assert_eq!(contract.rate_dimension(day(0), 3, U8::from(2)), Err(b"RatingWindowClosed".to_vec()));
+        assert_eq!(contract.rate_dimension(day(0), 3, U8::ZERO), Err(b"RatingWindowClosed".to_vec()));
+        assert_eq!(contract.rate_dimension(day(0), LEGACY_DIMENSION, U8::from(2)), Err(b"RatingWindowClosed".to_vec()));
+        assert_eq!(contract.get_dimension_rating(user, day(0), 3), U8::from(4));
+        contract.rate_dimension(day(1), 3, U8::from(3)).unwrap();
+        assert_eq!(contract.get_dimension_stats(user, 3), (U256::from(2), U256::from(35)));
+    }
+    
+    #[test]
+    fn test_commit_reveal_rating() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        // Dates here trail the clock by months
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        assert_eq!(contract.get_reveal_timing(), (DEFAULT_REVEAL_DELAY, DEFAULT_REVEAL_WINDOW));
+        assert_eq!(contract.set_reveal_timing(60, 0), Err(b"InvalidRevealWindow".to_vec()));
+        contract.set_reveal_timing(3600, 86_400).unwrap();
+        assert_eq!(contract.get_reveal_timing(), (3600, 86_400));
+        vm.set_block_timestamp(NOW);
+        let date = U256::from(20_000 * DAY);
+        
+        let target = Address::repeat_byte(0x51);
+        let (alice, bob) = (Address::repeat_byte(0x52), Address::repeat_byte(0x53));
+        for (user, byte) in [(target, 0xa1), (alice, 0xa2), (bob, 0xa3)] {
+            vm.set_sender(user);
+            contract.register_user(B32::repeat_byte(byte)).unwrap();
+        }
+        vm.set_sender(target);
+        contract.store_prediction(date, B32::repeat_byte(0x01)).unwrap();
+        assert_eq!(
+            contract.commit_rating(target, date, B32::repeat_byte(0x01)),
+            Err(b"CannotRateOwnPrediction".to_vec())
+        );
+        
+        // Full commit -> reveal
+        vm.set_sender(alice);
+        let salt = B32::repeat_byte(0x5a);
+        let sealed = B32::from(sealed_rating::commitment(4, salt, alice));
+        assert_eq!(contract.commit_rating(target, date, B32::ZERO), Err(b"InvalidCommitment".to_vec()));
+        assert_eq!(contract.commit_rating(target, date + U256::from(DAY), sealed), Err(b"PredictionNotFound".to_vec()));
+        contract.commit_rating(target, date, sealed).unwrap();
+        assert!(contract.has_rating_commitment(alice, target, date));
+        assert_eq!(
+            contract.get_reveal_deadline(alice, target, date),
+            (U256::from(NOW + 3600), U256::from(NOW + 3600 + 86_400))
+        );
+        assert_eq!(contract.commit_rating(target, date, sealed), Err(b"CommitmentPending".to_vec()));
+        
+        // Too early, then wrong salt or rating
+        assert_eq!(contract.reveal_rating(target, date, U8::from(4), salt), Err(b"RevealTooEarly".to_vec()));
+        vm.set_block_timestamp(NOW + 3600);
+        assert_eq!(contract.reveal_rating(target, date, U8::from(4), B32::ZERO), Err(b"InvalidReveal".to_vec()));
+        assert_eq!(contract.reveal_rating(target, date, U8::from(5), salt), Err(b"InvalidReveal".to_vec()));
+        vm.set_sender(bob);
+        assert_eq!(contract.reveal_rating(target, date, U8::from(4), salt), Err(b"NoCommitment".to_vec()));
+        
+        vm.set_sender(alice);
+        contract.reveal_rating(target, date, U8::from(4), salt).unwrap();
+        assert!(!contract.has_rating_commitment(alice, target, date));
+        assert_eq!(contract.get_community_rating(target, date, alice), U8::from(4));
+        assert_eq!(contract.get_community_stats(target, date), (U256::from(1), U256::from(40)));
+        assert_eq!(contract.get_rating(target, date), U8::ZERO);
+        assert_eq!(contract.commit_rating(target, date, sealed), Err(b"AlreadyRated".to_vec()));
+        
+        // Bob never reveals: the commitment expires without effect
+        vm.set_sender(bob);
+        contract.commit_rating(target, date, B32::from(sealed_rating::commitment(1, salt, bob))).unwrap();
+        assert_eq!(contract.expire_rating_commitment(bob, target, date), Err(b"CommitmentNotExpired".to_vec()));
+        vm.set_block_timestamp(NOW + 3600 + 3600 + 86_400);
+        assert!(!contract.has_rating_commitment(bob, target, date));
+        assert_eq!(contract.reveal_rating(target, date, U8::from(1), salt), Err(b"CommitmentExpired".to_vec()));
+        assert_eq!(contract.get_community_stats(target, date), (U256::from(1), U256::from(40)));
+        contract.expire_rating_commitment(bob, target, date).unwrap();
+        assert_eq!(contract.get_reveal_deadline(bob, target, date), (U256::ZERO, U256::ZERO));
+        assert_eq!(contract.expire_rating_commitment(bob, target, date), Err(b"NoCommitment".to_vec()));
+        
+        let events: Vec<_> = vm.get_emitted_logs().into_iter().map(|(topics, _)| topics[0]).collect();
+        let count = |signature| events.iter().filter(|topic| **topic == signature).count();
+        assert_eq!(count(RatingCommitted::SIGNATURE_HASH), 2);
+        assert_eq!(count(RatingRevealed::SIGNATURE_HASH), 1);
+        assert_eq!(count(RatingCommitmentExpired::SIGNATURE_HASH), 1);
+    }
+    
+    /// Answer ArbSys as if `current` were the current block and `anchor`
+    /// had hash `hash`
+    fn mock_arb_sys(vm: &TestVM, current: u64, anchor: u64, hash: B32) {
+        vm.mock_static_call(
+            ARB_SYS,
+            IArbSys::arbBlockNumberCall {}.abi_encode(),
+            Ok(U256::from(current).to_be_bytes::<32>().to_vec()),
+        );
+        let call = IArbSys::arbBlockHashCall { arbBlockNum: U256::from(anchor) };
+        vm.mock_static_call(ARB_SYS, call.abi_encode(), Ok(hash.to_vec()));
+    }
+    
+    fn register_anchored(
+        contract: &mut FarcasterPredictions,
+        proof: &poseidon::ZkProof,
+        anchor_block: u64,
+    ) -> Result<(), Vec<u8>> {
+        let (commitment, proof_bytes, nonce) = calldata(proof);
+        contract.register_user_with_anchored_proof(
+            commitment,
+            proof_bytes,
+            nonce,
+            positions::to_words(&sample_chart()),
+            proof.epoch,
+            U256::from(anchor_block),
+        )
+    }
+    
+    #[test]
+    fn test_anchored_proof_window() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        vm.set_block_timestamp(NOW);
+        
+        let hash = B32::repeat_byte(0x5a);
+        let anchor = BlockAnchor { number: 900, hash: hash.0 };
+        let epoch = poseidon::compute_epoch(NOW);
+        let proof =
+            prover::generate_proof_v2_anchored(&sample_commitment(), "nonce", &sample_chart(), epoch, anchor).unwrap();
+        let stale = |current: u64| {
+            Err(StaleAnchor { anchorBlock: U256::from(900), currentBlock: U256::from(current) }.abi_encode())
+        };
+        
+        // The anchor block itself and earlier: not yet usable
+        for current in [899, 900] {
+            mock_arb_sys(&vm, current, 900, hash);
+            assert_eq!(register_anchored(&mut contract, &proof, 900), stale(current));
+        }
+        
+        // Oldest block still in the window
+        mock_arb_sys(&vm, 1156, 900, hash);
+        assert_eq!(register_anchored(&mut contract, &proof, 900), Ok(()));
+        assert!(contract.is_user_registered(vm.msg_sender()));
+        
+        // One block later the anchor has expired
+        vm.set_sender(Address::repeat_byte(0x22));
+        mock_arb_sys(&vm, 1157, 900, hash);
+        assert_eq!(register_anchored(&mut contract, &proof, 900), stale(1157));
+    }
+    
+    #[test]
+    fn test_anchored_proof_binds_block_hash() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        vm.set_block_timestamp(NOW);
+        
+        let epoch = poseidon::compute_epoch(NOW);
+        let anchor = BlockAnchor { number: 900, hash: [0x5a; 32] };
+        let proof =
+            prover::generate_proof_v2_anchored(&sample_commitment(), "nonce", &sample_chart(), epoch, anchor).unwrap();
+        
+        // Precomputed against a guessed hash
+        mock_arb_sys(&vm, 1000, 900, B32::repeat_byte(0x5b));
+        assert_eq!(register_anchored(&mut contract, &proof, 900), Err(b"InvalidProof".to_vec()));
+        
+        // An unanchored proof doesn't pass as anchored
+        let unanchored = prover::generate_proof_v2(&sample_commitment(), "nonce", &sample_chart(), epoch).unwrap();
+        mock_arb_sys(&vm, 1000, 900, B32::repeat_byte(0x5a));
+        assert_eq!(register_anchored(&mut contract, &unanchored, 900), Err(b"InvalidProof".to_vec()));
+        assert_eq!(register_anchored(&mut contract, &proof, 900), Ok(()));
+    }
+    
+    fn bucketed_user(dob: &str) -> (poseidon::ZkProof, u8, B32) {
+        let birth_data = prover::BirthData {
+            dob: String::from(dob),
+            tob: String::from("08:15"),
+            tz: String::from("UTC"),
+            lat: String::from("40.7128"),
+            lon: String::from("-74.006"),
+        };
+        let (commitment, bucket, opening) = prover::generate_bucketed_commitment(&birth_data, [5u8; 32]).unwrap();
+        let epoch = poseidon::compute_epoch(NOW);
+        let proof = prover::generate_proof_v2_bucketed(&commitment, "nonce", &sample_chart(), epoch, bucket).unwrap();
+        (proof, bucket, B32::from(opening))
+    }
+    
+    fn register_bucketed(
+        contract: &mut FarcasterPredictions,
+        proof: &poseidon::ZkProof,
+        bucket: u8,
+        opening: B32,
+    ) -> Result<(), Vec<u8>> {
+        let (commitment, proof_bytes, nonce) = calldata(proof);
+        contract.register_user_with_bucket(
+            commitment,
+            proof_bytes,
+            nonce,
+            positions::to_words(&sample_chart()),
+            proof.epoch,
+            bucket,
+            opening,
+        )
+    }
+    
+    #[test]
+    fn test_register_with_bucket_claim() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        vm.set_block_timestamp(NOW);
+        
+        let (proof, bucket, opening) = bucketed_user("1998-03-02");
+        assert_eq!(bucket, 9);
+        
+        // Claiming the neighbouring decade doesn't open the commitment
+        assert_eq!(
+            register_bucketed(&mut contract, &proof, bucket + 1, opening),
+            Err(b"InvalidBucketClaim".to_vec())
+        );
+        assert_eq!(register_bucketed(&mut contract, &proof, 13, opening), Err(b"InvalidBucket".to_vec()));
+        
+        assert_eq!(register_bucketed(&mut contract, &proof, bucket, opening), Ok(()));
+        assert_eq!(contract.get_user_bucket(vm.msg_sender()), (true, 9));
+        assert!(contract.is_user_registered(vm.msg_sender()));
+    }
+    
+    #[test]
+    fn test_bucket_rating_aggregates() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        // Dates here trail the clock by months
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        vm.set_block_timestamp(NOW);
+        
+        let day = U256::from(20_000 * DAY);
+        let millennial = Address::repeat_byte(0x31);
+        let gen_z = Address::repeat_byte(0x32);
+        for (user, dob, ratings) in [(millennial, "1991-05-20", [4u8, 2]), (gen_z, "2004-11-09", [5, 3])] {
+            vm.set_sender(user);
+            let (proof, bucket, opening) = bucketed_user(dob);
+            register_bucketed(&mut contract, &proof, bucket, opening).unwrap();
+            for (i, rating) in ratings.iter().enumerate() {
+                let date = day + U256::from(i as u64 * DAY);
+                contract.store_prediction(date, B32::repeat_byte(0x40 + i as u8)).unwrap();
+                contract.rate_prediction(date, U8::from(*rating)).unwrap();
+            }
+        }
+        
+        // Users without a bucket don't count towards any bucket
+        vm.set_sender(Address::repeat_byte(0x33));
+        contract.register_user(B32::repeat_byte(0x99)).unwrap();
+        contract.store_prediction(day, B32::repeat_byte(0x40)).unwrap();
+        contract.rate_prediction(day, U8::from(1)).unwrap();
+        
+        assert_eq!(contract.get_bucket_stats(9), (U256::from(2), U256::from(6)));
+        assert_eq!(contract.get_bucket_stats(10), (U256::from(2), U256::from(8)));
+        
+        // Re-rating moves the sum but not the count
+        vm.set_sender(gen_z);
+        contract.rate_prediction(day, U8::from(1)).unwrap();
+        assert_eq!(contract.get_bucket_stats(10), (U256::from(2), U256::from(4)));
+        assert_eq!(contract.get_bucket_stats(9), (U256::from(2), U256::from(6)));
+    }
+    
+    #[test]
+    fn test_timezone_date_keys() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        let midnight = |y, m, d| calendar::days_from_civil(y, m, d) as u64 * DAY;
+        let key = |contract: &FarcasterPredictions, user, timestamp: u64| {
+            contract.local_date_key(user, U256::from(timestamp)).unwrap()
+        };
+        let (india, california) = (Address::repeat_byte(0x51), Address::repeat_byte(0x52));
+        
+        vm.set_sender(india);
+        contract.set_timezone(330).unwrap();
+        vm.set_sender(california);
+        contract.set_timezone(-480).unwrap();
+        assert_eq!((contract.get_timezone(india), contract.get_timezone(california)), (330, -480));
+        assert_eq!(contract.get_timezone(Address::repeat_byte(0x53)), 0);
+        
+        // 2024-12-31 20:00 UTC is already New Year's Day in India, and
+        // 2025-01-01 05:00 UTC still New Year's Eve in California
+        let new_years_eve = midnight(2024, 12, 31);
+        let new_year = midnight(2025, 1, 1);
+        assert_eq!(key(&contract, india, new_years_eve + 20 * 3600), U256::from(new_year));
+        assert_eq!(key(&contract, india, new_years_eve + 18 * 3600 + 1799), U256::from(new_years_eve));
+        assert_eq!(key(&contract, california, new_year + 5 * 3600), U256::from(new_years_eve));
+        assert_eq!(key(&contract, california, new_year + 8 * 3600), U256::from(new_year));
+        assert_eq!(key(&contract, Address::repeat_byte(0x53), new_year + 5 * 3600), U256::from(new_year));
+        
+        // A fixed offset has no daylight saving time: local midnight is
+        // 08:00 UTC in July as in January
+        for day in [midnight(2024, 7, 1), midnight(2025, 1, 1)] {
+            assert_eq!(key(&contract, california, day + 8 * 3600 - 1), U256::from(day - DAY));
+            assert_eq!(key(&contract, california, day + 8 * 3600), U256::from(day));
+        }
+        
+        assert_eq!(contract.local_date_key(california, U256::from(3600)), Err(b"InvalidDate".to_vec()));
+        assert_eq!(contract.local_date_key(india, U256::MAX), Err(b"InvalidDate".to_vec()));
+        for offset in [845, -855, 20, i16::MIN] {
+            assert_eq!(contract.set_timezone(offset), Err(b"InvalidUtcOffset".to_vec()));
+        }
+        assert_eq!(contract.get_timezone(california), -480);
+    }
+    
+    #[test]
+    fn test_aggregate_opt_out() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        // Dates here trail the clock by months
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        vm.set_block_timestamp(NOW);
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        let user = Address::repeat_byte(0x31);
+        
+        // Opted out before registering: not even counted as a user
+        vm.set_sender(user);
+        assert!(!contract.is_opted_out(user));
+        contract.set_aggregate_opt_out(true).unwrap();
+        assert!(contract.is_opted_out(user));
+        let (proof, bucket, opening) = bucketed_user("1991-05-20");
+        register_bucketed(&mut contract, &proof, bucket, opening).unwrap();
+        contract.store_prediction(day(0), B32::repeat_byte(1)).unwrap();
+        contract.rate_prediction(day(0), U8::from(5)).unwrap();
+        assert_eq!(contract.get_global_stats(), (U256::ZERO, U256::ZERO));
+        assert_eq!(contract.get_bucket_stats(bucket), (U256::ZERO, U256::ZERO));
+        
+        // Opted in: only what follows counts
+        contract.set_aggregate_opt_out(false).unwrap();
+        assert_eq!(contract.get_global_stats(), (U256::ZERO, U256::ZERO));
+        contract.store_prediction(day(1), B32::repeat_byte(2)).unwrap();
+        contract.rate_prediction(day(1), U8::from(4)).unwrap();
+        contract.rate_prediction(day(0), U8::from(2)).unwrap();
+        assert_eq!(contract.get_global_stats(), (U256::ZERO, U256::from(1)));
+        assert_eq!(contract.get_bucket_stats(bucket), (U256::from(2), U256::from(6)));
+        
+        // Opted out again: changes stay out and the 4 and 2 keep counting
+        contract.set_aggregate_opt_out(true).unwrap();
+        contract.store_prediction(day(2), B32::repeat_byte(3)).unwrap();
+        contract.rate_prediction(day(1), U8::from(1)).unwrap();
+        contract.rate_prediction(day(0), U8::ZERO).unwrap();
+        contract.rate_prediction(day(1), U8::from(3)).unwrap();
+        assert_eq!(contract.get_global_stats(), (U256::ZERO, U256::from(1)));
+        assert_eq!(contract.get_bucket_stats(bucket), (U256::from(2), U256::from(6)));
+        
+        // Back in, a change replaces the rating the aggregates held
+        contract.set_aggregate_opt_out(false).unwrap();
+        contract.rate_prediction(day(1), U8::from(5)).unwrap();
+        assert_eq!(contract.get_bucket_stats(bucket), (U256::from(2), U256::from(7)));
+        contract.rate_prediction(day(0), U8::from(3)).unwrap();
+        assert_eq!(contract.get_bucket_stats(bucket), (U256::from(2), U256::from(8)));
+        contract.rate_prediction(day(0), U8::ZERO).unwrap();
+        assert_eq!(contract.get_bucket_stats(bucket), (U256::from(1), U256::from(5)));
+        
+        // Personal stats count everything
+        assert_eq!(contract.get_user_stats(user), (U256::from(3), U256::from(1), U256::from(50)));
+    }
+    
+    #[test]
+    fn test_proposed_predictions() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        // Dates here trail the clock by months
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        vm.set_block_timestamp(NOW);
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        let user = Address::repeat_byte(0x31);
+        let relayer = Address::repeat_byte(0x32);
+        contract.set_relayer(relayer).unwrap();
+        vm.set_sender(user);
+        let (proof, bucket, opening) = bucketed_user("1991-05-20");
+        register_bucketed(&mut contract, &proof, bucket, opening).unwrap();
+        let (_, predictions_before) = contract.get_global_stats();
+        
+        // Disabled until the owner sets a window
+        vm.set_sender(relayer);
+        assert_eq!(
+            contract.propose_prediction_for(user, day(0), B32::repeat_byte(1), B32::ZERO),
+            Err(b"ProposalsDisabled".to_vec())
+        );
+        vm.set_sender(user);
+        assert_eq!(contract.set_challenge_window(3600), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.set_challenge_window(3600).unwrap();
+        assert_eq!(contract.get_challenge_window(), 3600);
+        
+        // Only the relayer or owner proposes, once per date
+        vm.set_sender(user);
+        assert!(contract.propose_prediction_for(user, day(0), B32::repeat_byte(1), B32::ZERO).is_err());
+        vm.set_sender(relayer);
+        for n in 0..4 {
+            contract.propose_prediction_for(user, day(n), B32::repeat_byte(n as u8 + 1), B32::ZERO).unwrap();
+        }
+        assert_eq!(
+            contract.propose_prediction_for(user, day(0), B32::repeat_byte(9), B32::ZERO),
+            Err(b"PredictionPending".to_vec())
+        );
+        assert_eq!(contract.get_pending_prediction(user, day(0)), (B32::repeat_byte(1), U256::from(NOW + 3600)));
+        
+        // Pending predictions are in no stat
+        assert!(!contract.has_prediction(user, day(0)));
+        assert_eq!(contract.get_user_stats(user).0, U256::ZERO);
+        assert_eq!(contract.get_global_stats().1, predictions_before);
+        
+        // Confirmed: stored at once
+        vm.set_sender(user);
+        contract.confirm_prediction(day(0)).unwrap();
+        assert_eq!(contract.get_prediction(user, day(0)), B32::repeat_byte(1));
+        assert_eq!(contract.get_pending_prediction(user, day(0)), (B32::ZERO, U256::ZERO));
+        assert_eq!(contract.confirm_prediction(day(0)), Err(b"NoPendingPrediction".to_vec()));
+        
+        // Rejected: gone, and may be proposed again
+        contract.reject_prediction(day(1)).unwrap();
+        assert!(!contract.has_prediction(user, day(1)));
+        assert_eq!(contract.reject_prediction(day(1)), Err(b"NoPendingPrediction".to_vec()));
+        
+        // Stored directly: the proposal is dropped
+        contract.store_prediction(day(2), B32::repeat_byte(7)).unwrap();
+        assert_eq!(contract.get_prediction(user, day(2)), B32::repeat_byte(7));
+        assert_eq!(contract.get_pending_prediction(user, day(2)), (B32::ZERO, U256::ZERO));
+        
+        // Unchallenged: anyone finalizes once the window passes
+        let anyone = Address::repeat_byte(0x33);
+        vm.set_sender(anyone);
+        assert_eq!(contract.finalize_prediction(user, day(3)), Err(b"ChallengeWindowOpen".to_vec()));
+        vm.set_block_timestamp(NOW + 3600);
+        vm.set_sender(user);
+        assert_eq!(contract.reject_prediction(day(3)), Err(b"ChallengeWindowClosed".to_vec()));
+        vm.set_sender(anyone);
+        contract.finalize_prediction(user, day(3)).unwrap();
+        assert_eq!(contract.get_prediction(user, day(3)), B32::repeat_byte(4));
+        assert_eq!(contract.finalize_prediction(user, day(3)), Err(b"NoPendingPrediction".to_vec()));
+        
+        // A stored date takes no proposal
+        vm.set_sender(relayer);
+        assert_eq!(
+            contract.propose_prediction_for(user, day(0), B32::repeat_byte(9), B32::ZERO),
+            Err(b"PredictionAlreadyExists".to_vec())
+        );
+        contract.propose_prediction_for(user, day(1), B32::repeat_byte(5), B32::ZERO).unwrap();
+        assert_eq!(contract.get_pending_prediction(user, day(1)), (B32::repeat_byte(5), U256::from(NOW + 7200)));
+        
+        assert_eq!(contract.get_user_stats(user).0, U256::from(3));
+        assert_eq!(contract.get_global_stats().1, predictions_before + U256::from(3));
+    }
+    
+    #[test]
+    fn test_finalize_batch() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        contract.set_challenge_window(3600).unwrap();
+        vm.set_block_timestamp(NOW);
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        let user = Address::repeat_byte(0x41);
+        let relayer = Address::repeat_byte(0x42);
+        contract.set_relayer(relayer).unwrap();
+        vm.set_sender(user);
+        let (proof, bucket, opening) = bucketed_user("1991-05-20");
+        register_bucketed(&mut contract, &proof, bucket, opening).unwrap();
+        let queue = |contract: &FarcasterPredictions| {
+            let queue = contract.pending_queues.getter(relayer);
+            (queue.head.get().to::<u64>(), queue.entries.len())
+        };
+        
+        // Five proposals, deadlines 100s apart
+        vm.set_sender(relayer);
+        for n in 0..5 {
+            vm.set_block_timestamp(NOW + n * 100);
+            contract.propose_prediction_for(user, day(n), B32::repeat_byte(n as u8 + 1), B32::ZERO).unwrap();
+        }
+        assert_eq!(queue(&contract), (0, 5));
+        assert!(contract.get_finalizable(relayer, U256::MAX).is_empty());
+        
+        // Settled before the window ends: skipped
+        vm.set_sender(user);
+        contract.confirm_prediction(day(1)).unwrap();
+        contract.reject_prediction(day(2)).unwrap();
+        
+        // Days 0 and 3 are past their deadlines, day 4 isn't
+        vm.set_block_timestamp(NOW + 3950);
+        assert_eq!(contract.get_finalizable(relayer, U256::MAX), vec![(user, day(0)), (user, day(3))]);
+        assert_eq!(contract.get_finalizable(relayer, U256::from(1)), vec![(user, day(0))]);
+        assert!(contract.get_finalizable(Address::repeat_byte(0x43), U256::MAX).is_empty());
+        
+        vm.set_sender(Address::repeat_byte(0x43));
+        let entries = vec![(user, day(0)), (user, day(3)), (user, day(0)), (user, day(1)), (user, day(2)), (user, day(4))];
+        assert_eq!(
+            contract.finalize_batch(entries).unwrap(),
+            vec![
+                finalize::FINALIZED,
+                finalize::FINALIZED,
+                finalize::ALREADY_STORED,
+                finalize::ALREADY_STORED,
+                finalize::NOT_PENDING,
+                finalize::WINDOW_OPEN,
+            ]
+        );
+        assert_eq!(contract.get_prediction(user, day(0)), B32::repeat_byte(1));
+        assert_eq!(contract.get_prediction(user, day(3)), B32::repeat_byte(4));
+        assert!(contract.get_finalizable(relayer, U256::MAX).is_empty());
+        assert_eq!(
+            contract.finalize_batch(vec![(user, day(0)); finalize::MAX_FINALIZE_ENTRIES + 1]),
+            Err(b"TooManyEntries".to_vec())
+        );
+        
+        // The next proposal drops the settled front, up to the pending day 4
+        vm.set_sender(relayer);
+        contract.propose_prediction_for(user, day(5), B32::repeat_byte(6), B32::ZERO).unwrap();
+        assert_eq!(queue(&contract), (4, 6));
+        
+        // Once all are settled the queue starts over
+        vm.set_block_timestamp(NOW + 8000);
+        assert_eq!(contract.get_finalizable(relayer, U256::MAX), vec![(user, day(4)), (user, day(5))]);
+        contract.finalize_batch(vec![(user, day(4)), (user, day(5))]).unwrap();
+        contract.propose_prediction_for(user, day(6), B32::repeat_byte(7), B32::ZERO).unwrap();
+        assert_eq!(queue(&contract), (0, 1));
+        assert_eq!(contract.get_user_stats(user).0, U256::from(5));
+    }
+    
+    #[test]
+    fn test_get_sign_for_longitude() {
+        let vm = TestVM::default();
+        let contract = FarcasterPredictions::from(&vm);
+        
+        assert_eq!(contract.get_sign_for_longitude(U256::from(2999)), Ok(0));
+        assert_eq!(contract.get_sign_for_longitude(U256::from(3000)), Ok(1));
+        assert_eq!(contract.get_sign_for_longitude(U256::from(36000)), Ok(0));
+        assert_eq!(contract.get_sign_for_longitude(U256::from(36001)), Err(b"InvalidLongitude".to_vec()));
+        assert_eq!(contract.get_sign_for_longitude(U256::MAX), Err(b"InvalidLongitude".to_vec()));
+    }
+    
+    #[test]
+    fn test_get_sign_compatibility() {
+        let vm = TestVM::default();
+        let contract = FarcasterPredictions::from(&vm);
+        
+        // Aries and Leo are both fire
+        assert_eq!(contract.get_sign_compatibility(0, 4), Ok(95));
+        assert_eq!(contract.get_sign_compatibility(4, 0), Ok(95));
+        assert_eq!(contract.get_sign_compatibility(0, 12), Err(b"InvalidSign".to_vec()));
+    }
+    
+    #[test]
+    fn test_get_moon_phase() {
+        let vm = TestVM::default();
+        let contract = FarcasterPredictions::from(&vm);
+        
+        // Full moon of 2024-01-25 17:54 UTC
+        let (phase, illumination) = contract.get_moon_phase(U256::from(1_706_205_240u64)).unwrap();
+        assert_eq!(phase, 4);
+        assert!(illumination > U256::from(990));
+        assert_eq!(contract.get_moon_phase(U256::MAX), Err(b"InvalidDate".to_vec()));
+    }
+    
+    #[test]
+    fn test_derive_lucky_number_and_color() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        let date = U256::from(20_000 * DAY);
+        
+        assert_eq!(contract.derive_lucky_number(user, date), Err(b"UserNotRegistered".to_vec()));
+        assert_eq!(contract.derive_lucky_color(user, date), Err(b"UserNotRegistered".to_vec()));
+        
+        // Golden vectors of `lucky`, for any time of the day
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        let evening = date + U256::from(DAY - 1);
+        assert_eq!(contract.derive_lucky_number(user, evening), Ok(U256::from(59)));
+        assert_eq!(contract.derive_lucky_color(user, evening), Ok(1));
+        assert_eq!(contract.derive_lucky_number(user, date + U256::from(DAY)), Ok(U256::from(21)));
+        assert_eq!(contract.derive_lucky_color(user, date + U256::from(DAY)), Ok(11));
+        // Dates past the last day number don't share its numbers
+        assert_eq!(contract.derive_lucky_number(user, U256::MAX), Err(b"InvalidDate".to_vec()));
+        assert_eq!(contract.derive_lucky_color(user, U256::MAX), Err(b"InvalidDate".to_vec()));
+        
+        // The documented formula, through the host's keccak
+        let preimage = [&[0xab; 32][..], &date.to_be_bytes::<32>(), lucky::LUCKY_NUMBER_TAG].concat();
+        let number = U256::from_be_bytes(keccak(preimage).0) % U256::from(100) + U256::from(1);
+        assert_eq!(contract.derive_lucky_number(user, date), Ok(number));
+    }
+    
+    #[test]
+    fn test_get_nakshatra() {
+        let vm = TestVM::default();
+        let contract = FarcasterPredictions::from(&vm);
+        
+        // 64.17° tropical less 24.17° ayanamsa is 40° sidereal, the start of Rohini
+        assert_eq!(contract.get_nakshatra(U256::from(6417), U256::from(2417)), Ok((3, 1)));
+        assert_eq!(contract.get_nakshatra(U256::from(1000), U256::from(2417)), Ok((25, 4)));
+        assert_eq!(contract.get_nakshatra(U256::from(36001), U256::ZERO), Err(b"InvalidLongitude".to_vec()));
+        assert_eq!(contract.get_nakshatra(U256::ZERO, U256::from(36001)), Err(b"InvalidAyanamsa".to_vec()));
+    }
+    
+    #[test]
+    fn test_prediction_requires_published_positions() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        contract.register_user(B32::repeat_byte(0xaa)).unwrap();
+        
+        let oracle = Address::repeat_byte(0xe0);
+        let date = U256::from(20_000 * DAY);
+        let next = date + U256::from(DAY);
+        let has_positions = |date| IEphemerisOracle::hasPositionsCall { date }.abi_encode();
+        vm.mock_static_call(oracle, has_positions(date), Ok(U256::from(1).to_be_bytes::<32>().to_vec()));
+        vm.mock_static_call(oracle, has_positions(next), Ok(U256::ZERO.to_be_bytes::<32>().to_vec()));
+        
+        // No oracle configured: any date goes
+        let unchecked = next + U256::from(DAY);
+        contract.store_prediction(unchecked, B32::repeat_byte(0x01)).unwrap();
+        
+        vm.set_sender(Address::repeat_byte(0x66));
+        assert_eq!(contract.set_ephemeris_oracle(oracle), Err(b"NotOwner".to_vec()));
+        vm.set_sender(contract.owner());
+        contract.set_ephemeris_oracle(oracle).unwrap();
+        assert_eq!(contract.get_ephemeris_oracle(), oracle);
+        
+        assert_eq!(contract.store_prediction(date, B32::repeat_byte(0x02)), Ok(()));
+        assert_eq!(
+            contract.store_prediction(next, B32::repeat_byte(0x03)),
+            Err(b"NoEphemerisForDate".to_vec())
+        );
+        // Dates the oracle doesn't answer for at all
+        assert_eq!(
+            contract.store_prediction(unchecked + U256::from(DAY), B32::repeat_byte(0x04)),
+            Err(b"EphemerisUnavailable".to_vec())
+        );
+        assert_eq!(contract.get_user_stats(contract.owner()).0, U256::from(2));
+    }
+    
+    #[test]
+    fn test_score_transits() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let user = Address::repeat_byte(0x31);
+        let registry = Address::repeat_byte(0xc0);
+        let oracle = Address::repeat_byte(0xe0);
+        let date = U256::from(20_000 * DAY);
+        assert_eq!(contract.score_transits(user, date), Err(b"NoNatalPositions".to_vec()));
+        
+        contract.set_chart_registry(registry).unwrap();
+        let chart_ids = vec![String::from("natal"), String::from("draft")];
+        vm.mock_static_call(
+            registry,
+            IChartRegistry::getUserChartsCall { user }.abi_encode(),
+            Ok(IChartRegistry::getUserChartsCall::abi_encode_returns(&(chart_ids,))),
+        );
+        let chart_positions = |chart_id: &str| IChartRegistry::getChartPositionsCall { chartId: String::from(chart_id) }.abi_encode();
+        // The latest chart has no positions, so the earlier one is used
+        vm.mock_static_call(
+            registry,
+            chart_positions("draft"),
+            Ok(IChartRegistry::getChartPositionsCall::abi_encode_returns(&(false, [B32::ZERO; 2]))),
+        );
+        vm.mock_static_call(
+            registry,
+            chart_positions("natal"),
+            Ok(IChartRegistry::getChartPositionsCall::abi_encode_returns(&(true, ephemeris::tests::packed_day(0)))),
+        );
+        assert_eq!(contract.score_transits(user, date), Err(b"NoEphemerisForDate".to_vec()));
+        
+        contract.set_ephemeris_oracle(oracle).unwrap();
+        let has_positions = |published: bool| IEphemerisOracle::hasPositionsCall::abi_encode_returns(&(published,));
+        vm.mock_static_call(oracle, IEphemerisOracle::hasPositionsCall { date }.abi_encode(), Ok(has_positions(false)));
+        assert_eq!(contract.score_transits(user, date), Err(b"NoEphemerisForDate".to_vec()));
+        
+        // Natal bodies at 31° steps, transits 10° further along: six pairs
+        // fall within 3° of an aspect
+        vm.mock_static_call(oracle, IEphemerisOracle::hasPositionsCall { date }.abi_encode(), Ok(has_positions(true)));
+        vm.mock_static_call(
+            oracle,
+            IEphemerisOracle::getPositionsCall { date }.abi_encode(),
+            Ok(IEphemerisOracle::getPositionsCall::abi_encode_returns(&(ephemeris::tests::packed_day(1000),))),
+        );
+        assert_eq!(contract.score_transits(user, date), Ok((U256::from(13), U256::from(6))));
+        
+        let other = Address::repeat_byte(0x32);
+        vm.mock_static_call(
+            registry,
+            IChartRegistry::getUserChartsCall { user: other }.abi_encode(),
+            Ok(IChartRegistry::getUserChartsCall::abi_encode_returns(&(Vec::<String>::new(),))),
+        );
+        assert_eq!(contract.score_transits(other, date), Err(b"NoNatalPositions".to_vec()));
+        
+        // Charts older than the latest MAX_PAGE_SIZE aren't read, even when
+        // they have positions
+        let chart_ids: Vec<String> = (0..=MAX_PAGE_SIZE).map(|index| format!("chart-{index}")).collect();
+        vm.mock_static_call(
+            registry,
+            IChartRegistry::getUserChartsCall { user: other }.abi_encode(),
+            Ok(IChartRegistry::getUserChartsCall::abi_encode_returns(&(chart_ids.clone(),))),
+        );
+        for chart_id in &chart_ids[1..] {
+            vm.mock_static_call(
+                registry,
+                chart_positions(chart_id),
+                Ok(IChartRegistry::getChartPositionsCall::abi_encode_returns(&(false, [B32::ZERO; 2]))),
+            );
+        }
+        vm.mock_static_call(
+            registry,
+            chart_positions(&chart_ids[0]),
+            Ok(IChartRegistry::getChartPositionsCall::abi_encode_returns(&(true, ephemeris::tests::packed_day(0)))),
+        );
+        assert_eq!(contract.score_transits(other, date), Err(b"NoNatalPositions".to_vec()));
+    }
+    
+    #[test]
+    fn test_sunset_freezes_writes() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        let successor = Address::repeat_byte(0x5c);
+        contract.initialize(owner).unwrap();
+        vm.set_block_timestamp(20_000 * DAY);
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        let date = U256::from(20_000 * DAY);
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        
+        assert_eq!(contract.sunset(successor), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.sunset(successor).unwrap();
+        assert!(contract.is_sunset());
+        assert_eq!(contract.successor(), successor);
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics, vec![Sunset::SIGNATURE_HASH, B32::left_padding_from(successor.as_slice())]);
+        assert_eq!(data, Sunset { timestamp: U256::from(20_000 * DAY), successor }.encode_data());
+        
+        // The owner can't write, configure or undo the sunset
+        let sunset = Err(b"ContractSunset".to_vec());
+        assert_eq!(contract.register_user(B32::repeat_byte(0xac)), sunset);
+        assert_eq!(contract.set_relayer(owner), sunset);
+        assert_eq!(contract.set_min_retention_days(1), sunset);
+        assert_eq!(contract.anchor_daily_root(date, B32::repeat_byte(2), U256::from(1), B32::ZERO), sunset);
+        assert_eq!(contract.sunset(Address::ZERO), sunset);
+        assert_eq!(contract.repair_user_counters(user), sunset);
+        assert_eq!(contract.set_facet(owner, vec![FixedBytes(IFarcasterPredictions::storePredictionCall::SELECTOR)]), sunset);
+        
+        vm.set_sender(user);
+        assert_eq!(contract.store_prediction(date + U256::from(DAY), B32::repeat_byte(2)), sunset);
+        assert_eq!(contract.rate_prediction(date, U8::from(5)), sunset);
+        assert_eq!(contract.prune_predictions(vec![date]), sunset);
+        vm.set_sender(Address::repeat_byte(0xa2));
+        assert_eq!(contract.register_user(B32::repeat_byte(0xad)), sunset);
+        
+        // Reads keep working
+        assert_eq!(contract.successor(), successor);
+        assert_eq!(contract.get_prediction(user, date), B32::repeat_byte(1));
+        assert_eq!(contract.get_global_stats(), (U256::from(1), U256::from(1)));
+    }
+    
+    #[test]
+    fn test_predictions_range() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        contract.initialize(owner).unwrap();
+        vm.set_block_timestamp(20_000 * DAY);
+        vm.set_sender(owner);
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        let date = |y, m, d| U256::from(calendar::days_from_civil(y, m, d) as u64 * DAY);
+        let stored = [date(2024, 2, 29), date(2024, 12, 30), date(2024, 12, 31), date(2025, 1, 1)];
+        for (i, date) in stored.iter().enumerate() {
+            contract.store_prediction(*date, B32::repeat_byte(i as u8 + 1)).unwrap();
+        }
+        // Not at midnight, so not in the bitmap
+        contract.store_prediction(date(2025, 1, 2) + U256::from(5), B32::repeat_byte(9)).unwrap();
+        
+        let range = |contract: &FarcasterPredictions, start, end| contract.get_predictions_range(user, start, end).unwrap();
+        assert_eq!(
+            range(&contract, date(2024, 12, 25), date(2025, 1, 5) + U256::from(DAY - 1)),
+            (stored[1..].to_vec(), vec![B32::repeat_byte(2), B32::repeat_byte(3), B32::repeat_byte(4)])
+        );
+        assert_eq!(range(&contract, date(2024, 2, 28), date(2024, 3, 1)), (vec![stored[0]], vec![B32::repeat_byte(1)]));
+        assert_eq!(range(&contract, date(2025, 1, 1), date(2025, 1, 1)).0, vec![stored[3]]);
+        
+        // Empty days never touch prediction storage: a hash written
+        // without its bit stays invisible
+        contract.test_force_prediction(user, date(2024, 6, 10), B32::repeat_byte(0x66));
+        assert_eq!(range(&contract, date(2024, 6, 1), date(2024, 6, 30)), (vec![], vec![]));
+        
+        assert_eq!(contract.get_predictions_range(user, date(2025, 1, 2), date(2025, 1, 1)), Err(b"InvalidRange".to_vec()));
+        assert_eq!(
+            contract.get_predictions_range(user, date(2024, 1, 1), date(2024, 12, 31) + U256::from(DAY)),
+            Err(b"RangeTooLong".to_vec())
+        );
+        assert_eq!(range(&contract, date(2024, 1, 1), date(2024, 12, 31)).0.len(), 3);
+        assert_eq!(contract.get_predictions_range(user, U256::ZERO, U256::MAX), Err(b"InvalidDate".to_vec()));
+        
+        // Pruning clears the day
+        vm.set_sender(owner);
+        contract.set_min_retention_days(1).unwrap();
+        vm.set_sender(user);
+        vm.set_block_timestamp(21_000 * DAY);
+        contract.prune_predictions(vec![stored[1]]).unwrap();
+        assert_eq!(range(&contract, date(2024, 12, 30), date(2024, 12, 31)).0, vec![stored[2]]);
+        
+        // The audit checks the bitmap against the dates, and repair
+        // rewrites it
+        let all = U256::from(MAX_PAGE_SIZE);
+        assert!(contract.audit_user(user, U256::ZERO, all).0);
+        contract.set_prediction_day(user, stored[3], false);
+        contract.set_prediction_day(user, stored[1], true);
+        assert!(!contract.audit_user(user, U256::ZERO, all).0);
+        vm.set_sender(owner);
+        contract.repair_user_counters(user).unwrap();
+        assert!(contract.audit_user(user, U256::ZERO, all).0);
+        assert_eq!(range(&contract, date(2024, 12, 30), date(2025, 1, 1)).0, stored[2..].to_vec());
+    }
+    
+    #[test]
+    fn test_endorsements() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        contract.initialize(owner).unwrap();
+        let user = Address::repeat_byte(0xa1);
+        let date = U256::from(20_000 * DAY);
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        
+        // Only astrologers the owner approved can endorse
+        let astrologer = |i: u8| Address::repeat_byte(0x50 + i);
+        assert_eq!(contract.set_astrologer(astrologer(0), true), Err(b"NotOwner".to_vec()));
+        vm.set_sender(astrologer(0));
+        assert_eq!(contract.endorse_prediction(user, date, B32::repeat_byte(0xe0)), Err(b"NotAstrologer".to_vec()));
+        vm.set_sender(owner);
+        for i in 0..=MAX_ENDORSEMENTS as u8 {
+            contract.set_astrologer(astrologer(i), true).unwrap();
+        }
+        assert!(contract.is_astrologer(astrologer(0)));
+        
+        // Only existing predictions, once per astrologer
+        vm.set_block_timestamp(20_000 * DAY);
+        vm.set_sender(astrologer(0));
+        let other_day = date + U256::from(DAY);
+        assert_eq!(contract.endorse_prediction(user, other_day, B32::ZERO), Err(b"PredictionNotFound".to_vec()));
+        contract.endorse_prediction(user, date, B32::repeat_byte(0xe0)).unwrap();
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics[0], PredictionEndorsed::SIGNATURE_HASH);
+        assert_eq!(topics[3], B32::left_padding_from(astrologer(0).as_slice()));
+        assert_eq!(data, [B32::from(U256::from(20_000)), B32::repeat_byte(0xe0)].concat());
+        assert_eq!(contract.endorse_prediction(user, date, B32::repeat_byte(0xe1)), Err(b"AlreadyEndorsed".to_vec()));
+        
+        // Up to the cap, read back in order
+        for i in 1..MAX_ENDORSEMENTS as u8 {
+            vm.set_block_timestamp(20_000 * DAY + u64::from(i));
+            vm.set_sender(astrologer(i));
+            contract.endorse_prediction(user, date, B32::repeat_byte(0xe0 + i)).unwrap();
+        }
+        vm.set_sender(astrologer(MAX_ENDORSEMENTS as u8));
+        assert_eq!(contract.endorse_prediction(user, date, B32::ZERO), Err(b"TooManyEndorsements".to_vec()));
+        
+        let (astrologers, notes, timestamps) = contract.get_endorsements(user, date);
+        let expected: Vec<u8> = (0..MAX_ENDORSEMENTS as u8).collect();
+        assert_eq!(astrologers, expected.iter().map(|&i| astrologer(i)).collect::<Vec<_>>());
+        assert_eq!(notes, expected.iter().map(|&i| B32::repeat_byte(0xe0 + i)).collect::<Vec<_>>());
+        assert_eq!(timestamps, expected.iter().map(|&i| U256::from(20_000 * DAY + u64::from(i))).collect::<Vec<_>>());
+        
+        // Revoking stops new endorsements and keeps the past ones
+        vm.set_sender(owner);
+        contract.set_astrologer(astrologer(0), false).unwrap();
+        vm.set_sender(user);
+        contract.store_prediction(other_day, B32::repeat_byte(2)).unwrap();
+        vm.set_sender(astrologer(0));
+        assert_eq!(contract.endorse_prediction(user, other_day, B32::ZERO), Err(b"NotAstrologer".to_vec()));
+        assert_eq!(contract.get_endorsements(user, date).0.len(), MAX_ENDORSEMENTS);
+        assert_eq!(contract.get_endorsements(user, other_day), (vec![], vec![], vec![]));
+    }
+    
+    #[test]
+    fn test_daily_accumulator_replays_events() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        contract.set_min_retention_days(30).unwrap();
+        let date = U256::from(20_000 * DAY);
+        assert_eq!(contract.get_daily_accumulator(date), (B32::ZERO, U256::ZERO));
+        
+        for byte in [0xa1, 0xa2, 0xa3] {
+            vm.set_sender(Address::repeat_byte(byte));
+            contract.register_user(B32::repeat_byte(byte)).unwrap();
+            contract.store_prediction(date, B32::repeat_byte(byte)).unwrap();
+        }
+        // Another day's predictions don't enter this one's
+        contract.store_prediction(date + U256::from(DAY), B32::repeat_byte(0xff)).unwrap();
+        
+        // What an indexer would see: the date's PredictionStored logs, in order
+        let stored: Vec<(B32, B32)> = vm
+            .get_emitted_logs()
+            .into_iter()
+            .filter(|(topics, _)| topics[0] == PredictionStored::SIGNATURE_HASH && topics[2] == B32::from(date))
+            .map(|(topics, data)| {
+                // The day number word comes first, then the hash
+                assert_eq!(U256::from_be_slice(&data[..32]), U256::from(calendar::to_day_number(date).unwrap()));
+                (topics[1], B32::from_slice(&data[32..]))
+            })
+            .collect();
+        assert_eq!(stored.len(), 3);
+        
+        // keccak256(previous || user || prediction_hash), recomputed by hand
+        let replay = |stored: &[(B32, B32)]| {
+            stored.iter().fold(B32::ZERO, |previous, (user, hash)| {
+                keccak([previous.as_slice(), &user[12..], hash.as_slice()].concat())
+            })
+        };
+        let (accumulator, count) = contract.get_daily_accumulator(date);
+        assert_eq!(count, U256::from(3));
+        assert_eq!(accumulator, replay(&stored));
+        
+        // A dropped or reordered event gives another hash
+        assert_ne!(accumulator, replay(&[stored[0], stored[2]]));
+        assert_ne!(accumulator, replay(&[stored[1], stored[0], stored[2]]));
+        
+        // Pruning doesn't rewrite history
+        vm.set_block_timestamp(20_060 * DAY);
+        vm.set_sender(Address::repeat_byte(0xa2));
+        contract.prune_predictions(vec![date]).unwrap();
+        assert_eq!(contract.get_daily_accumulator(date), (accumulator, count));
+    }
+    
+    #[test]
+    fn test_rating_attestation() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        let user = vm.msg_sender();
+        let date = U256::from(20_000 * DAY);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        contract.store_prediction(date, B32::repeat_byte(0x01)).unwrap();
+        contract.rate_prediction(date, U8::from(4)).unwrap();
+        vm.set_chain_id(42_161);
+        vm.set_block_number(1_234);
+        
+        let (rating, exists, block_number, hash) = contract.get_rating_attestation(user, date);
+        assert_eq!((rating, exists, block_number), (4, true, U256::from(1_234)));
+        let contract_address = vm.contract_address();
+        let attest = |chain_id, contract_address, user, date, rating, block_number| {
+            B32::from(attestation::rating_attestation(chain_id, contract_address, user, date, rating, block_number))
+        };
+        assert_eq!(hash, attest(42_161, contract_address, user, date, 4, 1_234));
+        
+        // Any altered field gives another hash
+        let other = Address::repeat_byte(0x77);
+        for altered in [
+            attest(1, contract_address, user, date, 4, 1_234),
+            attest(42_161, other, user, date, 4, 1_234),
+            attest(42_161, contract_address, other, date, 4, 1_234),
+            attest(42_161, contract_address, user, date + U256::from(DAY), 4, 1_234),
+            attest(42_161, contract_address, user, date, 5, 1_234),
+            attest(42_161, contract_address, user, date, 4, 1_235),
+        ] {
+            assert_ne!(hash, altered);
+        }
+        
+        // The attestation follows the block
+        vm.set_block_number(1_235);
+        let (_, _, block_number, later) = contract.get_rating_attestation(user, date);
+        assert_eq!((block_number, later), (U256::from(1_235), attest(42_161, contract_address, user, date, 4, 1_235)));
+        
+        let unrated = contract.get_rating_attestation(user, date + U256::from(DAY));
+        assert_eq!((unrated.0, unrated.1), (0, false));
+        assert_eq!(unrated.3, attest(42_161, contract_address, user, date + U256::from(DAY), 0, 1_235));
+    }
+    
+    #[test]
+    fn test_solar_return_window() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        
+        let user = Address::repeat_byte(0x31);
+        let registry = Address::repeat_byte(0xc0);
+        let oracle = Address::repeat_byte(0xe0);
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        let orb = 100;
+        assert_eq!(contract.is_solar_return_window(user, day(0), orb), Err(b"NoNatalPositions".to_vec()));
+        
+        // Natal Sun at 359.50°
+        contract.set_chart_registry(registry).unwrap();
+        vm.mock_static_call(
+            registry,
+            IChartRegistry::getUserChartsCall { user }.abi_encode(),
+            Ok(IChartRegistry::getUserChartsCall::abi_encode_returns(&(vec![String::from("natal")],))),
+        );
+        vm.mock_static_call(
+            registry,
+            IChartRegistry::getChartPositionsCall { chartId: String::from("natal") }.abi_encode(),
+            Ok(IChartRegistry::getChartPositionsCall::abi_encode_returns(&(true, ephemeris::tests::packed_day(35_950)))),
+        );
+        assert_eq!(contract.is_solar_return_window(user, day(0), orb), Err(b"NoEphemerisForDate".to_vec()));
+        
+        // Transiting Sun across 0°: exactly 1° away, just inside, just outside
+        contract.set_ephemeris_oracle(oracle).unwrap();
+        for (n, sun) in [(0, 50), (1, 49), (2, 51), (3, 35_900), (4, 35_849)] {
+            vm.mock_static_call(
+                oracle,
+                IEphemerisOracle::hasPositionsCall { date: day(n) }.abi_encode(),
+                Ok(IEphemerisOracle::hasPositionsCall::abi_encode_returns(&(true,))),
+            );
+            vm.mock_static_call(
+                oracle,
+                IEphemerisOracle::getPositionsCall { date: day(n) }.abi_encode(),
+                Ok(IEphemerisOracle::getPositionsCall::abi_encode_returns(&(ephemeris::tests::packed_day(sun),))),
+            );
+        }
+        let window: Vec<bool> = (0..5).map(|n| contract.is_solar_return_window(user, day(n), orb).unwrap()).collect();
+        assert_eq!(window, [true, true, false, true, false]);
+        assert_eq!(contract.is_solar_return_window(user, day(2), orb + 1), Ok(true));
+    }
+    
+    #[test]
+    fn test_reentrancy_guard() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        let user = vm.msg_sender();
+        let date = U256::from(20_000 * DAY);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        // A hook calling back in runs as a new call on the same storage,
+        // while the store that called it holds the lock
+        let reentrancy = Err(b"Reentrancy".to_vec());
+        contract
+            .non_reentrant(|contract| {
+                contract.store_prediction_for(user, date, B32::repeat_byte(1), B32::ZERO)?;
+                let mut hook = FarcasterPredictions::from(&vm);
+                assert_eq!(hook.store_prediction(date + U256::from(DAY), B32::repeat_byte(2)), reentrancy);
+                assert_eq!(hook.claim_anchored_prediction(date, B32::repeat_byte(3), vec![]), reentrancy);
+                assert_eq!(hook.multicall(vec![]).map(|_| ()), reentrancy);
+                Ok(())
+            })
+            .unwrap();
+        
+        // Only the outer store happened, and the lock is released
+        assert!(!contract.reentrancy_locked.get());
+        assert_eq!(contract.get_prediction(user, date), B32::repeat_byte(1));
+        assert!(!contract.has_prediction(user, date + U256::from(DAY)));
+        assert_eq!(contract.get_user_stats(user).0, U256::from(1));
+        
+        // Multicall releases its lock for the facet methods it delegates,
+        // which take it themselves, and holds it again afterwards
+        contract
+            .non_reentrant(|contract| {
+                for n in 1..=2u64 {
+                    let date = date + U256::from(n * DAY);
+                    contract.released(|contract| contract.store_prediction(date, B32::repeat_byte(2)))?;
+                    assert!(contract.reentrancy_locked.get());
+                }
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(contract.get_user_stats(user).0, U256::from(3));
+        assert!(!contract.reentrancy_locked.get());
+        
+        // A failing guarded call releases it too
+        assert_eq!(contract.store_prediction(date, B32::repeat_byte(4)), Err(b"PredictionAlreadyExists".to_vec()));
+        assert!(!contract.reentrancy_locked.get());
+        contract.store_prediction(date + U256::from(3 * DAY), B32::repeat_byte(4)).unwrap();
+    }
+    
+    #[test]
+    fn test_daily_seed() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        let oracle = Address::repeat_byte(0xe0);
+        let date = U256::from(20_000 * DAY);
+        let not_yet = Err(b"SeedNotAvailable".to_vec());
+        assert_eq!(contract.get_daily_seed(date), not_yet);
+        
+        // Configured but not yet published
+        contract.set_ephemeris_oracle(oracle).unwrap();
+        let has_positions = |published: bool| {
+            vm.mock_static_call(
+                oracle,
+                IEphemerisOracle::hasPositionsCall { date }.abi_encode(),
+                Ok(IEphemerisOracle::hasPositionsCall::abi_encode_returns(&(published,))),
+            );
+        };
+        has_positions(false);
+        assert_eq!(contract.get_daily_seed(date), not_yet);
+        
+        let packed = ephemeris::tests::packed_day(12_345);
+        has_positions(true);
+        vm.mock_static_call(
+            oracle,
+            IEphemerisOracle::getPositionsCall { date }.abi_encode(),
+            Ok(IEphemerisOracle::getPositionsCall::abi_encode_returns(&(packed,))),
+        );
+        let seed = contract.get_daily_seed(date).unwrap();
+        let contract_address = vm.contract_address();
+        let preimage = [packed[0].as_slice(), packed[1].as_slice(), &date.to_be_bytes::<32>(), contract_address.as_slice()];
+        assert_eq!(seed, keccak(preimage.concat()));
+        assert_eq!(contract.get_daily_seed(date), Ok(seed));
+        
+        // 78 tarot cards: picks differ per user under one seed
+        let cards = U256::from(78);
+        let picks: Vec<U256> = (1..=4)
+            .map(|byte| contract.pick_from_seed(seed, Address::repeat_byte(byte), cards).unwrap())
+            .collect();
+        assert!(picks.iter().all(|&pick| pick < cards));
+        assert!(picks.windows(2).any(|pair| pair[0] != pair[1]));
+        assert_eq!(contract.pick_from_seed(seed, Address::repeat_byte(1), cards), Ok(picks[0]));
+        assert_eq!(contract.pick_from_seed(seed, Address::repeat_byte(1), U256::ZERO), Err(b"InvalidModulus".to_vec()));
+    }
+    
+    /// Run `f` as a transaction, discarding its storage writes if it fails
+    /// the way a revert would (TestVM never rolls back by itself)
+    fn transact<T>(vm: &TestVM, f: impl FnOnce() -> Result<T, Vec<u8>>) -> Result<T, Vec<u8>> {
+        let storage = vm.snapshot().storage;
+        let result = f();
+        if result.is_err() {
+            vm.clear_storage();
+            for (key, value) in storage {
+                vm.set_storage(key, value);
+            }
+        }
+        result
+    }
+    
+    #[test]
+    fn test_multicall_routes_and_delegates() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let facet = Address::repeat_byte(0xfa);
+        contract.initialize(vm.msg_sender()).unwrap();
+        let store_selector = FixedBytes(IFarcasterPredictions::storePredictionCall::SELECTOR);
+        contract.set_facet(facet, vec![store_selector]).unwrap();
+        vm.set_block_timestamp(vm.block_timestamp() + FACET_DELAY);
+        
+        let store = IFarcasterPredictions::storePredictionCall { date: U256::from(20_000 * DAY), predictionHash: B32::repeat_byte(1) };
+        vm.mock_delegate_call(facet, store.abi_encode(), Ok(b"stored".to_vec()));
+        let calls = vec![
+            store.abi_encode().into(),
+            IFarcasterPredictions::getFacetCall { selector: store_selector }.abi_encode().into(),
+        ];
+        let results = contract.multicall(calls).unwrap();
+        
+        // The facet gets the call as sent and its output is passed on; the
+        // core's own methods are routed internally
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0], Bytes::from(b"stored".to_vec()));
+        assert_eq!(IFarcasterPredictions::getFacetCall::abi_decode_returns(&results[1], true).unwrap()._0, facet);
+        assert!(!contract.reentrancy_locked.get());
+    }
+    
+    #[test]
+    fn test_multicall_reverts_whole_batch() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let facet = Address::repeat_byte(0xfa);
+        contract.initialize(vm.msg_sender()).unwrap();
+        let register_selector = FixedBytes(IFarcasterPredictions::registerUserCall::SELECTOR);
+        let store = IFarcasterPredictions::storePredictionCall { date: U256::from(20_000 * DAY), predictionHash: B32::ZERO };
+        contract.set_facet(facet, vec![FixedBytes(IFarcasterPredictions::storePredictionCall::SELECTOR)]).unwrap();
+        vm.set_block_timestamp(vm.block_timestamp() + FACET_DELAY);
+        vm.mock_delegate_call(facet, store.abi_encode(), Err(b"InvalidPredictionHash".to_vec()));
+        
+        // A facet's revert fails the batch with its data, undoing the calls
+        // before it
+        let calls = vec![
+            IFarcasterPredictions::setFacetCall { facet, selectors: vec![register_selector] }.abi_encode().into(),
+            store.abi_encode().into(),
+        ];
+        assert_eq!(transact(&vm, || contract.multicall(calls)), Err(b"InvalidPredictionHash".to_vec()));
+        assert_eq!(contract.get_pending_facet(register_selector), (Address::ZERO, 0));
+        
+        // Unknown or truncated selectors fail the batch too, as do those
+        // without a facet
+        assert_eq!(contract.multicall(vec![Bytes::from(vec![0xde, 0xad])]), Err(b"UnknownSelector".to_vec()));
+        assert_eq!(
+            contract.multicall(vec![Bytes::from(vec![0xde, 0xad, 0xbe, 0xef])]),
+            Err(b"UnknownSelector".to_vec())
+        );
+        let register = IFarcasterPredictions::registerUserCall { commitment: B32::repeat_byte(0xab) };
+        assert_eq!(contract.multicall(vec![register.abi_encode().into()]), Err(b"UnknownSelector".to_vec()));
+        assert_eq!(contract.multicall(vec![]), Ok(vec![]));
+    }
+    
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_multicall_not_payable() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        vm.set_value(U256::from(1));
+        
+        let initialize = IFarcasterPredictions::initializeCall { owner: vm.msg_sender() };
+        assert!(contract.multicall(vec![initialize.abi_encode().into()]).is_err());
+        assert_eq!(contract.owner(), Address::ZERO);
+    }
+    
+    #[test]
+    fn test_multicall_forwards_sender_to_facets() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let forwarder = Address::repeat_byte(0xf0);
+        let user = Address::repeat_byte(0xa1);
+        let facet = Address::repeat_byte(0xfa);
+        contract.initialize(owner).unwrap();
+        vm.set_sender(owner);
+        contract.set_trusted_forwarder(forwarder).unwrap();
+        contract.set_facet(facet, vec![FixedBytes(IFarcasterPredictions::storePredictionCall::SELECTOR)]).unwrap();
+        vm.set_block_timestamp(vm.block_timestamp() + FACET_DELAY);
+        
+        // Delegated calls of a forwarded batch get the sender appended, so
+        // the facet resolves it as the core did
+        let store = IFarcasterPredictions::storePredictionCall { date: U256::from(20_000 * DAY), predictionHash: B32::repeat_byte(1) };
+        vm.mock_delegate_call(facet, forwarded(store.clone(), user), Ok(b"for user".to_vec()));
+        vm.set_sender(forwarder);
+        let batch = IFarcasterPredictions::multicallCall { calls: vec![store.abi_encode().into()] };
+        let output = forwarder::route(&mut contract, &forwarded(batch.clone(), user)).unwrap();
+        let results = IFarcasterPredictions::multicallCall::abi_decode_returns(&output, true).unwrap().results;
+        assert_eq!(results[0].as_ref(), b"for user");
+        
+        // Those of a direct batch are delegated as they are
+        vm.mock_delegate_call(facet, store.abi_encode(), Ok(b"direct".to_vec()));
+        vm.set_sender(user);
+        let output = forwarder::route(&mut contract, &batch.abi_encode()).unwrap();
+        let results = IFarcasterPredictions::multicallCall::abi_decode_returns(&output, true).unwrap().results;
+        assert_eq!(results[0].as_ref(), b"direct");
+    }
+    
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_sdk_selectors_route() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = vm.msg_sender();
+        let facet = Address::repeat_byte(0xfa);
+        contract.initialize(owner).unwrap();
+        
+        // Empty input reaches the decoder or the function, never "no route",
+        // for every function of the SDK's interface the core implements;
+        // the others reach the facet set for them
+        let selectors = IFarcasterPredictions::IFarcasterPredictionsCalls::SELECTORS;
+        contract.set_facet(facet, selectors.iter().map(|selector| FixedBytes(*selector)).collect()).unwrap();
+        vm.set_block_timestamp(vm.block_timestamp() + FACET_DELAY);
+        let core = [
+            IFarcasterPredictions::initializeCall::SELECTOR,
+            IFarcasterPredictions::multicallCall::SELECTOR,
+            IFarcasterPredictions::setFacetCall::SELECTOR,
+            IFarcasterPredictions::getFacetCall::SELECTOR,
+            IFarcasterPredictions::getPendingFacetCall::SELECTOR,
+        ];
+        for selector in selectors {
+            let routed =
+                <FarcasterPredictions as Router<FarcasterPredictions>>::route(&mut contract, u32::from_be_bytes(*selector), &[]);
+            assert_eq!(routed.is_some(), core.contains(selector), "{selector:02x?}");
+            if routed.is_none() {
+                vm.mock_delegate_call(facet, selector.to_vec(), Ok(b"facet".to_vec()));
+                assert_eq!(forwarder::route(&mut contract, selector), Ok(b"facet".to_vec()), "{selector:02x?}");
+            }
+        }
+    }
+    
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_hooks_route_only_with_feature() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        
+        for signature in [
+            "testSetTotalPredictions(address,uint256)",
+            "testSetTotalRatings(address,uint256)",
+            "testSetRatingSum(address,uint256)",
+            "testForcePrediction(address,uint256,bytes32)",
+            "testForceRating(address,uint256,uint8)",
+        ] {
+            let selector = u32::from_be_bytes(keccak(signature)[..4].try_into().unwrap());
+            let routed = <FarcasterPredictions as Router<FarcasterPredictions>>::route(&mut contract, selector, &[]);
+            assert_eq!(routed.is_some(), cfg!(feature = "test-hooks"), "{signature}");
+        }
+    }
+    
+    #[test]
+    fn test_sdk_calldata_round_trip() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        
+        // The builders' calldata decodes to the arguments the methods take
+        let date = 20_000 * DAY;
+        let register = IFarcasterPredictions::registerUserCall::abi_decode(&sdk::encode_register_user(&Commitment([0xab; 32])), true).unwrap();
+        contract.register_user(register.commitment).unwrap();
+        let store = IFarcasterPredictions::storePredictionCall::abi_decode(&sdk::encode_store_prediction(date, [1; 32]), true).unwrap();
+        contract.store_prediction(store.date, store.predictionHash).unwrap();
+        let rate = IFarcasterPredictions::ratePredictionCall::abi_decode(&sdk::encode_rate_prediction(date, 4), true).unwrap();
+        contract.rate_prediction(rate.date, U8::from(rate.rating)).unwrap();
+        let stats = IFarcasterPredictions::getUserStatsCall::abi_decode(&sdk::encode_get_user_stats(user), true).unwrap();
+        let output = IFarcasterPredictions::getUserStatsCall::abi_encode_returns(&contract.get_user_stats(stats.user));
+        
+        assert_eq!(contract.get_user_commitment(user), B32::repeat_byte(0xab));
+        assert_eq!(contract.get_prediction(user, U256::from(date)), B32::repeat_byte(1));
+        assert_eq!(
+            sdk::decode_user_stats(&output).unwrap(),
+            sdk::UserStats {
+                total_predictions: U256::from(1),
+                total_ratings: U256::from(1),
+                average_rating_x10: U256::from(40),
+            }
+        );
+    }
+    
+    #[test]
+    fn test_points_for_engagement() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        contract.initialize(owner).unwrap();
+        assert_eq!(contract.get_point_weights(), DEFAULT_POINT_WEIGHTS);
+        
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        assert_eq!(contract.get_points(user), U256::from(10));
+        
+        // Seven consecutive days: 1 each and 5 for the milestone
+        let day = |i: u64| U256::from((20_000 + i) * DAY);
+        for i in 0..7 {
+            contract.store_prediction(day(i), B32::repeat_byte(1)).unwrap();
+        }
+        assert_eq!(contract.get_points(user), U256::from(10 + 7 + 5));
+        
+        for i in 0..3 {
+            contract.rate_prediction(day(i), U8::from(4)).unwrap();
+        }
+        assert_eq!(contract.get_points(user), U256::from(28));
+        
+        // Updating, clearing and re-rating earn nothing more
+        contract.rate_prediction(day(0), U8::from(5)).unwrap();
+        contract.rate_prediction(day(0), U8::ZERO).unwrap();
+        contract.rate_prediction(day(0), U8::from(3)).unwrap();
+        assert_eq!(contract.get_points(user), U256::from(28));
+        
+        // New weights apply to later activity only
+        vm.set_sender(user);
+        assert_eq!(contract.set_point_weights(10, 3, 2, 5), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.set_point_weights(10, 3, 2, 5).unwrap();
+        assert_eq!(contract.get_point_weights(), (10, 3, 2, 5));
+        vm.set_sender(user);
+        assert_eq!(contract.get_points(user), U256::from(28));
+        contract.store_prediction(day(7), B32::repeat_byte(1)).unwrap();
+        contract.rate_prediction(day(7), U8::from(4)).unwrap();
+        assert_eq!(contract.get_points(user), U256::from(28 + 3 + 2));
+        
+        let credited = vm
+            .get_emitted_logs()
+            .iter()
+            .filter(|(topics, _)| topics[0] == PointsCredited::SIGNATURE_HASH)
+            .count();
+        assert_eq!(credited, 1 + 7 + 1 + 3 + 2);
+    }
+    
+    #[test]
+    fn test_streak_milestones() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        let day = |i: u64| U256::from((20_000 + i) * DAY);
+        let store = |contract: &mut FarcasterPredictions, days: &[u64]| {
+            for i in days {
+                contract.store_prediction(day(*i), B32::repeat_byte(1)).unwrap();
+            }
+        };
+        
+        // A missed day restarts the streak
+        store(&mut contract, &[0, 1, 2, 3, 4, 5, 7, 8, 9, 10, 11, 12]);
+        assert_eq!(contract.get_points(user), U256::from(10 + 12));
+        
+        // Backfilling the gap neither completes nor breaks it
+        store(&mut contract, &[6]);
+        assert_eq!(contract.get_points(user), U256::from(10 + 13));
+        store(&mut contract, &[13]);
+        assert_eq!(contract.get_points(user), U256::from(10 + 14 + 5));
+        
+        // The second milestone is seven days later
+        store(&mut contract, &[14, 15, 16, 17, 18, 19]);
+        assert_eq!(contract.get_points(user), U256::from(10 + 20 + 5));
+        store(&mut contract, &[20]);
+        assert_eq!(contract.get_points(user), U256::from(10 + 21 + 10));
+    }
+    
+    #[test]
+    fn test_monthly_ratings() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        let date = |y, m, d| U256::from(calendar::days_from_civil(y, m, d) as u64 * DAY);
+        let (january, february, march) = (660, 661, 662);
+        
+        // Rated on the first of February, across the month boundary
+        vm.set_block_timestamp(date(2025, 2, 1).to::<u64>() + DAY / 2);
+        for (day, rating) in [(date(2025, 1, 30), 4), (date(2025, 1, 31), 3), (date(2025, 2, 1), 2)] {
+            contract.store_prediction(day, B32::repeat_byte(rating)).unwrap();
+            contract.rate_prediction(day, U8::from(rating)).unwrap();
+        }
+        assert_eq!(contract.get_monthly_rating(user, january), (U256::from(2), U256::from(35)));
+        assert_eq!(contract.get_monthly_rating(user, february), (U256::from(1), U256::from(20)));
+        
+        // Edits in March move the month of the prediction, not March
+        vm.set_block_timestamp(date(2025, 3, 3).to::<u64>());
+        contract.rate_prediction(date(2025, 1, 31), U8::from(5)).unwrap();
+        assert_eq!(contract.get_monthly_rating(user, january), (U256::from(2), U256::from(45)));
+        assert_eq!(contract.get_monthly_rating(user, february), (U256::from(1), U256::from(20)));
+        assert_eq!(contract.get_monthly_rating(user, march), (U256::ZERO, U256::ZERO));
+        contract.rate_prediction(date(2025, 2, 1), U8::ZERO).unwrap();
+        assert_eq!(contract.get_monthly_rating(user, february), (U256::ZERO, U256::ZERO));
+        contract.rate_prediction(date(2025, 2, 1), U8::from(1)).unwrap();
+        assert_eq!(contract.get_monthly_rating(user, february), (U256::from(1), U256::from(10)));
+        
+        // A rating from before monthly aggregates counts once edited, and
+        // clearing it uncounted changes nothing
+        let (legacy, unedited) = (date(2025, 1, 29), date(2025, 1, 28));
+        for day in [legacy, unedited] {
+            contract.test_force_prediction(user, day, B32::repeat_byte(9));
+            contract.test_force_rating(user, day, 1);
+        }
+        contract.rate_prediction(legacy, U8::from(3)).unwrap();
+        contract.rate_prediction(unedited, U8::ZERO).unwrap();
+        assert_eq!(contract.get_monthly_rating(user, january), (U256::from(3), U256::from(40)));
+    }
+    
+    #[test]
+    fn test_unrated_dates() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        let day = |i: u64| U256::from((20_000 + i) * DAY);
+        contract.initialize(owner).unwrap();
+        vm.set_sender(owner);
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        vm.set_block_timestamp(20_010 * DAY);
+        for i in 0..8 {
+            contract.store_prediction(day(i), B32::repeat_byte(i as u8 + 1)).unwrap();
+        }
+        contract.rate_prediction(day(1), U8::from(4)).unwrap();
+        contract.rate_prediction(day(6), U8::from(2)).unwrap();
+        
+        let all = U256::from(MAX_PAGE_SIZE);
+        let unrated = |contract: &FarcasterPredictions, before: U256, offset: u64, limit: U256| {
+            contract.get_unrated_dates(user, before, U256::from(offset), limit)
+        };
+        assert_eq!(unrated(&contract, U256::MAX, 0, all), [day(0), day(2), day(3), day(4), day(5), day(7)]);
+        
+        // Ratings close 5 days after the date: days 0-4 have
+        vm.set_sender(user);
+        assert_eq!(contract.set_rating_window_days(5), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.set_rating_window_days(5).unwrap();
+        assert_eq!(contract.get_rating_window_days(), 5);
+        vm.set_sender(user);
+        assert_eq!(unrated(&contract, U256::MAX, 0, all), [day(5), day(7)]);
+        assert_eq!(unrated(&contract, day(7), 0, all), [day(5)]);
+        assert_eq!(contract.rate_prediction(day(4), U8::from(3)), Err(b"RatingWindowClosed".to_vec()));
+        
+        // Pages count dates looked at, not dates returned
+        assert!(unrated(&contract, U256::MAX, 0, U256::from(5)).is_empty());
+        assert_eq!(unrated(&contract, U256::MAX, 5, U256::from(2)), [day(5)]);
+        assert_eq!(unrated(&contract, U256::MAX, 7, U256::MAX), [day(7)]);
+        assert!(unrated(&contract, U256::MAX, 8, all).is_empty());
+        
+        // Day 5's window closes exactly 5 days after its date
+        vm.set_block_timestamp(20_010 * DAY + 1);
+        assert_eq!(unrated(&contract, U256::MAX, 0, all), [day(7)]);
+        assert_eq!(contract.rate_prediction(day(5), U8::from(3)), Err(b"RatingWindowClosed".to_vec()));
+        contract.rate_prediction(day(7), U8::from(5)).unwrap();
+        assert!(unrated(&contract, U256::MAX, 0, all).is_empty());
+        
+        vm.set_sender(owner);
+        contract.set_rating_window_days(0).unwrap();
+        assert_eq!(unrated(&contract, U256::MAX, 0, all), [day(0), day(2), day(3), day(4), day(5)]);
+    }
+    
+    /// Group operations that answer with the pure Rust results and mock
+    /// each call on the precompiles, so running a verification here
+    /// prepares the test VM for the contract running the same one
+    struct MockPrecompiles<'a>(&'a TestVM);
+    
+    impl G1Ops for MockPrecompiles<'_> {
+        fn ec_add(&self, a: &G1Point, b: &G1Point) -> Option<G1Point> {
+            let sum = commitment::Native.ec_add(a, b)?;
+            let input = [a.to_bytes(), b.to_bytes()].concat();
+            self.0.mock_static_call(commitment::EC_ADD, input, Ok(sum.to_bytes().to_vec()));
+            Some(sum)
+        }
+        
+        fn ec_mul(&self, p: &G1Point, scalar: U256) -> Option<G1Point> {
+            let product = commitment::Native.ec_mul(p, scalar)?;
+            let input = [&p.to_bytes()[..], &scalar.to_be_bytes::<32>()].concat();
+            self.0.mock_static_call(commitment::EC_MUL, input, Ok(product.to_bytes().to_vec()));
+            Some(product)
+        }
+    }
+    
+    #[test]
+    fn test_raters_per_date() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let (alice, bob) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb0));
+        let day = |i: u64| U256::from((20_000 + i) * DAY);
+        contract.initialize(Address::repeat_byte(0x0a)).unwrap();
+        vm.set_block_timestamp(20_003 * DAY);
+        for user in [alice, bob] {
+            vm.set_sender(user);
+            contract.register_user(B32::repeat_byte(0xab)).unwrap();
+            contract.store_prediction(day(0), B32::repeat_byte(1)).unwrap();
+        }
+        assert_eq!(contract.get_raters_for_date(day(0)), U256::ZERO);
+        
+        // First rating, an edit, then a clear and a new rating: one rater
+        vm.set_sender(alice);
+        contract.rate_prediction(day(0), U8::from(4)).unwrap();
+        assert_eq!(contract.get_raters_for_date(day(0)), U256::from(1));
+        contract.rate_prediction(day(0), U8::from(5)).unwrap();
+        assert_eq!(contract.get_raters_for_date(day(0)), U256::from(1));
+        contract.rate_prediction(day(0), U8::ZERO).unwrap();
+        assert_eq!(contract.get_raters_for_date(day(0)), U256::from(1));
+        contract.rate_prediction(day(0), U8::from(3)).unwrap();
+        assert_eq!(contract.get_raters_for_date(day(0)), U256::from(1));
+        
+        vm.set_sender(bob);
+        contract.rate_prediction(day(0), U8::from(2)).unwrap();
+        assert_eq!(contract.get_raters_for_date(day(0)), U256::from(2));
+        
+        // A date key past midnight counts towards its day
+        let noon = day(2) + U256::from(DAY / 2);
+        contract.store_prediction(noon, B32::repeat_byte(2)).unwrap();
+        contract.rate_prediction(noon, U8::from(5)).unwrap();
+        assert_eq!(contract.get_raters_for_date(day(2)), U256::from(1));
+        assert_eq!(contract.get_raters_for_date(noon), U256::from(1));
+        
+        let week: Vec<U256> = [2u64, 0, 1, 0, 0, 0, 0].into_iter().map(U256::from).collect();
+        assert_eq!(contract.get_raters_for_week(day(0)), week);
+        assert_eq!(contract.get_raters_for_week(U256::MAX), vec![U256::ZERO; 7]);
+    }
+    
+    #[test]
+    fn test_raters_per_date_skip_opted_out() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let (alice, bob) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb0));
+        let day = U256::from(20_000 * DAY);
+        contract.initialize(Address::repeat_byte(0x0a)).unwrap();
+        vm.set_block_timestamp(20_001 * DAY);
+        for user in [alice, bob] {
+            vm.set_sender(user);
+            contract.register_user(B32::repeat_byte(0xab)).unwrap();
+            contract.store_prediction(day, B32::repeat_byte(1)).unwrap();
+        }
+        
+        // Alice rates while opted out: points, but no rater
+        vm.set_sender(alice);
+        contract.set_aggregate_opt_out(true).unwrap();
+        let points = contract.get_points(alice);
+        contract.rate_prediction(day, U8::from(4)).unwrap();
+        assert_eq!(contract.get_raters_for_date(day), U256::ZERO);
+        assert!(contract.get_points(alice) > points);
+        
+        // Opting back in doesn't count that first rating, even when edited
+        contract.set_aggregate_opt_out(false).unwrap();
+        contract.rate_prediction(day, U8::from(5)).unwrap();
+        assert_eq!(contract.get_raters_for_date(day), U256::ZERO);
+        
+        vm.set_sender(bob);
+        contract.rate_prediction(day, U8::from(2)).unwrap();
+        assert_eq!(contract.get_raters_for_date(day), U256::from(1));
+    }
+    
+    #[test]
+    fn test_daily_checkpoint() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let (alice, bob) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb0));
+        let day = |i: u64| U256::from((20_000 + i) * DAY);
+        contract.initialize(Address::repeat_byte(0x0a)).unwrap();
+        vm.set_block_timestamp(20_001 * DAY + 60);
+        for user in [alice, bob] {
+            vm.set_sender(user);
+            contract.register_user(B32::repeat_byte(0xab)).unwrap();
+            contract.store_prediction(day(0), B32::repeat_byte(1)).unwrap();
+            contract.store_prediction(day(1), B32::repeat_byte(2)).unwrap();
+        }
+        contract.rate_prediction(day(0), U8::from(4)).unwrap();
+        contract.rate_prediction(day(1), U8::from(2)).unwrap();
+        vm.set_sender(alice);
+        contract.rate_prediction(day(0), U8::from(5)).unwrap();
+        contract.rate_prediction(day(0), U8::from(3)).unwrap();
+        assert_eq!(contract.get_global_rating_stats(), (U256::from(3), U256::from(9)));
+        
+        // Opted out, the totals keep the rating they hold until opted back in
+        contract.set_aggregate_opt_out(true).unwrap();
+        contract.rate_prediction(day(0), U8::ZERO).unwrap();
+        contract.rate_prediction(day(1), U8::from(5)).unwrap();
+        assert_eq!(contract.get_global_rating_stats(), (U256::from(3), U256::from(9)));
+        contract.set_aggregate_opt_out(false).unwrap();
+        contract.rate_prediction(day(0), U8::from(1)).unwrap();
+        assert_eq!(contract.get_global_rating_stats(), (U256::from(3), U256::from(7)));
+        
+        let checkpoints = |vm: &TestVM| -> Vec<_> {
+            vm.get_emitted_logs().into_iter().filter(|(topics, _)| topics[0] == DailyCheckpoint::SIGNATURE_HASH).collect()
+        };
+        let expected = |contract: &FarcasterPredictions, day: u64| {
+            let (total_users, global_predictions) = contract.get_global_stats();
+            let (rating_count, rating_sum) = contract.get_global_rating_stats();
+            let event = DailyCheckpoint {
+                day,
+                totalUsers: total_users,
+                globalPredictions: global_predictions,
+                globalRatingCount: rating_count,
+                globalRatingSum: rating_sum,
+            };
+            (vec![DailyCheckpoint::SIGNATURE_HASH, B32::from(U256::from(day))], event.encode_data())
+        };
+        
+        // Anyone can checkpoint, once a day
+        vm.set_sender(Address::repeat_byte(0xcc));
+        assert_eq!(contract.get_last_checkpoint_day(), 0);
+        contract.checkpoint().unwrap();
+        let first = expected(&contract, 20_001);
+        assert_eq!(contract.get_last_checkpoint_day(), 20_001);
+        assert_eq!(checkpoints(&vm), std::slice::from_ref(&first));
+        assert_eq!(contract.checkpoint(), Err(b"AlreadyCheckpointed".to_vec()));
+        vm.set_block_timestamp(20_002 * DAY - 1);
+        assert_eq!(contract.checkpoint(), Err(b"AlreadyCheckpointed".to_vec()));
+        
+        vm.set_block_timestamp(20_002 * DAY);
+        vm.set_sender(bob);
+        contract.store_prediction(day(2), B32::repeat_byte(3)).unwrap();
+        contract.rate_prediction(day(2), U8::from(5)).unwrap();
+        contract.checkpoint().unwrap();
+        assert_eq!(contract.get_last_checkpoint_day(), 20_002);
+        assert_eq!(checkpoints(&vm), [first, expected(&contract, 20_002)]);
+        assert_eq!(contract.get_global_stats(), (U256::from(2), U256::from(5)));
+        assert_eq!(contract.get_global_rating_stats(), (U256::from(4), U256::from(12)));
+    }
+    
+    #[test]
+    fn test_day_number_index() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = Address::repeat_byte(0xa1);
+        let day = |y, m, d| calendar::days_from_civil(y, m, d) as u32;
+        contract.initialize(Address::repeat_byte(0x0a)).unwrap();
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        // Predictions stored by timestamp are found by day number
+        let leap_day = calendar::to_timestamp(day(2024, 2, 29));
+        vm.set_block_timestamp(leap_day.to::<u64>() + 60);
+        contract.store_prediction(leap_day, B32::repeat_byte(1)).unwrap();
+        let year_end = calendar::to_timestamp(day(2024, 12, 31));
+        vm.set_block_timestamp(year_end.to::<u64>() + DAY + 60);
+        contract.store_prediction(year_end, B32::repeat_byte(2)).unwrap();
+        contract.store_prediction(year_end + U256::from(DAY), B32::repeat_byte(3)).unwrap();
+        
+        assert!(contract.has_prediction_on_day(user, day(2024, 2, 29)));
+        assert!(!contract.has_prediction_on_day(user, day(2024, 2, 28)));
+        assert!(!contract.has_prediction_on_day(user, day(2024, 3, 1)));
+        assert!(contract.has_prediction_on_day(user, day(2024, 12, 31)));
+        assert!(contract.has_prediction_on_day(user, day(2025, 1, 1)));
+        assert!(!contract.has_prediction_on_day(Address::repeat_byte(0xb0), day(2025, 1, 1)));
+        assert!(!contract.has_prediction_on_day(user, u32::MAX));
+        
+        // A range read across the year end converts the days back to the
+        // timestamps they were stored under
+        let (dates, hashes) = contract.get_predictions_range(user, leap_day, year_end + U256::from(DAY + 1)).unwrap();
+        assert_eq!(dates, [leap_day, year_end, year_end + U256::from(DAY)]);
+        assert_eq!(hashes, [B32::repeat_byte(1), B32::repeat_byte(2), B32::repeat_byte(3)]);
+        
+        // A timestamp other than midnight isn't indexed by day
+        let noon = year_end + U256::from(DAY + DAY / 2);
+        contract.store_prediction(noon, B32::repeat_byte(4)).unwrap();
+        assert!(contract.has_prediction(user, noon));
+        assert!(!contract.has_prediction_on_day(user, day(2025, 1, 2)));
+        
+        contract.checkpoint().unwrap();
+        assert_eq!(contract.get_last_checkpoint_day(), u64::from(day(2025, 1, 1)));
+    }
+    
+    #[test]
+    fn test_sponsor_pool() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let users = [0xa1, 0xa2, 0xa3, 0xa4].map(Address::repeat_byte);
+        let fee = U256::from(1_000);
+        let fees = |n: u64| fee * U256::from(n);
+        // The test VM doesn't move the call value, so credit it by hand
+        let receive = |value: U256| vm.set_balance(vm.contract_address(), vm.balance(vm.contract_address()) + value);
+        contract.initialize(owner).unwrap();
+        vm.set_block_timestamp(20_000 * DAY + 60);
+        vm.set_sender(owner);
+        contract.set_registration_fee(fee).unwrap();
+        contract.set_sponsorship_daily_cap(2).unwrap();
+        assert_eq!(contract.get_registration_fee(), fee);
+        assert_eq!(contract.get_sponsorship_daily_cap(), 2);
+        assert_eq!(contract.get_sponsored_slots_today(), U256::ZERO);
+        
+        // Anyone can fund the pool
+        let funder = Address::repeat_byte(0xf0);
+        vm.set_sender(funder);
+        assert_eq!(contract.fund_sponsorship(), Err(b"InvalidAmount".to_vec()));
+        vm.set_value(fees(5));
+        contract.fund_sponsorship().unwrap();
+        receive(fees(5));
+        assert_eq!(contract.get_sponsor_pool(), fees(5));
+        assert_eq!(contract.get_sponsored_slots_today(), U256::from(2));
+        let funded = SponsorshipFunded { funder, amount: fees(5), pool: fees(5) };
+        assert!(vm.get_emitted_logs().contains(&(vec![SponsorshipFunded::SIGNATURE_HASH, funder.into_word()], funded.encode_data())));
+        
+        // Two users register free, which exhausts the daily cap; the pool
+        // pays their fees
+        vm.set_value(U256::ZERO);
+        for (i, user) in users[..2].iter().enumerate() {
+            vm.set_sender(*user);
+            contract.register_user(B32::repeat_byte(0xab)).unwrap();
+            let remaining = fees(4 - i as u64);
+            assert_eq!(contract.get_sponsor_pool(), remaining);
+            let sponsored = RegistrationSponsored { user: *user, amount: fee, remainingPool: remaining };
+            let topics = vec![RegistrationSponsored::SIGNATURE_HASH, user.into_word()];
+            assert!(vm.get_emitted_logs().contains(&(topics, sponsored.encode_data())));
+        }
+        assert_eq!(contract.get_sponsored_slots_today(), U256::ZERO);
+        assert_eq!(contract.get_collected_fees(), fees(2));
+        
+        // The third user must pay the exact fee
+        vm.set_sender(users[2]);
+        let incorrect = |sent: U256| Err(IncorrectFee { expected: fee, sent }.abi_encode());
+        assert_eq!(contract.register_user(B32::repeat_byte(0xab)), incorrect(U256::ZERO));
+        vm.set_value(fee - U256::from(1));
+        assert_eq!(contract.register_user(B32::repeat_byte(0xab)), incorrect(fee - U256::from(1)));
+        vm.set_value(fee);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        receive(fee);
+        assert!(contract.is_user_registered(users[2]));
+        assert_eq!(contract.get_sponsor_pool(), fees(3));
+        assert_eq!(contract.get_collected_fees(), fees(3));
+        
+        // The cap starts over the next day, and only the owner withdraws fees
+        vm.set_block_timestamp(20_001 * DAY);
+        assert_eq!(contract.get_sponsored_slots_today(), U256::from(2));
+        vm.set_value(U256::ZERO);
+        vm.set_sender(users[3]);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        assert_eq!(contract.withdraw_fees(users[3]), Err(b"NotOwner".to_vec()));
+        
+        assert_eq!(contract.get_sponsor_pool(), fees(2));
+        let late_funder = Address::repeat_byte(0xf1);
+        vm.set_sender(late_funder);
+        vm.set_value(fees(2));
+        contract.fund_sponsorship().unwrap();
+        receive(fees(2));
+        vm.set_value(U256::ZERO);
+        assert_eq!(contract.get_sponsor_contribution(funder), fees(5));
+        assert_eq!(contract.get_sponsor_contribution(late_funder), fees(2));
+        assert_eq!(contract.reclaim_sponsorship(), Err(b"NotSunset".to_vec()));
+        
+        // Every wei received is either in the pool or withdrawable
+        assert_eq!(vm.balance(vm.contract_address()), contract.get_sponsor_pool() + contract.get_collected_fees());
+        vm.set_sender(owner);
+        assert_eq!(contract.withdraw_fees(Address::ZERO), Err(b"InvalidRecipient".to_vec()));
+        contract.withdraw_fees(owner).unwrap();
+        assert_eq!((contract.get_collected_fees(), vm.balance(owner)), (U256::ZERO, fees(4)));
+        assert_eq!(vm.balance(vm.contract_address()), contract.get_sponsor_pool());
+        
+        // After sunset the owner still gets only fees, and funders take
+        // back the pool pro rata: 4000 wei over 5000 and 2000 contributed
+        contract.sunset(Address::ZERO).unwrap();
+        vm.set_value(fee);
+        assert_eq!(contract.fund_sponsorship(), Err(b"ContractSunset".to_vec()));
+        vm.set_value(U256::ZERO);
+        contract.withdraw_fees(owner).unwrap();
+        assert_eq!((contract.get_sponsor_pool(), vm.balance(owner)), (fees(4), fees(4)));
+        
+        vm.set_sender(Address::repeat_byte(0xf2));
+        assert_eq!(contract.reclaim_sponsorship(), Err(b"NothingToReclaim".to_vec()));
+        vm.set_sender(funder);
+        contract.reclaim_sponsorship().unwrap();
+        assert_eq!(vm.balance(funder), U256::from(2_857));
+        let reclaimed = SponsorshipReclaimed { funder, amount: U256::from(2_857) };
+        let topics = vec![SponsorshipReclaimed::SIGNATURE_HASH, funder.into_word()];
+        assert_eq!(vm.get_emitted_logs().last(), Some(&(topics, reclaimed.encode_data())));
+        assert_eq!(contract.reclaim_sponsorship(), Err(b"NothingToReclaim".to_vec()));
+        vm.set_sender(late_funder);
+        contract.reclaim_sponsorship().unwrap();
+        assert_eq!(vm.balance(late_funder), U256::from(1_143));
+        assert_eq!(contract.get_sponsor_pool(), U256::ZERO);
+        assert_eq!(vm.balance(vm.contract_address()), U256::ZERO);
+    }
+    
+    #[test]
+    fn test_counter_overflow() {
+        let user = Address::repeat_byte(0xa1);
+        let date = U256::from(20_010 * DAY);
+        let overflow = |field| Err(Overflow { field }.abi_encode());
+        let setup = || {
+            let vm = TestVM::default();
+            let mut contract = FarcasterPredictions::from(&vm);
+            contract.initialize(Address::repeat_byte(0x0a)).unwrap();
+            vm.set_block_timestamp(20_010 * DAY);
+            vm.set_sender(user);
+            contract.register_user(B32::repeat_byte(0xab)).unwrap();
+            (vm, contract)
+        };
+        
+        // Rating counters fail instead of wrapping, first and edited ratings
+        let (_vm, mut contract) = setup();
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        contract.total_ratings.setter(user).set(U256::MAX);
+        assert_eq!(contract.rate_prediction(date, U8::from(4)), overflow(math::FIELD_TOTAL_RATINGS));
+        
+        let (_vm, mut contract) = setup();
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        contract.global_ratings.total_ratings.set(U128::MAX);
+        assert_eq!(contract.rate_prediction(date, U8::from(4)), overflow(math::FIELD_GLOBAL_RATINGS));
+        
+        let (_vm, mut contract) = setup();
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        let month = U32::from(rating_month(date).unwrap());
+        contract.monthly_ratings.setter(user).setter(month).total_ratings.set(U128::MAX);
+        assert_eq!(contract.rate_prediction(date, U8::from(4)), overflow(math::FIELD_MONTHLY_RATINGS));
+        
+        let (_vm, mut contract) = setup();
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        contract.rate_prediction(date, U8::from(4)).unwrap();
+        contract.rating_sum.setter(user).set(U256::MAX);
+        assert_eq!(contract.rate_prediction(date, U8::from(5)), overflow(math::FIELD_RATING_SUM));
+        
+        let (_vm, mut contract) = setup();
+        contract.total_predictions.setter(user).set(U256::MAX);
+        assert_eq!(contract.store_prediction(date, B32::repeat_byte(1)), overflow(math::FIELD_TOTAL_PREDICTIONS));
+        
+        // So do the pruned totals
+        let (vm, mut contract) = setup();
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        contract.rate_prediction(date, U8::from(4)).unwrap();
+        vm.set_sender(Address::repeat_byte(0x0a));
+        contract.set_min_retention_days(1).unwrap();
+        vm.set_sender(user);
+        vm.set_block_timestamp(20_011 * DAY);
+        let prune = |vm: &TestVM, contract: &mut FarcasterPredictions| transact(vm, || contract.prune_predictions(vec![date]));
+        contract.pruned_count.setter(user).set(U256::MAX);
+        assert_eq!(prune(&vm, &mut contract), overflow(math::FIELD_PRUNED_COUNT));
+        contract.pruned_count.setter(user).set(U256::ZERO);
+        contract.pruned_ratings.setter(user).set(U256::MAX);
+        assert_eq!(prune(&vm, &mut contract), overflow(math::FIELD_PRUNED_RATINGS));
+        contract.pruned_ratings.setter(user).set(U256::ZERO);
+        contract.pruned_rating_sum.setter(user).set(U256::MAX);
+        assert_eq!(prune(&vm, &mut contract), overflow(math::FIELD_PRUNED_RATING_SUM));
+        assert!(contract.has_prediction(user, date));
+        
+        // The streak and the points balance saturate
+        let (_vm, mut contract) = setup();
+        let mut streak = contract.streaks.setter(user);
+        streak.last_day.set(U64::from(20_009));
+        streak.length.set(U64::MAX);
+        contract.points.setter(user).set(U256::MAX - U256::from(1));
+        contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        let streak = contract.streaks.getter(user);
+        assert_eq!((streak.last_day.get(), streak.length.get()), (U64::from(20_010), U64::MAX));
+        assert_eq!(contract.get_points(user), U256::MAX);
+    }
+    
+    #[test]
+    fn test_pair_predictions() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let (alice, bob, carol) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb0), Address::repeat_byte(0xc0));
+        let (alice_chart, bob_chart) = (keccak(b"alice-natal"), keccak(b"bob-natal"));
+        let key = pair_key(alice_chart, bob_chart);
+        let day = |i: u64| U256::from((20_000 + i) * DAY);
+        contract.initialize(Address::repeat_byte(0x0a)).unwrap();
+        vm.set_block_timestamp(20_001 * DAY);
+        assert_eq!(pair_key(bob_chart, alice_chart), key);
+        
+        // The pair forms once both partners joined with their charts
+        vm.set_sender(alice);
+        assert_eq!(contract.join_pair(alice_chart, alice_chart), Err(b"InvalidPair".to_vec()));
+        contract.join_pair(alice_chart, bob_chart).unwrap();
+        assert_eq!(contract.join_pair(alice_chart, bob_chart), Err(b"AlreadyJoined".to_vec()));
+        assert_eq!(contract.join_pair(bob_chart, alice_chart), Err(b"InvalidPair".to_vec()));
+        assert_eq!(contract.store_pair_prediction(key, day(0), B32::repeat_byte(1)), Err(b"PairNotFormed".to_vec()));
+        vm.set_sender(bob);
+        contract.join_pair(bob_chart, alice_chart).unwrap();
+        let (lower, higher) = if alice_chart < bob_chart { (alice_chart, bob_chart) } else { (bob_chart, alice_chart) };
+        let (owner_a, owner_b) = if alice_chart < bob_chart { (alice, bob) } else { (bob, alice) };
+        assert_eq!(contract.get_pair(key), (lower, owner_a, higher, owner_b));
+        
+        // Either partner stores, once per date; others can't
+        contract.store_pair_prediction(key, day(0), B32::repeat_byte(1)).unwrap();
+        assert_eq!(contract.store_pair_prediction(key, day(0), B32::repeat_byte(2)), Err(b"PredictionAlreadyExists".to_vec()));
+        vm.set_sender(alice);
+        assert_eq!(contract.store_pair_prediction(key, day(1), B32::ZERO), Err(b"InvalidPredictionHash".to_vec()));
+        contract.store_pair_prediction(key, day(1), B32::repeat_byte(2)).unwrap();
+        vm.set_sender(carol);
+        assert_eq!(contract.join_pair(alice_chart, bob_chart), Err(b"AlreadyJoined".to_vec()));
+        assert_eq!(contract.store_pair_prediction(key, day(2), B32::repeat_byte(3)), Err(b"NotPairMember".to_vec()));
+        assert_eq!(contract.rate_pair_prediction(key, day(0), U8::from(5)), Err(b"NotPairMember".to_vec()));
+        assert!(contract.has_pair_prediction(key, day(1)));
+        assert_eq!(contract.get_pair_prediction(key, day(0)), B32::repeat_byte(1));
+        assert!(!contract.has_pair_prediction(key, day(2)));
+        
+        let stored = vm.get_emitted_logs().into_iter().find(|(topics, _)| topics[0] == PairPredictionStored::SIGNATURE_HASH).unwrap();
+        assert_eq!(stored.0, [PairPredictionStored::SIGNATURE_HASH, key, B32::from(day(0)), bob.into_word()]);
+        
+        // Partners rate apart; the average is over those who rated
+        vm.set_sender(alice);
+        assert_eq!(contract.rate_pair_prediction(key, day(2), U8::from(5)), Err(b"PredictionNotFound".to_vec()));
+        assert_eq!(contract.rate_pair_prediction(key, day(0), U8::from(6)), Err(b"InvalidRating".to_vec()));
+        contract.rate_pair_prediction(key, day(0), U8::from(5)).unwrap();
+        vm.set_sender(bob);
+        contract.rate_pair_prediction(key, day(0), U8::from(2)).unwrap();
+        let by_chart = |alice_rating: u8, bob_rating: u8| {
+            if alice_chart < bob_chart { (U8::from(alice_rating), U8::from(bob_rating)) } else { (U8::from(bob_rating), U8::from(alice_rating)) }
+        };
+        let (rating_a, rating_b) = by_chart(5, 2);
+        assert_eq!(contract.get_pair_rating(key, day(0)), (rating_a, rating_b, U256::from(35)));
+        contract.rate_pair_prediction(key, day(1), U8::from(4)).unwrap();
+        assert_eq!(contract.get_pair_stats(key), (U256::from(2), U256::from(3), U256::from(36)));
+        
+        // Edits and clears move the totals, not the prediction count
+        contract.rate_pair_prediction(key, day(0), U8::ZERO).unwrap();
+        let (rating_a, rating_b) = by_chart(5, 0);
+        assert_eq!(contract.get_pair_rating(key, day(0)), (rating_a, rating_b, U256::from(50)));
+        contract.rate_pair_prediction(key, day(1), U8::from(1)).unwrap();
+        assert_eq!(contract.get_pair_stats(key), (U256::from(2), U256::from(2), U256::from(30)));
+        assert_eq!(contract.get_pair_rating(key, day(2)), (U8::ZERO, U8::ZERO, U256::ZERO));
+        
+        let rated = vm.get_emitted_logs().into_iter().rfind(|(topics, _)| topics[0] == PairPredictionRated::SIGNATURE_HASH).unwrap();
+        assert_eq!(rated.0, [PairPredictionRated::SIGNATURE_HASH, key, B32::from(day(1)), bob.into_word()]);
+        assert_eq!(rated.1, PairPredictionRated { pairKey: key, date: day(1), day: calendar::to_day_number(day(1)).unwrap(), partner: bob, rating: 1 }.encode_data());
+        
+        // Pair predictions stay out of the partners' own stats
+        assert_eq!(contract.get_user_stats(alice), (U256::ZERO, U256::ZERO, U256::ZERO));
+        assert_eq!(contract.get_global_stats(), (U256::ZERO, U256::ZERO));
+        
+        // The dates store_prediction rejects are rejected before any write
+        let oracle = Address::repeat_byte(0xe0);
+        vm.set_sender(Address::repeat_byte(0x0a));
+        contract.set_ephemeris_oracle(oracle).unwrap();
+        vm.mock_static_call(oracle, IEphemerisOracle::hasPositionsCall { date: day(2) }.abi_encode(), Ok(U256::ZERO.to_be_bytes::<32>().to_vec()));
+        vm.set_sender(alice);
+        assert_eq!(contract.store_pair_prediction(key, day(2), B32::repeat_byte(3)), Err(b"NoEphemerisForDate".to_vec()));
+        vm.set_sender(Address::repeat_byte(0x0a));
+        contract.set_ephemeris_oracle(Address::ZERO).unwrap();
+        vm.set_sender(alice);
+        assert_eq!(contract.store_pair_prediction(key, U256::MAX, B32::repeat_byte(3)), Err(b"InvalidDate".to_vec()));
+        assert!(!contract.has_pair_prediction(key, day(2)));
+        assert!(!contract.has_pair_prediction(key, U256::MAX));
+        assert_eq!(contract.get_pair_stats(key).0, U256::from(2));
+    }
+    
+    #[test]
+    fn test_blinded_ratings() {
+        use range_proof::RatingContext;
+        
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let (alice, bob) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb0));
+        let date = U256::from(20_000 * DAY);
+        let model = 7;
+        vm.set_block_timestamp(20_000 * DAY);
+        contract.initialize(owner).unwrap();
+        for user in [alice, bob] {
+            vm.set_sender(user);
+            contract.register_user(B32::repeat_byte(0xab)).unwrap();
+            contract.store_prediction(date, B32::repeat_byte(1)).unwrap();
+        }
+        
+        // Mock the precompile calls the contract will make, then rate
+        let rate = |contract: &mut FarcasterPredictions, user, rating: &prover::BlindedRating, model_id| {
+            let context = RatingContext { rater: user, date, model_id };
+            let ops = MockPrecompiles(&vm);
+            if range_proof::verify_with(&ops, &rating.commitment, &rating.proof, &context) {
+                let (count, sum) = contract.get_blinded_aggregate(model_id);
+                let sum = if count.is_zero() { G1Point::INFINITY } else { G1Point::decompress(sum).unwrap() };
+                ops.ec_add(&sum, &rating.commitment);
+            }
+            vm.set_sender(user);
+            let proof = Bytes::from(rating.proof.to_bytes());
+            contract.rate_prediction_blinded(date, model_id, rating.commitment.compress(), proof)
+        };
+        let blinded = |user, rating, secret: u64| {
+            let context = RatingContext { rater: user, date, model_id: model };
+            prover::generate_blinded_rating(rating, U256::from(secret), &context).unwrap()
+        };
+        let (alice_rating, bob_rating) = (blinded(alice, 4, 1), blinded(bob, 5, 2));
+        
+        // A rating of 6 from bits (0, 1, 2) fails the last bit's proof
+        let context = RatingContext { rater: alice, date, model_id: model };
+        let forged = prover::prove_rating_bits([0, 1, 2], U256::from(3), &context);
+        assert_eq!(rate(&mut contract, alice, &forged, model), Err(b"InvalidRangeProof".to_vec()));
+        // Proofs are bound to the rater and model
+        assert_eq!(rate(&mut contract, bob, &alice_rating, model), Err(b"InvalidRangeProof".to_vec()));
+        assert_eq!(rate(&mut contract, alice, &alice_rating, model + 1), Err(b"InvalidRangeProof".to_vec()));
+        assert_eq!(contract.get_blinded_aggregate(model), (U256::ZERO, U256::ZERO));
+        
+        assert_eq!(rate(&mut contract, alice, &alice_rating, model), Ok(()));
+        assert_eq!(rate(&mut contract, alice, &alice_rating, model), Err(b"AlreadyRated".to_vec()));
+        assert_eq!(rate(&mut contract, bob, &bob_rating, model), Ok(()));
+        assert_eq!(contract.get_blinded_rating(alice, date), alice_rating.commitment.compress());
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.last().unwrap().0[0], BlindedRatingAdded::SIGNATURE_HASH);
+        
+        // Only the commitments are stored; the plain stats don't move
+        let aggregate = alice_rating.commitment.add(&bob_rating.commitment);
+        assert_eq!(contract.get_blinded_aggregate(model), (U256::from(2), aggregate.compress()));
+        assert_eq!(contract.get_user_stats(alice).1, U256::ZERO);
+        
+        // The sum opens with the blinding factors added up
+        let blinding_sum = field::add(alice_rating.blinding, bob_rating.blinding);
+        let reveal = |contract: &mut FarcasterPredictions, sum: u64, blinding: U256| {
+            // Only run for its mocks of the precompile calls the reveal
+            // makes to open the aggregate; the contract checks the opening
+            let ops = MockPrecompiles(&vm);
+            commitment::commit_with(&ops, U256::from(sum), blinding).unwrap();
+            contract.reveal_blinded_aggregate(model, U256::from(sum), blinding)
+        };
+        vm.set_sender(alice);
+        assert_eq!(reveal(&mut contract, 9, blinding_sum), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        assert_eq!(reveal(&mut contract, 8, blinding_sum), Err(b"InvalidOpening".to_vec()));
+        assert_eq!(reveal(&mut contract, 9, alice_rating.blinding), Err(b"InvalidOpening".to_vec()));
+        assert_eq!(contract.get_revealed_aggregate(model), (U256::ZERO, U256::ZERO));
+        assert_eq!(reveal(&mut contract, 9, blinding_sum), Ok(()));
+        assert_eq!(contract.get_revealed_aggregate(model), (U256::from(2), U256::from(9)));
+        
+        // The sum is capped at 5 per rating, so the group order can't be
+        // added to it
+        let wrapped = U256::from(9) + commitment::GROUP_ORDER;
+        assert_eq!(
+            contract.reveal_blinded_aggregate(model, wrapped, blinding_sum),
+            Err(b"InvalidOpening".to_vec())
+        );
+    }
+    
+    #[test]
+    fn test_backfill_window() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        contract.initialize(owner).unwrap();
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        let day = |i: u64| U256::from((20_000 + i) * DAY);
+        let streak = |contract: &FarcasterPredictions| {
+            let streak = contract.streaks.getter(user);
+            (streak.last_day.get().to::<u64>(), streak.length.get().to::<u64>())
+        };
+        
+        // Noon on day 10: day 7 is half a day too old, day 8 in the window
+        vm.set_block_timestamp((20_010 * DAY) + DAY / 2);
+        assert_eq!(contract.get_max_backfill_days(), DEFAULT_MAX_BACKFILL_DAYS);
+        assert_eq!(
+            contract.store_prediction(day(7), B32::repeat_byte(1)),
+            Err(DateTooOld { daysLate: U256::from(1) }.abi_encode())
+        );
+        assert_eq!(
+            contract.store_prediction_for_chart(day(2), B32::repeat_byte(1), B32::repeat_byte(0xc1)),
+            Err(DateTooOld { daysLate: U256::from(6) }.abi_encode())
+        );
+        assert_eq!(contract.get_user_stats(user).0, U256::ZERO);
+        assert_eq!(contract.get_global_stats(), (U256::from(1), U256::ZERO));
+        assert_eq!(streak(&contract), (0, 0));
+        assert_eq!(contract.get_points(user), U256::from(10));
+        
+        // Backfills in the window count and extend the streak
+        contract.store_prediction(day(8), B32::repeat_byte(1)).unwrap();
+        contract.store_prediction(day(9), B32::repeat_byte(1)).unwrap();
+        contract.store_prediction(day(10), B32::repeat_byte(1)).unwrap();
+        assert_eq!(contract.get_user_stats(user).0, U256::from(3));
+        assert_eq!(streak(&contract), (20_010, 3));
+        
+        // Relayer proposals are held to the same window
+        vm.set_sender(owner);
+        contract.set_challenge_window(DAY).unwrap();
+        assert_eq!(
+            contract.propose_prediction_for(user, day(5), B32::repeat_byte(2), B32::ZERO),
+            Err(DateTooOld { daysLate: U256::from(3) }.abi_encode())
+        );
+        
+        // An anchored claim for an old date is stored but leaves the
+        // streak alone
+        let leaves = [prediction_leaf(user, day(1), B32::repeat_byte(3)), B32::repeat_byte(0x77)];
+        contract.anchor_daily_root(day(1), merkle::root(&leaves), U256::from(2), B32::ZERO).unwrap();
+        vm.set_sender(user);
+        contract.claim_anchored_prediction(day(1), B32::repeat_byte(3), merkle::proof(&leaves, 0).unwrap()).unwrap();
+        assert_eq!(contract.get_user_stats(user).0, U256::from(4));
+        assert_eq!(streak(&contract), (20_010, 3));
+        
+        // A zero window allows only dates from the current moment on
+        vm.set_sender(user);
+        assert_eq!(contract.set_max_backfill_days(0), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.set_max_backfill_days(0).unwrap();
+        assert_eq!(contract.get_max_backfill_days(), 0);
+        vm.set_sender(user);
+        assert_eq!(
+            contract.store_prediction(day(10) + U256::from(1), B32::repeat_byte(1)),
+            Err(DateTooOld { daysLate: U256::from(1) }.abi_encode())
+        );
+        contract.store_prediction(day(11), B32::repeat_byte(1)).unwrap();
+        assert_eq!(streak(&contract), (20_011, 4));
+    }
+    
+    #[test]
+    fn test_points_not_credited_twice() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        contract.initialize(owner).unwrap();
+        let user = vm.msg_sender();
+        let date = U256::from(20_000 * DAY);
+        let prediction = B32::repeat_byte(0x42);
+        
+        // A prediction stored directly can't be claimed again from its root
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        contract.store_prediction(date, prediction).unwrap();
+        let leaves = [prediction_leaf(user, date, prediction), B32::repeat_byte(0x77)];
+        vm.set_sender(owner);
+        contract.anchor_daily_root(date, merkle::root(&leaves), U256::from(2), B32::ZERO).unwrap();
+        vm.set_sender(user);
+        assert_eq!(
+            contract.claim_anchored_prediction(date, prediction, merkle::proof(&leaves, 0).unwrap()),
+            Err(b"PredictionAlreadyExists".to_vec())
+        );
+        
+        // Nor stored again, nor the user registered again
+        assert_eq!(contract.store_prediction(date, prediction), Err(b"PredictionAlreadyExists".to_vec()));
+        assert_eq!(contract.register_user(B32::repeat_byte(0xcd)), Err(b"UserAlreadyRegistered".to_vec()));
+        assert_eq!(contract.get_points(user), U256::from(10 + 1));
+    }
+    
+    #[test]
+    fn test_deduct_points() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        contract.initialize(owner).unwrap();
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        let reason = B32::repeat_byte(0x99);
+        assert_eq!(contract.deduct_points(user, U256::from(4), reason), Err(b"NotOwner".to_vec()));
+        
+        vm.set_sender(owner);
+        contract.deduct_points(user, U256::from(4), reason).unwrap();
+        assert_eq!(contract.get_points(user), U256::from(6));
+        // At most the balance is deducted, and logged
+        contract.deduct_points(user, U256::from(100), reason).unwrap();
+        assert_eq!(contract.get_points(user), U256::ZERO);
+        
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics, vec![PointsDeducted::SIGNATURE_HASH, B32::left_padding_from(user.as_slice())]);
+        assert_eq!(data, PointsDeducted { user, amount: U256::from(6), reasonHash: reason }.encode_data());
+    }
+    
+    #[test]
+    fn test_export_user_pages_match_getters() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.initialize(user).unwrap();
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        vm.set_block_timestamp(1_754_006_400);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        for i in 0..25u64 {
+            let date = U256::from((20_000 + i) * DAY);
+            contract.store_prediction(date, B32::repeat_byte(i as u8 + 1)).unwrap();
+            if i % 3 == 0 {
+                contract.rate_prediction(date, U8::from(i % 5 + 1)).unwrap();
+            }
+        }
+        
+        let pages: Vec<UserExport> = (0..3)
+            .map(|page| contract.export_user(user, U256::from(page * 10), U256::from(10)))
+            .collect();
+        assert_eq!(pages.iter().map(|page| page.dates.len()).collect::<Vec<_>>(), vec![10, 10, 5]);
+        for page in &pages {
+            assert_eq!(page.commitment, contract.get_user_commitment(user));
+            assert!(page.registered);
+            assert_eq!(page.registeredAt, U256::from(1_754_006_400));
+            let (total_predictions, total_ratings, average) = contract.get_user_stats(user);
+            assert_eq!(page.stats, UserStats { totalPredictions: total_predictions, totalRatings: total_ratings, averageRatingX10: average });
+        }
+        
+        let dates: Vec<U256> = pages.iter().flat_map(|page| page.dates.clone()).collect();
+        let hashes: Vec<B32> = pages.iter().flat_map(|page| page.hashes.clone()).collect();
+        let ratings: Vec<u8> = pages.iter().flat_map(|page| page.ratings.clone()).collect();
+        assert_eq!(dates, (0..25u64).map(|i| U256::from((20_000 + i) * DAY)).collect::<Vec<_>>());
+        for ((date, hash), rating) in dates.iter().zip(&hashes).zip(&ratings) {
+            assert_eq!(*hash, contract.get_prediction(user, *date));
+            assert_eq!(U8::from(*rating), contract.get_rating(user, *date));
+        }
+        
+        // Past the end, and over the page size cap
+        assert!(contract.export_user(user, U256::from(25), U256::from(10)).dates.is_empty());
+        assert!(contract.export_user(user, U256::MAX, U256::MAX).dates.is_empty());
+        assert_eq!(contract.export_user(user, U256::ZERO, U256::MAX).dates.len(), 25);
+        
+        // Decodes as the SDK's interface declares it
+        let output = alloy_sol_types::SolValue::abi_encode(&contract.export_user(user, U256::from(20), U256::from(10)));
+        let export = IFarcasterPredictions::exportUserCall::abi_decode_returns(&output, true).unwrap().export;
+        assert_eq!((export.dates, export.hashes, export.ratings), (pages[2].dates.clone(), pages[2].hashes.clone(), pages[2].ratings.clone()));
+        assert_eq!(export.stats.totalPredictions, U256::from(25));
+        
+        let stranger = contract.export_user(Address::repeat_byte(0x77), U256::ZERO, U256::from(10));
+        assert!(!stranger.registered);
+        assert_eq!(stranger.registeredAt, U256::ZERO);
+        assert!(stranger.dates.is_empty() && stranger.hashes.is_empty() && stranger.ratings.is_empty());
+    }
+    
+    #[test]
+    fn test_export_user_caps_page_size() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        for i in 0..MAX_PAGE_SIZE + 5 {
+            contract.store_prediction(U256::from((20_000 + i) * DAY), B32::repeat_byte(0x01)).unwrap();
+        }
+        
+        let page = contract.export_user(user, U256::ZERO, U256::MAX);
+        assert_eq!(page.dates.len() as u64, MAX_PAGE_SIZE);
+        assert_eq!(contract.export_user(user, U256::from(MAX_PAGE_SIZE), U256::MAX).dates.len(), 5);
+    }
+    
+    #[test]
+    fn test_prune_old_predictions() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        contract.initialize(owner).unwrap();
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        for n in [0, 1, 2, 40, 45] {
+            contract.store_prediction_for_chart(day(n), B32::repeat_byte(n as u8 + 1), keccak(b"natal")).unwrap();
+        }
+        contract.rate_prediction(day(1), U8::from(5)).unwrap();
+        contract.rate_prediction(day(40), U8::from(2)).unwrap();
+        vm.set_block_timestamp((20_000 + 45) * DAY);
+        
+        assert_eq!(contract.prune_predictions(vec![day(0)]), Err(b"PruningDisabled".to_vec()));
+        assert_eq!(contract.set_min_retention_days(30), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.set_min_retention_days(30).unwrap();
+        assert_eq!(contract.get_min_retention_days(), 30);
+        contract.register_dimension(2, keccak(b"humor")).unwrap();
+        vm.set_sender(user);
+        contract.rate_dimension(day(1), 2, U8::from(3)).unwrap();
+        
+        let stats = contract.get_user_stats(user);
+        let global = contract.get_global_stats();
+        
+        // One date inside the horizon rejects the whole batch
+        assert_eq!(
+            transact(&vm, || contract.prune_predictions(vec![day(0), day(1), day(40)])),
+            Err(b"PredictionTooRecent".to_vec())
+        );
+        assert!(contract.has_prediction(user, day(0)) && contract.has_prediction(user, day(1)));
+        assert_eq!(contract.get_pruned_count(user), U256::ZERO);
+        assert_eq!(contract.prune_predictions(vec![day(3)]), Err(b"PredictionNotFound".to_vec()));
+        
+        // Exactly 30 days old is past the horizon
+        vm.set_block_timestamp((20_000 + 32) * DAY);
+        contract.prune_predictions(vec![day(0), day(1), day(2)]).unwrap();
+        for n in [0, 1, 2] {
+            assert!(!contract.has_prediction(user, day(n)));
+            assert!(contract.is_prediction_pruned(user, day(n)));
+            assert_eq!(contract.get_prediction(user, day(n)), B32::ZERO);
+            assert_eq!(contract.get_rating(user, day(n)), U8::ZERO);
+            assert_eq!(contract.get_dimension_rating(user, day(n), 2), U8::ZERO);
+            assert_eq!(contract.get_prediction_meta(user, day(n)), (B32::ZERO, 0, B32::ZERO));
+        }
+        assert!(contract.has_prediction(user, day(40)));
+        assert_eq!(contract.get_prediction_meta(user, day(40)), (B32::repeat_byte(41), 2, keccak(b"natal")));
+        assert_eq!(contract.get_pruned_count(user), U256::from(3));
+        
+        // The rated prediction stays in the rating aggregates
+        assert_eq!(contract.get_user_stats(user), stats);
+        assert_eq!(contract.get_dimension_stats(user, 2), (U256::from(1), U256::from(30)));
+        assert_eq!(contract.get_global_stats(), global);
+        
+        // TestVM keeps the logs of the rejected batch's first two dates
+        let logs: Vec<_> = vm
+            .get_emitted_logs()
+            .into_iter()
+            .filter(|(topics, _)| topics[0] == PredictionPruned::SIGNATURE_HASH)
+            .skip(2)
+            .collect();
+        assert_eq!(logs.len(), 3);
+        assert_eq!(logs[1].0, vec![PredictionPruned::SIGNATURE_HASH, B32::left_padding_from(user.as_slice()), B32::from(day(1))]);
+        assert_eq!(logs[1].1, PredictionPruned { user, date: day(1), day: calendar::to_day_number(day(1)).unwrap(), predictionHash: B32::repeat_byte(2) }.encode_data());
+        
+        // Pruned dates are gone for good, even with backfilling open
+        assert_eq!(contract.prune_predictions(vec![day(1)]), Err(b"PredictionNotFound".to_vec()));
+        assert_eq!(contract.rate_prediction(day(1), U8::from(4)), Err(b"PredictionNotFound".to_vec()));
+        assert_eq!(
+            contract.store_prediction(day(1), B32::repeat_byte(0x09)),
+            Err(DateTooOld { daysLate: U256::from(28) }.abi_encode())
+        );
+        vm.set_sender(owner);
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        vm.set_sender(user);
+        assert_eq!(contract.store_prediction(day(1), B32::repeat_byte(0x09)), Err(b"PredictionAlreadyExists".to_vec()));
+        assert_eq!(contract.get_user_stats(user), stats);
+    }
+    
+    #[test]
+    fn test_community_eligibility() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        contract.initialize(owner).unwrap();
+        assert!(!contract.community_eligible(user));
+        
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        assert!(contract.community_eligible(user));
+        assert_eq!(contract.set_min_predictions_for_community(U256::from(3)), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.set_min_predictions_for_community(U256::from(3)).unwrap();
+        assert_eq!(contract.get_min_predictions_for_community(), U256::from(3));
+        
+        vm.set_sender(user);
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        for n in 0..2 {
+            contract.store_prediction(day(n), B32::repeat_byte(n as u8 + 1)).unwrap();
+        }
+        assert!(!contract.community_eligible(user));
+        assert_eq!(
+            contract.commit_rating(owner, day(0), B32::repeat_byte(0x5e)),
+            Err(b"InsufficientHistory".to_vec())
+        );
+        contract.store_prediction(day(2), B32::repeat_byte(3)).unwrap();
+        assert!(contract.community_eligible(user));
+        
+        // Pruning keeps the count
+        vm.set_sender(owner);
+        contract.set_min_retention_days(1).unwrap();
+        vm.set_sender(user);
+        vm.set_block_timestamp(20_010 * DAY);
+        contract.prune_predictions(vec![day(0), day(1)]).unwrap();
+        assert!(contract.community_eligible(user));
+    }
+    
+    #[test]
+    fn test_audit_and_repair_counters() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        contract.initialize(owner).unwrap();
+        vm.set_block_timestamp(20_000 * DAY);
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        for n in 0..5 {
+            contract.store_prediction(day(n), B32::repeat_byte(n as u8 + 1)).unwrap();
+        }
+        contract.rate_prediction(day(0), U8::from(5)).unwrap();
+        contract.rate_prediction(day(3), U8::from(2)).unwrap();
+        vm.set_sender(owner);
+        contract.set_min_retention_days(1).unwrap();
+        vm.set_sender(user);
+        vm.set_block_timestamp(20_010 * DAY);
+        contract.prune_predictions(vec![day(0), day(1)]).unwrap();
+        
+        // Pruned predictions count on the first page
+        let all = U256::from(MAX_PAGE_SIZE);
+        let expected = (U256::from(5), U256::from(2), U256::from(7));
+        assert_eq!(contract.audit_user(user, U256::ZERO, all), (true, expected.0, expected.1, expected.2));
+        let first = contract.audit_user(user, U256::ZERO, U256::from(3));
+        let rest = contract.audit_user(user, U256::from(3), all);
+        assert_eq!((first, rest), ((true, U256::from(3), U256::from(1), U256::from(5)), (true, U256::from(2), U256::from(1), U256::from(2))));
+        
+        // Corrupt the cached counters
+        contract.test_set_total_ratings(user, U256::from(9));
+        contract.test_set_rating_sum(user, U256::from(1));
+        assert_eq!(contract.audit_user(user, U256::ZERO, all), (false, expected.0, expected.1, expected.2));
+        // A page larger than the cached sum is flagged on its own
+        assert!(!contract.audit_user(user, U256::ZERO, U256::from(3)).0);
+        
+        assert_eq!(contract.repair_user_counters(user), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        let global = contract.get_global_stats();
+        contract.repair_user_counters(user).unwrap();
+        assert!(contract.audit_user(user, U256::ZERO, all).0);
+        assert_eq!(contract.get_user_stats(user), (expected.0, expected.1, U256::from(35)));
+        assert_eq!(contract.get_global_stats(), global);
+        
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics, vec![CountersRepaired::SIGNATURE_HASH, B32::left_padding_from(user.as_slice())]);
+        let repaired = CountersRepaired { user, totalPredictions: expected.0, totalRatings: expected.1, ratingSum: expected.2 };
+        assert_eq!(data, repaired.encode_data());
+        
+        // Without a registration time the date list may be incomplete
+        let legacy = Address::repeat_byte(0xa2);
+        vm.set_block_timestamp(0);
+        vm.set_sender(legacy);
+        contract.register_user(B32::repeat_byte(0xac)).unwrap();
+        vm.set_sender(owner);
+        assert_eq!(contract.repair_user_counters(legacy), Err(b"IncompleteDateHistory".to_vec()));
+        assert_eq!(contract.repair_user_counters(Address::repeat_byte(0xa3)), Err(b"UserNotRegistered".to_vec()));
+    }
+    
+    #[test]
+    fn test_prediction_hook_best_effort() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let user = Address::repeat_byte(0xa1);
+        let hook = Address::repeat_byte(0x40);
+        contract.initialize(owner).unwrap();
+        
+        vm.set_sender(user);
+        assert_eq!(contract.set_prediction_hook(hook), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        contract.set_prediction_hook(hook).unwrap();
+        assert_eq!(contract.get_prediction_hook(), hook);
+        
+        vm.set_sender(user);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        let day = |n: u64| U256::from((20_000 + n) * DAY);
+        let notified = |date: U256, streak: u64| {
+            IPredictionHook::onPredictionStoredCall { user, date, streak: U256::from(streak) }.abi_encode()
+        };
+        // The selector rewards contracts implement
+        assert_eq!(notified(day(0), 1)[..4], keccak(b"onPredictionStored(address,uint256,uint256)")[..4]);
+        let failures = |vm: &TestVM| {
+            vm.get_emitted_logs()
+                .into_iter()
+                .filter(|(topics, _)| topics[0] == HookFailed::SIGNATURE_HASH)
+                .collect::<Vec<_>>()
+        };
+        
+        vm.mock_call(hook, notified(day(0), 1), Ok(vec![]));
+        contract.store_prediction(day(0), B32::repeat_byte(0x01)).unwrap();
+        assert!(failures(&vm).is_empty());
+        
+        // TestVM answers unmocked calls with success, so this revert also
+        // shows the hook got the user, date and current streak
+        vm.mock_call(hook, notified(day(1), 2), Err(b"RewardsPaused".to_vec()));
+        contract.store_prediction(day(1), B32::repeat_byte(0x02)).unwrap();
+        assert!(contract.has_prediction(user, day(1)));
+        assert_eq!(contract.get_user_stats(user).0, U256::from(2));
+        
+        let failed = failures(&vm);
+        assert_eq!(failed.len(), 1);
+        let (topics, data) = &failed[0];
+        assert_eq!(topics[1..], [B32::left_padding_from(hook.as_slice()), B32::left_padding_from(user.as_slice())]);
+        let event = HookFailed { hook, user, date: day(1), reason: b"RewardsPaused".to_vec().into() };
+        assert_eq!(*data, event.encode_data());
+        
+        // The hook is called once the prediction is attributed to its chart
+        vm.mock_call(hook, notified(day(2), 3), Err(b"RewardsPaused".to_vec()));
+        contract.store_prediction_for_chart(day(2), B32::repeat_byte(0x03), keccak(b"natal")).unwrap();
+        let order: Vec<B32> = vm
+            .get_emitted_logs()
+            .into_iter()
+            .map(|(topics, _)| topics[0])
+            .filter(|topic| [PredictionStored::SIGNATURE_HASH, PredictionAttributed::SIGNATURE_HASH, HookFailed::SIGNATURE_HASH].contains(topic))
+            .collect();
+        assert_eq!(
+            order[order.len() - 3..],
+            [PredictionStored::SIGNATURE_HASH, PredictionAttributed::SIGNATURE_HASH, HookFailed::SIGNATURE_HASH]
+        );
+        assert_eq!(failures(&vm).len(), 2);
+        
+        // A zero hook isn't called
+        vm.set_sender(owner);
+        contract.set_prediction_hook(Address::ZERO).unwrap();
+        vm.set_sender(user);
+        vm.mock_call(Address::ZERO, notified(day(3), 4), Err(b"RewardsPaused".to_vec()));
+        contract.store_prediction(day(3), B32::repeat_byte(0x04)).unwrap();
+        assert_eq!(failures(&vm).len(), 2);
+    }
+    
+    /// `call`'s calldata with `sender` appended, as a forwarder relays it
+    fn forwarded<C: SolCall>(call: C, sender: Address) -> Vec<u8> {
+        [call.abi_encode(), sender.to_vec()].concat()
+    }
+    
+    #[test]
+    fn test_trusted_forwarder_owner_only() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let forwarder = Address::repeat_byte(0xf0);
+        contract.initialize(owner).unwrap();
+        
+        vm.set_sender(forwarder);
+        assert_eq!(contract.set_trusted_forwarder(forwarder), Err(b"NotOwner".to_vec()));
+        assert!(!contract.is_trusted_forwarder(forwarder));
+        // No forwarder set: not even the zero address is trusted
+        assert!(!contract.is_trusted_forwarder(Address::ZERO));
+        
+        vm.set_sender(owner);
+        contract.set_trusted_forwarder(forwarder).unwrap();
+        assert_eq!(contract.get_trusted_forwarder(), forwarder);
+        assert!(contract.is_trusted_forwarder(forwarder));
+        assert!(!contract.is_trusted_forwarder(owner));
+    }
+    
+    #[test]
+    fn test_forwarded_calls_act_for_appended_sender() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let forwarder = Address::repeat_byte(0xf0);
+        let user = Address::repeat_byte(0xa1);
+        let facet = Address::repeat_byte(0xfa);
+        contract.initialize(owner).unwrap();
+        vm.set_sender(owner);
+        contract.set_trusted_forwarder(forwarder).unwrap();
+        
+        // The core's methods act for the appended sender
+        let store_selector = FixedBytes(IFarcasterPredictions::storePredictionCall::SELECTOR);
+        vm.set_sender(forwarder);
+        let set_facet = forwarded(IFarcasterPredictions::setFacetCall { facet, selectors: vec![store_selector] }, owner);
+        assert_eq!(forwarder::route(&mut contract, &set_facet), Ok(vec![]));
+        vm.set_block_timestamp(vm.block_timestamp() + FACET_DELAY);
+        assert_eq!(contract.get_facet(store_selector), facet);
+        let (_, actors, _, _) = contract.get_admin_log(contract.get_admin_log_length() - U256::from(1), U256::from(1));
+        assert_eq!(actors, vec![owner]);
+        
+        // Facets get the call with its suffix, so they resolve the same sender
+        let store = forwarded(
+            IFarcasterPredictions::storePredictionCall { date: U256::from(20_000 * DAY), predictionHash: B32::repeat_byte(1) },
+            user,
+        );
+        vm.mock_delegate_call(facet, store.clone(), Ok(b"for user".to_vec()));
+        assert_eq!(forwarder::route(&mut contract, &store), Ok(b"for user".to_vec()));
+    }
+    
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_sender_suffix_from_untrusted_caller_ignored() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let mallory = Address::repeat_byte(0x66);
+        contract.initialize(owner).unwrap();
+        vm.set_sender(owner);
+        contract.set_trusted_forwarder(Address::repeat_byte(0xf0)).unwrap();
+        
+        let store_selector = FixedBytes(IFarcasterPredictions::storePredictionCall::SELECTOR);
+        let set_facet = IFarcasterPredictions::setFacetCall { facet: mallory, selectors: vec![store_selector] };
+        vm.set_sender(mallory);
+        // The suffix stays part of the arguments, which then fail to decode
+        assert_eq!(forwarder::route(&mut contract, &forwarded(set_facet.clone(), owner)), Err(vec![]));
+        assert_eq!(contract.get_pending_facet(store_selector), (Address::ZERO, 0));
+        
+        // Direct calls route as before
+        vm.set_sender(owner);
+        assert_eq!(forwarder::route(&mut contract, &set_facet.abi_encode()), Ok(vec![]));
+        assert_eq!(contract.get_pending_facet(store_selector).0, mallory);
+        assert_eq!(forwarder::route(&mut contract, &[0xde, 0xad]), Err(vec![]));
+    }
+    
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_facets() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        let facet = Address::repeat_byte(0xfa);
+        contract.initialize(owner).unwrap();
+        let store_selector = FixedBytes(IFarcasterPredictions::storePredictionCall::SELECTOR);
+        let rate_selector = FixedBytes(IFarcasterPredictions::ratePredictionCall::SELECTOR);
+        
+        assert_eq!(contract.set_facet(facet, vec![store_selector, rate_selector]), Err(b"NotOwner".to_vec()));
+        vm.set_sender(owner);
+        vm.set_block_timestamp(NOW);
+        contract.set_facet(facet, vec![store_selector, rate_selector]).unwrap();
+        // Logged once per selector
+        let (actions, _, targets, _) = contract.get_admin_log(U256::from(1), U256::from(10));
+        assert_eq!(actions, vec![admin_log::SET_FACET; 2]);
+        let target = U256::from(u32::from_be_bytes(store_selector.0)) << 160 | U256::from_be_slice(facet.as_slice());
+        assert_eq!(targets[0], B32::from(target));
+        
+        // Facets take over only after the delay
+        let store = IFarcasterPredictions::storePredictionCall { date: U256::from(20_000 * DAY), predictionHash: B32::repeat_byte(1) };
+        vm.mock_delegate_call(facet, store.abi_encode(), Err(b"UserNotRegistered".to_vec()));
+        assert_eq!(contract.get_facet(store_selector), Address::ZERO);
+        assert_eq!(contract.get_pending_facet(store_selector), (facet, NOW + FACET_DELAY));
+        vm.set_block_timestamp(NOW + FACET_DELAY - 1);
+        assert_eq!(forwarder::route(&mut contract, &store.abi_encode()), Err(vec![]));
+        vm.set_block_timestamp(NOW + FACET_DELAY);
+        assert_eq!(contract.get_facet(store_selector), facet);
+        assert_eq!(contract.get_facet(rate_selector), facet);
+        assert_eq!(contract.get_pending_facet(store_selector), (Address::ZERO, 0));
+        
+        // Calls outside the core are delegated through the fallback, and
+        // the facet's revert data passed on
+        assert_eq!(forwarder::route(&mut contract, &store.abi_encode()), Err(b"UserNotRegistered".to_vec()));
+        assert_eq!(contract.delegate_to_facet(&store.abi_encode()), Err(b"UserNotRegistered".to_vec()));
+        
+        // A replacement waits out the delay too, and registering the
+        // current facet again cancels it
+        let other = Address::repeat_byte(0xfb);
+        contract.set_facet(other, vec![store_selector]).unwrap();
+        vm.set_block_timestamp(NOW + 2 * FACET_DELAY - 1);
+        assert_eq!(contract.get_facet(store_selector), facet);
+        contract.set_facet(facet, vec![store_selector]).unwrap();
+        vm.set_block_timestamp(NOW + 4 * FACET_DELAY);
+        assert_eq!(contract.get_facet(store_selector), facet);
+        
+        // Unset selectors revert with no data, as unknown ones do
+        contract.set_facet(Address::ZERO, vec![rate_selector]).unwrap();
+        assert_eq!(contract.get_facet(rate_selector), facet);
+        vm.set_block_timestamp(NOW + 5 * FACET_DELAY);
+        assert_eq!(contract.get_facet(rate_selector), Address::ZERO);
+        let rate = IFarcasterPredictions::ratePredictionCall { date: U256::from(20_000 * DAY), rating: 4 };
+        assert_eq!(forwarder::route(&mut contract, &rate.abi_encode()), Err(vec![]));
+        assert_eq!(forwarder::route(&mut contract, &[0xde, 0xad, 0xbe, 0xef]), Err(vec![]));
+        assert_eq!(forwarder::route(&mut contract, &[]), Err(vec![]));
+    }
+    
+    #[test]
+    fn test_initialize_once() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        
+        assert_eq!(contract.initialize(Address::ZERO), Err(b"InvalidOwner".to_vec()));
+        contract.initialize(owner).unwrap();
+        assert_eq!(contract.owner(), owner);
+        assert_eq!(contract.get_accepted_versions(), (SCHEME_V1, LATEST_SCHEME_VERSION));
+        assert_eq!(contract.get_implementation_version(), U256::from(IMPLEMENTATION_VERSION));
+        
+        vm.set_sender(owner);
+        assert_eq!(contract.initialize(owner), Err(b"AlreadyInitialized".to_vec()));
+    }
+    
+    #[test]
+    fn test_initialize_rejected_on_legacy_deployment() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        
+        // Owner slot written by the old init, without the initialized flag
+        let owner = Address::repeat_byte(0x0a);
+        vm.set_storage(U256::from(11), B32::left_padding_from(owner.as_slice()));
+        assert_eq!(contract.owner(), owner);
+        assert_eq!(
+            contract.initialize(Address::repeat_byte(0x66)),
+            Err(b"AlreadyInitialized".to_vec())
+        );
+        assert_eq!(contract.owner(), owner);
+    }
+    
+    #[test]
+    fn test_storage_proof_slots() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let user = vm.msg_sender();
+        let date = U256::from(20_000 * DAY);
+        contract.register_user(B32::repeat_byte(0xab)).unwrap();
+        contract.store_prediction(date, B32::repeat_byte(0x42)).unwrap();
+        
+        let raw = |slot: B32| vm.get_storage(U256::from_be_bytes(slot.0));
+        assert_eq!(raw(FarcasterPredictions::commitment_slot(user)), B32::repeat_byte(0xab));
+        assert_eq!(raw(FarcasterPredictions::prediction_slot(user, date)), B32::repeat_byte(0x42));
+        
+        // Other dates and users map elsewhere, to empty slots
+        assert_eq!(raw(FarcasterPredictions::prediction_slot(user, date + U256::from(DAY))), B32::ZERO);
+        assert_eq!(raw(FarcasterPredictions::prediction_slot(Address::repeat_byte(0x3c), date)), B32::ZERO);
+    }
+    
+    #[test]
+    fn test_storage_layout_survives_upgrade() {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+        
+        // Sentinel in the total_users slot reads back through the contract
+        let sentinel = B32::repeat_byte(0x5e);
+        vm.set_storage(U256::from(8), sentinel);
+        assert_eq!(contract.get_global_stats().0, U256::from_be_bytes(sentinel.0));
+        
+        // So do sentinels at the entries of user_commitments (slot 0) and
+        // predictions (slot 2) that the storage proof views name
+        let user = Address::repeat_byte(0x3c);
+        let date = U256::from(20_000 * DAY);
+        vm.set_storage(U256::from_be_bytes(FarcasterPredictions::commitment_slot(user).0), sentinel);
+        vm.set_storage(U256::from_be_bytes(FarcasterPredictions::prediction_slot(user, date).0), sentinel);
+        assert_eq!(contract.get_user_commitment(user), sentinel);
+        assert_eq!(contract.get_prediction(user, date), sentinel);
+        
+        // The flag packs next to chart_registry in slot 23
+        contract.initialize(owner).unwrap();
+        assert_eq!(vm.get_storage(U256::from(23)), B32::from(U256::from(1) << 160));
+        
+        // The forwarder, the first field after the gap, is slot 74
+        vm.set_sender(owner);
+        contract.set_trusted_forwarder(Address::repeat_byte(0xf0)).unwrap();
+        assert_eq!(vm.get_storage(U256::from(74)), B32::left_padding_from(&[0xf0; 20]));
+        
+        // sponsor_contributions_total is slot 143
+        contract.sponsor_contributions_total.set(U256::from(7));
+        assert_eq!(vm.get_storage(U256::from(143)), B32::from(U256::from(7)));
+        
+        // The last declared field, facets, is rooted at slot 144: the
+        // pending facet and its time share the second word of an entry
+        let selector = FixedBytes([0xab; 4]);
+        contract.set_facet(Address::repeat_byte(0xfa), vec![selector]).unwrap();
+        let mut key = [0; 32];
+        key[..4].copy_from_slice(&selector.0);
+        let entry = U256::from_be_bytes(slots::mapping_slot(key, slots::base_slot(144)));
+        let effective_at = U256::from(vm.block_timestamp() + FACET_DELAY);
+        let pending = effective_at << 160 | U256::from_be_slice(&[0xfa; 20]);
+        assert_eq!(vm.get_storage(entry + U256::from(1)), B32::from(pending));
+        
+        // The gap spans slots 24..=73
+        let before = vm.snapshot().storage;
+        contract.__gap.setter(0).unwrap().set(U256::from(1));
+        contract.__gap.setter(49).unwrap().set(U256::from(2));
+        assert_eq!(vm.get_storage(U256::from(24)), B32::from(U256::from(1)));
+        assert_eq!(vm.get_storage(U256::from(73)), B32::from(U256::from(2)));
+        
+        // A field added by the next release (slot 145) leaves every
+        // existing slot untouched and is invisible to the current fields
+        vm.set_storage(U256::from(145), B32::repeat_byte(0xfe));
+        let after = vm.snapshot().storage;
+        assert_eq!(after.len(), before.len() + 3);
+        assert!(before.iter().all(|(slot, value)| after.get(slot) == Some(value)));
+        assert_eq!(contract.owner(), owner);
+        assert_eq!(contract.get_global_stats().0, U256::from_be_bytes(sentinel.0));
+        assert_eq!(contract.get_chart_registry(), Address::ZERO);
+        assert_eq!(contract.get_trusted_forwarder(), Address::repeat_byte(0xf0));
+        assert_eq!(contract.sponsor_contributions_total.get(), U256::from(7));
+        assert_eq!(contract.get_pending_facet(selector), (Address::repeat_byte(0xfa), effective_at.to::<u64>()));
+    }
+}
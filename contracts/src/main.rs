@@ -0,0 +1,13 @@
+//! Entrypoint binary: empty in the WASM build, prints the selected
+//! contract's ABI with `--features export-abi`
+
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+
+#[cfg(not(any(test, feature = "export-abi")))]
+#[no_mangle]
+pub extern "C" fn main() {}
+
+#[cfg(feature = "export-abi")]
+fn main() {
+    farcaster_predictions_stylus::abi::print_from_args();
+}
@@ -0,0 +1,93 @@
+//! Checked counter math
+//!
+//! Storage counters only grow by small steps, so few of them can overflow
+//! in practice, but a wrap would silently corrupt everything built on them.
+//! Every counter update therefore either goes through [`checked_add`],
+//! failing with `Overflow(field)` where `field` is one of the `FIELD_*` ids
+//! below, or saturates. Which one is chosen where the counter is updated:
+//!
+//! - totals that stats and averages are derived from (users, predictions,
+//!   rating counts and sums, pruned totals, charts, synastry pairs) and
+//!   wei balances (fees, the sponsor pool) fail, since a capped total would
+//!   skew them without notice
+//! - the points balance, a streak's length and the hourly write count
+//!   saturate: a balance or streak at the maximum stays there, and a write
+//!   count at the maximum still trips the circuit breaker
+//!
+//! Decrements stay unchecked: they never take a counter below zero.
+//!
+//! Field ids are never reused or renumbered.
+
+use alloc::vec::Vec;
+
+use alloy_sol_types::SolError;
+use stylus_sdk::alloy_primitives::Uint;
+
+pub use crate::Overflow;
+
+pub const FIELD_TOTAL_USERS: u8 = 1;
+pub const FIELD_TOTAL_PREDICTIONS: u8 = 2;
+pub const FIELD_GLOBAL_PREDICTIONS: u8 = 3;
+pub const FIELD_DAILY_PREDICTIONS: u8 = 4;
+pub const FIELD_TOTAL_RATINGS: u8 = 5;
+pub const FIELD_RATING_SUM: u8 = 6;
+pub const FIELD_BUCKET_RATINGS: u8 = 7;
+pub const FIELD_RATERS_PER_DATE: u8 = 8;
+pub const FIELD_DIMENSION_RATINGS: u8 = 9;
+pub const FIELD_COMMUNITY_RATINGS: u8 = 10;
+pub const FIELD_MONTHLY_RATINGS: u8 = 11;
+pub const FIELD_GLOBAL_RATINGS: u8 = 12;
+pub const FIELD_BLINDED_RATINGS: u8 = 13;
+pub const FIELD_TOTAL_CHARTS: u8 = 14;
+pub const FIELD_PAIR_PREDICTIONS: u8 = 15;
+pub const FIELD_PAIR_RATINGS: u8 = 16;
+pub const FIELD_SPONSOR_POOL: u8 = 17;
+pub const FIELD_COLLECTED_FEES: u8 = 18;
+pub const FIELD_SPONSORED_REGISTRATIONS: u8 = 19;
+pub const FIELD_SPONSOR_CONTRIBUTIONS: u8 = 20;
+pub const FIELD_PRUNED_COUNT: u8 = 21;
+pub const FIELD_PRUNED_RATINGS: u8 = 22;
+pub const FIELD_PRUNED_RATING_SUM: u8 = 23;
+
+/// `value + amount` for counters of any width, from `U8` to `U256`, or
+/// `Overflow(field)` past the width's maximum
+pub fn checked_add<const BITS: usize, const LIMBS: usize>(
+    value: Uint<BITS, LIMBS>,
+    amount: Uint<BITS, LIMBS>,
+    field: u8,
+) -> Result<Uint<BITS, LIMBS>, Overflow> {
+    value.checked_add(amount).ok_or(Overflow { field })
+}
+
+/// `value + 1`, or `Overflow(field)` at the width's maximum
+pub fn checked_inc<const BITS: usize, const LIMBS: usize>(
+    value: Uint<BITS, LIMBS>,
+    field: u8,
+) -> Result<Uint<BITS, LIMBS>, Overflow> {
+    checked_add(value, Uint::from(1), field)
+}
+
+impl From<Overflow> for Vec<u8> {
+    fn from(error: Overflow) -> Self {
+        error.abi_encode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::alloy_primitives::{U256, U8};
+
+    #[test]
+    fn test_checked_add() {
+        assert_eq!(checked_inc(U8::from(254), FIELD_RATING_SUM), Ok(U8::MAX));
+        assert_eq!(checked_inc(U8::MAX, FIELD_RATING_SUM), Err(Overflow { field: FIELD_RATING_SUM }));
+        assert_eq!(checked_add(U256::MAX - U256::from(5), U256::from(5), FIELD_TOTAL_USERS), Ok(U256::MAX));
+        assert_eq!(
+            checked_add(U256::MAX - U256::from(4), U256::from(5), FIELD_TOTAL_USERS),
+            Err(Overflow { field: FIELD_TOTAL_USERS })
+        );
+        let revert: Vec<u8> = Overflow { field: FIELD_TOTAL_CHARTS }.into();
+        assert_eq!(Overflow::abi_decode(&revert, true).unwrap().field, FIELD_TOTAL_CHARTS);
+    }
+}
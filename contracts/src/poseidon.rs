@@ -0,0 +1,1277 @@
+//! ZK proof verification for birth data commitments
+//!
+//! Mirrors the Fiat-Shamir scheme in `lib/zkproof/poseidon-proof.ts`, with
+//! keccak256 standing in for Poseidon so verification stays cheap on-chain:
+//! 1. Challenge: e = H(commitment || positions)
+//! 2. Proof: H(commitment || nonce || hex(e))
+//!
+//! Strings are hashed as raw UTF-8 bytes and hex output is lowercase without
+//! a 0x prefix. Commitments are parsed into a [`Commitment`] first and always
+//! hashed in that canonical lowercase form. The challenge preimage comes in two byte encodings, see
+//! [`Encoding`]; the proof preimage is identical in both.
+//!
+//! Proofs carry a scheme version so deployed contracts can accept old and
+//! new schemes side by side during a migration, see [`verify_dispatch`].
+//!
+//! Verification itself works on bytes ([`ProofBytes`]); the hex strings of
+//! [`ZkProof`] and the `&str` verifiers are decoded once up front.
+//! From v2 on, every hash input goes through a [`Transcript`] so the byte
+//! encoding is defined in exactly one place.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(any(test, feature = "poseidon2"))]
+use stylus_sdk::alloy_primitives::{FixedBytes, U256};
+
+// Shared with the prover and other contracts; re-exported so existing
+// `poseidon::` paths keep working
+pub use zkastro_core::commitment::{Commitment, CommitmentError};
+#[cfg(any(test, feature = "prover"))]
+pub(crate) use zkastro_core::hash::keccak256;
+pub(crate) use zkastro_core::hash::to_hex;
+use zkastro_core::hash::from_hex32;
+pub use zkastro_core::hash::{Keccak256Builder, Transcript};
+pub use zkastro_core::packing::{pack_positions, unpack_positions, MAX_PACKED_POSITIONS};
+pub use zkastro_core::positions::{MAX_POSITION, MAX_POSITIONS};
+
+#[cfg(any(test, feature = "poseidon2"))]
+use crate::field;
+#[cfg(any(test, feature = "poseidon2"))]
+use crate::poseidon2::{poseidon2_compress, poseidon2_hash2};
+use crate::positions::{positions_digest, words_digest, PlanetPosition, PositionError};
+#[cfg(any(test, feature = "poseidon2"))]
+use crate::positions::{sorted_encodings, BODY_COUNT};
+
+/// Original keccak Fiat-Shamir scheme
+pub const SCHEME_V1: u8 = 1;
+
+/// Transcript-based scheme
+pub const SCHEME_V2: u8 = 2;
+
+/// Poseidon2 scheme with field-native challenges (`poseidon2` feature)
+pub const SCHEME_V3: u8 = 3;
+
+/// Newest scheme version this build can verify
+#[cfg(not(any(test, feature = "poseidon2")))]
+pub const LATEST_SCHEME_VERSION: u8 = SCHEME_V2;
+
+/// Newest scheme version this build can verify
+#[cfg(any(test, feature = "poseidon2"))]
+pub const LATEST_SCHEME_VERSION: u8 = SCHEME_V3;
+
+/// Largest accepted string field (commitment, proof, nonce) in bytes,
+/// twice a 32-byte hex string with room for a prefix
+pub const MAX_FIELD_BYTES: usize = 130;
+
+/// Fewest positions a proof may cover, the seven classical bodies
+pub const MIN_POSITIONS: usize = 7;
+
+/// Input ids of [`InputError`], as the contracts' `InputTooLarge` carries
+/// them
+pub const INPUT_COMMITMENT: u8 = 1;
+pub const INPUT_PROOF: u8 = 2;
+pub const INPUT_NONCE: u8 = 3;
+pub const INPUT_POSITIONS: u8 = 4;
+
+/// Length of a proof epoch (one week)
+pub const EPOCH_SECONDS: u64 = 604_800;
+
+/// Transcript domain of the v2 proof scheme
+pub const V2_DOMAIN: &[u8] = b"ZKAstro.proof.v2";
+
+/// Domain of bucketed commitments, see [`bucket_commitment`]
+pub const BUCKET_DOMAIN: &[u8] = b"ZKAstro.bucket.v1";
+
+/// Largest birth-year bucket (decades 1900s to 2020s are 0-12)
+pub const MAX_YEAR_BUCKET: u8 = 12;
+
+/// First year of bucket 0
+pub const BUCKET_BASE_YEAR: u16 = 1900;
+
+/// Hash-to-field domain of the v3 domain tag
+pub const V3_DOMAIN: &[u8] = b"ZKAstro.proof.v3";
+
+/// Hash-to-field domain of the v3 nonce
+pub const V3_NONCE_DOMAIN: &[u8] = b"ZKAstro.proof.v3.nonce";
+
+/// Reasons a proof could not be checked at all
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofError {
+    /// No verifier for this scheme version
+    UnsupportedProofVersion(u8),
+    /// Proof bound to an epoch outside the accepted window
+    ProofExpired(u64),
+}
+
+/// Reasons raw proof input is rejected before any hashing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputError {
+    /// String input `.0` (an `INPUT_*` id) of `.1` bytes, longer than
+    /// [`MAX_FIELD_BYTES`]
+    FieldTooLong(u8, usize),
+    /// A string field that is not UTF-8
+    InvalidUtf8,
+    /// No [`Encoding`] with this id
+    InvalidEncoding(u8),
+    /// Fewer than [`MIN_POSITIONS`] or more than [`MAX_POSITIONS`]
+    /// positions
+    PositionCount(usize),
+}
+
+/// Byte encoding of the positions in the challenge preimage
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// `commitment || u64_le(p0) || u64_le(p1) || ...`
+    ///
+    /// Each position is 8 bytes little-endian. Used by the mobile client.
+    Legacy,
+    /// `abi.encodePacked(string commitment, uint64[] positions)`
+    ///
+    /// Each position is left-padded to a 32-byte big-endian word, matching
+    /// ethers' `solidityPackedKeccak256(["string", "uint64[]"], ...)` used
+    /// by the web client.
+    SolidityPacked,
+}
+
+impl Encoding {
+    /// Stable name used in fixtures and client configs
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Legacy => "legacy",
+            Encoding::SolidityPacked => "solidity_packed",
+        }
+    }
+
+    /// Parse a name produced by [`Encoding::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "legacy" => Some(Encoding::Legacy),
+            "solidity_packed" => Some(Encoding::SolidityPacked),
+            _ => None,
+        }
+    }
+
+    /// Numeric id used in contract calldata
+    pub fn id(&self) -> u8 {
+        match self {
+            Encoding::Legacy => 0,
+            Encoding::SolidityPacked => 1,
+        }
+    }
+
+    /// Parse an id produced by [`Encoding::id`]
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Encoding::Legacy),
+            1 => Some(Encoding::SolidityPacked),
+            _ => None,
+        }
+    }
+}
+
+/// Recent block a v2 proof is salted with, resolved by the caller
+///
+/// Proofs over an anchor can't have been generated before that block was
+/// produced. How recent the block must be is the caller's concern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockAnchor {
+    /// Block number
+    pub number: u64,
+    /// Hash of that block
+    pub hash: [u8; 32],
+}
+
+/// Proof submitted alongside a commitment
+///
+/// Positions are public inputs and travel separately.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZkProof {
+    /// Commitment to the birth data, hex as submitted (see [`Commitment`])
+    pub commitment: String,
+    /// H(commitment || nonce || hex(challenge)) as hex
+    pub proof: String,
+    /// Prover-chosen nonce
+    pub nonce: String,
+    /// Challenge encoding the proof was generated with (v1 only, the
+    /// transcript fixes the encoding from v2 on)
+    pub encoding: Encoding,
+    /// Scheme version the proof was generated under
+    pub scheme_version: u8,
+    /// Epoch the proof is bound to (v2 on, ignored by v1)
+    pub epoch: u64,
+    /// Block the challenge is salted with (v2 only, anchored proofs under
+    /// other versions never verify)
+    pub anchor: Option<BlockAnchor>,
+    /// Birth-year bucket the challenge is bound to (v2 only, like `anchor`)
+    pub claimed_bucket: Option<u8>,
+}
+
+impl ZkProof {
+    /// Assemble a proof from raw calldata fields
+    ///
+    /// Each string field must be UTF-8 and at most [`MAX_FIELD_BYTES`]
+    /// long; the commitment itself is only parsed at verification.
+    pub fn from_bytes(
+        commitment: &[u8],
+        proof: &[u8],
+        nonce: &[u8],
+        encoding: u8,
+        scheme_version: u8,
+        epoch: u64,
+    ) -> Result<Self, InputError> {
+        let encoding = Encoding::from_id(encoding).ok_or(InputError::InvalidEncoding(encoding))?;
+        Ok(ZkProof {
+            commitment: field_string(INPUT_COMMITMENT, commitment)?,
+            proof: field_string(INPUT_PROOF, proof)?,
+            nonce: field_string(INPUT_NONCE, nonce)?,
+            encoding,
+            scheme_version,
+            epoch,
+            anchor: None,
+            claimed_bucket: None,
+        })
+    }
+
+    /// Decode into [`ProofBytes`], `None` if the commitment or proof is
+    /// not valid hex
+    pub fn to_bytes(&self) -> Option<ProofBytes> {
+        Some(ProofBytes {
+            commitment: Commitment::from_hex(&self.commitment).ok()?,
+            proof: from_hex32(self.proof.as_bytes())?,
+            nonce: self.nonce.as_bytes().to_vec(),
+            encoding: self.encoding,
+            scheme_version: self.scheme_version,
+            epoch: self.epoch,
+            anchor: self.anchor,
+            claimed_bucket: self.claimed_bucket,
+        })
+    }
+
+    /// Verify against `position_values` under the proof's own scheme version
+    ///
+    /// A malformed commitment or proof verifies as false.
+    pub fn verify(&self, position_values: &[u64]) -> Result<bool, ProofError> {
+        match self.to_bytes() {
+            Some(proof) => proof.verify(position_values),
+            None => Ok(false),
+        }
+    }
+
+    /// Verify as [`ZkProof::verify`], also enforcing the epoch window
+    /// relative to `current_epoch` (see [`check_epoch`])
+    pub fn verify_at(&self, position_values: &[u64], current_epoch: u64) -> Result<bool, ProofError> {
+        if self.scheme_version >= SCHEME_V2 {
+            check_epoch(self.epoch, current_epoch)?;
+        }
+        self.verify(position_values)
+    }
+}
+
+/// [`ZkProof`] in calldata form: commitment and proof as raw 32 bytes
+///
+/// What contracts verify; nothing is hex encoded or parsed on this path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofBytes {
+    /// Commitment to the birth data
+    pub commitment: Commitment,
+    /// Proof digest, see [`ZkProof::proof`]
+    pub proof: [u8; 32],
+    /// Prover-chosen nonce, hashed as is
+    pub nonce: Vec<u8>,
+    /// See [`ZkProof::encoding`]
+    pub encoding: Encoding,
+    /// See [`ZkProof::scheme_version`]
+    pub scheme_version: u8,
+    /// See [`ZkProof::epoch`]
+    pub epoch: u64,
+    /// See [`ZkProof::anchor`]
+    pub anchor: Option<BlockAnchor>,
+    /// See [`ZkProof::claimed_bucket`]
+    pub claimed_bucket: Option<u8>,
+}
+
+impl ProofBytes {
+    /// Assemble a proof from calldata fields
+    ///
+    /// The nonce must be at most [`MAX_FIELD_BYTES`] long.
+    pub fn new(
+        commitment: Commitment,
+        proof: [u8; 32],
+        nonce: Vec<u8>,
+        encoding: u8,
+        scheme_version: u8,
+        epoch: u64,
+    ) -> Result<Self, InputError> {
+        let encoding = Encoding::from_id(encoding).ok_or(InputError::InvalidEncoding(encoding))?;
+        if nonce.len() > MAX_FIELD_BYTES {
+            return Err(InputError::FieldTooLong(INPUT_NONCE, nonce.len()));
+        }
+        Ok(ProofBytes { commitment, proof, nonce, encoding, scheme_version, epoch, anchor: None, claimed_bucket: None })
+    }
+
+    /// Verify against `position_values` under the proof's own scheme version
+    pub fn verify(&self, position_values: &[u64]) -> Result<bool, ProofError> {
+        if self.anchor.is_some() || self.claimed_bucket.is_some() {
+            let verified = self.scheme_version == SCHEME_V2
+                && verify_v2(
+                    &self.commitment,
+                    &self.proof,
+                    &self.nonce,
+                    position_values,
+                    self.epoch,
+                    self.anchor.as_ref(),
+                    self.claimed_bucket,
+                );
+            return Ok(verified);
+        }
+        dispatch(
+            self.scheme_version,
+            &self.commitment,
+            &self.proof,
+            &self.nonce,
+            position_values,
+            self.encoding,
+            self.epoch,
+        )
+    }
+
+    /// Verify as [`ProofBytes::verify`], also enforcing the epoch window
+    /// relative to `current_epoch` (see [`check_epoch`])
+    pub fn verify_at(&self, position_values: &[u64], current_epoch: u64) -> Result<bool, ProofError> {
+        if self.scheme_version >= SCHEME_V2 {
+            check_epoch(self.epoch, current_epoch)?;
+        }
+        self.verify(position_values)
+    }
+}
+
+fn field_string(field: u8, bytes: &[u8]) -> Result<String, InputError> {
+    if bytes.len() > MAX_FIELD_BYTES {
+        return Err(InputError::FieldTooLong(field, bytes.len()));
+    }
+    let s = core::str::from_utf8(bytes).map_err(|_| InputError::InvalidUtf8)?;
+    Ok(String::from(s))
+}
+
+/// Reject position lists shorter than [`MIN_POSITIONS`] or longer than
+/// [`MAX_POSITIONS`]
+pub fn check_positions(position_values: &[u64]) -> Result<(), InputError> {
+    if !(MIN_POSITIONS..=MAX_POSITIONS).contains(&position_values.len()) {
+        return Err(InputError::PositionCount(position_values.len()));
+    }
+    Ok(())
+}
+
+/// Whether verifier inputs are non-empty and within the size limits
+fn within_limits(nonce: &[u8], position_values: &[u64]) -> bool {
+    !nonce.is_empty() && nonce.len() <= MAX_FIELD_BYTES && check_positions(position_values).is_ok()
+}
+
+/// Epoch containing `timestamp` (seconds since the Unix epoch)
+pub fn compute_epoch(timestamp: u64) -> u64 {
+    timestamp / EPOCH_SECONDS
+}
+
+/// Accept proofs bound to the current or the previous epoch only
+///
+/// The one-epoch grace period covers proofs generated just before a
+/// boundary; anything older (or from the future) is `ProofExpired`.
+pub fn check_epoch(epoch: u64, current_epoch: u64) -> Result<(), ProofError> {
+    if epoch == current_epoch || epoch.checked_add(1) == Some(current_epoch) {
+        Ok(())
+    } else {
+        Err(ProofError::ProofExpired(epoch))
+    }
+}
+
+/// Build the challenge preimage for `encoding`
+///
+/// Reference layout only; the verifier streams the same bytes through
+/// [`Keccak256Builder`] without building this buffer.
+pub fn challenge_preimage(commitment: &str, position_values: &[u64], encoding: Encoding) -> Vec<u8> {
+    let word_len = match encoding {
+        Encoding::Legacy => 8,
+        Encoding::SolidityPacked => 32,
+    };
+
+    let capacity = position_values.len().saturating_mul(word_len).saturating_add(commitment.len());
+    let mut preimage = Vec::with_capacity(capacity);
+    preimage.extend_from_slice(commitment.as_bytes());
+    for position in position_values {
+        match encoding {
+            Encoding::Legacy => preimage.extend_from_slice(&position.to_le_bytes()),
+            Encoding::SolidityPacked => {
+                preimage.extend_from_slice(&[0u8; 24]);
+                preimage.extend_from_slice(&position.to_be_bytes());
+            }
+        }
+    }
+    preimage
+}
+
+fn challenge_digest(commitment: &[u8], position_values: &[u64], encoding: Encoding) -> [u8; 32] {
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(commitment);
+    match encoding {
+        Encoding::Legacy => {
+            hasher.update_u64s(position_values);
+        }
+        Encoding::SolidityPacked => {
+            for position in position_values {
+                hasher.update_u64_word(*position);
+            }
+        }
+    }
+    hasher.finalize32()
+}
+
+fn proof_digest(commitment: &[u8], nonce: &[u8], challenge: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256Builder::new();
+    hasher.update(commitment).update(nonce).update(&to_hex(challenge));
+    hasher.finalize32()
+}
+
+/// Compute the challenge e = H(commitment || positions)
+pub fn compute_challenge(commitment: &str, position_values: &[u64], encoding: Encoding) -> [u8; 32] {
+    challenge_digest(commitment.as_bytes(), position_values, encoding)
+}
+
+/// Compute the proof H(commitment || nonce || hex(challenge))
+pub fn compute_proof(commitment: &str, nonce: &str, challenge: &[u8; 32]) -> [u8; 32] {
+    proof_digest(commitment.as_bytes(), nonce.as_bytes(), challenge)
+}
+
+/// Verify a proof of knowledge of the birth data behind `commitment`
+///
+/// # Arguments
+/// * `commitment` - Parsed commitment, hashed in canonical lowercase form
+/// * `proof` - Hex proof (either case, no 0x prefix)
+/// * `nonce` - Nonce used when generating the proof
+/// * `position_values` - Planetary positions in centidegrees
+/// * `encoding` - Challenge encoding the client used
+///
+/// Empty or oversized inputs and position counts outside
+/// [`MIN_POSITIONS`]..=[`MAX_POSITIONS`] verify as false, before any
+/// hashing.
+pub fn verify_zk_proof(
+    commitment: &Commitment,
+    proof: &str,
+    nonce: &str,
+    position_values: &[u64],
+    encoding: Encoding,
+) -> bool {
+    from_hex32(proof.as_bytes())
+        .is_some_and(|proof| verify_v1(commitment, &proof, nonce.as_bytes(), position_values, encoding))
+}
+
+fn verify_v1(
+    commitment: &Commitment,
+    proof: &[u8; 32],
+    nonce: &[u8],
+    position_values: &[u64],
+    encoding: Encoding,
+) -> bool {
+    if !within_limits(nonce, position_values) {
+        return false;
+    }
+
+    // The scheme hashes the commitment in its canonical hex form
+    let commitment = to_hex(&commitment.0);
+    let challenge = challenge_digest(&commitment, position_values, encoding);
+    proof_digest(&commitment, nonce, &challenge) == *proof
+}
+
+/// Compute the v2 (challenge, proof) pair
+///
+/// ```text
+/// t = Transcript::new("ZKAstro.proof.v2")
+/// t.append_bytes("commitment", commitment)
+/// t.append_u64s("epoch", [epoch])
+/// // anchored proofs only, see compute_proof_v2_anchored
+/// t.append_u64s("anchor_block", [anchor.number])
+/// t.append_bytes("anchor_hash", anchor.hash)
+/// // bucket claims only, see compute_proof_v2_bucketed
+/// t.append_bytes("claimed_bucket", [bucket])
+/// t.append_bytes("positions", positions_digest(positions))
+/// challenge = t.challenge_bytes32("challenge")
+/// t.append_bytes("nonce", nonce)
+/// proof = t.challenge_bytes32("proof")
+/// ```
+///
+/// Positions are bound through their order-independent digest, see
+/// [`positions_digest`].
+pub fn compute_proof_v2(
+    commitment: &str,
+    nonce: &str,
+    positions: &[PlanetPosition],
+    epoch: u64,
+) -> Result<([u8; 32], [u8; 32]), PositionError> {
+    let digest = positions_digest(positions)?;
+    Ok(proof_v2_digests(commitment.as_bytes(), nonce.as_bytes(), &digest, epoch, None, None))
+}
+
+/// Compute the v2 (challenge, proof) pair salted with a recent block, see
+/// [`compute_proof_v2`]
+pub fn compute_proof_v2_anchored(
+    commitment: &str,
+    nonce: &str,
+    positions: &[PlanetPosition],
+    epoch: u64,
+    anchor: &BlockAnchor,
+) -> Result<([u8; 32], [u8; 32]), PositionError> {
+    let digest = positions_digest(positions)?;
+    Ok(proof_v2_digests(commitment.as_bytes(), nonce.as_bytes(), &digest, epoch, Some(anchor), None))
+}
+
+/// Compute the v2 (challenge, proof) pair bound to a birth-year bucket
+/// claim, see [`compute_proof_v2`] and [`verify_bucket_claim`]
+pub fn compute_proof_v2_bucketed(
+    commitment: &str,
+    nonce: &str,
+    positions: &[PlanetPosition],
+    epoch: u64,
+    claimed_bucket: u8,
+) -> Result<([u8; 32], [u8; 32]), PositionError> {
+    let digest = positions_digest(positions)?;
+    Ok(proof_v2_digests(commitment.as_bytes(), nonce.as_bytes(), &digest, epoch, None, Some(claimed_bucket)))
+}
+
+fn proof_v2_digests(
+    commitment: &[u8],
+    nonce: &[u8],
+    positions_digest: &[u8; 32],
+    epoch: u64,
+    anchor: Option<&BlockAnchor>,
+    claimed_bucket: Option<u8>,
+) -> ([u8; 32], [u8; 32]) {
+    let mut transcript = Transcript::new(V2_DOMAIN);
+    transcript.append_bytes(b"commitment", commitment);
+    transcript.append_u64s(b"epoch", &[epoch]);
+    if let Some(anchor) = anchor {
+        transcript.append_u64s(b"anchor_block", &[anchor.number]);
+        transcript.append_bytes(b"anchor_hash", &anchor.hash);
+    }
+    if let Some(bucket) = claimed_bucket {
+        transcript.append_bytes(b"claimed_bucket", &[bucket]);
+    }
+    transcript.append_bytes(b"positions", positions_digest);
+    let challenge = transcript.challenge_bytes32(b"challenge");
+    transcript.append_bytes(b"nonce", nonce);
+    let proof = transcript.challenge_bytes32(b"proof");
+    (challenge, proof)
+}
+
+/// Verify a v2 (transcript-based) proof
+///
+/// Same inputs as [`verify_zk_proof`] minus the encoding, which the
+/// transcript fixes, plus the `epoch` the proof is bound to. Freshness of
+/// the epoch is the caller's concern, see [`check_epoch`].
+///
+/// `position_words` are [`PlanetPosition`] words (see
+/// [`PlanetPosition::to_word`]) in any order; unknown or duplicate bodies
+/// fail verification.
+pub fn verify_zk_proof_v2(
+    commitment: &Commitment,
+    proof: &str,
+    nonce: &str,
+    position_words: &[u64],
+    epoch: u64,
+) -> bool {
+    verify_v2_hex(commitment, proof, nonce, position_words, epoch, None, None)
+}
+
+/// Verify a v2 proof salted with `anchor`, see [`verify_zk_proof_v2`]
+pub fn verify_zk_proof_v2_anchored(
+    commitment: &Commitment,
+    proof: &str,
+    nonce: &str,
+    position_words: &[u64],
+    epoch: u64,
+    anchor: &BlockAnchor,
+) -> bool {
+    verify_v2_hex(commitment, proof, nonce, position_words, epoch, Some(anchor), None)
+}
+
+/// Verify a v2 proof bound to `claimed_bucket`, see [`verify_zk_proof_v2`]
+///
+/// This only checks the proof; that the commitment carries the bucket is
+/// [`verify_bucket_claim`].
+pub fn verify_zk_proof_v2_bucketed(
+    commitment: &Commitment,
+    proof: &str,
+    nonce: &str,
+    position_words: &[u64],
+    epoch: u64,
+    claimed_bucket: u8,
+) -> bool {
+    verify_v2_hex(commitment, proof, nonce, position_words, epoch, None, Some(claimed_bucket))
+}
+
+fn verify_v2_hex(
+    commitment: &Commitment,
+    proof: &str,
+    nonce: &str,
+    position_words: &[u64],
+    epoch: u64,
+    anchor: Option<&BlockAnchor>,
+    claimed_bucket: Option<u8>,
+) -> bool {
+    from_hex32(proof.as_bytes()).is_some_and(|proof| {
+        verify_v2(commitment, &proof, nonce.as_bytes(), position_words, epoch, anchor, claimed_bucket)
+    })
+}
+
+fn verify_v2(
+    commitment: &Commitment,
+    proof: &[u8; 32],
+    nonce: &[u8],
+    position_words: &[u64],
+    epoch: u64,
+    anchor: Option<&BlockAnchor>,
+    claimed_bucket: Option<u8>,
+) -> bool {
+    if !within_limits(nonce, position_words) {
+        return false;
+    }
+    let Ok(digest) = words_digest(position_words) else {
+        return false;
+    };
+
+    let (_, expected) = proof_v2_digests(&to_hex(&commitment.0), nonce, &digest, epoch, anchor, claimed_bucket);
+    expected == *proof
+}
+
+/// Birth-year bucket (decade index) of `year`, `None` outside 1900-2029
+pub fn year_bucket(year: u16) -> Option<u8> {
+    let bucket = year.checked_sub(BUCKET_BASE_YEAR)? / 10;
+    u8::try_from(bucket).ok().filter(|bucket| *bucket <= MAX_YEAR_BUCKET)
+}
+
+/// Commitment that carries a birth-year bucket
+///
+/// `keccak256(u32_be(len(domain)) || domain || u8(bucket) || opening)`
+/// where `opening` is the plain commitment to the birth data (keccak over
+/// the framed fields and salt). The salt keeps the opening hiding, so
+/// revealing it discloses nothing beyond the bucket.
+pub fn bucket_commitment(bucket: u8, opening: &[u8; 32]) -> Commitment {
+    let mut hasher = Keccak256Builder::new();
+    hasher
+        .update(&(BUCKET_DOMAIN.len() as u32).to_be_bytes())
+        .update(BUCKET_DOMAIN)
+        .update(&[bucket])
+        .update(opening);
+    Commitment(hasher.finalize32())
+}
+
+/// Check that `commitment` carries `bucket`, given its opening proof
+///
+/// Buckets above [`MAX_YEAR_BUCKET`] never verify. Whether the bucket
+/// matches the year in the committed date of birth is up to the prover
+/// (see `prover::generate_bucketed_commitment`); this binds the claim to
+/// the commitment so it can't change later.
+pub fn verify_bucket_claim(commitment: &Commitment, bucket: u8, opening_proof: &[u8; 32]) -> bool {
+    bucket <= MAX_YEAR_BUCKET && bucket_commitment(bucket, opening_proof) == *commitment
+}
+
+/// Compute the v3 (Poseidon2) challenge and proof as 32-byte big-endian
+/// field elements
+///
+/// ```text
+/// c = commitment bytes as a big-endian integer mod r
+/// challenge = poseidon2_compress([
+///     hash_to_field("ZKAstro.proof.v3", ""), c, epoch,
+///     positions sorted by body, each its 4-byte encoding as an integer,
+/// ])
+/// proof = poseidon2_hash2(challenge, hash_to_field("ZKAstro.proof.v3.nonce", nonce))
+/// ```
+///
+/// Everything after the commitment and nonce mapping is field-native, so
+/// circuits can recompute the challenge without keccak.
+#[cfg(any(test, feature = "poseidon2"))]
+pub fn compute_proof_v3(
+    commitment: &Commitment,
+    nonce: &str,
+    positions: &[PlanetPosition],
+    epoch: u64,
+) -> Result<([u8; 32], [u8; 32]), PositionError> {
+    let sorted = sorted_encodings(positions.iter().copied().map(Ok))?;
+    Ok(proof_v3_digests(commitment, nonce.as_bytes(), sorted, epoch))
+}
+
+#[cfg(any(test, feature = "poseidon2"))]
+fn proof_v3_digests(
+    commitment: &Commitment,
+    nonce: &[u8],
+    (sorted, len): ([[u8; 4]; BODY_COUNT], usize),
+    epoch: u64,
+) -> ([u8; 32], [u8; 32]) {
+    let mut inputs = [U256::ZERO; 3 + BODY_COUNT];
+    inputs[0] = field::hash_to_field(V3_DOMAIN, b"");
+    inputs[1] = field::reduce_to_field(FixedBytes(commitment.0));
+    inputs[2] = U256::from(epoch);
+    for (input, encoding) in inputs[3..].iter_mut().zip(&sorted[..len]) {
+        *input = U256::from(u32::from_be_bytes(*encoding));
+    }
+
+    let challenge = poseidon2_compress(&inputs[..3 + len]);
+    let proof = poseidon2_hash2(challenge, field::hash_to_field(V3_NONCE_DOMAIN, nonce));
+    (challenge.to_be_bytes(), proof.to_be_bytes())
+}
+
+/// Verify a v3 (Poseidon2) proof
+///
+/// Same inputs and position semantics as [`verify_zk_proof_v2`]; the proof
+/// is the hex of a 32-byte field element, see [`compute_proof_v3`].
+#[cfg(any(test, feature = "poseidon2"))]
+pub fn verify_zk_proof_v3(
+    commitment: &Commitment,
+    proof: &str,
+    nonce: &str,
+    position_words: &[u64],
+    epoch: u64,
+) -> bool {
+    from_hex32(proof.as_bytes())
+        .is_some_and(|proof| verify_v3(commitment, &proof, nonce.as_bytes(), position_words, epoch))
+}
+
+#[cfg(any(test, feature = "poseidon2"))]
+fn verify_v3(
+    commitment: &Commitment,
+    proof: &[u8; 32],
+    nonce: &[u8],
+    position_words: &[u64],
+    epoch: u64,
+) -> bool {
+    if !within_limits(nonce, position_words) {
+        return false;
+    }
+    let Ok(sorted) = sorted_encodings(position_words.iter().map(|word| PlanetPosition::from_word(*word))) else {
+        return false;
+    };
+
+    let (_, expected) = proof_v3_digests(commitment, nonce, sorted, epoch);
+    expected == *proof
+}
+
+/// Verify a proof with the verifier for scheme `version`
+///
+/// Returns `Err` only when no verifier exists for `version`; a proof that
+/// simply doesn't check out is `Ok(false)`. Callers enforce which versions
+/// they accept before dispatching. v3 is only available with the
+/// `poseidon2` feature.
+///
+/// `position_values` are plain centidegrees in client order for v1 and
+/// [`PlanetPosition`] words from v2 on.
+pub fn verify_dispatch(
+    version: u8,
+    commitment: &Commitment,
+    proof: &str,
+    nonce: &str,
+    position_values: &[u64],
+    encoding: Encoding,
+    epoch: u64,
+) -> Result<bool, ProofError> {
+    match from_hex32(proof.as_bytes()) {
+        Some(proof) => dispatch(version, commitment, &proof, nonce.as_bytes(), position_values, encoding, epoch),
+        // Malformed hex never verifies, but unknown versions still report as such
+        None => dispatch(version, commitment, &[0; 32], b"", &[], encoding, epoch).map(|_| false),
+    }
+}
+
+fn dispatch(
+    version: u8,
+    commitment: &Commitment,
+    proof: &[u8; 32],
+    nonce: &[u8],
+    position_values: &[u64],
+    encoding: Encoding,
+    epoch: u64,
+) -> Result<bool, ProofError> {
+    match version {
+        SCHEME_V1 => Ok(verify_v1(commitment, proof, nonce, position_values, encoding)),
+        SCHEME_V2 => Ok(verify_v2(commitment, proof, nonce, position_values, epoch, None, None)),
+        #[cfg(any(test, feature = "poseidon2"))]
+        SCHEME_V3 => Ok(verify_v3(commitment, proof, nonce, position_values, epoch)),
+        _ => Err(ProofError::UnsupportedProofVersion(version)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMITMENT: &str = "1b7f3c9e0d2a4b6c8e0f1a3b5c7d9e1f2a4b6c8d0e1f3a5b7c9d1e3f5a7b9c1d";
+    const NONCE: &str = "5f0e3a9c2b7d4e1f";
+    const POSITIONS: [u64; 7] = [12345, 23456, 0, 36000, 9000, 18000, 27000];
+
+    fn v2_chart() -> Vec<PlanetPosition> {
+        V2_WORDS.iter().map(|word| PlanetPosition::from_word(*word).unwrap()).collect()
+    }
+
+    fn commitment() -> Commitment {
+        Commitment::from_hex(COMMITMENT).unwrap()
+    }
+
+    // Fixtures computed independently of this module (keccak256 over the
+    // documented byte layouts, as ethers' solidityPackedKeccak256 does)
+    const LEGACY_CHALLENGE: &str = "379b0745b6da2f6bb384266530a2535efd3f88188a35a66fe130e83793b944a3";
+    const LEGACY_PROOF: &str = "4ae278ed0dbc24c84bf5b36dd72302114db75b0786b27af1bb73811da3f1fb2a";
+    const PACKED_CHALLENGE: &str = "5cd26d61156b961a67c899fc5d77ed02baf28cceb9fb8b2080866f2eadcd3eae";
+    const PACKED_PROOF: &str = "b8f6e395a25a17349f87f6b101cc13cc014ea285b51803ba27949c9f45fe37f6";
+    const EPOCH: u64 = 2900;
+    // POSITIONS as bodies 0-6, Venus and Saturn retrograde
+    const V2_WORDS: [u64; 7] = [0x0030_3900, 0x015b_a000, 0x0200_0000, 0x038c_a001, 0x0423_2800, 0x0546_5000, 0x0669_7801];
+    const V2_CHALLENGE: &str = "8a7af147e0eb8f8bb54f4058b30acb2392b81aae717add471e14a69e6d96f9fe";
+    const V2_PROOF: &str = "a330d491cea1bfd5ce87ac6e69747596e956612fa15e022ae56e6cb4007dfe9d";
+    // Computed with a Python model of Poseidon2 that reproduces the
+    // reference test vector, see `poseidon2`
+    const V3_CHALLENGE: &str = "1f36098856399c5414cba226699c47008f3d56415ac75390453bda46e554aab0";
+    const V3_PROOF: &str = "235bfce9b796d8e16e2574d913aeb2a6e9fb8f575c0e85fcf3747e97bcdbc243";
+
+    #[test]
+    fn test_legacy_preimage_layout() {
+        let preimage = challenge_preimage("ab", &[1, 0x0102], Encoding::Legacy);
+        assert_eq!(
+            preimage,
+            [
+                b'a', b'b', //
+                1, 0, 0, 0, 0, 0, 0, 0, //
+                2, 1, 0, 0, 0, 0, 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_solidity_packed_preimage_layout() {
+        let preimage = challenge_preimage("ab", &[1, 0x0102], Encoding::SolidityPacked);
+        assert_eq!(preimage.len(), 2 + 2 * 32);
+        assert_eq!(&preimage[..2], b"ab");
+        assert!(preimage[2..33].iter().all(|b| *b == 0));
+        assert_eq!(preimage[33], 1);
+        assert!(preimage[34..64].iter().all(|b| *b == 0));
+        assert_eq!(&preimage[64..], &[1, 2]);
+    }
+
+    #[test]
+    fn test_legacy_fixture() {
+        let challenge = compute_challenge(COMMITMENT, &POSITIONS, Encoding::Legacy);
+        assert_eq!(&to_hex(&challenge), LEGACY_CHALLENGE.as_bytes());
+        assert_eq!(&to_hex(&compute_proof(COMMITMENT, NONCE, &challenge)), LEGACY_PROOF.as_bytes());
+        assert!(verify_zk_proof(&commitment(), LEGACY_PROOF, NONCE, &POSITIONS, Encoding::Legacy));
+    }
+
+    #[test]
+    fn test_solidity_packed_fixture() {
+        let challenge = compute_challenge(COMMITMENT, &POSITIONS, Encoding::SolidityPacked);
+        assert_eq!(&to_hex(&challenge), PACKED_CHALLENGE.as_bytes());
+        assert_eq!(&to_hex(&compute_proof(COMMITMENT, NONCE, &challenge)), PACKED_PROOF.as_bytes());
+        assert!(verify_zk_proof(&commitment(), PACKED_PROOF, NONCE, &POSITIONS, Encoding::SolidityPacked));
+    }
+
+    #[test]
+    fn test_encodings_not_interchangeable() {
+        assert!(!verify_zk_proof(&commitment(), LEGACY_PROOF, NONCE, &POSITIONS, Encoding::SolidityPacked));
+        assert!(!verify_zk_proof(&commitment(), PACKED_PROOF, NONCE, &POSITIONS, Encoding::Legacy));
+    }
+
+    #[test]
+    fn test_uppercase_proof_accepted() {
+        let upper = LEGACY_PROOF.to_ascii_uppercase();
+        assert!(verify_zk_proof(&commitment(), &upper, NONCE, &POSITIONS, Encoding::Legacy));
+    }
+
+    #[test]
+    fn test_dispatch_v1() {
+        let result = verify_dispatch(SCHEME_V1, &commitment(), LEGACY_PROOF, NONCE, &POSITIONS, Encoding::Legacy, EPOCH);
+        assert_eq!(result, Ok(true));
+    }
+
+    #[test]
+    fn test_dispatch_unknown_version() {
+        for version in [0, LATEST_SCHEME_VERSION + 1, u8::MAX] {
+            let result = verify_dispatch(version, &commitment(), LEGACY_PROOF, NONCE, &POSITIONS, Encoding::Legacy, EPOCH);
+            assert_eq!(result, Err(ProofError::UnsupportedProofVersion(version)));
+        }
+    }
+
+    #[test]
+    fn test_v2_fixture() {
+        let (challenge, proof) = compute_proof_v2(COMMITMENT, NONCE, &v2_chart(), EPOCH).unwrap();
+        assert_eq!(&to_hex(&challenge), V2_CHALLENGE.as_bytes());
+        assert_eq!(&to_hex(&proof), V2_PROOF.as_bytes());
+        assert!(verify_zk_proof_v2(&commitment(), V2_PROOF, NONCE, &V2_WORDS, EPOCH));
+        assert_eq!(
+            verify_dispatch(SCHEME_V2, &commitment(), V2_PROOF, NONCE, &V2_WORDS, Encoding::Legacy, EPOCH),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_v1_proof_rejected_by_v2() {
+        assert!(!verify_zk_proof_v2(&commitment(), LEGACY_PROOF, NONCE, &V2_WORDS, EPOCH));
+        assert!(!verify_zk_proof_v2(&commitment(), V2_PROOF, "", &V2_WORDS, EPOCH));
+    }
+
+    #[test]
+    fn test_v2_positions_order_independent() {
+        let mut shuffled = V2_WORDS;
+        shuffled.reverse();
+        assert!(verify_zk_proof_v2(&commitment(), V2_PROOF, NONCE, &shuffled, EPOCH));
+
+        let mut retrograde = V2_WORDS;
+        retrograde[0] |= 1;
+        assert!(!verify_zk_proof_v2(&commitment(), V2_PROOF, NONCE, &retrograde, EPOCH));
+
+        // Plain centidegrees are not valid v2 positions
+        assert!(!verify_zk_proof_v2(&commitment(), V2_PROOF, NONCE, &POSITIONS, EPOCH));
+    }
+
+    #[test]
+    fn test_v2_rejects_duplicate_body() {
+        let mut duplicated = V2_WORDS;
+        duplicated[6] = 0x0000_0000;
+        assert!(!verify_zk_proof_v2(&commitment(), V2_PROOF, NONCE, &duplicated, EPOCH));
+
+        let mut chart = v2_chart();
+        chart[1].body = chart[0].body;
+        assert_eq!(
+            compute_proof_v2(COMMITMENT, NONCE, &chart, EPOCH),
+            Err(PositionError::DuplicateBody(chart[0].body))
+        );
+    }
+
+    #[test]
+    fn test_v2_anchor_binds_block() {
+        let anchor = BlockAnchor { number: 7_000_000, hash: [0x11; 32] };
+        let (_, proof) = compute_proof_v2_anchored(COMMITMENT, NONCE, &v2_chart(), EPOCH, &anchor).unwrap();
+        let proof = to_hex(&proof);
+        let proof = core::str::from_utf8(&proof).unwrap();
+        assert_ne!(proof, V2_PROOF);
+        assert!(verify_zk_proof_v2_anchored(&commitment(), proof, NONCE, &V2_WORDS, EPOCH, &anchor));
+        assert!(!verify_zk_proof_v2(&commitment(), proof, NONCE, &V2_WORDS, EPOCH));
+
+        let other_hash = BlockAnchor { hash: [0x12; 32], ..anchor };
+        assert!(!verify_zk_proof_v2_anchored(&commitment(), proof, NONCE, &V2_WORDS, EPOCH, &other_hash));
+        let other_number = BlockAnchor { number: anchor.number + 1, ..anchor };
+        assert!(!verify_zk_proof_v2_anchored(&commitment(), proof, NONCE, &V2_WORDS, EPOCH, &other_number));
+    }
+
+    #[test]
+    fn test_year_bucket() {
+        assert_eq!(year_bucket(1899), None);
+        assert_eq!(year_bucket(1900), Some(0));
+        assert_eq!(year_bucket(1999), Some(9));
+        assert_eq!(year_bucket(2000), Some(10));
+        assert_eq!(year_bucket(2029), Some(MAX_YEAR_BUCKET));
+        assert_eq!(year_bucket(2030), None);
+        assert_eq!(year_bucket(u16::MAX), None);
+    }
+
+    #[test]
+    fn test_bucket_claim() {
+        let opening = commitment().0;
+        let bucketed = bucket_commitment(9, &opening);
+        assert!(verify_bucket_claim(&bucketed, 9, &opening));
+        assert!(!verify_bucket_claim(&bucketed, 10, &opening));
+        assert!(!verify_bucket_claim(&bucketed, 9, &[0u8; 32]));
+
+        // Out-of-range buckets are rejected even when the hash matches
+        let out_of_range = bucket_commitment(MAX_YEAR_BUCKET + 1, &opening);
+        assert!(!verify_bucket_claim(&out_of_range, MAX_YEAR_BUCKET + 1, &opening));
+    }
+
+    #[test]
+    fn test_v2_bucket_binds_claim() {
+        let (_, proof) = compute_proof_v2_bucketed(COMMITMENT, NONCE, &v2_chart(), EPOCH, 9).unwrap();
+        let proof = to_hex(&proof);
+        let proof = core::str::from_utf8(&proof).unwrap();
+        assert_ne!(proof, V2_PROOF);
+        assert!(verify_zk_proof_v2_bucketed(&commitment(), proof, NONCE, &V2_WORDS, EPOCH, 9));
+        assert!(!verify_zk_proof_v2_bucketed(&commitment(), proof, NONCE, &V2_WORDS, EPOCH, 10));
+        assert!(!verify_zk_proof_v2(&commitment(), proof, NONCE, &V2_WORDS, EPOCH));
+    }
+
+    #[test]
+    fn test_v2_bound_to_epoch() {
+        assert!(!verify_zk_proof_v2(&commitment(), V2_PROOF, NONCE, &V2_WORDS, EPOCH + 1));
+        assert!(!verify_zk_proof_v2(&commitment(), V2_PROOF, NONCE, &V2_WORDS, EPOCH - 1));
+    }
+
+    #[test]
+    fn test_v3_fixture() {
+        let (challenge, proof) = compute_proof_v3(&commitment(), NONCE, &v2_chart(), EPOCH).unwrap();
+        assert_eq!(&to_hex(&challenge), V3_CHALLENGE.as_bytes());
+        assert_eq!(&to_hex(&proof), V3_PROOF.as_bytes());
+        assert_eq!(
+            verify_dispatch(SCHEME_V3, &commitment(), V3_PROOF, NONCE, &V2_WORDS, Encoding::Legacy, EPOCH),
+            Ok(true)
+        );
+
+        let mut shuffled = V2_WORDS;
+        shuffled.reverse();
+        assert!(verify_zk_proof_v3(&commitment(), V3_PROOF, NONCE, &shuffled, EPOCH));
+        assert!(!verify_zk_proof_v3(&commitment(), V3_PROOF, NONCE, &V2_WORDS, EPOCH + 1));
+        assert!(!verify_zk_proof_v3(&commitment(), V3_PROOF, "", &V2_WORDS, EPOCH));
+    }
+
+    // Same inputs under the keccak (v2) and Poseidon2 (v3) schemes
+    #[test]
+    fn test_v2_and_v3_differ() {
+        let v2 = compute_proof_v2(COMMITMENT, NONCE, &v2_chart(), EPOCH).unwrap();
+        let v3 = compute_proof_v3(&commitment(), NONCE, &v2_chart(), EPOCH).unwrap();
+        assert_ne!(v2.0, v3.0);
+        assert_ne!(v2.1, v3.1);
+        assert!(!verify_zk_proof_v3(&commitment(), V2_PROOF, NONCE, &V2_WORDS, EPOCH));
+        assert!(!verify_zk_proof_v2(&commitment(), V3_PROOF, NONCE, &V2_WORDS, EPOCH));
+    }
+
+    #[test]
+    fn test_compute_epoch() {
+        assert_eq!(compute_epoch(0), 0);
+        assert_eq!(compute_epoch(EPOCH_SECONDS - 1), 0);
+        assert_eq!(compute_epoch(EPOCH_SECONDS), 1);
+        assert_eq!(compute_epoch(1_754_000_000), EPOCH);
+    }
+
+    #[test]
+    fn test_check_epoch_window() {
+        assert_eq!(check_epoch(EPOCH, EPOCH), Ok(()));
+        assert_eq!(check_epoch(EPOCH - 1, EPOCH), Ok(()));
+        assert_eq!(check_epoch(EPOCH - 2, EPOCH), Err(ProofError::ProofExpired(EPOCH - 2)));
+        assert_eq!(check_epoch(EPOCH + 1, EPOCH), Err(ProofError::ProofExpired(EPOCH + 1)));
+        assert_eq!(check_epoch(u64::MAX, 0), Err(ProofError::ProofExpired(u64::MAX)));
+    }
+
+    #[test]
+    fn test_packed_verifies_like_unpacked() {
+        let (lo, hi) = pack_positions(&POSITIONS).unwrap();
+        let unpacked = unpack_positions(&[lo, hi], POSITIONS.len() as u8).unwrap();
+        assert!(verify_zk_proof(&commitment(), LEGACY_PROOF, NONCE, &unpacked, Encoding::Legacy));
+    }
+
+    #[test]
+    fn test_encoding_ids_round_trip() {
+        for encoding in [Encoding::Legacy, Encoding::SolidityPacked] {
+            assert_eq!(Encoding::from_id(encoding.id()), Some(encoding));
+            assert_eq!(Encoding::from_name(encoding.name()), Some(encoding));
+        }
+        assert_eq!(Encoding::from_id(2), None);
+    }
+
+    #[test]
+    fn test_empty_inputs_rejected() {
+        assert!(!verify_zk_proof(&commitment(), "", NONCE, &POSITIONS, Encoding::Legacy));
+        assert!(!verify_zk_proof(&commitment(), LEGACY_PROOF, "", &POSITIONS, Encoding::Legacy));
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let long_nonce = "n".repeat(MAX_FIELD_BYTES + 1);
+        assert!(!verify_zk_proof(&commitment(), LEGACY_PROOF, &long_nonce, &POSITIONS, Encoding::Legacy));
+        let many = [0u64; MAX_POSITIONS + 1];
+        assert!(!verify_zk_proof(&commitment(), LEGACY_PROOF, NONCE, &many, Encoding::Legacy));
+        assert!(!verify_zk_proof_v2(&commitment(), V2_PROOF, NONCE, &many, EPOCH));
+        assert_eq!(check_positions(&many), Err(InputError::PositionCount(MAX_POSITIONS + 1)));
+        assert_eq!(check_positions(&many[1..]), Ok(()));
+        assert_eq!(check_positions(&POSITIONS), Ok(()));
+        assert_eq!(check_positions(&POSITIONS[1..]), Err(InputError::PositionCount(MIN_POSITIONS - 1)));
+        assert!(!verify_zk_proof(&commitment(), LEGACY_PROOF, NONCE, &POSITIONS[1..], Encoding::Legacy));
+
+        // A 5 KB nonce and 1,000 positions are turned away, not hashed
+        let nonce = vec![b'n'; 5 * 1024];
+        let positions = vec![0u64; 1000];
+        assert_eq!(
+            ZkProof::from_bytes(COMMITMENT.as_bytes(), LEGACY_PROOF.as_bytes(), &nonce, 0, SCHEME_V1, 0),
+            Err(InputError::FieldTooLong(INPUT_NONCE, nonce.len()))
+        );
+        assert_eq!(check_positions(&positions), Err(InputError::PositionCount(1000)));
+        assert!(!verify_zk_proof(&commitment(), LEGACY_PROOF, NONCE, &positions, Encoding::Legacy));
+    }
+
+    #[test]
+    fn test_proof_from_bytes() {
+        let proof = ZkProof::from_bytes(COMMITMENT.as_bytes(), LEGACY_PROOF.as_bytes(), NONCE.as_bytes(), 0, SCHEME_V1, 0);
+        assert_eq!(proof.unwrap().verify(&POSITIONS), Ok(true));
+
+        let long = [b'a'; MAX_FIELD_BYTES + 1];
+        assert_eq!(
+            ZkProof::from_bytes(COMMITMENT.as_bytes(), LEGACY_PROOF.as_bytes(), &long, 0, SCHEME_V1, 0),
+            Err(InputError::FieldTooLong(INPUT_NONCE, MAX_FIELD_BYTES + 1))
+        );
+        assert_eq!(
+            ZkProof::from_bytes(&long, LEGACY_PROOF.as_bytes(), NONCE.as_bytes(), 0, SCHEME_V1, 0),
+            Err(InputError::FieldTooLong(INPUT_COMMITMENT, MAX_FIELD_BYTES + 1))
+        );
+        assert_eq!(
+            ZkProof::from_bytes(COMMITMENT.as_bytes(), &long, NONCE.as_bytes(), 0, SCHEME_V1, 0),
+            Err(InputError::FieldTooLong(INPUT_PROOF, MAX_FIELD_BYTES + 1))
+        );
+        assert_eq!(
+            ZkProof::from_bytes(b"\xff\xfe", LEGACY_PROOF.as_bytes(), NONCE.as_bytes(), 0, SCHEME_V1, 0),
+            Err(InputError::InvalidUtf8)
+        );
+        assert_eq!(
+            ZkProof::from_bytes(COMMITMENT.as_bytes(), LEGACY_PROOF.as_bytes(), NONCE.as_bytes(), 9, SCHEME_V1, 0),
+            Err(InputError::InvalidEncoding(9))
+        );
+    }
+
+    #[test]
+    fn test_proof_bytes_new() {
+        let commitment = Commitment::from_hex(COMMITMENT).unwrap();
+        let proof = from_hex32(LEGACY_PROOF.as_bytes()).unwrap();
+        let bytes = ProofBytes::new(commitment, proof, NONCE.into(), 0, SCHEME_V1, 0).unwrap();
+        assert_eq!(bytes.verify(&POSITIONS), Ok(true));
+
+        assert_eq!(
+            ProofBytes::new(commitment, proof, vec![b'a'; MAX_FIELD_BYTES + 1], 0, SCHEME_V1, 0),
+            Err(InputError::FieldTooLong(INPUT_NONCE, MAX_FIELD_BYTES + 1))
+        );
+        assert_eq!(
+            ProofBytes::new(commitment, proof, NONCE.into(), 9, SCHEME_V1, 0),
+            Err(InputError::InvalidEncoding(9))
+        );
+    }
+
+    // Arbitrary input must never panic, only be rejected. Fixed seed so
+    // `cargo test` is reproducible.
+    mod fuzz {
+        use super::*;
+        use proptest::prelude::*;
+        use proptest::test_runner::RngSeed;
+
+        fn config() -> ProptestConfig {
+            ProptestConfig { cases: 512, rng_seed: RngSeed::Fixed(0x5a4b_4173_7472_6f00), ..ProptestConfig::default() }
+        }
+
+        fn text() -> impl Strategy<Value = String> {
+            prop_oneof![
+                any::<String>(),
+                "[0-9a-fA-Fx\\x00]{0,70}",
+                (0..3 * MAX_FIELD_BYTES).prop_map(|len| "\0".repeat(len)),
+            ]
+        }
+
+        fn words() -> impl Strategy<Value = Vec<u64>> {
+            prop_oneof![
+                prop::collection::vec(any::<u64>(), 0..2 * MAX_POSITIONS),
+                prop::collection::vec(0u64..0x0d00_0000, 0..2 * MAX_POSITIONS),
+            ]
+        }
+
+        proptest! {
+            #![proptest_config(config())]
+
+            #[test]
+            fn fuzz_proof_from_bytes(
+                commitment in prop::collection::vec(any::<u8>(), 0..80),
+                proof in prop::collection::vec(any::<u8>(), 0..80),
+                nonce in prop::collection::vec(any::<u8>(), 0..2 * MAX_FIELD_BYTES),
+                encoding in any::<u8>(),
+                version in any::<u8>(),
+                epoch in any::<u64>(),
+                positions in words(),
+            ) {
+                if let Ok(proof) = ZkProof::from_bytes(&commitment, &proof, &nonce, encoding, version, epoch) {
+                    let _ = proof.verify_at(&positions, epoch);
+                }
+            }
+
+            #[test]
+            fn fuzz_verify(
+                proof in text(),
+                nonce in text(),
+                positions in words(),
+                epoch in any::<u64>(),
+                legacy in any::<bool>(),
+            ) {
+                let encoding = if legacy { Encoding::Legacy } else { Encoding::SolidityPacked };
+                for version in [SCHEME_V1, SCHEME_V2, SCHEME_V3] {
+                    let verified = verify_dispatch(version, &commitment(), &proof, &nonce, &positions, encoding, epoch);
+                    prop_assert_eq!(verified, Ok(false));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_commitment_case_normalized() {
+        let upper = Commitment::from_hex(&COMMITMENT.to_ascii_uppercase()).unwrap();
+        assert!(verify_zk_proof(&upper, LEGACY_PROOF, NONCE, &POSITIONS, Encoding::Legacy));
+        assert!(verify_zk_proof_v2(&upper, V2_PROOF, NONCE, &V2_WORDS, EPOCH));
+    }
+
+    #[test]
+    fn test_streaming_matches_concatenation() {
+        for encoding in [Encoding::Legacy, Encoding::SolidityPacked] {
+            for positions in [&POSITIONS[..], &[], &[u64::MAX, 0, 1]] {
+                let streamed = compute_challenge(COMMITMENT, positions, encoding);
+                assert_eq!(streamed, keccak256(&challenge_preimage(COMMITMENT, positions, encoding)));
+
+                let mut proof_preimage = Vec::new();
+                proof_preimage.extend_from_slice(COMMITMENT.as_bytes());
+                proof_preimage.extend_from_slice(NONCE.as_bytes());
+                proof_preimage.extend_from_slice(&to_hex(&streamed));
+                assert_eq!(compute_proof(COMMITMENT, NONCE, &streamed), keccak256(&proof_preimage));
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_does_not_allocate() {
+        let commitment = commitment();
+        let v1 = alloc_counter::count(|| {
+            assert!(verify_zk_proof(&commitment, LEGACY_PROOF, NONCE, &POSITIONS, Encoding::Legacy));
+            assert!(verify_zk_proof(&commitment, PACKED_PROOF, NONCE, &POSITIONS, Encoding::SolidityPacked));
+        });
+        let v2 = alloc_counter::count(|| {
+            assert!(verify_zk_proof_v2(&commitment, V2_PROOF, NONCE, &V2_WORDS, EPOCH));
+        });
+        let v3 = alloc_counter::count(|| {
+            assert!(verify_zk_proof_v3(&commitment, V3_PROOF, NONCE, &V2_WORDS, EPOCH));
+        });
+        assert_eq!((v1, v2, v3), (0, 0, 0));
+
+        // Sanity check that the counter sees allocations at all
+        assert!(alloc_counter::count(|| challenge_preimage(COMMITMENT, &POSITIONS, Encoding::Legacy)) > 0);
+    }
+}
+
+/// Per-thread allocation counter for tests
+///
+/// Counts are kept per thread so parallel tests don't interfere.
+#[cfg(test)]
+mod alloc_counter {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOC: CountingAlloc = CountingAlloc;
+
+    /// Number of allocations made by this thread while running `f`
+    pub fn count<T>(f: impl FnOnce() -> T) -> usize {
+        let before = ALLOCATIONS.with(Cell::get);
+        let result = f();
+        let after = ALLOCATIONS.with(Cell::get);
+        drop(result);
+        after - before
+    }
+}
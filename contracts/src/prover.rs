@@ -0,0 +1,718 @@
+//! Off-chain prover mirroring the on-chain verifier
+//!
+//! Executable spec for client implementations: anything produced here is
+//! guaranteed to satisfy `poseidon::verify_zk_proof` (or
+//! `schnorr::verify_schnorr` for Schnorr proofs). Compiled only for tests
+//! and the `prover` feature so the contract WASM stays small.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use stylus_sdk::alloy_primitives::{Address, U256};
+use zkastro_core::birth_data::{self, BirthDataError};
+
+use crate::commitment::{G1Point, G, H};
+use crate::field;
+use crate::poseidon::{
+    bucket_commitment, compute_challenge, compute_proof, compute_proof_v2, compute_proof_v2_anchored,
+    compute_proof_v2_bucketed, keccak256, to_hex, year_bucket, BlockAnchor, Commitment, Encoding, ZkProof, SCHEME_V1,
+    SCHEME_V2,
+};
+#[cfg(test)]
+use crate::poseidon::ProofError;
+#[cfg(any(test, feature = "poseidon2"))]
+use crate::poseidon::{compute_proof_v3, SCHEME_V3};
+use crate::positions::{to_words, PlanetPosition, PositionError};
+use crate::range_proof::{self, BitProof, RangeProof, RatingContext, BITS, MAX_RATING};
+use crate::schnorr::{self, SchnorrProof};
+
+/// Domain for deriving the Schnorr secret from birth data
+const SCHNORR_SECRET_DOMAIN: &[u8] = b"ZKAstro.schnorr.secret";
+
+/// Domain for deriving deterministic Schnorr nonces
+const SCHNORR_NONCE_DOMAIN: &[u8] = b"ZKAstro.schnorr.nonce";
+
+/// Domain for deriving the bit blindings and OR proof nonces of a blinded
+/// rating
+const RATING_DOMAIN: &[u8] = b"ZKAstro.rating.v1";
+
+/// Birth data as collected by the clients
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BirthData {
+    /// Date of birth, `YYYY-MM-DD`
+    pub dob: String,
+    /// Time of birth, `HH:MM`
+    pub tob: String,
+    /// IANA timezone name
+    pub tz: String,
+    /// Latitude in decimal degrees, as the client formats it
+    pub lat: String,
+    /// Longitude in decimal degrees, as the client formats it
+    pub lon: String,
+}
+
+/// Cross-language test vector, serialized to `tests/fixtures/` by the
+/// `gen-fixtures` binary
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fixture {
+    pub name: String,
+    pub proof: ZkProof,
+    pub positions: Vec<u64>,
+    /// Expected challenge hex (lowercase)
+    pub challenge: String,
+    /// Whether `verify_zk_proof` must accept the fixture
+    pub valid: bool,
+}
+
+/// Lowercase hex string of a digest
+fn hex_string(digest: &[u8; 32]) -> String {
+    to_hex(digest).iter().map(|b| char::from(*b)).collect()
+}
+
+/// Generate the commitment to `birth_data`
+///
+/// commitment = keccak256(field_0 || ... || field_4 || salt) where each
+/// field is `u32_be(len) || utf8_bytes` in the order dob, tob, tz, lat, lon.
+pub fn generate_commitment(birth_data: &BirthData, salt: [u8; 32]) -> Commitment {
+    Commitment(keccak256(&birth_data_preimage(birth_data, salt)))
+}
+
+/// Commitment to packed birth data, as a chart's input commitment
+///
+/// commitment = keccak256(packed || salt), see `birth_data`; ChartRegistry's
+/// `verify_birth_commitment` and `reveal_input` accept the 16 packed bytes
+/// with `salt`. Fails for out-of-range fields.
+pub fn generate_packed_commitment(
+    birth_data: &birth_data::BirthData,
+    salt: [u8; 32],
+) -> Result<Commitment, BirthDataError> {
+    birth_data.validate()?;
+    Ok(Commitment(birth_data.commitment(salt)))
+}
+
+/// Bucketed commitment to `birth_data`
+///
+/// Returns `(commitment, bucket, opening)`: the bucket is the decade of the
+/// year in `dob`, the opening the plain [`generate_commitment`] value (see
+/// `poseidon::bucket_commitment`). `None` if `dob` doesn't start with a
+/// year in 1900-2029.
+pub fn generate_bucketed_commitment(birth_data: &BirthData, salt: [u8; 32]) -> Option<(Commitment, u8, [u8; 32])> {
+    let year = birth_data.dob.get(..4)?.parse::<u16>().ok()?;
+    let bucket = year_bucket(year)?;
+    let opening = generate_commitment(birth_data, salt).0;
+    Some((bucket_commitment(bucket, &opening), bucket, opening))
+}
+
+/// Framed birth data fields followed by the salt
+fn birth_data_preimage(birth_data: &BirthData, salt: [u8; 32]) -> Vec<u8> {
+    let fields = [
+        &birth_data.dob,
+        &birth_data.tob,
+        &birth_data.tz,
+        &birth_data.lat,
+        &birth_data.lon,
+    ];
+
+    let mut preimage = Vec::new();
+    for field in fields {
+        preimage.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        preimage.extend_from_slice(field.as_bytes());
+    }
+    preimage.extend_from_slice(&salt);
+    preimage
+}
+
+/// Derive the Schnorr secret x for `birth_data`
+///
+/// x = hash_to_field("ZKAstro.schnorr.secret", framed fields || salt), with
+/// the same framing as [`generate_commitment`].
+pub fn generate_schnorr_secret(birth_data: &BirthData, salt: [u8; 32]) -> U256 {
+    field::hash_to_field(SCHNORR_SECRET_DOMAIN, &birth_data_preimage(birth_data, salt))
+}
+
+/// Schnorr commitment C = x·G
+pub fn generate_schnorr_commitment(secret: U256) -> G1Point {
+    G.mul(secret)
+}
+
+/// Prove knowledge of `secret` for `position_values` and `sender`
+///
+/// The nonce k is derived deterministically from the secret and every
+/// challenge input (as in RFC 6979), so it never repeats across distinct
+/// statements and no RNG is needed.
+pub fn generate_schnorr_proof(secret: U256, position_values: &[u64], sender: Address) -> SchnorrProof {
+    let commitment = generate_schnorr_commitment(secret);
+
+    let mut nonce_input = Vec::new();
+    nonce_input.extend_from_slice(&secret.to_be_bytes::<32>());
+    nonce_input.extend_from_slice(sender.as_slice());
+    for position in position_values {
+        nonce_input.extend_from_slice(&position.to_be_bytes());
+    }
+    let k = field::hash_to_field(SCHNORR_NONCE_DOMAIN, &nonce_input);
+
+    let r = G.mul(k);
+    let e = schnorr::challenge(&commitment, &r, position_values, sender);
+    SchnorrProof { r, s: field::add(k, field::mul(e, secret)) }
+}
+
+/// Rating commitment with its blinding factor and range proof
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlindedRating {
+    /// C = rating·G + blinding·H
+    pub commitment: G1Point,
+    /// Blinding factor, needed to reveal sums containing this rating
+    pub blinding: U256,
+    pub proof: RangeProof,
+}
+
+/// Commit to `rating` and prove it's at most `MAX_RATING`, `None` above
+///
+/// Blindings and nonces are derived from `secret` and `context`, as
+/// Schnorr nonces are, so no RNG is needed; use a fresh secret per rating
+/// that must stay unlinkable.
+pub fn generate_blinded_rating(rating: u8, secret: U256, context: &RatingContext) -> Option<BlindedRating> {
+    if rating > MAX_RATING {
+        return None;
+    }
+    // rating = b0 + 2·(b1 + b2)
+    let half = rating / 2;
+    let bits = [(rating % 2) as u64, (half >= 1) as u64, (half == 2) as u64];
+    Some(prove_rating_bits(bits, secret, context))
+}
+
+/// Commit to `b0 + 2·(b1 + b2)` and prove each `b_i` is a bit
+///
+/// Values other than 0 and 1 yield a proof `range_proof::verify` rejects;
+/// this is public so tests can feed the verifier out-of-range ratings.
+pub fn prove_rating_bits(bits: [u64; BITS], secret: U256, context: &RatingContext) -> BlindedRating {
+    let derive = |index: usize, tag: u8| {
+        let mut input = Vec::new();
+        input.extend_from_slice(&secret.to_be_bytes::<32>());
+        input.extend_from_slice(context.rater.as_slice());
+        input.extend_from_slice(&context.date.to_be_bytes::<32>());
+        input.extend_from_slice(&context.model_id.to_be_bytes());
+        input.extend_from_slice(&[index as u8, tag]);
+        field::hash_to_field(RATING_DOMAIN, &input)
+    };
+
+    let blindings: [U256; BITS] = core::array::from_fn(|i| derive(i, 0));
+    let bit_commitments: [G1Point; BITS] =
+        core::array::from_fn(|i| G.mul(U256::from(bits[i])).add(&H.mul(blindings[i])));
+    let [c0, c1, c2] = bit_commitments;
+    let commitment = c0.add(&c1.add(&c2).mul(U256::from(2)));
+    let blinding = field::add(blindings[0], field::mul(U256::from(2), field::add(blindings[1], blindings[2])));
+
+    let proofs = core::array::from_fn(|i| {
+        // Branch k claims C_i - k·G = r·H; the other one is simulated
+        let branches = [bit_commitments[i], bit_commitments[i].add(&G.neg())];
+        let real = (bits[i] != 0) as usize;
+        let (k, e_fake, z_fake) = (derive(i, 1), derive(i, 2), derive(i, 3));
+        let mut nonces = [G1Point::INFINITY; 2];
+        nonces[real] = H.mul(k);
+        nonces[1 - real] = H.mul(z_fake).add(&branches[1 - real].mul(field::neg(e_fake)));
+
+        let e = range_proof::bit_challenge(&commitment, context, i, &bit_commitments[i], &nonces[0], &nonces[1]);
+        let e_real = field::sub(e, e_fake);
+        let z_real = field::add(k, field::mul(e_real, blindings[i]));
+        let (mut es, mut zs) = ([U256::ZERO; 2], [U256::ZERO; 2]);
+        (es[real], zs[real], es[1 - real], zs[1 - real]) = (e_real, z_real, e_fake, z_fake);
+        BitProof { commitment: bit_commitments[i], e0: es[0], z0: zs[0], e1: es[1], z1: zs[1] }
+    });
+
+    BlindedRating { commitment, blinding, proof: RangeProof { bits: proofs } }
+}
+
+/// Generate a proof for `commitment` over `position_values`
+pub fn generate_proof(
+    commitment: &Commitment,
+    nonce: &str,
+    position_values: &[u64],
+    encoding: Encoding,
+) -> ZkProof {
+    let commitment = commitment.to_hex_lower();
+    let challenge = compute_challenge(&commitment, position_values, encoding);
+    let proof = compute_proof(&commitment, nonce, &challenge);
+
+    ZkProof {
+        commitment,
+        proof: hex_string(&proof),
+        nonce: String::from(nonce),
+        encoding,
+        scheme_version: SCHEME_V1,
+        epoch: 0,
+        anchor: None,
+        claimed_bucket: None,
+    }
+}
+
+/// Generate a v2 (transcript-based) proof for `commitment` over
+/// `positions`, bound to `epoch` (see `poseidon::compute_epoch`)
+///
+/// The encoding field is informational only; v2 ignores it. Verify against
+/// `positions::to_words(positions)`, in any order.
+pub fn generate_proof_v2(
+    commitment: &Commitment,
+    nonce: &str,
+    positions: &[PlanetPosition],
+    epoch: u64,
+) -> Result<ZkProof, PositionError> {
+    let commitment = commitment.to_hex_lower();
+    let (_, proof) = compute_proof_v2(&commitment, nonce, positions, epoch)?;
+
+    Ok(ZkProof {
+        commitment,
+        proof: hex_string(&proof),
+        nonce: String::from(nonce),
+        encoding: Encoding::Legacy,
+        scheme_version: SCHEME_V2,
+        epoch,
+        anchor: None,
+        claimed_bucket: None,
+    })
+}
+
+/// Generate a v2 proof salted with the recent block `anchor`, otherwise as
+/// [`generate_proof_v2`]
+pub fn generate_proof_v2_anchored(
+    commitment: &Commitment,
+    nonce: &str,
+    positions: &[PlanetPosition],
+    epoch: u64,
+    anchor: BlockAnchor,
+) -> Result<ZkProof, PositionError> {
+    let commitment = commitment.to_hex_lower();
+    let (_, proof) = compute_proof_v2_anchored(&commitment, nonce, positions, epoch, &anchor)?;
+
+    Ok(ZkProof {
+        commitment,
+        proof: hex_string(&proof),
+        nonce: String::from(nonce),
+        encoding: Encoding::Legacy,
+        scheme_version: SCHEME_V2,
+        epoch,
+        anchor: Some(anchor),
+        claimed_bucket: None,
+    })
+}
+
+/// Generate a v2 proof bound to a birth-year bucket claim, otherwise as
+/// [`generate_proof_v2`]
+///
+/// `commitment` must be a bucketed commitment, see
+/// [`generate_bucketed_commitment`].
+pub fn generate_proof_v2_bucketed(
+    commitment: &Commitment,
+    nonce: &str,
+    positions: &[PlanetPosition],
+    epoch: u64,
+    bucket: u8,
+) -> Result<ZkProof, PositionError> {
+    let commitment = commitment.to_hex_lower();
+    let (_, proof) = compute_proof_v2_bucketed(&commitment, nonce, positions, epoch, bucket)?;
+
+    Ok(ZkProof {
+        commitment,
+        proof: hex_string(&proof),
+        nonce: String::from(nonce),
+        encoding: Encoding::Legacy,
+        scheme_version: SCHEME_V2,
+        epoch,
+        anchor: None,
+        claimed_bucket: Some(bucket),
+    })
+}
+
+/// Generate a v3 (Poseidon2) proof, otherwise as [`generate_proof_v2`]
+#[cfg(any(test, feature = "poseidon2"))]
+pub fn generate_proof_v3(
+    commitment: &Commitment,
+    nonce: &str,
+    positions: &[PlanetPosition],
+    epoch: u64,
+) -> Result<ZkProof, PositionError> {
+    let (_, proof) = compute_proof_v3(commitment, nonce, positions, epoch)?;
+
+    Ok(ZkProof {
+        commitment: commitment.to_hex_lower(),
+        proof: hex_string(&proof),
+        nonce: String::from(nonce),
+        encoding: Encoding::Legacy,
+        scheme_version: SCHEME_V3,
+        epoch,
+        anchor: None,
+        claimed_bucket: None,
+    })
+}
+
+/// Generate a proof for a fixed sample and run it through the verifier
+///
+/// Returns false if the prover and verifier have drifted apart.
+pub fn self_check() -> bool {
+    let birth_data = BirthData {
+        dob: String::from("1990-01-01"),
+        tob: String::from("12:00"),
+        tz: String::from("UTC"),
+        lat: String::from("51.5074"),
+        lon: String::from("-0.1278"),
+    };
+    let positions = [28045u64, 12030, 30011, 33520, 21077, 4590, 17333];
+    let commitment = generate_commitment(&birth_data, [7u8; 32]);
+
+    let v1 = [Encoding::Legacy, Encoding::SolidityPacked].iter().all(|encoding| {
+        generate_proof(&commitment, "self-check", &positions, *encoding).verify(&positions) == Ok(true)
+    });
+    let chart: Vec<PlanetPosition> = positions
+        .iter()
+        .zip(0u8..)
+        .map(|(longitude, body)| PlanetPosition { body, longitude_centideg: *longitude as u16, is_retrograde: false })
+        .collect();
+    let v2 = generate_proof_v2(&commitment, "self-check", &chart, 0)
+        .is_ok_and(|proof| proof.verify(&to_words(&chart)) == Ok(true));
+    v1 && v2
+}
+
+/// Tampering applied to a generated proof before it becomes a fixture
+type Tamper = fn(&mut ZkProof);
+
+fn keep(_: &mut ZkProof) {}
+
+fn empty_nonce(proof: &mut ZkProof) {
+    proof.nonce.clear();
+}
+
+fn uppercase_proof(proof: &mut ZkProof) {
+    proof.proof.make_ascii_uppercase();
+}
+
+fn truncated_proof(proof: &mut ZkProof) {
+    proof.proof.truncate(63);
+}
+
+fn uppercase_commitment(proof: &mut ZkProof) {
+    proof.commitment.make_ascii_uppercase();
+}
+
+fn prefixed_commitment(proof: &mut ZkProof) {
+    proof.commitment.insert_str(0, "0x");
+}
+
+fn short_commitment(proof: &mut ZkProof) {
+    proof.commitment.truncate(63);
+}
+
+/// The canonical fixture set every client implementation must reproduce
+///
+/// Each case is generated once per encoding, named `<encoding>_<case>`.
+pub fn fixtures() -> Vec<Fixture> {
+    let birth_data = BirthData {
+        dob: String::from("1990-01-01"),
+        tob: String::from("12:00"),
+        tz: String::from("UTC"),
+        lat: String::from("51.5074"),
+        lon: String::from("-0.1278"),
+    };
+    let commitment = generate_commitment(&birth_data, [7u8; 32]);
+
+    let chart: &[u64] = &[28045, 12030, 30011, 33520, 21077, 4590, 17333];
+    let full_chart: &[u64] = &[28045, 12030, 30011, 33520, 21077, 4590, 17333, 9120, 2455, 25010, 13300];
+
+    let cases: [(&str, &[u64], Tamper); 11] = [
+        ("basic", chart, keep),
+        ("min_positions", &[0; 7], keep),
+        ("max_positions", &[36000; 7], keep),
+        ("eleven_bodies", full_chart, keep),
+        ("wide_word", &[0x001f_2e3d_4c5b_6a79, 1, 0, 0, 0, 0, 0], keep),
+        ("empty_nonce", chart, empty_nonce),
+        ("uppercase_proof", chart, uppercase_proof),
+        ("uppercase_commitment", chart, uppercase_commitment),
+        ("prefixed_commitment", chart, prefixed_commitment),
+        ("short_commitment", chart, short_commitment),
+        ("truncated_proof", chart, truncated_proof),
+    ];
+
+    let mut all = Vec::new();
+    for encoding in [Encoding::Legacy, Encoding::SolidityPacked] {
+        for (case, positions, tamper) in cases.iter() {
+            let challenge = compute_challenge(&commitment.to_hex_lower(), positions, encoding);
+            let mut proof = generate_proof(&commitment, "9f8e7d6c", positions, encoding);
+            tamper(&mut proof);
+            let valid = proof.verify(positions) == Ok(true);
+
+            all.push(Fixture {
+                name: format!("{}_{}", encoding.name(), case),
+                proof,
+                positions: positions.to_vec(),
+                challenge: hex_string(&challenge),
+                valid,
+            });
+        }
+    }
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::verify_bucket_claim;
+
+    const POSITIONS: [u64; 7] = [12345, 23456, 0, 36000, 9000, 18000, 27000];
+
+    fn birth_data() -> BirthData {
+        BirthData {
+            dob: String::from("1995-07-14"),
+            tob: String::from("06:30"),
+            tz: String::from("Asia/Kolkata"),
+            lat: String::from("28.6139"),
+            lon: String::from("77.209"),
+        }
+    }
+
+    fn verify(proof: &ZkProof, positions: &[u64]) -> bool {
+        proof.verify(positions) == Ok(true)
+    }
+
+    fn sample_proof(encoding: Encoding) -> ZkProof {
+        let commitment = generate_commitment(&birth_data(), [1u8; 32]);
+        generate_proof(&commitment, "a1b2c3", &POSITIONS, encoding)
+    }
+
+    #[test]
+    fn test_fixture_names_unique() {
+        let all = fixtures();
+        for (i, a) in all.iter().enumerate() {
+            assert!(all[i + 1..].iter().all(|b| b.name != a.name), "duplicate fixture {}", a.name);
+        }
+    }
+
+    #[test]
+    fn test_self_check() {
+        assert!(self_check());
+    }
+
+    #[test]
+    fn test_commitment_format() {
+        let proof = sample_proof(Encoding::Legacy);
+        assert_eq!(proof.commitment.len(), 64);
+        assert!(proof.commitment.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)));
+    }
+
+    #[test]
+    fn test_commitment_depends_on_salt() {
+        assert_ne!(
+            generate_commitment(&birth_data(), [1u8; 32]),
+            generate_commitment(&birth_data(), [2u8; 32])
+        );
+    }
+
+    #[test]
+    fn test_commitment_fields_framed() {
+        let mut shifted = birth_data();
+        shifted.dob.push('0');
+        shifted.tob.remove(0);
+        assert_ne!(
+            generate_commitment(&birth_data(), [1u8; 32]),
+            generate_commitment(&shifted, [1u8; 32])
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert!(verify(&sample_proof(Encoding::Legacy), &POSITIONS));
+        assert!(verify(&sample_proof(Encoding::SolidityPacked), &POSITIONS));
+    }
+
+    #[test]
+    fn test_mutated_commitment_fails() {
+        let mut proof = sample_proof(Encoding::Legacy);
+        let flipped = if proof.commitment.starts_with('0') { "1" } else { "0" };
+        proof.commitment.replace_range(0..1, flipped);
+        assert!(!verify(&proof, &POSITIONS));
+    }
+
+    #[test]
+    fn test_mutated_proof_fails() {
+        let mut proof = sample_proof(Encoding::Legacy);
+        let flipped = if proof.proof.starts_with('0') { "1" } else { "0" };
+        proof.proof.replace_range(0..1, flipped);
+        assert!(!verify(&proof, &POSITIONS));
+    }
+
+    #[test]
+    fn test_mutated_nonce_fails() {
+        let mut proof = sample_proof(Encoding::Legacy);
+        proof.nonce.push('0');
+        assert!(!verify(&proof, &POSITIONS));
+    }
+
+    #[test]
+    fn test_mutated_positions_fail() {
+        let proof = sample_proof(Encoding::Legacy);
+        for i in 0..POSITIONS.len() {
+            let mut positions = POSITIONS;
+            positions[i] += 1;
+            assert!(!verify(&proof, &positions));
+        }
+        assert!(!verify(&proof, &POSITIONS[..6]));
+    }
+
+    #[test]
+    fn test_verify_through_dispatch() {
+        let proof = sample_proof(Encoding::SolidityPacked);
+        assert_eq!(proof.scheme_version, SCHEME_V1);
+        assert_eq!(proof.verify(&POSITIONS), Ok(true));
+    }
+
+    fn chart() -> Vec<PlanetPosition> {
+        POSITIONS
+            .iter()
+            .zip(0u8..)
+            .map(|(longitude, body)| PlanetPosition {
+                body,
+                longitude_centideg: *longitude as u16,
+                is_retrograde: body == 4,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_v2_round_trip() {
+        let commitment = generate_commitment(&birth_data(), [1u8; 32]);
+        let proof = generate_proof_v2(&commitment, "a1b2c3", &chart(), 2900).unwrap();
+        let words = to_words(&chart());
+        assert_eq!(proof.scheme_version, SCHEME_V2);
+        assert_eq!(proof.verify(&words), Ok(true));
+        assert_eq!(proof.verify_at(&words, 2901), Ok(true));
+        assert_eq!(proof.verify_at(&words, 2902), Err(ProofError::ProofExpired(2900)));
+
+        let mut moved = proof.clone();
+        moved.epoch += 1;
+        assert_eq!(moved.verify(&words), Ok(false));
+
+        let mut chart = chart();
+        chart[0].longitude_centideg += 1;
+        assert_eq!(proof.verify(&to_words(&chart)), Ok(false));
+
+        // A v2 proof relabelled as v1 must not verify
+        let mut relabelled = proof.clone();
+        relabelled.scheme_version = SCHEME_V1;
+        assert_eq!(relabelled.verify(&words), Ok(false));
+    }
+
+    #[test]
+    fn test_v2_rejects_duplicate_body() {
+        let commitment = generate_commitment(&birth_data(), [1u8; 32]);
+        let mut chart = chart();
+        chart[2].body = 0;
+        assert_eq!(
+            generate_proof_v2(&commitment, "a1b2c3", &chart, 2900),
+            Err(PositionError::DuplicateBody(0))
+        );
+    }
+
+    #[test]
+    fn test_v2_anchored_round_trip() {
+        let commitment = generate_commitment(&birth_data(), [1u8; 32]);
+        let anchor = BlockAnchor { number: 1_000, hash: [0x5a; 32] };
+        let proof = generate_proof_v2_anchored(&commitment, "a1b2c3", &chart(), 2900, anchor).unwrap();
+        let words = to_words(&chart());
+        assert_eq!(proof.verify_at(&words, 2900), Ok(true));
+
+        let mut unanchored = proof.clone();
+        unanchored.anchor = None;
+        assert_eq!(unanchored.verify(&words), Ok(false));
+
+        let mut other_block = proof.clone();
+        other_block.anchor = Some(BlockAnchor { number: 1_001, ..anchor });
+        assert_eq!(other_block.verify(&words), Ok(false));
+
+        let mut relabelled = proof.clone();
+        relabelled.scheme_version = SCHEME_V1;
+        assert_eq!(relabelled.verify(&words), Ok(false));
+    }
+
+    #[test]
+    fn test_bucketed_round_trip() {
+        let (commitment, bucket, opening) = generate_bucketed_commitment(&birth_data(), [1u8; 32]).unwrap();
+        assert_eq!(bucket, 9);
+        assert!(verify_bucket_claim(&commitment, bucket, &opening));
+        assert!(!verify_bucket_claim(&commitment, bucket + 1, &opening));
+
+        let proof = generate_proof_v2_bucketed(&commitment, "a1b2c3", &chart(), 2900, bucket).unwrap();
+        let words = to_words(&chart());
+        assert_eq!(proof.verify(&words), Ok(true));
+
+        let mut other_bucket = proof.clone();
+        other_bucket.claimed_bucket = Some(bucket + 1);
+        assert_eq!(other_bucket.verify(&words), Ok(false));
+        let mut unbucketed = proof.clone();
+        unbucketed.claimed_bucket = None;
+        assert_eq!(unbucketed.verify(&words), Ok(false));
+
+        let mut undated = birth_data();
+        undated.dob = String::from("unknown");
+        assert_eq!(generate_bucketed_commitment(&undated, [1u8; 32]), None);
+    }
+
+    #[test]
+    fn test_v3_round_trip() {
+        let commitment = generate_commitment(&birth_data(), [1u8; 32]);
+        let proof = generate_proof_v3(&commitment, "a1b2c3", &chart(), 2900).unwrap();
+        let words = to_words(&chart());
+        assert_eq!(proof.scheme_version, SCHEME_V3);
+        assert_eq!(proof.verify_at(&words, 2900), Ok(true));
+        assert_eq!(proof.verify_at(&words, 2902), Err(ProofError::ProofExpired(2900)));
+
+        // Not interchangeable with v2
+        let mut relabelled = proof.clone();
+        relabelled.scheme_version = SCHEME_V2;
+        assert_eq!(relabelled.verify(&words), Ok(false));
+    }
+
+    #[test]
+    fn test_schnorr_round_trip() {
+        let sender = Address::repeat_byte(0x42);
+        let secret = generate_schnorr_secret(&birth_data(), [1u8; 32]);
+        let commitment = generate_schnorr_commitment(secret);
+        let proof = generate_schnorr_proof(secret, &POSITIONS, sender);
+        assert!(schnorr::verify_schnorr(&commitment, &proof, &POSITIONS, sender));
+
+        // Bound to the sender and the positions
+        assert!(!schnorr::verify_schnorr(&commitment, &proof, &POSITIONS, Address::ZERO));
+        assert!(!schnorr::verify_schnorr(&commitment, &proof, &POSITIONS[..6], sender));
+    }
+
+    #[test]
+    fn test_schnorr_forged_response_fails() {
+        let sender = Address::repeat_byte(0x42);
+        let secret = generate_schnorr_secret(&birth_data(), [1u8; 32]);
+        let commitment = generate_schnorr_commitment(secret);
+        let proof = generate_schnorr_proof(secret, &POSITIONS, sender);
+
+        let forged = SchnorrProof { s: field::add(proof.s, U256::from(1)), ..proof };
+        assert!(!schnorr::verify_schnorr(&commitment, &forged, &POSITIONS, sender));
+
+        // Same s shifted by n is non-canonical
+        let shifted = SchnorrProof { s: proof.s + crate::commitment::GROUP_ORDER, ..proof };
+        assert!(!schnorr::verify_schnorr(&commitment, &shifted, &POSITIONS, sender));
+
+        // A proof for another secret doesn't transfer
+        let other = generate_schnorr_proof(field::add(secret, U256::from(1)), &POSITIONS, sender);
+        assert!(!schnorr::verify_schnorr(&commitment, &other, &POSITIONS, sender));
+    }
+
+    #[test]
+    fn test_schnorr_secret_depends_on_salt() {
+        assert_ne!(
+            generate_schnorr_secret(&birth_data(), [1u8; 32]),
+            generate_schnorr_secret(&birth_data(), [2u8; 32])
+        );
+    }
+
+    #[test]
+    fn test_mutated_encoding_fails() {
+        let mut proof = sample_proof(Encoding::Legacy);
+        proof.encoding = Encoding::SolidityPacked;
+        assert!(!verify(&proof, &POSITIONS));
+    }
+}
@@ -0,0 +1,252 @@
+//! Range proof that a Pedersen commitment holds a rating from 0 to 5
+//!
+//! The rating v is split over three bits as `v = b0 + 2·b1 + 2·b2`, which
+//! reaches exactly 0..=5. The prover commits to each bit,
+//! `C_i = b_i·G + r_i·H` (see `commitment`), and the verifier checks:
+//!
+//! - `C == C_0 + 2·(C_1 + C_2)`, so the rating commitment C is the bits'
+//!   weighted sum, with blinding `r_0 + 2·(r_1 + r_2)`
+//! - for each bit, an OR proof (Cramer-Damgård-Schoenmakers) that `C_i` or
+//!   `C_i - G` is a multiple of H, i.e. that `b_i` is 0 or 1:
+//!   `(e_0, z_0, e_1, z_1)` with `A_k = z_k·H - e_k·(C_i - k·G)` and
+//!   `e_0 + e_1 == e`, the challenge over `A_0` and `A_1`
+//!
+//! Challenges come from a [`Transcript`] over C, the bit and the
+//! [`RatingContext`], so a proof can't be replayed for another rater,
+//! date or model. A proof is encoded as three 160-byte bit proofs,
+//! `compress(C_i) || e_0 || z_0 || e_1 || z_1`, 32 bytes each, big-endian.
+//!
+//! The group operations go through [`G1Ops`], as in `schnorr`.
+
+use alloc::vec::Vec;
+
+use stylus_sdk::alloy_primitives::{Address, FixedBytes, U256};
+
+#[cfg(any(test, feature = "prover"))]
+use crate::commitment::Native;
+use crate::commitment::{G1Ops, G1Point, G, GROUP_ORDER, H};
+use crate::field;
+use crate::poseidon::Transcript;
+
+/// Transcript domain of the bit challenges
+pub const RANGE_DOMAIN: &[u8] = b"ZKAstro.rating-range.v1";
+
+/// Largest rating a proof can cover
+pub const MAX_RATING: u8 = 5;
+
+/// Bits a rating is split into
+pub const BITS: usize = 3;
+
+/// Weight of each bit in the rating
+pub const BIT_WEIGHTS: [u8; BITS] = [1, 2, 2];
+
+/// Length of one encoded bit proof
+pub const BIT_PROOF_LEN: usize = 160;
+
+/// Length of an encoded range proof
+pub const PROOF_LEN: usize = BITS * BIT_PROOF_LEN;
+
+/// Commitment to one bit and its OR proof
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitProof {
+    /// C_i = b_i·G + r_i·H
+    pub commitment: G1Point,
+    /// Challenge and response of the `b_i = 0` branch
+    pub e0: U256,
+    pub z0: U256,
+    /// Challenge and response of the `b_i = 1` branch
+    pub e1: U256,
+    pub z1: U256,
+}
+
+/// Proof that a commitment holds a rating from 0 to [`MAX_RATING`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangeProof {
+    pub bits: [BitProof; BITS],
+}
+
+/// What a proof is bound to besides the rating commitment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RatingContext {
+    /// Account rating its prediction
+    pub rater: Address,
+    /// Date of the rated prediction
+    pub date: U256,
+    /// Model whose aggregate the rating joins
+    pub model_id: u32,
+}
+
+impl RangeProof {
+    /// Encode as [`PROOF_LEN`] bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PROOF_LEN);
+        for bit in &self.bits {
+            bytes.extend_from_slice(&bit.commitment.compress().to_be_bytes::<32>());
+            for scalar in [bit.e0, bit.z0, bit.e1, bit.z1] {
+                bytes.extend_from_slice(&scalar.to_be_bytes::<32>());
+            }
+        }
+        bytes
+    }
+
+    /// Decode [`RangeProof::to_bytes`], rejecting points off the curve and
+    /// scalars not below the group order
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != PROOF_LEN {
+            return None;
+        }
+        let word = |offset: usize| U256::from_be_slice(&bytes[offset..offset + 32]);
+        let mut bits = [BitProof {
+            commitment: G1Point::INFINITY,
+            e0: U256::ZERO,
+            z0: U256::ZERO,
+            e1: U256::ZERO,
+            z1: U256::ZERO,
+        }; BITS];
+        for (i, bit) in bits.iter_mut().enumerate() {
+            let at = i * BIT_PROOF_LEN;
+            let scalars = [word(at + 32), word(at + 64), word(at + 96), word(at + 128)];
+            if scalars.iter().any(|scalar| *scalar >= GROUP_ORDER) {
+                return None;
+            }
+            *bit = BitProof {
+                commitment: G1Point::decompress(word(at))?,
+                e0: scalars[0],
+                z0: scalars[1],
+                e1: scalars[2],
+                z1: scalars[3],
+            };
+        }
+        Some(RangeProof { bits })
+    }
+}
+
+/// Challenge e of bit `index`, from its commitment and the OR proof's
+/// nonce commitments `a0` and `a1`
+pub fn bit_challenge(
+    commitment: &G1Point,
+    context: &RatingContext,
+    index: usize,
+    bit_commitment: &G1Point,
+    a0: &G1Point,
+    a1: &G1Point,
+) -> U256 {
+    let mut transcript = Transcript::new(RANGE_DOMAIN);
+    transcript.append_bytes(b"commitment", &commitment.to_bytes());
+    transcript.append_address(b"rater", context.rater);
+    transcript.append_bytes(b"date", &context.date.to_be_bytes::<32>());
+    transcript.append_u64s(b"model", &[context.model_id as u64, index as u64]);
+    transcript.append_bytes(b"C_i", &bit_commitment.to_bytes());
+    transcript.append_bytes(b"A0", &a0.to_bytes());
+    transcript.append_bytes(b"A1", &a1.to_bytes());
+    field::reduce_to_field(FixedBytes(transcript.challenge_bytes32(b"e")))
+}
+
+/// `z·H - e·point`, a branch's nonce commitment
+fn branch_nonce(ops: &impl G1Ops, point: &G1Point, e: U256, z: U256) -> Option<G1Point> {
+    let z_h = ops.ec_mul(&H, z)?;
+    let e_point = ops.ec_mul(point, field::neg(e))?;
+    ops.ec_add(&z_h, &e_point)
+}
+
+/// Verify `proof` for `commitment` with the group operations of `ops`
+///
+/// Rejects points off the curve and the point at infinity as commitment.
+pub fn verify_with(ops: &impl G1Ops, commitment: &G1Point, proof: &RangeProof, context: &RatingContext) -> bool {
+    if commitment.is_infinity() || !commitment.is_on_curve() {
+        return false;
+    }
+    for (index, bit) in proof.bits.iter().enumerate() {
+        if !bit.commitment.is_on_curve() {
+            return false;
+        }
+        let Some(shifted) = ops.ec_add(&bit.commitment, &G.neg()) else {
+            return false;
+        };
+        let a0 = branch_nonce(ops, &bit.commitment, bit.e0, bit.z0);
+        let a1 = branch_nonce(ops, &shifted, bit.e1, bit.z1);
+        let (Some(a0), Some(a1)) = (a0, a1) else {
+            return false;
+        };
+        let e = bit_challenge(commitment, context, index, &bit.commitment, &a0, &a1);
+        if field::add(bit.e0, bit.e1) != e {
+            return false;
+        }
+    }
+
+    // C_0 + 2·(C_1 + C_2), following BIT_WEIGHTS
+    let [c0, c1, c2] = proof.bits.map(|bit| bit.commitment);
+    let weighted = ops
+        .ec_add(&c1, &c2)
+        .and_then(|sum| ops.ec_mul(&sum, U256::from(BIT_WEIGHTS[1])))
+        .and_then(|sum| ops.ec_add(&c0, &sum));
+    weighted == Some(*commitment)
+}
+
+/// Verify `proof` with the pure Rust group operations
+#[cfg(any(test, feature = "prover"))]
+pub fn verify(commitment: &G1Point, proof: &RangeProof, context: &RatingContext) -> bool {
+    verify_with(&Native, commitment, proof, context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover;
+
+    fn context() -> RatingContext {
+        RatingContext { rater: Address::repeat_byte(0xa1), date: U256::from(1_728_000_000u64), model_id: 7 }
+    }
+
+    #[test]
+    fn test_every_rating_proves() {
+        for rating in 0..=MAX_RATING {
+            let blinded = prover::generate_blinded_rating(rating, U256::from(99), &context()).unwrap();
+            assert!(verify(&blinded.commitment, &blinded.proof, &context()), "rating {rating}");
+            assert_eq!(blinded.commitment, G.mul(U256::from(rating)).add(&H.mul(blinded.blinding)));
+        }
+        assert!(prover::generate_blinded_rating(MAX_RATING + 1, U256::from(99), &context()).is_none());
+    }
+
+    #[test]
+    fn test_out_of_range_rejected() {
+        // 6 = 0 + 2·1 + 2·2: the last "bit" is 2, whose OR proof fails
+        let forged = prover::prove_rating_bits([0, 1, 2], U256::from(99), &context());
+        assert_eq!(forged.commitment, G.mul(U256::from(6)).add(&H.mul(forged.blinding)));
+        assert!(!verify(&forged.commitment, &forged.proof, &context()));
+
+        // Honest bits under a commitment to another value
+        let honest = prover::generate_blinded_rating(5, U256::from(99), &context()).unwrap();
+        let shifted = honest.commitment.add(&G);
+        assert!(!verify(&shifted, &honest.proof, &context()));
+    }
+
+    #[test]
+    fn test_bound_to_context() {
+        let blinded = prover::generate_blinded_rating(3, U256::from(5), &context()).unwrap();
+        for other in [
+            RatingContext { rater: Address::repeat_byte(0xb0), ..context() },
+            RatingContext { date: context().date + U256::from(86_400), ..context() },
+            RatingContext { model_id: 8, ..context() },
+        ] {
+            assert!(!verify(&blinded.commitment, &blinded.proof, &other));
+        }
+
+        let mut tampered = blinded.proof;
+        tampered.bits[1].z0 = field::add(tampered.bits[1].z0, U256::from(1));
+        assert!(!verify(&blinded.commitment, &tampered, &context()));
+    }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        let blinded = prover::generate_blinded_rating(4, U256::from(5), &context()).unwrap();
+        let bytes = blinded.proof.to_bytes();
+        assert_eq!(bytes.len(), PROOF_LEN);
+        assert_eq!(RangeProof::from_bytes(&bytes), Some(blinded.proof));
+
+        assert_eq!(RangeProof::from_bytes(&bytes[1..]), None);
+        let mut unreduced = bytes.clone();
+        unreduced[32..64].copy_from_slice(&GROUP_ORDER.to_be_bytes::<32>());
+        assert_eq!(RangeProof::from_bytes(&unreduced), None);
+    }
+}
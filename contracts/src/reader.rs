@@ -0,0 +1,420 @@
+//! ZkAstroReader - one call per frontend screen
+//!
+//! A screen of the app reads from FarcasterPredictions, the chart registry
+//! and the ephemeris oracle; on a slow network each `eth_call` is a round
+//! trip. The reader holds the three addresses and assembles a screen from
+//! static calls to them in a single call. A contract that is unset, reverts
+//! or returns something undecodable leaves its part of the screen zeroed
+//! instead of failing the whole screen.
+//!
+//! The crate has a single deployable entrypoint; build with the `reader`
+//! feature to make this contract the entrypoint instead of
+//! `FarcasterPredictions`.
+
+use alloc::vec::Vec;
+
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256},
+    prelude::*,
+    stylus_core::calls::context::Call,
+};
+
+use crate::astro;
+use crate::calendar;
+use crate::ephemeris::IEphemerisOracle;
+use crate::IChartRegistry;
+
+type B32 = FixedBytes<32>;
+
+/// `get_home_screen` result: (stats, todays_prediction, todays_rating,
+/// has_verified_chart, moon_phase)
+type HomeScreen = (UserStats, B32, u8, bool, u8);
+
+/// Days `get_profile_screen` covers
+pub const PROFILE_DAYS: u64 = 7;
+
+/// Most of a user's charts, newest first, checked for a verified one
+pub const MAX_CHARTS_CHECKED: usize = 8;
+
+sol_declarations! {
+    pub(crate) const DECLARATIONS;
+
+    /// A user's prediction and rating totals, as `getUserStats` of
+    /// FarcasterPredictions returns them
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct UserStats {
+        uint256 totalPredictions;
+        uint256 totalRatings;
+        uint256 averageRatingX10;
+    }
+
+    /// A user's profile and one week of their predictions; `hashes`,
+    /// `ratings` and `hasPositions` are indexed by day of the week
+    #[derive(Debug, PartialEq, Eq)]
+    struct ProfileScreen {
+        UserStats stats;
+        uint256 points;
+        uint256 weekStart;
+        bytes32[] hashes;
+        uint8[] ratings;
+        bool[] hasPositions;
+        uint256 chartCount;
+        bool hasVerifiedChart;
+    }
+}
+
+sol! {
+    /// Views of FarcasterPredictions the screens read
+    interface IFarcasterPredictions {
+        function getUserStats(address user) external view returns (uint256, uint256, uint256);
+        function getPrediction(address user, uint256 date) external view returns (bytes32);
+        function getRating(address user, uint256 date) external view returns (uint8);
+        function getPoints(address user) external view returns (uint256);
+    }
+}
+
+sol_storage! {
+    #[cfg_attr(feature = "reader", entrypoint)]
+    pub struct ZkAstroReader {
+        // Contract owner (set once by initialize), and the flag it sets
+        address owner;
+        bool initialized;
+
+        // Contracts read from; zero while unset
+        address predictions;
+        address chart_registry;
+        address ephemeris_oracle;
+    }
+}
+
+#[public]
+impl ZkAstroReader {
+    /// Initialize the contract (once), setting its owner
+    pub fn initialize(&mut self, owner: Address) -> Result<(), Vec<u8>> {
+        if self.initialized.get() {
+            return Err(b"AlreadyInitialized".to_vec());
+        }
+        if owner == Address::ZERO {
+            return Err(b"InvalidOwner".to_vec());
+        }
+
+        self.initialized.set(true);
+        self.owner.set(owner);
+        Ok(())
+    }
+
+    /// Get the contract owner
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Set the contracts the screens read from (owner only); zero unsets
+    /// one, zeroing its part of every screen
+    pub fn set_contracts(
+        &mut self,
+        predictions: Address,
+        chart_registry: Address,
+        ephemeris_oracle: Address,
+    ) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.owner.get() {
+            return Err(b"NotOwner".to_vec());
+        }
+        self.predictions.set(predictions);
+        self.chart_registry.set(chart_registry);
+        self.ephemeris_oracle.set(ephemeris_oracle);
+        Ok(())
+    }
+
+    /// Get the contracts the screens read from
+    /// Returns: (predictions, chart_registry, ephemeris_oracle)
+    pub fn get_contracts(&self) -> (Address, Address, Address) {
+        (self.predictions.get(), self.chart_registry.get(), self.ephemeris_oracle.get())
+    }
+
+    /// Everything the home screen shows for `user` today (UTC)
+    ///
+    /// The moon phase is computed here, it needs no other contract.
+    /// Returns: (stats, todays_prediction, todays_rating,
+    /// has_verified_chart, moon_phase 0-7 from new moon)
+    pub fn get_home_screen(&self, user: Address) -> HomeScreen {
+        let now = self.vm().block_timestamp();
+        let today = U256::from(calendar::align_to_midnight_utc(now));
+        let predictions = self.predictions.get();
+
+        let prediction = self
+            .view(predictions, IFarcasterPredictions::getPredictionCall { user, date: today })
+            .map_or(B32::ZERO, |prediction| prediction._0);
+        let rating = self
+            .view(predictions, IFarcasterPredictions::getRatingCall { user, date: today })
+            .map_or(0, |rating| rating._0);
+        let (moon_phase, _) = astro::moon_phase(now);
+
+        (self.user_stats(user), prediction, rating, self.verified_chart(user).1, moon_phase)
+    }
+
+    /// Everything the profile screen shows for `user` and the week
+    /// starting on the day of `week_start`
+    ///
+    /// Parameters:
+    /// - week_start: Timestamp in the first day of the week
+    pub fn get_profile_screen(&self, user: Address, week_start: U256) -> Result<ProfileScreen, Vec<u8>> {
+        let start = u64::try_from(week_start)
+            .ok()
+            .filter(|start| start.checked_add(PROFILE_DAYS * calendar::SECONDS_PER_DAY).is_some())
+            .ok_or(b"InvalidDate".to_vec())?;
+        let start = calendar::align_to_midnight_utc(start);
+        let predictions = self.predictions.get();
+        let oracle = self.ephemeris_oracle.get();
+
+        let mut hashes = Vec::new();
+        let mut ratings = Vec::new();
+        let mut has_positions = Vec::new();
+        for day in 0..PROFILE_DAYS {
+            let date = U256::from(start + day * calendar::SECONDS_PER_DAY);
+            let hash = self
+                .view(predictions, IFarcasterPredictions::getPredictionCall { user, date })
+                .map_or(B32::ZERO, |prediction| prediction._0);
+            let rating = if hash.is_zero() {
+                0
+            } else {
+                self.view(predictions, IFarcasterPredictions::getRatingCall { user, date })
+                    .map_or(0, |rating| rating._0)
+            };
+            hashes.push(hash);
+            ratings.push(rating);
+            has_positions.push(
+                self.view(oracle, IEphemerisOracle::hasPositionsCall { date })
+                    .is_some_and(|published| published._0),
+            );
+        }
+
+        let (chart_count, has_verified_chart) = self.verified_chart(user);
+        Ok(ProfileScreen {
+            stats: self.user_stats(user),
+            points: self
+                .view(predictions, IFarcasterPredictions::getPointsCall { user })
+                .map_or(U256::ZERO, |points| points._0),
+            weekStart: U256::from(start),
+            hashes,
+            ratings,
+            hasPositions: has_positions,
+            chartCount: U256::from(chart_count),
+            hasVerifiedChart: has_verified_chart,
+        })
+    }
+}
+
+impl ZkAstroReader {
+    /// Static call `call` on `target`; `None` if `target` is unset, the
+    /// call reverts or its result doesn't decode
+    fn view<C: SolCall>(&self, target: Address, call: C) -> Option<C::Return> {
+        if target == Address::ZERO {
+            return None;
+        }
+        let output = self.vm().static_call(&Call::new(), target, &call.abi_encode()).ok()?;
+        C::abi_decode_returns(&output, true).ok()
+    }
+
+    /// `user`'s stats from FarcasterPredictions, zero when unavailable
+    fn user_stats(&self, user: Address) -> UserStats {
+        self.view(self.predictions.get(), IFarcasterPredictions::getUserStatsCall { user })
+            .map(|stats| UserStats { totalPredictions: stats._0, totalRatings: stats._1, averageRatingX10: stats._2 })
+            .unwrap_or_default()
+    }
+
+    /// Number of `user`'s charts, and whether one of the newest
+    /// `MAX_CHARTS_CHECKED` is ZK verified
+    fn verified_chart(&self, user: Address) -> (usize, bool) {
+        let registry = self.chart_registry.get();
+        let Some(charts) = self.view(registry, IChartRegistry::getUserChartsCall { user }) else {
+            return (0, false);
+        };
+        let count = charts.chartIds.len();
+        let verified = charts.chartIds.into_iter().rev().take(MAX_CHARTS_CHECKED).any(|chart_id| {
+            self.view(registry, IChartRegistry::isZkVerifiedCall { chartId: chart_id })
+                .is_some_and(|verified| verified._0)
+        });
+        (count, verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use stylus_sdk::abi::Router;
+    use stylus_sdk::testing::*;
+    use zkastro_core::sdk::IZkAstroReader;
+
+    // Routing needs top-level storage, which only the deployed entrypoint gets
+    #[cfg(not(feature = "reader"))]
+    unsafe impl TopLevelStorage for ZkAstroReader {}
+
+    const DAY: u64 = calendar::SECONDS_PER_DAY;
+    const PREDICTIONS: Address = Address::repeat_byte(0x0a);
+    const REGISTRY: Address = Address::repeat_byte(0x0b);
+    const ORACLE: Address = Address::repeat_byte(0x0c);
+
+    fn reader(vm: &TestVM) -> ZkAstroReader {
+        let mut reader = ZkAstroReader::from(vm);
+        reader.initialize(vm.msg_sender()).unwrap();
+        reader.set_contracts(PREDICTIONS, REGISTRY, ORACLE).unwrap();
+        reader
+    }
+
+    /// Make a static call of `Interface::fnCall { .. }` on `$target`
+    /// return `$ret`
+    macro_rules! mock {
+        ($vm:expr, $target:expr, $iface:ident::$call:ident { $($args:tt)* } => $ret:expr) => {
+            $vm.mock_static_call(
+                $target,
+                $iface::$call { $($args)* }.abi_encode(),
+                Ok($iface::$call::abi_encode_returns(&$ret)),
+            )
+        };
+    }
+
+    /// Mock `user`'s stats and points in FarcasterPredictions, and their
+    /// `(chart_id, zk_verified)` charts in the registry, newest last
+    fn mock_user(vm: &TestVM, user: Address, charts: &[(&str, bool)]) {
+        mock!(vm, PREDICTIONS, IFarcasterPredictions::getUserStatsCall { user } => (U256::from(3), U256::from(2), U256::from(45)));
+        mock!(vm, PREDICTIONS, IFarcasterPredictions::getPointsCall { user } => (U256::from(17),));
+        let chart_ids: Vec<String> = charts.iter().map(|(chart_id, _)| String::from(*chart_id)).collect();
+        mock!(vm, REGISTRY, IChartRegistry::getUserChartsCall { user } => (chart_ids,));
+        for (chart_id, verified) in charts {
+            mock!(vm, REGISTRY, IChartRegistry::isZkVerifiedCall { chartId: String::from(*chart_id) } => (*verified,));
+        }
+    }
+
+    #[test]
+    fn test_initialize_once() {
+        let vm = TestVM::default();
+        let mut reader = ZkAstroReader::from(&vm);
+        let owner = Address::repeat_byte(0x0a);
+
+        assert_eq!(reader.initialize(Address::ZERO), Err(b"InvalidOwner".to_vec()));
+        reader.initialize(owner).unwrap();
+        assert_eq!(reader.owner(), owner);
+
+        // Neither a later caller nor the owner can take it over again
+        vm.set_sender(Address::repeat_byte(0x66));
+        assert_eq!(reader.initialize(Address::repeat_byte(0x66)), Err(b"AlreadyInitialized".to_vec()));
+        vm.set_sender(owner);
+        assert_eq!(reader.initialize(owner), Err(b"AlreadyInitialized".to_vec()));
+        assert_eq!(reader.owner(), owner);
+    }
+
+    #[test]
+    fn test_owner_sets_contracts() {
+        let vm = TestVM::default();
+        let mut reader = reader(&vm);
+        assert_eq!(reader.get_contracts(), (PREDICTIONS, REGISTRY, ORACLE));
+
+        vm.set_sender(Address::repeat_byte(0x0e));
+        assert_eq!(reader.set_contracts(Address::ZERO, Address::ZERO, Address::ZERO), Err(b"NotOwner".to_vec()));
+        assert_eq!(reader.get_contracts(), (PREDICTIONS, REGISTRY, ORACLE));
+    }
+
+    #[test]
+    fn test_home_screen() {
+        let vm = TestVM::default();
+        let reader = reader(&vm);
+        let user = Address::repeat_byte(0x01);
+        let now = 20_000 * DAY + 3600;
+        let today = U256::from(20_000 * DAY);
+        vm.set_block_timestamp(now);
+
+        mock_user(&vm, user, &[("verified", true), ("pending", false)]);
+        mock!(vm, PREDICTIONS, IFarcasterPredictions::getPredictionCall { user, date: today } => (B32::repeat_byte(7),));
+        mock!(vm, PREDICTIONS, IFarcasterPredictions::getRatingCall { user, date: today } => (4,));
+
+        let stats = UserStats { totalPredictions: U256::from(3), totalRatings: U256::from(2), averageRatingX10: U256::from(45) };
+        let moon_phase = astro::moon_phase(now).0;
+        assert_eq!(reader.get_home_screen(user), (stats, B32::repeat_byte(7), 4, true, moon_phase));
+
+        // Only the newest charts count towards a verified one
+        let other = Address::repeat_byte(0x02);
+        let mut charts = vec![("old", true)];
+        charts.extend([("new", false); MAX_CHARTS_CHECKED]);
+        mock_user(&vm, other, &charts);
+        assert!(!reader.get_home_screen(other).3);
+    }
+
+    #[test]
+    fn test_profile_screen() {
+        let vm = TestVM::default();
+        let reader = reader(&vm);
+        let user = Address::repeat_byte(0x01);
+        let start = 20_000 * DAY;
+
+        mock_user(&vm, user, &[("natal", false)]);
+        for (day, rating) in [(0, 5), (2, 0)] {
+            let date = U256::from(start + day * DAY);
+            mock!(vm, PREDICTIONS, IFarcasterPredictions::getPredictionCall { user, date } => (B32::repeat_byte(day as u8 + 1),));
+            mock!(vm, PREDICTIONS, IFarcasterPredictions::getRatingCall { user, date } => (rating,));
+        }
+        for day in [0, 1, 6] {
+            mock!(vm, ORACLE, IEphemerisOracle::hasPositionsCall { date: U256::from(start + day * DAY) } => (true,));
+        }
+
+        // Any time in the first day starts the week at its midnight
+        let screen = reader.get_profile_screen(user, U256::from(start + 5000)).unwrap();
+        assert_eq!(screen.stats.averageRatingX10, U256::from(45));
+        assert_eq!(screen.points, U256::from(17));
+        assert_eq!(screen.weekStart, U256::from(start));
+        assert_eq!(
+            screen.hashes,
+            [B32::repeat_byte(1), B32::ZERO, B32::repeat_byte(3), B32::ZERO, B32::ZERO, B32::ZERO, B32::ZERO]
+        );
+        assert_eq!(screen.ratings, [5, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(screen.hasPositions, [true, true, false, false, false, false, true]);
+        assert_eq!((screen.chartCount, screen.hasVerifiedChart), (U256::from(1), false));
+
+        assert_eq!(reader.get_profile_screen(user, U256::MAX), Err(b"InvalidDate".to_vec()));
+        assert_eq!(reader.get_profile_screen(user, U256::from(u64::MAX)), Err(b"InvalidDate".to_vec()));
+    }
+
+    #[test]
+    fn test_screens_degrade_to_zero() {
+        let vm = TestVM::default();
+        let mut reader = reader(&vm);
+        let user = Address::repeat_byte(0x01);
+        let now = 20_000 * DAY;
+        vm.set_block_timestamp(now);
+        let moon_phase = astro::moon_phase(now).0;
+
+        // Stats revert, other FarcasterPredictions and oracle calls return
+        // nothing (unmocked), the registry works
+        let calldata = IFarcasterPredictions::getUserStatsCall { user }.abi_encode();
+        vm.mock_static_call(PREDICTIONS, calldata, Err(b"Paused".to_vec()));
+        mock!(vm, REGISTRY, IChartRegistry::getUserChartsCall { user } => (vec![String::from("natal")],));
+        mock!(vm, REGISTRY, IChartRegistry::isZkVerifiedCall { chartId: String::from("natal") } => (true,));
+
+        assert_eq!(reader.get_home_screen(user), (UserStats::default(), B32::ZERO, 0, true, moon_phase));
+        let screen = reader.get_profile_screen(user, U256::from(now)).unwrap();
+        assert_eq!(screen.stats, UserStats::default());
+        assert_eq!(screen.points, U256::ZERO);
+        assert_eq!(screen.hashes, [B32::ZERO; PROFILE_DAYS as usize]);
+        assert_eq!(screen.hasPositions, [false; PROFILE_DAYS as usize]);
+        assert_eq!((screen.chartCount, screen.hasVerifiedChart), (U256::from(1), true));
+
+        // Unset contracts read as nothing at all
+        reader.set_contracts(Address::ZERO, Address::ZERO, Address::ZERO).unwrap();
+        assert_eq!(reader.get_home_screen(user), (UserStats::default(), B32::ZERO, 0, false, moon_phase));
+        let screen = reader.get_profile_screen(user, U256::from(now)).unwrap();
+        assert_eq!((screen.chartCount, screen.hasVerifiedChart), (U256::ZERO, false));
+    }
+
+    #[test]
+    #[cfg_attr(any(feature = "debug", feature = "export-abi"), ignore = "debug builds log through hostio, which TestVM lacks")]
+    fn test_sdk_selectors_route() {
+        let vm = TestVM::default();
+        let mut reader = ZkAstroReader::from(&vm);
+
+        for selector in IZkAstroReader::IZkAstroReaderCalls::SELECTORS {
+            let routed = <ZkAstroReader as Router<ZkAstroReader>>::route(&mut reader, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "no route for {selector:02x?}");
+        }
+    }
+}
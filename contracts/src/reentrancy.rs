@@ -0,0 +1,116 @@
+//! Reentrancy guard
+//!
+//! A guarded call sets a storage flag while it runs, so a call back into
+//! the contract from an external call it makes (such as the prediction
+//! hook) finds the flag set and fails with `Reentrancy`.
+//!
+//! The flag alone would also stop `multicall` from routing to guarded
+//! methods, which run in the same call. Each call therefore also keeps,
+//! without storage, whether it holds the lock itself: methods it routes
+//! run under that lock, and only other calls are turned away. Methods it
+//! delegates to a facet run in a call of their own, so it releases the
+//! lock around those (see [`NonReentrant::released`]).
+
+use core::cell::Cell;
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{alloy_primitives::U256, host::VM, prelude::*};
+
+/// Whether the current call holds the reentrancy lock
+///
+/// Takes no storage: it only lives for the call, and a reentrant call
+/// starts without it.
+pub struct LockHeld(Cell<bool>);
+
+impl StorageType for LockHeld {
+    type Wraps<'a>
+        = bool
+    where
+        Self: 'a;
+    type WrapsMut<'a>
+        = bool
+    where
+        Self: 'a;
+
+    const SLOT_BYTES: usize = 0;
+    const REQUIRED_SLOTS: usize = 0;
+
+    unsafe fn new(_slot: U256, _offset: u8, _host: VM) -> Self {
+        Self(Cell::new(false))
+    }
+
+    fn load<'s>(self) -> Self::Wraps<'s>
+    where
+        Self: 's,
+    {
+        self.0.get()
+    }
+
+    fn load_mut<'s>(self) -> Self::WrapsMut<'s>
+    where
+        Self: 's,
+    {
+        self.0.get()
+    }
+}
+
+/// A contract whose external calls must not be re-entered
+pub trait NonReentrant: Sized {
+    /// Whether a guarded call is running (the storage flag)
+    fn locked(&self) -> bool;
+
+    /// Set or clear the storage flag
+    fn set_locked(&mut self, locked: bool);
+
+    /// Whether the current call holds the lock
+    fn lock_held(&self) -> &LockHeld;
+
+    /// Take the lock, failing with `Reentrancy` when another call holds it
+    ///
+    /// Returns: whether this took the lock, false when the current call
+    /// already held it
+    fn enter(&mut self) -> Result<bool, Vec<u8>> {
+        if self.lock_held().0.get() {
+            return Ok(false);
+        }
+        if self.locked() {
+            return Err(b"Reentrancy".to_vec());
+        }
+        self.set_locked(true);
+        self.lock_held().0.set(true);
+        Ok(true)
+    }
+
+    /// Release the lock if `enter` took it
+    fn exit(&mut self, entered: bool) {
+        if entered {
+            self.set_locked(false);
+            self.lock_held().0.set(false);
+        }
+    }
+
+    /// Run `f` with the lock the current call holds released, taking it
+    /// back afterwards
+    ///
+    /// For calls made by the current one that guard themselves, such as
+    /// delegate calls into this contract's facets.
+    fn released<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let held = self.lock_held().0.get();
+        self.exit(held);
+        let result = f(self);
+        if held {
+            self.set_locked(true);
+            self.lock_held().0.set(true);
+        }
+        result
+    }
+
+    /// Run `f` holding the lock
+    fn non_reentrant<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Vec<u8>>) -> Result<T, Vec<u8>> {
+        let entered = self.enter()?;
+        let result = f(self);
+        self.exit(entered);
+        result
+    }
+}
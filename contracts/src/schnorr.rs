@@ -0,0 +1,82 @@
+//! Schnorr proof of knowledge of a commitment's discrete log
+//!
+//! Unlike the keccak scheme, which anyone holding the commitment string can
+//! satisfy, this proves knowledge of the secret behind the commitment:
+//!
+//! - Commitment: `C = x·G` over BN254 G1, with x derived from the birth data
+//!   and salt (see `prover::generate_schnorr_secret`)
+//! - Proof: `(R = k·G, s = k + e·x mod n)`
+//! - Challenge: e from a [`Transcript`] over C, R, the positions and the
+//!   sender, so a proof can't be replayed for other positions or by another
+//!   account
+//! - Verification: `s·G == R + e·C`
+//!
+//! The group operations go through [`G1Ops`], so the contract can verify on
+//! the ecAdd/ecMul precompiles while tests and clients use pure Rust.
+
+use stylus_sdk::alloy_primitives::{Address, FixedBytes, U256};
+
+#[cfg(any(test, feature = "prover"))]
+use crate::commitment::Native;
+use crate::commitment::{G1Ops, G1Point, G, GROUP_ORDER};
+use crate::field;
+use crate::poseidon::Transcript;
+
+/// Transcript domain of the Schnorr challenge
+pub const SCHNORR_DOMAIN: &[u8] = b"ZKAstro.schnorr.v1";
+
+/// Schnorr proof (R, s)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchnorrProof {
+    /// Nonce commitment R = k·G
+    pub r: G1Point,
+    /// Response s = k + e·x mod n
+    pub s: U256,
+}
+
+/// Derive the challenge e for `commitment` and nonce commitment `r`
+pub fn challenge(commitment: &G1Point, r: &G1Point, position_values: &[u64], sender: Address) -> U256 {
+    let mut transcript = Transcript::new(SCHNORR_DOMAIN);
+    transcript.append_bytes(b"commitment", &commitment.to_bytes());
+    transcript.append_bytes(b"R", &r.to_bytes());
+    transcript.append_u64s(b"positions", position_values);
+    transcript.append_address(b"sender", sender);
+    field::reduce_to_field(FixedBytes(transcript.challenge_bytes32(b"e")))
+}
+
+/// Verify `proof` with the group operations of `ops`
+///
+/// Rejects points off the curve, the point at infinity as commitment and
+/// non-canonical responses (s >= n).
+pub fn verify_schnorr_with(
+    ops: &impl G1Ops,
+    commitment: &G1Point,
+    proof: &SchnorrProof,
+    position_values: &[u64],
+    sender: Address,
+) -> bool {
+    if commitment.is_infinity() || !commitment.is_on_curve() || !proof.r.is_on_curve() {
+        return false;
+    }
+    if proof.s >= GROUP_ORDER {
+        return false;
+    }
+
+    let e = challenge(commitment, &proof.r, position_values, sender);
+    let lhs = ops.ec_mul(&G, proof.s);
+    let rhs = ops
+        .ec_mul(commitment, e)
+        .and_then(|e_c| ops.ec_add(&proof.r, &e_c));
+    matches!((lhs, rhs), (Some(lhs), Some(rhs)) if lhs == rhs)
+}
+
+/// Verify `proof` with the pure Rust group operations
+#[cfg(any(test, feature = "prover"))]
+pub fn verify_schnorr(
+    commitment: &G1Point,
+    proof: &SchnorrProof,
+    position_values: &[u64],
+    sender: Address,
+) -> bool {
+    verify_schnorr_with(&Native, commitment, proof, position_values, sender)
+}
@@ -0,0 +1,240 @@
+//! Model-based property tests for the rating and stats bookkeeping
+//!
+//! Random operation sequences run against the contract in the test VM and
+//! against [`Model`], which keeps only each user's predictions and ratings
+//! and recomputes every counter from them, and what the aggregates saw
+//! while users weren't opted out of them. All stats must agree after each
+//! step. Fixed seed so `cargo test` is reproducible; proptest shrinks a
+//! failure down to a short sequence.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use proptest::prelude::*;
+use proptest::test_runner::RngSeed;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256, U8},
+    testing::*,
+};
+
+use crate::positions::{self, PlanetPosition};
+use crate::{poseidon, prover, FarcasterPredictions};
+
+type B32 = FixedBytes<32>;
+
+const USERS: usize = 5;
+/// Users from this index on register with a birth-year bucket
+const FIRST_BUCKETED: usize = 3;
+/// Decade both bucketed users were born in
+const BUCKET: u8 = 9;
+const DATES: u64 = 30;
+const NOW: u64 = 1_754_006_400;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Register(usize),
+    Store(usize, u64),
+    /// Rate 1-5 (new or update), or out of range with 6
+    Rate(usize, u64, u8),
+    Clear(usize, u64),
+    /// Opt out of aggregates (true) or back in
+    OptOut(usize, bool),
+}
+
+/// What the stats should be, from the stored predictions alone
+#[derive(Default)]
+struct Model {
+    registered: [bool; USERS],
+    /// Rating per stored prediction date, 0 while unrated
+    ratings: [BTreeMap<u64, u8>; USERS],
+    opted_out: [bool; USERS],
+    /// Registrations and predictions made while opted in
+    aggregated_users: usize,
+    aggregated_predictions: usize,
+    /// Rating per date as last made while opted in
+    aggregated_ratings: [BTreeMap<u64, u8>; USERS],
+}
+
+impl Model {
+    /// Apply `op`, returning what the contract should return
+    fn apply(&mut self, op: &Op) -> Result<(), Vec<u8>> {
+        match *op {
+            Op::Register(user) => {
+                if self.registered[user] {
+                    return Err(b"UserAlreadyRegistered".to_vec());
+                }
+                self.registered[user] = true;
+                self.aggregated_users += usize::from(!self.opted_out[user]);
+            }
+            Op::Store(user, date) => {
+                if !self.registered[user] {
+                    return Err(b"UserNotRegistered".to_vec());
+                }
+                if self.ratings[user].contains_key(&date) {
+                    return Err(b"PredictionAlreadyExists".to_vec());
+                }
+                self.ratings[user].insert(date, 0);
+                self.aggregated_predictions += usize::from(!self.opted_out[user]);
+            }
+            Op::Rate(user, date, rating) => return self.rate(user, date, rating),
+            Op::Clear(user, date) => return self.rate(user, date, 0),
+            Op::OptOut(user, opted_out) => self.opted_out[user] = opted_out,
+        }
+        Ok(())
+    }
+
+    fn rate(&mut self, user: usize, date: u64, rating: u8) -> Result<(), Vec<u8>> {
+        if rating > 5 {
+            return Err(b"InvalidRating".to_vec());
+        }
+        let slot = self.ratings[user].get_mut(&date).ok_or(b"PredictionNotFound".to_vec())?;
+        *slot = rating;
+        if !self.opted_out[user] {
+            self.aggregated_ratings[user].insert(date, rating);
+        }
+        Ok(())
+    }
+
+    /// (total ratings, rating sum) over `ratings`
+    fn sum<'a>(ratings: impl Iterator<Item = &'a u8>) -> (u64, u64) {
+        ratings
+            .filter(|rating| **rating != 0)
+            .fold((0, 0), |(count, sum), rating| (count + 1, sum + u64::from(*rating)))
+    }
+
+    fn user_stats(&self, user: usize) -> (U256, U256, U256) {
+        let (count, sum) = Self::sum(self.ratings[user].values());
+        let average_x10 = (sum * 10).checked_div(count).unwrap_or_default();
+        (U256::from(self.ratings[user].len()), U256::from(count), U256::from(average_x10))
+    }
+
+    fn global_stats(&self) -> (U256, U256) {
+        (U256::from(self.aggregated_users), U256::from(self.aggregated_predictions))
+    }
+
+    fn bucket_stats(&self) -> (U256, U256) {
+        let (count, sum) = Self::sum((FIRST_BUCKETED..USERS).flat_map(|user| self.aggregated_ratings[user].values()));
+        (U256::from(count), U256::from(sum))
+    }
+}
+
+fn address(user: usize) -> Address {
+    Address::repeat_byte(0x10 + user as u8)
+}
+
+fn date(index: u64) -> U256 {
+    U256::from((20_000 + index) * 86_400)
+}
+
+/// Commitment, proof, nonce, epoch and opening of a bucketed registration
+type Registration = (B32, B32, Bytes, u64, B32);
+
+/// Registration of each bucketed user, proven once
+fn bucketed_registrations() -> &'static [Registration] {
+    static REGISTRATIONS: OnceLock<Vec<Registration>> = OnceLock::new();
+    REGISTRATIONS.get_or_init(|| {
+        ["1991-05-14", "1997-11-02"]
+            .iter()
+            .map(|dob| {
+                let birth_data = prover::BirthData {
+                    dob: String::from(*dob),
+                    tob: String::from("08:15"),
+                    tz: String::from("UTC"),
+                    lat: String::from("40.7128"),
+                    lon: String::from("-74.006"),
+                };
+                let (commitment, bucket, opening) = prover::generate_bucketed_commitment(&birth_data, [5u8; 32]).unwrap();
+                assert_eq!(bucket, BUCKET);
+                let epoch = poseidon::compute_epoch(NOW);
+                let proof = prover::generate_proof_v2_bucketed(&commitment, "nonce", &chart(), epoch, bucket).unwrap();
+                let bytes = proof.to_bytes().unwrap();
+                (bytes.commitment.into(), B32::from(bytes.proof), Bytes::from(bytes.nonce), epoch, B32::from(opening))
+            })
+            .collect()
+    })
+}
+
+fn chart() -> Vec<PlanetPosition> {
+    (0u8..7)
+        .map(|body| PlanetPosition { body, longitude_centideg: u16::from(body) * 4100, is_retrograde: false })
+        .collect()
+}
+
+fn execute(vm: &TestVM, contract: &mut FarcasterPredictions, op: &Op) -> Result<(), Vec<u8>> {
+    match *op {
+        Op::Register(user) => {
+            vm.set_sender(address(user));
+            if user < FIRST_BUCKETED {
+                return contract.register_user(B32::repeat_byte(user as u8 + 1));
+            }
+            let (commitment, proof, nonce, epoch, opening) = bucketed_registrations()[user - FIRST_BUCKETED].clone();
+            contract.register_user_with_bucket(commitment, proof, nonce, positions::to_words(&chart()), epoch, BUCKET, opening)
+        }
+        Op::Store(user, index) => {
+            vm.set_sender(address(user));
+            contract.store_prediction(date(index), B32::repeat_byte(0x80 + index as u8))
+        }
+        Op::Rate(user, index, rating) => {
+            vm.set_sender(address(user));
+            contract.rate_prediction(date(index), U8::from(rating))
+        }
+        Op::Clear(user, index) => {
+            vm.set_sender(address(user));
+            contract.rate_prediction(date(index), U8::ZERO)
+        }
+        Op::OptOut(user, opted_out) => {
+            vm.set_sender(address(user));
+            contract.set_aggregate_opt_out(opted_out)
+        }
+    }
+}
+
+fn assert_matches(contract: &FarcasterPredictions, model: &Model, op: &Op) -> Result<(), TestCaseError> {
+    prop_assert_eq!(contract.get_global_stats(), model.global_stats());
+    prop_assert_eq!(contract.get_bucket_stats(BUCKET), model.bucket_stats());
+    for user in 0..USERS {
+        prop_assert_eq!(contract.get_user_stats(address(user)), model.user_stats(user), "user {}", user);
+    }
+    if let Op::Rate(user, index, _) | Op::Clear(user, index) = *op {
+        let rating = model.ratings[user].get(&index).copied().unwrap_or_default();
+        prop_assert_eq!(contract.get_rating(address(user), date(index)), U8::from(rating));
+    }
+    Ok(())
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    // Half the dates come from a few days so ratings hit stored predictions
+    let date = || prop_oneof![0..4u64, 0..DATES];
+    prop_oneof![
+        1 => (0..USERS).prop_map(Op::Register),
+        3 => (0..USERS, date()).prop_map(|(user, date)| Op::Store(user, date)),
+        4 => (0..USERS, date(), 1u8..=6).prop_map(|(user, date, rating)| Op::Rate(user, date, rating)),
+        1 => (0..USERS, date()).prop_map(|(user, date)| Op::Clear(user, date)),
+        1 => (0..USERS, any::<bool>()).prop_map(|(user, opted_out)| Op::OptOut(user, opted_out)),
+    ]
+}
+
+fn config() -> ProptestConfig {
+    ProptestConfig { cases: 256, rng_seed: RngSeed::Fixed(0x5a4b_4173_7472_6f04), ..ProptestConfig::default() }
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn stats_match_model(ops in prop::collection::vec(op(), 1..60)) {
+        let vm = TestVM::default();
+        let mut contract = FarcasterPredictions::from(&vm);
+        contract.initialize(vm.msg_sender()).unwrap();
+        contract.set_max_backfill_days(u64::MAX).unwrap();
+        vm.set_block_timestamp(NOW);
+
+        let mut model = Model::default();
+        for op in &ops {
+            let expected = model.apply(op);
+            prop_assert_eq!(execute(&vm, &mut contract, op), expected, "{:?}", op);
+            assert_matches(&contract, &model, op)?;
+        }
+    }
+}
@@ -198,18 +198,18 @@ async function checkContractFiles() {
   console.log('📁 Checking contract files...\n');
 
   // Check Rust contract
-  const rustContractPath = path.join(process.cwd(), 'contracts/ChartRegistry.rs');
+  const rustContractPath = path.join(process.cwd(), 'contracts/src/chart_registry.rs');
   if (fs.existsSync(rustContractPath)) {
     checks.push({
       name: 'Rust Contract',
       status: 'pass',
-      message: 'ChartRegistry.rs exists',
+      message: 'src/chart_registry.rs exists',
     });
   } else {
     checks.push({
       name: 'Rust Contract',
       status: 'fail',
-      message: 'ChartRegistry.rs not found',
+      message: 'src/chart_registry.rs not found',
     });
   }
 
@@ -0,0 +1,55 @@
+/**
+ * Check Stylus WASM Size
+ * Builds every deploy artifact and fails when one exceeds the Stylus limits
+ *
+ * Each deployed program is its own feature-selected build (see
+ * stylus-artifacts.ts): the FarcasterPredictions and ChartRegistry cores,
+ * each of their facets, EphemerisOracle and ZkAstroReader. Every one must
+ * stay within 24 KiB brotli-compressed and 128 KiB raw. The builds are left
+ * in contracts/target/stylus for deployment. Pass artifact names to check
+ * only those, e.g. `npm run stylus:size -- farcaster farcaster-admin`.
+ */
+
+import {
+  MAX_COMPRESSED_SIZE,
+  MAX_RAW_SIZE,
+  artifactSize,
+  buildArtifact,
+  listArtifacts,
+} from './stylus-artifacts';
+
+function main() {
+  const requested = process.argv.slice(2);
+  const artifacts = listArtifacts().filter((artifact) => requested.length === 0 || requested.includes(artifact.name));
+  const unknown = requested.filter((name) => !artifacts.some((artifact) => artifact.name === name));
+  if (unknown.length > 0) {
+    console.error(`❌ Unknown artifacts: ${unknown.join(', ')}`);
+    process.exit(1);
+  }
+
+  const rows = artifacts.map((artifact) => {
+    console.log(`🔨 Building ${artifact.name}...`);
+    const size = artifactSize(buildArtifact(artifact));
+    return { name: artifact.name, ...size };
+  });
+
+  const percent = (size: number, limit: number) => `${((size / limit) * 100).toFixed(1)}%`;
+  console.log(`\n${'artifact'.padEnd(36)} ${'raw'.padStart(8)} ${'brotli'.padStart(8)}`);
+  let failed = false;
+  for (const row of rows) {
+    const over = row.raw > MAX_RAW_SIZE || row.compressed > MAX_COMPRESSED_SIZE;
+    failed ||= over;
+    console.log(
+      `${over ? '❌' : '✅'} ${row.name.padEnd(33)} ${String(row.raw).padStart(8)} ${String(row.compressed).padStart(8)}` +
+        `  (${percent(row.compressed, MAX_COMPRESSED_SIZE)} of ${MAX_COMPRESSED_SIZE})`,
+    );
+  }
+
+  if (failed) {
+    console.error(`\n❌ Artifacts over ${MAX_COMPRESSED_SIZE} bytes compressed or ${MAX_RAW_SIZE} bytes raw can't be deployed`);
+    process.exit(1);
+  }
+  console.log(`\n✅ All ${rows.length} artifacts fit the Stylus size limits`);
+}
+
+main();
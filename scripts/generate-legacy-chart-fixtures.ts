@@ -0,0 +1,84 @@
+/**
+ * Generate Legacy Chart Hash Fixtures
+ * Writes contracts/tests/legacy_chart_hashes.json, the golden vectors of
+ * `legacy_hash` in the contracts' core crate
+ *
+ * Each chart is hashed exactly as recordChartOnChain and
+ * recordChartOnArbitrum (lib/blockchain) hash it, so the Rust rebuild of
+ * the JSON is checked against what production registered. Run with
+ * `npx tsx scripts/generate-legacy-chart-fixtures.ts`.
+ */
+
+import { ethers } from 'ethers';
+import * as fs from 'fs';
+import * as path from 'path';
+import { fileURLToPath } from 'url';
+
+const __filename = fileURLToPath(import.meta.url);
+const __dirname = path.dirname(__filename);
+
+const outputPath = path.join(__dirname, '..', 'contracts', 'tests', 'legacy_chart_hashes.json');
+
+type Planets = {
+  sun: number;
+  moon: number;
+  mercury: number;
+  venus: number;
+  mars: number;
+  jupiter: number;
+  saturn: number;
+};
+
+type ChartData = { planets: Planets; asc: number; mc: number };
+
+// Same as lib/blockchain/onchain-registry.ts
+function legacyChartHash(chartData: ChartData, zkProof: string): { json: string; hash: string } {
+  const chartDataStr = JSON.stringify({
+    planets: chartData.planets,
+    asc: chartData.asc,
+    mc: chartData.mc,
+    zkProof,
+  });
+  return { json: chartDataStr, hash: ethers.keccak256(ethers.toUtf8Bytes(chartDataStr)) };
+}
+
+const planets = (values: number[]): Planets => {
+  const [sun, moon, mercury, venus, mars, jupiter, saturn] = values;
+  return { sun, moon, mercury, venus, mars, jupiter, saturn };
+};
+
+const cases: { name: string; chart: ChartData; zkProof: string }[] = [
+  {
+    name: 'typical',
+    chart: { planets: planets([28045, 12030, 30011, 33520, 21077, 4590, 17333]), asc: 9120, mc: 35010 },
+    zkProof: '0x4f1c2a9be07d3355a1e8c6f2d90b7e4a5c3d2f1e0a9b8c7d6e5f4a3b2c1d0e9f',
+  },
+  {
+    // Charts before 2000 come out negative from `%` in astro-client.ts
+    name: 'negative_longitudes',
+    chart: { planets: planets([-1234, -35999, 0, -1, 17, -27, 35999]), asc: -4500, mc: 0 },
+    zkProof: '0x00',
+  },
+  {
+    name: 'empty_proof',
+    chart: { planets: planets([0, 0, 0, 0, 0, 0, 0]), asc: 0, mc: 0 },
+    zkProof: '',
+  },
+  {
+    name: 'escaped_proof',
+    chart: { planets: planets([1, 2, 3, 4, 5, 6, 7]), asc: 8, mc: 9 },
+    zkProof: 'quote " backslash \\ slash / tab \t newline \n bell \u0007 unit \u001f é ♈',
+  },
+];
+
+const fixtures = cases.map(({ name, chart, zkProof }) => ({
+  name,
+  planets: Object.values(chart.planets),
+  asc: chart.asc,
+  mc: chart.mc,
+  zkProof,
+  ...legacyChartHash(chart, zkProof),
+}));
+
+fs.writeFileSync(outputPath, JSON.stringify(fixtures, null, 2) + '\n');
+console.log(`Wrote ${fixtures.length} legacy chart hashes to ${path.relative(process.cwd(), outputPath)}`);
@@ -0,0 +1,119 @@
+/**
+ * Tests of the selector extraction in stylus-artifacts.ts
+ * Run with `npm run stylus:test`
+ */
+
+import assert from 'node:assert/strict';
+import { describe, it } from 'node:test';
+
+import { Artifact, facetSelectors, parseSignatures } from './stylus-artifacts';
+
+// Trimmed `abi --routed` output of a farcaster facet
+const ABI = `
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IFarcasterPredictions  {
+    function owner() external view returns (address);
+
+    function multicall(bytes[] memory calls) external returns (bytes[] memory);
+
+    function setFacet(bytes4 selector, address facet) external;
+
+    function finalizeBatch((address,uint256)[] memory entries) external returns (uint8[] memory);
+
+    function exportUser(address user, uint256 date_offset, uint256 date_limit) external view returns (UserExport memory);
+
+    function importStats(UserStats memory stats, UserExport[] calldata exports) external;
+
+    struct UserStats {
+        uint256 total;
+        uint8[5] ratings;
+    }
+
+    struct UserExport {
+        bytes32 commitment;
+        bool registered;
+        uint256 registeredAt;
+        UserStats stats;
+        uint256[] dates;
+        bytes32[] hashes;
+        uint8[] ratings;
+    }
+
+    event FacetScheduled(bytes4 indexed selector, address indexed facet, uint64 effective_at);
+
+    error Unauthorized();
+}
+`;
+
+const artifact = (name: string, facet = true): Artifact => ({
+  name,
+  contract: 'farcaster',
+  features: facet ? [name] : [],
+  facet,
+});
+
+describe('parseSignatures', () => {
+  it('reads each function, and nothing else', () => {
+    assert.deepEqual(parseSignatures(ABI), [
+      'owner()',
+      'multicall(bytes[])',
+      'setFacet(bytes4,address)',
+      'finalizeBatch((address,uint256)[])',
+      'exportUser(address,uint256,uint256)',
+      'importStats((uint256,uint8[5]),(bytes32,bool,uint256,(uint256,uint8[5]),uint256[],bytes32[],uint8[])[])',
+    ]);
+  });
+
+  it('ignores line breaks inside a parameter list', () => {
+    const abi = 'function register(\n        bytes32 commitment,\n        uint256[8] calldata proof\n    ) external;';
+    assert.deepEqual(parseSignatures(abi), ['register(bytes32,uint256[8])']);
+  });
+
+  it('reads nested tuples', () => {
+    const abi = 'function pairs(((address,uint256),bytes32)[] memory pairs, bool strict) external;';
+    assert.deepEqual(parseSignatures(abi), ['pairs(((address,uint256),bytes32)[],bool)']);
+  });
+});
+
+describe('facetSelectors', () => {
+  const routes: Record<string, string[]> = {
+    farcaster: ['owner()', 'setFacet(bytes4,address)'],
+    'farcaster-admin': ['getFacet(bytes4)', 'initialize(address)'],
+    'farcaster-batch': ['multicall(bytes[])'],
+  };
+  const signatures = (artifact: Artifact) => routes[artifact.name] ?? [];
+
+  it('lists the selectors of each facet', () => {
+    const selectors = facetSelectors(artifact('farcaster', false), [artifact('farcaster-admin'), artifact('farcaster-batch')], signatures);
+    assert.deepEqual(
+      selectors,
+      new Map([
+        ['farcaster-admin', ['0x112b6a67', '0xc4d66de8']],
+        ['farcaster-batch', ['0xac9650d8']],
+      ]),
+    );
+  });
+
+  it('rejects a selector routed by two facets', () => {
+    const clashing = { ...routes, 'farcaster-batch': ['multicall(bytes[])', 'initialize(address)'] };
+    assert.throws(
+      () => facetSelectors(artifact('farcaster', false), [artifact('farcaster-admin'), artifact('farcaster-batch')], (artifact) => clashing[artifact.name]),
+      /initialize\(address\) is routed by both farcaster-admin and farcaster-batch/,
+    );
+  });
+
+  it('rejects a facet selector the core routes', () => {
+    const clashing = { ...routes, 'farcaster-admin': ['owner()'] };
+    assert.throws(
+      () => facetSelectors(artifact('farcaster', false), [artifact('farcaster-admin')], (artifact) => clashing[artifact.name]),
+      /owner\(\) is routed by both farcaster and farcaster-admin/,
+    );
+  });
+});
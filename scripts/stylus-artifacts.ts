@@ -0,0 +1,183 @@
+/**
+ * Stylus Deploy Artifacts
+ * One feature-selected release build of the contracts crate per deployed program
+ *
+ * FarcasterPredictions and ChartRegistry are too large for one program, so
+ * each deploys as a core plus one facet per `farcaster-*` / `chart-registry-*`
+ * feature (see contracts/src/facets.rs); EphemerisOracle and ZkAstroReader
+ * deploy whole. The list is read from the features in contracts/Cargo.toml,
+ * so a new facet is checked and deployed without touching the scripts.
+ */
+
+import { execFileSync } from 'child_process';
+import { ethers } from 'ethers';
+import * as fs from 'fs';
+import * as path from 'path';
+import { fileURLToPath } from 'url';
+import * as zlib from 'zlib';
+
+// Stylus rejects programs over 24 KiB once brotli-compressed, or over
+// 128 KiB decompressed
+export const MAX_COMPRESSED_SIZE = 24 * 1024;
+export const MAX_RAW_SIZE = 128 * 1024;
+
+const __filename = fileURLToPath(import.meta.url);
+const __dirname = path.dirname(__filename);
+
+export const contractsDir = path.join(__dirname, '..', 'contracts');
+const buildPath = path.join(contractsDir, 'target', 'wasm32-unknown-unknown', 'release', 'farcaster_predictions_stylus.wasm');
+const artifactsDir = path.join(contractsDir, 'target', 'stylus');
+
+export type Contract = 'farcaster' | 'chart-registry' | 'ephemeris-oracle' | 'reader';
+
+export interface Artifact {
+  /** File name under contracts/target/stylus, without `.wasm` */
+  name: string;
+  contract: Contract;
+  /** Cargo features selecting the build (none for the FarcasterPredictions core) */
+  features: string[];
+  /** Whether this is a facet, registered with its core's `setFacet` */
+  facet: boolean;
+}
+
+export interface ArtifactSize {
+  raw: number;
+  compressed: number;
+}
+
+/** Features declared in contracts/Cargo.toml, with the features each enables */
+function cargoFeatures(): Map<string, string[]> {
+  const manifest = fs.readFileSync(path.join(contractsDir, 'Cargo.toml'), 'utf8');
+  const section = manifest.split(/^\[features\]$/m)[1]?.split(/^\[/m)[0] ?? '';
+  const features = new Map<string, string[]>();
+  for (const match of section.matchAll(/^([a-z0-9-]+)\s*=\s*\[(.*)\]/gm)) {
+    features.set(match[1], [...match[2].matchAll(/"([^"]+)"/g)].map((enabled) => enabled[1]));
+  }
+  return features;
+}
+
+/** Every deployed program, cores before their facets */
+export function listArtifacts(): Artifact[] {
+  const features = cargoFeatures();
+  const facets = (contract: Contract) =>
+    [...features]
+      .filter(([, enabled]) => enabled.includes(`${contract}-facet`))
+      .map(([feature]): Artifact => ({ name: feature, contract, features: [feature], facet: true }));
+
+  return [
+    { name: 'farcaster', contract: 'farcaster', features: [], facet: false },
+    ...facets('farcaster'),
+    { name: 'chart-registry', contract: 'chart-registry', features: ['chart-registry'], facet: false },
+    ...facets('chart-registry'),
+    { name: 'ephemeris-oracle', contract: 'ephemeris-oracle', features: ['ephemeris-oracle'], facet: false },
+    { name: 'reader', contract: 'reader', features: ['reader'], facet: false },
+  ];
+}
+
+/** Build `artifact` and copy it to contracts/target/stylus/<name>.wasm */
+export function buildArtifact(artifact: Artifact): string {
+  const features = artifact.features.length > 0 ? ['--features', artifact.features.join(',')] : [];
+  execFileSync('cargo', ['build', '--release', '--lib', '--target', 'wasm32-unknown-unknown', ...features], {
+    cwd: contractsDir,
+    stdio: 'inherit',
+  });
+
+  fs.mkdirSync(artifactsDir, { recursive: true });
+  const wasmPath = path.join(artifactsDir, `${artifact.name}.wasm`);
+  fs.copyFileSync(buildPath, wasmPath);
+  return wasmPath;
+}
+
+/** Raw size and size compressed the way `cargo stylus` deploys it */
+export function artifactSize(wasmPath: string): ArtifactSize {
+  const wasm = fs.readFileSync(wasmPath);
+  const compressed = zlib.brotliCompressSync(wasm, {
+    params: {
+      [zlib.constants.BROTLI_PARAM_QUALITY]: 11,
+      [zlib.constants.BROTLI_PARAM_LGWIN]: 22,
+    },
+  });
+  return { raw: wasm.length, compressed: compressed.length };
+}
+
+/**
+ * Canonical signatures (`name(type,...)`) of the functions `artifact` routes
+ *
+ * `abi --routed` exports the build's own functions; the default export of a
+ * core or facet covers every build of its contract.
+ */
+export function functionSignatures(artifact: Artifact): string[] {
+  const features = ['export-abi', ...artifact.features].join(',');
+  return parseSignatures(
+    execFileSync('cargo', ['run', '--quiet', '--features', features, '--', 'abi', '--routed'], {
+      cwd: contractsDir,
+      encoding: 'utf8',
+    }),
+  );
+}
+
+/** Canonical signatures of the functions in a Solidity interface from the contracts' ABI export */
+export function parseSignatures(source: string): string[] {
+  const abi = source.replace(/\s+/g, ' ');
+  const structs = new Map<string, string[]>();
+  for (const match of abi.matchAll(/struct (\w+) \{([^}]*)\}/g)) {
+    structs.set(match[1], match[2].split(';').map((field) => field.trim()).filter(Boolean).map((field) => field.split(' ')[0]));
+  }
+  // Structs are encoded as tuples of their fields, arrays keep their suffix
+  const canonical = (type: string): string => {
+    const [, base, suffix] = type.match(/^(\w+)(.*)$/) ?? [];
+    const fields = structs.get(base);
+    return fields ? `(${fields.map(canonical).join(',')})${suffix}` : type;
+  };
+
+  return [...abi.matchAll(/function (\w+)\(/g)].map((match) => {
+    // Parameters end at the parenthesis closing the list, and are split
+    // on commas outside tuple types such as `(address,uint256)[]`
+    const params: string[] = [];
+    let depth = 0;
+    let param = '';
+    for (const char of abi.slice(match.index! + match[0].length)) {
+      if (depth === 0 && (char === ',' || char === ')')) {
+        if (param.trim()) params.push(param.trim());
+        param = '';
+        if (char === ')') break;
+        continue;
+      }
+      if (char === '(') depth++;
+      if (char === ')') depth--;
+      param += char;
+    }
+    const types = params.map((param) => canonical(param.replace(/\s*,\s*/g, ',').split(' ')[0]));
+    return `${match[1]}(${types.join(',')})`;
+  });
+}
+
+/**
+ * Selectors to register for each facet of `core`, by facet name
+ *
+ * A selector the core routes itself never reaches a facet, and one routed by
+ * two facets would silently go to whichever registered last, so both throw
+ * before anything is deployed. `signatures` lists what an artifact routes.
+ */
+export function facetSelectors(
+  core: Artifact,
+  facets: Artifact[],
+  signatures: (artifact: Artifact) => string[] = functionSignatures,
+): Map<string, string[]> {
+  const selectorOf = (signature: string) => ethers.id(signature).slice(0, 10);
+  const routedBy = new Map(signatures(core).map((signature) => [selectorOf(signature), core.name]));
+  return new Map(
+    facets.map((facet) => [
+      facet.name,
+      signatures(facet).map((signature) => {
+        const selector = selectorOf(signature);
+        const other = routedBy.get(selector);
+        if (other) {
+          throw new Error(`${signature} is routed by both ${other} and ${facet.name}`);
+        }
+        routedBy.set(selector, facet.name);
+        return selector;
+      }),
+    ]),
+  );
+}